@@ -574,6 +574,7 @@ def external_crates_repository(name, static_openssl, cargo_lockfile, lockfile):
                     "ecdsa",
                     "pem",
                     "pkcs8",
+                    "schnorr",
                 ],
                 default_features = False,
             ),
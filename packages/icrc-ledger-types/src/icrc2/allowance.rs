@@ -14,3 +14,27 @@ pub struct Allowance {
     #[serde(default)]
     pub expires_at: Option<u64>,
 }
+
+#[derive(CandidType, Deserialize, Clone, Debug, PartialEq, Eq)]
+pub struct ListAllowancesArgs {
+    pub account: Account,
+    // The last spender seen by the client for the given account.
+    // This spender is excluded in the result.
+    // If None then the results will start from the first
+    // spender in natural order.
+    #[serde(default)]
+    pub from_spender: Option<Account>,
+    // Maximum number of allowances to fetch.
+    #[serde(default)]
+    pub limit: Option<u64>,
+}
+
+#[derive(CandidType, Deserialize, Clone, Debug, PartialEq, Eq)]
+pub struct SpenderAllowance {
+    pub spender: Account,
+    pub allowance: Nat,
+    #[serde(default)]
+    pub expires_at: Option<u64>,
+}
+
+pub type ListAllowancesResponse = Vec<SpenderAllowance>;
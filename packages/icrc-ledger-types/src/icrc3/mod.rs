@@ -1,3 +1,4 @@
 pub mod archive;
 pub mod blocks;
+pub mod proofs;
 pub mod transactions;
@@ -0,0 +1,56 @@
+use crate::icrc1::transfer::BlockIndex;
+use candid::{CandidType, Deserialize, Principal};
+use serde_bytes::ByteBuf;
+
+/// Identifies a node of the Merkle mountain range (MMR) over the block
+/// hashes. The node covers the blocks
+/// `[index * 2^height, (index + 1) * 2^height)`; leaves have height 0.
+#[derive(CandidType, Deserialize, Clone, Copy, Debug, PartialEq, Eq)]
+pub struct MmrNode {
+    pub height: u8,
+    pub index: u64,
+}
+
+#[derive(CandidType, Deserialize, Clone, Debug, PartialEq, Eq)]
+pub struct GetBlockProofArgs {
+    pub block_index: BlockIndex,
+    /// The node above the block at which the proof starts. Set this field to
+    /// the node returned by the archive storing the block.
+    /// If None then the proof starts at the leaf of the block.
+    pub from_node: Option<MmrNode>,
+}
+
+/// The path from an archived block to the largest MMR node stored in the
+/// archive that covers the block.
+#[derive(CandidType, Deserialize, Clone, Debug, PartialEq, Eq)]
+pub struct ArchivedBlockPath {
+    /// The node reached by the path.
+    pub node: MmrNode,
+    /// The hashes of the siblings on the path, starting at the leaf.
+    pub siblings: Vec<ByteBuf>,
+}
+
+/// An inclusion proof for a block, verifiable against the MMR root that the
+/// ledger certifies under the `mmr_root` label.
+#[derive(CandidType, Deserialize, Clone, Debug, PartialEq, Eq)]
+pub struct BlockProof {
+    /// The range of blocks `[mmr_start, mmr_end)` covered by the MMR.
+    pub mmr_start: u64,
+    pub mmr_end: u64,
+    /// The hashes of the siblings on the path from the requested node to
+    /// its peak.
+    pub siblings: Vec<ByteBuf>,
+    /// The hashes of all the peaks of the MMR, ordered from left to right.
+    pub peaks: Vec<ByteBuf>,
+    pub certificate: Option<ByteBuf>,
+}
+
+#[derive(CandidType, Deserialize, Clone, Debug, PartialEq, Eq)]
+pub enum GetBlockProofError {
+    /// The ledger no longer stores the nodes below the archive's part of the
+    /// MMR. Fetch the path of the block from the archive and retry with the
+    /// returned node as `from_node`.
+    BlockArchived { archive: Principal },
+    /// The requested block or node is not covered by the MMR.
+    ProofUnavailable { mmr_start: u64, mmr_end: u64 },
+}
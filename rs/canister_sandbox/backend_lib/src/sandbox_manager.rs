@@ -136,6 +136,8 @@ impl Execution {
                 allocated_bytes,
                 allocated_message_bytes,
                 instance_stats,
+                canister_log,
+                instruction_profile,
             },
             deltas,
             instance_or_system_api,
@@ -199,6 +201,8 @@ impl Execution {
                     allocated_message_bytes,
                     num_instructions_left,
                     instance_stats,
+                    canister_log,
+                    instruction_profile,
                 };
                 self.sandbox_manager.controller.execution_finished(
                     protocol::ctlsvc::ExecutionFinishedRequest {
@@ -224,6 +228,8 @@ impl Execution {
                     allocated_bytes,
                     allocated_message_bytes,
                     instance_stats,
+                    canister_log,
+                    instruction_profile,
                 };
 
                 self.sandbox_manager.controller.execution_finished(
@@ -22,6 +22,7 @@ use ic_test_utilities::types::{
 use ic_types::{
     crypto::CryptoHash,
     messages::{CallbackId, Payload, RejectContext, Request, RequestOrResponse, Response},
+    time::CoarseTime,
     xnet::StreamHeader,
     CryptoHashOfPartialState, Cycles, Funds,
 };
@@ -177,6 +178,81 @@ fn canonical_encoding_request() {
     );
 }
 
+/// Canonical CBOR encoding of:
+///
+/// ```no_run
+/// RequestOrResponse::Request(
+///     Request {
+///         receiver: canister_test_id(1),
+///         sender: canister_test_id(2),
+///         sender_reply_callback: CallbackId::from(3),
+///         payment: Cycles::new(4),
+///         method_name: "test".to_string(),
+///         method_payload: vec![6],
+///         deadline: CoarseTime::from_secs_since_unix_epoch(8),
+///     }
+/// )
+/// ```
+///
+/// Expected:
+///
+/// ```text
+/// A1                            # map(1)
+///    00                         # field_index(RequestOrResponse::request)
+///    A7                         # map(7)
+///       00                      # field_index(Request::receiver)
+///       4A                      # bytes(10)
+///          00000000000000010101 # "\x00\x00\x00\x00\x00\x00\x00\x01\x01\x01"
+///       01                      # field_index(Request::sender)
+///       4A                      # bytes(10)
+///          00000000000000020101 # "\x00\x00\x00\x00\x00\x00\x00\x02\x01\x01"
+///       02                      # field_index(Request::sender_reply_callback)
+///       03                      # unsigned(3)
+///       03                      # field_index(Request::payment)
+///       A1                      # map(1)
+///          00                   # field_index(Funds::cycles)
+///          A1                   # map(1)
+///             00                # field_index(Cycles::raw)
+///             04                # unsigned(4)
+///       04                      # field_index(Request::method_name)
+///       64                      # text(4)
+///          74657374             # "test"
+///       05                      # field_index(Request::method_payload)
+///       41                      # bytes(1)
+///          06                   # "\x06"
+///       07                      # field_index(Request::deadline)
+///       08                      # unsigned(8)
+/// Used http://cbor.me/ for printing the human friendly output.
+/// ```
+#[test]
+fn canonical_encoding_best_effort_request() {
+    let request: RequestOrResponse = RequestBuilder::new()
+        .receiver(canister_test_id(1))
+        .sender(canister_test_id(2))
+        .sender_reply_callback(CallbackId::from(3))
+        .payment(Cycles::new(4))
+        .method_name("test".to_string())
+        .method_payload(vec![6])
+        .deadline(CoarseTime::from_secs_since_unix_epoch(8))
+        .build()
+        .into();
+
+    for certification_version in all_supported_versions() {
+        if certification_version < CertificationVersion::V12 {
+            // The deadline is not encoded before V12.
+            assert_eq!(
+                "A1 00 A6 00 4A 00 00 00 00 00 00 00 01 01 01 01 4A 00 00 00 00 00 00 00 02 01 01 02 03 03 A1 00 A1 00 04 04 64 74 65 73 74 05 41 06",
+                as_hex(&encode_message(&request, certification_version))
+            );
+        } else {
+            assert_eq!(
+                "A1 00 A7 00 4A 00 00 00 00 00 00 00 01 01 01 01 4A 00 00 00 00 00 00 00 02 01 01 02 03 03 A1 00 A1 00 04 04 64 74 65 73 74 05 41 06 07 08",
+                as_hex(&encode_message(&request, certification_version))
+            );
+        }
+    }
+}
+
 /// Canonical CBOR encoding of:
 ///
 /// ```no_run
@@ -517,6 +593,16 @@ fn valid_request() {
     }
 }
 
+#[test]
+fn valid_best_effort_request() {
+    let request = RequestBuilder::new()
+        .deadline(CoarseTime::from_secs_since_unix_epoch(13))
+        .build();
+    let bytes = types::Request::proxy_encode((&request, CertificationVersion::V12)).unwrap();
+
+    assert_eq!(request, types::Request::proxy_decode(&bytes).unwrap());
+}
+
 #[test]
 fn invalid_request_extra_field() {
     for certification_version in all_supported_versions() {
@@ -527,7 +613,7 @@ fn invalid_request_extra_field() {
         assert_matches!(
             res,
             Err(ProxyDecodeError::CborDecodeError(err))
-                if err.to_string().contains("expected field index 0 <= i < 7")
+                if err.to_string().contains("expected field index 0 <= i < 8")
         );
     }
 }
@@ -622,7 +708,7 @@ fn invalid_response_extra_field() {
         assert_matches!(
             res,
             Err(ProxyDecodeError::CborDecodeError(err))
-                if err.to_string().contains("expected field index 0 <= i < 6")
+                if err.to_string().contains("expected field index 0 <= i < 7")
         );
     }
 }
@@ -69,7 +69,7 @@ mod tests {
         state::new_canister_state,
         types::ids::{canister_test_id, subnet_test_id, user_test_id},
     };
-    use ic_types::{CanisterId, Cycles, ExecutionRound};
+    use ic_types::{instruction_profile::InstructionProfile, CanisterId, Cycles, ExecutionRound};
     use ic_wasm_types::CanisterModule;
     use maplit::{btreemap, btreeset};
     use std::collections::{BTreeSet, VecDeque};
@@ -249,6 +249,7 @@ mod tests {
             metadata: WasmMetadata::new(metadata),
             last_executed_round: ExecutionRound::from(0),
             next_scheduled_method: NextScheduledMethod::default(),
+            instruction_profile: InstructionProfile::default(),
         };
 
         canister_state.execution_state = Some(execution_state);
@@ -68,6 +68,10 @@ pub struct FeatureFlags {
     /// Track dirty pages with a write barrier instead of the signal handler.
     pub write_barrier: FlagStatus,
     pub wasm_native_stable_memory: FlagStatus,
+    /// Allow canisters with a 64-bit Wasm memory.
+    pub wasm64: FlagStatus,
+    /// Record per-function instruction counts of canister executions.
+    pub canister_profiling: FlagStatus,
 }
 
 impl FeatureFlags {
@@ -76,6 +80,8 @@ impl FeatureFlags {
             rate_limiting_of_debug_prints: FlagStatus::Enabled,
             write_barrier: FlagStatus::Disabled,
             wasm_native_stable_memory: FlagStatus::Enabled,
+            wasm64: FlagStatus::Disabled,
+            canister_profiling: FlagStatus::Disabled,
         }
     }
 }
@@ -231,6 +231,11 @@ pub struct Config {
     /// Query cache capacity in bytes
     pub query_cache_capacity: NumBytes,
 
+    /// Indicates whether query cache entries stay valid while the memory pages
+    /// read by the query are unchanged, instead of being invalidated whenever
+    /// the canister version or balance changes.
+    pub query_cache_data_dependencies: FlagStatus,
+
     /// Sandbox process eviction does not activate if the number of sandbox
     /// processes is below this threshold.
     pub min_sandbox_count: usize,
@@ -253,6 +258,15 @@ pub struct Config {
     /// If this flag is enabled, then execution of a slice will produce a log
     /// entry with the number of executed instructions and the duration.
     pub trace_execution: FlagStatus,
+
+    /// Indicates whether canisters with a 64-bit Wasm memory are accepted.
+    /// The `Hypervisor` passes it on to `embedders::FeatureFlags::wasm64`.
+    pub wasm64: FlagStatus,
+
+    /// If this flag is enabled, then canister executions record the number of
+    /// instructions spent in each function. The profiles are meant for local
+    /// development and should not be enabled in production.
+    pub canister_profiling: FlagStatus,
 }
 
 impl Default for Config {
@@ -318,6 +332,7 @@ impl Default for Config {
             composite_queries: FlagStatus::Enabled,
             query_caching: FlagStatus::Enabled,
             query_cache_capacity: QUERY_CACHE_CAPACITY,
+            query_cache_data_dependencies: FlagStatus::Disabled,
             min_sandbox_count: embedders::DEFAULT_MIN_SANDBOX_COUNT,
             max_sandbox_count: embedders::DEFAULT_MAX_SANDBOX_COUNT,
             max_sandbox_idle_time: embedders::DEFAULT_MAX_SANDBOX_IDLE_TIME,
@@ -326,6 +341,8 @@ impl Default for Config {
             ),
             max_compilation_cache_size: MAX_COMPILATION_CACHE_SIZE,
             trace_execution: FlagStatus::Disabled,
+            wasm64: FlagStatus::Disabled,
+            canister_profiling: FlagStatus::Disabled,
         }
     }
 }
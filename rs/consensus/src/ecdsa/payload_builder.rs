@@ -1012,6 +1012,7 @@ pub(crate) fn get_signing_requests<'a>(
                         context.key_id
                     ),
                 }),
+                deadline: context.request.deadline,
             };
             ecdsa_payload.signature_agreements.insert(
                 context.pseudo_random_id,
@@ -1068,6 +1069,7 @@ pub(crate) fn get_signing_requests<'a>(
                         code: RejectCode::CanisterError,
                         message: "Signature request expired".to_string(),
                     }),
+                    deadline: context.request.deadline,
                 };
                 ecdsa_payload.signature_agreements.insert(
                     context.pseudo_random_id,
@@ -1156,6 +1158,7 @@ pub(crate) fn update_signature_agreements(
                 }
                 .encode(),
             ),
+            deadline: context.request.deadline,
         };
         completed.insert(*request_id, ecdsa::CompletedSignature::Unreported(response));
     }
@@ -1623,6 +1626,7 @@ fn make_reshare_dealings_response(
                             }
                             .encode(),
                         ),
+                        deadline: context.request.deadline,
                     });
                 }
             }
@@ -0,0 +1,214 @@
+//! Key derivation for threshold Schnorr signatures.
+use curve25519_dalek::constants::ED25519_BASEPOINT_TABLE;
+use curve25519_dalek::edwards::{CompressedEdwardsY, EdwardsPoint};
+use curve25519_dalek::scalar::Scalar;
+use ic_crypto_sha::{DomainSeparationContext, Sha512};
+use ic_crypto_tecdsa::derive_tecdsa_public_key;
+use ic_types::crypto::canister_threshold_sig::error::{
+    ThresholdEcdsaGetPublicKeyError, ThresholdSchnorrGetPublicKeyError,
+};
+use ic_types::crypto::canister_threshold_sig::{
+    ExtendedDerivationPath, MasterEcdsaPublicKey, MasterSchnorrPublicKey, SchnorrPublicKey,
+};
+use ic_types::crypto::AlgorithmId;
+
+const ED25519_KEY_DERIVATION_DOMAIN: &str = "ic-tschnorr-ed25519-key-derivation";
+
+/// Derives the Schnorr public key from the specified `master_public_key` for
+/// the given `extended_derivation_path`.
+///
+/// BIP340 keys are derived exactly like threshold ECDSA keys on secp256k1, so
+/// the derived key is returned as a compressed SEC1 point. Ed25519 keys are
+/// derived by adding an offset to the master key, see
+/// [`derive_ed25519_key_offset`].
+pub fn derive_tschnorr_public_key(
+    master_public_key: &MasterSchnorrPublicKey,
+    extended_derivation_path: &ExtendedDerivationPath,
+) -> Result<SchnorrPublicKey, ThresholdSchnorrGetPublicKeyError> {
+    match master_public_key.algorithm_id {
+        AlgorithmId::SchnorrSecp256k1 => {
+            let master_ecdsa_public_key = MasterEcdsaPublicKey {
+                algorithm_id: AlgorithmId::EcdsaSecp256k1,
+                public_key: master_public_key.public_key.clone(),
+            };
+            let derived =
+                derive_tecdsa_public_key(&master_ecdsa_public_key, extended_derivation_path)
+                    .map_err(|e| match e {
+                        ThresholdEcdsaGetPublicKeyError::InvalidArgument(s) => {
+                            ThresholdSchnorrGetPublicKeyError::InvalidArgument(s)
+                        }
+                        ThresholdEcdsaGetPublicKeyError::InternalError(s) => {
+                            ThresholdSchnorrGetPublicKeyError::InternalError(s)
+                        }
+                    })?;
+            Ok(SchnorrPublicKey {
+                algorithm_id: master_public_key.algorithm_id,
+                public_key: derived.public_key,
+                chain_key: derived.chain_key,
+            })
+        }
+        AlgorithmId::Ed25519 => {
+            let master_point = deserialize_ed25519_point(&master_public_key.public_key)?;
+            let (offset, chain_key) = ed25519_derive(&master_point, extended_derivation_path);
+            let derived = master_point + &offset * &ED25519_BASEPOINT_TABLE;
+            Ok(SchnorrPublicKey {
+                algorithm_id: master_public_key.algorithm_id,
+                public_key: derived.compress().to_bytes().to_vec(),
+                chain_key: chain_key.to_vec(),
+            })
+        }
+        algorithm_id => Err(ThresholdSchnorrGetPublicKeyError::InvalidArgument(format!(
+            "Algorithm {:?} is not supported for threshold Schnorr",
+            algorithm_id
+        ))),
+    }
+}
+
+/// Returns the offset, as a little-endian scalar, that is added to the Ed25519
+/// `master_public_key` to obtain the key for `extended_derivation_path`.
+///
+/// The derived secret scalar is the master secret scalar plus this offset,
+/// which allows environments holding the master secret key in the clear (e.g.
+/// tests) to sign with derived keys.
+pub fn derive_ed25519_key_offset(
+    master_public_key: &[u8],
+    extended_derivation_path: &ExtendedDerivationPath,
+) -> Result<[u8; 32], ThresholdSchnorrGetPublicKeyError> {
+    let master_point = deserialize_ed25519_point(master_public_key)?;
+    let (offset, _chain_key) = ed25519_derive(&master_point, extended_derivation_path);
+    Ok(offset.to_bytes())
+}
+
+fn deserialize_ed25519_point(
+    public_key: &[u8],
+) -> Result<EdwardsPoint, ThresholdSchnorrGetPublicKeyError> {
+    if public_key.len() != 32 {
+        return Err(ThresholdSchnorrGetPublicKeyError::InvalidArgument(format!(
+            "Invalid Ed25519 public key length {}",
+            public_key.len()
+        )));
+    }
+    CompressedEdwardsY::from_slice(public_key)
+        .decompress()
+        .ok_or_else(|| {
+            ThresholdSchnorrGetPublicKeyError::InvalidArgument(
+                "Invalid Ed25519 public key".to_string(),
+            )
+        })
+}
+
+/// Derives the key one path element at a time, starting with the caller: each
+/// element is hashed together with the current chain key and public key into
+/// the offset of this step and the next chain key.
+fn ed25519_derive(
+    master_public_key: &EdwardsPoint,
+    extended_derivation_path: &ExtendedDerivationPath,
+) -> (Scalar, [u8; 32]) {
+    let mut public_key = *master_public_key;
+    let mut chain_key = [0u8; 32];
+    let mut offset = Scalar::zero();
+
+    let path = std::iter::once(extended_derivation_path.caller.as_slice()).chain(
+        extended_derivation_path
+            .derivation_path
+            .iter()
+            .map(|index| index.as_slice()),
+    );
+    for index in path {
+        let mut hash =
+            Sha512::new_with_context(&DomainSeparationContext::new(ED25519_KEY_DERIVATION_DOMAIN));
+        hash.write(&chain_key);
+        hash.write(public_key.compress().as_bytes());
+        hash.write(&(index.len() as u64).to_be_bytes());
+        hash.write(index);
+        let digest = hash.finish();
+
+        let step = Scalar::from_bytes_mod_order_wide(&digest);
+        chain_key.copy_from_slice(&Sha512::hash(&digest)[..32]);
+        public_key += &step * &ED25519_BASEPOINT_TABLE;
+        offset += step;
+    }
+    (offset, chain_key)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use ic_types::PrincipalId;
+
+    fn path(caller: u64, derivation_path: Vec<Vec<u8>>) -> ExtendedDerivationPath {
+        ExtendedDerivationPath {
+            caller: PrincipalId::new_user_test_id(caller),
+            derivation_path,
+        }
+    }
+
+    #[test]
+    fn ed25519_derived_key_matches_derived_secret() {
+        let secret = Scalar::from_bytes_mod_order([7; 32]);
+        let master_public_key = MasterSchnorrPublicKey {
+            algorithm_id: AlgorithmId::Ed25519,
+            public_key: (&secret * &ED25519_BASEPOINT_TABLE)
+                .compress()
+                .to_bytes()
+                .to_vec(),
+        };
+        let path = path(1, vec![vec![1, 2, 3], vec![]]);
+
+        let derived = derive_tschnorr_public_key(&master_public_key, &path).unwrap();
+        let offset = derive_ed25519_key_offset(&master_public_key.public_key, &path).unwrap();
+        let derived_secret = secret + Scalar::from_bytes_mod_order(offset);
+        assert_eq!(
+            derived.public_key,
+            (&derived_secret * &ED25519_BASEPOINT_TABLE)
+                .compress()
+                .to_bytes()
+                .to_vec()
+        );
+        assert_eq!(derived.chain_key.len(), 32);
+    }
+
+    #[test]
+    fn ed25519_derivation_depends_on_caller_and_path() {
+        let master_public_key = MasterSchnorrPublicKey {
+            algorithm_id: AlgorithmId::Ed25519,
+            public_key: (&Scalar::from_bytes_mod_order([9; 32]) * &ED25519_BASEPOINT_TABLE)
+                .compress()
+                .to_bytes()
+                .to_vec(),
+        };
+        let derive = |path| {
+            derive_tschnorr_public_key(&master_public_key, &path)
+                .unwrap()
+                .public_key
+        };
+        assert_eq!(
+            derive(path(1, vec![vec![1]])),
+            derive(path(1, vec![vec![1]]))
+        );
+        assert_ne!(
+            derive(path(1, vec![vec![1]])),
+            derive(path(2, vec![vec![1]]))
+        );
+        assert_ne!(
+            derive(path(1, vec![vec![1]])),
+            derive(path(1, vec![vec![2]]))
+        );
+        assert_ne!(
+            derive(path(1, vec![vec![1, 2]])),
+            derive(path(1, vec![vec![1], vec![2]]))
+        );
+    }
+
+    #[test]
+    fn rejects_unsupported_algorithm() {
+        let master_public_key = MasterSchnorrPublicKey {
+            algorithm_id: AlgorithmId::EcdsaSecp256k1,
+            public_key: vec![],
+        };
+        assert!(matches!(
+            derive_tschnorr_public_key(&master_public_key, &path(1, vec![])),
+            Err(ThresholdSchnorrGetPublicKeyError::InvalidArgument(_))
+        ));
+    }
+}
@@ -139,6 +139,7 @@ pub(crate) fn setup() -> (
         Arc::clone(&cycles_account_manager),
         Arc::clone(&state_manager) as Arc<_>,
         Arc::clone(&state_manager.get_fd_factory()),
+        None,
     );
 
     let message_routing = MessageRoutingImpl::new(
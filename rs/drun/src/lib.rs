@@ -2,10 +2,10 @@
 
 use crate::message::{msg_stream_from_file, Message};
 use hex::encode;
-use ic_config::{subnet_config::SubnetConfig, Config};
+use ic_config::{flag_status::FlagStatus, subnet_config::SubnetConfig, Config};
 use ic_cycles_account_manager::CyclesAccountManager;
 use ic_error_types::{ErrorCode, UserError};
-use ic_execution_environment::ExecutionServices;
+use ic_execution_environment::{util::folded_instruction_profile, ExecutionServices};
 use ic_http_endpoints_metrics::MetricsHttpEndpoint;
 use ic_interfaces::{execution_environment::IngressHistoryReader, messaging::MessageRouting};
 use ic_interfaces_state_manager::StateReader;
@@ -62,6 +62,7 @@ pub struct DrunOptions {
     pub log_file: Option<PathBuf>,
     pub instruction_limit: Option<u64>,
     pub subnet_type: SubnetType,
+    pub instruction_profile_file: Option<PathBuf>,
 }
 
 /// Deliver a single message to the Message Routing layer
@@ -161,6 +162,7 @@ pub fn run_drun(uo: DrunOptions) -> Result<(), String> {
         log_file,
         instruction_limit,
         subnet_type,
+        instruction_profile_file,
     } = uo;
     // Hardcoded magic values to create a ReplicaConfig that parses.
     let mut subnet_config = SubnetConfig::new(subnet_type);
@@ -175,6 +177,10 @@ pub fn run_drun(uo: DrunOptions) -> Result<(), String> {
         cfg.hypervisor.max_query_call_graph_instructions = NumInstructions::new(instruction_limit);
     }
 
+    if instruction_profile_file.is_some() {
+        cfg.hypervisor.canister_profiling = FlagStatus::Enabled;
+    }
+
     let subnet_id = SubnetId::from(PrincipalId::new_subnet_test_id(0));
     let root_subnet_id = SubnetId::from(PrincipalId::new_subnet_test_id(1));
     let replica_config = ReplicaConfig {
@@ -225,6 +231,7 @@ pub fn run_drun(uo: DrunOptions) -> Result<(), String> {
             Arc::clone(&cycles_account_manager),
             Arc::clone(&state_manager) as Arc<_>,
             state_manager.get_fd_factory(),
+            None,
         )
         .into_parts();
 
@@ -249,7 +256,7 @@ pub fn run_drun(uo: DrunOptions) -> Result<(), String> {
         MaliciousFlags::default(),
     );
 
-    msg_stream.try_for_each(|parse_result| {
+    let result = msg_stream.try_for_each(|parse_result| {
         parse_result.map(|msg| match msg {
             Message::Install(msg) => {
                 deliver_message(
@@ -288,7 +295,30 @@ pub fn run_drun(uo: DrunOptions) -> Result<(), String> {
                 );
             }
         })
-    })
+    });
+
+    if let Some(instruction_profile_file) = instruction_profile_file {
+        // Every canister becomes the root frame of its functions, so that the
+        // profiles of all canisters can be rendered as a single flamegraph.
+        let state = state_manager.get_latest_state().take();
+        let mut folded = String::new();
+        for (canister_id, canister) in state.canister_states.iter() {
+            if let Some(execution_state) = canister.execution_state.as_ref() {
+                for line in folded_instruction_profile(execution_state).lines() {
+                    folded.push_str(&format!("{};{}\n", canister_id, line));
+                }
+            }
+        }
+        std::fs::write(&instruction_profile_file, folded).map_err(|err| {
+            format!(
+                "Failed to write the instruction profile to {}: {}",
+                instruction_profile_file.display(),
+                err
+            )
+        })?;
+    }
+
+    result
 }
 
 fn print_query_result(res: Result<WasmResult, UserError>) {
@@ -344,6 +374,7 @@ fn build_batch(message_routing: &dyn MessageRouting, msgs: Vec<SignedIngress>) -
         },
         randomness: Randomness::from(get_random_seed()),
         ecdsa_subnet_public_keys: BTreeMap::new(),
+        schnorr_subnet_public_keys: BTreeMap::new(),
         registry_version: RegistryVersion::from(1),
         time: time::current_time(),
         consensus_responses: vec![],
@@ -16,6 +16,7 @@ const ARG_MESSAGES: &str = "messages";
 const ARG_EXTRA_BATCHES: &str = "extra-batches";
 const ARG_INSTRUCTION_LIMIT: &str = "instruction-limit";
 const ARG_SUBNET_TYPE: &str = "subnet-type";
+const ARG_INSTRUCTION_PROFILE: &str = "instruction-profile";
 
 fn main() -> Result<(), String> {
     // Check if `drun` is running in the canister sandbox mode where it waits
@@ -81,6 +82,8 @@ async fn drun_main() -> Result<(), String> {
             })
             .unwrap_or(SubnetType::System);
 
+        let instruction_profile_file = matches.value_of(ARG_INSTRUCTION_PROFILE).map(PathBuf::from);
+
         let uo = DrunOptions {
             msg_filename: matches.value_of(ARG_MESSAGES).unwrap().to_string(),
             cfg,
@@ -88,6 +91,7 @@ async fn drun_main() -> Result<(), String> {
             log_file,
             instruction_limit,
             subnet_type,
+            instruction_profile_file,
         };
         run_drun(uo)
     })
@@ -149,5 +153,15 @@ fn get_arg_matches() -> ArgMatches {
                 .value_name("Subnet Type")
                 .takes_value(true),
         )
+        .arg(
+            Arg::new(ARG_INSTRUCTION_PROFILE)
+                .long(ARG_INSTRUCTION_PROFILE)
+                .value_name("profile_file")
+                .help(
+                    "Records the instructions executed by each canister function and writes \
+                    them to the given file in the folded stack format (default: None).",
+                )
+                .takes_value(true),
+        )
         .get_matches()
 }
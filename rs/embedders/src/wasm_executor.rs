@@ -2,6 +2,7 @@ use std::path::PathBuf;
 use std::sync::Arc;
 
 use ic_replicated_state::canister_state::execution_state::WasmBinary;
+use ic_replicated_state::canister_state::WASM_PAGE_SIZE_IN_BYTES;
 use ic_replicated_state::page_map::PageAllocatorFileDescriptor;
 use ic_replicated_state::{ExportedFunctions, Global, Memory, NumWasmPages, PageMap};
 use ic_system_api::sandbox_safe_system_state::{SandboxSafeSystemState, SystemStateChanges};
@@ -29,7 +30,10 @@ use ic_sys::{page_bytes_from_ptr, PageBytes, PageIndex, PAGE_SIZE};
 use ic_system_api::{
     system_api_empty::SystemApiEmpty, ExecutionParameters, ModificationTracking, SystemApiImpl,
 };
-use ic_types::{CanisterId, NumBytes, NumInstructions};
+use ic_types::{
+    canister_log::CanisterLog, instruction_profile::InstructionProfile, CanisterId, NumBytes,
+    NumInstructions,
+};
 use ic_wasm_types::{BinaryEncodedWasm, CanisterModule};
 use std::collections::hash_map::DefaultHasher;
 use std::hash::{Hash, Hasher};
@@ -466,6 +470,8 @@ pub fn wasm_execution_error(
             allocated_bytes: NumBytes::from(0),
             allocated_message_bytes: NumBytes::from(0),
             instance_stats: InstanceStats::default(),
+            canister_log: CanisterLog::default(),
+            instruction_profile: InstructionProfile::default(),
         },
         None,
     )
@@ -589,6 +595,7 @@ pub fn process(
     Result<WasmtimeInstance<SystemApiImpl>, SystemApiImpl>,
 ) {
     let canister_id = sandbox_safe_system_state.canister_id();
+    let wasm_memory_limit = sandbox_safe_system_state.wasm_memory_limit();
     let modification_tracking = api_type.modification_tracking();
     let system_api = SystemApiImpl::new(
         api_type,
@@ -627,6 +634,8 @@ pub fn process(
                     allocated_bytes: NumBytes::from(0),
                     allocated_message_bytes: NumBytes::from(0),
                     instance_stats: InstanceStats::default(),
+                    canister_log: CanisterLog::default(),
+                    instruction_profile: InstructionProfile::default(),
                 },
                 None,
                 Err(system_api),
@@ -672,10 +681,36 @@ pub fn process(
     let wasm_heap_limit =
         NumWasmPages::from(wasmtime_environ::WASM32_MAX_PAGES as usize) - wasm_reserved_pages;
 
-    if wasm_heap_size_after > wasm_heap_limit {
+    // The reserved pages only apply to 32-bit memories, whose maximum size is
+    // enforced by instrumentation for Wasm64 canisters.
+    if !instance.is_wasm64() && wasm_heap_size_after > wasm_heap_limit {
         wasm_result = Err(HypervisorError::WasmReservedPages);
     }
 
+    // The Wasm memory limit only prevents the Wasm memory from growing, so that
+    // a canister whose memory already exceeds a newly set limit keeps working.
+    if let Some(limit) = wasm_memory_limit {
+        let wasm_heap_size_in_bytes =
+            NumBytes::from((wasm_heap_size_after.get() * WASM_PAGE_SIZE_IN_BYTES) as u64);
+        if wasm_result.is_ok()
+            && wasm_heap_size_after > wasm_memory.size
+            && wasm_heap_size_in_bytes > limit
+        {
+            wasm_result = Err(HypervisorError::WasmMemoryLimitExceeded {
+                bytes: wasm_heap_size_in_bytes,
+                limit,
+            });
+        }
+    }
+
+    if let Err(err) = &wasm_result {
+        instance.store_data_mut().system_api.save_trap_message(err);
+    }
+    let canister_log = instance.store_data_mut().system_api.take_canister_log();
+    let mut instruction_profile =
+        std::mem::take(&mut instance.store_data_mut().instruction_profile);
+    instruction_profile.finish(message_instructions_executed.get());
+
     let mut allocated_bytes = NumBytes::from(0);
     let mut allocated_message_bytes = NumBytes::from(0);
     let mut execution_complexity = ExecutionComplexity::default();
@@ -748,6 +783,8 @@ pub fn process(
             allocated_bytes,
             allocated_message_bytes,
             instance_stats,
+            canister_log,
+            instruction_profile,
         },
         wasm_state_changes,
         Ok(instance),
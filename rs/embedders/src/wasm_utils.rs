@@ -1,5 +1,5 @@
 use std::{
-    collections::{BTreeSet, HashMap},
+    collections::{BTreeMap, BTreeSet, HashMap},
     time::Instant,
 };
 
@@ -201,6 +201,28 @@ pub struct InstrumentationOutput {
     pub compilation_cost: NumInstructions,
 }
 
+/// Returns the function names from the `name` section of the given Wasm
+/// module, keyed by function index. Malformed entries are skipped, since the
+/// names are only used for presentation.
+pub fn function_names(wasm: &BinaryEncodedWasm) -> BTreeMap<u32, String> {
+    let mut names = BTreeMap::new();
+    for payload in wasmparser::Parser::new(0).parse_all(wasm.as_slice()) {
+        let reader = match payload {
+            Ok(wasmparser::Payload::CustomSection(reader)) if reader.name() == "name" => reader,
+            _ => continue,
+        };
+        let subsections = wasmparser::NameSectionReader::new(reader.data(), reader.data_offset());
+        for subsection in subsections {
+            if let Ok(wasmparser::Name::Function(map)) = subsection {
+                for naming in map.into_iter().flatten() {
+                    names.insert(naming.index, naming.name.to_string());
+                }
+            }
+        }
+    }
+    names
+}
+
 fn validate_and_instrument(
     wasm: &BinaryEncodedWasm,
     config: &EmbeddersConfig,
@@ -211,6 +233,7 @@ fn validate_and_instrument(
         config.cost_to_compile_wasm_instruction,
         config.feature_flags.write_barrier,
         config.feature_flags.wasm_native_stable_memory,
+        config.feature_flags.canister_profiling,
         config.subnet_type,
         config.dirty_page_overhead,
     )?;
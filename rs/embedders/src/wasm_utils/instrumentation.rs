@@ -32,6 +32,31 @@
 //! ```
 //! Where the last three will only be inserted if Wasm-native stable memory is enabled.
 //!
+//! If canister profiling is enabled, two more functions are inserted after
+//! them:
+//!
+//! ```wasm
+//! (import "__" "profile_enter" (func ((param i32))))
+//! (import "__" "profile_exit" (func ((param i32))))
+//! ```
+//!
+//! Every function of the original module then reports its entry and exit
+//! with its original function index, so that the host can attribute the
+//! executed instructions to call stacks:
+//!
+//! ```wasm
+//! (func (;x;) (type t)
+//!   i32.const x
+//!   call profile_enter
+//!   block (result ...)
+//!     ;; original body, with `profile_exit` called before every `return`
+//!   end
+//!   i32.const x
+//!   call profile_exit)
+//! ```
+//!
+//! These calls are injected after metering, so they are not charged.
+//!
 //! It then inserts (and exports) a global mutable counter:
 //! ```wasm
 //! (global (;0;) (mut i64) (i64.const 0))
@@ -117,7 +142,7 @@ use ic_config::flag_status::FlagStatus;
 use ic_registry_subnet_type::SubnetType;
 use ic_replicated_state::NumWasmPages;
 use ic_sys::PAGE_SIZE;
-use ic_types::{methods::WasmMethod, MAX_WASM_MEMORY_IN_BYTES};
+use ic_types::{methods::WasmMethod, MAX_WASM64_MEMORY_IN_BYTES, MAX_WASM_MEMORY_IN_BYTES};
 use ic_types::{NumInstructions, MAX_STABLE_MEMORY_IN_BYTES};
 use ic_wasm_types::{BinaryEncodedWasm, WasmError, WasmInstrumentationError};
 use wasmtime_environ::WASM_PAGE_SIZE;
@@ -145,13 +170,27 @@ pub(crate) enum InjectedImports {
 }
 
 impl InjectedImports {
-    fn count(wasm_native_stable_memory: FlagStatus) -> usize {
-        if wasm_native_stable_memory == FlagStatus::Enabled {
+    fn count(wasm_native_stable_memory: FlagStatus, canister_profiling: FlagStatus) -> usize {
+        let count = if wasm_native_stable_memory == FlagStatus::Enabled {
             5
         } else {
             2
+        };
+        match canister_profiling {
+            FlagStatus::Enabled => count + 2,
+            FlagStatus::Disabled => count,
         }
     }
+
+    // The profiling imports follow all other injected imports, so their
+    // indices depend on whether Wasm-native stable memory is enabled.
+    fn profile_enter(wasm_native_stable_memory: FlagStatus) -> u32 {
+        Self::count(wasm_native_stable_memory, FlagStatus::Disabled) as u32
+    }
+
+    fn profile_exit(wasm_native_stable_memory: FlagStatus) -> u32 {
+        Self::profile_enter(wasm_native_stable_memory) + 1
+    }
 }
 
 // Gets the cost of an instruction.
@@ -175,6 +214,8 @@ const UPDATE_MEMORY_FUN_NAME: &str = "update_available_memory";
 const TRY_GROW_STABLE_MEMORY_FUN_NAME: &str = "try_grow_stable_memory";
 const INTERNAL_TRAP_FUN_NAME: &str = "internal_trap";
 const STABLE_READ_FIRST_ACCESS_NAME: &str = "stable_read_first_access";
+const PROFILE_ENTER_FUN_NAME: &str = "profile_enter";
+const PROFILE_EXIT_FUN_NAME: &str = "profile_exit";
 const TABLE_STR: &str = "table";
 pub(crate) const INSTRUCTIONS_COUNTER_GLOBAL_NAME: &str = "canister counter_instructions";
 pub(crate) const DIRTY_PAGES_COUNTER_GLOBAL_NAME: &str = "canister counter_dirty_pages";
@@ -185,6 +226,10 @@ const CANISTER_START_STR: &str = "canister_start";
 const BYTEMAP_SIZE_IN_WASM_PAGES: u64 =
     MAX_WASM_MEMORY_IN_BYTES / (PAGE_SIZE as u64) / (WASM_PAGE_SIZE as u64);
 
+const MAX_WASM64_MEMORY_IN_WASM_PAGES: u64 = MAX_WASM64_MEMORY_IN_BYTES / (WASM_PAGE_SIZE as u64);
+/// There is one byte for each OS page in the wasm64 heap.
+const WASM64_BYTEMAP_SIZE_IN_WASM_PAGES: u64 = MAX_WASM64_MEMORY_IN_WASM_PAGES / (PAGE_SIZE as u64);
+
 const MAX_STABLE_MEMORY_IN_WASM_PAGES: u64 = MAX_STABLE_MEMORY_IN_BYTES / (WASM_PAGE_SIZE as u64);
 /// There is one byte for each OS page in the stable memory.
 const STABLE_BYTEMAP_SIZE_IN_WASM_PAGES: u64 = MAX_STABLE_MEMORY_IN_WASM_PAGES / (PAGE_SIZE as u64);
@@ -269,12 +314,24 @@ fn mutate_function_indices(module: &mut Module, f: impl Fn(u32) -> u32) {
 /// added as the last imports, we'd need to increment only non imported
 /// functions, since imported functions precede all others in the function index
 /// space, but this would be error-prone).
-fn inject_helper_functions(mut module: Module, wasm_native_stable_memory: FlagStatus) -> Module {
+fn inject_helper_functions(
+    mut module: Module,
+    wasm_native_stable_memory: FlagStatus,
+    canister_profiling: FlagStatus,
+    is_wasm64: bool,
+) -> Module {
     // insert types
     let ooi_type = Type::Func(FuncType::new([], []));
+    // The arguments and the result of `update_available_memory` have the type
+    // of the heap addresses.
+    let heap_index_type = if is_wasm64 {
+        ValType::I64
+    } else {
+        ValType::I32
+    };
     let uam_type = Type::Func(FuncType::new(
-        [ValType::I32, ValType::I32, ValType::I32],
-        [ValType::I32],
+        [heap_index_type, heap_index_type, heap_index_type],
+        [heap_index_type],
     ));
 
     let ooi_type_idx = add_type(&mut module, ooi_type);
@@ -294,8 +351,9 @@ fn inject_helper_functions(mut module: Module, wasm_native_stable_memory: FlagSt
     };
 
     let mut old_imports = module.imports;
-    module.imports =
-        Vec::with_capacity(old_imports.len() + InjectedImports::count(wasm_native_stable_memory));
+    module.imports = Vec::with_capacity(
+        old_imports.len() + InjectedImports::count(wasm_native_stable_memory, canister_profiling),
+    );
     module.imports.push(ooi_imp);
     module.imports.push(uam_imp);
 
@@ -334,10 +392,22 @@ fn inject_helper_functions(mut module: Module, wasm_native_stable_memory: FlagSt
         module.imports.push(fr_imp);
     }
 
+    if canister_profiling == FlagStatus::Enabled {
+        let profile_type = Type::Func(FuncType::new([ValType::I32], []));
+        let profile_type_idx = add_type(&mut module, profile_type);
+        for name in [PROFILE_ENTER_FUN_NAME, PROFILE_EXIT_FUN_NAME] {
+            module.imports.push(Import {
+                module: INSTRUMENTED_FUN_MODULE,
+                name,
+                ty: TypeRef::Func(profile_type_idx),
+            });
+        }
+    }
+
     module.imports.append(&mut old_imports);
 
     // now increment all function references by InjectedImports::Count
-    let cnt = InjectedImports::count(wasm_native_stable_memory) as u32;
+    let cnt = InjectedImports::count(wasm_native_stable_memory, canister_profiling) as u32;
     mutate_function_indices(&mut module, |i| i + cnt);
 
     debug_assert!(
@@ -360,6 +430,16 @@ fn inject_helper_functions(mut module: Module, wasm_native_stable_memory: FlagSt
                 == "stable_read_first_access"
         );
     }
+    if canister_profiling == FlagStatus::Enabled {
+        debug_assert!(
+            module.imports[InjectedImports::profile_enter(wasm_native_stable_memory) as usize].name
+                == "profile_enter"
+        );
+        debug_assert!(
+            module.imports[InjectedImports::profile_exit(wasm_native_stable_memory) as usize].name
+                == "profile_exit"
+        );
+    }
 
     module
 }
@@ -387,14 +467,27 @@ pub(super) fn instrument(
     cost_to_compile_wasm_instruction: NumInstructions,
     write_barrier: FlagStatus,
     wasm_native_stable_memory: FlagStatus,
+    canister_profiling: FlagStatus,
     subnet_type: SubnetType,
     dirty_page_overhead: NumInstructions,
 ) -> Result<InstrumentationOutput, WasmInstrumentationError> {
     let stable_memory_index;
-    let mut module = inject_helper_functions(module, wasm_native_stable_memory);
+    // The heap is always the first memory of the module.
+    let is_wasm64 = module.memories.first().map_or(false, |m| m.memory64);
+    let num_original_imported_functions = module
+        .imports
+        .iter()
+        .filter(|i| matches!(i.ty, TypeRef::Func(_)))
+        .count() as u32;
+    let mut module = inject_helper_functions(
+        module,
+        wasm_native_stable_memory,
+        canister_profiling,
+        is_wasm64,
+    );
     module = export_table(module);
     (module, stable_memory_index) =
-        update_memories(module, write_barrier, wasm_native_stable_memory);
+        update_memories(module, write_barrier, wasm_native_stable_memory, is_wasm64);
 
     let mut extra_strs: Vec<String> = Vec::new();
     module = export_mutable_globals(module, &mut extra_strs);
@@ -448,7 +541,15 @@ pub(super) fn instrument(
 
     // inject instructions counter decrementation
     for func_body in &mut module.code_sections {
-        inject_metering(&mut func_body.instructions, &special_indices);
+        inject_metering(&mut func_body.instructions, &special_indices, is_wasm64);
+    }
+
+    if canister_profiling == FlagStatus::Enabled {
+        inject_profiling(
+            &mut module,
+            num_original_imported_functions,
+            wasm_native_stable_memory,
+        );
     }
 
     // Collect all the function types of the locally defined functions inside the
@@ -468,9 +569,9 @@ pub(super) fn instrument(
     if !func_types.is_empty() {
         let func_bodies = &mut module.code_sections;
         for (func_ix, func_type) in func_types.into_iter().enumerate() {
-            inject_update_available_memory(&mut func_bodies[func_ix], &func_type);
+            inject_update_available_memory(&mut func_bodies[func_ix], &func_type, is_wasm64);
             if write_barrier == FlagStatus::Enabled {
-                inject_mem_barrier(&mut func_bodies[func_ix], &func_type);
+                inject_mem_barrier(&mut func_bodies[func_ix], &func_type, is_wasm64);
             }
         }
     }
@@ -483,6 +584,7 @@ pub(super) fn instrument(
             special_indices,
             subnet_type,
             dirty_page_overhead,
+            is_wasm64,
         )
     }
 
@@ -561,6 +663,7 @@ fn replace_system_api_functions(
     special_indices: SpecialIndices,
     subnet_type: SubnetType,
     dirty_page_overhead: NumInstructions,
+    is_wasm64: bool,
 ) {
     let api_indexes = calculate_api_indexes(module);
     let number_of_func_imports = module
@@ -573,7 +676,7 @@ fn replace_system_api_functions(
     // replaced.
     let mut func_index_replacements = BTreeMap::new();
     for (api, (ty, body)) in
-        replacement_functions(special_indices, subnet_type, dirty_page_overhead)
+        replacement_functions(special_indices, subnet_type, dirty_page_overhead, is_wasm64)
     {
         if let Some(old_index) = api_indexes.get(&api) {
             let type_idx = add_type(module, ty);
@@ -590,6 +693,67 @@ fn replace_system_api_functions(
     });
 }
 
+// Injects calls to `profile_enter` and `profile_exit` into all locally defined
+// functions of the module. The functions are identified by their index in the
+// original module, which is what the `name` section of the module refers to.
+//
+// The original body is wrapped in a block, so that branches to the outermost
+// label of the function also reach the call to `profile_exit`.
+fn inject_profiling(
+    module: &mut Module,
+    num_original_imported_functions: u32,
+    wasm_native_stable_memory: FlagStatus,
+) {
+    let profile_enter = InjectedImports::profile_enter(wasm_native_stable_memory);
+    let profile_exit = InjectedImports::profile_exit(wasm_native_stable_memory);
+    for func_ix in 0..module.code_sections.len() {
+        let Type::Func(func_type) = &module.types[module.functions[func_ix] as usize];
+        let results = func_type.results().to_vec();
+        let blockty = match results.as_slice() {
+            [] => BlockType::Empty,
+            [result] => BlockType::Type(*result),
+            _ => BlockType::FuncType(add_type(module, Type::Func(FuncType::new([], results)))),
+        };
+        let original_ix = num_original_imported_functions + func_ix as u32;
+        let body = &mut module.code_sections[func_ix].instructions;
+        let mut instructions = Vec::with_capacity(body.len() + 8);
+        instructions.extend_from_slice(&[
+            Operator::I32Const {
+                value: original_ix as i32,
+            },
+            Operator::Call {
+                function_index: profile_enter,
+            },
+            Operator::Block { blockty },
+        ]);
+        for op in body.drain(..) {
+            if let Operator::Return = op {
+                instructions.extend_from_slice(&[
+                    Operator::I32Const {
+                        value: original_ix as i32,
+                    },
+                    Operator::Call {
+                        function_index: profile_exit,
+                    },
+                ]);
+            }
+            instructions.push(op);
+        }
+        // The last instruction of the original body is the `end` of the
+        // function, which now closes the injected block.
+        instructions.extend_from_slice(&[
+            Operator::I32Const {
+                value: original_ix as i32,
+            },
+            Operator::Call {
+                function_index: profile_exit,
+            },
+            Operator::End,
+        ]);
+        *body = instructions;
+    }
+}
+
 // Helper function used by instrumentation to export additional symbols.
 //
 // Returns the new module or panics in debug mode if a symbol is not reserved.
@@ -830,7 +994,9 @@ enum Scope {
 // Describes how to calculate the instruction cost at this injection point.
 // `StaticCost` injection points contain information about the cost of the
 // following basic block. `DynamicCost` injection points assume there is an i32
-// on the stack which should be decremented from the instruction counter.
+// on the stack which should be decremented from the instruction counter. The
+// size argument of `memory.fill` and `memory.copy` on a 64-bit heap is an i64
+// instead.
 #[derive(Copy, Clone, Debug, PartialEq)]
 enum InjectionPointCostDetail {
     StaticCost { scope: Scope, cost: u64 },
@@ -880,7 +1046,7 @@ impl InjectionPoint {
 // - we insert a function call before each dynamic cost instruction which
 //   performs an overflow check and then decrements the counter by the value at
 //   the top of the stack.
-fn inject_metering(code: &mut Vec<Operator>, export_data_module: &SpecialIndices) {
+fn inject_metering(code: &mut Vec<Operator>, export_data_module: &SpecialIndices, is_wasm64: bool) {
     let points = injections(code);
     let points = points.iter().filter(|point| match point.cost_detail {
         InjectionPointCostDetail::StaticCost {
@@ -927,6 +1093,17 @@ fn inject_metering(code: &mut Vec<Operator>, export_data_module: &SpecialIndices
                     ]);
                 }
             }
+            InjectionPointCostDetail::DynamicCost
+                if is_wasm64
+                    && matches!(
+                        orig_elems[point.position],
+                        MemoryFill { .. } | MemoryCopy { .. }
+                    ) =>
+            {
+                elems.push(Call {
+                    function_index: export_data_module.decr_instruction_counter_fn,
+                });
+            }
             InjectionPointCostDetail::DynamicCost => {
                 elems.extend_from_slice(&[
                     I64ExtendI32U,
@@ -952,76 +1129,79 @@ fn write_barrier_instructions<'a>(
     offset: u64,
     val_arg_idx: u32,
     addr_arg_idx: u32,
+    is_wasm64: bool,
 ) -> Vec<Operator<'a>> {
     use Operator::*;
     let page_size_shift = PAGE_SIZE.trailing_zeros() as i32;
     let tracking_mem_idx = 1;
-    if offset % PAGE_SIZE as u64 == 0 {
-        vec![
-            LocalSet {
-                local_index: val_arg_idx,
-            }, // value
-            LocalTee {
-                local_index: addr_arg_idx,
-            }, // address
-            I32Const {
-                value: page_size_shift,
-            },
-            I32ShrU,
-            I32Const { value: 1 },
-            I32Store8 {
-                memarg: wasmparser::MemArg {
-                    align: 0,
-                    max_align: 0,
-                    offset: offset >> page_size_shift,
-                    memory: tracking_mem_idx,
+    let mut instructions = vec![
+        LocalSet {
+            local_index: val_arg_idx,
+        }, // value
+        LocalTee {
+            local_index: addr_arg_idx,
+        }, // address
+    ];
+    // A page aligned offset can be applied to the bytemap store directly.
+    let bytemap_offset = if offset % PAGE_SIZE as u64 == 0 {
+        offset >> page_size_shift
+    } else {
+        if is_wasm64 {
+            instructions.extend([
+                I64Const {
+                    value: offset as i64,
                 },
+                I64Add,
+            ]);
+        } else {
+            instructions.extend([
+                I32Const {
+                    value: offset as i32,
+                },
+                I32Add,
+            ]);
+        }
+        0
+    };
+    if is_wasm64 {
+        // The page index of a Wasm64 heap address always fits into an i32.
+        instructions.extend([
+            I64Const {
+                value: page_size_shift as i64,
             },
-            // Put original params on the stack
-            LocalGet {
-                local_index: addr_arg_idx,
-            },
-            LocalGet {
-                local_index: val_arg_idx,
-            },
-        ]
+            I64ShrU,
+            I32WrapI64,
+        ]);
     } else {
-        vec![
-            LocalSet {
-                local_index: val_arg_idx,
-            }, // value
-            LocalTee {
-                local_index: addr_arg_idx,
-            }, // address
-            I32Const {
-                value: offset as i32,
-            },
-            I32Add,
+        instructions.extend([
             I32Const {
                 value: page_size_shift,
             },
             I32ShrU,
-            I32Const { value: 1 },
-            I32Store8 {
-                memarg: wasmparser::MemArg {
-                    align: 0,
-                    max_align: 0,
-                    offset: 0,
-                    memory: tracking_mem_idx,
-                },
-            },
-            // Put original params on the stack
-            LocalGet {
-                local_index: addr_arg_idx,
-            },
-            LocalGet {
-                local_index: val_arg_idx,
-            },
-        ]
+        ]);
     }
+    instructions.extend([
+        I32Const { value: 1 },
+        I32Store8 {
+            memarg: wasmparser::MemArg {
+                align: 0,
+                max_align: 0,
+                offset: bytemap_offset,
+                memory: tracking_mem_idx,
+            },
+        },
+        // Put original params on the stack
+        LocalGet {
+            local_index: addr_arg_idx,
+        },
+        LocalGet {
+            local_index: val_arg_idx,
+        },
+    ]);
+    instructions
 }
 
-fn inject_mem_barrier(func_body: &mut wasm_transform::Body, func_type: &FuncType) {
+fn inject_mem_barrier(func_body: &mut wasm_transform::Body, func_type: &FuncType, is_wasm64: bool) {
     use Operator::*;
     let mut val_i32_needed = false;
     let mut val_i64_needed = false;
@@ -1060,8 +1240,11 @@ fn inject_mem_barrier(func_body: &mut wasm_transform::Body, func_type: &FuncType
         // the total number of locals.
         let n_locals: u32 = func_body.locals.iter().map(|x| x.0).sum();
         let mut next_local = func_type.params().len() as u32 + n_locals;
-        let arg_i32_addr_idx = next_local;
+        let arg_addr_idx = next_local;
         next_local += 1;
+        if is_wasm64 {
+            func_body.locals.push((1, ValType::I64));
+        }
 
         // conditionally add following locals
         let arg_i32_val_idx;
@@ -1069,7 +1252,15 @@ fn inject_mem_barrier(func_body: &mut wasm_transform::Body, func_type: &FuncType
         let arg_f32_val_idx;
         let arg_f64_val_idx;
 
-        if val_i32_needed {
+        if is_wasm64 {
+            if val_i32_needed {
+                arg_i32_val_idx = next_local;
+                next_local += 1;
+                func_body.locals.push((1, ValType::I32));
+            } else {
+                arg_i32_val_idx = u32::MAX;
+            }
+        } else if val_i32_needed {
             arg_i32_val_idx = next_local;
             next_local += 1;
             func_body.locals.push((2, ValType::I32)); // addr and val locals
@@ -1114,7 +1305,8 @@ fn inject_mem_barrier(func_body: &mut wasm_transform::Body, func_type: &FuncType
                     elems.extend_from_slice(&write_barrier_instructions(
                         memarg.offset,
                         arg_i32_val_idx,
-                        arg_i32_addr_idx,
+                        arg_addr_idx,
+                        is_wasm64,
                     ));
                 }
                 I64Store { memarg }
@@ -1124,21 +1316,24 @@ fn inject_mem_barrier(func_body: &mut wasm_transform::Body, func_type: &FuncType
                     elems.extend_from_slice(&write_barrier_instructions(
                         memarg.offset,
                         arg_i64_val_idx,
-                        arg_i32_addr_idx,
+                        arg_addr_idx,
+                        is_wasm64,
                     ));
                 }
                 F32Store { memarg } => {
                     elems.extend_from_slice(&write_barrier_instructions(
                         memarg.offset,
                         arg_f32_val_idx,
-                        arg_i32_addr_idx,
+                        arg_addr_idx,
+                        is_wasm64,
                     ));
                 }
                 F64Store { memarg } => {
                     elems.extend_from_slice(&write_barrier_instructions(
                         memarg.offset,
                         arg_f64_val_idx,
-                        arg_i32_addr_idx,
+                        arg_addr_idx,
+                        is_wasm64,
                     ));
                 }
                 _ => {}
@@ -1157,7 +1352,11 @@ fn inject_mem_barrier(func_body: &mut wasm_transform::Body, func_type: &FuncType
 // `table.grow` instruction to make sure that there's enough available memory
 // left to support the requested extra memory. If no `memory.grow` or
 // `table.grow` instructions are present then the code remains unchanged.
-fn inject_update_available_memory(func_body: &mut wasm_transform::Body, func_type: &FuncType) {
+fn inject_update_available_memory(
+    func_body: &mut wasm_transform::Body,
+    func_type: &FuncType,
+    is_wasm64: bool,
+) {
     // This is an overestimation of table element size computed based on the
     // existing canister limits.
     const TABLE_ELEMENT_SIZE: u32 = 1024;
@@ -1181,7 +1380,16 @@ fn inject_update_available_memory(func_body: &mut wasm_transform::Body, func_typ
         // the total number of locals.
         let n_locals: u32 = func_body.locals.iter().map(|x| x.0).sum();
         let memory_local_ix = func_type.params().len() as u32 + n_locals;
-        func_body.locals.push((1, ValType::I32));
+        // In Wasm64 modules `memory.grow` takes an i64 argument while
+        // `table.grow` still takes an i32, so each of them needs its own local.
+        let table_local_ix = if is_wasm64 {
+            func_body.locals.push((1, ValType::I64));
+            func_body.locals.push((1, ValType::I32));
+            memory_local_ix + 1
+        } else {
+            func_body.locals.push((1, ValType::I32));
+            memory_local_ix
+        };
 
         let orig_elems = &func_body.instructions;
         let mut elems: Vec<Operator> = Vec::new();
@@ -1189,24 +1397,65 @@ fn inject_update_available_memory(func_body: &mut wasm_transform::Body, func_typ
         for (point, element_size) in injection_points {
             let update_available_memory_instr = orig_elems[point].clone();
             elems.extend_from_slice(&orig_elems[last_injection_position..point]);
+            let is_table_grow = matches!(update_available_memory_instr, TableGrow { .. });
             // At this point we have a memory.grow so the argument to it will be on top of
             // the stack, which we just assign to `memory_local_ix` with a local.tee
             // instruction.
-            elems.extend_from_slice(&[
-                LocalTee {
-                    local_index: memory_local_ix,
-                },
-                update_available_memory_instr,
-                LocalGet {
-                    local_index: memory_local_ix,
-                },
-                I32Const {
-                    value: element_size as i32,
-                },
-                Call {
-                    function_index: InjectedImports::UpdateAvailableMemory as u32,
-                },
-            ]);
+            if !is_wasm64 {
+                elems.extend_from_slice(&[
+                    LocalTee {
+                        local_index: memory_local_ix,
+                    },
+                    update_available_memory_instr,
+                    LocalGet {
+                        local_index: memory_local_ix,
+                    },
+                    I32Const {
+                        value: element_size as i32,
+                    },
+                    Call {
+                        function_index: InjectedImports::UpdateAvailableMemory as u32,
+                    },
+                ]);
+            } else if is_table_grow {
+                // The arguments of `update_available_memory` are i64 values in
+                // Wasm64 modules, so the i32 operands of `table.grow` are
+                // extended and the result is wrapped back.
+                elems.extend_from_slice(&[
+                    LocalTee {
+                        local_index: table_local_ix,
+                    },
+                    update_available_memory_instr,
+                    I64ExtendI32S,
+                    LocalGet {
+                        local_index: table_local_ix,
+                    },
+                    I64ExtendI32U,
+                    I64Const {
+                        value: element_size as i64,
+                    },
+                    Call {
+                        function_index: InjectedImports::UpdateAvailableMemory as u32,
+                    },
+                    I32WrapI64,
+                ]);
+            } else {
+                elems.extend_from_slice(&[
+                    LocalTee {
+                        local_index: memory_local_ix,
+                    },
+                    update_available_memory_instr,
+                    LocalGet {
+                        local_index: memory_local_ix,
+                    },
+                    I64Const {
+                        value: element_size as i64,
+                    },
+                    Call {
+                        function_index: InjectedImports::UpdateAvailableMemory as u32,
+                    },
+                ]);
+            }
             last_injection_position = point + 1;
         }
         elems.extend_from_slice(&orig_elems[last_injection_position..]);
@@ -1285,6 +1534,7 @@ fn get_data(
                     offset_expr,
                 } => match offset_expr {
                     Operator::I32Const { value } => *value as usize,
+                    Operator::I64Const { value } => *value as u64 as usize,
                     _ => return Err(WasmInstrumentationError::WasmDeserializeError(WasmError::new(
                         "complex initialization expressions for data segments are not supported!".into()
                     ))),
@@ -1331,9 +1581,19 @@ fn update_memories(
     mut module: Module,
     write_barrier: FlagStatus,
     wasm_native_stable_memory: FlagStatus,
+    is_wasm64: bool,
 ) -> (Module, u32) {
     let mut stable_index = 0;
 
+    // A 64-bit heap has no implicit limit, so it is capped at the maximum
+    // supported Wasm64 memory size.
+    if is_wasm64 {
+        let heap = &mut module.memories[0];
+        heap.maximum = Some(heap.maximum.map_or(MAX_WASM64_MEMORY_IN_WASM_PAGES, |max| {
+            max.min(MAX_WASM64_MEMORY_IN_WASM_PAGES)
+        }));
+    }
+
     let mut memory_already_exported = false;
     for export in &mut module.exports {
         if let ExternalKind::Memory = export.kind {
@@ -1352,11 +1612,16 @@ fn update_memories(
     }
 
     if write_barrier == FlagStatus::Enabled && !module.memories.is_empty() {
+        let bytemap_size = if is_wasm64 {
+            WASM64_BYTEMAP_SIZE_IN_WASM_PAGES
+        } else {
+            BYTEMAP_SIZE_IN_WASM_PAGES
+        };
         module.memories.push(MemoryType {
             memory64: false,
             shared: false,
-            initial: BYTEMAP_SIZE_IN_WASM_PAGES,
-            maximum: Some(BYTEMAP_SIZE_IN_WASM_PAGES),
+            initial: bytemap_size,
+            maximum: Some(bytemap_size),
         });
 
         module.exports.push(Export {
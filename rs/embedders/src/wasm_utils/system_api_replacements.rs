@@ -30,6 +30,7 @@ pub(super) fn replacement_functions(
     special_indices: SpecialIndices,
     subnet_type: SubnetType,
     dirty_page_overhead: NumInstructions,
+    is_wasm64: bool,
 ) -> Vec<(SystemApiFunc, (Type, Body<'static>))> {
     let count_clean_pages_fn_index = special_indices.count_clean_pages_fn.unwrap();
     let dirty_pages_counter_index = special_indices.dirty_pages_counter_ix.unwrap();
@@ -40,6 +41,17 @@ pub(super) fn replacement_functions(
     use Operator::*;
     let page_size_shift = PAGE_SIZE.trailing_zeros() as i32;
     let stable_memory_bytemap_index = stable_memory_index + 1;
+    // Converts an i32 or i64 value on the stack into an address or size in the
+    // heap, which is a 64-bit memory in Wasm64 modules. The 64-bit values are
+    // checked to fit into 32 bits before they are used with a 32-bit heap.
+    let i32_to_heap_index = || {
+        if is_wasm64 {
+            vec![I64ExtendI32U]
+        } else {
+            vec![]
+        }
+    };
+    let i64_to_heap_index = || if is_wasm64 { vec![] } else { vec![I32WrapI64] };
     vec![
         (
             SystemApiFunc::StableSize,
@@ -229,41 +241,620 @@ pub(super) fn replacement_functions(
                     const SHOULD_CALL_READ_API: u32 = 7;
                     Body {
                         locals: vec![(5, ValType::I32)], // src on bytemap, src + len on bytemap, accessed page cnt, mark bytemap iterator, should call first read api
-                        instructions: vec![
+                        instructions: [
+                            vec![
+                                // Decrement instruction counter by the size of the copy
+                                // and fixed overhead.  On system subnets this charge is
+                                // skipped.
+                                match subnet_type {
+                                    SubnetType::System => I32Const { value: 0 },
+                                    SubnetType::Application | SubnetType::VerifiedApplication => {
+                                        LocalGet {
+                                            local_index: LENGTH,
+                                        }
+                                    }
+                                },
+                                I64ExtendI32U,
+                                I64Const {
+                                    value: overhead::STABLE_READ.get() as i64,
+                                },
+                                I64Add,
+                                Call {
+                                    function_index: decr_instruction_counter_fn,
+                                },
+                                Drop,
+                                // if size is 0 we return
+                                // (correctness of the code that follows depends on the size being > 0)
+                                // note that we won't return errors if addresses are out of bounds
+                                // in this case
+                                LocalGet {
+                                    local_index: LENGTH,
+                                },
+                                I32Const { value: 0 },
+                                I32Eq,
+                                If {
+                                    blockty: BlockType::Empty,
+                                },
+                                Return,
+                                End,
+                                // If memory is too big for 32bit api, we trap
+                                MemorySize {
+                                    mem: stable_memory_index,
+                                    mem_byte: 0, // This is ignored when serializing
+                                },
+                                I64Const {
+                                    value: MAX_32_BIT_STABLE_MEMORY_IN_PAGES,
+                                },
+                                I64GtU,
+                                If {
+                                    blockty: BlockType::Empty,
+                                },
+                                I32Const {
+                                    value: InternalErrorCode::StableMemoryTooBigFor32Bit as i32,
+                                },
+                                Call {
+                                    function_index: InjectedImports::InternalTrap as u32,
+                                },
+                                End,
+                                // check bounds on stable memory (fail if src + size > mem_size)
+                                LocalGet { local_index: SRC },
+                                I64ExtendI32U,
+                                LocalGet {
+                                    local_index: LENGTH,
+                                },
+                                I64ExtendI32U,
+                                I64Add,
+                                MemorySize {
+                                    mem: stable_memory_index,
+                                    mem_byte: 0, // This is ignored when serializing
+                                },
+                                I64Const {
+                                    value: WASM_PAGE_SIZE as i64,
+                                },
+                                I64Mul,
+                                I64GtU,
+                                If {
+                                    blockty: BlockType::Empty,
+                                },
+                                I32Const {
+                                    value: InternalErrorCode::StableMemoryOutOfBounds as i32,
+                                },
+                                Call {
+                                    function_index: InjectedImports::InternalTrap as u32,
+                                },
+                                End,
+                                // src
+                                LocalGet { local_index: SRC },
+                                I32Const {
+                                    value: page_size_shift,
+                                },
+                                I32ShrU,
+                                LocalTee {
+                                    local_index: BYTEMAP_START,
+                                }, // store b_start
+                                // b_end
+                                LocalGet { local_index: SRC },
+                                LocalGet {
+                                    local_index: LENGTH,
+                                },
+                                I32Add,
+                                I32Const { value: 1 },
+                                I32Sub,
+                                I32Const {
+                                    value: page_size_shift,
+                                },
+                                I32ShrU,
+                                I32Const { value: 1 },
+                                I32Add,
+                                LocalTee {
+                                    local_index: BYTEMAP_END,
+                                }, // store b_end
+                                Call {
+                                    function_index: count_clean_pages_fn_index,
+                                },
+                                // On top of the stack we have the number of pages
+                                // that haven't been accessed in the given range.
+                                // We need to call the first read API if this
+                                // matches the total range.
+                                LocalTee {
+                                    local_index: ACCESSED_PAGE_COUNT,
+                                },
+                                LocalGet {
+                                    local_index: BYTEMAP_END,
+                                },
+                                LocalGet {
+                                    local_index: BYTEMAP_START,
+                                },
+                                I32Sub,
+                                I32Eq,
+                                LocalSet {
+                                    local_index: SHOULD_CALL_READ_API,
+                                },
+                                Drop, // Drop the number of unwritten pages.
+                                LocalGet {
+                                    local_index: ACCESSED_PAGE_COUNT,
+                                },
+                                // fail if accessed pages limit exhausted
+                                I64ExtendI32U,
+                                GlobalGet {
+                                    global_index: accessed_pages_counter_index,
+                                },
+                                I64GtU,
+                                If {
+                                    blockty: BlockType::Empty,
+                                },
+                                I32Const {
+                                    value: InternalErrorCode::MemoryAccessLimitExceeded as i32,
+                                },
+                                Call {
+                                    function_index: InjectedImports::InternalTrap as u32,
+                                },
+                                End,
+                                // mark accessed pages if there are any to be marked
+                                LocalGet {
+                                    local_index: ACCESSED_PAGE_COUNT,
+                                },
+                                I32Const { value: 0 },
+                                I32GtU,
+                                If {
+                                    blockty: BlockType::Empty,
+                                },
+                                LocalGet {
+                                    local_index: BYTEMAP_START,
+                                }, // b_start
+                                LocalSet {
+                                    local_index: BYTEMAP_ITERATOR,
+                                }, // it
+                                Loop {
+                                    blockty: BlockType::Empty,
+                                },
+                                LocalGet {
+                                    local_index: BYTEMAP_ITERATOR,
+                                }, // it as arg for store
+                                LocalGet {
+                                    local_index: BYTEMAP_ITERATOR,
+                                }, // it as arg for load
+                                I32Load8U {
+                                    memarg: wasmparser::MemArg {
+                                        align: 0,
+                                        max_align: 0,
+                                        offset: 0,
+                                        // We assume the bytemap for stable memory is always
+                                        // inserted directly after the stable memory.
+                                        memory: special_indices.stable_memory_index + 1,
+                                    },
+                                },
+                                I32Const { value: 2 }, // READ_BIT
+                                I32Or,
+                                I32Store8 {
+                                    memarg: wasmparser::MemArg {
+                                        align: 0,
+                                        max_align: 0,
+                                        offset: 0,
+                                        // We assume the bytemap for stable memory is always
+                                        // inserted directly after the stable memory.
+                                        memory: special_indices.stable_memory_index + 1,
+                                    },
+                                },
+                                LocalGet {
+                                    local_index: BYTEMAP_ITERATOR,
+                                },
+                                I32Const { value: 1 },
+                                I32Add,
+                                LocalTee {
+                                    local_index: BYTEMAP_ITERATOR,
+                                },
+                                LocalGet {
+                                    local_index: BYTEMAP_END,
+                                }, //b_end
+                                I32LtU,
+                                BrIf { relative_depth: 0 },
+                                End, // end loop
+                                End, // end if
+                                // perform the copy, calling API if it's the first access.
+                                LocalGet {
+                                    local_index: SHOULD_CALL_READ_API,
+                                },
+                                If {
+                                    blockty: BlockType::Empty,
+                                },
+                                LocalGet { local_index: DST },
+                                I64ExtendI32U,
+                                LocalGet { local_index: SRC },
+                                I64ExtendI32U,
+                                LocalGet {
+                                    local_index: LENGTH,
+                                },
+                                I64ExtendI32U,
+                                Call {
+                                    function_index: InjectedImports::StableReadFirstAccess as u32,
+                                },
+                                Else,
+                                LocalGet { local_index: DST },
+                            ],
+                            i32_to_heap_index(),
+                            vec![
+                                LocalGet { local_index: SRC },
+                                I64ExtendI32U,
+                                LocalGet {
+                                    local_index: LENGTH,
+                                },
+                            ],
+                            i32_to_heap_index(),
+                            vec![
+                                MemoryCopy {
+                                    dst_mem: 0,
+                                    src_mem: stable_memory_index,
+                                },
+                                End, // End actual copy.
+                                GlobalGet {
+                                    global_index: accessed_pages_counter_index,
+                                },
+                                LocalGet {
+                                    local_index: ACCESSED_PAGE_COUNT,
+                                },
+                                I64ExtendI32U,
+                                I64Sub,
+                                GlobalSet {
+                                    global_index: accessed_pages_counter_index,
+                                },
+                                End,
+                            ],
+                        ]
+                        .concat(),
+                    }
+                },
+            ),
+        ),
+        (
+            SystemApiFunc::Stable64Read,
+            (
+                Type::Func(FuncType::new(
+                    [ValType::I64, ValType::I64, ValType::I64],
+                    [],
+                )),
+                {
+                    const DST: u32 = 0;
+                    const SRC: u32 = 1;
+                    const LENGTH: u32 = 2;
+                    const BYTEMAP_START: u32 = 3;
+                    const BYTEMAP_END: u32 = 4;
+                    const ACCESSED_PAGE_COUNT: u32 = 5;
+                    const BYTEMAP_ITERATOR: u32 = 6;
+                    const SHOULD_CALL_READ_API: u32 = 7;
+                    Body {
+                        locals: vec![(5, ValType::I32)], // src on bytemap, src + len on bytemap, accessed page cnt, mark bytemap iterator, should call first read api
+                        instructions: [
+                            vec![
+                                // Decrement instruction counter by the size of the copy
+                                // and fixed overhead.  On system subnets this charge is
+                                // skipped.
+                                match subnet_type {
+                                    SubnetType::System => I64Const { value: 0 },
+                                    SubnetType::Application | SubnetType::VerifiedApplication => {
+                                        LocalGet {
+                                            local_index: LENGTH,
+                                        }
+                                    }
+                                },
+                                I64Const {
+                                    value: overhead::STABLE64_READ.get() as i64,
+                                },
+                                I64Add,
+                                Call {
+                                    function_index: decr_instruction_counter_fn,
+                                },
+                                Drop,
+                                // if size is 0 we return
+                                // (correctness of the code that follows depends on the size being > 0)
+                                // note that we won't return errors if addresses are out of bounds
+                                // in this case
+                                LocalGet {
+                                    local_index: LENGTH,
+                                },
+                                I64Const { value: 0 },
+                                I64Eq,
+                                If {
+                                    blockty: BlockType::Empty,
+                                },
+                                Return,
+                                End,
+                                // check bounds on stable memory (fail if dst + size > mem_size)
+                                LocalGet { local_index: SRC },
+                                LocalGet {
+                                    local_index: LENGTH,
+                                },
+                                I64Add,
+                                LocalGet { local_index: SRC },
+                                // overflow (size != 0 because we checked earlier)
+                                I64LeU,
+                                If {
+                                    blockty: BlockType::Empty,
+                                },
+                                I32Const {
+                                    value: InternalErrorCode::StableMemoryOutOfBounds as i32,
+                                },
+                                Call {
+                                    function_index: InjectedImports::InternalTrap as u32,
+                                },
+                                End,
+                                LocalGet { local_index: SRC },
+                                LocalGet {
+                                    local_index: LENGTH,
+                                },
+                                I64Add,
+                                MemorySize {
+                                    mem: stable_memory_index,
+                                    mem_byte: 0, // This is ignored when serializing
+                                },
+                                I64Const {
+                                    value: WASM_PAGE_SIZE as i64,
+                                },
+                                I64Mul,
+                                I64GtU,
+                                If {
+                                    blockty: BlockType::Empty,
+                                },
+                                I32Const {
+                                    value: InternalErrorCode::StableMemoryOutOfBounds as i32,
+                                },
+                                Call {
+                                    function_index: InjectedImports::InternalTrap as u32,
+                                },
+                                End,
+                            ],
+                            if is_wasm64 {
+                                vec![]
+                            } else {
+                                vec![
+                                    // check if these i64 hold valid i32 heap addresses
+                                    // check dst
+                                    LocalGet { local_index: DST },
+                                    I64Const {
+                                        value: u32::MAX as i64,
+                                    },
+                                    I64GtU,
+                                    If {
+                                        blockty: BlockType::Empty,
+                                    },
+                                    I32Const {
+                                        value: InternalErrorCode::HeapOutOfBounds as i32,
+                                    },
+                                    Call {
+                                        function_index: InjectedImports::InternalTrap as u32,
+                                    },
+                                    End,
+                                    // check len
+                                    LocalGet {
+                                        local_index: LENGTH,
+                                    },
+                                    I64Const {
+                                        value: u32::MAX as i64,
+                                    },
+                                    I64GtU,
+                                    If {
+                                        blockty: BlockType::Empty,
+                                    },
+                                    I32Const {
+                                        value: InternalErrorCode::HeapOutOfBounds as i32,
+                                    },
+                                    Call {
+                                        function_index: InjectedImports::InternalTrap as u32,
+                                    },
+                                    End,
+                                ]
+                            },
+                            vec![
+                                // src
+                                LocalGet { local_index: SRC },
+                                I64Const {
+                                    value: page_size_shift as i64,
+                                },
+                                I64ShrU,
+                                I32WrapI64,
+                                LocalTee {
+                                    local_index: BYTEMAP_START,
+                                }, // store b_start
+                                // b_end
+                                LocalGet { local_index: SRC },
+                                LocalGet {
+                                    local_index: LENGTH,
+                                },
+                                I64Add,
+                                I64Const { value: 1 },
+                                I64Sub,
+                                I64Const {
+                                    value: page_size_shift as i64,
+                                },
+                                I64ShrU,
+                                I64Const { value: 1 },
+                                I64Add,
+                                I32WrapI64,
+                                LocalTee {
+                                    local_index: BYTEMAP_END,
+                                }, // store b_end
+                                Call {
+                                    function_index: count_clean_pages_fn_index,
+                                },
+                                // On top of the stack we have the number of pages
+                                // that haven't been accessed in the given range.
+                                // We need to call the first read API if this
+                                // matches the total range.
+                                LocalTee {
+                                    local_index: ACCESSED_PAGE_COUNT,
+                                },
+                                LocalGet {
+                                    local_index: BYTEMAP_END,
+                                },
+                                LocalGet {
+                                    local_index: BYTEMAP_START,
+                                },
+                                I32Sub,
+                                I32Eq,
+                                LocalSet {
+                                    local_index: SHOULD_CALL_READ_API,
+                                }, // Should use first read API
+                                Drop, // Drop the number of unwritten pages.
+                                LocalGet {
+                                    local_index: ACCESSED_PAGE_COUNT,
+                                }, // unaccessed pages
+                                // fail if accessed pages limit exhausted
+                                I64ExtendI32U,
+                                GlobalGet {
+                                    global_index: accessed_pages_counter_index,
+                                },
+                                I64GtU,
+                                If {
+                                    blockty: BlockType::Empty,
+                                },
+                                I32Const {
+                                    value: InternalErrorCode::MemoryAccessLimitExceeded as i32,
+                                },
+                                Call {
+                                    function_index: InjectedImports::InternalTrap as u32,
+                                },
+                                End,
+                                // mark accessed pages if there are any to be marked
+                                LocalGet {
+                                    local_index: ACCESSED_PAGE_COUNT,
+                                },
+                                I32Const { value: 0 },
+                                I32GtU,
+                                If {
+                                    blockty: BlockType::Empty,
+                                },
+                                LocalGet {
+                                    local_index: BYTEMAP_START,
+                                }, // b_start
+                                LocalSet {
+                                    local_index: BYTEMAP_ITERATOR,
+                                }, // it
+                                Loop {
+                                    blockty: BlockType::Empty,
+                                },
+                                LocalGet {
+                                    local_index: BYTEMAP_ITERATOR,
+                                }, // it as arg for store
+                                LocalGet {
+                                    local_index: BYTEMAP_ITERATOR,
+                                }, // it as arg for load
+                                I32Load8U {
+                                    memarg: wasmparser::MemArg {
+                                        align: 0,
+                                        max_align: 0,
+                                        offset: 0,
+                                        // We assume the bytemap for stable memory is always
+                                        // inserted directly after the stable memory.
+                                        memory: special_indices.stable_memory_index + 1,
+                                    },
+                                },
+                                I32Const { value: 2 }, // READ_BIT
+                                I32Or,
+                                I32Store8 {
+                                    memarg: wasmparser::MemArg {
+                                        align: 0,
+                                        max_align: 0,
+                                        offset: 0,
+                                        // We assume the bytemap for stable memory is always
+                                        // inserted directly after the stable memory.
+                                        memory: special_indices.stable_memory_index + 1,
+                                    },
+                                },
+                                LocalGet {
+                                    local_index: BYTEMAP_ITERATOR,
+                                },
+                                I32Const { value: 1 },
+                                I32Add,
+                                LocalTee {
+                                    local_index: BYTEMAP_ITERATOR,
+                                },
+                                LocalGet {
+                                    local_index: BYTEMAP_END,
+                                }, //b_end
+                                I32LtU,
+                                BrIf { relative_depth: 0 },
+                                End, // end loop
+                                End, // end if
+                                // perform the copy, calling API if it's the first access.
+                                LocalGet {
+                                    local_index: SHOULD_CALL_READ_API,
+                                },
+                                If {
+                                    blockty: BlockType::Empty,
+                                },
+                                LocalGet { local_index: DST },
+                                LocalGet { local_index: SRC },
+                                LocalGet {
+                                    local_index: LENGTH,
+                                },
+                                Call {
+                                    function_index: InjectedImports::StableReadFirstAccess as u32,
+                                },
+                                Else,
+                                LocalGet { local_index: DST },
+                            ],
+                            i64_to_heap_index(),
+                            vec![
+                                LocalGet { local_index: SRC },
+                                LocalGet {
+                                    local_index: LENGTH,
+                                },
+                            ],
+                            i64_to_heap_index(),
+                            vec![
+                                MemoryCopy {
+                                    dst_mem: 0,
+                                    src_mem: stable_memory_index,
+                                },
+                                End, // End actual copy.
+                                GlobalGet {
+                                    global_index: accessed_pages_counter_index,
+                                },
+                                LocalGet {
+                                    local_index: ACCESSED_PAGE_COUNT,
+                                },
+                                I64ExtendI32U,
+                                I64Sub,
+                                GlobalSet {
+                                    global_index: accessed_pages_counter_index,
+                                },
+                                End,
+                            ],
+                        ]
+                        .concat(),
+                    }
+                },
+            ),
+        ),
+        (
+            SystemApiFunc::StableWrite,
+            (
+                Type::Func(FuncType::new(
+                    [ValType::I32, ValType::I32, ValType::I32],
+                    [],
+                )),
+                Body {
+                    locals: vec![(4, ValType::I32)], // dst on bytemap, dst + len on bytemap, dirty page cnt, accessed page cnt
+                    instructions: [
+                        vec![
                             // Decrement instruction counter by the size of the copy
                             // and fixed overhead.  On system subnets this charge is
                             // skipped.
                             match subnet_type {
                                 SubnetType::System => I32Const { value: 0 },
                                 SubnetType::Application | SubnetType::VerifiedApplication => {
-                                    LocalGet {
-                                        local_index: LENGTH,
-                                    }
+                                    LocalGet { local_index: 2 }
                                 }
                             },
                             I64ExtendI32U,
                             I64Const {
-                                value: overhead::STABLE_READ.get() as i64,
+                                value: overhead::STABLE_WRITE.get() as i64,
                             },
                             I64Add,
                             Call {
                                 function_index: decr_instruction_counter_fn,
                             },
                             Drop,
-                            // if size is 0 we return
-                            // (correctness of the code that follows depends on the size being > 0)
-                            // note that we won't return errors if addresses are out of bounds
-                            // in this case
-                            LocalGet {
-                                local_index: LENGTH,
-                            },
-                            I32Const { value: 0 },
-                            I32Eq,
-                            If {
-                                blockty: BlockType::Empty,
-                            },
-                            Return,
-                            End,
                             // If memory is too big for 32bit api, we trap
                             MemorySize {
                                 mem: stable_memory_index,
@@ -283,12 +874,10 @@ pub(super) fn replacement_functions(
                                 function_index: InjectedImports::InternalTrap as u32,
                             },
                             End,
-                            // check bounds on stable memory (fail if src + size > mem_size)
-                            LocalGet { local_index: SRC },
+                            // check bounds on stable memory (fail if dst + size > mem_size)
+                            LocalGet { local_index: 0 },
                             I64ExtendI32U,
-                            LocalGet {
-                                local_index: LENGTH,
-                            },
+                            LocalGet { local_index: 2 },
                             I64ExtendI32U,
                             I64Add,
                             MemorySize {
@@ -310,20 +899,30 @@ pub(super) fn replacement_functions(
                                 function_index: InjectedImports::InternalTrap as u32,
                             },
                             End,
-                            // src
-                            LocalGet { local_index: SRC },
+                            // mark writes in the bytemap
+
+                            // if size is 0 we return
+                            // (correctness of the code that follows depends on the size being > 0)
+                            // note that we won't return error if src address is out of bounds
+                            // in this case
+                            LocalGet { local_index: 2 },
+                            I32Const { value: 0 },
+                            I32Eq,
+                            If {
+                                blockty: BlockType::Empty,
+                            },
+                            Return,
+                            End,
+                            // dst
+                            LocalGet { local_index: 0 },
                             I32Const {
                                 value: page_size_shift,
                             },
                             I32ShrU,
-                            LocalTee {
-                                local_index: BYTEMAP_START,
-                            }, // store b_start
+                            LocalTee { local_index: 3 }, // store b_start
                             // b_end
-                            LocalGet { local_index: SRC },
-                            LocalGet {
-                                local_index: LENGTH,
-                            },
+                            LocalGet { local_index: 0 },
+                            LocalGet { local_index: 2 },
                             I32Add,
                             I32Const { value: 1 },
                             I32Sub,
@@ -333,34 +932,12 @@ pub(super) fn replacement_functions(
                             I32ShrU,
                             I32Const { value: 1 },
                             I32Add,
-                            LocalTee {
-                                local_index: BYTEMAP_END,
-                            }, // store b_end
+                            LocalTee { local_index: 4 }, // store b_end
+                            // count pages already dirty
                             Call {
                                 function_index: count_clean_pages_fn_index,
                             },
-                            // On top of the stack we have the number of pages
-                            // that haven't been accessed in the given range.
-                            // We need to call the first read API if this
-                            // matches the total range.
-                            LocalTee {
-                                local_index: ACCESSED_PAGE_COUNT,
-                            },
-                            LocalGet {
-                                local_index: BYTEMAP_END,
-                            },
-                            LocalGet {
-                                local_index: BYTEMAP_START,
-                            },
-                            I32Sub,
-                            I32Eq,
-                            LocalSet {
-                                local_index: SHOULD_CALL_READ_API,
-                            },
-                            Drop, // Drop the number of unwritten pages.
-                            LocalGet {
-                                local_index: ACCESSED_PAGE_COUNT,
-                            },
+                            LocalTee { local_index: 6 },
                             // fail if accessed pages limit exhausted
                             I64ExtendI32U,
                             GlobalGet {
@@ -377,103 +954,76 @@ pub(super) fn replacement_functions(
                                 function_index: InjectedImports::InternalTrap as u32,
                             },
                             End,
-                            // mark accessed pages if there are any to be marked
-                            LocalGet {
-                                local_index: ACCESSED_PAGE_COUNT,
+                            LocalTee { local_index: 5 },
+                            // fail if dirty pages limit exhausted
+                            I64ExtendI32U,
+                            GlobalGet {
+                                global_index: dirty_pages_counter_index,
                             },
-                            I32Const { value: 0 },
-                            I32GtU,
+                            I64GtU,
                             If {
                                 blockty: BlockType::Empty,
                             },
-                            LocalGet {
-                                local_index: BYTEMAP_START,
-                            }, // b_start
-                            LocalSet {
-                                local_index: BYTEMAP_ITERATOR,
-                            }, // it
-                            Loop {
-                                blockty: BlockType::Empty,
-                            },
-                            LocalGet {
-                                local_index: BYTEMAP_ITERATOR,
-                            }, // it as arg for store
-                            LocalGet {
-                                local_index: BYTEMAP_ITERATOR,
-                            }, // it as arg for load
-                            I32Load8U {
-                                memarg: wasmparser::MemArg {
-                                    align: 0,
-                                    max_align: 0,
-                                    offset: 0,
-                                    // We assume the bytemap for stable memory is always
-                                    // inserted directly after the stable memory.
-                                    memory: special_indices.stable_memory_index + 1,
-                                },
-                            },
-                            I32Const { value: 2 }, // READ_BIT
-                            I32Or,
-                            I32Store8 {
-                                memarg: wasmparser::MemArg {
-                                    align: 0,
-                                    max_align: 0,
-                                    offset: 0,
-                                    // We assume the bytemap for stable memory is always
-                                    // inserted directly after the stable memory.
-                                    memory: special_indices.stable_memory_index + 1,
-                                },
-                            },
-                            LocalGet {
-                                local_index: BYTEMAP_ITERATOR,
-                            },
-                            I32Const { value: 1 },
-                            I32Add,
-                            LocalTee {
-                                local_index: BYTEMAP_ITERATOR,
-                            },
-                            LocalGet {
-                                local_index: BYTEMAP_END,
-                            }, //b_end
-                            I32LtU,
-                            BrIf { relative_depth: 0 },
-                            End, // end loop
-                            End, // end if
-                            // perform the copy, calling API if it's the first access.
-                            LocalGet {
-                                local_index: SHOULD_CALL_READ_API,
+                            I32Const {
+                                value: InternalErrorCode::MemoryWriteLimitExceeded as i32,
                             },
-                            If {
-                                blockty: BlockType::Empty,
+                            Call {
+                                function_index: InjectedImports::InternalTrap as u32,
                             },
-                            LocalGet { local_index: DST },
-                            I64ExtendI32U,
-                            LocalGet { local_index: SRC },
+                            End,
+                            // Decrement instruction counter to charge for dirty pages
+                            LocalGet { local_index: 5 },
                             I64ExtendI32U,
-                            LocalGet {
-                                local_index: LENGTH,
+                            I64Const {
+                                value: dirty_page_overhead.get().try_into().unwrap(),
                             },
-                            I64ExtendI32U,
+                            I64Mul,
+                            // Bounds check above should guarantee that we don't
+                            // overflow as the over head is a small constant.
                             Call {
-                                function_index: InjectedImports::StableReadFirstAccess as u32,
+                                function_index: decr_instruction_counter_fn,
                             },
-                            Else,
-                            LocalGet { local_index: DST },
-                            LocalGet { local_index: SRC },
-                            I64ExtendI32U,
-                            LocalGet {
-                                local_index: LENGTH,
+                            Drop,
+                            // perform memory fill
+                            LocalGet { local_index: 3 }, //b_start
+                            // value to fill with
+                            I32Const { value: 3 },
+                            // calculate b_size
+                            // b_end = (dst + size - 1) / PAGE_SIZE + 1
+                            // b_len = b_end - b_start
+                            LocalGet { local_index: 4 }, //b_end
+                            LocalGet { local_index: 3 }, //b_start
+                            // b_end - b_start
+                            I32Sub,
+                            MemoryFill {
+                                mem: stable_memory_bytemap_index,
                             },
+                            // copy memory contents
+                            LocalGet { local_index: 0 },
+                            I64ExtendI32U,
+                            LocalGet { local_index: 1 },
+                        ],
+                        i32_to_heap_index(),
+                        vec![LocalGet { local_index: 2 }],
+                        i32_to_heap_index(),
+                        vec![
                             MemoryCopy {
-                                dst_mem: 0,
-                                src_mem: stable_memory_index,
+                                dst_mem: stable_memory_index,
+                                src_mem: 0,
                             },
-                            End, // End actual copy.
                             GlobalGet {
-                                global_index: accessed_pages_counter_index,
+                                global_index: dirty_pages_counter_index,
+                            },
+                            LocalGet { local_index: 5 },
+                            I64ExtendI32U,
+                            I64Sub,
+                            GlobalSet {
+                                global_index: dirty_pages_counter_index,
                             },
-                            LocalGet {
-                                local_index: ACCESSED_PAGE_COUNT,
+                            GlobalGet {
+                                global_index: accessed_pages_counter_index,
                             },
+                            LocalGet { local_index: 6 },
                             I64ExtendI32U,
                             I64Sub,
                             GlobalSet {
@@ -481,42 +1031,33 @@ pub(super) fn replacement_functions(
                             },
                             End,
                         ],
-                    }
+                    ]
+                    .concat(),
                 },
             ),
         ),
         (
-            SystemApiFunc::Stable64Read,
+            SystemApiFunc::Stable64Write,
             (
                 Type::Func(FuncType::new(
                     [ValType::I64, ValType::I64, ValType::I64],
                     [],
                 )),
-                {
-                    const DST: u32 = 0;
-                    const SRC: u32 = 1;
-                    const LENGTH: u32 = 2;
-                    const BYTEMAP_START: u32 = 3;
-                    const BYTEMAP_END: u32 = 4;
-                    const ACCESSED_PAGE_COUNT: u32 = 5;
-                    const BYTEMAP_ITERATOR: u32 = 6;
-                    const SHOULD_CALL_READ_API: u32 = 7;
-                    Body {
-                        locals: vec![(5, ValType::I32)], // src on bytemap, src + len on bytemap, accessed page cnt, mark bytemap iterator, should call first read api
-                        instructions: vec![
+                Body {
+                    locals: vec![(4, ValType::I32)], // dst on bytemap, dst + len on bytemap, dirty page cnt, accessed page cnt
+                    instructions: [
+                        vec![
                             // Decrement instruction counter by the size of the copy
                             // and fixed overhead.  On system subnets this charge is
                             // skipped.
                             match subnet_type {
                                 SubnetType::System => I64Const { value: 0 },
                                 SubnetType::Application | SubnetType::VerifiedApplication => {
-                                    LocalGet {
-                                        local_index: LENGTH,
-                                    }
+                                    LocalGet { local_index: 2 }
                                 }
                             },
                             I64Const {
-                                value: overhead::STABLE64_READ.get() as i64,
+                                value: overhead::STABLE64_WRITE.get() as i64,
                             },
                             I64Add,
                             Call {
@@ -527,9 +1068,7 @@ pub(super) fn replacement_functions(
                             // (correctness of the code that follows depends on the size being > 0)
                             // note that we won't return errors if addresses are out of bounds
                             // in this case
-                            LocalGet {
-                                local_index: LENGTH,
-                            },
+                            LocalGet { local_index: 2 },
                             I64Const { value: 0 },
                             I64Eq,
                             If {
@@ -538,12 +1077,10 @@ pub(super) fn replacement_functions(
                             Return,
                             End,
                             // check bounds on stable memory (fail if dst + size > mem_size)
-                            LocalGet { local_index: SRC },
-                            LocalGet {
-                                local_index: LENGTH,
-                            },
+                            LocalGet { local_index: 0 },
+                            LocalGet { local_index: 2 },
                             I64Add,
-                            LocalGet { local_index: SRC },
+                            LocalGet { local_index: 0 },
                             // overflow (size != 0 because we checked earlier)
                             I64LeU,
                             If {
@@ -556,10 +1093,8 @@ pub(super) fn replacement_functions(
                                 function_index: InjectedImports::InternalTrap as u32,
                             },
                             End,
-                            LocalGet { local_index: SRC },
-                            LocalGet {
-                                local_index: LENGTH,
-                            },
+                            LocalGet { local_index: 0 },
+                            LocalGet { local_index: 2 },
                             I64Add,
                             MemorySize {
                                 mem: stable_memory_index,
@@ -580,205 +1115,158 @@ pub(super) fn replacement_functions(
                                 function_index: InjectedImports::InternalTrap as u32,
                             },
                             End,
-                            // check if these i64 hold valid i32 heap addresses
-                            // check dst
-                            LocalGet { local_index: DST },
+                        ],
+                        if is_wasm64 {
+                            vec![]
+                        } else {
+                            vec![
+                                // check if these i64 hold valid i32 heap addresses
+                                // check src
+                                LocalGet { local_index: 1 },
+                                I64Const {
+                                    value: u32::MAX as i64,
+                                },
+                                I64GtU,
+                                If {
+                                    blockty: BlockType::Empty,
+                                },
+                                I32Const {
+                                    value: InternalErrorCode::HeapOutOfBounds as i32,
+                                },
+                                Call {
+                                    function_index: InjectedImports::InternalTrap as u32,
+                                },
+                                End,
+                                // check len
+                                LocalGet { local_index: 2 },
+                                I64Const {
+                                    value: u32::MAX as i64,
+                                },
+                                I64GtU,
+                                If {
+                                    blockty: BlockType::Empty,
+                                },
+                                I32Const {
+                                    value: InternalErrorCode::HeapOutOfBounds as i32,
+                                },
+                                Call {
+                                    function_index: InjectedImports::InternalTrap as u32,
+                                },
+                                End,
+                            ]
+                        },
+                        vec![
+                            // dst
+                            LocalGet { local_index: 0 },
+                            I64Const {
+                                value: page_size_shift as i64,
+                            },
+                            I64ShrU,
+                            I32WrapI64,
+                            LocalTee { local_index: 3 }, // store b_start
+                            // b_end
+                            LocalGet { local_index: 0 },
+                            LocalGet { local_index: 2 },
+                            I64Add,
+                            I64Const { value: 1 },
+                            I64Sub,
                             I64Const {
-                                value: u32::MAX as i64,
+                                value: page_size_shift as i64,
+                            },
+                            I64ShrU,
+                            I64Const { value: 1 },
+                            I64Add,
+                            I32WrapI64,
+                            LocalTee { local_index: 4 }, // store b_end
+                            Call {
+                                function_index: count_clean_pages_fn_index,
+                            },
+                            LocalTee { local_index: 6 },
+                            // fail if accessed pages limit exhausted
+                            I64ExtendI32U,
+                            GlobalGet {
+                                global_index: accessed_pages_counter_index,
                             },
                             I64GtU,
                             If {
                                 blockty: BlockType::Empty,
                             },
                             I32Const {
-                                value: InternalErrorCode::HeapOutOfBounds as i32,
+                                value: InternalErrorCode::MemoryAccessLimitExceeded as i32,
                             },
                             Call {
                                 function_index: InjectedImports::InternalTrap as u32,
                             },
                             End,
-                            // check len
-                            LocalGet {
-                                local_index: LENGTH,
-                            },
-                            I64Const {
-                                value: u32::MAX as i64,
+                            LocalTee { local_index: 5 },
+                            // fail if dirty pages limit exhausted
+                            I64ExtendI32U,
+                            GlobalGet {
+                                global_index: dirty_pages_counter_index,
                             },
                             I64GtU,
                             If {
                                 blockty: BlockType::Empty,
                             },
                             I32Const {
-                                value: InternalErrorCode::HeapOutOfBounds as i32,
+                                value: InternalErrorCode::MemoryWriteLimitExceeded as i32,
                             },
                             Call {
                                 function_index: InjectedImports::InternalTrap as u32,
                             },
                             End,
-                            // src
-                            LocalGet { local_index: SRC },
+                            // Decrement instruction counter to charge for dirty pages
+                            LocalGet { local_index: 5 },
+                            I64ExtendI32U,
                             I64Const {
-                                value: page_size_shift as i64,
+                                value: dirty_page_overhead.get().try_into().unwrap(),
                             },
-                            I64ShrU,
-                            I32WrapI64,
-                            LocalTee {
-                                local_index: BYTEMAP_START,
-                            }, // store b_start
-                            // b_end
-                            LocalGet { local_index: SRC },
-                            LocalGet {
-                                local_index: LENGTH,
-                            },
-                            I64Add,
-                            I64Const { value: 1 },
-                            I64Sub,
-                            I64Const {
-                                value: page_size_shift as i64,
-                            },
-                            I64ShrU,
-                            I64Const { value: 1 },
-                            I64Add,
-                            I32WrapI64,
-                            LocalTee {
-                                local_index: BYTEMAP_END,
-                            }, // store b_end
+                            I64Mul,
+                            // Bounds check above should guarantee that we don't
+                            // overflow as the over head is a small constant.
                             Call {
-                                function_index: count_clean_pages_fn_index,
-                            },
-                            // On top of the stack we have the number of pages
-                            // that haven't been accessed in the given range.
-                            // We need to call the first read API if this
-                            // matches the total range.
-                            LocalTee {
-                                local_index: ACCESSED_PAGE_COUNT,
-                            },
-                            LocalGet {
-                                local_index: BYTEMAP_END,
-                            },
-                            LocalGet {
-                                local_index: BYTEMAP_START,
+                                function_index: decr_instruction_counter_fn,
                             },
+                            Drop,
+                            // perform memory fill
+                            LocalGet { local_index: 3 }, //b_start
+                            // value to fill with
+                            I32Const { value: 3 },
+                            // calculate b_size
+                            // b_end = (dst + size - 1) / PAGE_SIZE + 1
+                            // b_len = b_end - b_start
+                            LocalGet { local_index: 4 }, //b_end
+                            LocalGet { local_index: 3 }, //b_start
+                            // b_end - b_start
                             I32Sub,
-                            I32Eq,
-                            LocalSet {
-                                local_index: SHOULD_CALL_READ_API,
-                            }, // Should use first read API
-                            Drop, // Drop the number of unwritten pages.
-                            LocalGet {
-                                local_index: ACCESSED_PAGE_COUNT,
-                            }, // unaccessed pages
-                            // fail if accessed pages limit exhausted
-                            I64ExtendI32U,
-                            GlobalGet {
-                                global_index: accessed_pages_counter_index,
+                            MemoryFill {
+                                mem: stable_memory_bytemap_index,
                             },
-                            I64GtU,
-                            If {
-                                blockty: BlockType::Empty,
-                            },
-                            I32Const {
-                                value: InternalErrorCode::MemoryAccessLimitExceeded as i32,
-                            },
-                            Call {
-                                function_index: InjectedImports::InternalTrap as u32,
-                            },
-                            End,
-                            // mark accessed pages if there are any to be marked
-                            LocalGet {
-                                local_index: ACCESSED_PAGE_COUNT,
-                            },
-                            I32Const { value: 0 },
-                            I32GtU,
-                            If {
-                                blockty: BlockType::Empty,
-                            },
-                            LocalGet {
-                                local_index: BYTEMAP_START,
-                            }, // b_start
-                            LocalSet {
-                                local_index: BYTEMAP_ITERATOR,
-                            }, // it
-                            Loop {
-                                blockty: BlockType::Empty,
-                            },
-                            LocalGet {
-                                local_index: BYTEMAP_ITERATOR,
-                            }, // it as arg for store
-                            LocalGet {
-                                local_index: BYTEMAP_ITERATOR,
-                            }, // it as arg for load
-                            I32Load8U {
-                                memarg: wasmparser::MemArg {
-                                    align: 0,
-                                    max_align: 0,
-                                    offset: 0,
-                                    // We assume the bytemap for stable memory is always
-                                    // inserted directly after the stable memory.
-                                    memory: special_indices.stable_memory_index + 1,
-                                },
-                            },
-                            I32Const { value: 2 }, // READ_BIT
-                            I32Or,
-                            I32Store8 {
-                                memarg: wasmparser::MemArg {
-                                    align: 0,
-                                    max_align: 0,
-                                    offset: 0,
-                                    // We assume the bytemap for stable memory is always
-                                    // inserted directly after the stable memory.
-                                    memory: special_indices.stable_memory_index + 1,
-                                },
-                            },
-                            LocalGet {
-                                local_index: BYTEMAP_ITERATOR,
-                            },
-                            I32Const { value: 1 },
-                            I32Add,
-                            LocalTee {
-                                local_index: BYTEMAP_ITERATOR,
-                            },
-                            LocalGet {
-                                local_index: BYTEMAP_END,
-                            }, //b_end
-                            I32LtU,
-                            BrIf { relative_depth: 0 },
-                            End, // end loop
-                            End, // end if
-                            // perform the copy, calling API if it's the first access.
-                            LocalGet {
-                                local_index: SHOULD_CALL_READ_API,
-                            },
-                            If {
-                                blockty: BlockType::Empty,
-                            },
-                            LocalGet { local_index: DST },
-                            LocalGet { local_index: SRC },
-                            LocalGet {
-                                local_index: LENGTH,
-                            },
-                            Call {
-                                function_index: InjectedImports::StableReadFirstAccess as u32,
+                            // copy memory contents
+                            LocalGet { local_index: 0 },
+                            LocalGet { local_index: 1 },
+                        ],
+                        i64_to_heap_index(),
+                        vec![LocalGet { local_index: 2 }],
+                        i64_to_heap_index(),
+                        vec![
+                            MemoryCopy {
+                                dst_mem: stable_memory_index,
+                                src_mem: 0,
                             },
-                            Else,
-                            LocalGet { local_index: DST },
-                            I32WrapI64,
-                            LocalGet { local_index: SRC },
-                            LocalGet {
-                                local_index: LENGTH,
+                            GlobalGet {
+                                global_index: dirty_pages_counter_index,
                             },
-                            I32WrapI64,
-                            MemoryCopy {
-                                dst_mem: 0,
-                                src_mem: stable_memory_index,
+                            LocalGet { local_index: 5 },
+                            I64ExtendI32U,
+                            I64Sub,
+                            GlobalSet {
+                                global_index: dirty_pages_counter_index,
                             },
-                            End, // End actual copy.
                             GlobalGet {
                                 global_index: accessed_pages_counter_index,
                             },
-                            LocalGet {
-                                local_index: ACCESSED_PAGE_COUNT,
-                            },
+                            LocalGet { local_index: 6 },
                             I64ExtendI32U,
                             I64Sub,
                             GlobalSet {
@@ -786,440 +1274,8 @@ pub(super) fn replacement_functions(
                             },
                             End,
                         ],
-                    }
-                },
-            ),
-        ),
-        (
-            SystemApiFunc::StableWrite,
-            (
-                Type::Func(FuncType::new(
-                    [ValType::I32, ValType::I32, ValType::I32],
-                    [],
-                )),
-                Body {
-                    locals: vec![(4, ValType::I32)], // dst on bytemap, dst + len on bytemap, dirty page cnt, accessed page cnt
-                    instructions: vec![
-                        // Decrement instruction counter by the size of the copy
-                        // and fixed overhead.  On system subnets this charge is
-                        // skipped.
-                        match subnet_type {
-                            SubnetType::System => I32Const { value: 0 },
-                            SubnetType::Application | SubnetType::VerifiedApplication => {
-                                LocalGet { local_index: 2 }
-                            }
-                        },
-                        I64ExtendI32U,
-                        I64Const {
-                            value: overhead::STABLE_WRITE.get() as i64,
-                        },
-                        I64Add,
-                        Call {
-                            function_index: decr_instruction_counter_fn,
-                        },
-                        Drop,
-                        // If memory is too big for 32bit api, we trap
-                        MemorySize {
-                            mem: stable_memory_index,
-                            mem_byte: 0, // This is ignored when serializing
-                        },
-                        I64Const {
-                            value: MAX_32_BIT_STABLE_MEMORY_IN_PAGES,
-                        },
-                        I64GtU,
-                        If {
-                            blockty: BlockType::Empty,
-                        },
-                        I32Const {
-                            value: InternalErrorCode::StableMemoryTooBigFor32Bit as i32,
-                        },
-                        Call {
-                            function_index: InjectedImports::InternalTrap as u32,
-                        },
-                        End,
-                        // check bounds on stable memory (fail if dst + size > mem_size)
-                        LocalGet { local_index: 0 },
-                        I64ExtendI32U,
-                        LocalGet { local_index: 2 },
-                        I64ExtendI32U,
-                        I64Add,
-                        MemorySize {
-                            mem: stable_memory_index,
-                            mem_byte: 0, // This is ignored when serializing
-                        },
-                        I64Const {
-                            value: WASM_PAGE_SIZE as i64,
-                        },
-                        I64Mul,
-                        I64GtU,
-                        If {
-                            blockty: BlockType::Empty,
-                        },
-                        I32Const {
-                            value: InternalErrorCode::StableMemoryOutOfBounds as i32,
-                        },
-                        Call {
-                            function_index: InjectedImports::InternalTrap as u32,
-                        },
-                        End,
-                        // mark writes in the bytemap
-
-                        // if size is 0 we return
-                        // (correctness of the code that follows depends on the size being > 0)
-                        // note that we won't return error if src address is out of bounds
-                        // in this case
-                        LocalGet { local_index: 2 },
-                        I32Const { value: 0 },
-                        I32Eq,
-                        If {
-                            blockty: BlockType::Empty,
-                        },
-                        Return,
-                        End,
-                        // dst
-                        LocalGet { local_index: 0 },
-                        I32Const {
-                            value: page_size_shift,
-                        },
-                        I32ShrU,
-                        LocalTee { local_index: 3 }, // store b_start
-                        // b_end
-                        LocalGet { local_index: 0 },
-                        LocalGet { local_index: 2 },
-                        I32Add,
-                        I32Const { value: 1 },
-                        I32Sub,
-                        I32Const {
-                            value: page_size_shift,
-                        },
-                        I32ShrU,
-                        I32Const { value: 1 },
-                        I32Add,
-                        LocalTee { local_index: 4 }, // store b_end
-                        // count pages already dirty
-                        Call {
-                            function_index: count_clean_pages_fn_index,
-                        },
-                        LocalTee { local_index: 6 },
-                        // fail if accessed pages limit exhausted
-                        I64ExtendI32U,
-                        GlobalGet {
-                            global_index: accessed_pages_counter_index,
-                        },
-                        I64GtU,
-                        If {
-                            blockty: BlockType::Empty,
-                        },
-                        I32Const {
-                            value: InternalErrorCode::MemoryAccessLimitExceeded as i32,
-                        },
-                        Call {
-                            function_index: InjectedImports::InternalTrap as u32,
-                        },
-                        End,
-                        LocalTee { local_index: 5 },
-                        // fail if dirty pages limit exhausted
-                        I64ExtendI32U,
-                        GlobalGet {
-                            global_index: dirty_pages_counter_index,
-                        },
-                        I64GtU,
-                        If {
-                            blockty: BlockType::Empty,
-                        },
-                        I32Const {
-                            value: InternalErrorCode::MemoryWriteLimitExceeded as i32,
-                        },
-                        Call {
-                            function_index: InjectedImports::InternalTrap as u32,
-                        },
-                        End,
-                        // Decrement instruction counter to charge for dirty pages
-                        LocalGet { local_index: 5 },
-                        I64ExtendI32U,
-                        I64Const {
-                            value: dirty_page_overhead.get().try_into().unwrap(),
-                        },
-                        I64Mul,
-                        // Bounds check above should guarantee that we don't
-                        // overflow as the over head is a small constant.
-                        Call {
-                            function_index: decr_instruction_counter_fn,
-                        },
-                        Drop,
-                        // perform memory fill
-                        LocalGet { local_index: 3 }, //b_start
-                        // value to fill with
-                        I32Const { value: 3 },
-                        // calculate b_size
-                        // b_end = (dst + size - 1) / PAGE_SIZE + 1
-                        // b_len = b_end - b_start
-                        LocalGet { local_index: 4 }, //b_end
-                        LocalGet { local_index: 3 }, //b_start
-                        // b_end - b_start
-                        I32Sub,
-                        MemoryFill {
-                            mem: stable_memory_bytemap_index,
-                        },
-                        // copy memory contents
-                        LocalGet { local_index: 0 },
-                        I64ExtendI32U,
-                        LocalGet { local_index: 1 },
-                        LocalGet { local_index: 2 },
-                        MemoryCopy {
-                            dst_mem: stable_memory_index,
-                            src_mem: 0,
-                        },
-                        GlobalGet {
-                            global_index: dirty_pages_counter_index,
-                        },
-                        LocalGet { local_index: 5 },
-                        I64ExtendI32U,
-                        I64Sub,
-                        GlobalSet {
-                            global_index: dirty_pages_counter_index,
-                        },
-                        GlobalGet {
-                            global_index: accessed_pages_counter_index,
-                        },
-                        LocalGet { local_index: 6 },
-                        I64ExtendI32U,
-                        I64Sub,
-                        GlobalSet {
-                            global_index: accessed_pages_counter_index,
-                        },
-                        End,
-                    ],
-                },
-            ),
-        ),
-        (
-            SystemApiFunc::Stable64Write,
-            (
-                Type::Func(FuncType::new(
-                    [ValType::I64, ValType::I64, ValType::I64],
-                    [],
-                )),
-                Body {
-                    locals: vec![(4, ValType::I32)], // dst on bytemap, dst + len on bytemap, dirty page cnt, accessed page cnt
-                    instructions: vec![
-                        // Decrement instruction counter by the size of the copy
-                        // and fixed overhead.  On system subnets this charge is
-                        // skipped.
-                        match subnet_type {
-                            SubnetType::System => I64Const { value: 0 },
-                            SubnetType::Application | SubnetType::VerifiedApplication => {
-                                LocalGet { local_index: 2 }
-                            }
-                        },
-                        I64Const {
-                            value: overhead::STABLE64_WRITE.get() as i64,
-                        },
-                        I64Add,
-                        Call {
-                            function_index: decr_instruction_counter_fn,
-                        },
-                        Drop,
-                        // if size is 0 we return
-                        // (correctness of the code that follows depends on the size being > 0)
-                        // note that we won't return errors if addresses are out of bounds
-                        // in this case
-                        LocalGet { local_index: 2 },
-                        I64Const { value: 0 },
-                        I64Eq,
-                        If {
-                            blockty: BlockType::Empty,
-                        },
-                        Return,
-                        End,
-                        // check bounds on stable memory (fail if dst + size > mem_size)
-                        LocalGet { local_index: 0 },
-                        LocalGet { local_index: 2 },
-                        I64Add,
-                        LocalGet { local_index: 0 },
-                        // overflow (size != 0 because we checked earlier)
-                        I64LeU,
-                        If {
-                            blockty: BlockType::Empty,
-                        },
-                        I32Const {
-                            value: InternalErrorCode::StableMemoryOutOfBounds as i32,
-                        },
-                        Call {
-                            function_index: InjectedImports::InternalTrap as u32,
-                        },
-                        End,
-                        LocalGet { local_index: 0 },
-                        LocalGet { local_index: 2 },
-                        I64Add,
-                        MemorySize {
-                            mem: stable_memory_index,
-                            mem_byte: 0, // This is ignored when serializing
-                        },
-                        I64Const {
-                            value: WASM_PAGE_SIZE as i64,
-                        },
-                        I64Mul,
-                        I64GtU,
-                        If {
-                            blockty: BlockType::Empty,
-                        },
-                        I32Const {
-                            value: InternalErrorCode::StableMemoryOutOfBounds as i32,
-                        },
-                        Call {
-                            function_index: InjectedImports::InternalTrap as u32,
-                        },
-                        End,
-                        // check if these i64 hold valid i32 heap addresses
-                        // check src
-                        LocalGet { local_index: 1 },
-                        I64Const {
-                            value: u32::MAX as i64,
-                        },
-                        I64GtU,
-                        If {
-                            blockty: BlockType::Empty,
-                        },
-                        I32Const {
-                            value: InternalErrorCode::HeapOutOfBounds as i32,
-                        },
-                        Call {
-                            function_index: InjectedImports::InternalTrap as u32,
-                        },
-                        End,
-                        // check len
-                        LocalGet { local_index: 2 },
-                        I64Const {
-                            value: u32::MAX as i64,
-                        },
-                        I64GtU,
-                        If {
-                            blockty: BlockType::Empty,
-                        },
-                        I32Const {
-                            value: InternalErrorCode::HeapOutOfBounds as i32,
-                        },
-                        Call {
-                            function_index: InjectedImports::InternalTrap as u32,
-                        },
-                        End,
-                        // dst
-                        LocalGet { local_index: 0 },
-                        I64Const {
-                            value: page_size_shift as i64,
-                        },
-                        I64ShrU,
-                        I32WrapI64,
-                        LocalTee { local_index: 3 }, // store b_start
-                        // b_end
-                        LocalGet { local_index: 0 },
-                        LocalGet { local_index: 2 },
-                        I64Add,
-                        I64Const { value: 1 },
-                        I64Sub,
-                        I64Const {
-                            value: page_size_shift as i64,
-                        },
-                        I64ShrU,
-                        I64Const { value: 1 },
-                        I64Add,
-                        I32WrapI64,
-                        LocalTee { local_index: 4 }, // store b_end
-                        Call {
-                            function_index: count_clean_pages_fn_index,
-                        },
-                        LocalTee { local_index: 6 },
-                        // fail if accessed pages limit exhausted
-                        I64ExtendI32U,
-                        GlobalGet {
-                            global_index: accessed_pages_counter_index,
-                        },
-                        I64GtU,
-                        If {
-                            blockty: BlockType::Empty,
-                        },
-                        I32Const {
-                            value: InternalErrorCode::MemoryAccessLimitExceeded as i32,
-                        },
-                        Call {
-                            function_index: InjectedImports::InternalTrap as u32,
-                        },
-                        End,
-                        LocalTee { local_index: 5 },
-                        // fail if dirty pages limit exhausted
-                        I64ExtendI32U,
-                        GlobalGet {
-                            global_index: dirty_pages_counter_index,
-                        },
-                        I64GtU,
-                        If {
-                            blockty: BlockType::Empty,
-                        },
-                        I32Const {
-                            value: InternalErrorCode::MemoryWriteLimitExceeded as i32,
-                        },
-                        Call {
-                            function_index: InjectedImports::InternalTrap as u32,
-                        },
-                        End,
-                        // Decrement instruction counter to charge for dirty pages
-                        LocalGet { local_index: 5 },
-                        I64ExtendI32U,
-                        I64Const {
-                            value: dirty_page_overhead.get().try_into().unwrap(),
-                        },
-                        I64Mul,
-                        // Bounds check above should guarantee that we don't
-                        // overflow as the over head is a small constant.
-                        Call {
-                            function_index: decr_instruction_counter_fn,
-                        },
-                        Drop,
-                        // perform memory fill
-                        LocalGet { local_index: 3 }, //b_start
-                        // value to fill with
-                        I32Const { value: 3 },
-                        // calculate b_size
-                        // b_end = (dst + size - 1) / PAGE_SIZE + 1
-                        // b_len = b_end - b_start
-                        LocalGet { local_index: 4 }, //b_end
-                        LocalGet { local_index: 3 }, //b_start
-                        // b_end - b_start
-                        I32Sub,
-                        MemoryFill {
-                            mem: stable_memory_bytemap_index,
-                        },
-                        // copy memory contents
-                        LocalGet { local_index: 0 },
-                        LocalGet { local_index: 1 },
-                        I32WrapI64,
-                        LocalGet { local_index: 2 },
-                        I32WrapI64,
-                        MemoryCopy {
-                            dst_mem: stable_memory_index,
-                            src_mem: 0,
-                        },
-                        GlobalGet {
-                            global_index: dirty_pages_counter_index,
-                        },
-                        LocalGet { local_index: 5 },
-                        I64ExtendI32U,
-                        I64Sub,
-                        GlobalSet {
-                            global_index: dirty_pages_counter_index,
-                        },
-                        GlobalGet {
-                            global_index: accessed_pages_counter_index,
-                        },
-                        LocalGet { local_index: 6 },
-                        I64ExtendI32U,
-                        I64Sub,
-                        GlobalSet {
-                            global_index: accessed_pages_counter_index,
-                        },
-                        End,
-                    ],
+                    ]
+                    .concat(),
                 },
             ),
         ),
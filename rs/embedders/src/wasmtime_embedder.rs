@@ -13,13 +13,14 @@ use std::{
 
 use ic_system_api::ModificationTracking;
 use wasmtime::{
-    unix::StoreExt, Engine, Instance, Memory, Module, Mutability, OptLevel, Store, Val, ValType,
+    unix::StoreExt, Engine, ExternType, Instance, Memory, Module, Mutability, OptLevel, Store, Val,
+    ValType,
 };
 
 pub use host_memory::WasmtimeMemoryCreator;
 use ic_config::{embedders::Config as EmbeddersConfig, flag_status::FlagStatus};
 use ic_interfaces::execution_environment::{
-    HypervisorError, HypervisorResult, InstanceStats, SystemApi, TrapCode,
+    HypervisorError, HypervisorResult, InstanceStats, QueryDependencies, SystemApi, TrapCode,
 };
 use ic_logger::{debug, error, fatal, ReplicaLogger};
 use ic_replicated_state::{
@@ -28,6 +29,7 @@ use ic_replicated_state::{
 };
 use ic_sys::PAGE_SIZE;
 use ic_types::{
+    instruction_profile::InstructionProfile,
     methods::{FuncRef, WasmMethod},
     CanisterId, MAX_STABLE_MEMORY_IN_BYTES,
 };
@@ -191,7 +193,9 @@ impl WasmtimeEmbedder {
         {
             config.wasm_multi_memory(true);
         }
-        if embedder_config.feature_flags.wasm_native_stable_memory == FlagStatus::Enabled {
+        if embedder_config.feature_flags.wasm_native_stable_memory == FlagStatus::Enabled
+            || embedder_config.feature_flags.wasm64 == FlagStatus::Enabled
+        {
             config.wasm_memory64(true);
         }
         config
@@ -324,17 +328,36 @@ impl WasmtimeEmbedder {
             StoreData {
                 system_api,
                 num_instructions_global: None,
+                reads_system_state: false,
+                instruction_profile: InstructionProfile::default(),
             },
         );
 
-        let linker = system_api::syscalls(
-            self.log.clone(),
-            canister_id,
-            &store,
-            self.config.feature_flags,
-            self.config.stable_memory_dirty_page_limit,
-            self.config.stable_memory_accessed_page_limit,
-        );
+        // System API calls of Wasm64 canisters pass heap addresses and sizes
+        // as 64-bit values.
+        let is_wasm64 = match module.get_export(WASM_HEAP_MEMORY_NAME) {
+            Some(ExternType::Memory(memory_type)) => memory_type.is_64(),
+            _ => false,
+        };
+        let linker = if is_wasm64 {
+            system_api::syscalls::<S, u64>(
+                self.log.clone(),
+                canister_id,
+                &store,
+                self.config.feature_flags,
+                self.config.stable_memory_dirty_page_limit,
+                self.config.stable_memory_accessed_page_limit,
+            )
+        } else {
+            system_api::syscalls::<S, u32>(
+                self.log.clone(),
+                canister_id,
+                &store,
+                self.config.feature_flags,
+                self.config.stable_memory_dirty_page_limit,
+                self.config.stable_memory_accessed_page_limit,
+            )
+        };
 
         let instance = match linker.instantiate(&mut store, module) {
             Ok(instance) => instance,
@@ -443,6 +466,7 @@ impl WasmtimeEmbedder {
             write_barrier: self.config.feature_flags.write_barrier,
             wasm_native_stable_memory: self.config.feature_flags.wasm_native_stable_memory,
             modification_tracking,
+            is_wasm64,
             #[cfg(debug_assertions)]
             stable_memory_dirty_page_limit: self.config.stable_memory_dirty_page_limit,
         })
@@ -638,6 +662,12 @@ fn sigsegv_memory_tracker<S>(
 pub struct StoreData<S> {
     pub system_api: S,
     pub num_instructions_global: Option<wasmtime::Global>,
+    /// Set once the execution calls a system API that exposes state outside
+    /// of the canister memory.
+    pub reads_system_state: bool,
+    /// Per-function instruction counts, only recorded if canister profiling
+    /// is enabled.
+    pub instruction_profile: InstructionProfile,
 }
 
 pub struct PageAccessResults {
@@ -659,6 +689,7 @@ pub struct WasmtimeInstance<S: SystemApi> {
     write_barrier: FlagStatus,
     wasm_native_stable_memory: FlagStatus,
     modification_tracking: ModificationTracking,
+    is_wasm64: bool,
     #[cfg(debug_assertions)]
     stable_memory_dirty_page_limit: ic_types::NumPages,
 }
@@ -690,6 +721,30 @@ impl<S: SystemApi> WasmtimeInstance<S> {
             .map_err(wasmtime_error_to_hypervisor_error)
     }
 
+    /// Collects the pages accessed by an execution that ignores state
+    /// modifications, so that its result can be cached until they change.
+    /// Returns `None` for other executions and if a memory is not tracked.
+    fn query_dependencies(&self) -> Option<QueryDependencies> {
+        if self.modification_tracking != ModificationTracking::Ignore {
+            return None;
+        }
+        let accessed_pages = |memory_type| {
+            self.memory_trackers.get(&memory_type).map(|tracker| {
+                tracker
+                    .lock()
+                    .unwrap()
+                    .accessed_pages()
+                    .borrow()
+                    .marked_pages()
+            })
+        };
+        Some(QueryDependencies {
+            heap_pages: accessed_pages(CanisterMemoryType::Heap)?,
+            stable_pages: accessed_pages(CanisterMemoryType::Stable)?,
+            reads_system_state: self.store.data().reads_system_state,
+        })
+    }
+
     fn page_accesses(&mut self) -> HypervisorResult<PageAccessResults> {
         let mut stable_dirty_pages = vec![];
         if self.wasm_native_stable_memory == FlagStatus::Enabled {
@@ -816,6 +871,7 @@ impl<S: SystemApi> WasmtimeInstance<S> {
         self.instance_stats.dirty_pages += access.dirty_pages.len();
         self.instance_stats.read_before_write_count += access.read_before_write_count;
         self.instance_stats.direct_write_count += access.direct_write_count;
+        self.instance_stats.query_dependencies = self.query_dependencies();
 
         let stable_memory_dirty_pages: Vec<_> = match self.wasm_native_stable_memory {
             FlagStatus::Enabled => {
@@ -1009,6 +1065,11 @@ impl<S: SystemApi> WasmtimeInstance<S> {
         NumWasmPages::from(self.get_memory(name).map_or(0, |mem| mem.size(&self.store)) as usize)
     }
 
+    /// Returns true if the Wasm memory of the instance is 64-bit.
+    pub fn is_wasm64(&self) -> bool {
+        self.is_wasm64
+    }
+
     /// Returns a list of exported globals.
     pub fn get_exported_globals(&mut self) -> Vec<Global> {
         let globals = get_exported_globals(
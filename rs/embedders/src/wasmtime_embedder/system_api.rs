@@ -12,10 +12,67 @@ use ic_registry_subnet_type::SubnetType;
 use ic_sys::PAGE_SIZE;
 use ic_types::{CanisterId, Cycles, NumBytes, NumInstructions, NumPages, Time};
 
-use wasmtime::{AsContextMut, Caller, Global, Linker, Store, Val};
+use wasmtime::{AsContextMut, Caller, Global, Linker, Store, Val, WasmTy};
 
 use crate::InternalErrorCode;
 use std::convert::TryFrom;
+use std::num::TryFromIntError;
+
+/// The Wasm type of heap addresses and sizes passed to the System API:
+/// `u32` for canisters with a 32-bit memory and `u64` for Wasm64 canisters.
+pub(crate) trait WasmPointer: WasmTy + Copy + Send + Sync + 'static {
+    /// Converts an address or size in the Wasm heap to a native offset.
+    fn to_usize(self) -> usize;
+
+    /// Converts a function table index or a closure environment, which are
+    /// 32-bit values regardless of the memory type.
+    fn to_u32(self) -> HypervisorResult<u32>;
+
+    /// Converts a size returned by the System API to a Wasm value.
+    fn from_usize(value: usize) -> Result<Self, TryFromIntError>;
+
+    /// Interprets the result of `memory.grow` or `table.grow`, where `-1`
+    /// indicates a failure.
+    fn to_grow_result(self) -> i64;
+}
+
+impl WasmPointer for u32 {
+    fn to_usize(self) -> usize {
+        self as usize
+    }
+
+    fn to_u32(self) -> HypervisorResult<u32> {
+        Ok(self)
+    }
+
+    fn from_usize(value: usize) -> Result<Self, TryFromIntError> {
+        u32::try_from(value)
+    }
+
+    fn to_grow_result(self) -> i64 {
+        self as i32 as i64
+    }
+}
+
+impl WasmPointer for u64 {
+    fn to_usize(self) -> usize {
+        self as usize
+    }
+
+    fn to_u32(self) -> HypervisorResult<u32> {
+        u32::try_from(self).map_err(|_| {
+            HypervisorError::ContractViolation(format!("Value {} does not fit into 32 bits", self))
+        })
+    }
+
+    fn from_usize(value: usize) -> Result<Self, TryFromIntError> {
+        u64::try_from(value)
+    }
+
+    fn to_grow_result(self) -> i64 {
+        self as i64
+    }
+}
 
 fn process_err<S: SystemApi>(
     store: &mut impl AsContextMut<Data = StoreData<S>>,
@@ -100,6 +157,32 @@ fn store_value<S: SystemApi>(
     Ok(())
 }
 
+// Records the entry (or the exit) of the function with the given index in the
+// instruction profile, together with the number of instructions executed in
+// the message so far.
+fn record_profile_event<S: SystemApi>(
+    caller: &mut Caller<'_, StoreData<S>>,
+    func_idx: i32,
+    enter: bool,
+    log: &ReplicaLogger,
+    canister_id: CanisterId,
+) -> Result<(), anyhow::Error> {
+    let global = get_num_instructions_global(caller, log, canister_id)?;
+    let instruction_counter = load_value(&global, caller, log, canister_id)?;
+    let data = caller.as_context_mut().data_mut();
+    let instructions = data
+        .system_api
+        .message_instructions_executed(instruction_counter)
+        .get();
+    if enter {
+        data.instruction_profile
+            .enter(func_idx as u32, instructions);
+    } else {
+        data.instruction_profile.exit(func_idx as u32, instructions);
+    }
+    Ok(())
+}
+
 /// Updates heap bytemap marking which pages have been written to dst and size
 /// need to have valid values (need to pass checks performed by the function
 /// that actually writes to the heap)
@@ -153,7 +236,7 @@ fn charge_for_system_api_call<S: SystemApi>(
     canister_id: CanisterId,
     caller: &mut Caller<'_, StoreData<S>>,
     system_api_overhead: NumInstructions,
-    num_bytes: u32,
+    num_bytes: u64,
     complexity: ExecutionComplexity,
     dirty_page_cost: NumInstructions,
     stable_memory_dirty_page_limit: NumPages,
@@ -168,7 +251,7 @@ fn charge_for_system_api_call<S: SystemApi>(
     let num_instructions_from_bytes = caller
         .data()
         .system_api
-        .get_num_instructions_from_bytes(NumBytes::from(num_bytes as u64));
+        .get_num_instructions_from_bytes(NumBytes::from(num_bytes));
     let (num_instructions1, overflow1) = num_instructions_from_bytes
         .get()
         .overflowing_add(dirty_page_cost.get());
@@ -360,7 +443,7 @@ fn ic0_performance_counter_helper<S: SystemApi>(
     }
 }
 
-pub(crate) fn syscalls<S: SystemApi>(
+pub(crate) fn syscalls<S: SystemApi, I: WasmPointer>(
     log: ReplicaLogger,
     canister_id: CanisterId,
     store: &Store<StoreData<S>>,
@@ -372,6 +455,12 @@ pub(crate) fn syscalls<S: SystemApi>(
         f(&mut caller.as_context_mut().data_mut().system_api)
     }
 
+    // Records that the execution depends on state outside of the canister
+    // memory, which must not be cached based on the accessed pages alone.
+    fn mark_system_state_read<S>(caller: &mut Caller<'_, StoreData<S>>) {
+        caller.as_context_mut().data_mut().reads_system_state = true;
+    }
+
     fn with_memory_and_system_api<S: SystemApi, T>(
         mut caller: &mut Caller<'_, StoreData<S>>,
         f: impl Fn(&mut S, &mut [u8]) -> HypervisorResult<T>,
@@ -406,7 +495,7 @@ pub(crate) fn syscalls<S: SystemApi>(
     linker
         .func_wrap("ic0", "msg_caller_copy", {
             let log = log.clone();
-            move |mut caller: Caller<'_, StoreData<S>>, dst: i32, offset: i32, size: i32| {
+            move |mut caller: Caller<'_, StoreData<S>>, dst: I, offset: I, size: I| {
                 observe_execution_complexity(
                     &log,
                     canister_id,
@@ -418,10 +507,15 @@ pub(crate) fn syscalls<S: SystemApi>(
                     stable_memory_dirty_page_limit,
                 )?;
                 with_memory_and_system_api(&mut caller, |system_api, memory| {
-                    system_api.ic0_msg_caller_copy(dst as u32, offset as u32, size as u32, memory)
+                    system_api.ic0_msg_caller_copy(
+                        dst.to_usize(),
+                        offset.to_usize(),
+                        size.to_usize(),
+                        memory,
+                    )
                 })?;
                 if feature_flags.write_barrier == FlagStatus::Enabled {
-                    mark_writes_on_bytemap(&mut caller, dst as u32 as usize, size as u32 as usize)
+                    mark_writes_on_bytemap(&mut caller, dst.to_usize(), size.to_usize())
                 } else {
                     Ok(())
                 }
@@ -435,7 +529,7 @@ pub(crate) fn syscalls<S: SystemApi>(
                 with_system_api(&mut caller, |s| s.ic0_msg_caller_size())
                     .map_err(|e| process_err(&mut caller, e))
                     .and_then(|s| {
-                        i32::try_from(s).map_err(|e| {
+                        I::from_usize(s as usize).map_err(|e| {
                             anyhow::Error::msg(format!("ic0::msg_caller_size failed: {}", e))
                         })
                     })
@@ -449,7 +543,7 @@ pub(crate) fn syscalls<S: SystemApi>(
                 with_system_api(&mut caller, |s| s.ic0_msg_arg_data_size())
                     .map_err(|e| process_err(&mut caller, e))
                     .and_then(|s| {
-                        i32::try_from(s).map_err(|e| {
+                        I::from_usize(s as usize).map_err(|e| {
                             anyhow::Error::msg(format!("ic0::msg_arg_data_size failed: {}", e))
                         })
                     })
@@ -460,13 +554,13 @@ pub(crate) fn syscalls<S: SystemApi>(
     linker
         .func_wrap("ic0", "msg_arg_data_copy", {
             let log = log.clone();
-            move |mut caller: Caller<'_, StoreData<S>>, dst: i32, offset: i32, size: i32| {
+            move |mut caller: Caller<'_, StoreData<S>>, dst: I, offset: I, size: I| {
                 charge_for_system_api_call(
                     &log,
                     canister_id,
                     &mut caller,
                     system_api_complexity::overhead::MSG_ARG_DATA_COPY,
-                    size as u32,
+                    size.to_usize() as u64,
                     ExecutionComplexity {
                         cpu: system_api_complexity::cpu::MSG_ARG_DATA_COPY,
                         ..Default::default()
@@ -475,10 +569,15 @@ pub(crate) fn syscalls<S: SystemApi>(
                     stable_memory_dirty_page_limit,
                 )?;
                 with_memory_and_system_api(&mut caller, |system_api, mem| {
-                    system_api.ic0_msg_arg_data_copy(dst as u32, offset as u32, size as u32, mem)
+                    system_api.ic0_msg_arg_data_copy(
+                        dst.to_usize(),
+                        offset.to_usize(),
+                        size.to_usize(),
+                        mem,
+                    )
                 })?;
                 if feature_flags.write_barrier == FlagStatus::Enabled {
-                    mark_writes_on_bytemap(&mut caller, dst as u32 as usize, size as u32 as usize)
+                    mark_writes_on_bytemap(&mut caller, dst.to_usize(), size.to_usize())
                 } else {
                     Ok(())
                 }
@@ -492,7 +591,7 @@ pub(crate) fn syscalls<S: SystemApi>(
                 with_system_api(&mut caller, |s| s.ic0_msg_method_name_size())
                     .map_err(|e| process_err(&mut caller, e))
                     .and_then(|s| {
-                        i32::try_from(s).map_err(|e| {
+                        I::from_usize(s as usize).map_err(|e| {
                             anyhow::Error::msg(format!("ic0::msg_metohd_name_size failed: {}", e))
                         })
                     })
@@ -503,13 +602,13 @@ pub(crate) fn syscalls<S: SystemApi>(
     linker
         .func_wrap("ic0", "msg_method_name_copy", {
             let log = log.clone();
-            move |mut caller: Caller<'_, StoreData<S>>, dst: i32, offset: i32, size: i32| {
+            move |mut caller: Caller<'_, StoreData<S>>, dst: I, offset: I, size: I| {
                 charge_for_system_api_call(
                     &log,
                     canister_id,
                     &mut caller,
                     system_api_complexity::overhead::MSG_METHOD_NAME_COPY,
-                    size as u32,
+                    size.to_usize() as u64,
                     ExecutionComplexity {
                         cpu: system_api_complexity::cpu::MSG_METHOD_NAME_COPY,
                         ..Default::default()
@@ -519,14 +618,14 @@ pub(crate) fn syscalls<S: SystemApi>(
                 )?;
                 with_memory_and_system_api(&mut caller, |system_api, memory| {
                     system_api.ic0_msg_method_name_copy(
-                        dst as u32,
-                        offset as u32,
-                        size as u32,
+                        dst.to_usize(),
+                        offset.to_usize(),
+                        size.to_usize(),
                         memory,
                     )
                 })?;
                 if feature_flags.write_barrier == FlagStatus::Enabled {
-                    mark_writes_on_bytemap(&mut caller, dst as u32 as usize, size as u32 as usize)
+                    mark_writes_on_bytemap(&mut caller, dst.to_usize(), size.to_usize())
                 } else {
                     Ok(())
                 }
@@ -546,13 +645,13 @@ pub(crate) fn syscalls<S: SystemApi>(
     linker
         .func_wrap("ic0", "msg_reply_data_append", {
             let log = log.clone();
-            move |mut caller: Caller<'_, StoreData<S>>, src: i32, size: i32| {
+            move |mut caller: Caller<'_, StoreData<S>>, src: I, size: I| {
                 charge_for_system_api_call(
                     &log,
                     canister_id,
                     &mut caller,
                     system_api_complexity::overhead::MSG_REPLY_DATA_APPEND,
-                    size as u32,
+                    size.to_usize() as u64,
                     ExecutionComplexity {
                         cpu: system_api_complexity::cpu::MSG_REPLY_DATA_APPEND,
                         ..Default::default()
@@ -561,7 +660,7 @@ pub(crate) fn syscalls<S: SystemApi>(
                     stable_memory_dirty_page_limit,
                 )?;
                 with_memory_and_system_api(&mut caller, |system_api, memory| {
-                    system_api.ic0_msg_reply_data_append(src as u32, size as u32, memory)
+                    system_api.ic0_msg_reply_data_append(src.to_usize(), size.to_usize(), memory)
                 })
             }
         })
@@ -588,13 +687,13 @@ pub(crate) fn syscalls<S: SystemApi>(
     linker
         .func_wrap("ic0", "msg_reject", {
             let log = log.clone();
-            move |mut caller: Caller<'_, StoreData<S>>, src: i32, size: i32| {
+            move |mut caller: Caller<'_, StoreData<S>>, src: I, size: I| {
                 charge_for_system_api_call(
                     &log,
                     canister_id,
                     &mut caller,
                     system_api_complexity::overhead::MSG_REJECT,
-                    size as u32,
+                    size.to_usize() as u64,
                     ExecutionComplexity {
                         cpu: system_api_complexity::cpu::MSG_REJECT,
                         ..Default::default()
@@ -603,7 +702,7 @@ pub(crate) fn syscalls<S: SystemApi>(
                     stable_memory_dirty_page_limit,
                 )?;
                 with_memory_and_system_api(&mut caller, |system_api, memory| {
-                    system_api.ic0_msg_reject(src as u32, size as u32, memory)
+                    system_api.ic0_msg_reject(src.to_usize(), size.to_usize(), memory)
                 })
             }
         })
@@ -615,7 +714,7 @@ pub(crate) fn syscalls<S: SystemApi>(
                 with_system_api(&mut caller, |s| s.ic0_msg_reject_msg_size())
                     .map_err(|e| process_err(&mut caller, e))
                     .and_then(|s| {
-                        i32::try_from(s).map_err(|e| {
+                        I::from_usize(s as usize).map_err(|e| {
                             anyhow::Error::msg(format!("ic0_msg_reject_msg_size failed: {}", e))
                         })
                     })
@@ -626,13 +725,13 @@ pub(crate) fn syscalls<S: SystemApi>(
     linker
         .func_wrap("ic0", "msg_reject_msg_copy", {
             let log = log.clone();
-            move |mut caller: Caller<'_, StoreData<S>>, dst: i32, offset: i32, size: i32| {
+            move |mut caller: Caller<'_, StoreData<S>>, dst: I, offset: I, size: I| {
                 charge_for_system_api_call(
                     &log,
                     canister_id,
                     &mut caller,
                     system_api_complexity::overhead::MSG_REJECT_MSG_COPY,
-                    size as u32,
+                    size.to_usize() as u64,
                     ExecutionComplexity {
                         cpu: system_api_complexity::cpu::MSG_REJECT_MSG_COPY,
                         ..Default::default()
@@ -642,14 +741,14 @@ pub(crate) fn syscalls<S: SystemApi>(
                 )?;
                 with_memory_and_system_api(&mut caller, |system_api, memory| {
                     system_api.ic0_msg_reject_msg_copy(
-                        dst as u32,
-                        offset as u32,
-                        size as u32,
+                        dst.to_usize(),
+                        offset.to_usize(),
+                        size.to_usize(),
                         memory,
                     )
                 })?;
                 if feature_flags.write_barrier == FlagStatus::Enabled {
-                    mark_writes_on_bytemap(&mut caller, dst as u32 as usize, size as u32 as usize)
+                    mark_writes_on_bytemap(&mut caller, dst.to_usize(), size.to_usize())
                 } else {
                     Ok(())
                 }
@@ -663,7 +762,7 @@ pub(crate) fn syscalls<S: SystemApi>(
                 with_system_api(&mut caller, |s| s.ic0_canister_self_size())
                     .map_err(|e| process_err(&mut caller, e))
                     .and_then(|s| {
-                        i32::try_from(s).map_err(|e| {
+                        I::from_usize(s).map_err(|e| {
                             anyhow::Error::msg(format!("ic0_canister_self_size failed: {}", e))
                         })
                     })
@@ -674,7 +773,7 @@ pub(crate) fn syscalls<S: SystemApi>(
     linker
         .func_wrap("ic0", "canister_self_copy", {
             let log = log.clone();
-            move |mut caller: Caller<'_, StoreData<S>>, dst: i32, offset: i32, size: i32| {
+            move |mut caller: Caller<'_, StoreData<S>>, dst: I, offset: I, size: I| {
                 observe_execution_complexity(
                     &log,
                     canister_id,
@@ -687,14 +786,14 @@ pub(crate) fn syscalls<S: SystemApi>(
                 )?;
                 with_memory_and_system_api(&mut caller, |system_api, memory| {
                     system_api.ic0_canister_self_copy(
-                        dst as u32,
-                        offset as u32,
-                        size as u32,
+                        dst.to_usize(),
+                        offset.to_usize(),
+                        size.to_usize(),
                         memory,
                     )
                 })?;
                 if feature_flags.write_barrier == FlagStatus::Enabled {
-                    mark_writes_on_bytemap(&mut caller, dst as u32 as usize, size as u32 as usize)
+                    mark_writes_on_bytemap(&mut caller, dst.to_usize(), size.to_usize())
                 } else {
                     Ok(())
                 }
@@ -705,13 +804,13 @@ pub(crate) fn syscalls<S: SystemApi>(
     linker
         .func_wrap("ic0", "debug_print", {
             let log = log.clone();
-            move |mut caller: Caller<'_, StoreData<S>>, offset: i32, length: i32| {
+            move |mut caller: Caller<'_, StoreData<S>>, offset: I, length: I| {
                 charge_for_system_api_call(
                     &log,
                     canister_id,
                     &mut caller,
                     system_api_complexity::overhead::DEBUG_PRINT,
-                    length as u32,
+                    length.to_usize() as u64,
                     ExecutionComplexity {
                         cpu: system_api_complexity::cpu::DEBUG_PRINT,
                         ..Default::default()
@@ -719,6 +818,12 @@ pub(crate) fn syscalls<S: SystemApi>(
                     NumInstructions::from(0),
                     stable_memory_dirty_page_limit,
                 )?;
+                // The message is always retained in the canister log, only the
+                // output to the replica log is subject to rate limiting.
+                with_memory_and_system_api(&mut caller, |system_api, memory| {
+                    system_api.save_log_message(offset.to_usize(), length.to_usize(), memory);
+                    Ok(())
+                })?;
                 match (
                     caller.data().system_api.subnet_type(),
                     feature_flags.rate_limiting_of_debug_prints,
@@ -730,7 +835,7 @@ pub(crate) fn syscalls<S: SystemApi>(
                     // debug print produces output.
                     (_, FlagStatus::Disabled) | (SubnetType::System, FlagStatus::Enabled) => {
                         with_memory_and_system_api(&mut caller, |system_api, memory| {
-                            system_api.ic0_debug_print(offset as u32, length as u32, memory)
+                            system_api.ic0_debug_print(offset.to_usize(), length.to_usize(), memory)
                         })
                     }
                 }
@@ -741,13 +846,13 @@ pub(crate) fn syscalls<S: SystemApi>(
     linker
         .func_wrap("ic0", "trap", {
             let log = log.clone();
-            move |mut caller: Caller<'_, StoreData<S>>, offset: i32, length: i32| -> Result<(), _> {
+            move |mut caller: Caller<'_, StoreData<S>>, offset: I, length: I| -> Result<(), _> {
                 charge_for_system_api_call(
                     &log,
                     canister_id,
                     &mut caller,
                     system_api_complexity::overhead::TRAP,
-                    length as u32,
+                    length.to_usize() as u64,
                     ExecutionComplexity {
                         cpu: system_api_complexity::cpu::TRAP,
                         ..Default::default()
@@ -756,7 +861,7 @@ pub(crate) fn syscalls<S: SystemApi>(
                     stable_memory_dirty_page_limit,
                 )?;
                 with_memory_and_system_api(&mut caller, |system_api, memory| {
-                    system_api.ic0_trap(offset as u32, length as u32, memory)
+                    system_api.ic0_trap(offset.to_usize(), length.to_usize(), memory)
                 })
             }
         })
@@ -766,14 +871,14 @@ pub(crate) fn syscalls<S: SystemApi>(
         .func_wrap("ic0", "call_new", {
             let log = log.clone();
             move |mut caller: Caller<'_, StoreData<S>>,
-                  callee_src: i32,
-                  callee_size: i32,
-                  name_src: i32,
-                  name_len: i32,
-                  reply_fun: i32,
-                  reply_env: i32,
-                  reject_fun: i32,
-                  reject_env: i32| {
+                  callee_src: I,
+                  callee_size: I,
+                  name_src: I,
+                  name_len: I,
+                  reply_fun: I,
+                  reply_env: I,
+                  reject_fun: I,
+                  reject_env: I| {
                 observe_execution_complexity(
                     &log,
                     canister_id,
@@ -786,14 +891,14 @@ pub(crate) fn syscalls<S: SystemApi>(
                 )?;
                 with_memory_and_system_api(&mut caller, |system_api, memory| {
                     system_api.ic0_call_new(
-                        callee_src as u32,
-                        callee_size as u32,
-                        name_src as u32,
-                        name_len as u32,
-                        reply_fun as u32,
-                        reply_env as u32,
-                        reject_fun as u32,
-                        reject_env as u32,
+                        callee_src.to_usize(),
+                        callee_size.to_usize(),
+                        name_src.to_usize(),
+                        name_len.to_usize(),
+                        reply_fun.to_u32()?,
+                        reply_env.to_u32()?,
+                        reject_fun.to_u32()?,
+                        reject_env.to_u32()?,
                         memory,
                     )
                 })
@@ -804,13 +909,13 @@ pub(crate) fn syscalls<S: SystemApi>(
     linker
         .func_wrap("ic0", "call_data_append", {
             let log = log.clone();
-            move |mut caller: Caller<'_, StoreData<S>>, src: i32, size: i32| {
+            move |mut caller: Caller<'_, StoreData<S>>, src: I, size: I| {
                 charge_for_system_api_call(
                     &log,
                     canister_id,
                     &mut caller,
                     system_api_complexity::overhead::CALL_DATA_APPEND,
-                    size as u32,
+                    size.to_usize() as u64,
                     ExecutionComplexity {
                         cpu: system_api_complexity::cpu::CALL_DATA_APPEND,
                         ..Default::default()
@@ -819,7 +924,7 @@ pub(crate) fn syscalls<S: SystemApi>(
                     stable_memory_dirty_page_limit,
                 )?;
                 with_memory_and_system_api(&mut caller, |system_api, memory| {
-                    system_api.ic0_call_data_append(src as u32, size as u32, memory)
+                    system_api.ic0_call_data_append(src.to_usize(), size.to_usize(), memory)
                 })
             }
         })
@@ -827,9 +932,20 @@ pub(crate) fn syscalls<S: SystemApi>(
 
     linker
         .func_wrap("ic0", "call_on_cleanup", {
-            move |mut caller: Caller<'_, StoreData<S>>, fun: i32, env: i32| {
+            move |mut caller: Caller<'_, StoreData<S>>, fun: I, env: I| {
                 with_system_api(&mut caller, |s| {
-                    s.ic0_call_on_cleanup(fun as u32, env as u32)
+                    s.ic0_call_on_cleanup(fun.to_u32()?, env.to_u32()?)
+                })
+                .map_err(|e| process_err(&mut caller, e))
+            }
+        })
+        .unwrap();
+
+    linker
+        .func_wrap("ic0", "call_with_best_effort_response", {
+            move |mut caller: Caller<'_, StoreData<S>>, timeout_seconds: i32| {
+                with_system_api(&mut caller, |s| {
+                    s.ic0_call_with_best_effort_response(timeout_seconds as u32)
                 })
                 .map_err(|e| process_err(&mut caller, e))
             }
@@ -922,7 +1038,7 @@ pub(crate) fn syscalls<S: SystemApi>(
                     canister_id,
                     &mut caller,
                     system_api_complexity::overhead::STABLE_READ,
-                    size as u32,
+                    size as u64,
                     ExecutionComplexity {
                         cpu: system_api_complexity::cpu::STABLE_READ,
                         ..Default::default()
@@ -956,7 +1072,7 @@ pub(crate) fn syscalls<S: SystemApi>(
                     canister_id,
                     &mut caller,
                     system_api_complexity::overhead::STABLE_WRITE,
-                    size,
+                    size as u64,
                     ExecutionComplexity {
                         cpu: system_api_complexity::cpu::STABLE_WRITE,
                         stable_dirty_pages,
@@ -1019,7 +1135,7 @@ pub(crate) fn syscalls<S: SystemApi>(
                     )
                 })?;
                 if feature_flags.write_barrier == FlagStatus::Enabled {
-                    mark_writes_on_bytemap(&mut caller, dst as u32 as usize, size as u32 as usize)
+                    mark_writes_on_bytemap(&mut caller, dst as usize, size as usize)
                 } else {
                     Ok(())
                 }
@@ -1036,7 +1152,7 @@ pub(crate) fn syscalls<S: SystemApi>(
                     canister_id,
                     &mut caller,
                     system_api_complexity::overhead::STABLE64_READ,
-                    size as u32,
+                    size as u64,
                     ExecutionComplexity {
                         cpu: system_api_complexity::cpu::STABLE64_READ,
                         ..Default::default()
@@ -1048,7 +1164,7 @@ pub(crate) fn syscalls<S: SystemApi>(
                     system_api.ic0_stable64_read(dst as u64, offset as u64, size as u64, memory)
                 })?;
                 if feature_flags.write_barrier == FlagStatus::Enabled {
-                    mark_writes_on_bytemap(&mut caller, dst as u32 as usize, size as u32 as usize)
+                    mark_writes_on_bytemap(&mut caller, dst as usize, size as usize)
                 } else {
                     Ok(())
                 }
@@ -1070,7 +1186,7 @@ pub(crate) fn syscalls<S: SystemApi>(
                     canister_id,
                     &mut caller,
                     system_api_complexity::overhead::STABLE64_WRITE,
-                    size as u32,
+                    size,
                     ExecutionComplexity {
                         cpu: system_api_complexity::cpu::STABLE64_WRITE,
                         stable_dirty_pages,
@@ -1088,6 +1204,7 @@ pub(crate) fn syscalls<S: SystemApi>(
     linker
         .func_wrap("ic0", "time", {
             move |mut caller: Caller<'_, StoreData<S>>| {
+                mark_system_state_read(&mut caller);
                 with_system_api(&mut caller, |s| s.ic0_time())
                     .map_err(|e| process_err(&mut caller, e))
                     .map(|s| s.as_nanos_since_unix_epoch())
@@ -1098,6 +1215,7 @@ pub(crate) fn syscalls<S: SystemApi>(
     linker
         .func_wrap("ic0", "global_timer_set", {
             move |mut caller: Caller<'_, StoreData<S>>, time: i64| {
+                mark_system_state_read(&mut caller);
                 with_system_api(&mut caller, |s| {
                     s.ic0_global_timer_set(Time::from_nanos_since_unix_epoch(time as u64))
                 })
@@ -1132,6 +1250,7 @@ pub(crate) fn syscalls<S: SystemApi>(
     linker
         .func_wrap("ic0", "canister_version", {
             move |mut caller: Caller<'_, StoreData<S>>| {
+                mark_system_state_read(&mut caller);
                 with_system_api(&mut caller, |s| s.ic0_canister_version())
                     .map_err(|e| process_err(&mut caller, e))
             }
@@ -1141,6 +1260,7 @@ pub(crate) fn syscalls<S: SystemApi>(
     linker
         .func_wrap("ic0", "canister_cycle_balance", {
             move |mut caller: Caller<'_, StoreData<S>>| {
+                mark_system_state_read(&mut caller);
                 with_system_api(&mut caller, |s| s.ic0_canister_cycle_balance())
                     .map_err(|e| process_err(&mut caller, e))
                     .and_then(|s| {
@@ -1155,7 +1275,8 @@ pub(crate) fn syscalls<S: SystemApi>(
     linker
         .func_wrap("ic0", "canister_cycle_balance128", {
             let log = log.clone();
-            move |mut caller: Caller<'_, StoreData<S>>, dst: u32| {
+            move |mut caller: Caller<'_, StoreData<S>>, dst: I| {
+                mark_system_state_read(&mut caller);
                 observe_execution_complexity(
                     &log,
                     canister_id,
@@ -1167,10 +1288,10 @@ pub(crate) fn syscalls<S: SystemApi>(
                     stable_memory_dirty_page_limit,
                 )?;
                 with_memory_and_system_api(&mut caller, |system_api, memory| {
-                    system_api.ic0_canister_cycle_balance128(dst, memory)
+                    system_api.ic0_canister_cycle_balance128(dst.to_usize(), memory)
                 })?;
                 if feature_flags.write_barrier == FlagStatus::Enabled {
-                    mark_writes_on_bytemap(&mut caller, dst as usize, 16)
+                    mark_writes_on_bytemap(&mut caller, dst.to_usize(), 16)
                 } else {
                     Ok(())
                 }
@@ -1195,7 +1316,7 @@ pub(crate) fn syscalls<S: SystemApi>(
     linker
         .func_wrap("ic0", "msg_cycles_available128", {
             let log = log.clone();
-            move |mut caller: Caller<'_, StoreData<S>>, dst: u32| {
+            move |mut caller: Caller<'_, StoreData<S>>, dst: I| {
                 observe_execution_complexity(
                     &log,
                     canister_id,
@@ -1207,10 +1328,10 @@ pub(crate) fn syscalls<S: SystemApi>(
                     stable_memory_dirty_page_limit,
                 )?;
                 with_memory_and_system_api(&mut caller, |system_api, memory| {
-                    system_api.ic0_msg_cycles_available128(dst, memory)
+                    system_api.ic0_msg_cycles_available128(dst.to_usize(), memory)
                 })?;
                 if feature_flags.write_barrier == FlagStatus::Enabled {
-                    mark_writes_on_bytemap(&mut caller, dst as usize, 16)
+                    mark_writes_on_bytemap(&mut caller, dst.to_usize(), 16)
                 } else {
                     Ok(())
                 }
@@ -1235,7 +1356,7 @@ pub(crate) fn syscalls<S: SystemApi>(
     linker
         .func_wrap("ic0", "msg_cycles_refunded128", {
             let log = log.clone();
-            move |mut caller: Caller<'_, StoreData<S>>, dst: u32| {
+            move |mut caller: Caller<'_, StoreData<S>>, dst: I| {
                 observe_execution_complexity(
                     &log,
                     canister_id,
@@ -1247,10 +1368,10 @@ pub(crate) fn syscalls<S: SystemApi>(
                     stable_memory_dirty_page_limit,
                 )?;
                 with_memory_and_system_api(&mut caller, |system_api, memory| {
-                    system_api.ic0_msg_cycles_refunded128(dst, memory)
+                    system_api.ic0_msg_cycles_refunded128(dst.to_usize(), memory)
                 })?;
                 if feature_flags.write_barrier == FlagStatus::Enabled {
-                    mark_writes_on_bytemap(&mut caller, dst as usize, 16)
+                    mark_writes_on_bytemap(&mut caller, dst.to_usize(), 16)
                 } else {
                     Ok(())
                 }
@@ -1273,7 +1394,7 @@ pub(crate) fn syscalls<S: SystemApi>(
             move |mut caller: Caller<'_, StoreData<S>>,
                   amount_high: i64,
                   amount_low: i64,
-                  dst: u32| {
+                  dst: I| {
                 observe_execution_complexity(
                     &log,
                     canister_id,
@@ -1287,12 +1408,12 @@ pub(crate) fn syscalls<S: SystemApi>(
                 with_memory_and_system_api(&mut caller, |system_api, memory| {
                     system_api.ic0_msg_cycles_accept128(
                         Cycles::from_parts(amount_high as u64, amount_low as u64),
-                        dst,
+                        dst.to_usize(),
                         memory,
                     )
                 })?;
                 if feature_flags.write_barrier == FlagStatus::Enabled {
-                    mark_writes_on_bytemap(&mut caller, dst as usize, 16)
+                    mark_writes_on_bytemap(&mut caller, dst.to_usize(), 16)
                 } else {
                     Ok(())
                 }
@@ -1314,17 +1435,35 @@ pub(crate) fn syscalls<S: SystemApi>(
         })
         .unwrap();
 
+    linker
+        .func_wrap("__", "profile_enter", {
+            let log = log.clone();
+            move |mut caller: Caller<'_, StoreData<S>>, func_idx: i32| {
+                record_profile_event(&mut caller, func_idx, true, &log, canister_id)
+            }
+        })
+        .unwrap();
+
+    linker
+        .func_wrap("__", "profile_exit", {
+            let log = log.clone();
+            move |mut caller: Caller<'_, StoreData<S>>, func_idx: i32| {
+                record_profile_event(&mut caller, func_idx, false, &log, canister_id)
+            }
+        })
+        .unwrap();
+
     linker
         .func_wrap("__", "update_available_memory", {
             move |mut caller: Caller<'_, StoreData<S>>,
-                  native_memory_grow_res: i32,
-                  additional_elements: i32,
-                  element_size: i32| {
+                  native_memory_grow_res: I,
+                  additional_elements: I,
+                  element_size: I| {
                 with_system_api(&mut caller, |s| {
                     s.update_available_memory(
-                        native_memory_grow_res as i64,
-                        additional_elements as u32 as u64,
-                        element_size as u32 as u64,
+                        native_memory_grow_res.to_grow_result(),
+                        additional_elements.to_usize() as u64,
+                        element_size.to_usize() as u64,
                     )
                 })
                 .map(|()| native_memory_grow_res)
@@ -1359,6 +1498,7 @@ pub(crate) fn syscalls<S: SystemApi>(
     linker
         .func_wrap("ic0", "canister_status", {
             move |mut caller: Caller<'_, StoreData<S>>| {
+                mark_system_state_read(&mut caller);
                 with_system_api(&mut caller, |s| s.ic0_canister_status())
                     .map_err(|e| process_err(&mut caller, e))
             }
@@ -1368,7 +1508,7 @@ pub(crate) fn syscalls<S: SystemApi>(
     linker
         .func_wrap("ic0", "certified_data_set", {
             let log = log.clone();
-            move |mut caller: Caller<'_, StoreData<S>>, src: u32, size: u32| {
+            move |mut caller: Caller<'_, StoreData<S>>, src: I, size: I| {
                 observe_execution_complexity(
                     &log,
                     canister_id,
@@ -1380,7 +1520,7 @@ pub(crate) fn syscalls<S: SystemApi>(
                     stable_memory_dirty_page_limit,
                 )?;
                 with_memory_and_system_api(&mut caller, |system_api, memory| {
-                    system_api.ic0_certified_data_set(src, size, memory)
+                    system_api.ic0_certified_data_set(src.to_usize(), size.to_usize(), memory)
                 })
             }
         })
@@ -1389,6 +1529,7 @@ pub(crate) fn syscalls<S: SystemApi>(
     linker
         .func_wrap("ic0", "data_certificate_present", {
             move |mut caller: Caller<'_, StoreData<S>>| {
+                mark_system_state_read(&mut caller);
                 with_system_api(&mut caller, |s| s.ic0_data_certificate_present())
                     .map_err(|e| process_err(&mut caller, e))
             }
@@ -1398,8 +1539,14 @@ pub(crate) fn syscalls<S: SystemApi>(
     linker
         .func_wrap("ic0", "data_certificate_size", {
             move |mut caller: Caller<'_, StoreData<S>>| {
+                mark_system_state_read(&mut caller);
                 with_system_api(&mut caller, |s| s.ic0_data_certificate_size())
                     .map_err(|e| process_err(&mut caller, e))
+                    .and_then(|s| {
+                        I::from_usize(s as usize).map_err(|e| {
+                            anyhow::Error::msg(format!("ic0_data_certificate_size failed: {}", e))
+                        })
+                    })
             }
         })
         .unwrap();
@@ -1407,13 +1554,14 @@ pub(crate) fn syscalls<S: SystemApi>(
     linker
         .func_wrap("ic0", "is_controller", {
             let log = log.clone();
-            move |mut caller: Caller<'_, StoreData<S>>, src: i32, size: i32| {
+            move |mut caller: Caller<'_, StoreData<S>>, src: I, size: I| {
+                mark_system_state_read(&mut caller);
                 charge_for_system_api_call(
                     &log,
                     canister_id,
                     &mut caller,
                     system_api_complexity::overhead::IS_CONTROLLER,
-                    size as u32,
+                    size.to_usize() as u64,
                     ExecutionComplexity {
                         cpu: system_api_complexity::cpu::IS_CONTROLLER,
                         ..Default::default()
@@ -1422,7 +1570,7 @@ pub(crate) fn syscalls<S: SystemApi>(
                     stable_memory_dirty_page_limit,
                 )?;
                 with_memory_and_system_api(&mut caller, |system_api, memory| {
-                    system_api.ic0_is_controller(src as u32, size as u32, memory)
+                    system_api.ic0_is_controller(src.to_usize(), size.to_usize(), memory)
                 })
             }
         })
@@ -1430,7 +1578,8 @@ pub(crate) fn syscalls<S: SystemApi>(
 
     linker
         .func_wrap("ic0", "data_certificate_copy", {
-            move |mut caller: Caller<'_, StoreData<S>>, dst: u32, offset: u32, size: u32| {
+            move |mut caller: Caller<'_, StoreData<S>>, dst: I, offset: I, size: I| {
+                mark_system_state_read(&mut caller);
                 observe_execution_complexity(
                     &log,
                     canister_id,
@@ -1442,10 +1591,15 @@ pub(crate) fn syscalls<S: SystemApi>(
                     stable_memory_dirty_page_limit,
                 )?;
                 with_memory_and_system_api(&mut caller, |system_api, memory| {
-                    system_api.ic0_data_certificate_copy(dst, offset, size, memory)
+                    system_api.ic0_data_certificate_copy(
+                        dst.to_usize(),
+                        offset.to_usize(),
+                        size.to_usize(),
+                        memory,
+                    )
                 })?;
                 if feature_flags.write_barrier == FlagStatus::Enabled {
-                    mark_writes_on_bytemap(&mut caller, dst as usize, size as usize)
+                    mark_writes_on_bytemap(&mut caller, dst.to_usize(), size.to_usize())
                 } else {
                     Ok(())
                 }
@@ -15,7 +15,10 @@ use ic_system_api::{
 use ic_test_utilities::{
     cycles_account_manager::CyclesAccountManagerBuilder, mock_time, types::ids::canister_test_id,
 };
-use ic_types::{ComputeAllocation, MemoryAllocation, NumBytes, NumInstructions};
+use ic_types::{
+    instruction_profile::InstructionProfile, ComputeAllocation, MemoryAllocation, NumBytes,
+    NumInstructions,
+};
 use ic_wasm_types::BinaryEncodedWasm;
 
 use lazy_static::lazy_static;
@@ -80,6 +83,8 @@ fn test_wasmtime_system_api() {
         StoreData {
             system_api,
             num_instructions_global: None,
+            reads_system_state: false,
+            instruction_profile: InstructionProfile::default(),
         },
     );
 
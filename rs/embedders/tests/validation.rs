@@ -1,5 +1,5 @@
 use assert_matches::assert_matches;
-use ic_config::embedders::Config as EmbeddersConfig;
+use ic_config::{embedders::Config as EmbeddersConfig, flag_status::FlagStatus};
 use ic_embedders::{
     wasm_utils::{
         validate_and_instrument_for_testing,
@@ -606,6 +606,51 @@ fn can_reject_module_with_too_many_functions() {
     );
 }
 
+fn wasm64_config() -> EmbeddersConfig {
+    let mut config = EmbeddersConfig::default();
+    config.feature_flags.wasm64 = FlagStatus::Enabled;
+    config
+}
+
+#[test]
+fn can_reject_wasm64_module_if_wasm64_is_disabled() {
+    let wasm = wat2wasm(r#"(module (memory i64 1))"#).unwrap();
+    assert_matches!(
+        validate_wasm_binary(&wasm, &EmbeddersConfig::default()),
+        Err(WasmValidationError::WasmtimeValidation(_))
+    );
+}
+
+#[test]
+fn can_validate_wasm64_module_with_64_bit_system_api_imports() {
+    let wasm = wat2wasm(
+        r#"(module
+                (import "ic0" "msg_arg_data_size" (func $msg_arg_data_size (result i64)))
+                (import "ic0" "msg_arg_data_copy"
+                    (func $msg_arg_data_copy (param i64 i64 i64)))
+                (import "ic0" "msg_reply_data_append"
+                    (func $msg_reply_data_append (param i64 i64)))
+                (memory i64 1))"#,
+    )
+    .unwrap();
+    assert_matches!(validate_wasm_binary(&wasm, &wasm64_config()), Ok(_));
+}
+
+#[test]
+fn can_reject_wasm64_module_with_32_bit_system_api_imports() {
+    let wasm = wat2wasm(
+        r#"(module
+                (import "ic0" "msg_reply_data_append"
+                    (func $msg_reply_data_append (param i32 i32)))
+                (memory i64 1))"#,
+    )
+    .unwrap();
+    assert_matches!(
+        validate_wasm_binary(&wasm, &wasm64_config()),
+        Err(WasmValidationError::InvalidFunctionSignature(_))
+    );
+}
+
 #[test]
 fn can_validate_module_with_custom_sections() {
     let mut module = wasm_encoder::Module::new();
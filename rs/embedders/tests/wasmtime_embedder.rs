@@ -102,6 +102,55 @@ mod test {
         assert_eq!(instructions_used.get(), expected_instructions);
     }
 
+    #[test]
+    fn correctly_count_instructions_in_wasm64() {
+        let data_size = 1024;
+        let mut config = ic_config::embedders::Config::default();
+        config.feature_flags.wasm64 = ic_config::flag_status::FlagStatus::Enabled;
+        let mut instance = WasmtimeInstanceBuilder::new()
+            .with_config(config)
+            .with_wat(
+                format!(
+                    r#"
+                    (module
+                        (import "ic0" "msg_arg_data_copy"
+                            (func $ic0_msg_arg_data_copy (param i64 i64 i64)))
+                        (memory i64 1)
+                        (func (export "canister_update test_msg_arg_data_copy")
+                            (call $ic0_msg_arg_data_copy
+                                (i64.const 0) (i64.const 0) (i64.const {DATA_SIZE}))
+                        )
+                    )
+                    "#,
+                    DATA_SIZE = data_size
+                )
+                .as_str(),
+            )
+            .with_api_type(ic_system_api::ApiType::init(
+                mock_time(),
+                vec![0; 1024],
+                user_test_id(24).get(),
+            ))
+            .build();
+
+        instance
+            .run(ic_types::methods::FuncRef::Method(
+                ic_types::methods::WasmMethod::Update("test_msg_arg_data_copy".to_string()),
+            ))
+            .unwrap();
+
+        let instruction_counter = instance.instruction_counter();
+        let system_api = &instance.store_data().system_api;
+        let instructions_used = system_api.slice_instructions_executed(instruction_counter);
+
+        // The 64-bit API charges the same as its 32-bit counterpart.
+        let call_msg_arg_data_copy_with_3_const = 4;
+        let expected_instructions = call_msg_arg_data_copy_with_3_const
+            + data_size
+            + system_api_complexity::overhead::MSG_ARG_DATA_COPY.get();
+        assert_eq!(instructions_used.get(), expected_instructions);
+    }
+
     #[test]
     fn instruction_limit_traps() {
         let data_size = 1024;
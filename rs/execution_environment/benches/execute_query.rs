@@ -56,7 +56,7 @@ pub fn execute_query_bench(c: &mut Criterion) {
                 compute_allocation_used: 0,
             };
             let instructions_before = round_limits.instructions;
-            let (_, _, result) = execute_non_replicated_query(
+            let (_, _, result, _) = execute_non_replicated_query(
                 NonReplicatedQueryKind::Pure { caller: sender },
                 WasmMethod::Query("test".to_string()),
                 &[],
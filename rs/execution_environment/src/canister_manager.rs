@@ -13,8 +13,10 @@ use ic_config::flag_status::FlagStatus;
 use ic_cycles_account_manager::CyclesAccountManager;
 use ic_error_types::{ErrorCode, RejectCode, UserError};
 use ic_ic00_types::{
-    CanisterChangeDetails, CanisterChangeOrigin, CanisterInstallMode, CanisterStatusResultV2,
-    CanisterStatusType, InstallCodeArgs, Method as Ic00Method,
+    CanisterChangeDetails, CanisterChangeOrigin, CanisterInstallMode, CanisterSchedulingStats,
+    CanisterSnapshotResponse, CanisterStatusResultV2, CanisterStatusType, ChunkHash,
+    FetchCanisterLogsResponse, InstallCodeArgs, LogVisibility, Method as Ic00Method,
+    StoredChunksReply,
 };
 use ic_interfaces::execution_environment::{
     CanisterOutOfCyclesError, HypervisorError, IngressHistoryWriter, SubnetAvailableMemory,
@@ -23,10 +25,10 @@ use ic_interfaces::messages::CanisterCall;
 use ic_logger::{error, fatal, info, ReplicaLogger};
 use ic_registry_provisional_whitelist::ProvisionalWhitelist;
 use ic_registry_subnet_type::SubnetType;
-use ic_replicated_state::canister_state::system_state::CyclesUseCase;
+use ic_replicated_state::canister_state::system_state::{wasm_chunk_store, CyclesUseCase};
 use ic_replicated_state::{
-    CallOrigin, CanisterState, CanisterStatus, NetworkTopology, ReplicatedState, SchedulerState,
-    SystemState,
+    CallOrigin, CanisterSnapshot, CanisterState, CanisterStatus, NetworkTopology, ReplicatedState,
+    SchedulerState, SnapshotId, SystemState,
 };
 use ic_system_api::ExecutionParameters;
 use ic_types::messages::{MessageId, SignedIngressContent};
@@ -315,6 +317,8 @@ impl CanisterManager {
             | Ok(Ic00Method::SetupInitialDKG)
             | Ok(Ic00Method::SignWithECDSA)
             | Ok(Ic00Method::ComputeInitialEcdsaDealings)
+            | Ok(Ic00Method::SchnorrPublicKey)
+            | Ok(Ic00Method::SignWithSchnorr)
             // "DepositCycles" can be called by anyone however as ingress message
             // cannot carry cycles, it does not make sense to allow them from users.
             | Ok(Ic00Method::DepositCycles)
@@ -342,7 +346,15 @@ impl CanisterManager {
             | Ok(Ic00Method::DeleteCanister) |
             Ok(Ic00Method::UpdateSettings)|
             Ok(Ic00Method::InstallCode) |
-            Ok(Ic00Method::SetController) => {
+            Ok(Ic00Method::InstallChunkedCode) |
+            Ok(Ic00Method::UploadChunk) |
+            Ok(Ic00Method::StoredChunks) |
+            Ok(Ic00Method::ClearChunkStore) |
+            Ok(Ic00Method::SetController) |
+            Ok(Ic00Method::TakeCanisterSnapshot) |
+            Ok(Ic00Method::LoadCanisterSnapshot) |
+            Ok(Ic00Method::ListCanisterSnapshots) |
+            Ok(Ic00Method::DeleteCanisterSnapshot) => {
                 match effective_canister_id {
                     Some(canister_id) => {
                         let canister = state.canister_state(&canister_id).ok_or_else(|| UserError::new(
@@ -367,6 +379,29 @@ impl CanisterManager {
                 }
             },
 
+            // The logs of a canister can be fetched by anyone if its log
+            // visibility is public and only by its controllers otherwise.
+            Ok(Ic00Method::FetchCanisterLogs) => {
+                match effective_canister_id {
+                    Some(canister_id) => {
+                        let canister = state.canister_state(&canister_id).ok_or_else(|| UserError::new(
+                            ErrorCode::CanisterNotFound,
+                            format!("Canister {} not found", canister_id),
+                        ))?;
+                        match canister.system_state.log_visibility {
+                            LogVisibility::Public => Ok(()),
+                            LogVisibility::Controllers => {
+                                validate_controller(canister, &sender.get()).map_err(|err| err.into())
+                            }
+                        }
+                    },
+                    None => Err(UserError::new(
+                        ErrorCode::InvalidManagementPayload,
+                        format!("Failed to decode payload for ic00 method: {}", method_name),
+                    )),
+                }
+            },
+
             Ok(Ic00Method::ProvisionalCreateCanisterWithCycles)
             | Ok(Ic00Method::BitcoinGetSuccessors)
             | Ok(Ic00Method::ProvisionalTopUpCanister) => {
@@ -451,6 +486,14 @@ impl CanisterManager {
         if let Some(freezing_threshold) = settings.freezing_threshold() {
             canister.system_state.freeze_threshold = freezing_threshold;
         }
+        if let Some(log_visibility) = settings.log_visibility() {
+            canister.system_state.log_visibility = log_visibility;
+        }
+        if let Some(wasm_memory_limit) = settings.wasm_memory_limit() {
+            // A limit of 0 removes the limit.
+            canister.system_state.wasm_memory_limit =
+                (wasm_memory_limit.get() > 0).then_some(wasm_memory_limit);
+        }
     }
 
     /// Tries to apply the requested settings on the canister identified by
@@ -948,6 +991,17 @@ impl CanisterManager {
         let compute_allocation = canister.scheduler_state.compute_allocation;
         let memory_allocation = canister.memory_allocation();
         let freeze_threshold = canister.system_state.freeze_threshold;
+        let canister_metrics = &canister.system_state.canister_metrics;
+        let scheduling_stats = CanisterSchedulingStats {
+            rounds_scheduled_as_first: canister_metrics.scheduled_as_first,
+            rounds_executed: canister_metrics.executed,
+            rounds_interrupted: canister_metrics.interruped_during_execution,
+            rounds_skipped_due_to_no_messages: canister_metrics.skipped_round_due_to_no_messages,
+            rounds_skipped_due_to_limits: canister_metrics.skipped_round_due_to_limits,
+            slices_executed: canister_metrics.slices_executed,
+            accumulated_priority: canister.scheduler_state.accumulated_priority.get(),
+            priority_credit: canister.scheduler_state.priority_credit.get(),
+        };
 
         Ok(CanisterStatusResultV2::new(
             canister.status(),
@@ -970,6 +1024,13 @@ impl CanisterManager {
                     subnet_size,
                 )
                 .get(),
+        )
+        .with_scheduling_stats(scheduling_stats)
+        .with_wasm_memory_limit(
+            canister
+                .system_state
+                .wasm_memory_limit
+                .map(|limit| limit.get()),
         ))
     }
 
@@ -1045,6 +1106,9 @@ impl CanisterManager {
 
         // Take out the canister from `ReplicatedState`.
         let canister_to_delete = state.take_canister_state(&canister_id_to_delete).unwrap();
+        state
+            .canister_snapshots
+            .delete_snapshots(canister_id_to_delete);
         // Leftover cycles in the balance are considered `consumed`.
         let leftover_cycles = NominalCycles::from(canister_to_delete.system_state.balance());
         let consumed_cycles_by_canister_to_delete = leftover_cycles
@@ -1280,6 +1344,392 @@ impl CanisterManager {
         Ok(())
     }
 
+    /// Takes a snapshot of the canister's Wasm module, memories, globals and
+    /// certified data.
+    ///
+    /// The snapshot shares its `PageMap`s with the canister, so only pages
+    /// modified afterwards take up extra space on the replica. The full size of
+    /// the snapshot counts towards the memory usage of the canister, though,
+    /// and is charged for like any other canister memory.
+    ///
+    /// If `replace_snapshot` is given, the new snapshot replaces that existing
+    /// snapshot of the canister.
+    pub(crate) fn take_canister_snapshot(
+        &self,
+        sender: PrincipalId,
+        canister_id: CanisterId,
+        replace_snapshot: Option<&[u8]>,
+        state: &mut ReplicatedState,
+        round_limits: &mut RoundLimits,
+        subnet_size: usize,
+    ) -> Result<CanisterSnapshotResponse, CanisterManagerError> {
+        let time = state.time();
+        let canister = self.validate_canister_exists(state, canister_id)?;
+        validate_controller(canister, &sender)?;
+
+        let replaced_snapshot_id = match replace_snapshot {
+            Some(snapshot_id) => Some(validate_snapshot_id(state, canister_id, snapshot_id)?),
+            None => None,
+        };
+        if replaced_snapshot_id.is_none()
+            && state.canister_snapshots.count_snapshots(canister_id) >= MAX_SNAPSHOTS_PER_CANISTER
+        {
+            return Err(CanisterManagerError::CanisterSnapshotLimitExceeded {
+                canister_id,
+                limit: MAX_SNAPSHOTS_PER_CANISTER,
+            });
+        }
+
+        let snapshot = CanisterSnapshot::from_canister(canister, time)
+            .ok_or(CanisterManagerError::CanisterSnapshotEmpty(canister_id))?;
+        let old_size = replaced_snapshot_id
+            .and_then(|snapshot_id| state.canister_snapshots.get(&snapshot_id))
+            .map_or(NumBytes::from(0), |snapshot| snapshot.size());
+        let new_size = snapshot.size();
+        self.reserve_memory(canister, old_size, new_size, round_limits, subnet_size)?;
+
+        if let Some(snapshot_id) = replaced_snapshot_id {
+            state.canister_snapshots.remove(&snapshot_id);
+        }
+        let snapshot_id = state.metadata.generate_snapshot_id(canister_id);
+        state
+            .canister_snapshots
+            .push(snapshot_id, Arc::new(snapshot));
+        self.update_snapshots_memory_usage(state, canister_id);
+
+        Ok(CanisterSnapshotResponse::new(
+            snapshot_id.to_vec(),
+            time.as_nanos_since_unix_epoch(),
+            new_size.get(),
+        ))
+    }
+
+    /// Replaces the canister's Wasm module, memories, globals and certified
+    /// data with the ones from the given snapshot. The snapshot is kept.
+    pub(crate) fn load_canister_snapshot(
+        &self,
+        origin: CanisterChangeOrigin,
+        canister_id: CanisterId,
+        snapshot_id: &[u8],
+        state: &mut ReplicatedState,
+        round_limits: &mut RoundLimits,
+        subnet_size: usize,
+    ) -> Result<(), CanisterManagerError> {
+        let sender = origin.origin();
+        let time = state.time();
+        let canister = self.validate_canister_exists(state, canister_id)?;
+        validate_controller(canister, &sender)?;
+
+        let snapshot_id = validate_snapshot_id(state, canister_id, snapshot_id)?;
+        let snapshot = Arc::clone(state.canister_snapshots.get(&snapshot_id).unwrap());
+        let old_size = canister
+            .execution_state
+            .as_ref()
+            .map_or(NumBytes::from(0), |execution_state| {
+                execution_state.memory_usage()
+            });
+        let new_size = snapshot.execution_state().memory_usage();
+        self.reserve_memory(canister, old_size, new_size, round_limits, subnet_size)?;
+
+        // The memories are restored as fresh copies, so all of their pages
+        // end up in the heap delta.
+        state.metadata.heap_delta_estimate += new_size;
+        let canister = state.canister_state_mut(&canister_id).unwrap();
+        canister.execution_state =
+            Some(snapshot.restore_execution_state(self.hypervisor.fd_factory()));
+        canister.system_state.certified_data = snapshot.certified_data().to_vec();
+        canister.system_state.canister_version += 1;
+        canister.system_state.add_canister_change(
+            time,
+            origin,
+            CanisterChangeDetails::load_snapshot(
+                snapshot.canister_version(),
+                snapshot_id.to_vec(),
+                snapshot.taken_at_timestamp().as_nanos_since_unix_epoch(),
+            ),
+        );
+        Ok(())
+    }
+
+    /// Lists the snapshots of the canister, in the order they were taken.
+    pub(crate) fn list_canister_snapshots(
+        &self,
+        sender: PrincipalId,
+        canister_id: CanisterId,
+        state: &ReplicatedState,
+    ) -> Result<Vec<CanisterSnapshotResponse>, CanisterManagerError> {
+        let canister = self.validate_canister_exists(state, canister_id)?;
+        validate_controller(canister, &sender)?;
+
+        Ok(state
+            .canister_snapshots
+            .list_snapshots(canister_id)
+            .map(|(snapshot_id, snapshot)| {
+                CanisterSnapshotResponse::new(
+                    snapshot_id.to_vec(),
+                    snapshot.taken_at_timestamp().as_nanos_since_unix_epoch(),
+                    snapshot.size().get(),
+                )
+            })
+            .collect())
+    }
+
+    /// Returns the log records of the canister, provided that its log
+    /// visibility allows the sender to see them.
+    pub(crate) fn fetch_canister_logs(
+        &self,
+        sender: PrincipalId,
+        canister_id: CanisterId,
+        state: &ReplicatedState,
+    ) -> Result<FetchCanisterLogsResponse, CanisterManagerError> {
+        let canister = self.validate_canister_exists(state, canister_id)?;
+        match canister.system_state.log_visibility {
+            LogVisibility::Public => {}
+            LogVisibility::Controllers => validate_controller(canister, &sender)?,
+        }
+
+        Ok(FetchCanisterLogsResponse {
+            canister_log_records: canister
+                .system_state
+                .canister_log
+                .records()
+                .iter()
+                .cloned()
+                .collect(),
+        })
+    }
+
+    /// Deletes the given snapshot of the canister and releases its memory.
+    pub(crate) fn delete_canister_snapshot(
+        &self,
+        sender: PrincipalId,
+        canister_id: CanisterId,
+        snapshot_id: &[u8],
+        state: &mut ReplicatedState,
+        round_limits: &mut RoundLimits,
+    ) -> Result<(), CanisterManagerError> {
+        let canister = self.validate_canister_exists(state, canister_id)?;
+        validate_controller(canister, &sender)?;
+
+        let snapshot_id = validate_snapshot_id(state, canister_id, snapshot_id)?;
+        let memory_allocation = canister.memory_allocation();
+        let snapshot = state.canister_snapshots.remove(&snapshot_id).unwrap();
+        if let MemoryAllocation::BestEffort = memory_allocation {
+            round_limits.subnet_available_memory.increment(
+                snapshot.size(),
+                NumBytes::from(0),
+                NumBytes::from(0),
+            );
+        }
+        self.update_snapshots_memory_usage(state, canister_id);
+        Ok(())
+    }
+
+    /// Stores the given chunk in the canister's Wasm chunk store and returns
+    /// its hash. Uploading an already stored chunk is a no-op.
+    pub(crate) fn upload_chunk(
+        &self,
+        sender: PrincipalId,
+        canister_id: CanisterId,
+        chunk: &[u8],
+        state: &mut ReplicatedState,
+        round_limits: &mut RoundLimits,
+        subnet_size: usize,
+    ) -> Result<ChunkHash, CanisterManagerError> {
+        let canister = self.validate_canister_exists(state, canister_id)?;
+        validate_controller(canister, &sender)?;
+
+        let wasm_chunk_store = &canister.system_state.wasm_chunk_store;
+        wasm_chunk_store
+            .can_insert_chunk(wasm_chunk_store::DEFAULT_MAX_SIZE, chunk)
+            .map_err(|message| CanisterManagerError::WasmChunkStoreError { message })?;
+        let old_size = wasm_chunk_store.memory_usage();
+        let new_size = if wasm_chunk_store.contains_chunk(chunk) {
+            old_size
+        } else {
+            old_size + NumBytes::from(wasm_chunk_store::CHUNK_SIZE)
+        };
+        self.reserve_memory(canister, old_size, new_size, round_limits, subnet_size)?;
+
+        state.metadata.heap_delta_estimate += new_size - old_size;
+        let canister = state.canister_state_mut(&canister_id).unwrap();
+        let hash = canister
+            .system_state
+            .wasm_chunk_store
+            .insert_chunk(chunk, self.hypervisor.fd_factory());
+        Ok(ChunkHash {
+            hash: hash.to_vec(),
+        })
+    }
+
+    /// Returns the hashes of all chunks in the canister's Wasm chunk store.
+    pub(crate) fn stored_chunks(
+        &self,
+        sender: PrincipalId,
+        canister_id: CanisterId,
+        state: &ReplicatedState,
+    ) -> Result<StoredChunksReply, CanisterManagerError> {
+        let canister = self.validate_canister_exists(state, canister_id)?;
+        validate_controller(canister, &sender)?;
+
+        Ok(StoredChunksReply(
+            canister
+                .system_state
+                .wasm_chunk_store
+                .keys()
+                .map(|hash| ChunkHash {
+                    hash: hash.to_vec(),
+                })
+                .collect(),
+        ))
+    }
+
+    /// Removes all chunks from the canister's Wasm chunk store and releases
+    /// their memory.
+    pub(crate) fn clear_chunk_store(
+        &self,
+        sender: PrincipalId,
+        canister_id: CanisterId,
+        state: &mut ReplicatedState,
+        round_limits: &mut RoundLimits,
+    ) -> Result<(), CanisterManagerError> {
+        let canister = self.validate_canister_exists(state, canister_id)?;
+        validate_controller(canister, &sender)?;
+
+        if let MemoryAllocation::BestEffort = canister.memory_allocation() {
+            round_limits.subnet_available_memory.increment(
+                canister.system_state.wasm_chunk_store.memory_usage(),
+                NumBytes::from(0),
+                NumBytes::from(0),
+            );
+        }
+        let canister = state.canister_state_mut(&canister_id).unwrap();
+        canister.system_state.wasm_chunk_store.clear();
+        Ok(())
+    }
+
+    /// Assembles the Wasm module to be installed by `install_chunked_code`
+    /// from the given chunks in the Wasm chunk store of `storage_canister_id`.
+    ///
+    /// The sender must control the storage canister and the assembled module
+    /// must have the given hash.
+    pub(crate) fn assemble_chunked_wasm(
+        &self,
+        sender: PrincipalId,
+        storage_canister_id: CanisterId,
+        chunk_hashes: &[Vec<u8>],
+        wasm_module_hash: &[u8],
+        state: &ReplicatedState,
+    ) -> Result<CanisterModule, CanisterManagerError> {
+        let canister = self.validate_canister_exists(state, storage_canister_id)?;
+        validate_controller(canister, &sender)?;
+
+        let wasm_chunk_store = &canister.system_state.wasm_chunk_store;
+        let mut wasm_module = Vec::new();
+        for hash in chunk_hashes {
+            let chunk = <[u8; 32]>::try_from(hash.as_slice())
+                .ok()
+                .and_then(|hash| wasm_chunk_store.get_chunk_data(&hash))
+                .ok_or_else(|| CanisterManagerError::WasmChunkStoreError {
+                    message: format!("Chunk with hash {:?} not found in the store.", hash),
+                })?;
+            wasm_module.extend_from_slice(&chunk);
+        }
+
+        let wasm_module = CanisterModule::new(wasm_module);
+        if wasm_module.module_hash()[..] != wasm_module_hash[..] {
+            return Err(CanisterManagerError::WasmChunkStoreError {
+                message: format!(
+                    "Wasm module hash {:?} does not match the given hash {:?}.",
+                    wasm_module.module_hash(),
+                    wasm_module_hash
+                ),
+            });
+        }
+        Ok(wasm_module)
+    }
+
+    /// Checks that the canister can afford to grow from `old_size` to
+    /// `new_size` bytes of memory and updates the subnet available memory
+    /// accordingly.
+    fn reserve_memory(
+        &self,
+        canister: &CanisterState,
+        old_size: NumBytes,
+        new_size: NumBytes,
+        round_limits: &mut RoundLimits,
+        subnet_size: usize,
+    ) -> Result<(), CanisterManagerError> {
+        let memory_allocation = canister.memory_allocation();
+        if new_size <= old_size {
+            if let MemoryAllocation::BestEffort = memory_allocation {
+                round_limits.subnet_available_memory.increment(
+                    old_size - new_size,
+                    NumBytes::from(0),
+                    NumBytes::from(0),
+                );
+            }
+            return Ok(());
+        }
+
+        let bytes = new_size - old_size;
+        let new_memory_usage = canister.memory_usage() + bytes;
+        if let MemoryAllocation::Reserved(reserved_bytes) = memory_allocation {
+            if new_memory_usage > reserved_bytes {
+                return Err(CanisterManagerError::NotEnoughMemoryAllocationGiven {
+                    memory_allocation_given: memory_allocation,
+                    memory_usage_needed: new_memory_usage,
+                });
+            }
+        }
+
+        let threshold = self.cycles_account_manager.freeze_threshold_cycles(
+            canister.system_state.freeze_threshold,
+            memory_allocation,
+            new_memory_usage,
+            canister.compute_allocation(),
+            subnet_size,
+        );
+        if canister.system_state.balance() < threshold {
+            return Err(CanisterManagerError::InsufficientCyclesInMemoryGrow {
+                bytes,
+                available: canister.system_state.balance(),
+                threshold,
+            });
+        }
+
+        // Memory of canisters with a reserved allocation has already been
+        // accounted for.
+        if let MemoryAllocation::BestEffort = memory_allocation {
+            round_limits
+                .subnet_available_memory
+                .try_decrement(bytes, NumBytes::from(0), NumBytes::from(0))
+                .map_err(
+                    |_| CanisterManagerError::SubnetMemoryCapacityOverSubscribed {
+                        requested: bytes,
+                        available: NumBytes::from(
+                            round_limits
+                                .subnet_available_memory
+                                .get_execution_memory()
+                                .max(0) as u64,
+                        ),
+                    },
+                )?;
+        }
+        Ok(())
+    }
+
+    fn update_snapshots_memory_usage(&self, state: &mut ReplicatedState, canister_id: CanisterId) {
+        let snapshots_memory_usage = state
+            .canister_snapshots
+            .memory_taken_by_canister(canister_id);
+        if let Some(canister) = state.canister_state_mut(&canister_id) {
+            canister
+                .system_state
+                .set_snapshots_memory_usage(snapshots_memory_usage);
+        }
+    }
+
     fn validate_canister_is_stopped(
         &self,
         canister: &CanisterState,
@@ -1328,6 +1778,29 @@ impl CanisterManager {
     }
 }
 
+/// Maximum number of snapshots a canister can have at the same time.
+pub(crate) const MAX_SNAPSHOTS_PER_CANISTER: usize = 1;
+
+/// Parses `snapshot_id` and checks that it refers to an existing snapshot of
+/// the given canister.
+fn validate_snapshot_id(
+    state: &ReplicatedState,
+    canister_id: CanisterId,
+    snapshot_id: &[u8],
+) -> Result<SnapshotId, CanisterManagerError> {
+    match SnapshotId::try_from(snapshot_id) {
+        Ok(id)
+            if id.canister_id() == canister_id && state.canister_snapshots.get(&id).is_some() =>
+        {
+            Ok(id)
+        }
+        _ => Err(CanisterManagerError::CanisterSnapshotNotFound {
+            canister_id,
+            snapshot_id: snapshot_id.to_vec(),
+        }),
+    }
+}
+
 #[derive(Debug, PartialEq, Eq)]
 pub(crate) enum CanisterManagerError {
     CanisterInvalidController {
@@ -1393,6 +1866,18 @@ pub(crate) enum CanisterManagerError {
         available: Cycles,
         threshold: Cycles,
     },
+    CanisterSnapshotNotFound {
+        canister_id: CanisterId,
+        snapshot_id: Vec<u8>,
+    },
+    CanisterSnapshotLimitExceeded {
+        canister_id: CanisterId,
+        limit: usize,
+    },
+    CanisterSnapshotEmpty(CanisterId),
+    WasmChunkStoreError {
+        message: String,
+    },
 }
 
 impl From<CanisterManagerError> for UserError {
@@ -1592,6 +2077,41 @@ impl From<CanisterManagerError> for UserError {
                          threshold - available)
                 )
             }
+            CanisterSnapshotNotFound { canister_id, snapshot_id } => {
+                Self::new(
+                    ErrorCode::CanisterSnapshotNotFound,
+                    format!(
+                        "Could not find the snapshot ID {} for canister {}.",
+                        hex::encode(snapshot_id),
+                        canister_id,
+                    )
+                )
+            }
+            CanisterSnapshotLimitExceeded { canister_id, limit } => {
+                Self::new(
+                    ErrorCode::CanisterRejectedMessage,
+                    format!(
+                        "Canister {} has reached the maximum number of {} snapshots. \
+                         Specify a snapshot to replace or delete an existing snapshot first.",
+                        canister_id, limit,
+                    )
+                )
+            }
+            CanisterSnapshotEmpty(canister_id) => {
+                Self::new(
+                    ErrorCode::CanisterWasmModuleNotFound,
+                    format!(
+                        "Cannot take a snapshot of canister {} because it has no Wasm module.",
+                        canister_id,
+                    )
+                )
+            }
+            WasmChunkStoreError { message } => {
+                Self::new(
+                    ErrorCode::CanisterContractViolation,
+                    format!("Error from Wasm chunk store: {}", message)
+                )
+            }
         }
     }
 }
@@ -1673,7 +2193,7 @@ pub fn uninstall_canister(
                         },
                     }));
                 }
-                CallOrigin::CanisterUpdate(caller_canister_id, callback_id) => {
+                CallOrigin::CanisterUpdate(caller_canister_id, callback_id, deadline) => {
                     rejects.push(Response::Canister(CanisterResponse {
                         originator: *caller_canister_id,
                         respondent: canister_id,
@@ -1683,6 +2203,7 @@ pub fn uninstall_canister(
                             code: RejectCode::CanisterReject,
                             message: String::from("Canister has been uninstalled."),
                         }),
+                        deadline: *deadline,
                     }));
                 }
                 CallOrigin::CanisterQuery(_, _) | CallOrigin::Query(_) => fatal!(
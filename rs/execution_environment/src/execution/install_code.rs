@@ -532,6 +532,18 @@ impl InstallCodeHelper {
             .instruction_limits
             .update(output.num_instructions_left);
 
+        // Log records are only retained if the whole `install_code` succeeds,
+        // because otherwise the canister is reverted to its original state.
+        self.canister
+            .system_state
+            .canister_log
+            .append_delta(output.canister_log);
+        if let Some(execution_state) = self.canister.execution_state.as_mut() {
+            execution_state
+                .instruction_profile
+                .merge(output.instruction_profile);
+        }
+
         match output.wasm_result {
             Ok(None) => {}
             Ok(Some(_response)) => {
@@ -9,13 +9,15 @@ use crate::execution::common::{validate_canister, validate_method};
 use crate::execution_environment::RoundLimits;
 use crate::{Hypervisor, NonReplicatedQueryKind};
 use ic_error_types::UserError;
+use ic_interfaces::execution_environment::QueryDependencies;
 use ic_replicated_state::{CallOrigin, CanisterState, NetworkTopology};
 use ic_system_api::{ApiType, ExecutionParameters};
 use ic_types::ingress::WasmResult;
 use ic_types::methods::{FuncRef, WasmMethod};
 use ic_types::{Cycles, NumInstructions, Time};
 
-// Execute non replicated query.
+// Execute non replicated query. Besides the result, returns the inputs the
+// execution depended on if they could be determined.
 #[allow(clippy::too_many_arguments)]
 pub fn execute_non_replicated_query(
     query_kind: NonReplicatedQueryKind,
@@ -32,6 +34,7 @@ pub fn execute_non_replicated_query(
     CanisterState,
     NumInstructions,
     Result<Option<WasmResult>, UserError>,
+    Option<QueryDependencies>,
 ) {
     // Validate that the canister is running.
     if let Err(err) = validate_canister(&canister) {
@@ -39,6 +42,7 @@ pub fn execute_non_replicated_query(
             canister,
             execution_parameters.instruction_limits.message(),
             Err(err),
+            None,
         );
     }
 
@@ -51,6 +55,7 @@ pub fn execute_non_replicated_query(
             canister,
             execution_parameters.instruction_limits.message(),
             Err(err.into_user_error(&canister_id)),
+            None,
         );
     }
 
@@ -111,5 +116,10 @@ pub fn execute_non_replicated_query(
     let result = output
         .wasm_result
         .map_err(|err| err.into_user_error(&canister.canister_id()));
-    (canister, output.num_instructions_left, result)
+    (
+        canister,
+        output.num_instructions_left,
+        result,
+        output.instance_stats.query_dependencies,
+    )
 }
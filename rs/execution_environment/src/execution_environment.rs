@@ -22,15 +22,19 @@ use ic_config::execution_environment::Config as ExecutionConfig;
 use ic_config::flag_status::FlagStatus;
 use ic_constants::{LOG_CANISTER_OPERATION_CYCLES_THRESHOLD, SMALL_APP_SUBNET_MAX_SIZE};
 use ic_crypto_tecdsa::derive_tecdsa_public_key;
+use ic_crypto_tschnorr::derive_tschnorr_public_key;
 use ic_cycles_account_manager::{CyclesAccountManager, IngressInductionCost};
 use ic_error_types::{ErrorCode, RejectCode, UserError};
 use ic_ic00_types::{
     CanisterChangeOrigin, CanisterHttpRequestArgs, CanisterIdRecord, CanisterInfoRequest,
     CanisterInfoResponse, CanisterSettingsArgs, ComputeInitialEcdsaDealingsArgs,
-    CreateCanisterArgs, ECDSAPublicKeyArgs, ECDSAPublicKeyResponse, EcdsaKeyId, EmptyBlob,
-    InstallCodeArgs, Method as Ic00Method, Payload as Ic00Payload,
-    ProvisionalCreateCanisterWithCyclesArgs, ProvisionalTopUpCanisterArgs, SetControllerArgs,
-    SetupInitialDKGArgs, SignWithECDSAArgs, UninstallCodeArgs, UpdateSettingsArgs, IC_00,
+    CreateCanisterArgs, DeleteCanisterSnapshotArgs, ECDSAPublicKeyArgs, ECDSAPublicKeyResponse,
+    EcdsaKeyId, EmptyBlob, FetchCanisterLogsRequest, InstallChunkedCodeArgs, InstallCodeArgs,
+    LoadCanisterSnapshotArgs, Method as Ic00Method, Payload as Ic00Payload,
+    ProvisionalCreateCanisterWithCyclesArgs, ProvisionalTopUpCanisterArgs, SchnorrAlgorithm,
+    SchnorrKeyId, SchnorrPublicKeyArgs, SchnorrPublicKeyResponse, SetControllerArgs,
+    SetupInitialDKGArgs, SignWithECDSAArgs, SignWithSchnorrArgs, TakeCanisterSnapshotArgs,
+    UninstallCodeArgs, UpdateSettingsArgs, UploadChunkArgs, IC_00,
 };
 use ic_interfaces::{
     execution_environment::{
@@ -53,14 +57,16 @@ use ic_replicated_state::{
 };
 use ic_replicated_state::{
     metadata_state::subnet_call_context_manager::{
-        EcdsaDealingsContext, SetupInitialDkgContext, SignWithEcdsaContext,
+        EcdsaDealingsContext, SetupInitialDkgContext, SignWithEcdsaContext, SignWithSchnorrContext,
     },
     CanisterState, NetworkTopology, ReplicatedState,
 };
 use ic_system_api::{ExecutionParameters, InstructionLimits};
 use ic_types::{
     canister_http::CanisterHttpRequestContext,
-    crypto::canister_threshold_sig::{ExtendedDerivationPath, MasterEcdsaPublicKey},
+    crypto::canister_threshold_sig::{
+        ExtendedDerivationPath, MasterEcdsaPublicKey, MasterSchnorrPublicKey,
+    },
     crypto::threshold_sig::ni_dkg::NiDkgTargetId,
     ingress::{IngressState, IngressStatus, WasmResult},
     messages::{
@@ -69,10 +75,13 @@ use ic_types::{
     },
     methods::SystemMethod,
     nominal_cycles::NominalCycles,
-    CanisterId, CpuComplexity, Cycles, LongExecutionMode, NumBytes, NumInstructions, SubnetId,
-    Time,
+    CanisterId, CpuComplexity, Cycles, LongExecutionMode, NumBytes, NumInstructions,
+    QueryAllocation, SubnetId, Time,
+};
+use ic_types::{
+    messages::{MessageId, NO_DEADLINE},
+    methods::WasmMethod,
 };
-use ic_types::{messages::MessageId, methods::WasmMethod};
 use ic_wasm_types::WasmHash;
 use phantom_newtype::AmountOf;
 use prometheus::IntCounter;
@@ -355,6 +364,7 @@ impl ExecutionEnvironment {
         instruction_limits: InstructionLimits,
         rng: &mut dyn RngCore,
         ecdsa_subnet_public_keys: &BTreeMap<EcdsaKeyId, MasterEcdsaPublicKey>,
+        schnorr_subnet_public_keys: &BTreeMap<SchnorrKeyId, MasterSchnorrPublicKey>,
         registry_settings: &RegistryExecutionSettings,
         round_limits: &mut RoundLimits,
     ) -> (ReplicatedState, Option<NumInstructions>) {
@@ -395,6 +405,7 @@ impl ExecutionEnvironment {
                                 originator_reply_callback: request.sender_reply_callback,
                                 refund: request.payment,
                                 response_payload: response.response_payload.clone(),
+                                deadline: request.deadline,
                             }
                             .into(),
                         );
@@ -422,7 +433,7 @@ impl ExecutionEnvironment {
         }
 
         let result = match method {
-            Ok(Ic00Method::InstallCode) => {
+            Ok(Ic00Method::InstallCode) | Ok(Ic00Method::InstallChunkedCode) => {
                 // Tail call is needed for deterministic time slicing here to
                 // properly handle the case of a paused execution.
                 return self.execute_install_code(
@@ -458,6 +469,7 @@ impl ExecutionEnvironment {
                                         message: reject_message,
                                     },
                                 ),
+                                deadline: request.deadline,
                             }
                             .into(),
                         );
@@ -497,6 +509,36 @@ impl ExecutionEnvironment {
                 }
             },
 
+            Ok(Ic00Method::SignWithSchnorr) => match &msg {
+                CanisterCall::Request(request) => match SignWithSchnorrArgs::decode(payload) {
+                    Err(err) => Some((Err(err), msg.take_cycles())),
+                    Ok(args) => {
+                        match get_master_schnorr_public_key(
+                            schnorr_subnet_public_keys,
+                            self.own_subnet_id,
+                            &args.key_id,
+                        ) {
+                            Err(err) => Some((Err(err), msg.take_cycles())),
+                            Ok(_) => self
+                                .sign_with_schnorr(
+                                    (**request).clone(),
+                                    args.message,
+                                    args.derivation_path.get(),
+                                    args.key_id,
+                                    registry_settings.max_schnorr_queue_size,
+                                    &mut state,
+                                    rng,
+                                    registry_settings.subnet_size,
+                                )
+                                .map_or_else(|err| Some((Err(err), msg.take_cycles())), |()| None),
+                        }
+                    }
+                },
+                CanisterCall::Ingress(_) => {
+                    self.reject_unexpected_ingress(Ic00Method::SignWithSchnorr)
+                }
+            },
+
             Ok(Ic00Method::CreateCanister) => {
                 match &mut msg {
                     CanisterCall::Ingress(_) => {
@@ -711,6 +753,134 @@ impl ExecutionEnvironment {
                 Some((res, msg.take_cycles()))
             }
 
+            Ok(Ic00Method::TakeCanisterSnapshot) => {
+                let res = match TakeCanisterSnapshotArgs::decode(payload) {
+                    Err(err) => Err(err),
+                    Ok(args) => self
+                        .canister_manager
+                        .take_canister_snapshot(
+                            *msg.sender(),
+                            args.get_canister_id(),
+                            args.replace_snapshot(),
+                            &mut state,
+                            round_limits,
+                            registry_settings.subnet_size,
+                        )
+                        .map(|response| response.encode())
+                        .map_err(|err| err.into()),
+                };
+                Some((res, msg.take_cycles()))
+            }
+
+            Ok(Ic00Method::LoadCanisterSnapshot) => {
+                let res = match LoadCanisterSnapshotArgs::decode(payload) {
+                    Err(err) => Err(err),
+                    Ok(args) => self
+                        .canister_manager
+                        .load_canister_snapshot(
+                            msg.canister_change_origin(args.get_sender_canister_version()),
+                            args.get_canister_id(),
+                            args.snapshot_id(),
+                            &mut state,
+                            round_limits,
+                            registry_settings.subnet_size,
+                        )
+                        .map(|()| EmptyBlob.encode())
+                        .map_err(|err| err.into()),
+                };
+                Some((res, msg.take_cycles()))
+            }
+
+            Ok(Ic00Method::ListCanisterSnapshots) => {
+                let res = match CanisterIdRecord::decode(payload) {
+                    Err(err) => Err(err),
+                    Ok(args) => self
+                        .canister_manager
+                        .list_canister_snapshots(*msg.sender(), args.get_canister_id(), &state)
+                        .map(|snapshots| Encode!(&snapshots).unwrap())
+                        .map_err(|err| err.into()),
+                };
+                Some((res, msg.take_cycles()))
+            }
+
+            Ok(Ic00Method::DeleteCanisterSnapshot) => {
+                let res = match DeleteCanisterSnapshotArgs::decode(payload) {
+                    Err(err) => Err(err),
+                    Ok(args) => self
+                        .canister_manager
+                        .delete_canister_snapshot(
+                            *msg.sender(),
+                            args.get_canister_id(),
+                            args.snapshot_id(),
+                            &mut state,
+                            round_limits,
+                        )
+                        .map(|()| EmptyBlob.encode())
+                        .map_err(|err| err.into()),
+                };
+                Some((res, msg.take_cycles()))
+            }
+
+            Ok(Ic00Method::UploadChunk) => {
+                let res = match UploadChunkArgs::decode(payload) {
+                    Err(err) => Err(err),
+                    Ok(args) => self
+                        .canister_manager
+                        .upload_chunk(
+                            *msg.sender(),
+                            args.get_canister_id(),
+                            &args.chunk,
+                            &mut state,
+                            round_limits,
+                            registry_settings.subnet_size,
+                        )
+                        .map(|reply| reply.encode())
+                        .map_err(|err| err.into()),
+                };
+                Some((res, msg.take_cycles()))
+            }
+
+            Ok(Ic00Method::StoredChunks) => {
+                let res = match CanisterIdRecord::decode(payload) {
+                    Err(err) => Err(err),
+                    Ok(args) => self
+                        .canister_manager
+                        .stored_chunks(*msg.sender(), args.get_canister_id(), &state)
+                        .map(|reply| reply.encode())
+                        .map_err(|err| err.into()),
+                };
+                Some((res, msg.take_cycles()))
+            }
+
+            Ok(Ic00Method::ClearChunkStore) => {
+                let res = match CanisterIdRecord::decode(payload) {
+                    Err(err) => Err(err),
+                    Ok(args) => self
+                        .canister_manager
+                        .clear_chunk_store(
+                            *msg.sender(),
+                            args.get_canister_id(),
+                            &mut state,
+                            round_limits,
+                        )
+                        .map(|()| EmptyBlob.encode())
+                        .map_err(|err| err.into()),
+                };
+                Some((res, msg.take_cycles()))
+            }
+
+            Ok(Ic00Method::FetchCanisterLogs) => {
+                let res = match FetchCanisterLogsRequest::decode(payload) {
+                    Err(err) => Err(err),
+                    Ok(args) => self
+                        .canister_manager
+                        .fetch_canister_logs(*msg.sender(), args.get_canister_id(), &state)
+                        .map(|response| response.encode())
+                        .map_err(|err| err.into()),
+                };
+                Some((res, msg.take_cycles()))
+            }
+
             Ok(Ic00Method::RawRand) => match &msg {
                 CanisterCall::Ingress(_) => self.reject_unexpected_ingress(Ic00Method::RawRand),
                 CanisterCall::Request(_) => {
@@ -847,6 +1017,42 @@ impl ExecutionEnvironment {
                 }
             }
 
+            Ok(Ic00Method::SchnorrPublicKey) => {
+                let cycles = msg.take_cycles();
+                match &msg {
+                    CanisterCall::Request(request) => {
+                        let res = match SchnorrPublicKeyArgs::decode(request.method_payload()) {
+                            Err(err) => Some(Err(err)),
+                            Ok(args) => match get_master_schnorr_public_key(
+                                schnorr_subnet_public_keys,
+                                self.own_subnet_id,
+                                &args.key_id,
+                            ) {
+                                Err(err) => Some(Err(err)),
+                                Ok(pubkey) => {
+                                    let canister_id = match args.canister_id {
+                                        Some(id) => id.into(),
+                                        None => *msg.sender(),
+                                    };
+                                    Some(
+                                        self.get_schnorr_public_key(
+                                            pubkey,
+                                            canister_id,
+                                            args.derivation_path.get(),
+                                        )
+                                        .map(|res| res.encode()),
+                                    )
+                                }
+                            },
+                        };
+                        res.map(|res| (res, cycles))
+                    }
+                    CanisterCall::Ingress(_) => {
+                        self.reject_unexpected_ingress(Ic00Method::SchnorrPublicKey)
+                    }
+                }
+            }
+
             Ok(Ic00Method::ComputeInitialEcdsaDealings) => {
                 let cycles = msg.take_cycles();
                 match &msg {
@@ -993,9 +1199,10 @@ impl ExecutionEnvironment {
                 // responded to (which currently happens in the scheduler).
                 //
                 // This scenario also happens in the case of
-                // Ic00Method::SetupInitialDKG, Ic00Method::HttpRequest, and
-                // Ic00Method::SignWithECDSA. The request is saved and the
-                // response from consensus is handled separately.
+                // Ic00Method::SetupInitialDKG, Ic00Method::HttpRequest,
+                // Ic00Method::SignWithECDSA, and Ic00Method::SignWithSchnorr.
+                // The request is saved and the response from consensus is
+                // handled separately.
                 state
             }
         };
@@ -1621,6 +1828,7 @@ impl ExecutionEnvironment {
                     originator_reply_callback: req.sender_reply_callback,
                     refund,
                     response_payload: payload,
+                    deadline: req.deadline,
                 };
 
                 state.push_subnet_output_response(response.into());
@@ -1702,6 +1910,7 @@ impl ExecutionEnvironment {
                             code: RejectCode::CanisterReject,
                             message: format!("Canister {}'s stop request cancelled", canister_id),
                         }),
+                        deadline: NO_DEADLINE,
                     };
                     state.push_subnet_output_response(response.into());
                 }
@@ -1841,6 +2050,111 @@ impl ExecutionEnvironment {
         Ok(())
     }
 
+    fn get_schnorr_public_key(
+        &self,
+        subnet_public_key: &MasterSchnorrPublicKey,
+        principal_id: PrincipalId,
+        derivation_path: Vec<Vec<u8>>,
+    ) -> Result<SchnorrPublicKeyResponse, UserError> {
+        let _ = CanisterId::new(principal_id).map_err(|err| {
+            UserError::new(
+                ErrorCode::CanisterContractViolation,
+                format!("Not a canister id: {}", err),
+            )
+        })?;
+        let path = ExtendedDerivationPath {
+            caller: principal_id,
+            derivation_path,
+        };
+        derive_tschnorr_public_key(subnet_public_key, &path)
+            .map_err(|err| UserError::new(ErrorCode::CanisterRejectedMessage, format!("{}", err)))
+            .map(|res| SchnorrPublicKeyResponse {
+                public_key: res.public_key,
+                chain_code: res.chain_key,
+            })
+    }
+
+    #[allow(clippy::too_many_arguments)]
+    fn sign_with_schnorr(
+        &self,
+        mut request: Request,
+        message: Vec<u8>,
+        derivation_path: Vec<Vec<u8>>,
+        key_id: SchnorrKeyId,
+        max_queue_size: u32,
+        state: &mut ReplicatedState,
+        rng: &mut dyn RngCore,
+        subnet_size: usize,
+    ) -> Result<(), UserError> {
+        // BIP340 is only defined for 32-byte messages, which are usually the
+        // hash of the actual message.
+        if key_id.algorithm == SchnorrAlgorithm::Bip340Secp256k1 && message.len() != 32 {
+            return Err(UserError::new(
+                ErrorCode::CanisterRejectedMessage,
+                format!(
+                    "sign_with_schnorr request with a BIP340 key requires a 32-byte message, but the message has {} bytes.",
+                    message.len()
+                ),
+            ));
+        }
+
+        // If the request isn't from the NNS, then we need to charge for it.
+        // Consensus will return any remaining cycles.
+        let source_subnet = state
+            .metadata
+            .network_topology
+            .routing_table
+            .route(request.sender.get());
+        if source_subnet != Some(state.metadata.network_topology.nns_subnet_id) {
+            let signature_fee = self
+                .cycles_account_manager
+                .schnorr_signature_fee(subnet_size);
+            if request.payment < signature_fee {
+                return Err(UserError::new(
+                    ErrorCode::CanisterRejectedMessage,
+                    format!(
+                        "sign_with_schnorr request sent with {} cycles, but {} cycles are required.",
+                        request.payment, signature_fee
+                    ),
+                ));
+            } else {
+                request.payment -= signature_fee;
+                state
+                    .metadata
+                    .subnet_metrics
+                    .observe_consumed_cycles_with_use_case(
+                        CyclesUseCase::SchnorrOutcalls,
+                        NominalCycles::from(signature_fee),
+                    );
+            }
+        }
+
+        let mut pseudo_random_id = [0u8; 32];
+        rng.fill_bytes(&mut pseudo_random_id);
+
+        info!(
+            self.log,
+            "Assigned the pseudo_random_id {:?} to the new sign_with_schnorr request from {:?}",
+            pseudo_random_id,
+            request.sender()
+        );
+        state
+            .metadata
+            .subnet_call_context_manager
+            .push_sign_with_schnorr_request(
+                SignWithSchnorrContext {
+                    request,
+                    key_id,
+                    message,
+                    derivation_path,
+                    pseudo_random_id,
+                    batch_time: state.metadata.batch_time,
+                },
+                max_queue_size,
+            )?;
+        Ok(())
+    }
+
     fn compute_initial_ecdsa_dealings(
         &self,
         state: &mut ReplicatedState,
@@ -1867,7 +2181,8 @@ impl ExecutionEnvironment {
     /// exceeds the given slice limit.
     ///
     /// Precondition:
-    /// - The given message is an `install_code` message.
+    /// - The given message is an `install_code` or `install_chunked_code`
+    ///   message.
     /// - The canister does not have any paused execution in its task queue.
     ///
     /// Postcondition:
@@ -1887,14 +2202,44 @@ impl ExecutionEnvironment {
         // A helper function to make error handling more compact using `?`.
         fn decode_input_and_take_canister(
             msg: &CanisterCall,
+            canister_manager: &CanisterManager,
             state: &mut ReplicatedState,
         ) -> Result<(InstallCodeContext, CanisterState), UserError> {
             let payload = msg.method_payload();
-            let args = InstallCodeArgs::decode(payload)?;
-            let install_context = InstallCodeContext::try_from((
-                msg.canister_change_origin(args.get_sender_canister_version()),
-                args,
-            ))?;
+            let install_context = match Ic00Method::from_str(msg.method_name()) {
+                Ok(Ic00Method::InstallChunkedCode) => {
+                    let args = InstallChunkedCodeArgs::decode(payload)?;
+                    let chunk_hashes: Vec<_> = args
+                        .chunk_hashes_list
+                        .iter()
+                        .map(|chunk_hash| chunk_hash.hash.clone())
+                        .collect();
+                    let wasm_module = canister_manager.assemble_chunked_wasm(
+                        *msg.sender(),
+                        args.storage_canister_id(),
+                        &chunk_hashes,
+                        &args.wasm_module_hash,
+                        state,
+                    )?;
+                    InstallCodeContext {
+                        origin: msg.canister_change_origin(args.get_sender_canister_version()),
+                        mode: args.mode,
+                        canister_id: args.target_canister_id(),
+                        wasm_module,
+                        arg: args.arg,
+                        compute_allocation: None,
+                        memory_allocation: None,
+                        query_allocation: QueryAllocation::default(),
+                    }
+                }
+                _ => {
+                    let args = InstallCodeArgs::decode(payload)?;
+                    InstallCodeContext::try_from((
+                        msg.canister_change_origin(args.get_sender_canister_version()),
+                        args,
+                    ))?
+                }
+            };
             let canister = state
                 .take_canister_state(&install_context.canister_id)
                 .ok_or(CanisterManagerError::CanisterNotFound(
@@ -1906,16 +2251,16 @@ impl ExecutionEnvironment {
         // Start logging execution time for `install_code`.
         let timer = Timer::start();
 
-        let (install_context, old_canister) = match decode_input_and_take_canister(&msg, &mut state)
-        {
-            Ok(result) => result,
-            Err(err) => {
-                let refund = msg.take_cycles();
-                let state =
-                    self.finish_subnet_message_execution(state, msg, Err(err), refund, timer);
-                return (state, Some(NumInstructions::from(0)));
-            }
-        };
+        let (install_context, old_canister) =
+            match decode_input_and_take_canister(&msg, &self.canister_manager, &mut state) {
+                Ok(result) => result,
+                Err(err) => {
+                    let refund = msg.take_cycles();
+                    let state =
+                        self.finish_subnet_message_execution(state, msg, Err(err), refund, timer);
+                    return (state, Some(NumInstructions::from(0)));
+                }
+            };
 
         // Check the precondition.
         match old_canister.next_execution() {
@@ -2515,3 +2860,17 @@ fn get_master_ecdsa_public_key<'a>(
         Some(master_key) => Ok(master_key),
     }
 }
+
+fn get_master_schnorr_public_key<'a>(
+    schnorr_subnet_public_keys: &'a BTreeMap<SchnorrKeyId, MasterSchnorrPublicKey>,
+    subnet_id: SubnetId,
+    key_id: &SchnorrKeyId,
+) -> Result<&'a MasterSchnorrPublicKey, UserError> {
+    match schnorr_subnet_public_keys.get(key_id) {
+        None => Err(UserError::new(
+            ErrorCode::CanisterRejectedMessage,
+            format!("Subnet {} does not hold Schnorr key {}.", subnet_id, key_id),
+        )),
+        Some(master_key) => Ok(master_key),
+    }
+}
@@ -7,9 +7,10 @@ use ic_base_types::{NumBytes, NumSeconds};
 use ic_error_types::{ErrorCode, RejectCode, UserError};
 use ic_ic00_types::{
     self as ic00, CanisterChange, CanisterHttpRequestArgs, CanisterIdRecord,
-    CanisterStatusResultV2, CanisterStatusType, DerivationPath, EcdsaCurve, EcdsaKeyId, EmptyBlob,
-    HttpMethod, Method, Payload as Ic00Payload, ProvisionalCreateCanisterWithCyclesArgs,
-    ProvisionalTopUpCanisterArgs, TransformContext, TransformFunc, IC_00,
+    CanisterSchedulingStats, CanisterStatusResultV2, CanisterStatusType, DerivationPath,
+    EcdsaCurve, EcdsaKeyId, EmptyBlob, HttpMethod, Method, Payload as Ic00Payload,
+    ProvisionalCreateCanisterWithCyclesArgs, ProvisionalTopUpCanisterArgs, SchnorrAlgorithm,
+    SchnorrKeyId, TransformContext, TransformFunc, IC_00,
 };
 use ic_registry_routing_table::canister_id_into_u64;
 use ic_registry_routing_table::CanisterIdRange;
@@ -30,6 +31,7 @@ use ic_types::{
     ingress::{IngressState, IngressStatus, WasmResult},
     messages::{
         CallbackId, Payload, RejectContext, RequestOrResponse, Response, MAX_RESPONSE_COUNT_BYTES,
+        NO_DEADLINE,
     },
     CanisterId, Cycles, PrincipalId, RegistryVersion,
 };
@@ -37,6 +39,10 @@ use ic_types_test_utils::ids::{canister_test_id, node_test_id, subnet_test_id, u
 use ic_universal_canister::{call_args, wasm};
 use std::mem::size_of;
 
+#[cfg(test)]
+mod canister_logs;
+#[cfg(test)]
+mod canister_snapshots;
 #[cfg(test)]
 mod canister_task;
 
@@ -44,6 +50,8 @@ mod canister_task;
 mod compilation;
 #[cfg(test)]
 mod orthogonal_persistence;
+#[cfg(test)]
+mod wasm_chunk_store;
 
 const BALANCE_EPSILON: Cycles = Cycles::new(10_000_000);
 const ONE_GIB: i64 = 1 << 30;
@@ -669,6 +677,37 @@ fn get_running_canister_status_from_another_canister() {
     );
 }
 
+#[test]
+fn get_canister_status_includes_scheduling_stats() {
+    let mut test = ExecutionTestBuilder::new().build();
+    let canister = test.universal_canister().unwrap();
+    let canister_state = test.canister_state_mut(canister);
+    let canister_metrics = &mut canister_state.system_state.canister_metrics;
+    canister_metrics.scheduled_as_first = 1;
+    canister_metrics.executed = 2;
+    canister_metrics.interruped_during_execution = 3;
+    canister_metrics.skipped_round_due_to_no_messages = 4;
+    canister_metrics.skipped_round_due_to_limits = 5;
+    canister_metrics.slices_executed = 6;
+    canister_state.scheduler_state.accumulated_priority = (-7).into();
+    canister_state.scheduler_state.priority_credit = 8.into();
+    let result = test.canister_status(canister);
+    let csr = CanisterStatusResultV2::decode(&get_reply(result)).unwrap();
+    assert_eq!(
+        csr.scheduling_stats(),
+        Some(&CanisterSchedulingStats {
+            rounds_scheduled_as_first: 1,
+            rounds_executed: 2,
+            rounds_interrupted: 3,
+            rounds_skipped_due_to_no_messages: 4,
+            rounds_skipped_due_to_limits: 5,
+            slices_executed: 6,
+            accumulated_priority: -7,
+            priority_credit: 8,
+        })
+    );
+}
+
 #[test]
 fn get_canister_status_from_another_canister_when_memory_low() {
     let mut test = ExecutionTestBuilder::new().build();
@@ -704,6 +743,33 @@ fn get_canister_status_from_another_canister_when_memory_low() {
     );
 }
 
+#[test]
+fn canister_status_reports_wasm_memory_limit() {
+    let mut test = ExecutionTestBuilder::new().build();
+    let canister = test.universal_canister().unwrap();
+    let wasm_memory_limit = |test: &mut ExecutionTest| {
+        let reply = get_reply(test.canister_status(canister));
+        CanisterStatusResultV2::decode(&reply)
+            .unwrap()
+            .wasm_memory_limit()
+    };
+    assert_eq!(wasm_memory_limit(&mut test), None);
+
+    let limit = 10 * ONE_GIB as u64;
+    test.update_wasm_memory_limit(canister, NumBytes::from(limit))
+        .unwrap();
+    assert_eq!(wasm_memory_limit(&mut test), Some(limit));
+
+    // A limit of 0 removes the limit.
+    test.update_wasm_memory_limit(canister, NumBytes::from(0))
+        .unwrap();
+    assert_eq!(wasm_memory_limit(&mut test), None);
+    assert_eq!(
+        test.canister_state(canister).system_state.wasm_memory_limit,
+        None
+    );
+}
+
 #[test]
 fn get_stopped_canister_status_from_another_canister() {
     let mut test = ExecutionTestBuilder::new().build();
@@ -1079,6 +1145,7 @@ fn setup_initial_dkg_sender_not_on_nns() {
                     other_canister,
                 )
             })
+            deadline: NO_DEADLINE,
         }
         .into()
     );
@@ -1945,6 +2012,242 @@ fn ecdsa_signature_queue_fills_up() {
     );
 }
 
+fn make_schnorr_key(algorithm: SchnorrAlgorithm, name: &str) -> SchnorrKeyId {
+    SchnorrKeyId {
+        algorithm,
+        name: name.to_string(),
+    }
+}
+
+#[test]
+fn schnorr_signature_fee_charged() {
+    let fee = 1_000_000;
+    let payment = 2_000_000;
+    let schnorr_key = make_schnorr_key(SchnorrAlgorithm::Ed25519, "ed25519");
+    let mut test = ExecutionTestBuilder::new()
+        .with_subnet_type(SubnetType::System)
+        .with_own_subnet_id(subnet_test_id(1))
+        .with_nns_subnet_id(subnet_test_id(2))
+        .with_schnorr_signature_fee(fee)
+        .with_schnorr_key(schnorr_key.clone())
+        .build();
+
+    let canister_id = test.universal_canister().unwrap();
+    let args = ic00::SignWithSchnorrArgs {
+        message: b"an arbitrary-length message".to_vec(),
+        derivation_path: DerivationPath::new(vec![]),
+        key_id: schnorr_key.clone(),
+    };
+    let run = wasm()
+        .call_with_cycles(
+            ic00::IC_00,
+            Method::SignWithSchnorr,
+            call_args()
+                .other_side(args.encode())
+                .on_reject(wasm().reject_message().reject()),
+            Cycles::from(payment),
+        )
+        .build();
+
+    let (_, ingress_status) = test.ingress_raw(canister_id, "update", run);
+    assert_eq!(
+        ingress_status,
+        IngressStatus::Known {
+            receiver: canister_id.get(),
+            user_id: test.user_id(),
+            time: test.time(),
+            state: IngressState::Processing,
+        }
+    );
+    let (_, context) = test
+        .state()
+        .metadata
+        .subnet_call_context_manager
+        .sign_with_schnorr_contexts
+        .iter()
+        .next()
+        .unwrap();
+    assert_eq!(context.request.payment.get(), payment - fee);
+    assert_eq!(context.key_id, schnorr_key);
+    assert_eq!(context.message, b"an arbitrary-length message".to_vec());
+
+    assert_eq!(
+        *test
+            .state()
+            .metadata
+            .subnet_metrics
+            .get_consumed_cycles_by_use_case()
+            .get(&CyclesUseCase::SchnorrOutcalls)
+            .unwrap(),
+        NominalCycles::from(fee)
+    );
+}
+
+#[test]
+fn schnorr_signature_queue_fills_up() {
+    let schnorr_key = make_schnorr_key(SchnorrAlgorithm::Ed25519, "ed25519");
+    let mut test = ExecutionTestBuilder::new()
+        .with_subnet_type(SubnetType::System)
+        .with_own_subnet_id(subnet_test_id(1))
+        .with_nns_subnet_id(subnet_test_id(2))
+        .with_schnorr_signature_fee(1_000_000)
+        .with_schnorr_key(schnorr_key.clone())
+        .with_max_schnorr_queue_size(2)
+        .build();
+    let canister_id = test.universal_canister().unwrap();
+    let args = ic00::SignWithSchnorrArgs {
+        message: b"an arbitrary-length message".to_vec(),
+        derivation_path: DerivationPath::new(vec![]),
+        key_id: schnorr_key,
+    };
+    let run = wasm()
+        .call_with_cycles(
+            ic00::IC_00,
+            Method::SignWithSchnorr,
+            call_args()
+                .other_side(args.encode())
+                .on_reject(wasm().reject_message().reject()),
+            Cycles::from(2_000_000u128),
+        )
+        .build();
+
+    for _ in 0..2 {
+        test.ingress_raw(canister_id, "update", run.clone());
+    }
+    let result = test.ingress(canister_id, "update", run).unwrap();
+
+    assert_eq!(
+        result,
+        WasmResult::Reject(
+            "sign_with_schnorr request could not be handled, the Schnorr signature queue is full."
+                .to_string()
+        )
+    );
+    assert_eq!(
+        test.state()
+            .metadata
+            .subnet_call_context_manager
+            .sign_with_schnorr_contexts
+            .len(),
+        2
+    );
+}
+
+#[test]
+fn schnorr_signature_with_unknown_key_rejected() {
+    let correct_key = make_schnorr_key(SchnorrAlgorithm::Ed25519, "correct_key");
+    let wrong_key = make_schnorr_key(SchnorrAlgorithm::Bip340Secp256k1, "correct_key");
+    let mut test = ExecutionTestBuilder::new()
+        .with_subnet_type(SubnetType::System)
+        .with_own_subnet_id(subnet_test_id(1))
+        .with_nns_subnet_id(subnet_test_id(2))
+        .with_schnorr_key(correct_key)
+        .build();
+    let canister_id = test.universal_canister().unwrap();
+    let args = ic00::SignWithSchnorrArgs {
+        message: vec![1; 32],
+        derivation_path: DerivationPath::new(vec![]),
+        key_id: wrong_key.clone(),
+    };
+    let run = wasm()
+        .call_with_cycles(
+            ic00::IC_00,
+            Method::SignWithSchnorr,
+            call_args()
+                .other_side(args.encode())
+                .on_reject(wasm().reject_message().reject()),
+            Cycles::from(1_000_000_000_000u128),
+        )
+        .build();
+
+    let result = test.ingress(canister_id, "update", run).unwrap();
+    assert_eq!(
+        WasmResult::Reject(format!(
+            "Subnet {} does not hold Schnorr key {}.",
+            subnet_test_id(1),
+            wrong_key
+        )),
+        result
+    );
+}
+
+#[test]
+fn bip340_signature_requires_32_byte_message() {
+    let schnorr_key = make_schnorr_key(SchnorrAlgorithm::Bip340Secp256k1, "bip340");
+    let mut test = ExecutionTestBuilder::new()
+        .with_subnet_type(SubnetType::System)
+        .with_own_subnet_id(subnet_test_id(1))
+        .with_nns_subnet_id(subnet_test_id(2))
+        .with_schnorr_key(schnorr_key.clone())
+        .build();
+    let canister_id = test.universal_canister().unwrap();
+    let args = ic00::SignWithSchnorrArgs {
+        message: vec![1; 31],
+        derivation_path: DerivationPath::new(vec![]),
+        key_id: schnorr_key,
+    };
+    let run = wasm()
+        .call_with_cycles(
+            ic00::IC_00,
+            Method::SignWithSchnorr,
+            call_args()
+                .other_side(args.encode())
+                .on_reject(wasm().reject_message().reject()),
+            Cycles::from(1_000_000_000_000u128),
+        )
+        .build();
+
+    let result = test.ingress(canister_id, "update", run).unwrap();
+    assert_eq!(
+        WasmResult::Reject(
+            "sign_with_schnorr request with a BIP340 key requires a 32-byte message, but the message has 31 bytes."
+                .to_string()
+        ),
+        result
+    );
+}
+
+#[test]
+fn schnorr_public_key_is_derived_per_algorithm() {
+    let bip340_key = make_schnorr_key(SchnorrAlgorithm::Bip340Secp256k1, "key");
+    let ed25519_key = make_schnorr_key(SchnorrAlgorithm::Ed25519, "key");
+    let mut test = ExecutionTestBuilder::new()
+        .with_subnet_type(SubnetType::System)
+        .with_own_subnet_id(subnet_test_id(1))
+        .with_nns_subnet_id(subnet_test_id(2))
+        .with_schnorr_key(bip340_key.clone())
+        .with_schnorr_key(ed25519_key.clone())
+        .build();
+    let canister_id = test.universal_canister().unwrap();
+
+    let mut public_key = |key_id: SchnorrKeyId| {
+        let args = ic00::SchnorrPublicKeyArgs {
+            canister_id: None,
+            derivation_path: DerivationPath::new(vec![vec![1, 2, 3]]),
+            key_id,
+        };
+        let run = wasm()
+            .call_simple(
+                ic00::IC_00,
+                Method::SchnorrPublicKey,
+                call_args()
+                    .other_side(args.encode())
+                    .on_reject(wasm().reject_message().reject()),
+            )
+            .build();
+        let reply = get_reply(test.ingress(canister_id, "update", run));
+        ic00::SchnorrPublicKeyResponse::decode(&reply).unwrap()
+    };
+
+    let bip340_response = public_key(bip340_key);
+    assert_eq!(bip340_response.public_key.len(), 33);
+    assert_eq!(bip340_response.chain_code.len(), 32);
+
+    let ed25519_response = public_key(ed25519_key);
+    assert_eq!(ed25519_response.public_key.len(), 32);
+    assert_eq!(ed25519_response.chain_code.len(), 32);
+}
+
 #[test]
 fn canister_output_queue_does_not_overflow_when_calling_ic00() {
     let own_subnet = subnet_test_id(1);
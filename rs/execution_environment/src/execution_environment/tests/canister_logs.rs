@@ -0,0 +1,86 @@
+use ic_error_types::ErrorCode;
+use ic_ic00_types::{FetchCanisterLogsResponse, LogVisibility, Payload};
+use ic_test_utilities_execution_environment::{get_reply, ExecutionTest, ExecutionTestBuilder};
+use ic_types::CanisterId;
+use ic_types_test_utils::ids::user_test_id;
+use ic_universal_canister::wasm;
+
+fn fetch_log_contents(test: &mut ExecutionTest, canister_id: CanisterId) -> Vec<Vec<u8>> {
+    let reply = get_reply(test.fetch_canister_logs(canister_id));
+    FetchCanisterLogsResponse::decode(&reply)
+        .unwrap()
+        .canister_log_records
+        .into_iter()
+        .map(|record| record.content)
+        .collect()
+}
+
+#[test]
+fn debug_print_is_recorded_in_canister_log() {
+    let mut test = ExecutionTestBuilder::new().build();
+    let canister_id = test.universal_canister().unwrap();
+    let payload = wasm()
+        .debug_print(b"first")
+        .debug_print(b"second")
+        .reply()
+        .build();
+    test.ingress(canister_id, "update", payload).unwrap();
+
+    let reply = get_reply(test.fetch_canister_logs(canister_id));
+    let records = FetchCanisterLogsResponse::decode(&reply)
+        .unwrap()
+        .canister_log_records;
+    assert_eq!(records.len(), 2);
+    assert_eq!(records[0].content, b"first".to_vec());
+    assert_eq!(records[1].content, b"second".to_vec());
+    assert_eq!(records[1].idx, records[0].idx + 1);
+    assert_eq!(
+        records[0].timestamp_nanos,
+        test.time().as_nanos_since_unix_epoch()
+    );
+}
+
+#[test]
+fn trap_message_is_recorded_in_canister_log() {
+    let mut test = ExecutionTestBuilder::new().build();
+    let canister_id = test.universal_canister().unwrap();
+    let payload = wasm()
+        .debug_print(b"before trap")
+        .trap_with_blob(b"boom")
+        .build();
+    let err = test.ingress(canister_id, "update", payload).unwrap_err();
+    assert_eq!(err.code(), ErrorCode::CanisterCalledTrap);
+
+    assert_eq!(
+        fetch_log_contents(&mut test, canister_id),
+        vec![b"before trap".to_vec(), b"[TRAP]: boom".to_vec()]
+    );
+}
+
+#[test]
+fn log_visibility_restricts_who_can_fetch_logs() {
+    let mut test = ExecutionTestBuilder::new().build();
+    let canister_id = test.universal_canister().unwrap();
+    let payload = wasm().debug_print(b"hello").reply().build();
+    test.ingress(canister_id, "update", payload).unwrap();
+    let controller = test.user_id();
+
+    // Only controllers can fetch logs by default.
+    test.set_user_id(user_test_id(42));
+    let err = test.fetch_canister_logs(canister_id).unwrap_err();
+    assert_eq!(err.code(), ErrorCode::CanisterInvalidController);
+
+    test.set_user_id(controller);
+    test.update_log_visibility(canister_id, LogVisibility::Public)
+        .unwrap();
+    assert_eq!(
+        test.canister_state(canister_id).system_state.log_visibility,
+        LogVisibility::Public
+    );
+
+    test.set_user_id(user_test_id(42));
+    assert_eq!(
+        fetch_log_contents(&mut test, canister_id),
+        vec![b"hello".to_vec()]
+    );
+}
@@ -0,0 +1,199 @@
+use candid::Decode;
+use ic_error_types::ErrorCode;
+use ic_ic00_types::{
+    CanisterChange, CanisterChangeDetails, CanisterChangeOrigin, CanisterIdRecord,
+    CanisterSnapshotResponse, DeleteCanisterSnapshotArgs, EmptyBlob, LoadCanisterSnapshotArgs,
+    Method, Payload, TakeCanisterSnapshotArgs,
+};
+use ic_replicated_state::SnapshotId;
+use ic_test_utilities_execution_environment::{get_reply, ExecutionTest, ExecutionTestBuilder};
+use ic_types::{ingress::WasmResult, CanisterId, NumBytes};
+use ic_types_test_utils::ids::user_test_id;
+use ic_universal_canister::wasm;
+use std::convert::TryFrom;
+
+fn take_snapshot(
+    test: &mut ExecutionTest,
+    canister_id: CanisterId,
+    replace_snapshot: Option<Vec<u8>>,
+) -> CanisterSnapshotResponse {
+    let args = TakeCanisterSnapshotArgs::new(canister_id, replace_snapshot);
+    let reply = get_reply(test.take_canister_snapshot(args));
+    Decode!(&reply, CanisterSnapshotResponse).unwrap()
+}
+
+fn list_snapshots(
+    test: &mut ExecutionTest,
+    canister_id: CanisterId,
+) -> Vec<CanisterSnapshotResponse> {
+    let reply = get_reply(test.list_canister_snapshots(canister_id));
+    Decode!(&reply, Vec<CanisterSnapshotResponse>).unwrap()
+}
+
+fn write_stable_memory(test: &mut ExecutionTest, canister_id: CanisterId, data: &[u8]) {
+    let payload = wasm().stable_write(0, data).reply().build();
+    test.ingress(canister_id, "update", payload).unwrap();
+}
+
+fn read_stable_memory(test: &mut ExecutionTest, canister_id: CanisterId, size: u32) -> Vec<u8> {
+    let payload = wasm().stable_read(0, size).append_and_reply().build();
+    get_reply(test.ingress(canister_id, "update", payload))
+}
+
+#[test]
+fn load_snapshot_restores_canister_state() {
+    let mut test = ExecutionTestBuilder::new().build();
+    let canister_id = test.universal_canister().unwrap();
+    let payload = wasm().stable_grow(1).reply().build();
+    test.ingress(canister_id, "update", payload).unwrap();
+    write_stable_memory(&mut test, canister_id, b"before");
+    test.canister_state_mut(canister_id)
+        .system_state
+        .certified_data = vec![1, 2, 3];
+
+    let snapshot = take_snapshot(&mut test, canister_id, None);
+    let snapshot_version = test
+        .canister_state(canister_id)
+        .system_state
+        .canister_version;
+    assert_eq!(
+        SnapshotId::try_from(snapshot.snapshot_id())
+            .unwrap()
+            .canister_id(),
+        canister_id
+    );
+    assert_eq!(
+        snapshot.taken_at_timestamp(),
+        test.time().as_nanos_since_unix_epoch()
+    );
+
+    write_stable_memory(&mut test, canister_id, b"after!");
+    test.canister_state_mut(canister_id)
+        .system_state
+        .certified_data = vec![4, 5, 6];
+
+    let result = test.load_canister_snapshot(LoadCanisterSnapshotArgs::new(
+        canister_id,
+        snapshot.snapshot_id().to_vec(),
+        None,
+    ));
+    assert_eq!(result, Ok(WasmResult::Reply(EmptyBlob.encode())));
+
+    let canister = test.canister_state(canister_id);
+    assert_eq!(canister.system_state.certified_data, vec![1, 2, 3]);
+    let last_change = canister
+        .system_state
+        .get_canister_history()
+        .get_changes(1)
+        .next()
+        .unwrap();
+    assert_eq!(
+        **last_change,
+        CanisterChange::new(
+            test.time().as_nanos_since_unix_epoch(),
+            canister.system_state.canister_version,
+            CanisterChangeOrigin::from_user(test.user_id().get()),
+            CanisterChangeDetails::load_snapshot(
+                snapshot_version,
+                snapshot.snapshot_id().to_vec(),
+                snapshot.taken_at_timestamp(),
+            ),
+        )
+    );
+    assert_eq!(read_stable_memory(&mut test, canister_id, 6), b"before");
+}
+
+#[test]
+fn snapshots_count_towards_canister_memory_usage() {
+    let mut test = ExecutionTestBuilder::new().build();
+    let canister_id = test.universal_canister().unwrap();
+    let memory_usage_before = test.canister_state(canister_id).memory_usage();
+
+    let snapshot = take_snapshot(&mut test, canister_id, None);
+    assert!(snapshot.total_size() > 0);
+    assert_eq!(
+        test.canister_state(canister_id).memory_usage(),
+        memory_usage_before + NumBytes::from(snapshot.total_size())
+    );
+
+    test.delete_canister_snapshot(DeleteCanisterSnapshotArgs::new(
+        canister_id,
+        snapshot.snapshot_id().to_vec(),
+    ))
+    .unwrap();
+    assert_eq!(
+        test.canister_state(canister_id).memory_usage(),
+        memory_usage_before
+    );
+    assert!(list_snapshots(&mut test, canister_id).is_empty());
+}
+
+#[test]
+fn take_snapshot_respects_limit_and_replaces_snapshot() {
+    let mut test = ExecutionTestBuilder::new().build();
+    let canister_id = test.universal_canister().unwrap();
+
+    let first = take_snapshot(&mut test, canister_id, None);
+    let err = test
+        .take_canister_snapshot(TakeCanisterSnapshotArgs::new(canister_id, None))
+        .unwrap_err();
+    assert_eq!(err.code(), ErrorCode::CanisterRejectedMessage);
+
+    let second = take_snapshot(&mut test, canister_id, Some(first.snapshot_id().to_vec()));
+    assert_ne!(first.snapshot_id(), second.snapshot_id());
+    assert_eq!(list_snapshots(&mut test, canister_id), vec![second]);
+}
+
+#[test]
+fn snapshot_operations_fail_on_invalid_input() {
+    let mut test = ExecutionTestBuilder::new().build();
+    let canister_id = test.universal_canister().unwrap();
+    let other_canister_id = test.universal_canister().unwrap();
+    let snapshot = take_snapshot(&mut test, canister_id, None);
+
+    // The snapshot belongs to a different canister.
+    let err = test
+        .load_canister_snapshot(LoadCanisterSnapshotArgs::new(
+            other_canister_id,
+            snapshot.snapshot_id().to_vec(),
+            None,
+        ))
+        .unwrap_err();
+    assert_eq!(err.code(), ErrorCode::CanisterSnapshotNotFound);
+
+    let err = test
+        .delete_canister_snapshot(DeleteCanisterSnapshotArgs::new(canister_id, vec![1, 2, 3]))
+        .unwrap_err();
+    assert_eq!(err.code(), ErrorCode::CanisterSnapshotNotFound);
+
+    // Only controllers can manage snapshots.
+    test.set_user_id(user_test_id(42));
+    let err = test.list_canister_snapshots(canister_id).unwrap_err();
+    assert_eq!(err.code(), ErrorCode::CanisterInvalidController);
+}
+
+#[test]
+fn cannot_take_snapshot_of_empty_canister() {
+    let mut test = ExecutionTestBuilder::new().build();
+    let canister_id = test.universal_canister().unwrap();
+    test.uninstall_code(canister_id).unwrap();
+
+    let err = test
+        .take_canister_snapshot(TakeCanisterSnapshotArgs::new(canister_id, None))
+        .unwrap_err();
+    assert_eq!(err.code(), ErrorCode::CanisterWasmModuleNotFound);
+}
+
+#[test]
+fn deleting_canister_deletes_its_snapshots() {
+    let mut test = ExecutionTestBuilder::new().build();
+    let canister_id = test.universal_canister().unwrap();
+    take_snapshot(&mut test, canister_id, None);
+
+    test.stop_canister(canister_id);
+    test.process_stopping_canisters();
+    let payload = CanisterIdRecord::from(canister_id).encode();
+    test.subnet_message(Method::DeleteCanister, payload)
+        .unwrap();
+    assert!(test.state().canister_snapshots.is_empty());
+}
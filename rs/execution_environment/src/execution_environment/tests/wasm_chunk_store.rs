@@ -0,0 +1,216 @@
+use candid::Decode;
+use ic_error_types::ErrorCode;
+use ic_ic00_types::{
+    CanisterInstallMode, ChunkHash, EmptyBlob, InstallChunkedCodeArgs, Payload, StoredChunksReply,
+    UploadChunkArgs,
+};
+use ic_replicated_state::canister_state::system_state::wasm_chunk_store::CHUNK_SIZE;
+use ic_test_utilities_execution_environment::{get_reply, ExecutionTest, ExecutionTestBuilder};
+use ic_types::{ingress::WasmResult, CanisterId, Cycles, NumBytes};
+use ic_types_test_utils::ids::user_test_id;
+use ic_universal_canister::UNIVERSAL_CANISTER_WASM;
+
+const CYCLES: Cycles = Cycles::new(1_000_000_000_000_000);
+
+fn upload_chunk(test: &mut ExecutionTest, canister_id: CanisterId, chunk: &[u8]) -> Vec<u8> {
+    let args = UploadChunkArgs::new(canister_id, chunk.to_vec());
+    let reply = get_reply(test.upload_chunk(args));
+    Decode!(&reply, ChunkHash).unwrap().hash
+}
+
+fn stored_chunks(test: &mut ExecutionTest, canister_id: CanisterId) -> Vec<Vec<u8>> {
+    let reply = get_reply(test.stored_chunks(canister_id));
+    let StoredChunksReply(hashes) = Decode!(&reply, StoredChunksReply).unwrap();
+    hashes
+        .into_iter()
+        .map(|chunk_hash| chunk_hash.hash)
+        .collect()
+}
+
+/// Uploads the universal canister in three chunks to `storage_canister` and
+/// returns the chunk hashes.
+fn upload_universal_canister(
+    test: &mut ExecutionTest,
+    storage_canister: CanisterId,
+) -> Vec<Vec<u8>> {
+    let chunk_len = UNIVERSAL_CANISTER_WASM.len() / 3 + 1;
+    UNIVERSAL_CANISTER_WASM
+        .chunks(chunk_len)
+        .map(|chunk| upload_chunk(test, storage_canister, chunk))
+        .collect()
+}
+
+fn universal_canister_hash() -> Vec<u8> {
+    ic_crypto_sha::Sha256::hash(UNIVERSAL_CANISTER_WASM).to_vec()
+}
+
+#[test]
+fn upload_chunk_returns_hash_and_stores_chunk() {
+    let mut test = ExecutionTestBuilder::new().build();
+    let canister_id = test.create_canister(CYCLES);
+
+    let chunk = vec![1, 2, 3];
+    let hash = upload_chunk(&mut test, canister_id, &chunk);
+    assert_eq!(hash, ic_crypto_sha::Sha256::hash(&chunk).to_vec());
+    assert_eq!(stored_chunks(&mut test, canister_id), vec![hash.clone()]);
+
+    // Uploading the same chunk again is a no-op.
+    assert_eq!(upload_chunk(&mut test, canister_id, &chunk), hash);
+    assert_eq!(stored_chunks(&mut test, canister_id), vec![hash]);
+    assert_eq!(
+        test.canister_state(canister_id)
+            .wasm_chunk_store_memory_usage(),
+        NumBytes::from(CHUNK_SIZE)
+    );
+}
+
+#[test]
+fn clear_chunk_store_removes_chunks() {
+    let mut test = ExecutionTestBuilder::new().build();
+    let canister_id = test.create_canister(CYCLES);
+    upload_chunk(&mut test, canister_id, &[1, 2, 3]);
+    upload_chunk(&mut test, canister_id, &[4, 5, 6]);
+    assert_eq!(stored_chunks(&mut test, canister_id).len(), 2);
+
+    let result = test.clear_chunk_store(canister_id);
+    assert_eq!(result, Ok(WasmResult::Reply(EmptyBlob.encode())));
+    assert!(stored_chunks(&mut test, canister_id).is_empty());
+    assert_eq!(
+        test.canister_state(canister_id)
+            .wasm_chunk_store_memory_usage(),
+        NumBytes::from(0)
+    );
+}
+
+#[test]
+fn upload_chunk_fails_for_oversized_chunk() {
+    let mut test = ExecutionTestBuilder::new().build();
+    let canister_id = test.create_canister(CYCLES);
+
+    let args = UploadChunkArgs::new(canister_id, vec![0; CHUNK_SIZE as usize + 1]);
+    let err = test.upload_chunk(args).unwrap_err();
+    assert_eq!(err.code(), ErrorCode::CanisterContractViolation);
+}
+
+#[test]
+fn upload_chunk_fails_when_store_is_full() {
+    let mut test = ExecutionTestBuilder::new().build();
+    let canister_id = test.create_canister(CYCLES);
+    for i in 0..100_u32 {
+        upload_chunk(&mut test, canister_id, &i.to_le_bytes());
+    }
+
+    let args = UploadChunkArgs::new(canister_id, vec![1; 10]);
+    let err = test.upload_chunk(args).unwrap_err();
+    assert_eq!(err.code(), ErrorCode::CanisterContractViolation);
+    assert_eq!(stored_chunks(&mut test, canister_id).len(), 100);
+}
+
+#[test]
+fn chunk_store_methods_require_controller() {
+    let mut test = ExecutionTestBuilder::new().build();
+    let canister_id = test.create_canister(CYCLES);
+    test.set_user_id(user_test_id(42));
+
+    let args = UploadChunkArgs::new(canister_id, vec![1, 2, 3]);
+    let err = test.upload_chunk(args).unwrap_err();
+    assert_eq!(err.code(), ErrorCode::CanisterInvalidController);
+    let err = test.stored_chunks(canister_id).unwrap_err();
+    assert_eq!(err.code(), ErrorCode::CanisterInvalidController);
+    let err = test.clear_chunk_store(canister_id).unwrap_err();
+    assert_eq!(err.code(), ErrorCode::CanisterInvalidController);
+}
+
+#[test]
+fn install_chunked_code_installs_assembled_module() {
+    let mut test = ExecutionTestBuilder::new().build();
+    let canister_id = test.create_canister(CYCLES);
+    let chunk_hashes = upload_universal_canister(&mut test, canister_id);
+
+    let args = InstallChunkedCodeArgs::new(
+        CanisterInstallMode::Install,
+        canister_id,
+        None,
+        chunk_hashes,
+        universal_canister_hash(),
+        vec![],
+    );
+    let result = test.install_chunked_code(args);
+    assert_eq!(result, Ok(WasmResult::Reply(EmptyBlob.encode())));
+    assert_eq!(
+        test.canister_state(canister_id)
+            .execution_state
+            .as_ref()
+            .unwrap()
+            .wasm_binary
+            .binary
+            .module_hash()
+            .to_vec(),
+        universal_canister_hash()
+    );
+}
+
+#[test]
+fn install_chunked_code_from_storage_canister() {
+    let mut test = ExecutionTestBuilder::new().build();
+    let storage_canister = test.create_canister(CYCLES);
+    let target_canister = test.create_canister(CYCLES);
+    let chunk_hashes = upload_universal_canister(&mut test, storage_canister);
+
+    let args = InstallChunkedCodeArgs::new(
+        CanisterInstallMode::Install,
+        target_canister,
+        Some(storage_canister),
+        chunk_hashes,
+        universal_canister_hash(),
+        vec![],
+    );
+    let result = test.install_chunked_code(args);
+    assert_eq!(result, Ok(WasmResult::Reply(EmptyBlob.encode())));
+    assert!(test
+        .canister_state(target_canister)
+        .execution_state
+        .is_some());
+    // The chunks are kept in the storage canister.
+    assert_eq!(stored_chunks(&mut test, storage_canister).len(), 3);
+}
+
+#[test]
+fn install_chunked_code_fails_on_hash_mismatch() {
+    let mut test = ExecutionTestBuilder::new().build();
+    let canister_id = test.create_canister(CYCLES);
+    let mut chunk_hashes = upload_universal_canister(&mut test, canister_id);
+    chunk_hashes.swap(0, 1);
+
+    let args = InstallChunkedCodeArgs::new(
+        CanisterInstallMode::Install,
+        canister_id,
+        None,
+        chunk_hashes,
+        universal_canister_hash(),
+        vec![],
+    );
+    let err = test.install_chunked_code(args).unwrap_err();
+    assert_eq!(err.code(), ErrorCode::CanisterContractViolation);
+    assert!(test.canister_state(canister_id).execution_state.is_none());
+}
+
+#[test]
+fn install_chunked_code_fails_on_missing_chunk() {
+    let mut test = ExecutionTestBuilder::new().build();
+    let canister_id = test.create_canister(CYCLES);
+    let mut chunk_hashes = upload_universal_canister(&mut test, canister_id);
+    chunk_hashes.push(vec![0; 32]);
+
+    let args = InstallChunkedCodeArgs::new(
+        CanisterInstallMode::Install,
+        canister_id,
+        None,
+        chunk_hashes,
+        universal_canister_hash(),
+        vec![],
+    );
+    let err = test.install_chunked_code(args).unwrap_err();
+    assert_eq!(err.code(), ErrorCode::CanisterContractViolation);
+    assert!(test.canister_state(canister_id).execution_state.is_none());
+}
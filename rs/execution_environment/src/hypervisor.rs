@@ -126,6 +126,7 @@ pub struct Hypervisor {
     deterministic_time_slicing: FlagStatus,
     cost_to_compile_wasm_instruction: NumInstructions,
     dirty_page_overhead: NumInstructions,
+    fd_factory: Arc<dyn PageAllocatorFileDescriptor>,
 }
 
 impl Hypervisor {
@@ -137,6 +138,11 @@ impl Hypervisor {
         self.own_subnet_type
     }
 
+    /// The factory for the file descriptors backing new `PageMap`s.
+    pub(crate) fn fd_factory(&self) -> Arc<dyn PageAllocatorFileDescriptor> {
+        Arc::clone(&self.fd_factory)
+    }
+
     pub fn create_execution_state(
         &self,
         canister_module: CanisterModule,
@@ -209,6 +215,8 @@ impl Hypervisor {
         embedder_config.subnet_type = own_subnet_type;
         embedder_config.dirty_page_overhead = dirty_page_overhead;
         embedder_config.trace_execution = config.trace_execution;
+        embedder_config.feature_flags.wasm64 = config.wasm64;
+        embedder_config.feature_flags.canister_profiling = config.canister_profiling;
 
         let wasm_executor: Arc<dyn WasmExecutor> = match config.canister_sandboxing_flag {
             FlagStatus::Enabled => {
@@ -243,6 +251,7 @@ impl Hypervisor {
             deterministic_time_slicing: config.deterministic_time_slicing,
             cost_to_compile_wasm_instruction: config.cost_to_compile_wasm_instruction,
             dirty_page_overhead,
+            fd_factory,
         }
     }
 
@@ -257,6 +266,7 @@ impl Hypervisor {
         deterministic_time_slicing: FlagStatus,
         cost_to_compile_wasm_instruction: NumInstructions,
         dirty_page_overhead: NumInstructions,
+        fd_factory: Arc<dyn PageAllocatorFileDescriptor>,
     ) -> Self {
         Self {
             wasm_executor,
@@ -269,6 +279,7 @@ impl Hypervisor {
             deterministic_time_slicing,
             cost_to_compile_wasm_instruction,
             dirty_page_overhead,
+            fd_factory,
         }
     }
 
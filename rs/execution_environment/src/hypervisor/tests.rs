@@ -1,3 +1,4 @@
+use crate::util::folded_instruction_profile;
 use assert_matches::assert_matches;
 use candid::{Decode, Encode};
 use ic_base_types::{NumSeconds, PrincipalId};
@@ -2735,6 +2736,242 @@ fn grow_memory_beyond_32_bit_limit_fails() {
     assert_eq!(ErrorCode::CanisterTrapped, err.code());
 }
 
+#[test]
+fn wasm64_canister_is_rejected_if_wasm64_is_disabled() {
+    let mut test = ExecutionTestBuilder::new().build();
+    let wat = "(module (memory i64 1))";
+    let err = test.canister_from_wat(wat).unwrap_err();
+    assert_eq!(ErrorCode::CanisterInvalidWasm, err.code());
+}
+
+#[test]
+fn wasm64_canister_can_access_memory_beyond_32_bit_limit() {
+    let mut test = ExecutionTestBuilder::new().with_wasm64().build();
+    let wat = r#"
+        (module
+            (import "ic0" "msg_reply" (func $msg_reply))
+            (import "ic0" "msg_reply_data_append"
+                (func $msg_reply_data_append (param i64 i64)))
+            (func (export "canister_update test")
+                ;; 65537 pages is one page more than a 32-bit memory can hold.
+                (if (i64.eq (memory.grow (i64.const 65537)) (i64.const -1))
+                    (then unreachable)
+                )
+                (i64.store8 (i64.const 4294967296) (i32.const 42))
+                (call $msg_reply_data_append (i64.const 4294967296) (i64.const 1))
+                (call $msg_reply)
+            )
+            (memory i64 0)
+        )"#;
+    let canister_id = test
+        .canister_from_cycles_and_wat(Cycles::new(100_000_000_000_000), wat)
+        .unwrap();
+    let result = test.ingress(canister_id, "test", vec![]).unwrap();
+    assert_eq!(result, WasmResult::Reply(vec![42]));
+    assert_eq!(
+        test.execution_state(canister_id).wasm_memory.size,
+        NumWasmPages::from(65537)
+    );
+}
+
+#[test]
+fn wasm64_canister_with_32_bit_system_api_imports_is_rejected() {
+    let mut test = ExecutionTestBuilder::new().with_wasm64().build();
+    let wat = r#"
+        (module
+            (import "ic0" "msg_reply_data_append"
+                (func $msg_reply_data_append (param i32 i32)))
+            (memory i64 1)
+        )"#;
+    let err = test.canister_from_wat(wat).unwrap_err();
+    assert_eq!(ErrorCode::CanisterInvalidWasm, err.code());
+}
+
+// Verify that `memory.fill` and `memory.copy` on a 64-bit memory have cost
+// linear with their 64-bit size argument.
+#[test]
+fn account_for_size_of_wasm64_bulk_memory_instructions() {
+    let mut test = ExecutionTestBuilder::new().with_wasm64().build();
+    let wat = r#"
+        (module
+            (memory i64 1)
+            (func (;0;)
+                (memory.fill
+                    (i64.const 0)
+                    (i32.const 0)
+                    (i64.const 1000))
+                (memory.copy
+                    (i64.const 1000)
+                    (i64.const 0)
+                    (i64.const 1000)))
+            (start 0)
+        )"#;
+    assert_eq!(test.executed_instructions(), NumInstructions::from(0));
+    test.canister_from_wat(wat).unwrap();
+    assert!(test.executed_instructions() > NumInstructions::from(2000));
+}
+
+#[test]
+fn wasm64_memory_fill_can_trigger_out_of_instructions() {
+    let mut test = ExecutionTestBuilder::new()
+        .with_wasm64()
+        .with_install_code_instruction_limit(4_000_000_000)
+        .build();
+    let wat = r#"
+        (module
+            (memory i64 1)
+            (func (;0;)
+            (memory.fill
+                (i64.const 0)
+                (i32.const 0)
+                (i64.const 8589934592))) ;; 8 GiB
+            (start 0)
+        )"#;
+    let err = test.canister_from_wat(wat).unwrap_err();
+    assert_eq!(ErrorCode::CanisterInstructionLimitExceeded, err.code());
+}
+
+#[test]
+fn wasm_memory_limit_prevents_memory_grow() {
+    let mut test = ExecutionTestBuilder::new().build();
+    let wat = r#"
+        (module
+            (import "ic0" "msg_reply" (func $msg_reply))
+            (func (export "canister_update grow")
+                (drop (memory.grow (i32.const 10)))
+                (call $msg_reply)
+            )
+            (memory 1)
+        )"#;
+    let canister_id = test.canister_from_wat(wat).unwrap();
+    test.update_wasm_memory_limit(
+        canister_id,
+        NumBytes::from(5 * WASM_PAGE_SIZE_IN_BYTES as u64),
+    )
+    .unwrap();
+    let err = test.ingress(canister_id, "grow", vec![]).unwrap_err();
+    assert_eq!(ErrorCode::CanisterOutOfMemory, err.code());
+    assert_eq!(
+        test.execution_state(canister_id).wasm_memory.size,
+        NumWasmPages::from(1)
+    );
+
+    test.update_wasm_memory_limit(
+        canister_id,
+        NumBytes::from(20 * WASM_PAGE_SIZE_IN_BYTES as u64),
+    )
+    .unwrap();
+    test.ingress(canister_id, "grow", vec![]).unwrap();
+    assert_eq!(
+        test.execution_state(canister_id).wasm_memory.size,
+        NumWasmPages::from(11)
+    );
+}
+
+// The functions have indices 2 (`work`), 3 (`run`), and 4 (`fail`).
+const PROFILING_WAT: &str = r#"
+    (module
+        (import "ic0" "msg_reply" (func $msg_reply))
+        (import "ic0" "trap" (func $ic_trap (param i32) (param i32)))
+        (func $work (param $n i32)
+            (loop $loop
+                (local.set $n (i32.sub (local.get $n) (i32.const 1)))
+                (br_if $loop (i32.ne (local.get $n) (i32.const 0)))
+            )
+        )
+        (func $run (export "canister_update run")
+            (call $work (i32.const 100))
+            (call $work (i32.const 10))
+            (call $msg_reply)
+        )
+        (func $fail (export "canister_update fail")
+            (call $work (i32.const 10))
+            (call $ic_trap (i32.const 0) (i32.const 0))
+        )
+        (memory 1)
+    )"#;
+
+#[test]
+fn canister_profiling_is_disabled_by_default() {
+    let mut test = ExecutionTestBuilder::new().build();
+    let canister_id = test.canister_from_wat(PROFILING_WAT).unwrap();
+    test.ingress(canister_id, "run", vec![]).unwrap();
+    assert!(test
+        .execution_state(canister_id)
+        .instruction_profile
+        .is_empty());
+}
+
+#[test]
+fn canister_profiling_attributes_instructions_to_call_stacks() {
+    let mut test = ExecutionTestBuilder::new()
+        .with_canister_profiling()
+        .build();
+    let canister_id = test.canister_from_wat(PROFILING_WAT).unwrap();
+    test.ingress(canister_id, "run", vec![]).unwrap();
+    let execution_state = test.execution_state(canister_id);
+    let stacks = execution_state.instruction_profile.stacks();
+    assert_eq!(
+        stacks.keys().cloned().collect::<Vec<_>>(),
+        vec![vec![3], vec![3, 2]]
+    );
+    assert!(stacks[&vec![3, 2]] > stacks[&vec![3]]);
+
+    let folded = folded_instruction_profile(execution_state);
+    let frames: Vec<_> = folded
+        .lines()
+        .map(|line| line.rsplit_once(' ').unwrap().0)
+        .collect();
+    assert_eq!(frames, vec!["run", "run;work"]);
+}
+
+#[test]
+fn canister_profiling_keeps_profile_of_trapped_execution() {
+    let mut test = ExecutionTestBuilder::new()
+        .with_canister_profiling()
+        .build();
+    let canister_id = test.canister_from_wat(PROFILING_WAT).unwrap();
+    let err = test.ingress(canister_id, "fail", vec![]).unwrap_err();
+    assert_eq!(ErrorCode::CanisterCalledTrap, err.code());
+    let stacks = test
+        .execution_state(canister_id)
+        .instruction_profile
+        .stacks();
+    assert_eq!(
+        stacks.keys().cloned().collect::<Vec<_>>(),
+        vec![vec![4], vec![4, 2]]
+    );
+}
+
+#[test]
+fn canister_profiling_covers_all_dts_slices() {
+    let mut test = ExecutionTestBuilder::new()
+        .with_instruction_limit(100_000_000)
+        .with_slice_instruction_limit(10_000)
+        .with_deterministic_time_slicing()
+        .with_canister_profiling()
+        .build();
+    let wat = r#"
+        (module
+            (import "ic0" "msg_reply" (func $msg_reply))
+            (func $work (param $n i32)
+                (loop $loop
+                    (local.set $n (i32.sub (local.get $n) (i32.const 1)))
+                    (br_if $loop (i32.ne (local.get $n) (i32.const 0)))
+                )
+            )
+            (func (export "canister_update run")
+                (call $work (i32.const 100000))
+                (call $msg_reply)
+            )
+        )"#;
+    let canister_id = test.canister_from_wat(wat).unwrap();
+    test.ingress(canister_id, "run", vec![]).unwrap();
+    let profile = &test.execution_state(canister_id).instruction_profile;
+    // The loop executes at least 6 instructions per iteration.
+    assert!(profile.stacks()[&vec![2, 1]] > 600_000);
+}
+
 const STABLE_MEMORY_WAT: &str = r#"
     (import "ic0" "msg_reply" (func $msg_reply))
     (import "ic0" "msg_reply_data_append"
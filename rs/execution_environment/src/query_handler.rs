@@ -18,7 +18,10 @@ use ic_config::flag_status::FlagStatus;
 use ic_crypto_tree_hash::{flatmap, Label, LabeledTree, LabeledTree::SubTree};
 use ic_cycles_account_manager::CyclesAccountManager;
 use ic_error_types::{ErrorCode, RejectCode, UserError};
-use ic_interfaces::execution_environment::{QueryExecutionService, QueryHandler};
+use ic_interfaces::execution_environment::{
+    CrossSubnetQuery, CrossSubnetQueryClient, CrossSubnetQueryResponse, QueryExecutionService,
+    QueryHandler,
+};
 use ic_interfaces_state_manager::StateReader;
 use ic_logger::ReplicaLogger;
 use ic_metrics::MetricsRegistry;
@@ -99,6 +102,7 @@ pub struct InternalHttpQueryHandler {
     max_instructions_per_query: NumInstructions,
     cycles_account_manager: Arc<CyclesAccountManager>,
     query_cache: query_cache::QueryCache,
+    cross_subnet_query_client: Option<Arc<dyn CrossSubnetQueryClient>>,
 }
 
 #[derive(Clone)]
@@ -118,6 +122,7 @@ impl InternalHttpQueryHandler {
         metrics_registry: &MetricsRegistry,
         max_instructions_per_query: NumInstructions,
         cycles_account_manager: Arc<CyclesAccountManager>,
+        cross_subnet_query_client: Option<Arc<dyn CrossSubnetQueryClient>>,
     ) -> Self {
         let query_cache_capacity = config.query_cache_capacity;
         Self {
@@ -129,6 +134,7 @@ impl InternalHttpQueryHandler {
             max_instructions_per_query,
             cycles_account_manager,
             query_cache: query_cache::QueryCache::new(metrics_registry, query_cache_capacity),
+            cross_subnet_query_client,
         }
     }
 }
@@ -151,8 +157,18 @@ impl QueryHandler for InternalHttpQueryHandler {
         {
             let key = query_cache::EntryKey::from(&query);
             let env = query_cache::EntryEnv::try_from((&key, state.as_ref()))?;
+            let canister = state.get_active_canister(&key.receiver)?;
 
-            if let Some(result) = self.query_cache.get_valid_result(&key, &env) {
+            // Entries kept valid by their data dependencies may outlive a
+            // change of the balance, so check the freezing threshold first.
+            if self.config.query_cache_data_dependencies == FlagStatus::Enabled {
+                query_context::check_canister_not_frozen(
+                    canister,
+                    &self.cycles_account_manager,
+                    &state.metadata.network_topology,
+                )?;
+            }
+            if let Some(result) = self.query_cache.get_valid_result(&key, &env, canister) {
                 return result;
             }
             (Some(key), Some(env))
@@ -169,7 +185,7 @@ impl QueryHandler for InternalHttpQueryHandler {
             &self.log,
             self.hypervisor.as_ref(),
             self.own_subnet_type,
-            state,
+            Arc::clone(&state),
             data_certificate,
             subnet_available_memory,
             self.config.subnet_memory_capacity,
@@ -182,6 +198,7 @@ impl QueryHandler for InternalHttpQueryHandler {
             self.config.composite_queries,
             query.receiver,
             &self.metrics.query_critical_error,
+            self.cross_subnet_query_client.as_deref(),
         );
         let result = context.run(
             query,
@@ -193,12 +210,52 @@ impl QueryHandler for InternalHttpQueryHandler {
         // Add the query execution result to the query cache  (if the query caching is enabled).
         if self.config.query_caching == FlagStatus::Enabled {
             if let (Some(key), Some(env)) = (cache_entry_key, cache_entry_env) {
-                self.query_cache
-                    .push(key, query_cache::EntryValue::new(env, result.clone()));
+                let dependencies = match self.config.query_cache_data_dependencies {
+                    FlagStatus::Enabled => context.take_query_dependencies().and_then(|d| {
+                        let canister = state.canister_state(&key.receiver)?;
+                        query_cache::EntryDependencies::new(canister, d)
+                    }),
+                    FlagStatus::Disabled => None,
+                };
+                self.query_cache.push(
+                    key,
+                    query_cache::EntryValue::new(env, result.clone(), dependencies),
+                );
             }
         }
         result
     }
+
+    fn cross_subnet_query(
+        &self,
+        query: CrossSubnetQuery,
+        state: Arc<ReplicatedState>,
+    ) -> CrossSubnetQueryResponse {
+        let measurement_scope = MeasurementScope::root(&self.metrics.query);
+        let subnet_available_memory = subnet_memory_capacity(&self.config);
+        // The sender is hosted on another subnet, so no canister executed
+        // here gets a data certificate, like any callee of a composite query.
+        let mut context = query_context::QueryContext::new(
+            &self.log,
+            self.hypervisor.as_ref(),
+            self.own_subnet_type,
+            state,
+            vec![],
+            subnet_available_memory,
+            self.config.subnet_memory_capacity,
+            self.config.max_canister_memory_size,
+            self.max_instructions_per_query,
+            query.max_call_graph_depth,
+            query.max_instructions,
+            query.max_walltime.min(self.config.max_query_call_walltime),
+            self.config.instruction_overhead_per_query_call,
+            self.config.composite_queries,
+            query.sender,
+            &self.metrics.query_critical_error,
+            self.cross_subnet_query_client.as_deref(),
+        );
+        context.run_cross_subnet(query, &measurement_scope)
+    }
 }
 
 impl HttpQueryHandler {
@@ -226,6 +283,14 @@ impl QueryHandler for HttpQueryHandler {
     ) -> Result<WasmResult, UserError> {
         self.internal.query(query, state, data_certificate)
     }
+
+    fn cross_subnet_query(
+        &self,
+        query: CrossSubnetQuery,
+        state: Arc<Self::State>,
+    ) -> CrossSubnetQueryResponse {
+        self.internal.cross_subnet_query(query, state)
+    }
 }
 
 impl Service<(UserQuery, Option<CertificateDelegation>)> for HttpQueryHandler {
@@ -1,7 +1,12 @@
 use ic_base_types::{CanisterId, NumBytes};
 use ic_error_types::UserError;
+use ic_ic00_types::CanisterStatusType;
+use ic_interfaces::execution_environment::QueryDependencies;
 use ic_metrics::MetricsRegistry;
-use ic_replicated_state::ReplicatedState;
+use ic_replicated_state::{
+    CanisterState, ExecutionState, Global, NumWasmPages, PageIndex, PageMap, ReplicatedState,
+};
+use ic_sys::PageBytes;
 use ic_types::{ingress::WasmResult, messages::UserQuery, CountBytes, Cycles, Time, UserId};
 use ic_utils_lru_cache::LruCache;
 use prometheus::{Histogram, IntCounter, IntGauge};
@@ -13,6 +18,7 @@ use crate::metrics::duration_histogram;
 /// Query Cache metrics.
 pub(crate) struct QueryCacheMetrics {
     pub hits: IntCounter,
+    pub hits_by_data_dependencies: IntCounter,
     pub misses: IntCounter,
     pub evicted_entries: IntCounter,
     pub evicted_entries_duration: Histogram,
@@ -20,6 +26,7 @@ pub(crate) struct QueryCacheMetrics {
     pub invalidated_entries_by_time: IntCounter,
     pub invalidated_entries_by_canister_version: IntCounter,
     pub invalidated_entries_by_canister_balance: IntCounter,
+    pub invalidated_entries_by_data_dependencies: IntCounter,
     pub invalidated_entries_duration: Histogram,
     pub count_bytes: IntGauge,
     pub len: IntGauge,
@@ -32,6 +39,10 @@ impl QueryCacheMetrics {
                 "execution_query_cache_hits_total",
                 "The total number of replica side query cache hits",
             ),
+            hits_by_data_dependencies: metrics_registry.int_counter(
+                "execution_query_cache_hits_by_data_dependencies_total",
+                "The total number of query cache hits served because the data read by the query was unchanged, which the canister version and balance based invalidation would have missed",
+            ),
             misses: metrics_registry.int_counter(
                 "execution_query_cache_misses_total",
                 "The total number of replica side query cache misses",
@@ -61,6 +72,10 @@ impl QueryCacheMetrics {
                 "execution_query_cache_invalidated_entries_by_canister_balance_total",
                 "The total number of invalidated entries due to the changed canister balance",
             ),
+            invalidated_entries_by_data_dependencies: metrics_registry.int_counter(
+                "execution_query_cache_invalidated_entries_by_data_dependencies_total",
+                "The total number of invalidated entries with tracked data dependencies that changed",
+            ),
             invalidated_entries_duration: duration_histogram(
                 "execution_query_cache_invalidated_entries_duration_seconds",
                 "The duration of invalidated cache entries in seconds",
@@ -146,28 +161,131 @@ impl TryFrom<(&EntryKey, &ReplicatedState)> for EntryEnv {
     }
 }
 
+////////////////////////////////////////////////////////////////////////
+/// Query Cache entry data dependencies.
+///
+/// Captures the parts of the receiving canister that the query execution
+/// read. As long as they are unchanged, the cached result remains valid even
+/// if the canister version, balance or the batch time changed.
+///
+/// Only copies of the pages the query read are kept, so that cache entries
+/// do not keep whole canister memories of old states alive, and the memory
+/// they take is accounted for by `count_bytes()`.
+pub(crate) struct EntryDependencies {
+    /// Hash of the Wasm module that produced the result.
+    module_hash: [u8; 32],
+    /// Exported globals at the time of the execution.
+    exported_globals: Vec<Global>,
+    /// Wasm heap size and the contents of the pages the query read.
+    heap_size: NumWasmPages,
+    heap_pages: Vec<(PageIndex, PageBytes)>,
+    /// Stable memory size and the contents of the pages the query read.
+    stable_size: NumWasmPages,
+    stable_pages: Vec<(PageIndex, PageBytes)>,
+}
+
+impl CountBytes for EntryDependencies {
+    fn count_bytes(&self) -> usize {
+        size_of_val(self)
+            + size_of_val(self.exported_globals.as_slice())
+            + size_of_val(self.heap_pages.as_slice())
+            + size_of_val(self.stable_pages.as_slice())
+    }
+}
+
+impl EntryDependencies {
+    /// Returns the dependencies of a query executed on the given canister, or
+    /// `None` if the result depends on more than the canister memory.
+    pub(crate) fn new(canister: &CanisterState, dependencies: QueryDependencies) -> Option<Self> {
+        if dependencies.reads_system_state {
+            return None;
+        }
+        let execution_state = canister.execution_state.as_ref()?;
+        let copy_pages = |page_map: &PageMap, pages: Vec<PageIndex>| {
+            pages
+                .into_iter()
+                .map(|page| (page, *page_map.get_page(page)))
+                .collect()
+        };
+        Some(Self {
+            module_hash: execution_state.wasm_binary.binary.module_hash(),
+            exported_globals: execution_state.exported_globals.clone(),
+            heap_size: execution_state.wasm_memory.size,
+            heap_pages: copy_pages(
+                &execution_state.wasm_memory.page_map,
+                dependencies.heap_pages,
+            ),
+            stable_size: execution_state.stable_memory.size,
+            stable_pages: copy_pages(
+                &execution_state.stable_memory.page_map,
+                dependencies.stable_pages,
+            ),
+        })
+    }
+
+    fn is_valid(&self, execution_state: &ExecutionState) -> bool {
+        let unchanged = |pages: &[(PageIndex, PageBytes)], page_map: &PageMap| {
+            pages
+                .iter()
+                .all(|(page, contents)| page_map.get_page(*page) == contents)
+        };
+        self.module_hash == execution_state.wasm_binary.binary.module_hash()
+            && self.exported_globals == execution_state.exported_globals
+            && self.heap_size == execution_state.wasm_memory.size
+            && self.stable_size == execution_state.stable_memory.size
+            && unchanged(&self.heap_pages, &execution_state.wasm_memory.page_map)
+            && unchanged(&self.stable_pages, &execution_state.stable_memory.page_map)
+    }
+}
+
 ////////////////////////////////////////////////////////////////////////
 /// Query Cache entry value.
 pub(crate) struct EntryValue {
     env: EntryEnv,
     result: Result<WasmResult, UserError>,
+    dependencies: Option<EntryDependencies>,
 }
 
 impl CountBytes for EntryValue {
     fn count_bytes(&self) -> usize {
-        self.env.count_bytes() + self.result.count_bytes()
+        self.env.count_bytes()
+            + self.result.count_bytes()
+            + self
+                .dependencies
+                .as_ref()
+                .map_or(0, |dependencies| dependencies.count_bytes())
     }
 }
 
 impl EntryValue {
-    pub(crate) fn new(env: EntryEnv, result: Result<WasmResult, UserError>) -> Self {
-        Self { env, result }
+    pub(crate) fn new(
+        env: EntryEnv,
+        result: Result<WasmResult, UserError>,
+        dependencies: Option<EntryDependencies>,
+    ) -> Self {
+        Self {
+            env,
+            result,
+            dependencies,
+        }
     }
 
     fn is_valid(&self, env: &EntryEnv) -> bool {
         self.env == *env
     }
 
+    /// Checks whether the data the query read is unchanged in the given
+    /// canister. Always false for entries without tracked dependencies.
+    fn is_valid_data_dependencies(&self, canister: &CanisterState) -> bool {
+        match (&self.dependencies, &canister.execution_state) {
+            (Some(dependencies), Some(execution_state)) => {
+                canister.status() == CanisterStatusType::Running
+                    && dependencies.is_valid(execution_state)
+            }
+            _ => false,
+        }
+    }
+
     fn is_valid_time(&self, env: &EntryEnv) -> bool {
         self.env.batch_time == env.batch_time
     }
@@ -213,19 +331,27 @@ impl QueryCache {
         }
     }
 
+    /// Returns the cached result if it is still valid for the given
+    /// environment. Entries with tracked data dependencies also stay valid
+    /// while the data they read in the receiving `canister` is unchanged.
     pub(crate) fn get_valid_result(
         &self,
         key: &EntryKey,
         env: &EntryEnv,
+        canister: &CanisterState,
     ) -> Option<Result<WasmResult, UserError>> {
         let mut cache = self.cache.lock().unwrap();
         let now = env.batch_time;
 
         if let Some(value) = cache.get(key) {
-            if value.is_valid(env) {
+            let valid_env = value.is_valid(env);
+            if valid_env || value.is_valid_data_dependencies(canister) {
                 let res = value.result();
                 // Update the metrics.
                 self.metrics.hits.inc();
+                if !valid_env {
+                    self.metrics.hits_by_data_dependencies.inc();
+                }
                 let count_bytes = cache.count_bytes() as i64;
                 self.metrics.count_bytes.set(count_bytes);
                 // The cache entry is valid, return it.
@@ -246,6 +372,9 @@ impl QueryCache {
                 if !value.is_valid_canister_balance(env) {
                     self.metrics.invalidated_entries_by_canister_balance.inc();
                 }
+                if value.dependencies.is_some() {
+                    self.metrics.invalidated_entries_by_data_dependencies.inc();
+                }
                 // The cache entry is no longer valid, remove it.
                 cache.pop(key);
             }
@@ -292,7 +421,7 @@ mod tests {
             canister_version: 1,
             canister_balance: Cycles::new(0),
         };
-        let entry_value = EntryValue::new(entry_env, Result::Ok(WasmResult::Reply(vec![])));
+        let entry_value = EntryValue::new(entry_env, Result::Ok(WasmResult::Reply(vec![])), None);
         let forward_time = current_time + Duration::from_secs(2);
         assert_eq!(2.0, entry_value.elapsed_seconds(forward_time));
 
@@ -43,6 +43,10 @@ struct PendingCall(CanisterState, CallOrigin, VecDeque<Arc<Request>>);
 /// - the limit on the total number of executed instructions by all queries and
 ///   response callbacks.
 ///
+/// Query calls to canisters on other subnets are forwarded along with the
+/// remaining depth and instructions. The callee's subnet evaluates the
+/// sub-graph of the call and returns only its response.
+///
 /// A note on re-entrancy: currently re-entrant query calls are not allowed.
 /// In other words, if a canister is in the call stack, then an attemp to make a
 /// new query call to that canister will result in an error. This restriction
@@ -122,7 +126,10 @@ pub(super) fn evaluate_query_call_graph(
                     // properly handle the response of the callee.
                     call_stack.push(PendingCall(canister, call_origin, requests));
 
-                    match query_context.handle_request(request, measurement_scope) {
+                    // The callee's depth is the number of its ancestors.
+                    let call_graph_depth = call_stack.len();
+                    match query_context.handle_request(request, call_graph_depth, measurement_scope)
+                    {
                         ExecutionResult::Calls(canister, call_origin, requests) => {
                             call_stack.push(PendingCall(canister, call_origin, requests));
                         }
@@ -1,7 +1,7 @@
 use crate::{
     execution::common::{self, validate_method},
     execution::nonreplicated_query::execute_non_replicated_query,
-    execution_environment::{as_round_instructions, RoundLimits},
+    execution_environment::{as_num_instructions, as_round_instructions, RoundLimits},
     hypervisor::Hypervisor,
     metrics::{MeasurementScope, QueryHandlerMetrics, QUERY_HANDLER_CRITICAL_ERROR},
     NonReplicatedQueryKind, RoundInstructions,
@@ -12,7 +12,8 @@ use ic_constants::SMALL_APP_SUBNET_MAX_SIZE;
 use ic_cycles_account_manager::CyclesAccountManager;
 use ic_error_types::{ErrorCode, RejectCode, UserError};
 use ic_interfaces::execution_environment::{
-    ExecutionComplexity, ExecutionMode, HypervisorError, SubnetAvailableMemory,
+    CrossSubnetQuery, CrossSubnetQueryClient, CrossSubnetQueryResponse, ExecutionComplexity,
+    ExecutionMode, HypervisorError, QueryDependencies, SubnetAvailableMemory,
 };
 use ic_logger::{error, ReplicaLogger};
 use ic_registry_subnet_type::SubnetType;
@@ -22,9 +23,12 @@ use ic_replicated_state::{
 use ic_system_api::{ApiType, ExecutionParameters, InstructionLimits};
 use ic_types::{
     ingress::WasmResult,
-    messages::{Payload, RejectContext, Request, RequestOrResponse, Response, UserQuery},
+    messages::{
+        CallbackId, Payload, RejectContext, Request, RequestOrResponse, Response, UserQuery,
+        NO_DEADLINE,
+    },
     methods::WasmMethod,
-    CanisterId, Cycles, NumInstructions, NumMessages, Time,
+    CanisterId, Cycles, NumInstructions, NumMessages, SubnetId, Time,
 };
 use ic_types::{
     methods::{FuncRef, WasmClosure},
@@ -74,6 +78,43 @@ fn wasm_query_method(
     }
 }
 
+/// Returns a reject response to the given request.
+fn reject_response(request: &Request, code: RejectCode, message: String) -> Response {
+    Response {
+        originator: request.sender,
+        respondent: request.receiver,
+        originator_reply_callback: request.sender_reply_callback,
+        response_payload: Payload::Reject(RejectContext::new(code, message)),
+        refund: Cycles::zero(),
+        deadline: request.deadline,
+    }
+}
+
+/// Returns an error if the canister is frozen and hence cannot process queries.
+pub(super) fn check_canister_not_frozen(
+    canister: &CanisterState,
+    cycles_account_manager: &CyclesAccountManager,
+    network_topology: &NetworkTopology,
+) -> Result<(), UserError> {
+    let subnet_size = network_topology
+        .get_subnet_size(&cycles_account_manager.get_subnet_id())
+        .unwrap_or(SMALL_APP_SUBNET_MAX_SIZE);
+    if cycles_account_manager.freeze_threshold_cycles(
+        canister.system_state.freeze_threshold,
+        canister.system_state.memory_allocation,
+        canister.memory_usage(),
+        canister.scheduler_state.compute_allocation,
+        subnet_size,
+    ) > canister.system_state.balance()
+    {
+        return Err(UserError::new(
+            ErrorCode::CanisterOutOfCycles,
+            format!("Canister {} is unable to process query calls because it's frozen. Please top up the canister with cycles and try again.", canister.canister_id()))
+        );
+    }
+    Ok(())
+}
+
 /// Executes a single user query along with its outgoing query calls.
 pub(super) struct QueryContext<'a> {
     log: &'a ReplicaLogger,
@@ -95,6 +136,11 @@ pub(super) struct QueryContext<'a> {
     query_context_time_limit: Duration,
     query_critical_error: &'a IntCounter,
     subnet_memory_capacity: NumBytes,
+    // The inputs of the user query if it was answered by a single execution
+    // of the receiver.
+    query_dependencies: Option<QueryDependencies>,
+    // Forwards query calls to canisters on other subnets, if available.
+    cross_subnet_query_client: Option<&'a dyn CrossSubnetQueryClient>,
 }
 
 impl<'a> QueryContext<'a> {
@@ -116,6 +162,7 @@ impl<'a> QueryContext<'a> {
         composite_queries: FlagStatus,
         canister_id: CanisterId,
         query_critical_error: &'a IntCounter,
+        cross_subnet_query_client: Option<&'a dyn CrossSubnetQueryClient>,
     ) -> Self {
         let network_topology = Arc::new(state.metadata.network_topology.clone());
         let round_limits = RoundLimits {
@@ -144,9 +191,16 @@ impl<'a> QueryContext<'a> {
             query_context_time_limit: max_query_call_walltime,
             query_critical_error,
             subnet_memory_capacity,
+            query_dependencies: None,
+            cross_subnet_query_client,
         }
     }
 
+    /// Returns the inputs the last user query depended on, if known.
+    pub(super) fn take_query_dependencies(&mut self) -> Option<QueryDependencies> {
+        self.query_dependencies.take()
+    }
+
     /// Executes the given query sent by an end user.
     ///
     /// - If it produces a response return the response.
@@ -166,24 +220,11 @@ impl<'a> QueryContext<'a> {
     ) -> Result<WasmResult, UserError> {
         let canister_id = query.receiver;
         let old_canister = self.state.get_active_canister(&canister_id)?;
-
-        let subnet_size = self
-            .network_topology
-            .get_subnet_size(&cycles_account_manager.get_subnet_id())
-            .unwrap_or(SMALL_APP_SUBNET_MAX_SIZE);
-        if cycles_account_manager.freeze_threshold_cycles(
-            old_canister.system_state.freeze_threshold,
-            old_canister.system_state.memory_allocation,
-            old_canister.memory_usage(),
-            old_canister.scheduler_state.compute_allocation,
-            subnet_size,
-        ) > old_canister.system_state.balance()
-        {
-            return Err(UserError::new(
-                ErrorCode::CanisterOutOfCycles,
-                format!("Canister {} is unable to process query calls because it's frozen. Please top up the canister with cycles and try again.", canister_id))
-            );
-        }
+        check_canister_not_frozen(
+            old_canister,
+            &cycles_account_manager,
+            &self.network_topology,
+        )?;
 
         let call_origin = CallOrigin::Query(query.source);
 
@@ -204,7 +245,7 @@ impl<'a> QueryContext<'a> {
             }
         };
 
-        let (mut canister, mut result) = {
+        let (mut canister, mut result, mut dependencies) = {
             let measurement_scope =
                 MeasurementScope::nested(&metrics.query_initial_call, measurement_scope);
             self.execute_query(
@@ -229,7 +270,7 @@ impl<'a> QueryContext<'a> {
                     let measurement_scope =
                         MeasurementScope::nested(&metrics.query_retry_call, measurement_scope);
                     let old_canister = self.state.get_active_canister(&canister_id)?;
-                    let (new_canister, new_result) = self.execute_query(
+                    let (new_canister, new_result, new_dependencies) = self.execute_query(
                         old_canister.clone(),
                         method,
                         query.method_payload.as_slice(),
@@ -240,10 +281,17 @@ impl<'a> QueryContext<'a> {
                     );
                     canister = new_canister;
                     result = new_result;
+                    dependencies = new_dependencies;
                 }
             };
         }
 
+        // A query that spawned calls also depends on the state of the callees,
+        // so only the results of a single execution have known dependencies.
+        if !matches!(result, Ok(None)) {
+            self.query_dependencies = dependencies;
+        }
+
         match result {
             // If the canister produced a result or if execution failed then it
             // does not matter whether or not it produced any outgoing requests.
@@ -279,6 +327,73 @@ impl<'a> QueryContext<'a> {
         }
     }
 
+    /// Executes the given query call made by a composite query on another
+    /// subnet, along with the query calls it makes in turn.
+    ///
+    /// The call graph limits of the caller carry over: the callee is the root
+    /// of a call graph with the remaining depth and instructions.
+    pub(super) fn run_cross_subnet<'b>(
+        &mut self,
+        query: CrossSubnetQuery,
+        measurement_scope: &MeasurementScope<'b>,
+    ) -> CrossSubnetQueryResponse {
+        let request = Arc::new(Request {
+            receiver: query.receiver,
+            sender: query.sender,
+            // The callback is only used to address the response, which is
+            // returned directly to the caller's subnet.
+            sender_reply_callback: CallbackId::from(0),
+            payment: Cycles::zero(),
+            method_name: query.method_name,
+            method_payload: query.method_payload,
+            deadline: NO_DEADLINE,
+        });
+        let instructions_before = self.round_limits.instructions;
+
+        // Never forward the query again: the caller's subnet routed it here,
+        // so a disagreement about the receiver's subnet must not cause a loop.
+        let response = if self.remote_subnet(&request.receiver).is_some() {
+            QueryResponse::CanisterResponse(reject_response(
+                &request,
+                RejectCode::DestinationInvalid,
+                format!(
+                    "Canister {} is not hosted on subnet {}",
+                    request.receiver,
+                    self.hypervisor.subnet_id()
+                ),
+            ))
+        } else {
+            match self.handle_local_request(Arc::clone(&request), measurement_scope) {
+                ExecutionResult::Response(response) => response,
+                ExecutionResult::Calls(canister, call_origin, requests) => {
+                    evaluate_query_call_graph(
+                        self,
+                        canister,
+                        call_origin,
+                        requests,
+                        query.max_call_graph_depth,
+                        measurement_scope,
+                    )
+                }
+                ExecutionResult::SystemError(err) => QueryResponse::UserError(err),
+            }
+        };
+
+        let payload = match response {
+            QueryResponse::CanisterResponse(response) => response.response_payload,
+            QueryResponse::UserError(err) => Payload::Reject(RejectContext::from(err)),
+            QueryResponse::UserResponse(_) => {
+                unreachable!("A canister query cannot produce a user response.");
+            }
+        };
+        CrossSubnetQueryResponse {
+            payload,
+            instructions_executed: as_num_instructions(
+                instructions_before - self.round_limits.instructions,
+            ),
+        }
+    }
+
     // A helper function that extracts the query calls of the given canister and
     // enqueues them onto the given deque.
     fn extract_query_requests(
@@ -361,7 +476,11 @@ impl<'a> QueryContext<'a> {
         method_payload: &[u8],
         query_kind: NonReplicatedQueryKind,
         measurement_scope: &MeasurementScope,
-    ) -> (CanisterState, Result<Option<WasmResult>, UserError>) {
+    ) -> (
+        CanisterState,
+        Result<Option<WasmResult>, UserError>,
+        Option<QueryDependencies>,
+    ) {
         if let WasmMethod::CompositeQuery(_) = &method_name {
             if self.composite_queries == FlagStatus::Disabled {
                 return (
@@ -370,6 +489,7 @@ impl<'a> QueryContext<'a> {
                         ErrorCode::CanisterContractViolation,
                         "Composite queries are not enabled yet",
                     )),
+                    None,
                 );
             }
         }
@@ -381,7 +501,7 @@ impl<'a> QueryContext<'a> {
         let execution_parameters = self.execution_parameters(&canister, instruction_limits);
 
         let data_certificate = self.get_data_certificate(&canister.canister_id());
-        let (canister, instructions_left, result) = execute_non_replicated_query(
+        let (canister, instructions_left, result, dependencies) = execute_non_replicated_query(
             query_kind,
             method_name,
             method_payload,
@@ -399,7 +519,7 @@ impl<'a> QueryContext<'a> {
             NumSlices::from(1),
             NumMessages::from(1),
         );
-        (canister, result)
+        (canister, result, dependencies)
     }
 
     fn execute_callback(
@@ -451,7 +571,7 @@ impl<'a> QueryContext<'a> {
         };
         let func_ref = match call_origin {
             CallOrigin::Ingress(_, _)
-            | CallOrigin::CanisterUpdate(_, _)
+            | CallOrigin::CanisterUpdate(_, _, _)
             | CallOrigin::SystemTask => unreachable!("Unreachable in the QueryContext."),
             CallOrigin::CanisterQuery(_, _) | CallOrigin::Query(_) => {
                 FuncRef::QueryClosure(closure)
@@ -567,7 +687,7 @@ impl<'a> QueryContext<'a> {
     ) -> (NumInstructions, Result<Option<WasmResult>, HypervisorError>) {
         let func_ref = match call_origin {
             CallOrigin::Ingress(_, _)
-            | CallOrigin::CanisterUpdate(_, _)
+            | CallOrigin::CanisterUpdate(_, _, _)
             | CallOrigin::SystemTask => unreachable!("Unreachable in the QueryContext."),
             CallOrigin::CanisterQuery(_, _) | CallOrigin::Query(_) => {
                 FuncRef::QueryClosure(cleanup_closure)
@@ -615,7 +735,90 @@ impl<'a> QueryContext<'a> {
     /// of outgoing query calls (requests).
     /// If the execution produces a response, then the function returns it and
     /// discards the call context and outgoing requests.
+    ///
+    /// Query calls to canisters on other subnets are forwarded and always
+    /// return a response. `call_graph_depth` is the depth of the callee in the
+    /// query call graph, which bounds the depth available to the forwarded
+    /// call.
     pub fn handle_request(
+        &mut self,
+        request: Arc<Request>,
+        call_graph_depth: usize,
+        measurement_scope: &MeasurementScope,
+    ) -> ExecutionResult {
+        match self.remote_subnet(&request.receiver) {
+            Some(subnet_id) => {
+                self.handle_cross_subnet_request(subnet_id, request, call_graph_depth)
+            }
+            None => self.handle_local_request(request, measurement_scope),
+        }
+    }
+
+    // Returns the subnet hosting the given canister if it is not this subnet.
+    fn remote_subnet(&self, canister_id: &CanisterId) -> Option<SubnetId> {
+        let subnet_id = self
+            .network_topology
+            .routing_table
+            .route(canister_id.get())?;
+        (subnet_id != self.hypervisor.subnet_id()).then_some(subnet_id)
+    }
+
+    // Forwards the query call to the given subnet and returns its response, or
+    // a reject response if the subnet cannot be reached.
+    fn handle_cross_subnet_request(
+        &mut self,
+        subnet_id: SubnetId,
+        request: Arc<Request>,
+        call_graph_depth: usize,
+    ) -> ExecutionResult {
+        let query = CrossSubnetQuery {
+            sender: request.sender,
+            receiver: request.receiver,
+            method_name: request.method_name.clone(),
+            method_payload: request.method_payload.clone(),
+            max_call_graph_depth: self
+                .max_query_call_graph_depth
+                .saturating_sub(call_graph_depth),
+            max_instructions: as_num_instructions(self.round_limits.instructions),
+            max_walltime: self
+                .query_context_time_limit
+                .saturating_sub(self.query_context_time_start.elapsed()),
+        };
+        let own_subnet_id = self.hypervisor.subnet_id();
+        let unreachable_response = |reason: String| {
+            reject_response(
+                &request,
+                RejectCode::SysTransient,
+                format!(
+                    "Canister {} is hosted on subnet {}, which is unreachable from subnet {}: {}",
+                    request.receiver, subnet_id, own_subnet_id, reason
+                ),
+            )
+        };
+        let response = match self.cross_subnet_query_client {
+            Some(client) => match client.query(subnet_id, query) {
+                Ok(response) => {
+                    self.round_limits.instructions -=
+                        as_round_instructions(response.instructions_executed);
+                    Response {
+                        originator: request.sender,
+                        respondent: request.receiver,
+                        originator_reply_callback: request.sender_reply_callback,
+                        response_payload: response.payload,
+                        refund: Cycles::zero(),
+                        deadline: request.deadline,
+                    }
+                }
+                Err(err) => unreachable_response(err),
+            },
+            None => unreachable_response("cross-subnet query calls are not supported".to_string()),
+        };
+        self.round_limits.instructions -= self.instruction_overhead_per_query_call;
+        ExecutionResult::Response(QueryResponse::CanisterResponse(response))
+    }
+
+    // Executes a query call to a canister on this subnet.
+    fn handle_local_request(
         &mut self,
         request: Arc<Request>,
         measurement_scope: &MeasurementScope,
@@ -628,6 +831,7 @@ impl<'a> QueryContext<'a> {
                 originator_reply_callback: request.sender_reply_callback,
                 response_payload: payload,
                 refund: Cycles::zero(),
+                deadline: request.deadline,
             })
         };
 
@@ -653,7 +857,7 @@ impl<'a> QueryContext<'a> {
             }
         };
 
-        let (mut canister, result) = self.execute_query(
+        let (mut canister, result, _) = self.execute_query(
             canister.clone(),
             method,
             request.method_payload.as_slice(),
@@ -780,7 +984,7 @@ impl<'a> QueryContext<'a> {
             };
 
         match call_origin {
-            CallOrigin::CanisterUpdate(_, _)
+            CallOrigin::CanisterUpdate(_, _, _)
             | CallOrigin::Ingress(_, _)
             | CallOrigin::SystemTask => {
                 error!(
@@ -815,6 +1019,7 @@ impl<'a> QueryContext<'a> {
                         originator_reply_callback: callback_id,
                         refund: Cycles::zero(),
                         response_payload: payload,
+                        deadline: NO_DEADLINE,
                     };
                     QueryResponse::CanisterResponse(response)
                 };
@@ -865,7 +1070,7 @@ impl<'a> QueryContext<'a> {
         );
         match call_origin {
             CallOrigin::Ingress(_, _)
-            | CallOrigin::CanisterUpdate(_, _)
+            | CallOrigin::CanisterUpdate(_, _, _)
             | CallOrigin::SystemTask => {
                 unreachable!("Expected a query call context");
             }
@@ -877,6 +1082,7 @@ impl<'a> QueryContext<'a> {
                     originator_reply_callback: callback_id,
                     refund: Cycles::zero(),
                     response_payload: Payload::Reject(RejectContext::from(error)),
+                    deadline: NO_DEADLINE,
                 };
                 QueryResponse::CanisterResponse(response)
             }
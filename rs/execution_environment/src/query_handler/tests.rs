@@ -4,7 +4,7 @@ use ic_config::execution_environment::INSTRUCTION_OVERHEAD_PER_QUERY_CALL;
 use ic_error_types::{ErrorCode, UserError};
 use ic_interfaces::messages::CanisterTask;
 use ic_registry_subnet_type::SubnetType;
-use ic_replicated_state::canister_state::system_state::CyclesUseCase;
+use ic_replicated_state::{canister_state::system_state::CyclesUseCase, page_map::PAGE_SIZE};
 use ic_test_utilities::{
     types::ids::user_test_id,
     universal_canister::{call_args, wasm},
@@ -1064,6 +1064,143 @@ fn query_cache_capacity_zero() {
     }
 }
 
+#[test]
+fn query_cache_data_dependencies_survive_canister_version_and_balance_change() {
+    let mut test = ExecutionTestBuilder::new()
+        .with_query_caching()
+        .with_query_cache_data_dependencies()
+        .build();
+    let canister_id = test.universal_canister_with_cycles(CYCLES_BALANCE).unwrap();
+    let query = UserQuery {
+        source: user_test_id(1),
+        receiver: canister_id,
+        method_name: "query".into(),
+        method_payload: wasm().reply_data(&[42]).build(),
+        ingress_expiry: 0,
+        nonce: None,
+    };
+    let output_1 = test.query(query.clone(), Arc::new(test.state().clone()), vec![]);
+    assert_eq!(output_1, Ok(WasmResult::Reply([42].into())));
+
+    let canister = test.canister_state_mut(canister_id);
+    canister.system_state.canister_version += 1;
+    canister
+        .system_state
+        .remove_cycles(1_u128.into(), CyclesUseCase::Memory);
+    let output_2 = test.query(query, Arc::new(test.state().clone()), vec![]);
+
+    let metrics = &downcast_query_handler(test.query_handler())
+        .query_cache
+        .metrics;
+    assert_eq!(output_1, output_2);
+    assert_eq!(1, metrics.misses.get());
+    assert_eq!(1, metrics.hits.get());
+    assert_eq!(1, metrics.hits_by_data_dependencies.get());
+    assert_eq!(0, metrics.invalidated_entries.get());
+}
+
+#[test]
+fn query_cache_data_dependencies_invalidated_by_changed_memory() {
+    let mut test = ExecutionTestBuilder::new()
+        .with_query_caching()
+        .with_query_cache_data_dependencies()
+        .build();
+    let canister_id = test.universal_canister_with_cycles(CYCLES_BALANCE).unwrap();
+    let query = UserQuery {
+        source: user_test_id(1),
+        receiver: canister_id,
+        method_name: "query".into(),
+        method_payload: wasm().get_global_data().append_and_reply().build(),
+        ingress_expiry: 0,
+        nonce: None,
+    };
+    let output_1 = test.query(query.clone(), Arc::new(test.state().clone()), vec![]);
+    assert_eq!(output_1, Ok(WasmResult::Reply(vec![])));
+
+    test.ingress(
+        canister_id,
+        "update",
+        wasm().set_global_data(&[1, 2, 3]).reply().build(),
+    )
+    .unwrap();
+    let output_2 = test.query(query, Arc::new(test.state().clone()), vec![]);
+
+    let metrics = &downcast_query_handler(test.query_handler())
+        .query_cache
+        .metrics;
+    assert_eq!(output_2, Ok(WasmResult::Reply(vec![1, 2, 3])));
+    assert_eq!(2, metrics.misses.get());
+    assert_eq!(0, metrics.hits.get());
+    assert_eq!(1, metrics.invalidated_entries.get());
+    assert_eq!(1, metrics.invalidated_entries_by_data_dependencies.get());
+}
+
+#[test]
+fn query_cache_data_dependencies_count_the_pages_read() {
+    let mut test = ExecutionTestBuilder::new()
+        .with_query_caching()
+        .with_query_cache_data_dependencies()
+        .build();
+    let canister_id = test.universal_canister_with_cycles(CYCLES_BALANCE).unwrap();
+    let query = UserQuery {
+        source: user_test_id(1),
+        receiver: canister_id,
+        method_name: "query".into(),
+        method_payload: wasm().get_global_data().append_and_reply().build(),
+        ingress_expiry: 0,
+        nonce: None,
+    };
+    let output = test.query(query, Arc::new(test.state().clone()), vec![]);
+    assert_eq!(output, Ok(WasmResult::Reply(vec![])));
+
+    // The cache entry holds copies of the Wasm pages read by the query.
+    let count_bytes = downcast_query_handler(test.query_handler())
+        .query_cache
+        .count_bytes();
+    assert!(count_bytes > PAGE_SIZE);
+}
+
+#[test]
+fn query_cache_data_dependencies_not_tracked_when_reading_system_state() {
+    let mut test = ExecutionTestBuilder::new()
+        .with_query_caching()
+        .with_query_cache_data_dependencies()
+        // Use system subnet so all the executions are free.
+        .with_subnet_type(SubnetType::System)
+        // The reply size is given by the cycles balance.
+        .with_initial_canister_cycles(100)
+        .build();
+    let canister_id = test.canister_from_wat(QUERY_CACHE_WAT).unwrap();
+    let query = UserQuery {
+        source: user_test_id(1),
+        receiver: canister_id,
+        method_name: "canister_balance_sized_reply".into(),
+        method_payload: vec![],
+        ingress_expiry: 0,
+        nonce: None,
+    };
+    let output_1 = test
+        .query(query.clone(), Arc::new(test.state().clone()), vec![])
+        .unwrap();
+    assert_eq!(100, output_1.count_bytes());
+
+    test.canister_state_mut(canister_id)
+        .system_state
+        .remove_cycles(Cycles::new(1), CyclesUseCase::Memory);
+    let output_2 = test
+        .query(query, Arc::new(test.state().clone()), vec![])
+        .unwrap();
+
+    let metrics = &downcast_query_handler(test.query_handler())
+        .query_cache
+        .metrics;
+    assert_eq!(99, output_2.count_bytes());
+    assert_eq!(2, metrics.misses.get());
+    assert_eq!(0, metrics.hits_by_data_dependencies.get());
+    assert_eq!(1, metrics.invalidated_entries_by_canister_balance.get());
+    assert_eq!(0, metrics.invalidated_entries_by_data_dependencies.get());
+}
+
 #[test]
 fn query_call_with_side_effects() {
     // In this test we have two canisters A and B.
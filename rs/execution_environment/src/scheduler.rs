@@ -12,7 +12,7 @@ use ic_config::subnet_config::SchedulerConfig;
 use ic_crypto_prng::{Csprng, RandomnessPurpose::ExecutionThread};
 use ic_cycles_account_manager::CyclesAccountManager;
 use ic_error_types::{ErrorCode, UserError};
-use ic_ic00_types::{CanisterStatusType, EcdsaKeyId, Method as Ic00Method};
+use ic_ic00_types::{CanisterStatusType, EcdsaKeyId, Method as Ic00Method, SchnorrKeyId};
 use ic_interfaces::execution_environment::{
     ExecutionComplexity, ExecutionRoundType, RegistryExecutionSettings,
 };
@@ -31,7 +31,7 @@ use ic_replicated_state::{
 };
 use ic_system_api::InstructionLimits;
 use ic_types::{
-    crypto::canister_threshold_sig::MasterEcdsaPublicKey,
+    crypto::canister_threshold_sig::{MasterEcdsaPublicKey, MasterSchnorrPublicKey},
     ingress::{IngressState, IngressStatus},
     messages::{Ingress, MessageId},
     AccumulatedPriority, CanisterId, ComputeAllocation, Cycles, ExecutionRound, LongExecutionMode,
@@ -454,6 +454,7 @@ impl SchedulerImpl {
         long_running_canister_ids: BTreeSet<CanisterId>,
         registry_settings: &RegistryExecutionSettings,
         ecdsa_subnet_public_keys: &BTreeMap<EcdsaKeyId, MasterEcdsaPublicKey>,
+        schnorr_subnet_public_keys: &BTreeMap<SchnorrKeyId, MasterSchnorrPublicKey>,
     ) -> ReplicatedState {
         loop {
             let mut available_subnet_messages = false;
@@ -486,6 +487,7 @@ impl SchedulerImpl {
                     instruction_limits,
                     csprng,
                     ecdsa_subnet_public_keys,
+                    schnorr_subnet_public_keys,
                     registry_settings,
                     round_limits,
                 );
@@ -532,6 +534,10 @@ impl SchedulerImpl {
         let mut ingress_execution_results = Vec::new();
         let mut is_first_iteration = true;
         let mut round_filtered_canisters = FilteredCanisters::new();
+        // The canisters that executed in at least one iteration and the ones
+        // that were skipped due to the round limits in some iteration.
+        let mut round_executed_canister_ids = BTreeSet::new();
+        let mut round_skipped_canister_ids = BTreeSet::new();
 
         let mut total_heap_delta = NumBytes::from(0);
 
@@ -613,20 +619,36 @@ impl SchedulerImpl {
                     }
                 }
             }
+            let active_canister_ids: Vec<_> = active_canisters_partitioned_by_cores
+                .iter()
+                .flatten()
+                .map(|canister| canister.canister_id())
+                .collect();
             drop(preparation_timer);
 
             let instructions_before = round_limits.instructions;
-            let (executed_canisters, mut loop_ingress_execution_results, heap_delta) = self
-                .execute_canisters_in_inner_round(
-                    active_canisters_partitioned_by_cores,
-                    current_round,
-                    state.time(),
-                    Arc::new(state.metadata.network_topology.clone()),
-                    &measurement_scope,
-                    round_limits,
-                    subnet_size,
-                );
+            let (
+                executed_canisters,
+                mut loop_ingress_execution_results,
+                heap_delta,
+                skipped_canister_ids,
+            ) = self.execute_canisters_in_inner_round(
+                active_canisters_partitioned_by_cores,
+                current_round,
+                state.time(),
+                Arc::new(state.metadata.network_topology.clone()),
+                &measurement_scope,
+                round_limits,
+                subnet_size,
+            );
             let instructions_consumed = instructions_before - round_limits.instructions;
+            for canister_id in active_canister_ids {
+                if skipped_canister_ids.contains(&canister_id) {
+                    round_skipped_canister_ids.insert(canister_id);
+                } else {
+                    round_executed_canister_ids.insert(canister_id);
+                }
+            }
 
             let finalization_timer = self.metrics.round_inner_iteration_fin.start_timer();
             total_heap_delta += heap_delta;
@@ -677,6 +699,17 @@ impl SchedulerImpl {
             drop(finalization_timer);
         }; // end iteration loop.
 
+        // A canister is counted as skipped due to the round limits at most
+        // once per round, and only if it did not execute in any iteration.
+        for canister_id in round_skipped_canister_ids.difference(&round_executed_canister_ids) {
+            if let Some(canister) = state.canister_state_mut(canister_id) {
+                canister
+                    .system_state
+                    .canister_metrics
+                    .skipped_round_due_to_limits += 1;
+            }
+        }
+
         {
             let _timer = self
                 .metrics
@@ -753,7 +786,8 @@ impl SchedulerImpl {
     /// - the new states of the canisters,
     /// - the ingress results,
     /// - the maximum number of instructions executed on a thread,
-    /// - the total heap delta.
+    /// - the total heap delta,
+    /// - the IDs of the canisters skipped because the round limits were reached.
     #[allow(clippy::too_many_arguments, clippy::type_complexity)]
     fn execute_canisters_in_inner_round(
         &self,
@@ -768,6 +802,7 @@ impl SchedulerImpl {
         Vec<CanisterState>,
         Vec<(MessageId, IngressStatus)>,
         NumBytes,
+        BTreeSet<CanisterId>,
     ) {
         let thread_pool = &mut self.thread_pool.borrow_mut();
         let exec_env = self.exec_env.as_ref();
@@ -775,11 +810,9 @@ impl SchedulerImpl {
         // If there are no more instructions left, then skip execution and
         // return unchanged canisters.
         if round_limits.reached() {
-            return (
-                canisters_by_thread.into_iter().flatten().collect(),
-                vec![],
-                NumBytes::from(0),
-            );
+            let canisters: Vec<_> = canisters_by_thread.into_iter().flatten().collect();
+            let skipped_canister_ids = canisters.iter().map(|c| c.canister_id()).collect();
+            return (canisters, vec![], NumBytes::from(0), skipped_canister_ids);
         }
 
         // Reserve the space for holding the result of each execution thread.
@@ -846,8 +879,10 @@ impl SchedulerImpl {
         let mut max_instructions_executed_per_thread = NumInstructions::from(0);
         let mut max_execution_complexity_per_thread = ExecutionComplexity::default();
         let mut heap_delta = NumBytes::from(0);
+        let mut skipped_canister_ids = BTreeSet::new();
         for mut result in results_by_thread.into_iter() {
             canisters.append(&mut result.canisters);
+            skipped_canister_ids.append(&mut result.skipped_canister_ids);
             ingress_results.append(&mut result.ingress_results);
             let instructions_executed = as_num_instructions(
                 round_limits_per_thread.instructions - result.round_limits.instructions,
@@ -885,7 +920,7 @@ impl SchedulerImpl {
         self.metrics
             .instructions_consumed_per_round
             .observe(total_instructions_executed.get() as f64);
-        (canisters, ingress_results, heap_delta)
+        (canisters, ingress_results, heap_delta, skipped_canister_ids)
     }
 
     fn process_stopping_canisters(&self, state: ReplicatedState) -> ReplicatedState {
@@ -1290,6 +1325,7 @@ impl Scheduler for SchedulerImpl {
         mut state: ReplicatedState,
         randomness: Randomness,
         ecdsa_subnet_public_keys: BTreeMap<EcdsaKeyId, MasterEcdsaPublicKey>,
+        schnorr_subnet_public_keys: BTreeMap<SchnorrKeyId, MasterSchnorrPublicKey>,
         current_round: ExecutionRound,
         current_round_type: ExecutionRoundType,
         registry_settings: &RegistryExecutionSettings,
@@ -1425,6 +1461,7 @@ impl Scheduler for SchedulerImpl {
                     instruction_limits,
                     &mut csprng,
                     &ecdsa_subnet_public_keys,
+                    &schnorr_subnet_public_keys,
                     registry_settings,
                     &mut round_limits,
                 );
@@ -1469,6 +1506,7 @@ impl Scheduler for SchedulerImpl {
                 long_running_canister_ids,
                 registry_settings,
                 &ecdsa_subnet_public_keys,
+                &schnorr_subnet_public_keys,
             );
         }
 
@@ -1695,6 +1733,7 @@ struct ExecutionThreadResult {
     messages_executed: NumMessages,
     heap_delta: NumBytes,
     round_limits: RoundLimits,
+    skipped_canister_ids: BTreeSet<CanisterId>,
 }
 
 /// Executes the given canisters one by one. For each canister it
@@ -1728,6 +1767,7 @@ fn execute_canisters_on_thread(
     let mut total_slices_executed = NumSlices::from(0);
     let mut total_messages_executed = NumMessages::from(0);
     let mut total_heap_delta = NumBytes::from(0);
+    let mut skipped_canister_ids = BTreeSet::new();
 
     let instruction_limits = InstructionLimits::new(
         deterministic_time_slicing,
@@ -1739,6 +1779,7 @@ fn execute_canisters_on_thread(
         // If no more instructions are left or if heap delta is already too
         // large, then skip execution of the canister and keep its old state.
         if round_limits.reached() || total_heap_delta >= config.max_heap_delta_per_iteration {
+            skipped_canister_ids.insert(canister.canister_id());
             canisters.push(canister);
             continue;
         }
@@ -1804,6 +1845,11 @@ fn execute_canisters_on_thread(
             }
             total_slices_executed.inc_assign();
             canister = new_canister;
+            // Only the slices of long executions are counted, i.e. the ones
+            // that resumed or left behind a paused execution.
+            if canister_had_paused_execution || canister.has_paused_execution() {
+                canister.system_state.canister_metrics.slices_executed += 1;
+            }
             round_limits.instructions -=
                 as_round_instructions(config.instruction_overhead_per_message);
             total_heap_delta += heap_delta;
@@ -1850,6 +1896,7 @@ fn execute_canisters_on_thread(
         messages_executed: total_messages_executed,
         heap_delta: total_heap_delta,
         round_limits,
+        skipped_canister_ids,
     }
 }
 
@@ -1875,6 +1922,13 @@ fn observe_replicated_state_metrics(
     let mut consumed_cycles_total = NominalCycles::new(0);
     let mut consumed_cycles_total_by_use_case = BTreeMap::new();
 
+    let mut rounds_scheduled_as_first = 0;
+    let mut rounds_executed = 0;
+    let mut rounds_interrupted = 0;
+    let mut rounds_skipped_due_to_no_messages = 0;
+    let mut rounds_skipped_due_to_limits = 0;
+    let mut slices_executed = 0;
+
     let mut ingress_queue_message_count = 0;
     let mut ingress_queue_size_bytes = 0;
     let mut input_queues_message_count = 0;
@@ -1919,6 +1973,13 @@ fn observe_replicated_state_metrics(
                 .canister_metrics
                 .get_consumed_cycles_since_replica_started_by_use_cases(),
         );
+        let canister_metrics = &canister.system_state.canister_metrics;
+        rounds_scheduled_as_first += canister_metrics.scheduled_as_first;
+        rounds_executed += canister_metrics.executed;
+        rounds_interrupted += canister_metrics.interruped_during_execution;
+        rounds_skipped_due_to_no_messages += canister_metrics.skipped_round_due_to_no_messages;
+        rounds_skipped_due_to_limits += canister_metrics.skipped_round_due_to_limits;
+        slices_executed += canister_metrics.slices_executed;
         let queues = canister.system_state.queues();
         ingress_queue_message_count += queues.ingress_queue_message_count();
         ingress_queue_size_bytes += queues.ingress_queue_size_bytes();
@@ -1990,10 +2051,36 @@ fn observe_replicated_state_metrics(
     // Add the consumed cycles in http outcalls.
     consumed_cycles_total += state.metadata.subnet_metrics.consumed_cycles_http_outcalls;
 
+    // Add the consumed cycles in Schnorr outcalls, which are only tracked by use case.
+    if let Some(consumed_cycles_schnorr_outcalls) = state
+        .metadata
+        .subnet_metrics
+        .get_consumed_cycles_by_use_case()
+        .get(&CyclesUseCase::SchnorrOutcalls)
+    {
+        consumed_cycles_total += *consumed_cycles_schnorr_outcalls;
+    }
+
     metrics.observe_consumed_cycles(consumed_cycles_total);
 
     metrics.observe_consumed_cycles_by_use_case(&consumed_cycles_total_by_use_case);
 
+    metrics.observe_canister_scheduling_rounds(
+        ROUND_OUTCOME_SCHEDULED_AS_FIRST,
+        rounds_scheduled_as_first,
+    );
+    metrics.observe_canister_scheduling_rounds(ROUND_OUTCOME_EXECUTED, rounds_executed);
+    metrics.observe_canister_scheduling_rounds(ROUND_OUTCOME_INTERRUPTED, rounds_interrupted);
+    metrics.observe_canister_scheduling_rounds(
+        ROUND_OUTCOME_SKIPPED_NO_MESSAGES,
+        rounds_skipped_due_to_no_messages,
+    );
+    metrics.observe_canister_scheduling_rounds(
+        ROUND_OUTCOME_SKIPPED_LIMITS,
+        rounds_skipped_due_to_limits,
+    );
+    metrics.canister_slices_executed.set(slices_executed as i64);
+
     metrics
         .ecdsa_signature_agreements
         .set(state.metadata.subnet_metrics.ecdsa_signature_agreements as i64);
@@ -2081,7 +2168,9 @@ fn can_execute_msg(
         };
 
         // Only one install code message allowed at a time.
-        if let Some(Ic00Method::InstallCode) = maybe_instal_code_method {
+        if let Some(Ic00Method::InstallCode) | Some(Ic00Method::InstallChunkedCode) =
+            maybe_instal_code_method
+        {
             return false;
         }
     }
@@ -2127,10 +2216,20 @@ fn get_instructions_limits_for_subnet_message(
             | SetupInitialDKG
             | SignWithECDSA
             | ComputeInitialEcdsaDealings
+            | SchnorrPublicKey
+            | SignWithSchnorr
+            | UploadChunk
+            | StoredChunks
+            | ClearChunkStore
             | StartCanister
             | StopCanister
             | UninstallCode
             | UpdateSettings
+            | TakeCanisterSnapshot
+            | LoadCanisterSnapshot
+            | ListCanisterSnapshots
+            | DeleteCanisterSnapshot
+            | FetchCanisterLogs
             | BitcoinGetBalance
             | BitcoinGetUtxos
             | BitcoinSendTransaction
@@ -2139,7 +2238,7 @@ fn get_instructions_limits_for_subnet_message(
             | BitcoinGetSuccessors
             | ProvisionalCreateCanisterWithCycles
             | ProvisionalTopUpCanister => default_limits,
-            InstallCode => InstructionLimits::new(
+            InstallCode | InstallChunkedCode => InstructionLimits::new(
                 dts,
                 config.max_instructions_per_install_code,
                 config.max_instructions_per_install_code_slice,
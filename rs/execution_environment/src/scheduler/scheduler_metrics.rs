@@ -40,6 +40,8 @@ pub(super) struct SchedulerMetrics {
     pub(super) available_canister_ids: IntGauge,
     pub(super) consumed_cycles_since_replica_started: Gauge,
     pub(super) consumed_cycles_since_replica_started_by_use_case: GaugeVec,
+    pub(super) canister_scheduling_rounds: IntGaugeVec,
+    pub(super) canister_slices_executed: IntGauge,
     pub(super) input_queue_messages: IntGaugeVec,
     pub(super) input_queues_size_bytes: IntGaugeVec,
     pub(super) queues_response_bytes: IntGauge,
@@ -96,6 +98,13 @@ const LABEL_MESSAGE_KIND: &str = "kind";
 pub(super) const MESSAGE_KIND_INGRESS: &str = "ingress";
 pub(super) const MESSAGE_KIND_CANISTER: &str = "canister";
 
+const LABEL_ROUND_OUTCOME: &str = "outcome";
+pub(super) const ROUND_OUTCOME_SCHEDULED_AS_FIRST: &str = "scheduled_as_first";
+pub(super) const ROUND_OUTCOME_EXECUTED: &str = "executed";
+pub(super) const ROUND_OUTCOME_INTERRUPTED: &str = "interrupted";
+pub(super) const ROUND_OUTCOME_SKIPPED_NO_MESSAGES: &str = "skipped_no_messages";
+pub(super) const ROUND_OUTCOME_SKIPPED_LIMITS: &str = "skipped_limits";
+
 /// Alert for call contexts older than this cutoff (one day).
 pub(super) const OLD_CALL_CONTEXT_CUTOFF_ONE_DAY: Duration = Duration::from_secs(60 * 60 * 24);
 pub(super) const OLD_CALL_CONTEXT_LABEL_ONE_DAY: &str = "1d";
@@ -207,6 +216,15 @@ impl SchedulerMetrics {
                 "Number of cycles consumed since replica started by use cases.",
                 &["use_case"],
             ),
+            canister_scheduling_rounds: metrics_registry.int_gauge_vec(
+                "replicated_state_canister_scheduling_rounds",
+                "Total number of rounds of all canisters on the subnet, by scheduling outcome.",
+                &[LABEL_ROUND_OUTCOME],
+            ),
+            canister_slices_executed: metrics_registry.int_gauge(
+                "replicated_state_canister_slices_executed",
+                "Total number of slices of long executions executed by all canisters on the subnet.",
+            ),
             ecdsa_signature_agreements: metrics_registry.int_gauge(
                 "replicated_state_ecdsa_signature_agreements_total",
                 "Total number of ECDSA signature agreements created",
@@ -595,6 +613,12 @@ impl SchedulerMetrics {
         }
     }
 
+    pub(super) fn observe_canister_scheduling_rounds(&self, outcome: &str, rounds: u64) {
+        self.canister_scheduling_rounds
+            .with_label_values(&[outcome])
+            .set(rounds as i64);
+    }
+
     pub(super) fn observe_input_messages(&self, kind: &str, message_count: usize) {
         self.input_queue_messages
             .with_label_values(&[kind])
@@ -42,7 +42,9 @@ use ic_test_utilities::{
 use ic_test_utilities_metrics::{
     fetch_counter, fetch_gauge, fetch_gauge_vec, fetch_int_gauge, fetch_int_gauge_vec, metric_vec,
 };
-use ic_types::messages::{CallbackId, Payload, RejectContext, Response, MAX_RESPONSE_COUNT_BYTES};
+use ic_types::messages::{
+    CallbackId, Payload, RejectContext, Response, MAX_RESPONSE_COUNT_BYTES, NO_DEADLINE,
+};
 use ic_types::methods::SystemMethod;
 use ic_types::methods::WasmMethod;
 use ic_types::time::expiry_time_from_now;
@@ -1530,6 +1532,50 @@ fn can_execute_messages_from_multiple_canisters_until_out_of_instructions() {
     }
 }
 
+#[test]
+fn canisters_skipped_due_to_round_limits_are_counted() {
+    // In this test we have 4 canisters with 1 input message each, which are
+    // distributed over 2 cores. The first canister on each core consumes all
+    // instructions of the round, so the second canister on each core is
+    // scheduled but skipped.
+    let mut test = SchedulerTestBuilder::new()
+        .with_scheduler_config(SchedulerConfig {
+            scheduler_cores: 2,
+            max_instructions_per_round: NumInstructions::from(10),
+            max_instructions_per_message: NumInstructions::from(10),
+            max_instructions_per_message_without_dts: NumInstructions::from(10),
+            max_instructions_per_slice: NumInstructions::from(10),
+            instruction_overhead_per_message: NumInstructions::from(0),
+            instruction_overhead_per_canister: NumInstructions::from(0),
+            ..SchedulerConfig::application_subnet()
+        })
+        .build();
+
+    for _ in 0..4 {
+        let canister = test.create_canister();
+        test.send_ingress(canister, ingress(10));
+    }
+
+    test.execute_round(ExecutionRoundType::OrdinaryRound);
+
+    let mut executed = 0;
+    let mut skipped_due_to_limits = 0;
+    for canister in test.state().canisters_iter() {
+        let canister_metrics = &canister.system_state.canister_metrics;
+        assert_eq!(
+            canister_metrics.executed + canister_metrics.skipped_round_due_to_limits,
+            1
+        );
+        // The messages complete in a single slice, so they are not counted
+        // as slices of long executions.
+        assert_eq!(canister_metrics.slices_executed, 0);
+        executed += canister_metrics.executed;
+        skipped_due_to_limits += canister_metrics.skipped_round_due_to_limits;
+    }
+    assert_eq!(executed, 2);
+    assert_eq!(skipped_due_to_limits, 2);
+}
+
 #[test]
 fn subnet_messages_respect_instruction_limit_per_round() {
     // In this test we have a canister with 10 input messages and 20 subnet
@@ -2639,6 +2685,49 @@ fn replicated_state_metrics_different_canister_statuses() {
     );
 }
 
+#[test]
+fn replicated_state_metrics_canister_scheduling_stats() {
+    let mut state = ReplicatedState::new(subnet_test_id(1), SubnetType::Application);
+
+    for i in 0..2 {
+        let mut canister = get_running_canister(canister_test_id(i));
+        let canister_metrics = &mut canister.system_state.canister_metrics;
+        canister_metrics.scheduled_as_first = 1;
+        canister_metrics.executed = 2;
+        canister_metrics.interruped_during_execution = 3;
+        canister_metrics.skipped_round_due_to_no_messages = 4;
+        canister_metrics.skipped_round_due_to_limits = 5;
+        canister_metrics.slices_executed = 6;
+        state.put_canister_state(canister);
+    }
+
+    let registry = MetricsRegistry::new();
+    let scheduler_metrics = SchedulerMetrics::new(&registry);
+
+    observe_replicated_state_metrics(
+        subnet_test_id(1),
+        &state,
+        0.into(),
+        &scheduler_metrics,
+        &no_op_logger(),
+    );
+
+    assert_eq!(
+        fetch_int_gauge_vec(&registry, "replicated_state_canister_scheduling_rounds"),
+        metric_vec(&[
+            (&[("outcome", "scheduled_as_first")], 2),
+            (&[("outcome", "executed")], 4),
+            (&[("outcome", "interrupted")], 6),
+            (&[("outcome", "skipped_no_messages")], 8),
+            (&[("outcome", "skipped_limits")], 10),
+        ]),
+    );
+    assert_eq!(
+        fetch_int_gauge(&registry, "replicated_state_canister_slices_executed"),
+        Some(12)
+    );
+}
+
 #[test]
 fn replicated_state_metrics_all_canisters_in_routing_table() {
     let mut state = ReplicatedState::new(subnet_test_id(1), SubnetType::Application);
@@ -2863,6 +2952,7 @@ fn ecdsa_signature_agreements_metric_is_updated() {
             code: RejectCode::SysFatal,
             message: "".into(),
         }),
+        deadline: NO_DEADLINE,
     };
 
     test.state_mut().consensus_queue.push(response);
@@ -2911,6 +3001,7 @@ fn ecdsa_signature_agreements_metric_is_updated() {
             }
             .encode(),
         ),
+        deadline: NO_DEADLINE,
     };
 
     test.state_mut().consensus_queue.push(response);
@@ -3766,6 +3857,9 @@ fn dts_long_execution_completes() {
             .get_sample_sum(),
         9.0
     );
+    let canister_metrics = &test.canister_state(canister).system_state.canister_metrics;
+    assert_eq!(canister_metrics.slices_executed, 10);
+    assert_eq!(canister_metrics.executed, 10);
 }
 
 #[test]
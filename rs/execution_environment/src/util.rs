@@ -1,12 +1,13 @@
 use crate::types::Response;
 use ic_base_types::SubnetId;
+use ic_embedders::wasm_utils::{decoding::decode_wasm, function_names};
 use ic_ic00_types::{CanisterStatusType, EmptyBlob, Payload as Ic00Payload, IC_00};
 use ic_interfaces::execution_environment::IngressHistoryWriter;
 use ic_logger::{error, ReplicaLogger};
-use ic_replicated_state::{CanisterStatus, ReplicatedState};
+use ic_replicated_state::{CanisterStatus, ExecutionState, ReplicatedState};
 use ic_types::{
     ingress::{IngressState, IngressStatus, WasmResult},
-    messages::{Payload, StopCanisterContext},
+    messages::{Payload, StopCanisterContext, NO_DEADLINE},
     CanisterId,
 };
 use std::{mem, sync::Arc};
@@ -97,6 +98,7 @@ pub fn process_stopping_canisters(
                             originator_reply_callback: reply_callback,
                             refund: cycles,
                             response_payload: Payload::Data(EmptyBlob.encode()),
+                            deadline: NO_DEADLINE,
                         };
                         state.push_subnet_output_response(response.into());
                     }
@@ -107,3 +109,13 @@ pub fn process_stopping_canisters(
     state.put_canister_states(canister_states);
     state
 }
+
+/// Returns the instruction profile of the given execution state in the folded
+/// stack format, naming functions after the `name` section of the canister's
+/// Wasm module.
+pub fn folded_instruction_profile(execution_state: &ExecutionState) -> String {
+    let names = decode_wasm(execution_state.wasm_binary.binary.to_shared_vec())
+        .map(|wasm| function_names(&wasm))
+        .unwrap_or_default();
+    execution_state.instruction_profile.to_folded(&names)
+}
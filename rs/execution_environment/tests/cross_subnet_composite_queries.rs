@@ -0,0 +1,294 @@
+use ic_config::execution_environment::Config as HypervisorConfig;
+use ic_error_types::RejectCode;
+use ic_registry_routing_table::{routing_table_insert_subnet, RoutingTable};
+use ic_registry_subnet_type::SubnetType;
+use ic_state_machine_tests::{
+    CrossSubnetQueryRouter, StateMachine, StateMachineBuilder, WasmResult,
+};
+use ic_types::{CanisterId, Cycles, SubnetId};
+use ic_types_test_utils::ids::subnet_test_id;
+use ic_universal_canister::{call_args, wasm, UNIVERSAL_CANISTER_WASM};
+use std::sync::Arc;
+
+const INITIAL_CYCLES_BALANCE: Cycles = Cycles::new(100_000_000_000_000);
+
+struct TwoSubnets {
+    router: Arc<CrossSubnetQueryRouter>,
+    local_env: StateMachine,
+    remote_env: StateMachine,
+    local_canister: CanisterId,
+    remote_canister: CanisterId,
+}
+
+impl TwoSubnets {
+    fn local_subnet_id() -> SubnetId {
+        subnet_test_id(1)
+    }
+
+    fn remote_subnet_id() -> SubnetId {
+        subnet_test_id(2)
+    }
+
+    /// Sets up two connected subnets, each hosting a universal canister.
+    fn new() -> Self {
+        let mut routing_table = RoutingTable::new();
+        for subnet_id in [Self::local_subnet_id(), Self::remote_subnet_id()] {
+            routing_table_insert_subnet(&mut routing_table, subnet_id).unwrap();
+        }
+        let router = Arc::new(CrossSubnetQueryRouter::default());
+        let build = |subnet_id| {
+            StateMachineBuilder::new()
+                .with_subnet_id(subnet_id)
+                .with_subnet_type(SubnetType::Application)
+                .with_routing_table(routing_table.clone())
+                .with_cross_subnet_query_router(Arc::clone(&router))
+                .build()
+        };
+        let local_env = build(Self::local_subnet_id());
+        let remote_env = build(Self::remote_subnet_id());
+        let install = |env: &StateMachine| {
+            env.install_canister_with_cycles(
+                UNIVERSAL_CANISTER_WASM.to_vec(),
+                vec![],
+                None,
+                INITIAL_CYCLES_BALANCE,
+            )
+            .unwrap()
+        };
+        let local_canister = install(&local_env);
+        let remote_canister = install(&remote_env);
+        Self {
+            router,
+            local_env,
+            remote_env,
+            local_canister,
+            remote_canister,
+        }
+    }
+}
+
+#[test]
+fn composite_query_calls_canister_on_other_subnet() {
+    let subnets = TwoSubnets::new();
+    // The default `other_side` replies with the caller and the callee, which
+    // checks that the caller is preserved across subnets.
+    let result = subnets.local_env.query(
+        subnets.local_canister,
+        "composite_query",
+        wasm()
+            .composite_query(subnets.remote_canister, call_args())
+            .build(),
+    );
+    let expected = [
+        b"Hello ".as_slice(),
+        subnets.local_canister.get().as_slice(),
+        b" this is ",
+        subnets.remote_canister.get().as_slice(),
+    ]
+    .concat();
+    assert_eq!(result, Ok(WasmResult::Reply(expected)));
+}
+
+#[test]
+fn composite_query_calls_back_into_calling_subnet() {
+    let subnets = TwoSubnets::new();
+    // local -> remote -> local: the innermost call is forwarded back to the
+    // subnet the outermost query started on.
+    let result = subnets.local_env.query(
+        subnets.local_canister,
+        "composite_query",
+        wasm()
+            .composite_query(
+                subnets.remote_canister,
+                call_args().other_side(wasm().composite_query(
+                    subnets.local_canister,
+                    call_args().other_side(wasm().reply_data(b"pong")),
+                )),
+            )
+            .build(),
+    );
+    assert_eq!(result, Ok(WasmResult::Reply(b"pong".to_vec())));
+}
+
+#[test]
+fn cross_subnet_query_passes_on_remaining_walltime() {
+    let subnets = TwoSubnets::new();
+    // local -> remote -> local: each forwarded call may only use the wall time
+    // left to its caller.
+    let result = subnets.local_env.query(
+        subnets.local_canister,
+        "composite_query",
+        wasm()
+            .composite_query(
+                subnets.remote_canister,
+                call_args().other_side(wasm().composite_query(
+                    subnets.local_canister,
+                    call_args().other_side(wasm().reply_data(b"pong")),
+                )),
+            )
+            .build(),
+    );
+    assert_eq!(result, Ok(WasmResult::Reply(b"pong".to_vec())));
+
+    let forwarded_queries = subnets.router.forwarded_queries();
+    let callees: Vec<_> = forwarded_queries
+        .iter()
+        .map(|(subnet_id, _)| *subnet_id)
+        .collect();
+    assert_eq!(
+        callees,
+        vec![
+            TwoSubnets::remote_subnet_id(),
+            TwoSubnets::local_subnet_id()
+        ]
+    );
+    let (_, to_remote) = &forwarded_queries[0];
+    let (_, back_to_local) = &forwarded_queries[1];
+    assert!(to_remote.max_walltime <= HypervisorConfig::default().max_query_call_walltime);
+    assert!(back_to_local.max_walltime <= to_remote.max_walltime);
+}
+
+#[test]
+fn cyclic_cross_subnet_query_to_saturated_subnet_is_rejected() {
+    let subnets = TwoSubnets::new();
+    // Each subnet executes a single query call from another subnet at a time.
+    subnets.router.set_max_concurrent_queries(1);
+
+    // local -> remote -> local -> remote: the innermost call would have to
+    // wait for the outermost call to the remote subnet, which waits for it in
+    // turn. It is rejected instead.
+    let on_reject = wasm()
+        .reject_code()
+        .int_to_blob()
+        .reply_data_append()
+        .reject_message()
+        .reply_data_append()
+        .reply();
+    let result = subnets
+        .local_env
+        .query(
+            subnets.local_canister,
+            "composite_query",
+            wasm()
+                .composite_query(
+                    subnets.remote_canister,
+                    call_args().other_side(
+                        wasm().composite_query(
+                            subnets.local_canister,
+                            call_args().other_side(
+                                wasm().composite_query(
+                                    subnets.remote_canister,
+                                    call_args()
+                                        .other_side(wasm().reply_data(b"pong"))
+                                        .on_reject(on_reject),
+                                ),
+                            ),
+                        ),
+                    ),
+                )
+                .build(),
+        )
+        .unwrap();
+    match result {
+        WasmResult::Reply(data) => {
+            let (code, message) = data.split_at(4);
+            assert_eq!(code, (RejectCode::SysTransient as u32).to_le_bytes());
+            let message = String::from_utf8(message.to_vec()).unwrap();
+            assert!(
+                message.contains(&format!(
+                    "subnet {} is saturated",
+                    TwoSubnets::remote_subnet_id()
+                )),
+                "Unexpected reject message: {}",
+                message
+            );
+        }
+        WasmResult::Reject(message) => panic!("Unexpected reject: {}", message),
+    }
+
+    // The cycle left both subnets idle, so the next call goes through.
+    let result = subnets.local_env.query(
+        subnets.local_canister,
+        "composite_query",
+        wasm()
+            .composite_query(
+                subnets.remote_canister,
+                call_args().other_side(wasm().reply_data(b"pong")),
+            )
+            .build(),
+    );
+    assert_eq!(result, Ok(WasmResult::Reply(b"pong".to_vec())));
+}
+
+#[test]
+fn composite_query_to_unreachable_subnet_is_rejected() {
+    let subnets = TwoSubnets::new();
+    subnets.router.disconnect(TwoSubnets::remote_subnet_id());
+    let result = subnets
+        .local_env
+        .query(
+            subnets.local_canister,
+            "composite_query",
+            wasm()
+                .composite_query(
+                    subnets.remote_canister,
+                    call_args().on_reject(wasm().reject_message().reply_data_append().reply()),
+                )
+                .build(),
+        )
+        .unwrap();
+    match result {
+        WasmResult::Reply(message) => {
+            let message = String::from_utf8(message).unwrap();
+            assert!(
+                message.contains(&format!(
+                    "hosted on subnet {}, which is unreachable",
+                    TwoSubnets::remote_subnet_id()
+                )),
+                "Unexpected reject message: {}",
+                message
+            );
+        }
+        WasmResult::Reject(message) => panic!("Unexpected reject: {}", message),
+    }
+}
+
+#[test]
+fn cross_subnet_query_respects_call_graph_depth() {
+    let subnets = TwoSubnets::new();
+    // Bounce between the two subnets more often than the call graph depth
+    // limit allows. Every caller passes a reject on to its own caller.
+    let mut payload = wasm().reply_data(b"too deep");
+    for i in 0..10 {
+        let callee = if i % 2 == 0 {
+            subnets.local_canister
+        } else {
+            subnets.remote_canister
+        };
+        payload = wasm().composite_query(
+            callee,
+            call_args()
+                .other_side(payload)
+                .on_reject(wasm().reject_message().reject()),
+        );
+    }
+    let err = subnets
+        .local_env
+        .query(subnets.local_canister, "composite_query", payload.build())
+        .unwrap_err();
+    assert!(
+        err.description()
+            .contains("Composite query calls exceeded the maximum call depth."),
+        "Unexpected error: {}",
+        err
+    );
+    // The remote subnet is still usable afterwards.
+    assert!(subnets
+        .remote_env
+        .query(
+            subnets.remote_canister,
+            "query",
+            wasm().reply_data(b"ok").build()
+        )
+        .is_ok());
+}
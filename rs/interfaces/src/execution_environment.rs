@@ -4,22 +4,26 @@ mod errors;
 pub use errors::{CanisterOutOfCyclesError, HypervisorError, TrapCode};
 use ic_base_types::NumBytes;
 use ic_error_types::UserError;
-use ic_ic00_types::EcdsaKeyId;
+use ic_ic00_types::{EcdsaKeyId, SchnorrKeyId};
 use ic_registry_provisional_whitelist::ProvisionalWhitelist;
 use ic_registry_subnet_type::SubnetType;
 use ic_sys::{PageBytes, PageIndex};
 use ic_types::{
-    crypto::canister_threshold_sig::MasterEcdsaPublicKey,
+    canister_log::CanisterLog,
+    crypto::canister_threshold_sig::{MasterEcdsaPublicKey, MasterSchnorrPublicKey},
     ingress::{IngressStatus, WasmResult},
+    instruction_profile::InstructionProfile,
     messages::{
         AnonymousQuery, AnonymousQueryResponse, CertificateDelegation, HttpQueryResponse,
-        MessageId, SignedIngressContent, UserQuery,
+        MessageId, Payload, SignedIngressContent, UserQuery,
     },
-    CpuComplexity, Cycles, ExecutionRound, Height, NumInstructions, NumPages, Randomness, Time,
+    CanisterId, CpuComplexity, Cycles, ExecutionRound, Height, NumInstructions, NumPages,
+    Randomness, SubnetId, Time,
 };
 use serde::{Deserialize, Serialize};
 use std::convert::TryFrom;
 use std::sync::Arc;
+use std::time::Duration;
 use std::{collections::BTreeMap, ops};
 use std::{convert::Infallible, fmt};
 use tower::util::BoxCloneService;
@@ -45,6 +49,27 @@ pub struct InstanceStats {
     /// Number of times a write access is handled when the page has not yet been
     /// read.
     pub direct_write_count: usize,
+
+    /// The inputs the execution depended on. Only collected for executions
+    /// that ignore state modifications, and `None` if the accessed pages could
+    /// not be determined.
+    pub query_dependencies: Option<QueryDependencies>,
+}
+
+/// The parts of the canister state read by a query execution. The query cache
+/// uses them to keep a result valid for as long as these inputs are unchanged.
+#[derive(Serialize, Deserialize, Clone, Debug, Default, PartialEq, Eq)]
+pub struct QueryDependencies {
+    /// The Wasm heap pages accessed by the execution.
+    pub heap_pages: Vec<PageIndex>,
+
+    /// The stable memory pages accessed by the execution.
+    pub stable_pages: Vec<PageIndex>,
+
+    /// Whether the execution called a system API that exposes state outside of
+    /// the canister memory, e.g. the time, the cycle balance or the data
+    /// certificate.
+    pub reads_system_state: bool,
 }
 
 /// Errors that can be returned when fetching the available memory on a subnet.
@@ -333,6 +358,56 @@ pub trait QueryHandler: Send + Sync {
         state: Arc<Self::State>,
         data_certificate: Vec<u8>,
     ) -> Result<WasmResult, UserError>;
+
+    /// Handle a query call of type `CrossSubnetQuery` which was made by a
+    /// composite query executing on another subnet.
+    ///
+    /// `query.sender` is trusted to be the caller: callers must ensure that
+    /// the query was received from a node of the subnet hosting the sender.
+    fn cross_subnet_query(
+        &self,
+        query: CrossSubnetQuery,
+        state: Arc<Self::State>,
+    ) -> CrossSubnetQueryResponse;
+}
+
+/// A query call made by a composite query to a canister hosted on another
+/// subnet. The callee executes it along with its own outgoing query calls
+/// within the remaining limits of the caller's query call graph.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct CrossSubnetQuery {
+    pub sender: CanisterId,
+    pub receiver: CanisterId,
+    pub method_name: String,
+    #[serde(with = "serde_bytes")]
+    pub method_payload: Vec<u8>,
+    /// The depth of the query call graph still available to the callee.
+    pub max_call_graph_depth: usize,
+    /// The instructions still available to the callee and its query calls.
+    pub max_instructions: NumInstructions,
+    /// The wall time still available to the callee and its query calls. The
+    /// caller gives up on the query once it has elapsed.
+    pub max_walltime: Duration,
+}
+
+/// The response to a `CrossSubnetQuery`.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct CrossSubnetQueryResponse {
+    pub payload: Payload,
+    /// The instructions executed by the callee and its query calls.
+    pub instructions_executed: NumInstructions,
+}
+
+/// Interface for forwarding the query calls of composite queries to canisters
+/// hosted on other subnets, e.g. via the XNet endpoint of their nodes.
+pub trait CrossSubnetQueryClient: Send + Sync {
+    /// Executes the query on the given subnet. Returns an error describing
+    /// the failure if the subnet cannot be reached.
+    fn query(
+        &self,
+        subnet_id: SubnetId,
+        query: CrossSubnetQuery,
+    ) -> Result<CrossSubnetQueryResponse, String>;
 }
 
 /// Errors that can be returned when reading/writing from/to ingress history.
@@ -469,9 +544,9 @@ pub trait SystemApi {
     /// id in case of requests or the user id in case of an ingress message.
     fn ic0_msg_caller_copy(
         &self,
-        dst: u32,
-        offset: u32,
-        size: u32,
+        dst: usize,
+        offset: usize,
+        size: usize,
         heap: &mut [u8],
     ) -> HypervisorResult<()>;
 
@@ -485,9 +560,9 @@ pub trait SystemApi {
     /// memory[dst..dst+size].
     fn ic0_msg_arg_data_copy(
         &self,
-        dst: u32,
-        offset: u32,
-        size: u32,
+        dst: usize,
+        offset: usize,
+        size: usize,
         heap: &mut [u8],
     ) -> HypervisorResult<()>;
 
@@ -499,9 +574,9 @@ pub trait SystemApi {
     /// only be called in the context of inspecting messages.
     fn ic0_msg_method_name_copy(
         &self,
-        dst: u32,
-        offset: u32,
-        size: u32,
+        dst: usize,
+        offset: usize,
+        size: usize,
         heap: &mut [u8],
     ) -> HypervisorResult<()>;
 
@@ -514,8 +589,8 @@ pub trait SystemApi {
     /// it to the (initially empty) data reply.
     fn ic0_msg_reply_data_append(
         &mut self,
-        src: u32,
-        size: u32,
+        src: usize,
+        size: usize,
         heap: &[u8],
     ) -> HypervisorResult<()>;
 
@@ -531,7 +606,7 @@ pub trait SystemApi {
     fn ic0_msg_reject_code(&self) -> HypervisorResult<i32>;
 
     /// Replies to sender with an error message
-    fn ic0_msg_reject(&mut self, src: u32, size: u32, heap: &[u8]) -> HypervisorResult<()>;
+    fn ic0_msg_reject(&mut self, src: usize, size: usize, heap: &[u8]) -> HypervisorResult<()>;
 
     /// Returns the length of the reject message in bytes.
     ///
@@ -550,9 +625,9 @@ pub trait SystemApi {
     /// called from inside a reject callback.
     fn ic0_msg_reject_msg_copy(
         &self,
-        dst: u32,
-        offset: u32,
-        size: u32,
+        dst: usize,
+        offset: usize,
+        size: usize,
         heap: &mut [u8],
     ) -> HypervisorResult<()>;
 
@@ -563,17 +638,20 @@ pub trait SystemApi {
     /// canister to heap[dst..dst+size].
     fn ic0_canister_self_copy(
         &mut self,
-        dst: u32,
-        offset: u32,
-        size: u32,
+        dst: usize,
+        offset: usize,
+        size: usize,
         heap: &mut [u8],
     ) -> HypervisorResult<()>;
 
     /// Outputs the specified bytes on the heap as a string on STDOUT.
-    fn ic0_debug_print(&self, src: u32, size: u32, heap: &[u8]) -> HypervisorResult<()>;
+    fn ic0_debug_print(&self, src: usize, size: usize, heap: &[u8]) -> HypervisorResult<()>;
+
+    /// Appends the specified bytes on the heap to the canister log.
+    fn save_log_message(&mut self, src: usize, size: usize, heap: &[u8]);
 
     /// Traps, with a possibly helpful message
-    fn ic0_trap(&self, src: u32, size: u32, heap: &[u8]) -> HypervisorResult<()>;
+    fn ic0_trap(&self, src: usize, size: usize, heap: &[u8]) -> HypervisorResult<()>;
 
     /// Begins assembling a call to the canister specified by
     /// callee_src/callee_size at method name_src/name_size. Two mandatory
@@ -587,10 +665,10 @@ pub trait SystemApi {
     #[allow(clippy::too_many_arguments)]
     fn ic0_call_new(
         &mut self,
-        callee_src: u32,
-        callee_size: u32,
-        name_src: u32,
-        name_len: u32,
+        callee_src: usize,
+        callee_size: usize,
+        name_src: usize,
+        name_len: usize,
         reply_fun: u32,
         reply_env: u32,
         reject_fun: u32,
@@ -601,7 +679,12 @@ pub trait SystemApi {
     /// Appends the specified bytes to the argument of the call. Initially, the
     /// argument is empty. This can be called multiple times between
     /// `ic0.call_new` and `ic0.call_perform`.
-    fn ic0_call_data_append(&mut self, src: u32, size: u32, heap: &[u8]) -> HypervisorResult<()>;
+    fn ic0_call_data_append(
+        &mut self,
+        src: usize,
+        size: usize,
+        heap: &[u8],
+    ) -> HypervisorResult<()>;
 
     /// Specifies the closure to be called if the reply/reject closures trap.
     /// Can be called at most once between `ic0.call_new` and
@@ -610,6 +693,13 @@ pub trait SystemApi {
     /// See <https://sdk.dfinity.org/docs/interface-spec/index.html#system-api-call>
     fn ic0_call_on_cleanup(&mut self, fun: u32, env: u32) -> HypervisorResult<()>;
 
+    /// Turns the call under construction into a best-effort call that times
+    /// out after the given number of seconds, capped to
+    /// `MAX_CALL_TIMEOUT_SECONDS`. On timeout, the reject callback is invoked
+    /// with a `SYS_UNKNOWN` reject code. Can be called at most once between
+    /// `ic0.call_new` and `ic0.call_perform`.
+    fn ic0_call_with_best_effort_response(&mut self, timeout_seconds: u32) -> HypervisorResult<()>;
+
     /// (deprecated) Please use `ic0_call_cycles_add128` instead, as this API
     /// can only add a 64-bit value.
     ///
@@ -819,7 +909,7 @@ pub trait SystemApi {
     /// The amount of cycles is represented by a 128-bit value
     /// and is copied in the canister memory starting
     /// starting at the location `dst`.
-    fn ic0_canister_cycle_balance128(&self, dst: u32, heap: &mut [u8]) -> HypervisorResult<()>;
+    fn ic0_canister_cycle_balance128(&self, dst: usize, heap: &mut [u8]) -> HypervisorResult<()>;
 
     /// (deprecated) Please use `ic0_msg_cycles_available128` instead.
     /// This API supports only 64-bit values.
@@ -835,7 +925,7 @@ pub trait SystemApi {
     /// The amount of cycles is represented by a 128-bit value
     /// and is copied in the canister memory starting
     /// starting at the location `dst`.
-    fn ic0_msg_cycles_available128(&self, dst: u32, heap: &mut [u8]) -> HypervisorResult<()>;
+    fn ic0_msg_cycles_available128(&self, dst: usize, heap: &mut [u8]) -> HypervisorResult<()>;
 
     /// (deprecated) Please use `ic0_msg_cycles_refunded128` instead.
     /// This API supports only 64-bit values.
@@ -851,7 +941,7 @@ pub trait SystemApi {
     /// The amount of cycles is represented by a 128-bit value
     /// and is copied in the canister memory starting
     /// starting at the location `dst`.
-    fn ic0_msg_cycles_refunded128(&self, dst: u32, heap: &mut [u8]) -> HypervisorResult<()>;
+    fn ic0_msg_cycles_refunded128(&self, dst: usize, heap: &mut [u8]) -> HypervisorResult<()>;
 
     /// (deprecated) Please use `ic0_msg_cycles_accept128` instead.
     /// This API supports only 64-bit values.
@@ -897,13 +987,18 @@ pub trait SystemApi {
     fn ic0_msg_cycles_accept128(
         &mut self,
         max_amount: Cycles,
-        dst: u32,
+        dst: usize,
         heap: &mut [u8],
     ) -> HypervisorResult<()>;
 
     /// Sets the certified data for the canister.
     /// See: <https://sdk.dfinity.org/docs/interface-spec/index.html#system-api-certified-data>
-    fn ic0_certified_data_set(&mut self, src: u32, size: u32, heap: &[u8]) -> HypervisorResult<()>;
+    fn ic0_certified_data_set(
+        &mut self,
+        src: usize,
+        size: usize,
+        heap: &[u8],
+    ) -> HypervisorResult<()>;
 
     /// If run in non-replicated execution (i.e. query),
     /// returns 1 if the data certificate is present, 0 otherwise.
@@ -921,9 +1016,9 @@ pub trait SystemApi {
     /// Traps if data_certificate_present returns 0.
     fn ic0_data_certificate_copy(
         &self,
-        dst: u32,
-        offset: u32,
-        size: u32,
+        dst: usize,
+        offset: usize,
+        size: usize,
         heap: &mut [u8],
     ) -> HypervisorResult<()>;
 
@@ -948,7 +1043,7 @@ pub trait SystemApi {
     /// otherwise a 0 is returned. It can be called multiple times.
     ///
     /// This system call traps if src+size exceeds the size of the WebAssembly memory.
-    fn ic0_is_controller(&self, src: u32, size: u32, heap: &[u8]) -> HypervisorResult<u32>;
+    fn ic0_is_controller(&self, src: usize, size: usize, heap: &[u8]) -> HypervisorResult<u32>;
 }
 
 #[derive(Copy, Clone, Debug, Eq, PartialEq)]
@@ -965,6 +1060,7 @@ pub struct RegistryExecutionSettings {
     pub max_number_of_canisters: u64,
     pub provisional_whitelist: ProvisionalWhitelist,
     pub max_ecdsa_queue_size: u32,
+    pub max_schnorr_queue_size: u32,
     pub subnet_size: usize,
 }
 
@@ -1023,6 +1119,7 @@ pub trait Scheduler: Send {
         state: Self::State,
         randomness: Randomness,
         ecdsa_subnet_public_keys: BTreeMap<EcdsaKeyId, MasterEcdsaPublicKey>,
+        schnorr_subnet_public_keys: BTreeMap<SchnorrKeyId, MasterSchnorrPublicKey>,
         current_round: ExecutionRound,
         current_round_type: ExecutionRoundType,
         registry_settings: &RegistryExecutionSettings,
@@ -1036,6 +1133,12 @@ pub struct WasmExecutionOutput {
     pub allocated_bytes: NumBytes,
     pub allocated_message_bytes: NumBytes,
     pub instance_stats: InstanceStats,
+    /// Log records produced by the execution, including the trap message if
+    /// the execution trapped.
+    pub canister_log: CanisterLog,
+    /// Per-function instruction counts of the execution. Empty unless
+    /// canister profiling is enabled.
+    pub instruction_profile: InstructionProfile,
 }
 
 impl fmt::Display for WasmExecutionOutput {
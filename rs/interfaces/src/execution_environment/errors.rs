@@ -140,6 +140,12 @@ pub enum HypervisorError {
         available: Cycles,
         threshold: Cycles,
     },
+    /// The Wasm memory of a canister grew beyond the `wasm_memory_limit`
+    /// canister setting.
+    WasmMemoryLimitExceeded {
+        bytes: NumBytes,
+        limit: NumBytes,
+    },
 }
 
 impl From<WasmInstrumentationError> for HypervisorError {
@@ -333,6 +339,14 @@ impl HypervisorError {
                      bytes,
                      threshold - available)
             ),
+            Self::WasmMemoryLimitExceeded { bytes, limit } => UserError::new(
+                E::CanisterOutOfMemory,
+                format!(
+                    "Canister {} exceeded its Wasm memory limit: \
+                     the Wasm memory size is {} bytes, but the limit is {} bytes.",
+                    canister_id, bytes, limit
+                ),
+            ),
         }
     }
 
@@ -366,6 +380,7 @@ impl HypervisorError {
             HypervisorError::InsufficientCyclesInMemoryGrow { .. } => {
                 "InsufficientCyclesInMemoryGrow"
             }
+            HypervisorError::WasmMemoryLimitExceeded { .. } => "WasmMemoryLimitExceeded",
         }
     }
 }
@@ -110,6 +110,16 @@ impl PageBitmap {
         self.pages.get(page.get() as usize).unwrap_or(false)
     }
 
+    /// Returns the indices of all marked pages in ascending order.
+    pub fn marked_pages(&self) -> Vec<PageIndex> {
+        self.pages
+            .iter()
+            .enumerate()
+            .filter(|(_, marked)| *marked)
+            .map(|(index, _)| PageIndex::new(index as u64))
+            .collect()
+    }
+
     fn mark(&mut self, page: PageIndex) {
         self.pages.set(page.get() as usize, true);
         self.marked_count += 1;
@@ -14,7 +14,7 @@ use ic_interfaces::{
 use ic_interfaces_certified_stream_store::CertifiedStreamStore;
 use ic_interfaces_registry::RegistryClient;
 use ic_interfaces_state_manager::{CertificationScope, StateManager, StateManagerError};
-use ic_logger::{debug, fatal, info, warn, ReplicaLogger};
+use ic_logger::{debug, error, fatal, info, warn, ReplicaLogger};
 use ic_metrics::buckets::{add_bucket, decimal_buckets, decimal_buckets_with_zero};
 use ic_metrics::{MetricsRegistry, Timer};
 use ic_protobuf::proxy::ProxyDecodeError;
@@ -28,7 +28,7 @@ use ic_registry_client_helpers::{
 use ic_registry_provisional_whitelist::ProvisionalWhitelist;
 use ic_registry_subnet_features::SubnetFeatures;
 use ic_registry_subnet_type::SubnetType;
-use ic_replicated_state::{NetworkTopology, ReplicatedState, SubnetTopology};
+use ic_replicated_state::{NetworkTopology, ReplicatedState, StateError, SubnetTopology};
 use ic_types::{
     batch::Batch,
     malicious_flags::MaliciousFlags,
@@ -76,6 +76,7 @@ const PHASE_COMMIT: &str = "commit";
 const METRIC_PROCESS_BATCH_DURATION: &str = "mr_process_batch_duration_seconds";
 const METRIC_PROCESS_BATCH_PHASE_DURATION: &str = "mr_process_batch_phase_duration_seconds";
 const METRIC_TIMED_OUT_REQUESTS_TOTAL: &str = "mr_timed_out_requests_total";
+const METRIC_TIMED_OUT_CALLBACKS_TOTAL: &str = "mr_timed_out_callbacks_total";
 
 const METRIC_WASM_CUSTOM_SECTIONS_MEMORY_USAGE_BYTES: &str =
     "mr_wasm_custom_sections_memory_usage_bytes";
@@ -85,6 +86,7 @@ const METRIC_CANISTER_HISTORY_TOTAL_NUM_CHANGES: &str = "mr_canister_history_tot
 const CRITICAL_ERROR_MISSING_SUBNET_SIZE: &str = "cycles_account_manager_missing_subnet_size_error";
 const CRITICAL_ERROR_NO_CANISTER_ALLOCATION_RANGE: &str = "mr_empty_canister_allocation_range";
 const CRITICAL_ERROR_FAILED_TO_READ_REGISTRY: &str = "mr_failed_to_read_registry_error";
+const CRITICAL_ERROR_CALLBACK_TIMEOUT_FAILED: &str = "mr_callback_timeout_failed";
 
 /// Records the timestamp when all messages before the given index (down to the
 /// previous `MessageTime`) were first added to / learned about in a stream.
@@ -274,8 +276,13 @@ pub(crate) struct MessageRoutingMetrics {
     critical_error_no_canister_allocation_range: IntCounter,
     /// Critical error: reading from the registry failed during processing a batch.
     critical_error_failed_to_read_registry: IntCounter,
+    /// Critical error: the reject response for a timed out best-effort callback
+    /// could not be enqueued.
+    critical_error_callback_timeout_failed: IntCounter,
     /// Number of timed out requests.
     pub timed_out_requests_total: IntCounter,
+    /// Number of timed out best-effort callbacks.
+    pub timed_out_callbacks_total: IntCounter,
 }
 
 impl MessageRoutingMetrics {
@@ -335,10 +342,16 @@ impl MessageRoutingMetrics {
                 .error_counter(CRITICAL_ERROR_NO_CANISTER_ALLOCATION_RANGE),
             critical_error_failed_to_read_registry: metrics_registry
                 .error_counter(CRITICAL_ERROR_FAILED_TO_READ_REGISTRY),
+            critical_error_callback_timeout_failed: metrics_registry
+                .error_counter(CRITICAL_ERROR_CALLBACK_TIMEOUT_FAILED),
             timed_out_requests_total: metrics_registry.int_counter(
                 METRIC_TIMED_OUT_REQUESTS_TOTAL,
                 "Count of timed out requests.",
             ),
+            timed_out_callbacks_total: metrics_registry.int_counter(
+                METRIC_TIMED_OUT_CALLBACKS_TOTAL,
+                "Count of timed out best-effort callbacks.",
+            ),
         }
     }
 
@@ -351,6 +364,16 @@ impl MessageRoutingMetrics {
             CRITICAL_ERROR_NO_CANISTER_ALLOCATION_RANGE
         );
     }
+
+    pub fn observe_callback_timeout_error(&self, log: &ReplicaLogger, err: StateError) {
+        self.critical_error_callback_timeout_failed.inc();
+        error!(
+            log,
+            "{}: Failed to enqueue the reject response of a timed out callback: {}",
+            CRITICAL_ERROR_CALLBACK_TIMEOUT_FAILED,
+            err
+        );
+    }
 }
 
 /// Implementation of the `MessageRouting` trait.
@@ -551,7 +574,9 @@ impl BatchProcessorImpl {
             .ok_or_else(|| not_found_error("subnet record", Some(own_subnet_id)))?;
         let subnet_features = subnet_record.features.unwrap_or_default().into();
         let max_number_of_canisters = subnet_record.max_number_of_canisters;
-        let max_ecdsa_queue_size = subnet_record
+        // The chain-key config of the subnet bounds the queue of signature
+        // requests, for threshold ECDSA and threshold Schnorr keys alike.
+        let max_signature_queue_size = subnet_record
             .ecdsa_config
             .map(|c| c.max_queue_size)
             .unwrap_or(0);
@@ -578,7 +603,8 @@ impl BatchProcessorImpl {
             RegistryExecutionSettings {
                 max_number_of_canisters,
                 provisional_whitelist,
-                max_ecdsa_queue_size,
+                max_ecdsa_queue_size: max_signature_queue_size,
+                max_schnorr_queue_size: max_signature_queue_size,
                 subnet_size,
             },
         ))
@@ -448,6 +448,7 @@ fn make_batch_processor(
         max_number_of_canisters: 0,
         provisional_whitelist: ProvisionalWhitelist::All,
         max_ecdsa_queue_size: 0,
+        max_schnorr_queue_size: 0,
         subnet_size: 0,
     }));
     let batch_processor = BatchProcessorImpl {
@@ -643,6 +644,10 @@ fn try_read_registry_succeeds_with_fully_specified_registry_records() {
             own_subnet_record.ecdsa_config.max_queue_size,
             Some(registry_execution_settings.max_ecdsa_queue_size),
         );
+        assert_eq!(
+            own_subnet_record.ecdsa_config.max_queue_size,
+            Some(registry_execution_settings.max_schnorr_queue_size),
+        );
         assert_eq!(
             own_subnet_record.membership.len(),
             registry_execution_settings.subnet_size,
@@ -675,6 +680,7 @@ fn try_read_registry_succeeds_with_fully_specified_registry_records() {
             messages: BatchMessages::default(),
             randomness: Randomness::new([123; 32]),
             ecdsa_subnet_public_keys: BTreeMap::default(),
+            schnorr_subnet_public_keys: BTreeMap::default(),
             registry_version: fixture.registry.get_latest_version(),
             time: Time::from_nanos_since_unix_epoch(0),
             consensus_responses: Vec::new(),
@@ -77,6 +77,7 @@ const LABEL_VALUE_SUCCESS: &str = "success";
 const LABEL_VALUE_SENDER_SUBNET_MISMATCH: &str = "SenderSubnetMismatch";
 const LABEL_VALUE_RECEIVER_SUBNET_MISMATCH: &str = "ReceiverSubnetMismatch";
 const LABEL_VALUE_CANISTER_MIGRATED: &str = "CanisterMigrated";
+const LABEL_VALUE_DEADLINE_EXPIRED: &str = "DeadlineExpired";
 const LABEL_TYPE: &str = "type";
 const LABEL_VALUE_TYPE_REQUEST: &str = "request";
 const LABEL_VALUE_TYPE_RESPONSE: &str = "response";
@@ -566,7 +567,9 @@ impl StreamHandlerImpl {
     ///     * the receiver is not hosted by or being migrated off of this
     ///       subnet; or
     ///     * enqueuing a `Response` failed due to the canister having been
-    ///       removed.
+    ///       removed; or
+    ///     * the message is a best-effort message whose deadline has expired
+    ///       (the sender's callback times out on its own).
     ///
     /// Updates `subnet_available_memory` to reflect any change in memory usage.
     fn induct_message(
@@ -595,6 +598,14 @@ impl StreamHandlerImpl {
 
             let payload_size = msg.payload_size_bytes().get();
             match receiver_host_subnet {
+                // Matching receiver subnet, but the best-effort message has expired.
+                Some(host_subnet)
+                    if host_subnet == self.subnet_id && msg.has_expired(state.time()) =>
+                {
+                    debug!(self.log, "Dropping expired best-effort message {:?}", msg);
+                    self.observe_inducted_message_status(msg_type, LABEL_VALUE_DEADLINE_EXPIRED);
+                }
+
                 // Matching receiver subnet, try inducting message.
                 Some(host_subnet) if host_subnet == self.subnet_id => match state
                     .push_input(msg, subnet_available_memory)
@@ -847,6 +858,7 @@ fn generate_reject_response(
                 message,
                 MR_SYNTHETIC_REJECT_MESSAGE_MAX_LEN,
             )),
+            deadline: msg.deadline,
         }
         .into()
     } else {
@@ -31,8 +31,9 @@ use ic_test_utilities_metrics::{
 };
 use ic_types::{
     messages::{CallbackId, Payload, Request, MAX_RESPONSE_COUNT_BYTES},
+    time::CoarseTime,
     xnet::{testing::StreamSliceTesting, StreamIndex, StreamIndexedQueue},
-    CanisterId, Cycles,
+    CanisterId, Cycles, Time,
 };
 use lazy_static::lazy_static;
 use maplit::btreemap;
@@ -1280,6 +1281,7 @@ fn generate_reject_response_queue_full() {
                 RejectCode::SysTransient,
                 err.to_string(),
             )),
+            deadline: msg.deadline,
         }
         .into(),
     );
@@ -1320,6 +1322,7 @@ fn generate_reject_response_canister_not_found() {
                 RejectCode::DestinationInvalid,
                 err.to_string(),
             )),
+            deadline: msg.deadline,
         }
         .into(),
     );
@@ -1552,6 +1555,85 @@ fn induct_stream_slices_response_to_missing_canister() {
     });
 }
 
+/// Tests that best-effort messages whose deadline has expired are silently
+/// dropped instead of being inducted or rejected.
+#[test]
+fn induct_stream_slices_drops_expired_best_effort_messages() {
+    with_test_replica_logger(|log| {
+        let (stream_handler, mut initial_state, metrics_registry) = new_fixture(&log);
+        let deadline = CoarseTime::from_secs_since_unix_epoch(10);
+        initial_state.metadata.batch_time = Time::from(deadline);
+
+        let mut expected_state = initial_state.clone();
+
+        // Initial state with no canisters and one stream.
+        let outgoing_stream = generate_outgoing_stream(StreamConfig {
+            messages_begin: 21,
+            message_count: 0,
+            signals_end: 43,
+            reject_signals: None,
+        });
+        initial_state.with_streams(btreemap![REMOTE_SUBNET => outgoing_stream]);
+
+        // Incoming slice with an expired request and an expired response.
+        let mut stream_slice = generate_stream_slice(StreamSliceConfig {
+            header_begin: 43,
+            header_end: None,
+            messages_begin: 43,
+            message_count: 0,
+            signals_end: 21,
+            reject_signals: None,
+        });
+        let mut request = test_request(*REMOTE_CANISTER, *LOCAL_CANISTER);
+        request.deadline = deadline;
+        stream_slice.push_message(request.into());
+        let mut response = test_response(*REMOTE_CANISTER, *LOCAL_CANISTER);
+        response.deadline = deadline;
+        stream_slice.push_message(response.into());
+
+        // The expected stream should have `signals_end` incremented for the 2
+        // dropped messages, but no reject responses.
+        let expected_outgoing_stream = generate_outgoing_stream(StreamConfig {
+            messages_begin: 21,
+            message_count: 0,
+            signals_end: 45,
+            reject_signals: None,
+        });
+        expected_state.with_streams(btreemap![REMOTE_SUBNET => expected_outgoing_stream]);
+
+        let inducted_state = stream_handler
+            .induct_stream_slices(initial_state, btreemap![REMOTE_SUBNET => stream_slice]);
+
+        // Assert
+        assert_eq!(expected_state, inducted_state);
+
+        assert_inducted_xnet_messages_eq(
+            metric_vec(&[
+                (
+                    &[
+                        (LABEL_TYPE, LABEL_VALUE_TYPE_REQUEST),
+                        (LABEL_STATUS, LABEL_VALUE_DEADLINE_EXPIRED),
+                    ],
+                    1,
+                ),
+                (
+                    &[
+                        (LABEL_TYPE, LABEL_VALUE_TYPE_RESPONSE),
+                        (LABEL_STATUS, LABEL_VALUE_DEADLINE_EXPIRED),
+                    ],
+                    1,
+                ),
+            ]),
+            &metrics_registry,
+        );
+        assert_eq!(
+            0,
+            fetch_inducted_payload_sizes_stats(&metrics_registry).count
+        );
+        assert_eq_critical_errors(0, 0, 0, 0, &metrics_registry);
+    });
+}
+
 /// Tests that a message from a sender that is not currently and has not
 /// recently (according to `canister_migrations`) been hosted by the remote
 /// subnet is dropped, incrementing the respective critical error count.
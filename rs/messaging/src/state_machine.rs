@@ -90,7 +90,14 @@ impl StateMachine for StateMachineImpl {
             )
         }
 
-        // Time out requests.
+        // Time out expired best-effort callbacks, then requests.
+        let (timed_out_callbacks, errors) = state.time_out_callbacks(batch.time);
+        self.metrics
+            .timed_out_callbacks_total
+            .inc_by(timed_out_callbacks);
+        for err in errors {
+            self.metrics.observe_callback_timeout_error(&self.log, err);
+        }
         let timed_out_requests = state.time_out_requests(batch.time);
         self.metrics
             .timed_out_requests_total
@@ -120,6 +127,7 @@ impl StateMachine for StateMachineImpl {
             state_with_messages,
             batch.randomness,
             batch.ecdsa_subnet_public_keys,
+            batch.schnorr_subnet_public_keys,
             ExecutionRound::from(batch.batch_number.get()),
             execution_round_type,
             registry_settings,
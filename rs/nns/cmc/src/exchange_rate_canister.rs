@@ -1,10 +1,10 @@
 use crate::{
-    environment::Environment, mutate_state, read_state, set_icp_xdr_conversion_rate, State,
-    ONE_MINUTE_SECONDS,
+    compute_average_icp_xdr_rate_at_time, environment::Environment, mutate_state, print,
+    read_state, set_icp_xdr_conversion_rate, State, ONE_MINUTE_SECONDS,
 };
 use async_trait::async_trait;
 use candid::CandidType;
-use cycles_minting_canister::IcpXdrConversionRate;
+use cycles_minting_canister::{ExchangeRateSanityChecks, IcpXdrConversionRate};
 use dfn_candid::candid_one;
 use dfn_core::{api::call_with_cleanup, CanisterId};
 use ic_nns_common::types::UpdateIcpXdrConversionRatePayloadReason;
@@ -28,6 +28,10 @@ const MINIMUM_ICP_SOURCES: usize = 4;
 /// The minimum number of received sources to consider an ICP/CXDR rate's quote asset valid.
 const MINIMUM_CXDR_SOURCES: usize = 4;
 
+/// A rate set by proposal is only used as a source for the sanity checks if
+/// it is not older than this value.
+const MAX_PROPOSED_RATE_AGE_SECONDS: u64 = 24 * 60 * ONE_MINUTE_SECONDS;
+
 #[async_trait]
 pub trait ExchangeRateCanisterClient {
     async fn get_exchange_rate(&self) -> Result<ExchangeRate, GetExchangeRateError>;
@@ -395,7 +399,12 @@ pub async fn update_exchange_rate(
             Ok(exchange_rate) => {
                 validate_exchange_rate(&exchange_rate)
                     .map_err(|error| UpdateExchangeRateError::InvalidRate(error.to_string()))?;
-                let icp_xdr_conversion_rate = IcpXdrConversionRate::from(exchange_rate);
+                let icp_xdr_conversion_rate = apply_sanity_checks(
+                    safe_state,
+                    IcpXdrConversionRate::from(exchange_rate),
+                    now_timestamp_seconds,
+                )
+                .map_err(|error| UpdateExchangeRateError::InvalidRate(error.to_string()))?;
                 if let Err(error) =
                     set_icp_xdr_conversion_rate(safe_state, env, icp_xdr_conversion_rate)
                 {
@@ -496,6 +505,161 @@ fn validate_exchange_rate(exchange_rate: &ExchangeRate) -> Result<(), ValidateEx
     Ok(())
 }
 
+enum SanityCheckError {
+    MintingFrozen {
+        since_timestamp_seconds: u64,
+    },
+    RateLooksManipulated {
+        rate: u64,
+        median: u64,
+        divergence_permyriad: u64,
+    },
+}
+
+impl std::fmt::Display for SanityCheckError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            SanityCheckError::MintingFrozen {
+                since_timestamp_seconds,
+            } => write!(
+                f,
+                "Minting is frozen since {}, a new rate must be set by proposal",
+                since_timestamp_seconds
+            ),
+            SanityCheckError::RateLooksManipulated {
+                rate,
+                median,
+                divergence_permyriad,
+            } => write!(
+                f,
+                "Rate {} deviates from the median {} of the rate sources by {} basis points, minting is frozen",
+                rate, median, divergence_permyriad
+            ),
+        }
+    }
+}
+
+/// Checks the rate received from the exchange rate canister against the
+/// other rate sources if sanity checks are configured, and returns the rate
+/// that should be set.
+///
+/// The median of the available sources (the exchange rate canister, the
+/// latest rate set by proposal if it is recent, and the moving average) serves
+/// as the reference. If the sources disagree, this is recorded for alerting.
+/// If the received rate deviates too much from the median, minting is frozen
+/// and the rate is rejected. Otherwise, the received rate is returned, capped
+/// to the maximum change relative to the current rate.
+fn apply_sanity_checks(
+    safe_state: &'static LocalKey<RefCell<Option<State>>>,
+    rate: IcpXdrConversionRate,
+    now_timestamp_seconds: u64,
+) -> Result<IcpXdrConversionRate, SanityCheckError> {
+    mutate_state(safe_state, |state| {
+        let sanity_checks = match state.exchange_rate_sanity_checks.clone() {
+            Some(sanity_checks) => sanity_checks,
+            None => return Ok(rate),
+        };
+
+        if let Some(since_timestamp_seconds) = state.minting_frozen_since_timestamp_seconds {
+            return Err(SanityCheckError::MintingFrozen {
+                since_timestamp_seconds,
+            });
+        }
+
+        let mut sources = vec![rate.xdr_permyriad_per_icp];
+        if let Some(proposed_rate) = &state.last_proposed_icp_xdr_conversion_rate {
+            if proposed_rate.timestamp_seconds + MAX_PROPOSED_RATE_AGE_SECONDS
+                >= now_timestamp_seconds
+            {
+                sources.push(proposed_rate.xdr_permyriad_per_icp);
+            }
+        }
+        if let Some(average_rate) = state
+            .recent_icp_xdr_rates
+            .as_ref()
+            .and_then(|rates| compute_average_icp_xdr_rate_at_time(rates, now_timestamp_seconds))
+        {
+            sources.push(average_rate.xdr_permyriad_per_icp);
+        }
+
+        let median = median(&mut sources);
+        let divergence_permyriad = sources
+            .iter()
+            .map(|source| relative_difference_permyriad(*source, median))
+            .max()
+            .unwrap_or_default();
+        state.exchange_rate_sources_divergence_permyriad = Some(divergence_permyriad);
+        if divergence_permyriad > sanity_checks.max_source_divergence_permyriad {
+            *state
+                .exchange_rate_sources_disagreement_count
+                .get_or_insert(0) += 1;
+            print(format!(
+                "[cycles] ICP/XDR rate sources {:?} diverge from their median {} by up to {} basis points",
+                sources, median, divergence_permyriad
+            ));
+        }
+
+        let rate_divergence_permyriad =
+            relative_difference_permyriad(rate.xdr_permyriad_per_icp, median);
+        if rate_divergence_permyriad > sanity_checks.freeze_divergence_permyriad {
+            state.minting_frozen_since_timestamp_seconds = Some(now_timestamp_seconds);
+            let error = SanityCheckError::RateLooksManipulated {
+                rate: rate.xdr_permyriad_per_icp,
+                median,
+                divergence_permyriad: rate_divergence_permyriad,
+            };
+            print(format!("[cycles] {}", error));
+            return Err(error);
+        }
+
+        let xdr_permyriad_per_icp = match &state.icp_xdr_conversion_rate {
+            Some(current_rate) => cap_rate_change(
+                current_rate.xdr_permyriad_per_icp,
+                rate.xdr_permyriad_per_icp,
+                sanity_checks.max_rate_change_permyriad,
+            ),
+            None => rate.xdr_permyriad_per_icp,
+        };
+
+        Ok(IcpXdrConversionRate {
+            timestamp_seconds: rate.timestamp_seconds,
+            xdr_permyriad_per_icp,
+        })
+    })
+}
+
+/// Returns the median of the given values, which must not be empty. For an
+/// even number of values, the average of the two middle values is returned.
+fn median(values: &mut [u64]) -> u64 {
+    values.sort_unstable();
+    let middle = values.len() / 2;
+    if values.len() % 2 == 0 {
+        ((values[middle - 1] as u128 + values[middle] as u128) / 2) as u64
+    } else {
+        values[middle]
+    }
+}
+
+/// Returns the difference between `value` and `reference` relative to
+/// `reference`, in basis points.
+fn relative_difference_permyriad(value: u64, reference: u64) -> u64 {
+    if reference == 0 {
+        return u64::MAX;
+    }
+    let difference = (value as i128 - reference as i128).unsigned_abs();
+    (difference * 10_000 / reference as u128).min(u64::MAX as u128) as u64
+}
+
+/// Caps `new_rate` so that it differs from `current_rate` by at most
+/// `max_change_permyriad` basis points.
+fn cap_rate_change(current_rate: u64, new_rate: u64, max_change_permyriad: u64) -> u64 {
+    let max_change = (current_rate as u128 * max_change_permyriad as u128 / 10_000) as u64;
+    new_rate.clamp(
+        current_rate.saturating_sub(max_change),
+        current_rate.saturating_add(max_change),
+    )
+}
+
 #[cfg(test)]
 mod test {
 
@@ -1045,6 +1209,141 @@ mod test {
         );
     }
 
+    fn sanity_checks() -> ExchangeRateSanityChecks {
+        ExchangeRateSanityChecks {
+            max_rate_change_permyriad: 1_000,
+            max_source_divergence_permyriad: 500,
+            freeze_divergence_permyriad: 5_000,
+        }
+    }
+
+    #[test]
+    fn test_periodic_caps_the_rate_change_if_sanity_checks_are_configured() {
+        thread_local! {
+            static STATE: RefCell<Option<State>> = RefCell::new(Some(State {
+                exchange_rate_sanity_checks: Some(sanity_checks()),
+                ..State::default()
+            }));
+        }
+
+        let env = TestExchangeRateCanisterEnvironment {
+            now_timestamp_seconds: 1680044700,
+            ..Default::default()
+        };
+        let xrc_client = MockExchangeRateCanisterClient::new(
+            vec![Ok(new_exchange_rate(
+                env.now_timestamp_seconds(),
+                MINIMUM_ICP_SOURCES,
+                MINIMUM_CXDR_SOURCES,
+            ))]
+            .into(),
+        );
+
+        let result = update_exchange_rate(&STATE, &env, &xrc_client)
+            .now_or_never()
+            .unwrap();
+
+        assert!(matches!(result, Ok(_)), "{:?}", result);
+        // The rate drops from 100 XDR to 20 XDR, which is capped to a 10% drop.
+        let icp_xdr_conversion_rate =
+            read_state(&STATE, |state| state.icp_xdr_conversion_rate.clone());
+        assert_eq!(
+            icp_xdr_conversion_rate,
+            Some(IcpXdrConversionRate {
+                timestamp_seconds: 1680044700,
+                xdr_permyriad_per_icp: 900_000,
+            })
+        );
+        // The exchange rate canister is the only source, so there is no disagreement.
+        read_state(&STATE, |state| {
+            assert_eq!(state.exchange_rate_sources_divergence_permyriad, Some(0));
+            assert_eq!(state.exchange_rate_sources_disagreement_count, Some(0));
+            assert_eq!(state.minting_frozen_since_timestamp_seconds, None);
+        });
+    }
+
+    #[test]
+    fn test_periodic_freezes_minting_if_the_rate_deviates_from_the_median() {
+        let now_timestamp_seconds = 1680044700;
+        thread_local! {
+            static STATE: RefCell<Option<State>> = RefCell::new(Some(State {
+                exchange_rate_sanity_checks: Some(sanity_checks()),
+                ..State::default()
+            }));
+        }
+        mutate_state(&STATE, |state| {
+            let start_of_day = (now_timestamp_seconds / 86_400) * 86_400;
+            state.recent_icp_xdr_rates.as_mut().unwrap()[0] = IcpXdrConversionRate {
+                timestamp_seconds: start_of_day,
+                xdr_permyriad_per_icp: 1_000_000,
+            };
+            state.last_proposed_icp_xdr_conversion_rate = Some(IcpXdrConversionRate {
+                timestamp_seconds: now_timestamp_seconds - 3_600,
+                xdr_permyriad_per_icp: 1_050_000,
+            });
+        });
+
+        let env = TestExchangeRateCanisterEnvironment {
+            now_timestamp_seconds,
+            ..Default::default()
+        };
+        let xrc_client = MockExchangeRateCanisterClient::new(
+            vec![Ok(new_exchange_rate(
+                now_timestamp_seconds,
+                MINIMUM_ICP_SOURCES,
+                MINIMUM_CXDR_SOURCES,
+            ))]
+            .into(),
+        );
+
+        let result = update_exchange_rate(&STATE, &env, &xrc_client)
+            .now_or_never()
+            .unwrap();
+
+        // The sources are 20 XDR, 105 XDR and 100 XDR, so the rate from the
+        // exchange rate canister deviates from the median by 80%.
+        assert!(
+            matches!(result, Err(UpdateExchangeRateError::InvalidRate(ref message)) if message.contains("minting is frozen")),
+            "{:?}",
+            result
+        );
+        read_state(&STATE, |state| {
+            assert_eq!(
+                state.exchange_rate_sources_divergence_permyriad,
+                Some(8_000)
+            );
+            assert_eq!(state.exchange_rate_sources_disagreement_count, Some(1));
+            assert_eq!(
+                state.minting_frozen_since_timestamp_seconds,
+                Some(now_timestamp_seconds)
+            );
+            assert_eq!(
+                state
+                    .icp_xdr_conversion_rate
+                    .as_ref()
+                    .unwrap()
+                    .xdr_permyriad_per_icp,
+                1_000_000
+            );
+        });
+    }
+
+    #[test]
+    fn test_median() {
+        assert_eq!(median(&mut [5]), 5);
+        assert_eq!(median(&mut [7, 1, 4]), 4);
+        assert_eq!(median(&mut [10, 1]), 5);
+        assert_eq!(median(&mut [u64::MAX, u64::MAX]), u64::MAX);
+    }
+
+    #[test]
+    fn test_cap_rate_change() {
+        assert_eq!(cap_rate_change(1_000_000, 1_050_000, 1_000), 1_050_000);
+        assert_eq!(cap_rate_change(1_000_000, 2_000_000, 1_000), 1_100_000);
+        assert_eq!(cap_rate_change(1_000_000, 200_000, 1_000), 900_000);
+        assert_eq!(cap_rate_change(1_000_000, 0, 20_000), 0);
+    }
+
     #[test]
     fn test_set_update_exchange_rate_state() {
         thread_local! {
@@ -32,6 +32,23 @@ impl ExchangeRateCanister {
         }
     }
 }
+/// Sanity checks applied to the ICP/XDR rates obtained from the exchange rate
+/// canister. All bounds are relative and expressed in basis points.
+#[derive(Serialize, Deserialize, CandidType, Clone, Debug, PartialEq, Eq)]
+pub struct ExchangeRateSanityChecks {
+    /// The maximum change of the ICP/XDR rate in a single update from the
+    /// exchange rate canister. Larger changes are capped to this bound.
+    pub max_rate_change_permyriad: u64,
+    /// The rate sources (the exchange rate canister, the latest rate set by
+    /// proposal, and the moving average) are considered to disagree if one of
+    /// them deviates from their median by more than this bound.
+    pub max_source_divergence_permyriad: u64,
+    /// If the rate from the exchange rate canister deviates from the median of
+    /// the rate sources by more than this bound, minting is frozen until a new
+    /// rate is set by proposal.
+    pub freeze_divergence_permyriad: u64,
+}
+
 #[derive(Serialize, Deserialize, CandidType, Clone, Debug, PartialEq, Eq)]
 pub struct CyclesCanisterInitPayload {
     pub ledger_canister_id: Option<CanisterId>,
@@ -39,6 +56,7 @@ pub struct CyclesCanisterInitPayload {
     pub minting_account_id: Option<AccountIdentifier>,
     pub last_purged_notification: Option<BlockIndex>,
     pub exchange_rate_canister: Option<ExchangeRateCanister>,
+    pub exchange_rate_sanity_checks: Option<ExchangeRateSanityChecks>,
 }
 
 /// Argument taken by top up notification endpoint
@@ -56,6 +74,35 @@ pub struct NotifyCreateCanister {
     pub subnet_type: Option<String>,
 }
 
+/// Argument taken by the create_canister_with_icrc2 endpoint
+#[derive(Serialize, Deserialize, CandidType, Clone, Hash, Debug, PartialEq, Eq)]
+pub struct CreateCanisterWithIcrc2 {
+    /// The subaccount of the caller from which the payment is pulled. The
+    /// caller must have approved the cycles minting canister to spend at
+    /// least `amount` plus the ledger fee from this account.
+    pub from_subaccount: Option<Subaccount>,
+    pub amount: Tokens,
+    /// The time of the request in nanoseconds since the UNIX epoch. Requests
+    /// from the same caller with the same `created_at_time` are deduplicated.
+    pub created_at_time: u64,
+    pub controller: PrincipalId,
+    pub subnet_type: Option<String>,
+}
+
+/// Argument taken by the top_up_with_icrc2 endpoint
+#[derive(Serialize, Deserialize, CandidType, Clone, Hash, Debug, PartialEq, Eq)]
+pub struct TopUpWithIcrc2 {
+    /// The subaccount of the caller from which the payment is pulled. The
+    /// caller must have approved the cycles minting canister to spend at
+    /// least `amount` plus the ledger fee from this account.
+    pub from_subaccount: Option<Subaccount>,
+    pub amount: Tokens,
+    /// The time of the request in nanoseconds since the UNIX epoch. Requests
+    /// from the same caller with the same `created_at_time` are deduplicated.
+    pub created_at_time: u64,
+    pub canister_id: CanisterId,
+}
+
 /// Error for notify endpoints
 #[derive(Serialize, Deserialize, CandidType, Clone, Hash, Debug, PartialEq, Eq)]
 pub enum NotifyError {
@@ -80,6 +127,13 @@ pub enum NotifyErrorCode {
     FailedToFetchBlock = 2,
     /// The cycles minting canister failed to execute the refund transaction.
     RefundFailed = 3,
+    /// The cycles minting canister failed to pull the payment from the
+    /// caller's account using icrc2_transfer_from.
+    TransferFromFailed = 4,
+    /// Minting the requested cycles would exceed the cycles minting limit.
+    CyclesLimitExceeded = 5,
+    /// Minting is frozen because the ICP/XDR rate looks manipulated.
+    MintingFrozen = 6,
 }
 
 impl NotifyError {
@@ -1,4 +1,4 @@
-use candid::{candid_method, CandidType, Encode};
+use candid::{candid_method, CandidType, Encode, Nat};
 use cycles_minting_canister::*;
 use dfn_candid::{candid_one, CandidOne};
 use dfn_core::{
@@ -26,6 +26,10 @@ use icp_ledger::{
     AccountIdentifier, Block, BlockIndex, BlockRes, CyclesResponse, Memo, Operation, SendArgs,
     Subaccount, Tokens, TransactionNotification, DEFAULT_TRANSFER_FEE,
 };
+use icrc_ledger_types::{
+    icrc1::{account::Account, transfer::Memo as Icrc1Memo},
+    icrc2::transfer_from::{TransferFromArgs, TransferFromError},
+};
 use on_wire::{FromWire, IntoWire, NewType};
 use rand::{rngs::StdRng, seq::SliceRandom, SeedableRng};
 use serde::{Deserialize, Serialize};
@@ -55,6 +59,15 @@ const MAX_NOTIFY_HISTORY: usize = 1_000_000;
 /// The maximum number of old notification statuses we purge in one go.
 const MAX_NOTIFY_PURGE: usize = 100_000;
 
+/// How long the outcome of an ICRC-2 based request is remembered. This
+/// matches the ledger's transaction deduplication window, so that a request
+/// that is forgotten by the cycles minting canister is also rejected as too
+/// old by the ledger.
+const ICRC2_REQUEST_WINDOW: Duration = Duration::from_secs(24 * 60 * 60);
+/// How far in the future the `created_at_time` of an ICRC-2 based request
+/// may be.
+const ICRC2_PERMITTED_DRIFT: Duration = Duration::from_secs(60);
+
 /// The maturity modulation range in basis points.
 const MIN_MATURITY_MODULATION_PERMYRIAD: i32 = -500;
 const MAX_MATURITY_MODULATION_PERMYRIAD: i32 = 500;
@@ -163,6 +176,10 @@ pub struct State {
     pub blocks_notified: Option<BTreeMap<BlockIndex, NotificationStatus>>,
     pub last_purged_notification: Option<BlockIndex>,
 
+    /// The outcomes of create_canister_with_icrc2 and top_up_with_icrc2
+    /// requests, keyed by the caller and the `created_at_time` of the request.
+    pub icrc2_requests: Option<BTreeMap<(PrincipalId, u64), NotificationStatus>>,
+
     /// The current maturity modulation in basis points (permyriad), i.e.,
     /// a value of 123 corresponds to 1.23%.
     pub maturity_modulation_permyriad: Option<i32>,
@@ -186,6 +203,25 @@ pub struct State {
 
     /// This is used to ensure that only one exchange rate update is being performed at a time from heartbeat.
     pub update_exchange_rate_canister_state: Option<UpdateExchangeRateState>,
+
+    /// The sanity checks applied to rates from the exchange rate canister. If
+    /// not set, rates from the exchange rate canister are used as they are.
+    pub exchange_rate_sanity_checks: Option<ExchangeRateSanityChecks>,
+
+    /// The latest ICP/XDR rate set by an NNS proposal.
+    pub last_proposed_icp_xdr_conversion_rate: Option<IcpXdrConversionRate>,
+
+    /// The largest deviation of a rate source from the median of the rate
+    /// sources observed in the latest exchange rate update, in basis points.
+    pub exchange_rate_sources_divergence_permyriad: Option<u64>,
+
+    /// How many exchange rate updates found the rate sources to disagree.
+    pub exchange_rate_sources_disagreement_count: Option<u64>,
+
+    /// If set, minting is frozen since the given time because the rate from
+    /// the exchange rate canister looked manipulated. Minting resumes once a
+    /// new rate is set by proposal.
+    pub minting_frozen_since_timestamp_seconds: Option<u64>,
 }
 
 impl State {
@@ -223,6 +259,30 @@ impl State {
         last_purged = last_purged.max(self.last_purged_notification.unwrap());
         self.last_purged_notification = Some(last_purged);
     }
+
+    // Forget the outcomes of ICRC-2 based requests that were created more
+    // than ICRC2_REQUEST_WINDOW before `now_nanos`.
+    fn purge_old_icrc2_requests(&mut self, now_nanos: u64) {
+        let cutoff = now_nanos.saturating_sub(ICRC2_REQUEST_WINDOW.as_nanos() as u64);
+        self.icrc2_requests
+            .get_or_insert_with(BTreeMap::new)
+            .retain(|(_, created_at_time), _| *created_at_time >= cutoff);
+    }
+
+    // Checks that minting `cycles` now would not exceed the cycles limit.
+    fn check_cycles_limit(&mut self, now: SystemTime, cycles: Cycles) -> Result<(), String> {
+        self.limiter.purge_old(now);
+        let count = self.limiter.get_count();
+
+        if count + cycles > self.cycles_limit {
+            return Err(format!(
+                "More than {} cycles have been minted in the last {} seconds, please try again later.",
+                self.cycles_limit,
+                self.limiter.get_max_age().as_secs(),
+            ));
+        }
+        Ok(())
+    }
 }
 
 impl Default for State {
@@ -252,9 +312,15 @@ impl Default for State {
             total_cycles_minted: Cycles::zero(),
             blocks_notified: Some(BTreeMap::new()),
             last_purged_notification: Some(0),
+            icrc2_requests: Some(BTreeMap::new()),
             maturity_modulation_permyriad: Some(0),
             subnet_types_to_subnets: Some(BTreeMap::new()),
             update_exchange_rate_canister_state: Some(UpdateExchangeRateState::default()),
+            exchange_rate_sanity_checks: None,
+            last_proposed_icp_xdr_conversion_rate: None,
+            exchange_rate_sources_divergence_permyriad: None,
+            exchange_rate_sources_disagreement_count: Some(0),
+            minting_frozen_since_timestamp_seconds: None,
         }
     }
 }
@@ -303,6 +369,7 @@ fn init(maybe_args: Option<CyclesCanisterInitPayload>) {
         if let Some(xrc_flag) = args.exchange_rate_canister {
             state.exchange_rate_canister_id = xrc_flag.extract_exchange_rate_canister_id();
         }
+        state.exchange_rate_sanity_checks = args.exchange_rate_sanity_checks;
     });
 }
 
@@ -811,7 +878,14 @@ fn set_icp_xdr_conversion_rate_() {
             let env = CanisterEnvironment;
             let rate = IcpXdrConversionRate::from(&proposed_conversion_rate);
             let rate_timestamp_seconds = rate.timestamp_seconds;
-            let result = set_icp_xdr_conversion_rate(&STATE, &env, rate);
+            let result = set_icp_xdr_conversion_rate(&STATE, &env, rate.clone());
+            if result.is_ok() {
+                // A rate set by proposal is trusted, so it unfreezes minting.
+                with_state_mut(|state| {
+                    state.last_proposed_icp_xdr_conversion_rate = Some(rate);
+                    state.minting_frozen_since_timestamp_seconds = None;
+                });
+            }
             if result.is_ok() && with_state(|state| state.exchange_rate_canister_id.is_some()) {
                 exchange_rate_canister::set_update_exchange_rate_state(
                     &STATE,
@@ -989,6 +1063,16 @@ fn notify_create_canister_() {
     over_async(candid_one, notify_create_canister)
 }
 
+#[export_name = "canister_update top_up_with_icrc2"]
+fn top_up_with_icrc2_() {
+    over_async(candid_one, top_up_with_icrc2)
+}
+
+#[export_name = "canister_update create_canister_with_icrc2"]
+fn create_canister_with_icrc2_() {
+    over_async(candid_one, create_canister_with_icrc2)
+}
+
 fn is_transient_error<T>(result: &Result<T, NotifyError>) -> bool {
     if let Err(e) = result {
         return e.is_retriable();
@@ -1127,6 +1211,258 @@ async fn notify_create_canister(
     }
 }
 
+/// Top up a canister with cycles paid for by the caller through an ICRC-2
+/// approval on the ICP ledger.
+///
+/// The payment is pulled from the caller's account with icrc2_transfer_from
+/// and the result of the request is remembered for the caller and
+/// `created_at_time`, so that retrying the request is safe.
+#[candid_method(update, rename = "top_up_with_icrc2")]
+async fn top_up_with_icrc2(
+    TopUpWithIcrc2 {
+        from_subaccount,
+        amount,
+        created_at_time,
+        canister_id,
+    }: TopUpWithIcrc2,
+) -> Result<Cycles, NotifyError> {
+    let caller = caller();
+    let key = (caller, created_at_time);
+
+    let maybe_early_result =
+        with_state_mut(
+            |state| match begin_icrc2_request(state, key, now_nanos())? {
+                None => Ok(None),
+                Some(NotificationStatus::Processing) => Err(NotifyError::Processing),
+                Some(NotificationStatus::NotifiedTopUp(result)) => Ok(Some(result)),
+                Some(NotificationStatus::NotifiedCreateCanister(_)) => {
+                    Err(NotifyError::InvalidTransaction(
+                        "The same request is already processed as create canister request".into(),
+                    ))
+                }
+            },
+        )?;
+    if let Some(result) = maybe_early_result {
+        return result;
+    }
+
+    let sub = Subaccount::from(&canister_id);
+    let from = AccountIdentifier::new(caller, from_subaccount);
+    let result = match pull_payment(
+        caller,
+        from_subaccount,
+        sub,
+        amount,
+        MEMO_TOP_UP_CANISTER,
+        created_at_time,
+    )
+    .await
+    {
+        Ok(()) => process_top_up(canister_id, from, amount).await,
+        Err(err) => Err(err),
+    };
+
+    finish_icrc2_request(
+        key,
+        NotificationStatus::NotifiedTopUp(result.clone()),
+        &result,
+    );
+
+    result
+}
+
+/// Create a canister with cycles paid for by the caller through an ICRC-2
+/// approval on the ICP ledger.
+///
+/// The payment is pulled from the caller's account with icrc2_transfer_from
+/// and the result of the request is remembered for the caller and
+/// `created_at_time`, so that retrying the request is safe.
+#[candid_method(update, rename = "create_canister_with_icrc2")]
+async fn create_canister_with_icrc2(
+    CreateCanisterWithIcrc2 {
+        from_subaccount,
+        amount,
+        created_at_time,
+        controller,
+        subnet_type,
+    }: CreateCanisterWithIcrc2,
+) -> Result<CanisterId, NotifyError> {
+    let caller = caller();
+    let key = (caller, created_at_time);
+
+    let maybe_early_result =
+        with_state_mut(
+            |state| match begin_icrc2_request(state, key, now_nanos())? {
+                None => Ok(None),
+                Some(NotificationStatus::Processing) => Err(NotifyError::Processing),
+                Some(NotificationStatus::NotifiedCreateCanister(result)) => Ok(Some(result)),
+                Some(NotificationStatus::NotifiedTopUp(_)) => Err(NotifyError::InvalidTransaction(
+                    "The same request is already processed as a top up request.".into(),
+                )),
+            },
+        )?;
+    if let Some(result) = maybe_early_result {
+        return result;
+    }
+
+    let sub = Subaccount::from(&controller);
+    let from = AccountIdentifier::new(caller, from_subaccount);
+    let result = match pull_payment(
+        caller,
+        from_subaccount,
+        sub,
+        amount,
+        MEMO_CREATE_CANISTER,
+        created_at_time,
+    )
+    .await
+    {
+        Ok(()) => process_create_canister(controller, from, amount, subnet_type).await,
+        Err(err) => Err(err),
+    };
+
+    finish_icrc2_request(
+        key,
+        NotificationStatus::NotifiedCreateCanister(result.clone()),
+        &result,
+    );
+
+    result
+}
+
+fn now_nanos() -> u64 {
+    dfn_core::api::now()
+        .duration_since(UNIX_EPOCH)
+        .expect("Could not get the duration.")
+        .as_nanos() as u64
+}
+
+/// Validates the `created_at_time` of an ICRC-2 based request and looks up
+/// the status of the request. If the request is not known yet, it is marked
+/// as being processed and `None` is returned.
+fn begin_icrc2_request(
+    state: &mut State,
+    key: (PrincipalId, u64),
+    now_nanos: u64,
+) -> Result<Option<NotificationStatus>, NotifyError> {
+    let (_, created_at_time) = key;
+    state.purge_old_icrc2_requests(now_nanos);
+
+    if created_at_time < now_nanos.saturating_sub(ICRC2_REQUEST_WINDOW.as_nanos() as u64) {
+        return Err(NotifyError::InvalidTransaction(format!(
+            "The request is too old: created_at_time {} is more than {} seconds in the past",
+            created_at_time,
+            ICRC2_REQUEST_WINDOW.as_secs()
+        )));
+    }
+    if created_at_time > now_nanos.saturating_add(ICRC2_PERMITTED_DRIFT.as_nanos() as u64) {
+        return Err(NotifyError::InvalidTransaction(format!(
+            "The request was created in the future: created_at_time {} is later than {}",
+            created_at_time, now_nanos
+        )));
+    }
+
+    match state.icrc2_requests.as_mut().unwrap().entry(key) {
+        Entry::Occupied(entry) => Ok(Some(entry.get().clone())),
+        Entry::Vacant(entry) => {
+            entry.insert(NotificationStatus::Processing);
+            Ok(None)
+        }
+    }
+}
+
+/// Records the outcome of an ICRC-2 based request. Requests that failed with
+/// a transient error are forgotten so that they can be retried.
+fn finish_icrc2_request<T>(
+    key: (PrincipalId, u64),
+    status: NotificationStatus,
+    result: &Result<T, NotifyError>,
+) {
+    with_state_mut(|state| {
+        let requests = state.icrc2_requests.as_mut().unwrap();
+        if is_transient_error(result) {
+            requests.remove(&key);
+        } else {
+            requests.insert(key, status);
+        }
+    });
+}
+
+/// Pulls `amount` from the caller's account into the given subaccount of the
+/// cycles minting canister using icrc2_transfer_from.
+///
+/// The cycles limit is checked before the transfer, so that no payment is
+/// taken (and refunded) for a request that cannot be served. If the ledger
+/// reports the transfer as a duplicate, the payment was already taken by an
+/// earlier attempt of the same request whose processing failed with a
+/// transient error, and the payment is considered to have been made.
+async fn pull_payment(
+    caller: PrincipalId,
+    from_subaccount: Option<Subaccount>,
+    to_subaccount: Subaccount,
+    amount: Tokens,
+    memo: Memo,
+    created_at_time: u64,
+) -> Result<(), NotifyError> {
+    let cycles = tokens_to_cycles(amount)?;
+    with_state_mut(|state| state.check_cycles_limit(dfn_core::api::now(), cycles)).map_err(
+        |error_message| NotifyError::Other {
+            error_code: NotifyErrorCode::CyclesLimitExceeded as u64,
+            error_message,
+        },
+    )?;
+
+    let ledger_canister_id = with_state(|state| state.ledger_canister_id);
+    let args = TransferFromArgs {
+        spender_subaccount: None,
+        from: Account {
+            owner: caller.0,
+            subaccount: from_subaccount.map(|s| s.0),
+        },
+        to: Account {
+            owner: dfn_core::api::id().get().0,
+            subaccount: Some(to_subaccount.0),
+        },
+        amount: Nat::from(amount.get_e8s()),
+        fee: None,
+        memo: Some(Icrc1Memo::from(memo.0)),
+        created_at_time: Some(created_at_time),
+    };
+
+    let res: Result<Result<Nat, TransferFromError>, (Option<i32>, String)> =
+        call_with_cleanup(ledger_canister_id, "icrc2_transfer_from", candid_one, args).await;
+
+    let transfer_from_failed = |error_message: String| NotifyError::Other {
+        error_code: NotifyErrorCode::TransferFromFailed as u64,
+        error_message,
+    };
+    match res {
+        Ok(Ok(block_index)) => {
+            print(format!(
+                "[cycles] pulled {} from {} in block {}",
+                amount, caller, block_index
+            ));
+            Ok(())
+        }
+        Ok(Err(TransferFromError::Duplicate { duplicate_of })) => {
+            print(format!(
+                "[cycles] payment of {} from {} was already pulled in block {}",
+                amount, caller, duplicate_of
+            ));
+            Ok(())
+        }
+        Ok(Err(err)) => Err(transfer_from_failed(format!(
+            "Transferring {} from {} failed: {:?}",
+            amount, caller, err
+        ))),
+        Err((code, err)) => Err(transfer_from_failed(format!(
+            "Calling icrc2_transfer_from failed with code {}: {}",
+            code.unwrap_or_default(),
+            err
+        ))),
+    }
+}
+
 async fn query_block(block_index: BlockIndex, ledger_id: CanisterId) -> Result<Block, NotifyError> {
     fn failed_to_fetch_block(error_message: String) -> NotifyError {
         NotifyError::Other {
@@ -1348,6 +1684,17 @@ async fn transaction_notification(tn: TransactionNotification) -> Result<CyclesR
 // If conversion fails, log and return an error
 fn tokens_to_cycles(amount: Tokens) -> Result<Cycles, NotifyError> {
     with_state(|state| {
+        if let Some(frozen_since) = state.minting_frozen_since_timestamp_seconds {
+            let error_message = format!(
+                "Minting is frozen since {} because the ICP/XDR rate looks manipulated, notification aborted",
+                frozen_since
+            );
+            print(&error_message);
+            return Err(NotifyError::Other {
+                error_code: NotifyErrorCode::MintingFrozen as u64,
+                error_message,
+            });
+        }
         let xdr_permyriad_per_icp = state
             .icp_xdr_conversion_rate
             .as_ref()
@@ -1647,17 +1994,7 @@ fn ensure_balance(cycles: Cycles) -> Result<(), String> {
     let now = dfn_core::api::now();
 
     with_state_mut(|state| {
-        state.limiter.purge_old(now);
-        let count = state.limiter.get_count();
-
-        if count + cycles > state.cycles_limit {
-            return Err(format!(
-                "More than {} cycles have been minted in the last {} seconds, please try again later.",
-                state.cycles_limit,
-                state.limiter.get_max_age().as_secs(),
-            ));
-        }
-
+        state.check_cycles_limit(now, cycles)?;
         state.limiter.add(now, cycles);
         state.total_cycles_minted += cycles;
         Ok(())
@@ -1738,11 +2075,17 @@ fn post_upgrade(maybe_args: Option<CyclesCanisterInitPayload>) {
     if new_state.subnet_types_to_subnets.is_none() {
         new_state.subnet_types_to_subnets = Some(BTreeMap::new());
     }
+    if new_state.icrc2_requests.is_none() {
+        new_state.icrc2_requests = Some(BTreeMap::new());
+    }
 
     if let Some(args) = maybe_args {
         if let Some(xrc_flag) = args.exchange_rate_canister {
             new_state.exchange_rate_canister_id = xrc_flag.extract_exchange_rate_canister_id();
         }
+        if let Some(sanity_checks) = args.exchange_rate_sanity_checks {
+            new_state.exchange_rate_sanity_checks = Some(sanity_checks);
+        }
     }
 
     STATE.with(|state| state.replace(Some(new_state)));
@@ -1800,6 +2143,11 @@ fn encode_metrics(w: &mut ic_metrics_encoder::MetricsEncoder<Vec<u8>>) -> std::i
             state.blocks_notified.as_ref().unwrap().len() as f64,
             "Number of notifications stored in the cache.",
         )?;
+        w.encode_gauge(
+            "cmc_icrc2_requests_count",
+            state.icrc2_requests.as_ref().unwrap().len() as f64,
+            "Number of ICRC-2 based request outcomes stored in the cache.",
+        )?;
         w.encode_gauge(
             "cmc_icp_xdr_conversion_rate",
             state
@@ -1815,6 +2163,25 @@ fn encode_metrics(w: &mut ic_metrics_encoder::MetricsEncoder<Vec<u8>>) -> std::i
             state.cycles_per_xdr.get() as f64,
             "Number of cycles corresponding to 1 XDR.",
         )?;
+        w.encode_gauge(
+            "cmc_exchange_rate_sources_divergence_permyriad",
+            state
+                .exchange_rate_sources_divergence_permyriad
+                .unwrap_or_default() as f64,
+            "Largest deviation of an ICP/XDR rate source from the median of the sources in the latest update, in basis points.",
+        )?;
+        w.encode_counter(
+            "cmc_exchange_rate_sources_disagreements_total",
+            state
+                .exchange_rate_sources_disagreement_count
+                .unwrap_or_default() as f64,
+            "Number of exchange rate updates in which the ICP/XDR rate sources disagreed.",
+        )?;
+        w.encode_gauge(
+            "cmc_minting_frozen",
+            state.minting_frozen_since_timestamp_seconds.is_some() as u64 as f64,
+            "Whether minting is frozen because the ICP/XDR rate looks manipulated.",
+        )?;
         w.encode_counter(
             "cmc_cycles_minted_total",
             state.total_cycles_minted.get() as f64,
@@ -1869,6 +2236,7 @@ mod tests {
             ledger_canister_id: Some(CanisterId::ic_00()),
             governance_canister_id: Some(CanisterId::ic_00()),
             exchange_rate_canister: None,
+            exchange_rate_sanity_checks: None,
             minting_account_id: None,
             last_purged_notification: Some(0),
         }))
@@ -1905,6 +2273,10 @@ mod tests {
             .unwrap())),
         );
         state.blocks_notified = Some(blocks_notified);
+        state.icrc2_requests = Some(BTreeMap::from([(
+            (PrincipalId::new_user_test_id(5), 1_000),
+            NotificationStatus::NotifiedTopUp(Ok(Cycles::new(1_000))),
+        )]));
 
         let bytes = state.encode();
 
@@ -1969,6 +2341,95 @@ mod tests {
         );
     }
 
+    #[test]
+    fn test_begin_icrc2_request_deduplicates_on_caller_and_created_at_time() {
+        let mut state = State::default();
+        let now_nanos = 10 * ICRC2_REQUEST_WINDOW.as_nanos() as u64;
+        let caller = PrincipalId::new_user_test_id(1);
+        let other_caller = PrincipalId::new_user_test_id(2);
+
+        assert_eq!(
+            begin_icrc2_request(&mut state, (caller, now_nanos), now_nanos),
+            Ok(None)
+        );
+        assert_eq!(
+            begin_icrc2_request(&mut state, (caller, now_nanos), now_nanos),
+            Ok(Some(NotificationStatus::Processing))
+        );
+        // The same created_at_time from a different caller is a different request.
+        assert_eq!(
+            begin_icrc2_request(&mut state, (other_caller, now_nanos), now_nanos),
+            Ok(None)
+        );
+
+        let result = NotificationStatus::NotifiedTopUp(Ok(Cycles::new(42)));
+        state
+            .icrc2_requests
+            .as_mut()
+            .unwrap()
+            .insert((caller, now_nanos), result.clone());
+        assert_eq!(
+            begin_icrc2_request(&mut state, (caller, now_nanos), now_nanos + 1),
+            Ok(Some(result))
+        );
+    }
+
+    #[test]
+    fn test_begin_icrc2_request_rejects_old_and_future_requests() {
+        let mut state = State::default();
+        let now_nanos = 10 * ICRC2_REQUEST_WINDOW.as_nanos() as u64;
+        let caller = PrincipalId::new_user_test_id(1);
+
+        let too_old = now_nanos - ICRC2_REQUEST_WINDOW.as_nanos() as u64 - 1;
+        assert!(matches!(
+            begin_icrc2_request(&mut state, (caller, too_old), now_nanos),
+            Err(NotifyError::InvalidTransaction(_))
+        ));
+        let too_new = now_nanos + ICRC2_PERMITTED_DRIFT.as_nanos() as u64 + 1;
+        assert!(matches!(
+            begin_icrc2_request(&mut state, (caller, too_new), now_nanos),
+            Err(NotifyError::InvalidTransaction(_))
+        ));
+        let slightly_in_future = now_nanos + ICRC2_PERMITTED_DRIFT.as_nanos() as u64;
+        assert_eq!(
+            begin_icrc2_request(&mut state, (caller, slightly_in_future), now_nanos),
+            Ok(None)
+        );
+        assert_eq!(state.icrc2_requests.as_ref().unwrap().len(), 1);
+    }
+
+    #[test]
+    fn test_purge_old_icrc2_requests() {
+        let window_nanos = ICRC2_REQUEST_WINDOW.as_nanos() as u64;
+        let now_nanos = 10 * window_nanos;
+        let caller = PrincipalId::new_user_test_id(1);
+        let mut state = State {
+            icrc2_requests: Some(BTreeMap::from([
+                (
+                    (caller, now_nanos - window_nanos - 1),
+                    NotificationStatus::Processing,
+                ),
+                (
+                    (caller, now_nanos - window_nanos),
+                    NotificationStatus::Processing,
+                ),
+                ((caller, now_nanos), NotificationStatus::Processing),
+            ])),
+            ..Default::default()
+        };
+
+        state.purge_old_icrc2_requests(now_nanos);
+
+        assert_eq!(
+            state
+                .icrc2_requests
+                .unwrap()
+                .into_keys()
+                .collect::<Vec<_>>(),
+            vec![(caller, now_nanos - window_nanos), (caller, now_nanos)]
+        );
+    }
+
     /// The function returns sample conversion rates set for testing.
     fn get_sample_conversion_rates(timestamp: u64) -> Vec<IcpXdrConversionRate> {
         let average_rate_interval = NUM_DAYS_FOR_ICP_XDR_AVERAGE as u64;
@@ -10,7 +10,7 @@
 // did definition of the method.
 
 use async_trait::async_trait;
-use candid::{candid_method, Decode, Encode};
+use candid::{candid_method, CandidType, Decode, Encode};
 use dfn_candid::{candid, candid_one};
 use dfn_core::{
     api::{arg_data, call_with_callbacks, caller, now, reject_message},
@@ -291,6 +291,14 @@ fn canister_pre_upgrade() {
     });
 }
 
+/// The optional (Candid-encoded) argument of an upgrade.
+#[derive(CandidType, serde::Deserialize)]
+struct UpgradeArgs {
+    /// If set, enables or disables the migration of inactive neurons to the
+    /// stable neuron store.
+    inactive_neuron_migration_enabled: Option<bool>,
+}
+
 #[export_name = "canister_post_upgrade"]
 fn canister_post_upgrade() {
     dfn_core::printer::hook();
@@ -329,6 +337,17 @@ fn canister_post_upgrade() {
 
     canister_init_(proto);
 
+    let arg = arg_data();
+    if !arg.is_empty() {
+        let UpgradeArgs {
+            inactive_neuron_migration_enabled,
+        } = Decode!(&arg, UpgradeArgs).expect("Couldn't decode the upgrade argument");
+        if inactive_neuron_migration_enabled.is_some() {
+            governance_mut().proto.inactive_neuron_migration_enabled =
+                inactive_neuron_migration_enabled;
+        }
+    }
+
     // TODO: remove this after the incident is fully resolved.
     // Reset aging timestamps for https://forum.dfinity.org/t/icp-neuron-age-is-52-years/21261/26
     governance_mut().maybe_reset_aging_timestamps()
@@ -71,6 +71,10 @@ pub fn generate_prost_files(proto: ProtoPaths<'_>, out: &Path) {
     config.type_attribute("ic_nns_governance.pb.v1.BallotInfo", "#[derive(Eq)]");
     config.type_attribute("ic_nns_governance.pb.v1.NeuronInfo", "#[derive(Eq)]");
     config.type_attribute("ic_nns_governance.pb.v1.KnownNeuronData", "#[derive(Eq)]");
+    config.type_attribute(
+        "ic_nns_governance.pb.v1.VotingPowerDelegation",
+        "#[derive(Eq)]",
+    );
 
     // self_describing
     // ---------------
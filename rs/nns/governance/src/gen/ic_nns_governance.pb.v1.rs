@@ -104,6 +104,34 @@ pub struct NeuronInfo {
     /// If this neuron is a known neuron, this is data associated with it, including the neuron's name and (optionally) a description.
     #[prost(message, optional, tag = "10")]
     pub known_neuron_data: ::core::option::Option<KnownNeuronData>,
+    /// How the current voting power of the neuron is split across the
+    /// followees it delegates to with weights, ordered by topic.
+    #[prost(message, repeated, tag = "11")]
+    pub voting_power_delegations: ::prost::alloc::vec::Vec<VotingPowerDelegation>,
+}
+/// The part of a neuron's voting power delegated to one of its followees on
+/// a topic.
+#[derive(
+    candid::CandidType,
+    candid::Deserialize,
+    serde::Serialize,
+    comparable::Comparable,
+    Eq,
+    Clone,
+    PartialEq,
+    ::prost::Message,
+)]
+pub struct VotingPowerDelegation {
+    #[prost(enumeration = "Topic", tag = "1")]
+    pub topic: i32,
+    #[prost(message, optional, tag = "2")]
+    pub followee: ::core::option::Option<::ic_nns_common::pb::v1::NeuronId>,
+    #[prost(uint32, tag = "3")]
+    pub weight_basis_points: u32,
+    /// The share of the neuron's current voting power delegated to the
+    /// followee.
+    #[prost(uint64, tag = "4")]
+    pub delegated_voting_power: u64,
 }
 /// A transfer performed from some account to stake a new neuron.
 #[derive(
@@ -290,6 +318,12 @@ pub mod neuron {
     pub struct Followees {
         #[prost(message, repeated, tag = "1")]
         pub followees: ::prost::alloc::vec::Vec<::ic_nns_common::pb::v1::NeuronId>,
+        /// If empty, the neuron votes like the majority of its followees. If
+        /// not empty, it has the same length as `followees`, and the neuron
+        /// delegates the given share of its voting power, in basis points, to
+        /// the followee at the same position. The shares add up to 10_000.
+        #[prost(uint32, repeated, tag = "2")]
+        pub weights_basis_points: ::prost::alloc::vec::Vec<u32>,
     }
     /// At any time, at most one of `when_dissolved` and
     /// `dissolve_delay` are specified.
@@ -1088,6 +1122,13 @@ pub mod manage_neuron {
         pub topic: i32,
         #[prost(message, repeated, tag = "2")]
         pub followees: ::prost::alloc::vec::Vec<::ic_nns_common::pb::v1::NeuronId>,
+        /// If not empty, the neuron splits its voting power across the
+        /// followees instead of voting like their majority: the followee at
+        /// each position is delegated the share of the voting power given at
+        /// the same position, in basis points. Must have the same length as
+        /// 'followees', and the shares must add up to 10_000.
+        #[prost(uint32, repeated, tag = "3")]
+        pub weights_basis_points: ::prost::alloc::vec::Vec<u32>,
     }
     /// Have the neuron vote to either adopt or reject a proposal with a specified
     /// id.
@@ -1565,6 +1606,19 @@ pub struct Ballot {
     pub vote: i32,
     #[prost(uint64, tag = "2")]
     pub voting_power: u64,
+    /// For a neuron that delegates its voting power to several followees
+    /// with weights (see `Neuron.Followees.weights_basis_points`), the parts
+    /// of `voting_power` that have been cast yes and no by the followees so
+    /// far. Only set while `vote` is unspecified.
+    #[prost(uint64, tag = "3")]
+    pub delegated_yes_voting_power: u64,
+    #[prost(uint64, tag = "4")]
+    pub delegated_no_voting_power: u64,
+    /// True once all the followees the voting power is delegated to have
+    /// voted (directly, or through completed delegations of their own), so
+    /// that the delegated votes will not change anymore.
+    #[prost(bool, tag = "5")]
+    pub delegation_complete: bool,
 }
 /// A tally of votes.
 #[derive(candid::CandidType, candid::Deserialize, serde::Serialize, comparable::Comparable)]
@@ -2460,6 +2514,10 @@ pub struct Governance {
     /// that it should finish before being called again.
     #[prost(bool, optional, tag = "19")]
     pub spawning_neurons: ::core::option::Option<bool>,
+    /// Whether inactive neurons are moved to the stable neuron store by the
+    /// heartbeat. Can be set at init, or with the upgrade argument.
+    #[prost(bool, optional, tag = "20")]
+    pub inactive_neuron_migration_enabled: ::core::option::Option<bool>,
 }
 /// Nested message and enum types in `Governance`.
 pub mod governance {
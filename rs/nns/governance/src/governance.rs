@@ -36,6 +36,7 @@ use crate::{
         create_service_nervous_system_proposals_is_enabled,
         ExecutedCreateServiceNervousSystemProposal,
     },
+    storage::STABLE_NEURON_STORE,
 };
 use async_trait::async_trait;
 use candid::{Decode, Encode};
@@ -47,6 +48,7 @@ use ic_base_types::{CanisterId, PrincipalId};
 use ic_crypto_sha::Sha256;
 use ic_nervous_system_common::{
     cmc::CMC, ledger, ledger::IcpLedger, validate_proposal_url, NervousSystemError,
+    BASIS_POINTS_PER_UNITY,
 };
 use ic_nns_common::{
     pb::v1::{NeuronId, ProposalId},
@@ -71,6 +73,7 @@ use registry_canister::{
     mutations::do_add_node_operator::AddNodeOperatorPayload, pb::v1::NodeProvidersMonthlyXdrRewards,
 };
 use std::{
+    borrow::Cow,
     cmp::Ordering,
     collections::{BTreeMap, BTreeSet, HashMap, HashSet},
     convert::{TryFrom, TryInto},
@@ -151,6 +154,10 @@ pub const REWARD_DISTRIBUTION_PERIOD_SECONDS: u64 = ONE_DAY_SECONDS;
 /// The maximum number of neurons supported.
 pub const MAX_NUMBER_OF_NEURONS: usize = 220_000;
 
+/// The maximum number of heap neurons examined, and possibly moved to the
+/// stable neuron store, each time periodic tasks run.
+pub const MAX_NEURONS_EXAMINED_FOR_STABLE_MEMORY_PER_ROUND: usize = 1000;
+
 /// The maximum number results returned by the method `list_proposals`.
 pub const MAX_LIST_PROPOSAL_RESULTS: u32 = 100;
 
@@ -907,7 +914,19 @@ impl ProposalData {
         for ballot in self.ballots.values() {
             let lhs: &mut u64 = if let Some(vote) = Vote::from_i32(ballot.vote) {
                 match vote {
-                    Vote::Unspecified => &mut undecided,
+                    Vote::Unspecified => {
+                        // Parts of the voting power may have been cast
+                        // by the followees it is delegated to.
+                        yes = yes.saturating_add(ballot.delegated_yes_voting_power);
+                        no = no.saturating_add(ballot.delegated_no_voting_power);
+                        undecided = undecided.saturating_add(
+                            ballot
+                                .voting_power
+                                .saturating_sub(ballot.delegated_yes_voting_power)
+                                .saturating_sub(ballot.delegated_no_voting_power),
+                        );
+                        continue;
+                    }
                     Vote::Yes => &mut yes,
                     Vote::No => &mut no,
                 }
@@ -1333,6 +1352,12 @@ impl GovernanceProto {
         proposed: &HashMap<i32, Followees>,
     ) -> Result<(), GovernanceError> {
         for followees in proposed.values() {
+            if !followees.weights_basis_points.is_empty() {
+                return Err(GovernanceError::new_with_message(
+                    ErrorType::InvalidProposal,
+                    "Default followees cannot split voting power with weights.",
+                ));
+            }
             for followee in &followees.followees {
                 if !self.neurons.contains_key(&followee.id) {
                     return Err(GovernanceError::new_with_message(
@@ -1588,6 +1613,27 @@ pub struct Governance {
 
     /// The number of proposals after the last time GC was run.
     pub latest_gc_num_proposals: usize,
+
+    /// The smallest ID of the heap neurons to be examined by the next call to
+    /// `move_inactive_neurons_to_stable_memory`.
+    inactive_neuron_migration_cursor: u64,
+}
+
+/// The IDs and subaccounts of the neurons involved with open proposals.
+#[derive(Default)]
+struct NeuronsInvolvedWithOpenProposals {
+    neuron_ids: HashSet<u64>,
+    subaccounts: HashSet<Vec<u8>>,
+}
+
+impl NeuronsInvolvedWithOpenProposals {
+    fn contains(&self, neuron: &Neuron) -> bool {
+        neuron
+            .id
+            .as_ref()
+            .map_or(false, |id| self.neuron_ids.contains(&id.id))
+            || self.subaccounts.contains(&neuron.account)
+    }
 }
 
 pub fn governance_minting_account() -> AccountIdentifier {
@@ -1598,6 +1644,31 @@ pub fn neuron_subaccount(subaccount: Subaccount) -> AccountIdentifier {
     AccountIdentifier::new(GOVERNANCE_CANISTER_ID.get(), Some(subaccount))
 }
 
+fn num_archived_neurons() -> usize {
+    STABLE_NEURON_STORE.with(|store| store.borrow().len() as usize)
+}
+
+fn is_archived_neuron(neuron_id: u64) -> bool {
+    STABLE_NEURON_STORE.with(|store| store.borrow().contains(neuron_id))
+}
+
+fn archived_neuron_id(find_by: &NeuronIdOrSubaccount) -> Option<u64> {
+    match find_by {
+        NeuronIdOrSubaccount::NeuronId(nid) => Some(nid.id).filter(|id| is_archived_neuron(*id)),
+        NeuronIdOrSubaccount::Subaccount(sid) => {
+            let subaccount = Subaccount::try_from(&sid[..]).ok()?;
+            STABLE_NEURON_STORE
+                .with(|store| store.borrow().get_neuron_id_by_subaccount(&subaccount))
+                .map(|nid| nid.id)
+        }
+    }
+}
+
+fn find_archived_neuron(find_by: &NeuronIdOrSubaccount) -> Option<Neuron> {
+    let neuron_id = archived_neuron_id(find_by)?;
+    STABLE_NEURON_STORE.with(|store| store.borrow().get(neuron_id))
+}
+
 impl Governance {
     pub fn new(
         mut proto: GovernanceProto,
@@ -1634,6 +1705,7 @@ impl Governance {
             closest_proposal_deadline_timestamp_seconds: 0,
             latest_gc_timestamp_seconds: 0,
             latest_gc_num_proposals: 0,
+            inactive_neuron_migration_cursor: 0,
         };
 
         gov.initialize_indices();
@@ -1696,7 +1768,7 @@ impl Governance {
         let mut id = self.env.random_u64();
         // Don't allow IDs that are already in use. In addition, zero
         // is an invalid ID as it can be confused with an unset ID.
-        while self.proto.neurons.contains_key(&id) || id == 0 {
+        while self.proto.neurons.contains_key(&id) || is_archived_neuron(id) || id == 0 {
             id = self.env.random_u64();
         }
         NeuronId { id }
@@ -1730,6 +1802,7 @@ impl Governance {
     }
 
     pub fn get_neuron_mut(&mut self, nid: &NeuronId) -> Result<&mut Neuron, GovernanceError> {
+        self.restore_archived_neuron(&NeuronIdOrSubaccount::NeuronId(*nid));
         self.proto
             .neurons
             .get_mut(&nid.id)
@@ -1745,37 +1818,38 @@ impl Governance {
         }
     }
 
-    #[allow(dead_code)] // TODO NNS1-2351 remove allow(dead_code)
+    /// Like `find_neuron`, but also looks at the neurons that have been archived
+    /// in stable memory. Only meant for read-only access: use
+    /// `restore_archived_neuron` before modifying a neuron.
+    fn find_neuron_including_archived(
+        &self,
+        find_by: &NeuronIdOrSubaccount,
+    ) -> Result<Cow<'_, Neuron>, GovernanceError> {
+        match self.find_neuron(find_by) {
+            Ok(neuron) => Ok(Cow::Borrowed(neuron)),
+            Err(err) => find_archived_neuron(find_by).map(Cow::Owned).ok_or(err),
+        }
+    }
+
+    /// Returns the total number of neurons, including the ones archived in
+    /// the stable neuron store.
+    pub fn num_neurons(&self) -> usize {
+        self.proto.neurons.len() + num_archived_neurons()
+    }
+
     /// A neuron is considered inactive if it has no stake, no maturity, and is not currently
     /// involved in an open proposal or in the middle of a neuron operation.
     fn neuron_can_be_archived(&self, neuron: &Neuron) -> bool {
-        fn involved_with_open_proposal(
-            proposals: &BTreeMap<u64, ProposalData>,
-            neuron: &Neuron,
-        ) -> bool {
-            let id = neuron.id.as_ref().unwrap();
-            let subaccount = &neuron.account;
-
-            proposals.values().any(|p| {
-                if p.status() != ProposalStatus::Open {
-                    return false;
-                }
-
-                if p.proposer.as_ref() == Some(id) {
-                    return true;
-                }
-
-                let manage_neuron_proposal_involves_neuron = p.is_manage_neuron()
-                    && p.proposal.as_ref().map_or(false, |pr| {
-                        pr.managed_neuron() == Some(NeuronIdOrSubaccount::NeuronId(*id))
-                            || pr.managed_neuron()
-                                == Some(NeuronIdOrSubaccount::Subaccount(subaccount.clone()))
-                    });
-
-                manage_neuron_proposal_involves_neuron
-            })
-        }
+        self.neuron_can_be_archived_given(neuron, &self.neurons_involved_with_open_proposals())
+    }
 
+    /// Like `neuron_can_be_archived`, with the neurons involved with open
+    /// proposals computed upfront by `neurons_involved_with_open_proposals`.
+    fn neuron_can_be_archived_given(
+        &self,
+        neuron: &Neuron,
+        involved_with_open_proposals: &NeuronsInvolvedWithOpenProposals,
+    ) -> bool {
         let is_locked = neuron
             .id
             .as_ref()
@@ -1786,10 +1860,123 @@ impl Governance {
 
         let has_maturity = neuron.maturity_e8s_equivalent != 0;
 
-        !has_maturity
-            && !has_stake
-            && !is_locked
-            && !involved_with_open_proposal(&self.proto.proposals, neuron)
+        !has_maturity && !has_stake && !is_locked && !involved_with_open_proposals.contains(neuron)
+    }
+
+    /// Returns the neurons that are either the proposer or the managed neuron
+    /// of an open proposal.
+    fn neurons_involved_with_open_proposals(&self) -> NeuronsInvolvedWithOpenProposals {
+        let mut involved = NeuronsInvolvedWithOpenProposals::default();
+        for p in self.proto.proposals.values() {
+            if p.status() != ProposalStatus::Open {
+                continue;
+            }
+
+            if let Some(proposer) = p.proposer.as_ref() {
+                involved.neuron_ids.insert(proposer.id);
+            }
+
+            if !p.is_manage_neuron() {
+                continue;
+            }
+            match p.proposal.as_ref().and_then(|pr| pr.managed_neuron()) {
+                Some(NeuronIdOrSubaccount::NeuronId(id)) => {
+                    involved.neuron_ids.insert(id.id);
+                }
+                Some(NeuronIdOrSubaccount::Subaccount(subaccount)) => {
+                    involved.subaccounts.insert(subaccount);
+                }
+                None => (),
+            }
+        }
+        involved
+    }
+
+    /// Examines up to `max_neurons` heap neurons, in increasing order of their
+    /// IDs and starting where the previous call left off, and moves the
+    /// inactive ones (see `neuron_can_be_archived`) into the stable neuron
+    /// store. Returns how many were moved. Known neurons always stay on the
+    /// heap. Once all heap neurons have been examined, the next call starts
+    /// over from the smallest neuron ID.
+    ///
+    /// Archived neurons are moved from `principal_to_neuron_ids_index` and
+    /// `topic_followee_index` to the indexes of the stable neuron store, so
+    /// that the heap indices only have to be rebuilt for heap neurons on
+    /// upgrade. They are transparently moved back to the heap by
+    /// `restore_archived_neuron` as soon as they need to be modified.
+    pub fn move_inactive_neurons_to_stable_memory(&mut self, max_neurons: usize) -> usize {
+        let cursor = self.inactive_neuron_migration_cursor;
+        let mut batch = self
+            .proto
+            .neurons
+            .keys()
+            .copied()
+            .filter(|neuron_id| *neuron_id >= cursor)
+            .collect::<Vec<_>>();
+        self.inactive_neuron_migration_cursor = if batch.len() > max_neurons {
+            // Keep the `max_neurons` smallest IDs, and resume from the next one.
+            let (_, next_neuron_id, _) = batch.select_nth_unstable(max_neurons);
+            let next_neuron_id = *next_neuron_id;
+            batch.truncate(max_neurons);
+            next_neuron_id
+        } else {
+            0
+        };
+
+        let involved_with_open_proposals = self.neurons_involved_with_open_proposals();
+        let neuron_ids = batch
+            .into_iter()
+            .filter(|neuron_id| {
+                let neuron = &self.proto.neurons[neuron_id];
+                neuron.known_neuron_data.is_none()
+                    && neuron.account.len() == 32
+                    && self.neuron_can_be_archived_given(neuron, &involved_with_open_proposals)
+            })
+            .collect::<Vec<_>>();
+
+        STABLE_NEURON_STORE.with(|store| {
+            let mut store = store.borrow_mut();
+            for neuron_id in &neuron_ids {
+                if let Some(neuron) = self.proto.neurons.remove(neuron_id) {
+                    GovernanceProto::remove_neuron_from_principal_to_neuron_ids_index(
+                        &mut self.principal_to_neuron_ids_index,
+                        &neuron,
+                    );
+                    GovernanceProto::remove_neuron_from_topic_followee_index(
+                        &mut self.topic_followee_index,
+                        &neuron,
+                    );
+                    store.upsert(neuron);
+                }
+            }
+        });
+
+        neuron_ids.len()
+    }
+
+    /// If the neuron identified by `find_by` has been archived in stable
+    /// memory, moves it back to the heap. This is a no-op otherwise.
+    ///
+    /// The neuron moves from the indexes of the stable neuron store back to
+    /// the heap indices.
+    fn restore_archived_neuron(&mut self, find_by: &NeuronIdOrSubaccount) {
+        let neuron_id = match archived_neuron_id(find_by) {
+            Some(neuron_id) => neuron_id,
+            None => return,
+        };
+        if let Some(neuron) = STABLE_NEURON_STORE.with(|store| store.borrow_mut().remove(neuron_id))
+        {
+            GovernanceProto::add_neuron_to_principal_to_neuron_ids_index(
+                &mut self.principal_to_neuron_ids_index,
+                neuron_id,
+                &neuron,
+            );
+            GovernanceProto::add_neuron_to_topic_followee_index(
+                &mut self.topic_followee_index,
+                &neuron,
+            );
+            self.proto.neurons.insert(neuron_id, neuron);
+        }
     }
 
     /// Locks a given neuron for a specific, signaling there is an ongoing
@@ -1943,13 +2130,13 @@ impl Governance {
         // New neurons are not allowed when the heap is too large.
         self.check_heap_can_grow()?;
 
-        if self.proto.neurons.len() + 1 > MAX_NUMBER_OF_NEURONS {
+        if self.num_neurons() + 1 > MAX_NUMBER_OF_NEURONS {
             return Err(GovernanceError::new_with_message(
                 ErrorType::PreconditionFailed,
                 "Cannot add neuron. Max number of neurons reached.",
             ));
         }
-        if self.proto.neurons.contains_key(&neuron_id) {
+        if self.proto.neurons.contains_key(&neuron_id) || is_archived_neuron(neuron_id) {
             return Err(GovernanceError::new_with_message(
                 ErrorType::PreconditionFailed,
                 format!(
@@ -2013,12 +2200,17 @@ impl Governance {
     }
 
     /// Return the Neuron IDs of all Neurons that have `principal` as their
-    /// controller or as one of their hot keys.
+    /// controller or as one of their hot keys, including archived ones.
     pub fn get_neuron_ids_by_principal(&self, principal: &PrincipalId) -> Vec<u64> {
-        self.principal_to_neuron_ids_index
+        let mut neuron_ids: Vec<u64> = self
+            .principal_to_neuron_ids_index
             .get(principal)
             .map(|ids| ids.iter().copied().collect())
-            .unwrap_or_default()
+            .unwrap_or_default();
+        neuron_ids.extend(
+            STABLE_NEURON_STORE.with(|store| store.borrow().neuron_ids_by_principal(principal)),
+        );
+        neuron_ids
     }
 
     /// Return the union of `followees` with the set of Neuron IDs of all
@@ -2035,6 +2227,11 @@ impl Governance {
             {
                 managed.extend(followers)
             }
+            managed.extend(STABLE_NEURON_STORE.with(|store| {
+                store
+                    .borrow()
+                    .followers_of(Topic::NeuronManagement as i32, *followee)
+            }));
         }
 
         managed.iter().copied().collect()
@@ -2060,10 +2257,11 @@ impl Governance {
         ListNeuronsResponse {
             neuron_infos: requested_list()
                 .filter_map(|x| {
-                    self.proto
-                        .neurons
-                        .get(x)
-                        .map(|y| (*x, y.get_neuron_info(now)))
+                    self.find_neuron_including_archived(&NeuronIdOrSubaccount::NeuronId(NeuronId {
+                        id: *x,
+                    }))
+                    .ok()
+                    .map(|y| (*x, y.get_neuron_info(now)))
                 })
                 .collect(),
             full_neurons: requested_list()
@@ -2091,6 +2289,7 @@ impl Governance {
     }
 
     pub fn get_neuron_by_subaccount_mut(&mut self, subaccount: &Subaccount) -> Option<&mut Neuron> {
+        self.restore_archived_neuron(&NeuronIdOrSubaccount::Subaccount(subaccount.to_vec()));
         self.proto.neurons.values_mut().find(|n| {
             if let Ok(s) = &&Subaccount::try_from(&n.account[..]) {
                 return s == subaccount;
@@ -3271,10 +3470,8 @@ impl Governance {
     /// neuron is accessible to any caller.
     pub fn get_neuron_info(&self, id: &NeuronId) -> Result<NeuronInfo, GovernanceError> {
         let neuron = self
-            .proto
-            .neurons
-            .get(&id.id)
-            .ok_or_else(|| GovernanceError::new(ErrorType::NotFound))?;
+            .find_neuron_including_archived(&NeuronIdOrSubaccount::NeuronId(*id))
+            .map_err(|_| GovernanceError::new(ErrorType::NotFound))?;
         let now = self.env.now();
         Ok(neuron.get_neuron_info(now))
     }
@@ -3286,7 +3483,7 @@ impl Governance {
         &self,
         by: &NeuronIdOrSubaccount,
     ) -> Result<NeuronInfo, GovernanceError> {
-        let neuron = self.find_neuron(by)?;
+        let neuron = self.find_neuron_including_archived(by)?;
         let now = self.env.now();
         Ok(neuron.get_neuron_info(now))
     }
@@ -3301,7 +3498,7 @@ impl Governance {
         by: &NeuronIdOrSubaccount,
         caller: &PrincipalId,
     ) -> Result<Neuron, GovernanceError> {
-        let neuron = self.find_neuron(by)?;
+        let neuron = self.find_neuron_including_archived(by)?;
         // Check that the caller is authorized for the requested
         // neuron (controller or hot key).
         if !neuron.is_authorized_to_vote(caller) {
@@ -3322,7 +3519,7 @@ impl Governance {
                 return Err(GovernanceError::new(ErrorType::NotAuthorized));
             }
         }
-        Ok(neuron.clone())
+        Ok(neuron.into_owned())
     }
 
     /// Returns the complete neuron data for a given neuron `id` after
@@ -3363,13 +3560,12 @@ impl Governance {
         match proposal_data {
             None => None,
             Some(pd) => {
-                let empty = HashSet::<u64>::new();
-                let caller_neurons: &HashSet<u64> = self
-                    .principal_to_neuron_ids_index
-                    .get(caller)
-                    .unwrap_or(&empty);
+                let caller_neurons: HashSet<u64> = self
+                    .get_neuron_ids_by_principal(caller)
+                    .into_iter()
+                    .collect();
                 let now = self.env.now();
-                Some(self.proposal_data_to_info(pd, caller_neurons, now, false))
+                Some(self.proposal_data_to_info(pd, &caller_neurons, now, false))
             }
         }
     }
@@ -3385,14 +3581,13 @@ impl Governance {
     /// retrieve dropped payloads by calling `get_proposal_info` for
     /// each proposal of interest.
     pub fn get_pending_proposals(&self, caller: &PrincipalId) -> Vec<ProposalInfo> {
-        let empty = HashSet::<u64>::new();
-        let caller_neurons: &HashSet<u64> = self
-            .principal_to_neuron_ids_index
-            .get(caller)
-            .unwrap_or(&empty);
+        let caller_neurons: HashSet<u64> = self
+            .get_neuron_ids_by_principal(caller)
+            .into_iter()
+            .collect();
         let now = self.env.now();
         self.get_pending_proposals_data()
-            .map(|data| self.proposal_data_to_info(data, caller_neurons, now, true))
+            .map(|data| self.proposal_data_to_info(data, &caller_neurons, now, true))
             .collect()
     }
 
@@ -3548,11 +3743,10 @@ impl Governance {
         caller: &PrincipalId,
         req: &ListProposalInfo,
     ) -> ListProposalInfoResponse {
-        let empty = HashSet::<u64>::new();
-        let caller_neurons: &HashSet<u64> = self
-            .principal_to_neuron_ids_index
-            .get(caller)
-            .unwrap_or(&empty);
+        let caller_neurons: HashSet<u64> = self
+            .get_neuron_ids_by_principal(caller)
+            .into_iter()
+            .collect();
         let exclude_topic: HashSet<i32> = req.exclude_topic.iter().cloned().collect();
         let include_reward_status: HashSet<i32> =
             req.include_reward_status.iter().cloned().collect();
@@ -3581,7 +3775,7 @@ impl Governance {
             // include_all_manage_neuron_proposals is true the proposal is
             // always included.
             req.include_all_manage_neuron_proposals.unwrap_or(false)
-                || self.proposal_is_visible_to_neurons(data, caller_neurons)
+                || self.proposal_is_visible_to_neurons(data, &caller_neurons)
         };
         let limit = if req.limit == 0 || req.limit > MAX_LIST_PROPOSAL_RESULTS {
             MAX_LIST_PROPOSAL_RESULTS
@@ -3602,7 +3796,7 @@ impl Governance {
         //
         let proposal_info = limited_rng
             .map(|(_, y)| y)
-            .map(|pd| self.proposal_data_to_info(pd, caller_neurons, now, true))
+            .map(|pd| self.proposal_data_to_info(pd, &caller_neurons, now, true))
             .collect();
         // Ignore the keys and clone to a vector.
         ListProposalInfoResponse { proposal_info }
@@ -4717,6 +4911,7 @@ impl Governance {
                     Ballot {
                         vote,
                         voting_power: 1,
+                        ..Default::default()
                     },
                 )
             })
@@ -5109,6 +5304,14 @@ impl Governance {
         let topic = proposal.topic();
         let now_seconds = self.env.now();
 
+        // The neuron managed by a ManageNeuron proposal must be on the heap
+        // while the proposal is open.
+        if let Some(Action::ManageNeuron(manage_neuron)) = &proposal.action {
+            if let Ok(Some(managed_id)) = manage_neuron.get_neuron_id_or_subaccount() {
+                self.restore_archived_neuron(&managed_id);
+            }
+        }
+
         // Validate proposal
         self.validate_proposal(proposal).await?;
 
@@ -5256,6 +5459,7 @@ impl Governance {
                 Ballot {
                     vote: Vote::Unspecified as i32,
                     voting_power: power,
+                    ..Default::default()
                 },
             );
         }
@@ -5344,7 +5548,9 @@ impl Governance {
     // cascade voting according to the following relationships
     // specified in 'followee_index' (mapping followees to followers for
     // the topic) and 'neurons' (which contains a mapping of followers
-    // to followees).
+    // to followees). Followers that split their voting power across
+    // their followees with weights get the delegated parts of their
+    // ballots updated instead.
     fn cast_vote_and_cascade_follow(
         proposal_id: &ProposalId,
         ballots: &mut HashMap<u64, Ballot>,
@@ -5360,8 +5566,32 @@ impl Governance {
         // values not allowed).
         let mut induction_votes = BTreeMap::new();
         induction_votes.insert(voting_neuron_id.id, vote_of_neuron);
+        // The neurons whose delegation of voting power has just been
+        // completed. Like a vote, this may allow their followers to
+        // vote or delegate.
+        let mut induction_delegations = BTreeSet::new();
         let topic_cache = topic_followee_index.get(&topic);
         let unspecified_cache = topic_followee_index.get(&Topic::Unspecified);
+        // Insert the followers of the neuron with ID `k` into `all_followers`.
+        let collect_followers = |k: &u64, all_followers: &mut BTreeSet<u64>| {
+            // Insert followers from 'topic'
+            if let Some(more_followers) = topic_cache.and_then(|x| x.get(k)) {
+                all_followers.append(&mut more_followers.clone());
+            }
+            // Default following doesn't apply to governance or SNS decentralization sale proposals.
+            if ![
+                Topic::Governance,
+                Topic::SnsDecentralizationSale,
+                Topic::SnsAndCommunityFund,
+            ]
+            .contains(&topic)
+            {
+                // Insert followers from 'Unspecified' (default followers)
+                if let Some(more_followers) = unspecified_cache.and_then(|x| x.get(k)) {
+                    all_followers.append(&mut more_followers.clone());
+                }
+            }
+        };
         loop {
             // First, we cast the specified votes (in the first round,
             // this will be a single vote) and collect all neurons
@@ -5385,26 +5615,7 @@ impl Governance {
                             // Here k is the followee, i.e., the neuron
                             // that has just cast a vote that may be
                             // followed by other neurons.
-                            //
-                            // Insert followers from 'topic'
-                            if let Some(more_followers) = topic_cache.and_then(|x| x.get(k)) {
-                                all_followers.append(&mut more_followers.clone());
-                            }
-                            // Default following doesn't apply to governance or SNS decentralization sale proposals.
-                            if ![
-                                Topic::Governance,
-                                Topic::SnsDecentralizationSale,
-                                Topic::SnsAndCommunityFund,
-                            ]
-                            .contains(&topic)
-                            {
-                                // Insert followers from 'Unspecified' (default followers)
-                                if let Some(more_followers) =
-                                    unspecified_cache.and_then(|x| x.get(k))
-                                {
-                                    all_followers.append(&mut more_followers.clone());
-                                }
-                            }
+                            collect_followers(k, &mut all_followers);
                         } else {
                             // The voting neuron not found in the
                             // neurons table. This is a bad
@@ -5420,11 +5631,35 @@ impl Governance {
                     // vote.
                 }
             }
-            // Clear the induction_votes, as we are going to compute a
-            // new set now.
+            // The followers of neurons that have completed their
+            // delegation may now be able to vote as well.
+            for k in induction_delegations.iter() {
+                collect_followers(k, &mut all_followers);
+            }
+            // Clear the induction_votes and induction_delegations, as we
+            // are going to compute new sets now.
             induction_votes.clear();
+            induction_delegations.clear();
             for f in all_followers.iter() {
                 if let Some(f_neuron) = neurons.get(f) {
+                    // A neuron that splits its voting power across its
+                    // followees doesn't vote itself: the parts of its
+                    // ballot cast by its followees are updated instead.
+                    if let Some(f_ballot) = ballots.get(f) {
+                        if f_ballot.vote == (Vote::Unspecified as i32)
+                            && !f_ballot.delegation_complete
+                        {
+                            if let Some(new_ballot) =
+                                f_neuron.would_delegate_ballots(topic, f_ballot, ballots)
+                            {
+                                if new_ballot.delegation_complete {
+                                    induction_delegations.insert(*f);
+                                }
+                                ballots.insert(*f, new_ballot);
+                                continue;
+                            }
+                        }
+                    }
                     let f_vote = f_neuron.would_follow_ballots(topic, ballots);
                     if f_vote != Vote::Unspecified {
                         // f_vote is yes or no, i.e., f_neuron's
@@ -5434,9 +5669,9 @@ impl Governance {
                     }
                 }
             }
-            // If induction_votes is empty, the loop will terminate
-            // here.
-            if induction_votes.is_empty() {
+            // If induction_votes and induction_delegations are empty, the
+            // loop will terminate here.
+            if induction_votes.is_empty() && induction_delegations.is_empty() {
                 return;
             }
             // We now continue to the next iteration of the loop.
@@ -5474,6 +5709,11 @@ impl Governance {
             // propagated through the graph in a manner similar to the
             // breadth-first search (BFS) algorithm. A node is
             // explored when it has voted yes or no.
+            //
+            // The same holds for delegations: a ballot is marked as
+            // complete at most once, and a completed ballot is never
+            // updated again. Voting power delegated in a cycle is never
+            // cast, as none of the delegations in the cycle completes.
         }
     }
 
@@ -5532,8 +5772,13 @@ impl Governance {
         let mut neuron_ballot = proposal.ballots.get_mut(&neuron_id.id).ok_or_else(||
             // This neuron is not eligible to vote on this proposal.
             GovernanceError::new_with_message(ErrorType::NotAuthorized, "Neuron not authorized to vote on proposal."))?;
-        if neuron_ballot.vote != (Vote::Unspecified as i32) {
-            // Already voted.
+        if neuron_ballot.vote != (Vote::Unspecified as i32)
+            || neuron_ballot.delegated_yes_voting_power > 0
+            || neuron_ballot.delegated_no_voting_power > 0
+            || neuron_ballot.delegation_complete
+        {
+            // Already voted, possibly through the followees the voting
+            // power is delegated to.
             return Err(GovernanceError::new_with_message(
                 ErrorType::PreconditionFailed,
                 "Neuron already voted on proposal.",
@@ -5611,6 +5856,9 @@ impl Governance {
                 "Too many followees.",
             ));
         }
+        if !f.weights_basis_points.is_empty() {
+            Self::validate_followee_weights(id, f)?;
+        }
         // First, remove the current followees for this neuron and
         // this topic from the follower cache.
         if let Some(neuron_followees) = neuron.followees.get(&f.topic) {
@@ -5643,6 +5891,7 @@ impl Governance {
                     f.topic,
                     Followees {
                         followees: f.followees.clone(),
+                        weights_basis_points: f.weights_basis_points.clone(),
                     },
                 );
                 let cache = self
@@ -5672,6 +5921,53 @@ impl Governance {
         }
     }
 
+    /// Checks that the voting power of neuron `id` can be split across
+    /// the followees in `f` with the given weights.
+    fn validate_followee_weights(
+        id: &NeuronId,
+        f: &manage_neuron::Follow,
+    ) -> Result<(), GovernanceError> {
+        let invalid = |message: String| {
+            Err(GovernanceError::new_with_message(
+                ErrorType::InvalidCommand,
+                message,
+            ))
+        };
+        // Manage neuron proposals are decided by the majority of the
+        // followees, not by voting power.
+        if f.topic() == Topic::NeuronManagement {
+            return invalid(
+                "Voting power cannot be split across followees on the NeuronManagement topic."
+                    .to_string(),
+            );
+        }
+        if f.weights_basis_points.len() != f.followees.len() {
+            return invalid(format!(
+                "Expected one weight per followee, but got {} weights for {} followees.",
+                f.weights_basis_points.len(),
+                f.followees.len()
+            ));
+        }
+        if f.weights_basis_points.contains(&0) {
+            return invalid("The weight of each followee must be positive.".to_string());
+        }
+        let total_basis_points: u64 = f.weights_basis_points.iter().map(|w| *w as u64).sum();
+        if total_basis_points != BASIS_POINTS_PER_UNITY {
+            return invalid(format!(
+                "The weights must add up to {} basis points, but add up to {}.",
+                BASIS_POINTS_PER_UNITY, total_basis_points
+            ));
+        }
+        let followee_ids: HashSet<u64> = f.followees.iter().map(|followee| followee.id).collect();
+        if followee_ids.len() != f.followees.len() {
+            return invalid("The followees must be distinct.".to_string());
+        }
+        if followee_ids.contains(&id.id) {
+            return invalid("A neuron cannot delegate voting power to itself.".to_string());
+        }
+        Ok(())
+    }
+
     fn configure_neuron(
         &mut self,
         id: &NeuronId,
@@ -5742,6 +6038,7 @@ impl Governance {
         let controller = memo_and_controller.controller.unwrap_or(*caller);
         let memo = memo_and_controller.memo;
         let subaccount = ledger::compute_neuron_staking_subaccount(controller, memo);
+        self.restore_archived_neuron(&NeuronIdOrSubaccount::Subaccount(subaccount.to_vec()));
         match self.get_neuron_by_subaccount(&subaccount) {
             Some(neuron) => {
                 let nid = neuron.id.expect("Neuron must have an id");
@@ -6033,6 +6330,17 @@ impl Governance {
         caller: &PrincipalId,
         mgmt: &ManageNeuron,
     ) -> Result<ManageNeuronResponse, GovernanceError> {
+        // Neurons archived in stable memory are moved back to the heap before
+        // any command operates on them.
+        if let Ok(Some(id)) = mgmt.get_neuron_id_or_subaccount() {
+            self.restore_archived_neuron(&id);
+        }
+        if let Some(manage_neuron::Command::Merge(merge)) = &mgmt.command {
+            if let Some(source_neuron_id) = merge.source_neuron_id {
+                self.restore_archived_neuron(&NeuronIdOrSubaccount::NeuronId(source_neuron_id));
+            }
+        }
+
         // We run claim or refresh before we check whether a neuron exists because it
         // may not in the case of the neuron being claimed
         if let Some(manage_neuron::Command::ClaimOrRefresh(claim_or_refresh)) = &mgmt.command {
@@ -6268,6 +6576,16 @@ impl Governance {
 
         self.maybe_move_staked_maturity();
         self.maybe_gc();
+
+        if self
+            .proto
+            .inactive_neuron_migration_enabled
+            .unwrap_or_default()
+        {
+            self.move_inactive_neurons_to_stable_memory(
+                MAX_NEURONS_EXAMINED_FOR_STABLE_MEMORY_PER_ROUND,
+            );
+        }
     }
 
     fn should_update_maturity_modulation(&self) -> bool {
@@ -6574,6 +6892,18 @@ impl Governance {
                             *voters_to_used_voting_right
                                 .entry(NeuronId { id: *voter })
                                 .or_insert(0f64) += voting_rights;
+                        } else {
+                            // Delegated voting power is rewarded as far
+                            // as it has been cast.
+                            let delegated_voting_power = ballot
+                                .delegated_yes_voting_power
+                                .saturating_add(ballot.delegated_no_voting_power);
+                            if delegated_voting_power > 0 {
+                                *voters_to_used_voting_right
+                                    .entry(NeuronId { id: *voter })
+                                    .or_insert(0f64) +=
+                                    (delegated_voting_power as f64) * reward_weight;
+                            }
                         }
                     }
                 }
@@ -7781,6 +8111,8 @@ fn is_information_about_swap_from_different_sources_consistent(
                 nns_proposal_id: _,
                 neurons_fund_participants: _,
                 should_auto_finalize: _,
+                additional_payment_tokens: _,
+                exchange_rate_canister_id: _,
             } = swap_init;
 
             (
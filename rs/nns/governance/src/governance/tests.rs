@@ -1791,11 +1791,16 @@ mod neuron_archiving_tests {
             governance::NeuronInFlightCommand, manage_neuron::NeuronIdOrSubaccount, proposal,
             Governance as GovernanceProto, ManageNeuron, Motion, Neuron, Proposal, ProposalData,
         },
+        storage::STABLE_NEURON_STORE,
     };
+    use ic_base_types::PrincipalId;
     use ic_nns_common::pb::v1::NeuronId;
-    use maplit::{btreemap, hashmap};
+    use maplit::{btreemap, btreeset, hashmap};
     use proptest::proptest;
-    use std::sync::{Arc, Mutex};
+    use std::{
+        collections::BTreeSet,
+        sync::{Arc, Mutex},
+    };
 
     fn empty_neuron_id_1() -> Neuron {
         Neuron {
@@ -1923,6 +1928,153 @@ mod neuron_archiving_tests {
         assert!(!governance.neuron_can_be_archived(&has_neuron_lock));
     }
 
+    #[test]
+    fn test_move_inactive_neurons_to_stable_memory_and_restore() {
+        let inactive_neuron = Neuron {
+            account: vec![7; 32],
+            controller: Some(PrincipalId::new_user_test_id(1)),
+            ..empty_neuron_id_1()
+        };
+        let active_neuron = Neuron {
+            id: Some(NeuronId { id: 2 }),
+            account: vec![8; 32],
+            cached_neuron_stake_e8s: 100_000_000,
+            ..inactive_neuron.clone()
+        };
+
+        let mut governance = Governance::new(
+            GovernanceProto {
+                neurons: hashmap! {
+                    1 => inactive_neuron.clone(),
+                    2 => active_neuron,
+                },
+                ..GovernanceProto::default()
+            },
+            Box::new(MockEnvironment {
+                expected_call_canister_method_calls: Arc::new(Mutex::new(Default::default())),
+                now: Arc::new(Mutex::new(0)),
+            }),
+            Box::new(StubIcpLedger {}),
+            Box::new(StubCMC {}),
+        );
+
+        assert_eq!(governance.move_inactive_neurons_to_stable_memory(10), 1);
+        assert!(!governance.proto.neurons.contains_key(&1));
+        assert!(governance.proto.neurons.contains_key(&2));
+        assert!(STABLE_NEURON_STORE.with(|store| store.borrow().contains(1)));
+        assert_eq!(governance.num_neurons(), 2);
+
+        // Archived neurons can still be read, and stay indexed by principal.
+        assert_eq!(
+            governance
+                .get_neuron_info_by_id_or_subaccount(&NeuronIdOrSubaccount::Subaccount(vec![7; 32]))
+                .unwrap()
+                .stake_e8s,
+            0
+        );
+        assert_eq!(
+            governance.get_full_neuron(&NeuronId { id: 1 }, &PrincipalId::new_user_test_id(1)),
+            Ok(inactive_neuron.clone())
+        );
+        assert!(governance
+            .get_neuron_ids_by_principal(&PrincipalId::new_user_test_id(1))
+            .contains(&1));
+        // ... through the principal index of the stable neuron store, which
+        // survives an upgrade without being rebuilt.
+        assert!(
+            !governance.principal_to_neuron_ids_index[&PrincipalId::new_user_test_id(1)]
+                .contains(&1)
+        );
+        let mut governance = Governance::new(
+            governance.proto.clone(),
+            Box::new(MockEnvironment {
+                expected_call_canister_method_calls: Arc::new(Mutex::new(Default::default())),
+                now: Arc::new(Mutex::new(0)),
+            }),
+            Box::new(StubIcpLedger {}),
+            Box::new(StubCMC {}),
+        );
+        assert!(governance
+            .get_neuron_ids_by_principal(&PrincipalId::new_user_test_id(1))
+            .contains(&1));
+
+        // Mutable access moves the neuron back to the heap, and to the heap
+        // indices.
+        governance.get_neuron_mut(&NeuronId { id: 1 }).unwrap();
+        assert_eq!(governance.proto.neurons.get(&1), Some(&inactive_neuron));
+        assert!(!STABLE_NEURON_STORE.with(|store| store.borrow().contains(1)));
+        assert!(
+            governance.principal_to_neuron_ids_index[&PrincipalId::new_user_test_id(1)]
+                .contains(&1)
+        );
+        let mut neuron_ids =
+            governance.get_neuron_ids_by_principal(&PrincipalId::new_user_test_id(1));
+        neuron_ids.sort_unstable();
+        assert_eq!(neuron_ids, vec![1, 2]);
+    }
+
+    #[test]
+    fn test_move_inactive_neurons_to_stable_memory_resumes_from_cursor() {
+        let inactive_neuron = |id: u64| Neuron {
+            id: Some(NeuronId { id }),
+            account: vec![id as u8; 32],
+            controller: Some(PrincipalId::new_user_test_id(1)),
+            ..empty_neuron_id_1()
+        };
+
+        let mut governance = Governance::new(
+            GovernanceProto {
+                neurons: (1..=5).map(|id| (id, inactive_neuron(id))).collect(),
+                ..GovernanceProto::default()
+            },
+            Box::new(MockEnvironment {
+                expected_call_canister_method_calls: Arc::new(Mutex::new(Default::default())),
+                now: Arc::new(Mutex::new(0)),
+            }),
+            Box::new(StubIcpLedger {}),
+            Box::new(StubCMC {}),
+        );
+
+        // Each call examines the next neurons, by increasing ID.
+        assert_eq!(governance.move_inactive_neurons_to_stable_memory(2), 2);
+        assert_eq!(
+            governance
+                .proto
+                .neurons
+                .keys()
+                .copied()
+                .collect::<BTreeSet<_>>(),
+            btreeset! {3, 4, 5}
+        );
+        assert_eq!(governance.move_inactive_neurons_to_stable_memory(2), 2);
+        assert_eq!(
+            governance
+                .proto
+                .neurons
+                .keys()
+                .copied()
+                .collect::<BTreeSet<_>>(),
+            btreeset! {5}
+        );
+
+        // A neuron becoming active behind the cursor is only examined again
+        // once the cursor wraps around.
+        governance.restore_archived_neuron(&NeuronIdOrSubaccount::NeuronId(NeuronId { id: 1 }));
+        assert_eq!(governance.move_inactive_neurons_to_stable_memory(2), 1);
+        assert_eq!(
+            governance
+                .proto
+                .neurons
+                .keys()
+                .copied()
+                .collect::<BTreeSet<_>>(),
+            btreeset! {1}
+        );
+        assert_eq!(governance.move_inactive_neurons_to_stable_memory(2), 1);
+        assert!(governance.proto.neurons.is_empty());
+        assert_eq!(governance.num_neurons(), 5);
+    }
+
     proptest! {
     #[test]
     fn test_neuron_can_be_archived_stake_and_maturity(stake in 0u64..10_000,
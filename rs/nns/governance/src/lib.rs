@@ -167,9 +167,14 @@ pub fn encode_metrics(
     )?;
     w.encode_gauge(
         "governance_neurons_total",
-        governance.proto.neurons.len() as f64,
+        governance.num_neurons() as f64,
         "Total number of neurons.",
     )?;
+    w.encode_gauge(
+        "governance_stable_neurons_total",
+        crate::storage::STABLE_NEURON_STORE.with(|store| store.borrow().len()) as f64,
+        "Number of inactive neurons archived in the stable neuron store.",
+    )?;
     w.encode_gauge(
         "governance_latest_gc_timestamp_seconds",
         governance.latest_gc_timestamp_seconds as f64,
@@ -8,13 +8,14 @@ use crate::{
         audit_event::{Payload, ResetAging},
         governance_error::ErrorType,
         manage_neuron,
-        neuron::DissolveState,
+        neuron::{DissolveState, Followees},
         AuditEvent, Ballot, BallotInfo, GovernanceError, Neuron, NeuronInfo, NeuronState, Topic,
-        Vote,
+        Vote, VotingPowerDelegation,
     },
 };
 use dfn_core::println;
 use ic_base_types::PrincipalId;
+use ic_nervous_system_common::BASIS_POINTS_PER_UNITY;
 use ic_nns_common::pb::v1::{NeuronId, ProposalId};
 use std::collections::HashMap;
 
@@ -125,13 +126,10 @@ impl Neuron {
         topic: Topic,
         ballots: &HashMap<u64, Ballot>,
     ) -> Vote {
-        // Compute the list of followees for this topic. If no
-        // following is specified for the topic, use the followees
-        // from the 'Unspecified' topic.
         if let Some(followees) = self
-            .followees
-            .get(&(topic as i32))
-            .or_else(|| self.followees.get(&(Topic::Unspecified as i32)))
+            .followees_for_topic(topic)
+            // Weighted followees are handled by `would_delegate_ballots`.
+            .filter(|x| x.weights_basis_points.is_empty())
             // extract plain vector from 'Followees' proto
             .map(|x| &x.followees)
         {
@@ -164,6 +162,102 @@ impl Neuron {
         Vote::Unspecified
     }
 
+    /// Given the specified `ballots`: determine which parts of the
+    /// voting power in this neuron's `ballot` have been cast yes and no
+    /// by the followees this neuron delegates its voting power to on
+    /// `topic` with weights. Returns `None` if the neuron doesn't split
+    /// its voting power on this topic.
+    ///
+    /// Each followee is delegated its share of the voting power. A
+    /// followee that voted casts its whole share. A followee whose own
+    /// delegation is complete casts its share in the same proportions as
+    /// its ballot. A followee that is not eligible to vote never casts its
+    /// share. The delegation is complete once no followee can cast its
+    /// share anymore. All computations round down, so that the result
+    /// is deterministic and never exceeds the neuron's voting power.
+    pub(crate) fn would_delegate_ballots(
+        &self,
+        topic: Topic,
+        ballot: &Ballot,
+        ballots: &HashMap<u64, Ballot>,
+    ) -> Option<Ballot> {
+        let followees = self
+            .followees_for_topic(topic)
+            .filter(|x| !x.weights_basis_points.is_empty())?;
+
+        let mut yes: u128 = 0;
+        let mut no: u128 = 0;
+        let mut delegation_complete = true;
+        for (followee, weight) in followees
+            .followees
+            .iter()
+            .zip(followees.weights_basis_points.iter())
+        {
+            let share = (ballot.voting_power as u128) * (*weight as u128)
+                / (BASIS_POINTS_PER_UNITY as u128);
+            let f_ballot = match ballots.get(&followee.id) {
+                Some(f_ballot) => f_ballot,
+                // Not eligible to vote: the share is never cast.
+                None => continue,
+            };
+            if f_ballot.vote == (Vote::Yes as i32) {
+                yes += share;
+            } else if f_ballot.vote == (Vote::No as i32) {
+                no += share;
+            } else if f_ballot.delegation_complete {
+                if f_ballot.voting_power > 0 {
+                    let f_voting_power = f_ballot.voting_power as u128;
+                    yes += share * (f_ballot.delegated_yes_voting_power as u128) / f_voting_power;
+                    no += share * (f_ballot.delegated_no_voting_power as u128) / f_voting_power;
+                }
+            } else {
+                delegation_complete = false;
+            }
+        }
+
+        // The shares add up to at most the voting power of the ballot.
+        Some(Ballot {
+            vote: Vote::Unspecified as i32,
+            voting_power: ballot.voting_power,
+            delegated_yes_voting_power: yes as u64,
+            delegated_no_voting_power: no as u64,
+            delegation_complete,
+        })
+    }
+
+    /// Returns the followees of this neuron on `topic`, or on the
+    /// 'Unspecified' topic if no following is specified for `topic`.
+    fn followees_for_topic(&self, topic: Topic) -> Option<&Followees> {
+        self.followees
+            .get(&(topic as i32))
+            .or_else(|| self.followees.get(&(Topic::Unspecified as i32)))
+    }
+
+    /// Returns how `voting_power` is split across the followees this
+    /// neuron delegates to with weights, ordered by topic.
+    pub(crate) fn voting_power_delegations(&self, voting_power: u64) -> Vec<VotingPowerDelegation> {
+        let mut topics: Vec<&i32> = self.followees.keys().collect();
+        topics.sort();
+        topics
+            .into_iter()
+            .flat_map(|topic| {
+                let followees = &self.followees[topic];
+                followees
+                    .followees
+                    .iter()
+                    .zip(followees.weights_basis_points.iter())
+                    .map(move |(followee, weight)| VotingPowerDelegation {
+                        topic: *topic,
+                        followee: Some(followee.clone()),
+                        weight_basis_points: *weight,
+                        delegated_voting_power: ((voting_power as u128) * (*weight as u128)
+                            / (BASIS_POINTS_PER_UNITY as u128))
+                            as u64,
+                    })
+            })
+            .collect()
+    }
+
     /// Returns the list of followees on the manage neuron topic for
     /// this neuron.
     pub(crate) fn neuron_managers(&self) -> Option<&Vec<NeuronId>> {
@@ -579,17 +673,19 @@ impl Neuron {
 
     /// Get the 'public' information associated with this neuron.
     pub fn get_neuron_info(&self, now_seconds: u64) -> NeuronInfo {
+        let voting_power = self.voting_power(now_seconds);
         NeuronInfo {
             retrieved_at_timestamp_seconds: now_seconds,
             state: self.state(now_seconds) as i32,
             age_seconds: self.age_seconds(now_seconds),
             dissolve_delay_seconds: self.dissolve_delay_seconds(now_seconds),
             recent_ballots: self.recent_ballots.clone(),
-            voting_power: self.voting_power(now_seconds),
+            voting_power,
             created_timestamp_seconds: self.created_timestamp_seconds,
             stake_e8s: self.minted_stake_e8s(),
             joined_community_fund_timestamp_seconds: self.joined_community_fund_timestamp_seconds,
             known_neuron_data: self.known_neuron_data.as_ref().cloned(),
+            voting_power_delegations: self.voting_power_delegations(voting_power),
         }
     }
 
@@ -1,4 +1,5 @@
 use crate::pb::v1::AuditEvent;
+use crate::storage::neurons::{StableNeuronStore, StableNeuronStoreMemories};
 
 use ic_stable_structures::{
     memory_manager::{MemoryId, MemoryManager, VirtualMemory},
@@ -10,6 +11,14 @@ use std::cell::RefCell;
 const UPGRADES_MEMORY_ID: MemoryId = MemoryId::new(0);
 const AUDIT_EVENTS_INDEX_MEMORY_ID: MemoryId = MemoryId::new(1);
 const AUDIT_EVENTS_DATA_MEMORY_ID: MemoryId = MemoryId::new(2);
+const STABLE_NEURONS_MEMORY_ID: MemoryId = MemoryId::new(3);
+const STABLE_NEURON_FOLLOWEES_MEMORY_ID: MemoryId = MemoryId::new(4);
+const STABLE_NEURON_RECENT_BALLOTS_MEMORY_ID: MemoryId = MemoryId::new(5);
+const STABLE_NEURON_SUBACCOUNT_INDEX_MEMORY_ID: MemoryId = MemoryId::new(6);
+const STABLE_NEURON_PRINCIPAL_INDEX_MEMORY_ID: MemoryId = MemoryId::new(7);
+const STABLE_NEURON_FOLLOWEE_INDEX_MEMORY_ID: MemoryId = MemoryId::new(8);
+
+pub mod neurons;
 
 type VM = VirtualMemory<DefaultMemoryImpl>;
 
@@ -32,4 +41,18 @@ thread_local! {
                 .expect("Failed to initialize stable log"),
             )
         });
+
+    // Neurons that have been moved out of the heap, and their indexes.
+    pub static STABLE_NEURON_STORE: RefCell<StableNeuronStore<VM>> =
+        MEMORY_MANAGER.with(|memory_manager| {
+            let memory_manager = memory_manager.borrow();
+            RefCell::new(StableNeuronStore::init(StableNeuronStoreMemories {
+                main: memory_manager.get(STABLE_NEURONS_MEMORY_ID),
+                followees: memory_manager.get(STABLE_NEURON_FOLLOWEES_MEMORY_ID),
+                recent_ballots: memory_manager.get(STABLE_NEURON_RECENT_BALLOTS_MEMORY_ID),
+                subaccount_index: memory_manager.get(STABLE_NEURON_SUBACCOUNT_INDEX_MEMORY_ID),
+                principal_index: memory_manager.get(STABLE_NEURON_PRINCIPAL_INDEX_MEMORY_ID),
+                followee_index: memory_manager.get(STABLE_NEURON_FOLLOWEE_INDEX_MEMORY_ID),
+            }))
+        });
 }
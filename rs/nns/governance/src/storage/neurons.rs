@@ -0,0 +1,645 @@
+//! Storage of neurons in stable memory.
+//!
+//! A neuron is split in three parts so that every part has a bounded size:
+//! - the neuron itself, without its followees and recent ballots,
+//! - its followees, one entry per (topic, followee) with the followee's weight,
+//! - its recent ballots, one entry per ballot.
+//!
+//! A subaccount index allows neurons to be found by subaccount without
+//! loading all of them. The principal and followee indexes of the stored
+//! neurons are kept in stable memory as well, so that they don't have to be
+//! rebuilt on every upgrade.
+
+use crate::pb::v1::{neuron::Followees, BallotInfo, Neuron};
+use ic_base_types::PrincipalId;
+use ic_nns_common::pb::v1::NeuronId;
+use ic_stable_structures::{BoundedStorable, Memory, StableBTreeMap, Storable};
+use icp_ledger::Subaccount;
+use prost::Message;
+use std::borrow::Cow;
+use std::collections::HashMap;
+use std::convert::TryFrom;
+
+/// A neuron without its followees and recent ballots.
+#[derive(Clone, Debug, PartialEq)]
+struct NeuronMain(Neuron);
+
+impl Storable for NeuronMain {
+    fn to_bytes(&self) -> Cow<[u8]> {
+        let bytes = self.0.encode_to_vec();
+        assert!(
+            bytes.len() <= Self::MAX_SIZE as usize,
+            "Neuron {:?} is too large to be stored in stable memory: {} bytes",
+            self.0.id,
+            bytes.len()
+        );
+        bytes.into()
+    }
+
+    fn from_bytes(bytes: Cow<[u8]>) -> Self {
+        Self(Neuron::decode(&bytes[..]).expect("Cannot decode neuron"))
+    }
+}
+
+impl BoundedStorable for NeuronMain {
+    // Without followees, recent ballots and known neuron data (known neurons
+    // are never stored in stable memory) a neuron is dominated by its 10 hot
+    // keys of at most 31 bytes each.
+    const MAX_SIZE: u32 = 1024;
+    const IS_FIXED_SIZE: bool = false;
+}
+
+/// Key of a followee entry: (follower, topic, index in the followee list).
+/// Keys are big-endian encoded so that all the followees of a neuron are
+/// stored contiguously, ordered by topic and then by position. Topics are
+/// never negative.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord)]
+struct FolloweeKey {
+    follower_id: u64,
+    topic: i32,
+    index: u32,
+}
+
+impl Storable for FolloweeKey {
+    fn to_bytes(&self) -> Cow<[u8]> {
+        let mut bytes = Vec::with_capacity(Self::MAX_SIZE as usize);
+        bytes.extend_from_slice(&self.follower_id.to_be_bytes());
+        bytes.extend_from_slice(&(self.topic as u32).to_be_bytes());
+        bytes.extend_from_slice(&self.index.to_be_bytes());
+        bytes.into()
+    }
+
+    fn from_bytes(bytes: Cow<[u8]>) -> Self {
+        Self {
+            follower_id: u64::from_be_bytes(bytes[0..8].try_into().unwrap()),
+            topic: u32::from_be_bytes(bytes[8..12].try_into().unwrap()) as i32,
+            index: u32::from_be_bytes(bytes[12..16].try_into().unwrap()),
+        }
+    }
+}
+
+impl BoundedStorable for FolloweeKey {
+    const MAX_SIZE: u32 = 16;
+    const IS_FIXED_SIZE: bool = true;
+}
+
+/// Value of a followee entry: the id of the followee and, if the follower
+/// splits its voting power across its followees on the topic, the weight of
+/// the followee in basis points (zero otherwise).
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+struct FolloweeEntry {
+    followee_id: u64,
+    weight_basis_points: u32,
+}
+
+impl Storable for FolloweeEntry {
+    fn to_bytes(&self) -> Cow<[u8]> {
+        let mut bytes = Vec::with_capacity(Self::MAX_SIZE as usize);
+        bytes.extend_from_slice(&self.followee_id.to_be_bytes());
+        bytes.extend_from_slice(&self.weight_basis_points.to_be_bytes());
+        bytes.into()
+    }
+
+    fn from_bytes(bytes: Cow<[u8]>) -> Self {
+        Self {
+            followee_id: u64::from_be_bytes(bytes[0..8].try_into().unwrap()),
+            weight_basis_points: u32::from_be_bytes(bytes[8..12].try_into().unwrap()),
+        }
+    }
+}
+
+impl BoundedStorable for FolloweeEntry {
+    const MAX_SIZE: u32 = 12;
+    const IS_FIXED_SIZE: bool = true;
+}
+
+/// Key of a recent ballot entry: (neuron, index in the recent ballots).
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord)]
+struct BallotKey {
+    neuron_id: u64,
+    index: u32,
+}
+
+impl Storable for BallotKey {
+    fn to_bytes(&self) -> Cow<[u8]> {
+        let mut bytes = Vec::with_capacity(Self::MAX_SIZE as usize);
+        bytes.extend_from_slice(&self.neuron_id.to_be_bytes());
+        bytes.extend_from_slice(&self.index.to_be_bytes());
+        bytes.into()
+    }
+
+    fn from_bytes(bytes: Cow<[u8]>) -> Self {
+        Self {
+            neuron_id: u64::from_be_bytes(bytes[0..8].try_into().unwrap()),
+            index: u32::from_be_bytes(bytes[8..12].try_into().unwrap()),
+        }
+    }
+}
+
+impl BoundedStorable for BallotKey {
+    const MAX_SIZE: u32 = 12;
+    const IS_FIXED_SIZE: bool = true;
+}
+
+#[derive(Clone, Debug, PartialEq)]
+struct StorableBallotInfo(BallotInfo);
+
+impl Storable for StorableBallotInfo {
+    fn to_bytes(&self) -> Cow<[u8]> {
+        self.0.encode_to_vec().into()
+    }
+
+    fn from_bytes(bytes: Cow<[u8]>) -> Self {
+        Self(BallotInfo::decode(&bytes[..]).expect("Cannot decode ballot info"))
+    }
+}
+
+impl BoundedStorable for StorableBallotInfo {
+    // 0a + 0b + (08 + u64 varint) for the proposal id and 10 + i32 varint
+    // for the vote.
+    const MAX_SIZE: u32 = 32;
+    const IS_FIXED_SIZE: bool = false;
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord)]
+struct SubaccountKey([u8; 32]);
+
+impl Storable for SubaccountKey {
+    fn to_bytes(&self) -> Cow<[u8]> {
+        Cow::Borrowed(&self.0)
+    }
+
+    fn from_bytes(bytes: Cow<[u8]>) -> Self {
+        Self(bytes[..].try_into().expect("Subaccounts are 32 bytes long"))
+    }
+}
+
+impl BoundedStorable for SubaccountKey {
+    const MAX_SIZE: u32 = 32;
+    const IS_FIXED_SIZE: bool = true;
+}
+
+/// Key of the principal index: (principal, neuron). The principal is stored
+/// length-prefixed and zero-padded so that all the neurons of a principal are
+/// stored contiguously.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord)]
+struct PrincipalNeuronKey {
+    principal: PrincipalId,
+    neuron_id: u64,
+}
+
+impl Storable for PrincipalNeuronKey {
+    fn to_bytes(&self) -> Cow<[u8]> {
+        let principal = self.principal.as_slice();
+        let mut bytes = vec![0; Self::MAX_SIZE as usize];
+        bytes[0] = principal.len() as u8;
+        bytes[1..1 + principal.len()].copy_from_slice(principal);
+        bytes[1 + PrincipalId::MAX_LENGTH_IN_BYTES..]
+            .copy_from_slice(&self.neuron_id.to_be_bytes());
+        bytes.into()
+    }
+
+    fn from_bytes(bytes: Cow<[u8]>) -> Self {
+        let len = bytes[0] as usize;
+        Self {
+            principal: PrincipalId::try_from(&bytes[1..1 + len]).expect("Cannot decode principal"),
+            neuron_id: u64::from_be_bytes(
+                bytes[1 + PrincipalId::MAX_LENGTH_IN_BYTES..]
+                    .try_into()
+                    .unwrap(),
+            ),
+        }
+    }
+}
+
+impl BoundedStorable for PrincipalNeuronKey {
+    const MAX_SIZE: u32 = 1 + PrincipalId::MAX_LENGTH_IN_BYTES as u32 + 8;
+    const IS_FIXED_SIZE: bool = true;
+}
+
+/// Key of the followee index: (topic, followee, follower). This is the
+/// reverse of the followees map, so that the followers of a neuron on a
+/// topic are stored contiguously.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord)]
+struct TopicFolloweeKey {
+    topic: i32,
+    followee_id: u64,
+    follower_id: u64,
+}
+
+impl Storable for TopicFolloweeKey {
+    fn to_bytes(&self) -> Cow<[u8]> {
+        let mut bytes = Vec::with_capacity(Self::MAX_SIZE as usize);
+        bytes.extend_from_slice(&(self.topic as u32).to_be_bytes());
+        bytes.extend_from_slice(&self.followee_id.to_be_bytes());
+        bytes.extend_from_slice(&self.follower_id.to_be_bytes());
+        bytes.into()
+    }
+
+    fn from_bytes(bytes: Cow<[u8]>) -> Self {
+        Self {
+            topic: u32::from_be_bytes(bytes[0..4].try_into().unwrap()) as i32,
+            followee_id: u64::from_be_bytes(bytes[4..12].try_into().unwrap()),
+            follower_id: u64::from_be_bytes(bytes[12..20].try_into().unwrap()),
+        }
+    }
+}
+
+impl BoundedStorable for TopicFolloweeKey {
+    const MAX_SIZE: u32 = 20;
+    const IS_FIXED_SIZE: bool = true;
+}
+
+/// Memories of a `StableNeuronStore`, one per map.
+pub struct StableNeuronStoreMemories<M: Memory> {
+    pub main: M,
+    pub followees: M,
+    pub recent_ballots: M,
+    pub subaccount_index: M,
+    pub principal_index: M,
+    pub followee_index: M,
+}
+
+/// Neurons stored in stable memory, together with their followee and recent
+/// ballot indexes.
+pub struct StableNeuronStore<M: Memory> {
+    main: StableBTreeMap<u64, NeuronMain, M>,
+    followees: StableBTreeMap<FolloweeKey, FolloweeEntry, M>,
+    recent_ballots: StableBTreeMap<BallotKey, StorableBallotInfo, M>,
+    subaccount_index: StableBTreeMap<SubaccountKey, u64, M>,
+    principal_index: StableBTreeMap<PrincipalNeuronKey, (), M>,
+    followee_index: StableBTreeMap<TopicFolloweeKey, (), M>,
+}
+
+impl<M: Memory> StableNeuronStore<M> {
+    pub fn init(memories: StableNeuronStoreMemories<M>) -> Self {
+        let StableNeuronStoreMemories {
+            main,
+            followees,
+            recent_ballots,
+            subaccount_index,
+            principal_index,
+            followee_index,
+        } = memories;
+        Self {
+            main: StableBTreeMap::init(main),
+            followees: StableBTreeMap::init(followees),
+            recent_ballots: StableBTreeMap::init(recent_ballots),
+            subaccount_index: StableBTreeMap::init(subaccount_index),
+            principal_index: StableBTreeMap::init(principal_index),
+            followee_index: StableBTreeMap::init(followee_index),
+        }
+    }
+
+    pub fn len(&self) -> u64 {
+        self.main.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.main.is_empty()
+    }
+
+    pub fn contains(&self, neuron_id: u64) -> bool {
+        self.main.contains_key(&neuron_id)
+    }
+
+    /// Inserts `neuron`, replacing any neuron with the same id.
+    ///
+    /// Panics if the neuron has no id or its subaccount is not 32 bytes long.
+    pub fn upsert(&mut self, neuron: Neuron) {
+        let neuron_id = neuron.id.as_ref().expect("Neuron must have an id").id;
+        self.remove(neuron_id);
+
+        let Neuron {
+            followees,
+            recent_ballots,
+            ..
+        } = &neuron;
+        for (
+            topic,
+            Followees {
+                followees,
+                weights_basis_points,
+            },
+        ) in followees
+        {
+            for (index, followee) in followees.iter().enumerate() {
+                self.followee_index.insert(
+                    TopicFolloweeKey {
+                        topic: *topic,
+                        followee_id: followee.id,
+                        follower_id: neuron_id,
+                    },
+                    (),
+                );
+                self.followees.insert(
+                    FolloweeKey {
+                        follower_id: neuron_id,
+                        topic: *topic,
+                        index: index as u32,
+                    },
+                    FolloweeEntry {
+                        followee_id: followee.id,
+                        weight_basis_points: weights_basis_points.get(index).copied().unwrap_or(0),
+                    },
+                );
+            }
+        }
+        for (index, ballot) in recent_ballots.iter().enumerate() {
+            self.recent_ballots.insert(
+                BallotKey {
+                    neuron_id,
+                    index: index as u32,
+                },
+                StorableBallotInfo(ballot.clone()),
+            );
+        }
+        let subaccount = Subaccount::try_from(&neuron.account[..])
+            .expect("Neuron subaccount must be 32 bytes long");
+        self.subaccount_index
+            .insert(SubaccountKey(subaccount.0), neuron_id);
+        for principal in neuron.hot_keys.iter().chain(neuron.controller.iter()) {
+            self.principal_index.insert(
+                PrincipalNeuronKey {
+                    principal: *principal,
+                    neuron_id,
+                },
+                (),
+            );
+        }
+
+        self.main.insert(
+            neuron_id,
+            NeuronMain(Neuron {
+                followees: HashMap::new(),
+                recent_ballots: vec![],
+                ..neuron
+            }),
+        );
+    }
+
+    /// Returns the neuron with the given id, with its followees and recent
+    /// ballots.
+    pub fn get(&self, neuron_id: u64) -> Option<Neuron> {
+        let NeuronMain(mut neuron) = self.main.get(&neuron_id)?;
+        neuron.followees = self.followees_of(neuron_id);
+        neuron.recent_ballots = self
+            .recent_ballots
+            .range(
+                BallotKey {
+                    neuron_id,
+                    index: 0,
+                }..=BallotKey {
+                    neuron_id,
+                    index: u32::MAX,
+                },
+            )
+            .map(|(_, StorableBallotInfo(ballot))| ballot)
+            .collect();
+        Some(neuron)
+    }
+
+    pub fn get_neuron_id_by_subaccount(&self, subaccount: &Subaccount) -> Option<NeuronId> {
+        self.subaccount_index
+            .get(&SubaccountKey(subaccount.0))
+            .map(|id| NeuronId { id })
+    }
+
+    /// Removes the neuron with the given id and returns it, if present.
+    pub fn remove(&mut self, neuron_id: u64) -> Option<Neuron> {
+        let neuron = self.get(neuron_id)?;
+
+        let followee_keys: Vec<_> = self
+            .followees
+            .range(Self::followee_key_range(neuron_id))
+            .collect();
+        for (key, followee) in followee_keys {
+            self.followee_index.remove(&TopicFolloweeKey {
+                topic: key.topic,
+                followee_id: followee.followee_id,
+                follower_id: neuron_id,
+            });
+            self.followees.remove(&key);
+        }
+        for index in 0..neuron.recent_ballots.len() {
+            self.recent_ballots.remove(&BallotKey {
+                neuron_id,
+                index: index as u32,
+            });
+        }
+        if let Ok(subaccount) = Subaccount::try_from(&neuron.account[..]) {
+            self.subaccount_index.remove(&SubaccountKey(subaccount.0));
+        }
+        for principal in neuron.hot_keys.iter().chain(neuron.controller.iter()) {
+            self.principal_index.remove(&PrincipalNeuronKey {
+                principal: *principal,
+                neuron_id,
+            });
+        }
+        self.main.remove(&neuron_id);
+
+        Some(neuron)
+    }
+
+    /// Returns the ids of all stored neurons, in ascending order.
+    pub fn neuron_ids(&self) -> impl Iterator<Item = u64> + '_ {
+        self.main.iter().map(|(id, _)| id)
+    }
+
+    /// Returns the ids of the stored neurons that have `principal` as their
+    /// controller or as one of their hot keys.
+    pub fn neuron_ids_by_principal(&self, principal: &PrincipalId) -> Vec<u64> {
+        self.principal_index
+            .range(
+                PrincipalNeuronKey {
+                    principal: *principal,
+                    neuron_id: 0,
+                }..=PrincipalNeuronKey {
+                    principal: *principal,
+                    neuron_id: u64::MAX,
+                },
+            )
+            .map(|(key, _)| key.neuron_id)
+            .collect()
+    }
+
+    /// Returns the ids of the stored neurons that follow `followee_id` on
+    /// `topic`.
+    pub fn followers_of(&self, topic: i32, followee_id: u64) -> Vec<u64> {
+        self.followee_index
+            .range(
+                TopicFolloweeKey {
+                    topic,
+                    followee_id,
+                    follower_id: 0,
+                }..=TopicFolloweeKey {
+                    topic,
+                    followee_id,
+                    follower_id: u64::MAX,
+                },
+            )
+            .map(|(key, _)| key.follower_id)
+            .collect()
+    }
+
+    /// Returns the followees of the given neuron, by topic.
+    pub fn followees_of(&self, neuron_id: u64) -> HashMap<i32, Followees> {
+        let mut result: HashMap<i32, Followees> = HashMap::new();
+        for (key, followee) in self.followees.range(Self::followee_key_range(neuron_id)) {
+            let followees = result.entry(key.topic).or_default();
+            followees.followees.push(NeuronId {
+                id: followee.followee_id,
+            });
+            // Weights are either set for all followees of a topic, or
+            // for none.
+            if followee.weight_basis_points > 0 {
+                followees
+                    .weights_basis_points
+                    .push(followee.weight_basis_points);
+            }
+        }
+        result
+    }
+
+    fn followee_key_range(neuron_id: u64) -> std::ops::RangeInclusive<FolloweeKey> {
+        FolloweeKey {
+            follower_id: neuron_id,
+            topic: 0,
+            index: 0,
+        }..=FolloweeKey {
+            follower_id: neuron_id,
+            topic: i32::MAX,
+            index: u32::MAX,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::pb::v1::{KnownNeuronData, Vote};
+    use ic_base_types::PrincipalId;
+    use ic_nns_common::pb::v1::ProposalId;
+    use ic_stable_structures::VectorMemory;
+
+    fn new_store() -> StableNeuronStore<VectorMemory> {
+        StableNeuronStore::init(StableNeuronStoreMemories {
+            main: VectorMemory::default(),
+            followees: VectorMemory::default(),
+            recent_ballots: VectorMemory::default(),
+            subaccount_index: VectorMemory::default(),
+            principal_index: VectorMemory::default(),
+            followee_index: VectorMemory::default(),
+        })
+    }
+
+    fn neuron(id: u64) -> Neuron {
+        Neuron {
+            id: Some(NeuronId { id }),
+            account: vec![id as u8; 32],
+            controller: Some(PrincipalId::new_user_test_id(id)),
+            hot_keys: (0..10).map(PrincipalId::new_user_test_id).collect(),
+            followees: [
+                (
+                    0,
+                    Followees {
+                        followees: vec![NeuronId { id: 7 }, NeuronId { id: 3 }],
+                        weights_basis_points: vec![],
+                    },
+                ),
+                (
+                    4,
+                    Followees {
+                        followees: vec![NeuronId { id: 9 }, NeuronId { id: 5 }],
+                        weights_basis_points: vec![2_500, 7_500],
+                    },
+                ),
+            ]
+            .into_iter()
+            .collect(),
+            recent_ballots: (0..100)
+                .map(|p| BallotInfo {
+                    proposal_id: Some(ProposalId { id: p }),
+                    vote: Vote::Yes as i32,
+                })
+                .collect(),
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn neurons_round_trip_through_stable_memory() {
+        let mut store = new_store();
+        store.upsert(neuron(1));
+        store.upsert(neuron(2));
+
+        assert_eq!(store.len(), 2);
+        assert_eq!(store.get(1), Some(neuron(1)));
+        assert_eq!(store.get(2), Some(neuron(2)));
+        assert_eq!(store.get(3), None);
+        assert_eq!(store.neuron_ids().collect::<Vec<_>>(), vec![1, 2]);
+        assert_eq!(
+            store.get_neuron_id_by_subaccount(&Subaccount([2; 32])),
+            Some(NeuronId { id: 2 })
+        );
+        // Every neuron has the same 10 hot keys.
+        assert_eq!(
+            store.neuron_ids_by_principal(&PrincipalId::new_user_test_id(5)),
+            vec![1, 2]
+        );
+        assert_eq!(store.followers_of(4, 9), vec![1, 2]);
+        assert_eq!(store.followers_of(0, 9), Vec::<u64>::new());
+    }
+
+    #[test]
+    fn upsert_replaces_followees_and_ballots() {
+        let mut store = new_store();
+        store.upsert(neuron(1));
+
+        let updated = Neuron {
+            followees: HashMap::new(),
+            recent_ballots: vec![],
+            ..neuron(1)
+        };
+        store.upsert(updated.clone());
+
+        assert_eq!(store.get(1), Some(updated));
+        assert!(store.followees.is_empty());
+        assert!(store.recent_ballots.is_empty());
+    }
+
+    #[test]
+    fn remove_clears_all_indexes() {
+        let mut store = new_store();
+        store.upsert(neuron(1));
+        store.upsert(neuron(2));
+
+        assert_eq!(store.remove(1), Some(neuron(1)));
+        assert_eq!(store.remove(1), None);
+        assert_eq!(store.len(), 1);
+        assert_eq!(
+            store.get_neuron_id_by_subaccount(&Subaccount([1; 32])),
+            None
+        );
+        assert_eq!(store.followees_of(1), HashMap::new());
+        assert_eq!(
+            store.neuron_ids_by_principal(&PrincipalId::new_user_test_id(1)),
+            vec![2]
+        );
+        assert_eq!(store.followers_of(0, 7), vec![2]);
+        assert_eq!(store.get(2), Some(neuron(2)));
+    }
+
+    #[test]
+    #[should_panic(expected = "too large")]
+    fn neurons_with_unbounded_fields_are_rejected() {
+        let mut store = new_store();
+        store.upsert(Neuron {
+            known_neuron_data: Some(KnownNeuronData {
+                name: "a".repeat(200),
+                description: Some("b".repeat(3000)),
+            }),
+            ..neuron(1)
+        });
+    }
+}
@@ -388,6 +388,8 @@ impl Environment for FakeDriver {
                         nns_proposal_id: None,              // TODO[NNS1-2339]
                         neurons_fund_participants: None,    // TODO[NNS1-2339]
                         should_auto_finalize: Some(true),
+                        additional_payment_tokens: vec![],
+                        exchange_rate_canister_id: None,
                     }),
                     ..Default::default() // Not realistic, but sufficient for tests.
                 }),
@@ -85,7 +85,7 @@ use ic_nns_governance::{
         ProposalStatus::{self, Rejected},
         RewardEvent, RewardNodeProvider, RewardNodeProviders, SetDefaultFollowees,
         SettleCommunityFundParticipation, SwapBackgroundInformation, Tally, Topic,
-        UpdateNodeProvider, Vote, WaitForQuietState,
+        UpdateNodeProvider, Vote, VotingPowerDelegation, WaitForQuietState,
     },
 };
 use ic_sns_root::{GetSnsCanistersSummaryRequest, GetSnsCanistersSummaryResponse};
@@ -261,6 +261,7 @@ fn test_single_neuron_proposal_new() {
                             Ballot {
                                 vote: Vote::Yes as i32,
                                 voting_power: 1,
+                                ..Default::default()
                             },
                         )]),
                         ProposalDataChange::LatestTally(OptionChange::Different(
@@ -683,6 +684,7 @@ fn fixture_for_following_new() -> NNS {
                     neuron::Followees {
                         followees: [NeuronId { id: 1 }, NeuronId { id: 3 }, NeuronId { id: 4 }]
                             .to_vec(),
+                        weights_basis_points: vec![],
                     },
                 ),
         )
@@ -694,6 +696,7 @@ fn fixture_for_following_new() -> NNS {
                     neuron::Followees {
                         followees: [NeuronId { id: 5 }, NeuronId { id: 6 }, NeuronId { id: 7 }]
                             .to_vec(),
+                        weights_basis_points: vec![],
                     },
                 ),
         )
@@ -834,6 +837,7 @@ async fn test_cascade_following_new() {
                             Ballot {
                                 vote: Vote::Yes as i32,
                                 voting_power: 1125000000,
+                                ..Default::default()
                             },
                         ),
                         MapChange::Added(
@@ -841,6 +845,7 @@ async fn test_cascade_following_new() {
                             Ballot {
                                 vote: Vote::Unspecified as i32,
                                 voting_power: 1125000000,
+                                ..Default::default()
                             },
                         ),
                         MapChange::Added(
@@ -848,6 +853,7 @@ async fn test_cascade_following_new() {
                             Ballot {
                                 vote: Vote::Unspecified as i32,
                                 voting_power: 1125000000,
+                                ..Default::default()
                             },
                         ),
                         MapChange::Added(
@@ -855,6 +861,7 @@ async fn test_cascade_following_new() {
                             Ballot {
                                 vote: Vote::Unspecified as i32,
                                 voting_power: 1125000000,
+                                ..Default::default()
                             },
                         ),
                         MapChange::Added(
@@ -862,6 +869,7 @@ async fn test_cascade_following_new() {
                             Ballot {
                                 vote: Vote::Unspecified as i32,
                                 voting_power: 1125000000,
+                                ..Default::default()
                             },
                         ),
                         MapChange::Added(
@@ -869,6 +877,7 @@ async fn test_cascade_following_new() {
                             Ballot {
                                 vote: Vote::Unspecified as i32,
                                 voting_power: 1125000000,
+                                ..Default::default()
                             },
                         ),
                         MapChange::Added(
@@ -876,6 +885,7 @@ async fn test_cascade_following_new() {
                             Ballot {
                                 vote: Vote::Unspecified as i32,
                                 voting_power: 1125000000,
+                                ..Default::default()
                             },
                         ),
                         MapChange::Added(
@@ -883,6 +893,7 @@ async fn test_cascade_following_new() {
                             Ballot {
                                 vote: Vote::Unspecified as i32,
                                 voting_power: 1125000000,
+                                ..Default::default()
                             },
                         ),
                         MapChange::Added(
@@ -890,6 +901,7 @@ async fn test_cascade_following_new() {
                             Ballot {
                                 vote: Vote::Unspecified as i32,
                                 voting_power: 1125000000,
+                                ..Default::default()
                             },
                         ),
                     ]),
@@ -1097,6 +1109,7 @@ fn fixture_for_following() -> GovernanceProto {
                 followees: hashmap! {
                     Topic::NetworkEconomics as i32 => neuron::Followees {
                         followees: [NeuronId { id: 1 }, NeuronId { id: 3 }, NeuronId { id: 4 }].to_vec(),
+                        weights_basis_points: vec![],
                     },
                 },
                 ..neuron(2)
@@ -1105,6 +1118,7 @@ fn fixture_for_following() -> GovernanceProto {
                 followees: hashmap! {
                     Topic::Unspecified as i32 => neuron::Followees {
                         followees: [NeuronId { id: 5 }, NeuronId { id: 6 }, NeuronId { id: 7 }].to_vec(),
+                        weights_basis_points: vec![],
                     },
                 },
                 ..neuron(3)
@@ -1475,6 +1489,7 @@ async fn test_all_follow_proposer() {
             command: Some(manage_neuron::Command::Follow(manage_neuron::Follow {
                 topic: Topic::Unspecified as i32,
                 followees: [NeuronId { id: 1 }].to_vec(),
+                weights_basis_points: vec![],
             })),
         },
     )
@@ -1491,6 +1506,7 @@ async fn test_all_follow_proposer() {
             command: Some(manage_neuron::Command::Follow(manage_neuron::Follow {
                 topic: Topic::Unspecified as i32,
                 followees: [NeuronId { id: 1 }].to_vec(),
+                weights_basis_points: vec![],
             })),
         },
     )
@@ -1609,6 +1625,187 @@ async fn test_follow_negative() {
     );
 }
 
+/// Issues a manage_neuron command making neuron 8 follow `followees` on
+/// the NetworkEconomics topic with the given weights.
+fn follow_with_weights(
+    gov: &mut Governance,
+    followees: Vec<NeuronId>,
+    weights_basis_points: Vec<u32>,
+) -> ManageNeuronResponse {
+    gov.manage_neuron(
+        // Must match neuron 8's serialized_id.
+        &principal(8),
+        &ManageNeuron {
+            id: None,
+            neuron_id_or_subaccount: Some(NeuronIdOrSubaccount::NeuronId(NeuronId { id: 8 })),
+            command: Some(manage_neuron::Command::Follow(manage_neuron::Follow {
+                topic: Topic::NetworkEconomics as i32,
+                followees,
+                weights_basis_points,
+            })),
+        },
+    )
+    .now_or_never()
+    .unwrap()
+}
+
+/// Here we test that a neuron can split its voting power across several
+/// followees.
+///
+/// Neuron 8 delegates 25% of its voting power to neuron 5 and 75% to
+/// neuron 6 on the NetworkEconomics topic.
+///
+/// Neuron 1 makes a proposal, neuron 5 votes yes and neuron 6 votes
+/// no. Neuron 8's ballot should record the delegated voting power, and
+/// the tally should count it, even though neuron 8 has not voted itself.
+#[tokio::test]
+async fn test_follow_with_weights() {
+    let driver = fake::FakeDriver::default();
+    let mut gov = Governance::new(
+        fixture_for_following(),
+        driver.get_fake_env(),
+        driver.get_fake_ledger(),
+        driver.get_fake_cmc(),
+    );
+
+    // Invalid weights are rejected.
+    for (followees, weights) in [
+        // The weights do not add up to 100%.
+        (
+            vec![NeuronId { id: 5 }, NeuronId { id: 6 }],
+            vec![2_500, 2_500],
+        ),
+        // One weight per followee is required.
+        (vec![NeuronId { id: 5 }, NeuronId { id: 6 }], vec![10_000]),
+        // Weights must be positive.
+        (
+            vec![NeuronId { id: 5 }, NeuronId { id: 6 }],
+            vec![10_000, 0],
+        ),
+        // A neuron cannot delegate to itself.
+        (
+            vec![NeuronId { id: 5 }, NeuronId { id: 8 }],
+            vec![5_000, 5_000],
+        ),
+        // Followees must be distinct.
+        (
+            vec![NeuronId { id: 5 }, NeuronId { id: 5 }],
+            vec![5_000, 5_000],
+        ),
+    ] {
+        let result = follow_with_weights(&mut gov, followees, weights);
+        assert_matches!(
+            result.command,
+            Some(manage_neuron_response::Command::Error(err))
+                if err.error_type == ErrorType::InvalidCommand as i32
+        );
+    }
+
+    follow_with_weights(
+        &mut gov,
+        vec![NeuronId { id: 5 }, NeuronId { id: 6 }],
+        vec![2_500, 7_500],
+    )
+    .expect("Manage neuron failed");
+
+    gov.make_proposal(
+        &NeuronId { id: 1 },
+        // Must match neuron 1's serialized_id.
+        &principal(1),
+        &Proposal {
+            title: Some("A Reasonable Title".to_string()),
+            summary: "test".to_string(),
+            action: Some(proposal::Action::ManageNetworkEconomics(NetworkEconomics {
+                ..Default::default()
+            })),
+            ..Default::default()
+        },
+    )
+    .await
+    .unwrap();
+    let pid = ProposalId { id: 1 };
+    let voting_power = gov.get_proposal_data(pid).unwrap().ballots[&8].voting_power;
+    assert!(voting_power > 0);
+
+    fake::register_vote_assert_success(&mut gov, principal(5), NeuronId { id: 5 }, pid, Vote::Yes);
+    let ballot = gov.get_proposal_data(pid).unwrap().ballots[&8].clone();
+    assert_eq!(
+        ballot,
+        Ballot {
+            vote: Vote::Unspecified as i32,
+            voting_power,
+            delegated_yes_voting_power: voting_power / 4,
+            delegated_no_voting_power: 0,
+            delegation_complete: false,
+        }
+    );
+
+    // Neuron 8 cannot vote once part of its voting power has been cast.
+    let result = fake::register_vote(&mut gov, principal(8), NeuronId { id: 8 }, pid, Vote::No);
+    assert_matches!(
+        result.command,
+        Some(manage_neuron_response::Command::Error(err))
+            if err.error_type == ErrorType::PreconditionFailed as i32
+    );
+
+    fake::register_vote_assert_success(&mut gov, principal(6), NeuronId { id: 6 }, pid, Vote::No);
+    let ballot = gov.get_proposal_data(pid).unwrap().ballots[&8].clone();
+    assert_eq!(
+        ballot,
+        Ballot {
+            vote: Vote::Unspecified as i32,
+            voting_power,
+            delegated_yes_voting_power: voting_power / 4,
+            delegated_no_voting_power: voting_power * 3 / 4,
+            delegation_complete: true,
+        }
+    );
+
+    // The delegated voting power is part of the tally.
+    let yes: u64 = gov
+        .get_proposal_data(pid)
+        .unwrap()
+        .ballots
+        .values()
+        .map(|b| {
+            if b.vote == Vote::Yes as i32 {
+                b.voting_power
+            } else {
+                b.delegated_yes_voting_power
+            }
+        })
+        .sum();
+    assert_eq!(
+        gov.get_proposal_data(pid)
+            .unwrap()
+            .latest_tally
+            .as_ref()
+            .unwrap()
+            .yes,
+        yes
+    );
+
+    // The split is reported in the neuron info.
+    let neuron_info = gov.get_neuron_info(&NeuronId { id: 8 }).unwrap();
+    assert_eq!(
+        neuron_info.voting_power_delegations,
+        vec![
+            VotingPowerDelegation {
+                topic: Topic::NetworkEconomics as i32,
+                followee: Some(NeuronId { id: 5 }),
+                weight_basis_points: 2_500,
+                delegated_voting_power: neuron_info.voting_power / 4,
+            },
+            VotingPowerDelegation {
+                topic: Topic::NetworkEconomics as i32,
+                followee: Some(NeuronId { id: 6 }),
+                weight_basis_points: 7_500,
+                delegated_voting_power: neuron_info.voting_power * 3 / 4,
+            },
+        ]
+    );
+}
+
 /// Here we test that following doesn't apply to the Governance topic.
 ///
 /// Neuron 1 makes a proposal.
@@ -1775,6 +1972,7 @@ fn fixture_for_manage_neuron() -> GovernanceProto {
                     Topic::NeuronManagement as i32 => neuron::Followees {
                         followees: [NeuronId { id: 2 }, NeuronId { id: 3 }, NeuronId { id: 4 }]
                             .to_vec(),
+                        weights_basis_points: vec![],
                     },
                 },
                 ..neuron(1)
@@ -1962,6 +2160,7 @@ async fn test_manage_neuron() {
                 command: Some(manage_neuron::Command::Follow(manage_neuron::Follow {
                     topic: Topic::NeuronManagement as i32,
                     followees: [NeuronId { id: 2 }].to_vec(),
+                    weights_basis_points: vec![],
                 })),
             }))),
             ..Default::default()
@@ -2074,6 +2273,7 @@ async fn test_manage_neuron() {
                     topic: Topic::NeuronManagement as i32,
                     followees: [NeuronId { id: 2 }, NeuronId { id: 3 }, NeuronId { id: 4 }]
                         .to_vec(),
+                    weights_basis_points: vec![],
                 })),
             }))),
             ..Default::default()
@@ -2153,6 +2353,7 @@ async fn test_sufficient_stake_for_manage_neuron() {
                     command: Some(manage_neuron::Command::Follow(manage_neuron::Follow {
                         topic: Topic::NeuronManagement as i32,
                         followees: [NeuronId { id: 2 }].to_vec(),
+                        weights_basis_points: vec![],
                     })),
                 }))),
                 ..Default::default()
@@ -2181,6 +2382,7 @@ async fn test_sufficient_stake_for_manage_neuron() {
                 command: Some(manage_neuron::Command::Follow(manage_neuron::Follow {
                     topic: Topic::NeuronManagement as i32,
                     followees: [NeuronId { id: 2 }].to_vec(),
+                    weights_basis_points: vec![],
                 })),
             }))),
             ..Default::default()
@@ -2685,6 +2887,7 @@ async fn test_restricted_proposals_are_not_eligible_for_voting_rewards() {
                 command: Some(manage_neuron::Command::Follow(manage_neuron::Follow {
                     topic: Topic::NeuronManagement as i32,
                     followees: [NeuronId { id: 3 }].to_vec(),
+                    weights_basis_points: vec![],
                 })),
             }))),
             ..Default::default()
@@ -2757,6 +2960,7 @@ fn test_reward_distribution_skips_deleted_neurons() {
                     Ballot {
                         vote: Vote::Yes as i32,
                         voting_power: 250,
+                        ..Default::default()
                     },
                 ),
                 (
@@ -2765,6 +2969,7 @@ fn test_reward_distribution_skips_deleted_neurons() {
                     Ballot {
                         vote: Vote::Yes as i32,
                         voting_power: 750,
+                        ..Default::default()
                     },
                 ),
             ]
@@ -5600,7 +5805,13 @@ fn test_disburse_to_neuron() {
         assert!(
             parent_neuron
                 .followees
-                .insert(topic, Followees { followees: vec![] })
+                .insert(
+                    topic,
+                    Followees {
+                        followees: vec![],
+                        weights_basis_points: vec![]
+                    }
+                )
                 .is_none(),
             "{:#?}",
             parent_neuron,
@@ -5735,6 +5946,7 @@ async fn test_not_for_profit_neurons() {
         Topic::NeuronManagement as i32,
         Followees {
             followees: vec![normal_neuron.id.unwrap()],
+            weights_basis_points: vec![],
         },
     );
 
@@ -5832,6 +6044,7 @@ fn test_hot_keys_cant_change_followees_of_manage_neuron_topic() {
                 command: Some(manage_neuron::Command::Follow(manage_neuron::Follow {
                     topic: Topic::NetworkEconomics as i32,
                     followees: vec![second_neuron.id.unwrap()],
+                    weights_basis_points: vec![],
                 })),
             },
         )
@@ -5853,6 +6066,7 @@ fn test_hot_keys_cant_change_followees_of_manage_neuron_topic() {
                 command: Some(manage_neuron::Command::Follow(manage_neuron::Follow {
                     topic: Topic::NeuronManagement as i32,
                     followees: vec![second_neuron.id.unwrap()],
+                    weights_basis_points: vec![],
                 })),
             },
         )
@@ -6795,7 +7009,7 @@ fn test_default_followees() {
         governance_with_neurons(&init_neurons.values().cloned().collect::<Vec<Neuron>>());
 
     let default_followees = hashmap![
-        Topic::Unspecified as i32 => Followees { followees: vec![voter_neuron]},
+        Topic::Unspecified as i32 => Followees { followees: vec![voter_neuron], weights_basis_points: vec![] },
     ];
 
     gov.proto.default_followees = default_followees.clone();
@@ -6916,15 +7130,15 @@ fn test_default_followees() {
     );
 
     let default_followees2 = hashmap![
-        Topic::ExchangeRate as i32 => Followees { followees: vec![]},
-        Topic::NetworkEconomics as i32 => Followees { followees: vec![voter_neuron]},
-        Topic::Governance as i32 => Followees { followees: vec![]},
-        Topic::SnsAndCommunityFund as i32 => Followees { followees: vec![]},
-        Topic::NodeAdmin as i32 => Followees { followees: vec![voter_neuron]},
-        Topic::ParticipantManagement as i32 => Followees { followees: vec![]},
-        Topic::SubnetManagement as i32 => Followees { followees: vec![voter_neuron]},
-        Topic::NetworkCanisterManagement as i32 => Followees { followees: vec![voter_neuron]},
-        Topic::Kyc as i32 => Followees { followees: vec![]},
+        Topic::ExchangeRate as i32 => Followees { followees: vec![], weights_basis_points: vec![] },
+        Topic::NetworkEconomics as i32 => Followees { followees: vec![voter_neuron], weights_basis_points: vec![] },
+        Topic::Governance as i32 => Followees { followees: vec![], weights_basis_points: vec![] },
+        Topic::SnsAndCommunityFund as i32 => Followees { followees: vec![], weights_basis_points: vec![] },
+        Topic::NodeAdmin as i32 => Followees { followees: vec![voter_neuron], weights_basis_points: vec![] },
+        Topic::ParticipantManagement as i32 => Followees { followees: vec![], weights_basis_points: vec![] },
+        Topic::SubnetManagement as i32 => Followees { followees: vec![voter_neuron], weights_basis_points: vec![] },
+        Topic::NetworkCanisterManagement as i32 => Followees { followees: vec![voter_neuron], weights_basis_points: vec![] },
+        Topic::Kyc as i32 => Followees { followees: vec![], weights_basis_points: vec![] },
     ];
 
     // Make a proposal to change the default followees.
@@ -6991,6 +7205,7 @@ fn test_recompute_tally() {
         Ballot {
             vote: v as i32,
             voting_power: 10,
+            ..Default::default()
         }
     };
     let mut pinfo = ProposalData {
@@ -7300,6 +7515,7 @@ fn test_filter_proposals_neuron_visibility() {
                 followees: hashmap! {
                     Topic::NeuronManagement as i32 => neuron::Followees {
                         followees: vec![NeuronId { id: 1 }],
+                        weights_basis_points: vec![],
                     },
                 },
                 ..Default::default()
@@ -7387,6 +7603,7 @@ fn test_filter_proposals_include_all_manage_neuron_ignores_visibility() {
                 followees: hashmap! {
                     Topic::NeuronManagement as i32 => neuron::Followees {
                         followees: vec![NeuronId { id: 1 }],
+                        weights_basis_points: vec![],
                     },
                 },
                 ..Default::default()
@@ -7782,10 +7999,12 @@ fn test_filter_proposal_ballots() {
                         1 => Ballot {
                             vote: Vote::Yes as i32,
                             voting_power: 1,
+                            ..Default::default()
                         },
                         2 => Ballot {
                             vote: Vote::Yes as i32,
                             voting_power: 2,
+                            ..Default::default()
                         },
                 },
                 ..Default::default()
@@ -7810,6 +8029,7 @@ fn test_filter_proposal_ballots() {
                 1 => Ballot {
                     vote: Vote::Yes as i32,
                     voting_power: 1,
+                    ..Default::default()
                 },
         }
     );
@@ -7822,6 +8042,7 @@ fn test_filter_proposal_ballots() {
                 2 => Ballot {
                     vote: Vote::Yes as i32,
                     voting_power: 2,
+                    ..Default::default()
                 },
         }
     );
@@ -7834,6 +8055,7 @@ fn test_filter_proposal_ballots() {
                 1 => Ballot {
                     vote: Vote::Yes as i32,
                     voting_power: 1,
+                    ..Default::default()
                 },
         }
     );
@@ -7885,6 +8107,7 @@ fn test_list_neurons() {
     proto.neurons.get_mut(&42).unwrap().followees = hashmap! {
         Topic::NeuronManagement as i32 => neuron::Followees {
             followees: [NeuronId { id: 2 }, NeuronId { id: 4 }].to_vec(),
+            weights_basis_points: vec![],
         },
     };
     let driver = fake::FakeDriver::default();
@@ -8206,6 +8429,7 @@ async fn test_id_v1_works() {
                 command: Some(manage_neuron::Command::Follow(manage_neuron::Follow {
                     topic: Topic::NeuronManagement as i32,
                     followees: [NeuronId { id: 2 }].to_vec(),
+                    weights_basis_points: vec![],
                 })),
             }))),
             ..Default::default()
@@ -8251,6 +8475,7 @@ fn test_can_follow_by_subaccount_and_neuron_id() {
                 command: Some(manage_neuron::Command::Follow(manage_neuron::Follow {
                     topic: Topic::Unspecified as i32,
                     followees: [folowee].to_vec(),
+                    weights_basis_points: vec![],
                 })),
             },
         )
@@ -10643,6 +10868,8 @@ lazy_static! {
         nns_proposal_id: None, // TODO[NNS1-2339]
         neurons_fund_participants: None, // TODO[NNS1-2339]
         should_auto_finalize: Some(true),
+        additional_payment_tokens: vec![],
+        exchange_rate_canister_id: None,
     };
 }
 
@@ -11469,6 +11696,7 @@ async fn distribute_rewards_load_test() {
                         let ballot = Ballot {
                             vote: Vote::Yes as i32,
                             voting_power: n.voting_power(now),
+                            ..Default::default()
                         };
 
                         (n.id.as_ref().unwrap().id, ballot)
@@ -186,6 +186,7 @@ fn test_merge_neurons_fails() {
                 .set_dissolve_delay(MIN_DISSOLVE_DELAY_FOR_VOTE_ELIGIBILITY_SECONDS * 4)
                 .set_managers(Followees {
                     followees: vec![NeuronId { id: 14 }],
+                    weights_basis_points: vec![],
                 }),
         )
         .add_neuron(
@@ -193,6 +194,7 @@ fn test_merge_neurons_fails() {
                 .set_dissolve_delay(MIN_DISSOLVE_DELAY_FOR_VOTE_ELIGIBILITY_SECONDS * 4)
                 .set_managers(Followees {
                     followees: vec![NeuronId { id: 15 }],
+                    weights_basis_points: vec![],
                 }),
         )
         .add_neuron(
@@ -394,6 +396,7 @@ fn test_merge_neurons_fails() {
                 command: Some(Command::Follow(Follow {
                     topic: Topic::NeuronManagement as i32,
                     followees: (0..=11).map(|id| NeuronId { id }).collect(),
+                    weights_basis_points: vec![],
                 })),
             },
         )
@@ -408,6 +411,7 @@ fn test_merge_neurons_fails() {
                 command: Some(Command::Follow(Follow {
                     topic: Topic::NeuronManagement as i32,
                     followees: (0..=11).map(|id| NeuronId { id }).collect(),
+                    weights_basis_points: vec![],
                 })),
             },
         )
@@ -1034,6 +1038,7 @@ fn test_neuron_merge_follow() {
                 .set_aging_since_timestamp(DEFAULT_TEST_START_TIMESTAMP_SECONDS)
                 .set_managers(Followees {
                     followees: vec![NeuronId { id: 1 }],
+                    weights_basis_points: vec![],
                 }),
         )
         // the target
@@ -1043,6 +1048,7 @@ fn test_neuron_merge_follow() {
                 .set_aging_since_timestamp(DEFAULT_TEST_START_TIMESTAMP_SECONDS)
                 .set_managers(Followees {
                     followees: vec![NeuronId { id: 1 }],
+                    weights_basis_points: vec![],
                 }),
         )
         .create();
@@ -1,7 +1,11 @@
+use assert_matches::assert_matches;
+use candid::{Encode, Nat};
 use canister_test::Canister;
 use cycles_minting_canister::{
-    ChangeSubnetTypeAssignmentArgs, IcpXdrConversionRateCertifiedResponse, SubnetListWithType,
-    SubnetTypesToSubnetsResponse, UpdateSubnetTypeArgs, MEMO_TOP_UP_CANISTER,
+    ChangeSubnetTypeAssignmentArgs, CreateCanisterWithIcrc2, IcpXdrConversionRateCertifiedResponse,
+    NotifyError, NotifyErrorCode, SubnetListWithType, SubnetTypesToSubnetsResponse, TopUpWithIcrc2,
+    UpdateSubnetTypeArgs, CREATE_CANISTER_REFUND_FEE, MEMO_TOP_UP_CANISTER,
+    TOP_UP_CANISTER_REFUND_FEE,
 };
 use dfn_candid::candid_one;
 use dfn_protobuf::protobuf;
@@ -11,19 +15,27 @@ use ic_nervous_system_common_test_keys::{
     TEST_NEURON_1_OWNER_KEYPAIR, TEST_USER1_KEYPAIR, TEST_USER1_PRINCIPAL,
 };
 use ic_nns_common::types::{NeuronId, ProposalId, UpdateIcpXdrConversionRatePayload};
-use ic_nns_constants::{CYCLES_MINTING_CANISTER_ID, GOVERNANCE_CANISTER_ID};
+use ic_nns_constants::{CYCLES_MINTING_CANISTER_ID, GOVERNANCE_CANISTER_ID, LEDGER_CANISTER_ID};
 use ic_nns_governance::pb::v1::{NnsFunction, ProposalStatus};
 use ic_nns_test_utils::{
-    common::NnsInitPayloadsBuilder,
+    common::{build_cmc_wasm, NnsInitPayloadsBuilder},
     governance::{submit_external_update_proposal, wait_for_final_state},
     ids::TEST_NEURON_1_ID,
     itest_helpers::{local_test_on_nns_subnet, NnsCanisters},
+    state_test_helpers::{icrc1_balance, setup_nns_canisters, update_with_sender},
 };
+use ic_state_machine_tests::StateMachine;
+use ic_types::{CanisterId, Cycles};
 use ic_types_test_utils::ids::subnet_test_id;
 use icp_ledger::{
-    tokens_from_proto, AccountBalanceArgs, AccountIdentifier, BlockIndex, CyclesResponse, Memo,
-    NotifyCanisterArgs, SendArgs, Subaccount, Tokens, DEFAULT_TRANSFER_FEE,
+    tokens_from_proto, AccountBalanceArgs, AccountIdentifier, BlockIndex, CyclesResponse,
+    FeatureFlags, Memo, NotifyCanisterArgs, SendArgs, Subaccount, Tokens, DEFAULT_TRANSFER_FEE,
 };
+use icrc_ledger_types::{
+    icrc1::account::Account,
+    icrc2::approve::{ApproveArgs, ApproveError},
+};
+use std::time::UNIX_EPOCH;
 
 /// Test that the CMC's `icp_xdr_conversion_rate` can be updated via Governance
 /// proposal.
@@ -399,3 +411,259 @@ fn test_change_subnet_type_assignment() {
         Ok(())
     });
 }
+
+/// Sets up the NNS canisters on a StateMachine with ICRC-2 enabled on the
+/// ledger and `initial_balance` in the account of TEST_USER1, and approves
+/// the CMC to spend from that account.
+fn setup_icrc2_cmc_test(initial_balance: Tokens) -> StateMachine {
+    let state_machine = StateMachine::new();
+
+    let mut nns_init_payload_builder = NnsInitPayloadsBuilder::new();
+    nns_init_payload_builder.with_ledger_account(
+        AccountIdentifier::new(*TEST_USER1_PRINCIPAL, None),
+        initial_balance,
+    );
+    nns_init_payload_builder
+        .ledger
+        .init_args()
+        .unwrap()
+        .feature_flags = Some(FeatureFlags { icrc2: true });
+    setup_nns_canisters(&state_machine, nns_init_payload_builder.build());
+
+    let approve_result: Result<Nat, ApproveError> = update_with_sender(
+        &state_machine,
+        LEDGER_CANISTER_ID,
+        "icrc2_approve",
+        candid_one,
+        ApproveArgs {
+            from_subaccount: None,
+            spender: Account {
+                owner: CYCLES_MINTING_CANISTER_ID.get().0,
+                subaccount: None,
+            },
+            amount: Nat::from(initial_balance.get_e8s()),
+            expected_allowance: None,
+            expires_at: None,
+            fee: None,
+            memo: None,
+            created_at_time: None,
+        },
+        *TEST_USER1_PRINCIPAL,
+    )
+    .unwrap();
+    approve_result.expect("Failed to approve the CMC");
+
+    state_machine
+}
+
+fn now_nanos(state_machine: &StateMachine) -> u64 {
+    state_machine
+        .time()
+        .duration_since(UNIX_EPOCH)
+        .unwrap()
+        .as_nanos() as u64
+}
+
+fn user1_balance(state_machine: &StateMachine) -> Tokens {
+    icrc1_balance(
+        state_machine,
+        LEDGER_CANISTER_ID,
+        Account {
+            owner: TEST_USER1_PRINCIPAL.0,
+            subaccount: None,
+        },
+    )
+}
+
+fn top_up_with_icrc2(
+    state_machine: &StateMachine,
+    arg: TopUpWithIcrc2,
+) -> Result<Cycles, NotifyError> {
+    update_with_sender(
+        state_machine,
+        CYCLES_MINTING_CANISTER_ID,
+        "top_up_with_icrc2",
+        candid_one,
+        arg,
+        *TEST_USER1_PRINCIPAL,
+    )
+    .unwrap()
+}
+
+fn create_canister_with_icrc2(
+    state_machine: &StateMachine,
+    arg: CreateCanisterWithIcrc2,
+) -> Result<CanisterId, NotifyError> {
+    update_with_sender(
+        state_machine,
+        CYCLES_MINTING_CANISTER_ID,
+        "create_canister_with_icrc2",
+        candid_one,
+        arg,
+        *TEST_USER1_PRINCIPAL,
+    )
+    .unwrap()
+}
+
+/// Test that top_up_with_icrc2 pulls the payment from the caller's account
+/// and deposits the cycles, and that retrying the request, even once the CMC
+/// no longer remembers it and the ledger reports the transfer as a
+/// duplicate, does not charge the caller twice.
+#[test]
+fn test_top_up_with_icrc2() {
+    let initial_balance = Tokens::new(100, 0).unwrap();
+    let state_machine = setup_icrc2_cmc_test(initial_balance);
+    let balance_after_approve = user1_balance(&state_machine);
+    assert_eq!(
+        balance_after_approve,
+        initial_balance.checked_sub(&DEFAULT_TRANSFER_FEE).unwrap()
+    );
+
+    let canister_id = state_machine.create_canister(None);
+    let cycles_before = state_machine.cycle_balance(canister_id);
+
+    let arg = TopUpWithIcrc2 {
+        from_subaccount: None,
+        amount: Tokens::new(1, 0).unwrap(),
+        created_at_time: now_nanos(&state_machine),
+        canister_id,
+    };
+    // The default conversion rate of the CMC is 100 XDR per ICP.
+    let expected_cycles = Cycles::new(100_000_000_000_000);
+
+    assert_eq!(
+        top_up_with_icrc2(&state_machine, arg.clone()),
+        Ok(expected_cycles)
+    );
+    let expected_balance = balance_after_approve
+        .checked_sub(&arg.amount)
+        .and_then(|b| b.checked_sub(&DEFAULT_TRANSFER_FEE))
+        .unwrap();
+    assert_eq!(user1_balance(&state_machine), expected_balance);
+    assert_eq!(
+        state_machine.cycle_balance(canister_id),
+        cycles_before + expected_cycles.get()
+    );
+
+    // Retrying the request returns the remembered result.
+    assert_eq!(
+        top_up_with_icrc2(&state_machine, arg.clone()),
+        Ok(expected_cycles)
+    );
+    assert_eq!(user1_balance(&state_machine), expected_balance);
+    assert_eq!(
+        state_machine.cycle_balance(canister_id),
+        cycles_before + expected_cycles.get()
+    );
+
+    // Make the CMC forget about the request, as happens when its processing
+    // fails with a transient error after the payment was pulled. The retry
+    // then sees the transfer rejected as a duplicate by the ledger and must
+    // treat the payment as made.
+    state_machine
+        .reinstall_canister(
+            CYCLES_MINTING_CANISTER_ID,
+            build_cmc_wasm().bytes(),
+            Encode!(&NnsInitPayloadsBuilder::new().build().cycles_minting).unwrap(),
+        )
+        .unwrap();
+    assert_eq!(top_up_with_icrc2(&state_machine, arg), Ok(expected_cycles));
+    assert_eq!(user1_balance(&state_machine), expected_balance);
+}
+
+/// Test that the payment pulled by top_up_with_icrc2 is refunded, minus the
+/// fees, if the canister cannot be topped up.
+#[test]
+fn test_top_up_with_icrc2_refunds_on_failure() {
+    let state_machine = setup_icrc2_cmc_test(Tokens::new(100, 0).unwrap());
+    let balance_after_approve = user1_balance(&state_machine);
+
+    let arg = TopUpWithIcrc2 {
+        from_subaccount: None,
+        amount: Tokens::new(1, 0).unwrap(),
+        created_at_time: now_nanos(&state_machine),
+        // This canister does not exist.
+        canister_id: CanisterId::from_u64(1_000_000),
+    };
+
+    let result = top_up_with_icrc2(&state_machine, arg.clone());
+    assert_matches!(
+        result,
+        Err(NotifyError::Refunded {
+            block_index: Some(_),
+            ..
+        })
+    );
+    // The caller pays the fee of the transfer_from, the fee of the refund and
+    // the refund fee of the CMC.
+    let expected_balance = balance_after_approve
+        .checked_sub(&DEFAULT_TRANSFER_FEE)
+        .and_then(|b| b.checked_sub(&DEFAULT_TRANSFER_FEE))
+        .and_then(|b| b.checked_sub(&TOP_UP_CANISTER_REFUND_FEE))
+        .unwrap();
+    assert_eq!(user1_balance(&state_machine), expected_balance);
+
+    // The refund is final, retrying the request returns the same result.
+    assert_eq!(top_up_with_icrc2(&state_machine, arg), result);
+    assert_eq!(user1_balance(&state_machine), expected_balance);
+}
+
+/// Test that the payment pulled by create_canister_with_icrc2 is refunded,
+/// minus the fees, if no canister can be created.
+#[test]
+fn test_create_canister_with_icrc2_refunds_on_failure() {
+    let state_machine = setup_icrc2_cmc_test(Tokens::new(100, 0).unwrap());
+    let balance_after_approve = user1_balance(&state_machine);
+
+    // No subnets are authorized for canister creation in this test.
+    let result = create_canister_with_icrc2(
+        &state_machine,
+        CreateCanisterWithIcrc2 {
+            from_subaccount: None,
+            amount: Tokens::new(1, 0).unwrap(),
+            created_at_time: now_nanos(&state_machine),
+            controller: *TEST_USER1_PRINCIPAL,
+            subnet_type: None,
+        },
+    );
+    assert_matches!(
+        result,
+        Err(NotifyError::Refunded {
+            block_index: Some(_),
+            ..
+        })
+    );
+    let expected_balance = balance_after_approve
+        .checked_sub(&DEFAULT_TRANSFER_FEE)
+        .and_then(|b| b.checked_sub(&DEFAULT_TRANSFER_FEE))
+        .and_then(|b| b.checked_sub(&CREATE_CANISTER_REFUND_FEE))
+        .unwrap();
+    assert_eq!(user1_balance(&state_machine), expected_balance);
+}
+
+/// Test that no payment is pulled for a request that would exceed the cycles
+/// minting limit.
+#[test]
+fn test_top_up_with_icrc2_respects_cycles_limit() {
+    let state_machine = setup_icrc2_cmc_test(Tokens::new(1_000, 0).unwrap());
+    let balance_after_approve = user1_balance(&state_machine);
+    let canister_id = state_machine.create_canister(None);
+
+    // At 100 XDR per ICP, 501 ICP are worth more than the limit of 50 Pcycles
+    // per hour.
+    let result = top_up_with_icrc2(
+        &state_machine,
+        TopUpWithIcrc2 {
+            from_subaccount: None,
+            amount: Tokens::new(501, 0).unwrap(),
+            created_at_time: now_nanos(&state_machine),
+            canister_id,
+        },
+    );
+    assert_matches!(
+        result,
+        Err(NotifyError::Other { error_code, .. })
+            if error_code == NotifyErrorCode::CyclesLimitExceeded as u64
+    );
+    assert_eq!(user1_balance(&state_machine), balance_after_approve);
+}
@@ -0,0 +1,116 @@
+//! Measures the number of instructions the real `canister_pre_upgrade` and
+//! `canister_post_upgrade` of the governance canister take when most neurons
+//! are inactive, with and without moving them to the stable neuron store.
+
+use candid::{CandidType, Encode};
+use canister_test::{Project, Wasm};
+use ic_base_types::{CanisterId, PrincipalId};
+use ic_nns_common::pb::v1::NeuronId;
+use ic_nns_governance::pb::v1::{Governance as GovernanceProto, NetworkEconomics, Neuron};
+use ic_nns_test_utils::state_test_helpers::create_canister;
+use ic_state_machine_tests::StateMachine;
+use prost::Message;
+
+const NUM_NEURONS: u64 = 10_000;
+
+/// Mirrors the upgrade argument of the governance canister.
+#[derive(CandidType)]
+struct UpgradeArgs {
+    inactive_neuron_migration_enabled: Option<bool>,
+}
+
+// Creates the init payload of a governance canister with `NUM_NEURONS`
+// neurons, of which one in ten has stake and all others are empty.
+fn init_payload_with_inactive_neurons(inactive_neuron_migration_enabled: bool) -> Vec<u8> {
+    let mut proto = GovernanceProto {
+        economics: Some(NetworkEconomics::with_default_values()),
+        inactive_neuron_migration_enabled: Some(inactive_neuron_migration_enabled),
+        ..Default::default()
+    };
+    for i in 1..=NUM_NEURONS {
+        let mut account = vec![0; 32];
+        account[..8].copy_from_slice(&i.to_be_bytes());
+        proto.neurons.insert(
+            i,
+            Neuron {
+                id: Some(NeuronId { id: i }),
+                controller: Some(PrincipalId::new_user_test_id(i)),
+                account,
+                cached_neuron_stake_e8s: if i % 10 == 0 { 1_000_000_000 } else { 0 },
+                ..Default::default()
+            },
+        );
+    }
+    proto.encode_to_vec()
+}
+
+fn upgrade_instructions(
+    state_machine: &StateMachine,
+    canister_id: CanisterId,
+    wasm: &Wasm,
+    arg: Vec<u8>,
+) -> f64 {
+    let instructions_before = state_machine.subnet_message_instructions();
+    state_machine
+        .upgrade_canister(canister_id, wasm.clone().bytes(), arg)
+        .unwrap();
+    state_machine.subnet_message_instructions() - instructions_before
+}
+
+#[test]
+fn governance_upgrade_instructions_with_inactive_neurons() {
+    let state_machine = StateMachine::new();
+    let wasm = Project::cargo_bin_maybe_from_env("governance-canister", &[]);
+
+    let heap_canister_id = create_canister(
+        &state_machine,
+        wasm.clone(),
+        Some(init_payload_with_inactive_neurons(false)),
+        None,
+    );
+    let stable_canister_id = create_canister(
+        &state_machine,
+        wasm.clone(),
+        Some(init_payload_with_inactive_neurons(true)),
+        None,
+    );
+
+    // Let the heartbeat move the inactive neurons to stable memory, in
+    // batches.
+    for _ in 0..(2 * NUM_NEURONS / 1_000 + 10) {
+        state_machine.tick();
+    }
+
+    let heap_instructions = upgrade_instructions(&state_machine, heap_canister_id, &wasm, vec![]);
+    let stable_instructions =
+        upgrade_instructions(&state_machine, stable_canister_id, &wasm, vec![]);
+    println!(
+        "Upgrade with {} neurons, 90% of them inactive: {} instructions with all neurons on \
+         the heap, {} instructions with the inactive neurons in stable memory",
+        NUM_NEURONS, heap_instructions, stable_instructions
+    );
+    assert!(
+        stable_instructions * 2.0 < heap_instructions,
+        "{} >= {} / 2",
+        stable_instructions,
+        heap_instructions
+    );
+
+    // The migration can be turned on with the upgrade argument.
+    let enable = Encode!(&UpgradeArgs {
+        inactive_neuron_migration_enabled: Some(true),
+    })
+    .unwrap();
+    upgrade_instructions(&state_machine, heap_canister_id, &wasm, enable);
+    for _ in 0..(2 * NUM_NEURONS / 1_000 + 10) {
+        state_machine.tick();
+    }
+    let migrated_instructions =
+        upgrade_instructions(&state_machine, heap_canister_id, &wasm, vec![]);
+    assert!(
+        migrated_instructions * 2.0 < heap_instructions,
+        "{} >= {} / 2",
+        migrated_instructions,
+        heap_instructions
+    );
+}
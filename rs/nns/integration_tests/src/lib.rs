@@ -33,6 +33,9 @@ mod governance_mem_test;
 #[cfg(test)]
 mod governance_memory_migration_test;
 
+#[cfg(test)]
+mod governance_upgrade_instructions;
+
 #[cfg(test)]
 mod governance_get_build_metadata_test;
 
@@ -48,6 +51,9 @@ mod lifeline;
 #[cfg(test)]
 mod node_assignment;
 
+#[cfg(test)]
+mod proposal_dry_run;
+
 #[cfg(test)]
 mod reinstall_and_upgrade;
 
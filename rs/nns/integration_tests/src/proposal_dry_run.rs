@@ -0,0 +1,131 @@
+use candid::Encode;
+use ic_base_types::PrincipalId;
+use ic_nns_common::registry::encode_or_panic;
+use ic_nns_constants::{GOVERNANCE_CANISTER_ID, REGISTRY_CANISTER_ID};
+use ic_nns_governance::pb::v1::NnsFunction;
+use ic_nns_test_utils::{
+    common::NnsInitPayloadsBuilder,
+    proposal_dry_run::{dry_run_nns_function, NnsStateSnapshot},
+    state_test_helpers::setup_nns_canisters,
+};
+use ic_protobuf::registry::provisional_whitelist::v1::ProvisionalWhitelist;
+use ic_registry_keys::make_provisional_whitelist_record_key;
+use ic_registry_transport::{insert, pb::v1::RegistryAtomicMutateRequest};
+use ic_state_machine_tests::StateMachine;
+
+fn set_up_nns_with_provisional_whitelist() -> StateMachine {
+    let key = make_provisional_whitelist_record_key();
+    let provisional_whitelist = ProvisionalWhitelist {
+        list_type: 2,
+        set: vec![PrincipalId::new_user_test_id(1).into()],
+    };
+    let nns_init_payload = NnsInitPayloadsBuilder::new()
+        .with_initial_invariant_compliant_mutations()
+        .with_test_neurons()
+        .with_initial_mutations(vec![RegistryAtomicMutateRequest {
+            mutations: vec![insert(
+                key.as_bytes(),
+                encode_or_panic(&provisional_whitelist),
+            )],
+            preconditions: vec![],
+        }])
+        .build();
+
+    let state_machine = StateMachine::new();
+    setup_nns_canisters(&state_machine, nns_init_payload);
+    state_machine
+}
+
+#[test]
+fn test_dry_run_reports_registry_mutations() {
+    let state_machine = set_up_nns_with_provisional_whitelist();
+
+    let report = dry_run_nns_function(
+        &state_machine,
+        NnsFunction::ClearProvisionalWhitelist,
+        Encode!().unwrap(),
+    )
+    .unwrap();
+
+    assert_eq!(report.result, Ok(()));
+    assert_eq!(report.canister_id, REGISTRY_CANISTER_ID);
+    assert_eq!(report.method, "clear_provisional_whitelist");
+    assert_eq!(
+        report.registry_version_after,
+        report.registry_version_before + 1
+    );
+    assert_eq!(report.registry_changes.len(), 1);
+    assert_eq!(
+        report.registry_changes[0].key,
+        make_provisional_whitelist_record_key()
+    );
+    assert_eq!(
+        report.registry_changes[0].version,
+        report.registry_version_after
+    );
+    assert!(!report.registry_changes[0].deleted);
+    assert!(report.canister_upgrades.is_empty());
+}
+
+#[test]
+fn test_dry_run_reports_errors() {
+    let state_machine = set_up_nns_with_provisional_whitelist();
+
+    let report = dry_run_nns_function(
+        &state_machine,
+        NnsFunction::UpdateConfigOfSubnet,
+        b"not a valid payload".to_vec(),
+    )
+    .unwrap();
+
+    assert!(report.result.is_err(), "{}", report);
+    assert_eq!(
+        report.registry_version_after,
+        report.registry_version_before
+    );
+    assert!(report.registry_changes.is_empty());
+
+    assert!(dry_run_nns_function(&state_machine, NnsFunction::Unspecified, vec![]).is_err());
+}
+
+#[test]
+fn test_dry_run_on_restored_snapshot() {
+    // Capture a snapshot of the registry and governance after a mutation.
+    let source = set_up_nns_with_provisional_whitelist();
+    dry_run_nns_function(
+        &source,
+        NnsFunction::ClearProvisionalWhitelist,
+        Encode!().unwrap(),
+    )
+    .unwrap();
+    let snapshot =
+        NnsStateSnapshot::capture(&source, &[REGISTRY_CANISTER_ID, GOVERNANCE_CANISTER_ID]);
+    let source_report = dry_run_nns_function(
+        &source,
+        NnsFunction::ClearProvisionalWhitelist,
+        Encode!().unwrap(),
+    )
+    .unwrap();
+
+    // Restore the snapshot into freshly set up NNS canisters, and check that
+    // the dry run there has the same effect as on the source.
+    let target = StateMachine::new();
+    setup_nns_canisters(&target, NnsInitPayloadsBuilder::new().build());
+    snapshot.restore(&target).unwrap();
+    let target_report = dry_run_nns_function(
+        &target,
+        NnsFunction::ClearProvisionalWhitelist,
+        Encode!().unwrap(),
+    )
+    .unwrap();
+
+    assert_eq!(target_report.result, Ok(()));
+    assert_eq!(
+        target_report.registry_version_before,
+        source_report.registry_version_before
+    );
+    assert_eq!(
+        target_report.registry_changes,
+        source_report.registry_changes
+    );
+}
@@ -65,6 +65,7 @@ fn test_increase_maturity_just_after_init() {
                         Ballot {
                             vote: Vote::Yes as i32,
                             voting_power: 153,
+                            ..Default::default()
                         },
                     ))
                     .collect(),
@@ -296,6 +296,7 @@ lazy_static! {
                     Ballot {
                         vote: 0,
                         voting_power: 0,
+                        ..Default::default()
                     },
                 );
         }
@@ -0,0 +1,89 @@
+//! A utility to preview the effects of an `ExecuteNnsFunction` proposal.
+//!
+//! The NNS canisters are set up on a `StateMachine`, optionally restored from
+//! a snapshot of their states, and the proposal's payload is applied as if
+//! the proposal had been adopted. The resulting registry mutations, canister
+//! upgrades and errors are printed.
+
+use clap::Parser;
+use ic_nns_governance::pb::v1::NnsFunction;
+use ic_nns_test_utils::{
+    common::NnsInitPayloadsBuilder,
+    proposal_dry_run::{dry_run_nns_function, NnsStateSnapshot},
+    state_test_helpers::setup_nns_canisters,
+};
+use ic_state_machine_tests::StateMachine;
+use std::path::PathBuf;
+
+/// Command line argument to the utility.
+#[derive(Debug, Parser)]
+#[clap(
+    name = "nns-proposal-dry-run",
+    about = "Apply an ExecuteNnsFunction proposal payload to a snapshot of the NNS and report its effects.",
+    version
+)]
+struct CliArgs {
+    /// The NNS function of the proposal, either as its name (e.g.,
+    /// NNS_FUNCTION_CREATE_SUBNET) or as its numeric id.
+    #[clap(long)]
+    nns_function: String,
+
+    /// Path to a file containing the proposal's payload.
+    #[clap(long, parse(from_os_str))]
+    payload: PathBuf,
+
+    /// Path to a directory containing `<canister id>.wasm` and
+    /// `<canister id>.stable_memory` files for the NNS canisters whose states
+    /// should be restored. If not set, freshly initialized NNS canisters are
+    /// used.
+    #[clap(long, parse(from_os_str))]
+    snapshot: Option<PathBuf>,
+}
+
+fn parse_nns_function(value: &str) -> Result<NnsFunction, String> {
+    if let Ok(id) = value.parse::<i32>() {
+        return NnsFunction::from_i32(id).ok_or_else(|| format!("Unknown NNS function id {}", id));
+    }
+    (0..)
+        .map_while(NnsFunction::from_i32)
+        .find(|nns_function| nns_function.as_str_name() == value)
+        .ok_or_else(|| format!("Unknown NNS function {}", value))
+}
+
+/// Main method to run the utility.
+fn main() {
+    let args = CliArgs::try_parse_from(std::env::args())
+        .unwrap_or_else(|e| panic!("Illegal arguments: {}", e));
+
+    let nns_function = parse_nns_function(&args.nns_function).unwrap_or_else(|e| panic!("{}", e));
+    let payload = std::fs::read(&args.payload)
+        .unwrap_or_else(|e| panic!("Could not read {}: {}", args.payload.display(), e));
+
+    let machine = StateMachine::new();
+    setup_nns_canisters(&machine, NnsInitPayloadsBuilder::new().build());
+    if let Some(snapshot_dir) = &args.snapshot {
+        let snapshot = NnsStateSnapshot::load_from_dir(snapshot_dir).unwrap_or_else(|e| {
+            panic!(
+                "Could not load snapshot from {}: {}",
+                snapshot_dir.display(),
+                e
+            )
+        });
+        eprintln!(
+            "Restoring the states of {} canister(s) from {}",
+            snapshot.canisters.len(),
+            snapshot_dir.display()
+        );
+        snapshot
+            .restore(&machine)
+            .unwrap_or_else(|e| panic!("Could not restore snapshot: {}", e));
+    }
+
+    let report = dry_run_nns_function(&machine, nns_function, payload)
+        .unwrap_or_else(|e| panic!("Dry run failed: {}", e));
+    print!("{}", report);
+
+    if report.result.is_err() {
+        std::process::exit(1);
+    }
+}
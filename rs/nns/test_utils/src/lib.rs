@@ -6,6 +6,7 @@ pub mod common;
 pub mod governance;
 pub mod ids;
 pub mod itest_helpers;
+pub mod proposal_dry_run;
 pub mod registry;
 pub mod sns_wasm;
 pub mod state_test_helpers;
@@ -0,0 +1,323 @@
+//! Dry runs of NNS proposals on a `StateMachine`.
+//!
+//! A dry run loads a snapshot of the NNS canister states into a
+//! `StateMachine`, applies the payload of an `ExecuteNnsFunction` proposal as
+//! if the proposal had been adopted, and reports the resulting registry
+//! mutations, canister upgrades and errors. This allows validating a proposal
+//! before it is submitted for voting.
+use candid::Encode;
+use ic_base_types::{CanisterId, PrincipalId};
+use ic_nns_constants::{ALL_NNS_CANISTER_IDS, GOVERNANCE_CANISTER_ID, REGISTRY_CANISTER_ID};
+use ic_nns_governance::pb::v1::NnsFunction;
+use ic_registry_transport::{
+    deserialize_get_changes_since_response, deserialize_get_latest_version_response,
+    serialize_get_changes_since_request,
+};
+use ic_state_machine_tests::StateMachine;
+use ic_types::ingress::WasmResult;
+use std::{collections::BTreeMap, fmt, fs, io, path::Path, str::FromStr};
+
+/// The smallest valid Wasm module. Canisters are temporarily upgraded to this
+/// module so that their stable memory can be replaced without the original
+/// module's `pre_upgrade` hook overwriting it.
+const EMPTY_WASM: &[u8] = b"\x00asm\x01\x00\x00\x00";
+
+/// The number of rounds executed after applying a proposal, so that the
+/// asynchronous work it triggers (e.g., canister upgrades by root) completes.
+const TICKS_AFTER_EXECUTION: usize = 100;
+
+const WASM_FILE_EXTENSION: &str = "wasm";
+const STABLE_MEMORY_FILE_EXTENSION: &str = "stable_memory";
+
+/// The state of a single canister: its Wasm module and its stable memory.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct CanisterSnapshot {
+    pub wasm: Vec<u8>,
+    pub stable_memory: Vec<u8>,
+}
+
+/// A snapshot of the states of (a subset of) the NNS canisters.
+///
+/// On disk, a snapshot is a directory that contains the files
+/// `<canister id>.wasm` and `<canister id>.stable_memory` for each canister.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct NnsStateSnapshot {
+    pub canisters: BTreeMap<CanisterId, CanisterSnapshot>,
+}
+
+impl NnsStateSnapshot {
+    /// Loads a snapshot from the given directory.
+    pub fn load_from_dir(dir: &Path) -> io::Result<Self> {
+        let mut canisters = BTreeMap::new();
+        for entry in fs::read_dir(dir)? {
+            let path = entry?.path();
+            if path.extension().and_then(|e| e.to_str()) != Some(WASM_FILE_EXTENSION) {
+                continue;
+            }
+            let canister_id = path
+                .file_stem()
+                .and_then(|s| s.to_str())
+                .and_then(|s| PrincipalId::from_str(s).ok())
+                .and_then(|p| CanisterId::new(p).ok())
+                .ok_or_else(|| {
+                    io::Error::new(
+                        io::ErrorKind::InvalidData,
+                        format!("{} is not named after a canister id", path.display()),
+                    )
+                })?;
+            let wasm = fs::read(&path)?;
+            let stable_memory = fs::read(path.with_extension(STABLE_MEMORY_FILE_EXTENSION))?;
+            canisters.insert(
+                canister_id,
+                CanisterSnapshot {
+                    wasm,
+                    stable_memory,
+                },
+            );
+        }
+        Ok(Self { canisters })
+    }
+
+    /// Writes the snapshot to the given directory, which must exist.
+    pub fn save_to_dir(&self, dir: &Path) -> io::Result<()> {
+        for (canister_id, snapshot) in &self.canisters {
+            let path = dir.join(canister_id.to_string());
+            fs::write(path.with_extension(WASM_FILE_EXTENSION), &snapshot.wasm)?;
+            fs::write(
+                path.with_extension(STABLE_MEMORY_FILE_EXTENSION),
+                &snapshot.stable_memory,
+            )?;
+        }
+        Ok(())
+    }
+
+    /// Captures the states of the given canisters. Each canister is upgraded
+    /// to its own module first, so that its `pre_upgrade` hook writes its
+    /// complete state to stable memory.
+    pub fn capture(machine: &StateMachine, canister_ids: &[CanisterId]) -> Self {
+        let canisters = canister_ids
+            .iter()
+            .map(|canister_id| {
+                let wasm = canister_wasm(machine, *canister_id);
+                machine
+                    .upgrade_canister(*canister_id, wasm.clone(), Encode!().unwrap())
+                    .unwrap_or_else(|e| panic!("Failed to upgrade {}: {}", canister_id, e));
+                let stable_memory = machine.stable_memory(*canister_id);
+                (
+                    *canister_id,
+                    CanisterSnapshot {
+                        wasm,
+                        stable_memory,
+                    },
+                )
+            })
+            .collect();
+        Self { canisters }
+    }
+
+    /// Replaces the states of the canisters in the snapshot with the states
+    /// from the snapshot. The canisters must already exist in `machine`, e.g.,
+    /// by having been set up with `setup_nns_canisters`.
+    pub fn restore(&self, machine: &StateMachine) -> Result<(), String> {
+        for (canister_id, snapshot) in &self.canisters {
+            machine
+                .upgrade_canister(*canister_id, EMPTY_WASM.to_vec(), vec![])
+                .map_err(|e| format!("Failed to clear {}: {}", canister_id, e))?;
+            machine.set_stable_memory(*canister_id, &snapshot.stable_memory);
+            machine
+                .upgrade_canister(*canister_id, snapshot.wasm.clone(), Encode!().unwrap())
+                .map_err(|e| format!("Failed to restore {}: {}", canister_id, e))?;
+        }
+        Ok(())
+    }
+}
+
+fn canister_wasm(machine: &StateMachine, canister_id: CanisterId) -> Vec<u8> {
+    machine
+        .get_latest_state()
+        .canister_state(&canister_id)
+        .and_then(|canister| canister.execution_state.as_ref())
+        .map(|execution_state| execution_state.wasm_binary.binary.as_slice().to_vec())
+        .unwrap_or_else(|| panic!("Canister {} has no module", canister_id))
+}
+
+/// A registry record that was written by a dry run.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct RegistryChange {
+    pub key: String,
+    pub version: u64,
+    pub deleted: bool,
+}
+
+/// A canister whose module changed during a dry run.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct CanisterUpgrade {
+    pub canister_id: CanisterId,
+    pub module_hash_before: Option<[u8; 32]>,
+    pub module_hash_after: Option<[u8; 32]>,
+}
+
+/// The outcome of a dry run of an `ExecuteNnsFunction` proposal.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct DryRunReport {
+    pub nns_function: NnsFunction,
+    pub canister_id: CanisterId,
+    pub method: String,
+    /// The result of calling the method, as governance would upon adoption.
+    pub result: Result<(), String>,
+    pub registry_version_before: u64,
+    pub registry_version_after: u64,
+    pub registry_changes: Vec<RegistryChange>,
+    pub canister_upgrades: Vec<CanisterUpgrade>,
+}
+
+impl fmt::Display for DryRunReport {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(
+            f,
+            "{:?}: call to {} on {}",
+            self.nns_function, self.method, self.canister_id
+        )?;
+        match &self.result {
+            Ok(()) => writeln!(f, "  result: success")?,
+            Err(error) => writeln!(f, "  result: error: {}", error)?,
+        }
+        writeln!(
+            f,
+            "  registry: version {} -> {}, {} mutation(s)",
+            self.registry_version_before,
+            self.registry_version_after,
+            self.registry_changes.len()
+        )?;
+        for change in &self.registry_changes {
+            writeln!(
+                f,
+                "    {} {} at version {}",
+                if change.deleted { "delete" } else { "upsert" },
+                change.key,
+                change.version
+            )?;
+        }
+        writeln!(f, "  canister upgrades: {}", self.canister_upgrades.len())?;
+        for upgrade in &self.canister_upgrades {
+            writeln!(
+                f,
+                "    {}: {} -> {}",
+                upgrade.canister_id,
+                format_module_hash(&upgrade.module_hash_before),
+                format_module_hash(&upgrade.module_hash_after)
+            )?;
+        }
+        Ok(())
+    }
+}
+
+fn format_module_hash(module_hash: &Option<[u8; 32]>) -> String {
+    match module_hash {
+        Some(hash) => hash.iter().map(|b| format!("{:02x}", b)).collect(),
+        None => "<no module>".to_string(),
+    }
+}
+
+/// Applies `payload` to the canister method that an adopted
+/// `ExecuteNnsFunction` proposal with the given `nns_function` would call,
+/// with the governance canister as the caller, and reports the effects.
+pub fn dry_run_nns_function(
+    machine: &StateMachine,
+    nns_function: NnsFunction,
+    payload: Vec<u8>,
+) -> Result<DryRunReport, String> {
+    let (canister_id, method) = nns_function
+        .canister_and_function()
+        .map_err(|e| format!("Invalid NNS function {:?}: {}", nns_function, e))?;
+
+    let registry_version_before = registry_latest_version(machine)?;
+    let module_hashes_before = nns_module_hashes(machine);
+
+    let result = match machine.execute_ingress_as(
+        GOVERNANCE_CANISTER_ID.get(),
+        canister_id,
+        method,
+        payload,
+    ) {
+        Ok(WasmResult::Reply(_)) => Ok(()),
+        Ok(WasmResult::Reject(reject)) => Err(format!("Canister rejected: {}", reject)),
+        Err(error) => Err(error.to_string()),
+    };
+    for _ in 0..TICKS_AFTER_EXECUTION {
+        machine.tick();
+    }
+
+    let registry_version_after = registry_latest_version(machine)?;
+    let registry_changes = registry_changes_since(machine, registry_version_before)?;
+    let canister_upgrades = nns_module_hashes(machine)
+        .into_iter()
+        .filter_map(|(canister_id, module_hash_after)| {
+            let module_hash_before = module_hashes_before.get(&canister_id).copied().flatten();
+            (module_hash_before != module_hash_after).then_some(CanisterUpgrade {
+                canister_id,
+                module_hash_before,
+                module_hash_after,
+            })
+        })
+        .collect();
+
+    Ok(DryRunReport {
+        nns_function,
+        canister_id,
+        method: method.to_string(),
+        result,
+        registry_version_before,
+        registry_version_after,
+        registry_changes,
+        canister_upgrades,
+    })
+}
+
+fn nns_module_hashes(machine: &StateMachine) -> BTreeMap<CanisterId, Option<[u8; 32]>> {
+    ALL_NNS_CANISTER_IDS
+        .iter()
+        .map(|canister_id| (**canister_id, machine.module_hash(**canister_id)))
+        .collect()
+}
+
+fn registry_latest_version(machine: &StateMachine) -> Result<u64, String> {
+    let response = query_registry(machine, "get_latest_version", vec![])?;
+    deserialize_get_latest_version_response(response).map_err(|e| e.to_string())
+}
+
+fn registry_changes_since(
+    machine: &StateMachine,
+    version: u64,
+) -> Result<Vec<RegistryChange>, String> {
+    let request = serialize_get_changes_since_request(version).map_err(|e| e.to_string())?;
+    let response = query_registry(machine, "get_changes_since", request)?;
+    let (deltas, _) =
+        deserialize_get_changes_since_response(response).map_err(|e| e.to_string())?;
+
+    let mut changes: Vec<RegistryChange> = deltas
+        .into_iter()
+        .flat_map(|delta| {
+            let key = String::from_utf8_lossy(&delta.key).to_string();
+            delta.values.into_iter().map(move |value| RegistryChange {
+                key: key.clone(),
+                version: value.version,
+                deleted: value.deletion_marker,
+            })
+        })
+        .collect();
+    changes.sort_by(|a, b| (a.version, &a.key).cmp(&(b.version, &b.key)));
+    Ok(changes)
+}
+
+fn query_registry(
+    machine: &StateMachine,
+    method: &str,
+    payload: Vec<u8>,
+) -> Result<Vec<u8>, String> {
+    match machine.query(REGISTRY_CANISTER_ID, method, payload) {
+        Ok(WasmResult::Reply(bytes)) => Ok(bytes),
+        Ok(WasmResult::Reject(reject)) => Err(format!("Registry rejected {}: {}", method, reject)),
+        Err(error) => Err(error.to_string()),
+    }
+}
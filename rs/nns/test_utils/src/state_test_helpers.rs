@@ -678,6 +678,7 @@ pub fn nns_set_followees_for_neuron(
             .iter()
             .map(|leader| ic_nns_common::pb::v1::NeuronId { id: leader.id })
             .collect(),
+        weights_basis_points: vec![],
     });
 
     manage_neuron(state_machine, sender, neuron_id, command)
@@ -176,6 +176,7 @@ fn test_subnet_is_only_deleted_when_appropriate() {
                 ledger_canister_id: Some(LEDGER_CANISTER_ID),
                 governance_canister_id: Some(GOVERNANCE_CANISTER_ID),
                 exchange_rate_canister: None,
+                exchange_rate_sanity_checks: None,
                 minting_account_id: Some(GOVERNANCE_CANISTER_ID.get().into()),
                 last_purged_notification: Some(1),
             }),
@@ -195,6 +195,7 @@ pub fn cmd_make_trusted_neurons_follow_neuron(
             command: Some(Command::Follow(Follow {
                 topic: Topic::Unspecified as i32,
                 followees: [NeuronId { id: cmd.neuron_id }].to_vec(),
+                weights_basis_points: vec![],
             })),
         })
         .expect("Couldn't encode payload for manage neuron command");
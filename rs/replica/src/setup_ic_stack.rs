@@ -29,7 +29,9 @@ use ic_replica_setup_ic_network::{setup_consensus_and_p2p, P2PStateSyncClient};
 use ic_replicated_state::ReplicatedState;
 use ic_state_manager::{state_sync::StateSync, StateManagerImpl};
 use ic_types::{consensus::CatchUpPackage, messages::SignedIngress, NodeId, SubnetId};
-use ic_xnet_endpoint::{XNetEndpoint, XNetEndpointConfig};
+use ic_xnet_endpoint::{
+    CrossSubnetQueryServer, XNetCrossSubnetQueryClient, XNetEndpoint, XNetEndpointConfig,
+};
 use ic_xnet_payload_builder::XNetPayloadBuilderImpl;
 use std::sync::{Arc, RwLock};
 
@@ -156,6 +158,12 @@ pub fn construct_ic_stack(
         subnet_id,
         subnet_config.cycles_account_manager_config,
     ));
+    // Query calls to other subnets are sent to their XNet endpoints.
+    let cross_subnet_query_client = XNetCrossSubnetQueryClient::new(
+        rt_handle_xnet.clone(),
+        Arc::clone(&crypto) as Arc<_>,
+        registry.clone(),
+    );
     let execution_services = ExecutionServices::setup_execution(
         log.clone(),
         metrics_registry,
@@ -166,6 +174,7 @@ pub fn construct_ic_stack(
         cycles_account_manager.clone(),
         state_manager.clone(),
         state_manager.get_fd_factory(),
+        Some(Arc::new(cross_subnet_query_client)),
     );
     // ---------- MESSAGE ROUTING DEPS FOLLOW ----------
     let certified_stream_store: Arc<dyn CertifiedStreamStore> =
@@ -204,6 +213,10 @@ pub fn construct_ic_stack(
         Arc::clone(&certified_stream_store),
         Arc::clone(&crypto) as Arc<_>,
         registry.clone(),
+        Some(CrossSubnetQueryServer {
+            query_handler: Arc::clone(&execution_services.sync_query_handler),
+            state_reader: Arc::clone(&state_manager) as Arc<_>,
+        }),
         xnet_config,
         metrics_registry,
         log.clone(),
@@ -699,19 +699,25 @@ fn can_get_canister_information() {
                 ),
             ),
             // We can check exact equality because no costs are incurred for a
-            // canister that's created but has no code installed on it.
-            Ok(WasmResult::Reply(res)) if CanisterStatusResultV2::decode(&res).unwrap() == CanisterStatusResultV2::new(
-                CanisterStatusType::Running,
-                None,
-                canister_a.get(),
-                vec![canister_a.get()],
-                NumBytes::from((2 * size_of::<CanisterChange>() + 2 * size_of::<PrincipalId>()) as u64),
-                num_cycles.get(),
-                ComputeAllocation::default().as_percent(),
-                None,
-                2592000,
-                0u128,
-            )
+            // canister that's created but has no code installed on it. The
+            // scheduling stats depend on the rounds executed so far, so they
+            // are taken from the result.
+            Ok(WasmResult::Reply(res)) => {
+                let status = CanisterStatusResultV2::decode(&res).unwrap();
+                let scheduling_stats = status.scheduling_stats().cloned().unwrap();
+                assert_eq!(status, CanisterStatusResultV2::new(
+                    CanisterStatusType::Running,
+                    None,
+                    canister_a.get(),
+                    vec![canister_a.get()],
+                    NumBytes::from((2 * size_of::<CanisterChange>() + 2 * size_of::<PrincipalId>()) as u64),
+                    num_cycles.get(),
+                    ComputeAllocation::default().as_percent(),
+                    None,
+                    2592000,
+                    0u128,
+                ).with_scheduling_stats(scheduling_stats));
+            }
         );
 
         // Install code to canister_b.
@@ -61,6 +61,7 @@ pub fn push_response(
                 originator_reply_callback: callback_id,
                 refund: context.request.take_cycles(),
                 response_payload,
+                deadline: context.request.deadline,
             });
 
             Ok(())
@@ -87,6 +88,7 @@ pub fn push_response(
                 originator_reply_callback: callback_id,
                 refund: context.request.take_cycles(),
                 response_payload,
+                deadline: context.request.deadline,
             });
 
             Ok(())
@@ -0,0 +1,270 @@
+#[cfg(test)]
+mod tests;
+
+use crate::{page_map::PageAllocatorFileDescriptor, CanisterState, ExecutionState, Memory};
+use ic_types::{CanisterId, NumBytes, PrincipalId, Time};
+use std::{
+    collections::{BTreeMap, BTreeSet},
+    convert::TryFrom,
+    fmt,
+    mem::size_of,
+    sync::Arc,
+};
+
+/// Uniquely identifies a canister snapshot on a subnet.
+///
+/// The ID consists of the ID of the snapshotted canister and a local ID
+/// generated by `SystemMetadata::generate_snapshot_id()`. Ordering by
+/// `canister_id` first keeps all snapshots of a canister next to each other.
+#[derive(Copy, Clone, Debug, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct SnapshotId {
+    canister_id: CanisterId,
+    local_id: u64,
+}
+
+impl SnapshotId {
+    pub fn new(canister_id: CanisterId, local_id: u64) -> Self {
+        Self {
+            canister_id,
+            local_id,
+        }
+    }
+
+    /// The ID of the canister that the snapshot belongs to.
+    pub fn canister_id(&self) -> CanisterId {
+        self.canister_id
+    }
+
+    /// The subnet-local part of the snapshot ID.
+    pub fn local_id(&self) -> u64 {
+        self.local_id
+    }
+
+    /// Returns the byte representation exposed via the management canister:
+    /// the big-endian encoded `local_id`, followed by the canister ID bytes.
+    pub fn to_vec(&self) -> Vec<u8> {
+        let mut bytes = self.local_id.to_be_bytes().to_vec();
+        bytes.extend_from_slice(self.canister_id.get_ref().as_slice());
+        bytes
+    }
+}
+
+impl TryFrom<&[u8]> for SnapshotId {
+    type Error = String;
+
+    fn try_from(bytes: &[u8]) -> Result<Self, Self::Error> {
+        if bytes.len() <= size_of::<u64>() {
+            return Err(format!("Snapshot ID too short: {} bytes", bytes.len()));
+        }
+        let (local_id, canister_id) = bytes.split_at(size_of::<u64>());
+        let local_id = u64::from_be_bytes(local_id.try_into().unwrap());
+        let canister_id = PrincipalId::try_from(canister_id)
+            .map_err(|err| format!("Invalid canister ID in snapshot ID: {}", err))?;
+        let canister_id = CanisterId::new(canister_id)
+            .map_err(|err| format!("Invalid canister ID in snapshot ID: {}", err))?;
+        Ok(Self::new(canister_id, local_id))
+    }
+}
+
+impl fmt::Display for SnapshotId {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}-{}", self.canister_id, self.local_id)
+    }
+}
+
+/// A snapshot of a canister's Wasm module, heap, stable memory, globals and
+/// certified data, as taken via `take_canister_snapshot`.
+///
+/// The memories share their `PageMap`s with the canister, so taking a
+/// snapshot is cheap and only the pages modified afterwards take up extra
+/// space.
+#[derive(Clone, Debug, PartialEq)]
+pub struct CanisterSnapshot {
+    canister_id: CanisterId,
+    taken_at_timestamp: Time,
+    canister_version: u64,
+    certified_data: Vec<u8>,
+    execution_state: ExecutionState,
+}
+
+impl CanisterSnapshot {
+    pub fn new(
+        canister_id: CanisterId,
+        taken_at_timestamp: Time,
+        canister_version: u64,
+        certified_data: Vec<u8>,
+        execution_state: ExecutionState,
+    ) -> Self {
+        Self {
+            canister_id,
+            taken_at_timestamp,
+            canister_version,
+            certified_data,
+            execution_state,
+        }
+    }
+
+    /// Takes a snapshot of the given canister. Returns `None` if the canister
+    /// is empty.
+    pub fn from_canister(canister: &CanisterState, taken_at_timestamp: Time) -> Option<Self> {
+        let execution_state = canister.execution_state.as_ref()?;
+        Some(Self::new(
+            canister.canister_id(),
+            taken_at_timestamp,
+            canister.system_state.canister_version,
+            canister.system_state.certified_data.clone(),
+            detach_execution_state(execution_state),
+        ))
+    }
+
+    pub fn canister_id(&self) -> CanisterId {
+        self.canister_id
+    }
+
+    pub fn taken_at_timestamp(&self) -> Time {
+        self.taken_at_timestamp
+    }
+
+    /// The canister version at the time the snapshot was taken.
+    pub fn canister_version(&self) -> u64 {
+        self.canister_version
+    }
+
+    pub fn certified_data(&self) -> &[u8] {
+        &self.certified_data
+    }
+
+    pub fn execution_state(&self) -> &ExecutionState {
+        &self.execution_state
+    }
+
+    /// Returns a copy of the snapshotted execution state, to be installed into
+    /// the canister when the snapshot is loaded.
+    ///
+    /// The memories of the copy are not backed by the snapshot's checkpoint
+    /// files, because the state manager persists them as the canister's own.
+    pub fn restore_execution_state(
+        &self,
+        fd_factory: Arc<dyn PageAllocatorFileDescriptor>,
+    ) -> ExecutionState {
+        let mut execution_state = self.execution_state.clone();
+        execution_state.session_nonce = None;
+        execution_state.wasm_memory = Memory::new(
+            execution_state
+                .wasm_memory
+                .page_map
+                .detached_copy(Arc::clone(&fd_factory)),
+            execution_state.wasm_memory.size,
+        );
+        execution_state.stable_memory = Memory::new(
+            execution_state
+                .stable_memory
+                .page_map
+                .detached_copy(fd_factory),
+            execution_state.stable_memory.size,
+        );
+        execution_state
+    }
+
+    /// The amount of memory taken by the snapshot, as charged to the canister.
+    pub fn size(&self) -> NumBytes {
+        self.execution_state.memory_usage() + NumBytes::from(self.certified_data.len() as u64)
+    }
+}
+
+/// Clones the given execution state, sharing the underlying `PageMap`s but not
+/// the sandbox state, so that neither copy can observe changes to the other.
+fn detach_execution_state(execution_state: &ExecutionState) -> ExecutionState {
+    let mut execution_state = execution_state.clone();
+    execution_state.session_nonce = None;
+    execution_state.wasm_memory = Memory::new(
+        execution_state.wasm_memory.page_map.clone(),
+        execution_state.wasm_memory.size,
+    );
+    execution_state.stable_memory = Memory::new(
+        execution_state.stable_memory.page_map.clone(),
+        execution_state.stable_memory.size,
+    );
+    execution_state
+}
+
+/// The canister snapshots held by a subnet, indexed by snapshot ID.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct CanisterSnapshots {
+    snapshots: BTreeMap<SnapshotId, Arc<CanisterSnapshot>>,
+}
+
+impl CanisterSnapshots {
+    pub fn new(snapshots: BTreeMap<SnapshotId, Arc<CanisterSnapshot>>) -> Self {
+        Self { snapshots }
+    }
+
+    /// Adds a new snapshot under the given ID.
+    pub fn push(&mut self, snapshot_id: SnapshotId, snapshot: Arc<CanisterSnapshot>) {
+        self.snapshots.insert(snapshot_id, snapshot);
+    }
+
+    pub fn get(&self, snapshot_id: &SnapshotId) -> Option<&Arc<CanisterSnapshot>> {
+        self.snapshots.get(snapshot_id)
+    }
+
+    pub fn remove(&mut self, snapshot_id: &SnapshotId) -> Option<Arc<CanisterSnapshot>> {
+        self.snapshots.remove(snapshot_id)
+    }
+
+    /// Returns all snapshots of the given canister, in the order they were taken.
+    pub fn list_snapshots(
+        &self,
+        canister_id: CanisterId,
+    ) -> impl Iterator<Item = (&SnapshotId, &Arc<CanisterSnapshot>)> {
+        self.snapshots
+            .range(SnapshotId::new(canister_id, 0)..=SnapshotId::new(canister_id, u64::MAX))
+    }
+
+    /// Returns the number of snapshots of the given canister.
+    pub fn count_snapshots(&self, canister_id: CanisterId) -> usize {
+        self.list_snapshots(canister_id).count()
+    }
+
+    /// Returns the total memory taken by the snapshots of the given canister.
+    pub fn memory_taken_by_canister(&self, canister_id: CanisterId) -> NumBytes {
+        self.list_snapshots(canister_id)
+            .map(|(_, snapshot)| snapshot.size())
+            .fold(NumBytes::from(0), |acc, size| acc + size)
+    }
+
+    /// Deletes all snapshots of the given canister, e.g. when the canister is
+    /// deleted.
+    pub fn delete_snapshots(&mut self, canister_id: CanisterId) {
+        let snapshot_ids: Vec<_> = self
+            .list_snapshots(canister_id)
+            .map(|(snapshot_id, _)| *snapshot_id)
+            .collect();
+        for snapshot_id in snapshot_ids {
+            self.snapshots.remove(&snapshot_id);
+        }
+    }
+
+    /// Returns the IDs of all snapshots.
+    pub fn snapshot_ids(&self) -> BTreeSet<SnapshotId> {
+        self.snapshots.keys().cloned().collect()
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = (&SnapshotId, &Arc<CanisterSnapshot>)> {
+        self.snapshots.iter()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.snapshots.is_empty()
+    }
+
+    /// Retains only the snapshots of canisters for which the predicate holds.
+    /// Used when splitting a subnet.
+    pub fn retain<F>(&mut self, mut is_local_canister: F)
+    where
+        F: FnMut(&CanisterId) -> bool,
+    {
+        self.snapshots
+            .retain(|snapshot_id, _| is_local_canister(&snapshot_id.canister_id))
+    }
+}
@@ -0,0 +1,98 @@
+use super::*;
+use ic_test_utilities::mock_time;
+use ic_test_utilities::state::CanisterStateBuilder;
+use ic_types::PrincipalId;
+
+const CANISTER_1: CanisterId = CanisterId::from_u64(1);
+const CANISTER_2: CanisterId = CanisterId::from_u64(2);
+
+fn snapshot_of(canister_id: CanisterId) -> Arc<CanisterSnapshot> {
+    let canister = CanisterStateBuilder::new()
+        .with_canister_id(canister_id)
+        .with_wasm(vec![1, 2, 3])
+        .with_stable_memory(vec![4; 100])
+        .with_certified_data(vec![5; 10])
+        .build();
+    Arc::new(CanisterSnapshot::from_canister(&canister, mock_time()).unwrap())
+}
+
+#[test]
+fn snapshot_id_roundtrip() {
+    let snapshot_id = SnapshotId::new(CANISTER_1, 17);
+    let bytes = snapshot_id.to_vec();
+    assert_eq!(&bytes[..8], &17_u64.to_be_bytes());
+    assert_eq!(SnapshotId::try_from(bytes.as_slice()), Ok(snapshot_id));
+}
+
+#[test]
+fn snapshot_id_from_invalid_bytes() {
+    assert!(SnapshotId::try_from(&[][..]).is_err());
+    assert!(SnapshotId::try_from(&17_u64.to_be_bytes()[..]).is_err());
+
+    let mut bytes = 17_u64.to_be_bytes().to_vec();
+    bytes.extend_from_slice(&[0; PrincipalId::MAX_LENGTH_IN_BYTES + 1]);
+    assert!(SnapshotId::try_from(bytes.as_slice()).is_err());
+}
+
+#[test]
+fn snapshot_of_empty_canister_is_none() {
+    // No Wasm module, so no execution state.
+    let canister = CanisterStateBuilder::new()
+        .with_canister_id(CANISTER_1)
+        .build();
+    assert!(CanisterSnapshot::from_canister(&canister, mock_time()).is_none());
+}
+
+#[test]
+fn snapshot_captures_canister_state() {
+    let canister = CanisterStateBuilder::new()
+        .with_canister_id(CANISTER_1)
+        .with_wasm(vec![1, 2, 3])
+        .with_certified_data(vec![5; 10])
+        .build();
+    let snapshot = CanisterSnapshot::from_canister(&canister, mock_time()).unwrap();
+
+    assert_eq!(snapshot.canister_id(), CANISTER_1);
+    assert_eq!(snapshot.certified_data(), &[5; 10]);
+    assert_eq!(
+        snapshot.canister_version(),
+        canister.system_state.canister_version
+    );
+    assert_eq!(
+        snapshot.execution_state(),
+        canister.execution_state.as_ref().unwrap()
+    );
+    assert_eq!(
+        snapshot.size(),
+        canister.execution_state.as_ref().unwrap().memory_usage() + NumBytes::from(10)
+    );
+}
+
+#[test]
+fn list_and_delete_snapshots_by_canister() {
+    let mut snapshots = CanisterSnapshots::default();
+    let id_1 = SnapshotId::new(CANISTER_1, 0);
+    let id_2 = SnapshotId::new(CANISTER_2, 1);
+    let id_3 = SnapshotId::new(CANISTER_1, 2);
+    snapshots.push(id_1, snapshot_of(CANISTER_1));
+    snapshots.push(id_2, snapshot_of(CANISTER_2));
+    snapshots.push(id_3, snapshot_of(CANISTER_1));
+
+    let listed: Vec<_> = snapshots
+        .list_snapshots(CANISTER_1)
+        .map(|(id, _)| *id)
+        .collect();
+    assert_eq!(listed, vec![id_1, id_3]);
+    assert_eq!(snapshots.count_snapshots(CANISTER_2), 1);
+    assert_eq!(
+        snapshots.memory_taken_by_canister(CANISTER_1),
+        snapshots.get(&id_1).unwrap().size() + snapshots.get(&id_3).unwrap().size()
+    );
+
+    snapshots.delete_snapshots(CANISTER_1);
+    assert_eq!(snapshots.count_snapshots(CANISTER_1), 0);
+    assert_eq!(snapshots.snapshot_ids(), BTreeSet::from([id_2]));
+
+    assert!(snapshots.remove(&id_2).is_some());
+    assert!(snapshots.is_empty());
+}
@@ -383,10 +383,14 @@ impl CanisterState {
 
     /// The amount of memory currently being used by the canister.
     ///
-    /// This only includes execution memory (heap, stable, globals, Wasm)
-    /// and canister history memory.
+    /// This only includes execution memory (heap, stable, globals, Wasm),
+    /// canister history memory, the memory used by canister snapshots and
+    /// the Wasm chunk store.
     pub fn memory_usage(&self) -> NumBytes {
-        self.raw_memory_usage() + self.canister_history_memory_usage()
+        self.raw_memory_usage()
+            + self.canister_history_memory_usage()
+            + self.snapshots_memory_usage()
+            + self.wasm_chunk_store_memory_usage()
     }
 
     /// Returns the amount of raw memory currently used by the canister in bytes.
@@ -416,6 +420,17 @@ impl CanisterState {
         self.system_state.canister_history_memory_usage()
     }
 
+    /// Returns the amount of memory used by the canister's snapshots in bytes.
+    pub fn snapshots_memory_usage(&self) -> NumBytes {
+        self.system_state.snapshots_memory_usage()
+    }
+
+    /// Returns the amount of memory used by the canister's Wasm chunk store in
+    /// bytes.
+    pub fn wasm_chunk_store_memory_usage(&self) -> NumBytes {
+        self.system_state.wasm_chunk_store_memory_usage()
+    }
+
     /// Sets the (transient) size in bytes of responses from this canister
     /// routed into streams and not yet garbage collected.
     pub(super) fn set_stream_responses_size_bytes(&mut self, size_bytes: usize) {
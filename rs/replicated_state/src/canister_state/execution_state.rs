@@ -7,6 +7,7 @@ use ic_protobuf::{
 };
 use ic_sys::PAGE_SIZE;
 use ic_types::{
+    instruction_profile::InstructionProfile,
     methods::{SystemMethod, WasmMethod},
     CountBytes, ExecutionRound, NumBytes,
 };
@@ -456,6 +457,11 @@ pub struct ExecutionState {
 
     /// Round-robin across canister method types.
     pub next_scheduled_method: NextScheduledMethod,
+
+    /// Instructions executed by the functions of the Wasm module, recorded
+    /// only if canister profiling is enabled. The profile is a development
+    /// aid: it is neither persisted in checkpoints nor compared for equality.
+    pub instruction_profile: InstructionProfile,
 }
 
 // We have to implement it by hand as embedder_cache can not be compared for
@@ -476,6 +482,7 @@ impl PartialEq for ExecutionState {
             ref metadata,
             ref last_executed_round,
             ref next_scheduled_method,
+            instruction_profile: _,
         } = rhs;
 
         (
@@ -524,6 +531,7 @@ impl ExecutionState {
             metadata: wasm_metadata,
             last_executed_round: ExecutionRound::from(0),
             next_scheduled_method: NextScheduledMethod::default(),
+            instruction_profile: InstructionProfile::default(),
         }
     }
 
@@ -583,8 +583,14 @@ impl CanisterQueues {
         let oq_stats_delta =
             OutputQueuesStats::stats_delta(&RequestOrResponse::Request(msg.clone()));
 
+        // Best-effort requests time out at their own deadline.
+        let deadline = if msg.is_best_effort() {
+            msg.deadline.into()
+        } else {
+            time + REQUEST_LIFETIME
+        };
         output_queue
-            .push_request(msg, time + REQUEST_LIFETIME)
+            .push_request(msg, deadline)
             .expect("cannot fail due to the checks above");
 
         self.input_queues_stats.reserved_slots += 1;
@@ -628,6 +634,7 @@ impl CanisterQueues {
             originator_reply_callback: request.sender_reply_callback,
             refund: request.payment,
             response_payload: Payload::Reject(reject_context),
+            deadline: request.deadline,
         }));
         self.push_input(response, InputQueueType::LocalSubnet)
             .map_err(|(e, _msg)| e)
@@ -1007,6 +1014,11 @@ impl CanisterQueues {
     /// Times out requests in `OutputQueues` given a current time, enqueuing a reject response
     /// for each into the matching `InputQueue`.
     ///
+    /// Best-effort requests time out at their deadline and are dropped without a
+    /// reject response: by then their callbacks have already expired (see
+    /// `ReplicatedState::time_out_callbacks`) and the `SYS_UNKNOWN` reject enqueued
+    /// at that point used up the reserved slot.
+    ///
     /// Updating the correct input queues schedule after enqueuing a reject response into a
     /// previously empty queue also requires the full set of local canisters to decide whether
     /// the destination canister was local or remote.
@@ -1021,12 +1033,19 @@ impl CanisterQueues {
         let mut timed_out_requests_count = 0;
         for (canister_id, (input_queue, output_queue)) in self.canister_queues.iter_mut() {
             for request in output_queue.time_out_requests(current_time) {
+                let is_best_effort = request.is_best_effort();
                 let response = generate_timeout_response(&request);
 
                 // Request was dropped, update stats.
                 let request = RequestOrResponse::Request(request);
                 self.memory_usage_stats -= MemoryUsageStats::stats_delta(QueueOp::Pop, &request);
                 self.output_queues_stats -= OutputQueuesStats::stats_delta(&request);
+                timed_out_requests_count += 1;
+
+                if is_best_effort {
+                    // The callback has already expired and received its reject.
+                    continue;
+                }
 
                 // Push response, update stats.
                 let iq_stats_delta = InputQueuesStats::stats_delta(QueueOp::Push, &response);
@@ -1043,8 +1062,6 @@ impl CanisterQueues {
                         self.remote_subnet_input_schedule.push_back(*canister_id);
                     }
                 }
-
-                timed_out_requests_count += 1;
             }
         }
 
@@ -1110,6 +1127,7 @@ fn generate_timeout_response(request: &Arc<Request>) -> RequestOrResponse {
             "Request timed out.".to_string(),
             MR_SYNTHETIC_REJECT_MESSAGE_MAX_LEN,
         )),
+        deadline: request.deadline,
     }))
 }
 
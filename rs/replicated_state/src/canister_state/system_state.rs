@@ -1,12 +1,15 @@
 mod call_context_manager;
+pub mod wasm_chunk_store;
 
 use super::queues::can_push;
 pub use super::queues::memory_required_to_push_request;
 pub use crate::canister_state::queues::CanisterOutputQueuesIterator;
+use crate::replicated_state::MR_SYNTHETIC_REJECT_MESSAGE_MAX_LEN;
 use crate::{CanisterQueues, CanisterState, InputQueueType, StateError};
 pub use call_context_manager::{CallContext, CallContextAction, CallContextManager, CallOrigin};
 use ic_base_types::NumSeconds;
-use ic_ic00_types::{CanisterChange, CanisterChangeDetails, CanisterChangeOrigin};
+use ic_error_types::RejectCode;
+use ic_ic00_types::{CanisterChange, CanisterChangeDetails, CanisterChangeOrigin, LogVisibility};
 use ic_interfaces::messages::{CanisterCall, CanisterMessage, CanisterMessageOrTask, CanisterTask};
 use ic_logger::{error, ReplicaLogger};
 use ic_protobuf::{
@@ -15,7 +18,11 @@ use ic_protobuf::{
 };
 use ic_registry_subnet_type::SubnetType;
 use ic_types::{
-    messages::{Ingress, RejectContext, Request, RequestOrResponse, Response, StopCanisterContext},
+    canister_log::CanisterLog,
+    messages::{
+        Ingress, Payload, RejectContext, Request, RequestOrResponse, Response, StopCanisterContext,
+        NO_DEADLINE,
+    },
     nominal_cycles::NominalCycles,
     CanisterId, CanisterTimer, Cycles, MemoryAllocation, NumBytes, PrincipalId, Time,
 };
@@ -28,6 +35,7 @@ use std::{
 };
 use std::{collections::BTreeSet, sync::Arc};
 use std::{collections::VecDeque, str::FromStr};
+use wasm_chunk_store::WasmChunkStore;
 
 lazy_static! {
     static ref DEFAULT_PRINCIPAL_MULTIPLE_CONTROLLERS: PrincipalId =
@@ -53,6 +61,7 @@ pub enum CyclesUseCase {
     HTTPOutcalls,
     DeletedCanisters,
     NonConsumed,
+    SchnorrOutcalls,
 }
 
 impl CyclesUseCase {
@@ -71,6 +80,7 @@ impl CyclesUseCase {
             Self::HTTPOutcalls => "HTTPOutcalls",
             Self::DeletedCanisters => "DeletedCanisters",
             Self::NonConsumed => "NonConsumed",
+            Self::SchnorrOutcalls => "SchnorrOutcalls",
         }
     }
 }
@@ -89,6 +99,7 @@ impl From<CyclesUseCase> for i32 {
             CyclesUseCase::HTTPOutcalls => 9,
             CyclesUseCase::DeletedCanisters => 10,
             CyclesUseCase::NonConsumed => 11,
+            CyclesUseCase::SchnorrOutcalls => 12,
         }
     }
 }
@@ -107,6 +118,7 @@ impl From<i32> for CyclesUseCase {
             9 => Self::HTTPOutcalls,
             10 => Self::DeletedCanisters,
             11 => Self::NonConsumed,
+            12 => Self::SchnorrOutcalls,
             _ => panic!("Unsupported value"),
         }
     }
@@ -127,16 +139,21 @@ pub struct CanisterMetrics {
     pub skipped_round_due_to_no_messages: u64,
     pub executed: u64,
     pub interruped_during_execution: u64,
+    pub skipped_round_due_to_limits: u64,
+    pub slices_executed: u64,
     pub consumed_cycles_since_replica_started: NominalCycles,
     consumed_cycles_since_replica_started_by_use_cases: BTreeMap<CyclesUseCase, NominalCycles>,
 }
 
 impl CanisterMetrics {
+    #[allow(clippy::too_many_arguments)]
     pub fn new(
         scheduled_as_first: u64,
         skipped_round_due_to_no_messages: u64,
         executed: u64,
         interruped_during_execution: u64,
+        skipped_round_due_to_limits: u64,
+        slices_executed: u64,
         consumed_cycles_since_replica_started: NominalCycles,
         consumed_cycles_since_replica_started_by_use_cases: BTreeMap<CyclesUseCase, NominalCycles>,
     ) -> Self {
@@ -145,6 +162,8 @@ impl CanisterMetrics {
             skipped_round_due_to_no_messages,
             executed,
             interruped_during_execution,
+            skipped_round_due_to_limits,
+            slices_executed,
             consumed_cycles_since_replica_started,
             consumed_cycles_since_replica_started_by_use_cases,
         }
@@ -307,6 +326,27 @@ pub struct SystemState {
 
     /// Canister history.
     canister_history: CanisterHistory,
+
+    /// Memory used by the snapshots of this canister. Snapshots are stored in
+    /// `ReplicatedState::canister_snapshots`; this is a cached total so that
+    /// snapshots are accounted for (and charged for) as canister memory.
+    snapshots_memory_usage: NumBytes,
+
+    /// Who is allowed to fetch the canister log.
+    pub log_visibility: LogVisibility,
+
+    /// Log records emitted by the canister through `ic0.debug_print` and
+    /// trap messages.
+    pub canister_log: CanisterLog,
+
+    /// Chunks uploaded via `upload_chunk`, from which `install_chunked_code`
+    /// assembles Wasm modules.
+    pub wasm_chunk_store: WasmChunkStore,
+
+    /// Upper limit on the Wasm memory of the canister. Executions that grow
+    /// the Wasm memory beyond this limit fail. `None` means no limit beyond
+    /// the maximum Wasm memory size.
+    pub wasm_memory_limit: Option<NumBytes>,
 }
 
 /// A wrapper around the different canister statuses.
@@ -684,6 +724,11 @@ impl SystemState {
             global_timer: CanisterTimer::Inactive,
             canister_version: 0,
             canister_history: CanisterHistory::default(),
+            snapshots_memory_usage: NumBytes::from(0),
+            log_visibility: LogVisibility::default(),
+            canister_log: CanisterLog::default(),
+            wasm_chunk_store: WasmChunkStore::default(),
+            wasm_memory_limit: None,
         }
     }
 
@@ -719,6 +764,10 @@ impl SystemState {
         global_timer: CanisterTimer,
         canister_version: u64,
         canister_history: CanisterHistory,
+        log_visibility: LogVisibility,
+        canister_log: CanisterLog,
+        wasm_chunk_store: WasmChunkStore,
+        wasm_memory_limit: Option<NumBytes>,
     ) -> Self {
         Self {
             controllers,
@@ -735,6 +784,11 @@ impl SystemState {
             global_timer,
             canister_version,
             canister_history,
+            snapshots_memory_usage: NumBytes::from(0),
+            log_visibility,
+            canister_log,
+            wasm_chunk_store,
+            wasm_memory_limit,
         }
     }
 
@@ -885,8 +939,19 @@ impl SystemState {
             "Expected `Request` to have been sent from canister ID {}, but instead got {}",
             self.canister_id, request.sender
         );
+        let best_effort_callback = request
+            .is_best_effort()
+            .then_some(request.sender_reply_callback);
         self.queues
-            .reject_subnet_output_request(request, reject_context, subnet_ids)
+            .reject_subnet_output_request(request, reject_context, subnet_ids)?;
+        // Same as in `push_input()`: a best-effort callback no longer expires
+        // once a response for it was enqueued.
+        if let Some(callback_id) = best_effort_callback {
+            if let Some(call_context_manager) = self.call_context_manager_mut() {
+                call_context_manager.on_response_enqueued(callback_id);
+            }
+        }
+        Ok(())
     }
 
     /// Returns the number of output requests that can be pushed onto the queue
@@ -953,6 +1018,9 @@ impl SystemState {
     ///  * `CanisterStopped` if the canister is stopped.
     ///  * `NonMatchingResponse` if the callback is not found or the respondent
     ///    does not match.
+    ///
+    /// Best-effort responses whose callback is no longer awaiting a response
+    /// (because it expired or was already responded to) are silently dropped.
     pub(crate) fn push_input(
         &mut self,
         msg: RequestOrResponse,
@@ -968,7 +1036,7 @@ impl SystemState {
             msg.receiver()
         );
 
-        match (&msg, &self.status) {
+        match (&msg, &mut self.status) {
             // Requests and responses are both rejected when stopped.
             (_, CanisterStatus::Stopped { .. }) => {
                 Err((StateError::CanisterStopped(self.canister_id()), msg))
@@ -993,6 +1061,27 @@ impl SystemState {
                     ..
                 },
             ) => {
+                // Until all subnets encode deadlines in streams, a response to a
+                // best-effort call may arrive without its deadline, so the
+                // deadline of the callback is checked as well.
+                let best_effort_callback = match &msg {
+                    RequestOrResponse::Response(response)
+                        if response.is_best_effort()
+                            || call_context_manager
+                                .peek_callback(response.originator_reply_callback)
+                                .map_or(false, |callback| callback.deadline != NO_DEADLINE) =>
+                    {
+                        Some(response.originator_reply_callback)
+                    }
+                    _ => None,
+                };
+                if let Some(callback_id) = best_effort_callback {
+                    if !call_context_manager.is_awaiting_response(callback_id) {
+                        // Late response for an expired (or otherwise already
+                        // responded to) callback, drop it.
+                        return Ok(());
+                    }
+                }
                 if let RequestOrResponse::Response(response) = &msg {
                     call_context_manager
                         .validate_response(response)
@@ -1004,7 +1093,11 @@ impl SystemState {
                     subnet_available_memory,
                     own_subnet_type,
                     input_queue_type,
-                )
+                )?;
+                if let Some(callback_id) = best_effort_callback {
+                    call_context_manager.on_response_enqueued(callback_id);
+                }
+                Ok(())
             }
         }
     }
@@ -1083,6 +1176,22 @@ impl SystemState {
         self.canister_history.get_memory_usage()
     }
 
+    /// Returns the memory currently used by the Wasm chunk store.
+    pub fn wasm_chunk_store_memory_usage(&self) -> NumBytes {
+        self.wasm_chunk_store.memory_usage()
+    }
+
+    /// Returns the memory currently used by the snapshots of this canister.
+    pub fn snapshots_memory_usage(&self) -> NumBytes {
+        self.snapshots_memory_usage
+    }
+
+    /// Sets the memory used by the snapshots of this canister. Must be kept in
+    /// sync with `ReplicatedState::canister_snapshots`.
+    pub fn set_snapshots_memory_usage(&mut self, memory_usage: NumBytes) {
+        self.snapshots_memory_usage = memory_usage;
+    }
+
     /// Sets the (transient) size in bytes of responses from this canister
     /// routed into streams and not yet garbage collected.
     pub(super) fn set_stream_responses_size_bytes(&mut self, size_bytes: usize) {
@@ -1180,6 +1289,75 @@ impl SystemState {
             .time_out_requests(current_time, own_canister_id, local_canisters)
     }
 
+    /// Queries whether any best-effort callbacks have expired at `current_time`.
+    pub fn has_expired_callbacks(&self, current_time: Time) -> bool {
+        self.call_context_manager()
+            .map_or(false, |ccm| ccm.has_expired_callbacks(current_time))
+    }
+
+    /// Times out all best-effort callbacks whose deadline was reached at
+    /// `current_time`, enqueuing a `SYS_UNKNOWN` reject response for each into
+    /// the matching input queue (using the slot reserved for the response).
+    /// Any response arriving later for one of these callbacks is dropped.
+    ///
+    /// Returns the number of callbacks that were timed out, together with the
+    /// errors encountered when enqueuing the reject responses. Every
+    /// outstanding callback holds a reserved slot for its response, so any
+    /// such error is a bug.
+    pub fn time_out_callbacks(
+        &mut self,
+        current_time: Time,
+        own_canister_id: &CanisterId,
+        local_canisters: &BTreeMap<CanisterId, CanisterState>,
+    ) -> (u64, Vec<StateError>) {
+        let call_context_manager = match &mut self.status {
+            CanisterStatus::Running {
+                call_context_manager,
+            }
+            | CanisterStatus::Stopping {
+                call_context_manager,
+                ..
+            } => call_context_manager,
+            CanisterStatus::Stopped => return (0, Vec::new()),
+        };
+
+        let mut timed_out_callbacks_count = 0;
+        let mut errors = Vec::new();
+        for callback_id in call_context_manager.expire_callbacks(current_time) {
+            let callback = match call_context_manager.peek_callback(callback_id) {
+                Some(callback) => callback,
+                None => continue,
+            };
+            let respondent = match callback.respondent {
+                Some(respondent) => respondent,
+                None => continue,
+            };
+            let response = RequestOrResponse::Response(Arc::new(Response {
+                originator: self.canister_id,
+                respondent,
+                originator_reply_callback: callback_id,
+                refund: Cycles::zero(),
+                response_payload: Payload::Reject(RejectContext::new_with_message_length_limit(
+                    RejectCode::SysUnknown,
+                    "Request timed out.".to_string(),
+                    MR_SYNTHETIC_REJECT_MESSAGE_MAX_LEN,
+                )),
+                deadline: callback.deadline,
+            }));
+            let input_queue_type =
+                if respondent == *own_canister_id || local_canisters.contains_key(&respondent) {
+                    InputQueueType::LocalSubnet
+                } else {
+                    InputQueueType::RemoteSubnet
+                };
+            match self.queues.push_input(response, input_queue_type) {
+                Ok(()) => timed_out_callbacks_count += 1,
+                Err((err, _)) => errors.push(err),
+            }
+        }
+        (timed_out_callbacks_count, errors)
+    }
+
     /// Re-partitions the local and remote input schedules of `self.queues`
     /// following a canister migration, based on the updated set of local canisters.
     ///
@@ -1230,9 +1408,10 @@ impl SystemState {
         use_case: CyclesUseCase,
         consuming_cycles: ConsumingCycles,
     ) {
-        // The three CyclesUseCase below are not valid on the canister
+        // The four CyclesUseCase below are not valid on the canister
         // level, they should only appear on the subnet level.
         debug_assert_ne!(use_case, CyclesUseCase::ECDSAOutcalls);
+        debug_assert_ne!(use_case, CyclesUseCase::SchnorrOutcalls);
         debug_assert_ne!(use_case, CyclesUseCase::HTTPOutcalls);
         debug_assert_ne!(use_case, CyclesUseCase::DeletedCanisters);
 
@@ -11,12 +11,13 @@ use ic_types::messages::Response;
 use ic_types::Time;
 use ic_types::{
     ingress::WasmResult,
-    messages::{CallContextId, CallbackId, MessageId},
+    messages::{CallContextId, CallbackId, MessageId, NO_DEADLINE},
     methods::Callback,
+    time::CoarseTime,
     user_id_into_protobuf, user_id_try_from_protobuf, CanisterId, Cycles, Funds, UserId,
 };
 use serde::{Deserialize, Serialize};
-use std::collections::BTreeMap;
+use std::collections::{BTreeMap, BTreeSet};
 use std::convert::{From, TryFrom, TryInto};
 use std::time::Duration;
 
@@ -213,12 +214,20 @@ pub struct CallContextManager {
     /// Maps call context to its responded status.
     call_contexts: BTreeMap<CallContextId, CallContext>,
     callbacks: BTreeMap<CallbackId, Callback>,
+    /// Best-effort callbacks still awaiting a response, ordered by deadline.
+    ///
+    /// A callback is removed from this index as soon as a response for it is
+    /// enqueued or it expires (and a `SYS_UNKNOWN` reject is enqueued in its
+    /// place), ensuring that at most one response is delivered per callback.
+    unexpired_callbacks: BTreeSet<(CoarseTime, CallbackId)>,
 }
 
 #[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
 pub enum CallOrigin {
     Ingress(UserId, MessageId),
-    CanisterUpdate(CanisterId, CallbackId),
+    /// A call from another canister, with the deadline of the request
+    /// (`NO_DEADLINE` for guaranteed response calls).
+    CanisterUpdate(CanisterId, CallbackId, CoarseTime),
     Query(UserId),
     CanisterQuery(CanisterId, CallbackId),
     /// System task is either a `Heartbeat` or a `GlobalTimer`.
@@ -232,10 +241,11 @@ impl From<&CallOrigin> for pb::call_context::CallOrigin {
                 user_id: Some(user_id_into_protobuf(*user_id)),
                 message_id: message_id.as_bytes().to_vec(),
             }),
-            CallOrigin::CanisterUpdate(canister_id, callback_id) => {
+            CallOrigin::CanisterUpdate(canister_id, callback_id, deadline) => {
                 Self::CanisterUpdate(pb::call_context::CanisterUpdateOrQuery {
                     canister_id: Some(pb_types::CanisterId::from(*canister_id)),
                     callback_id: callback_id.get(),
+                    deadline_seconds: deadline.as_secs_since_unix_epoch(),
                 })
             }
             CallOrigin::Query(user_id) => Self::Query(user_id_into_protobuf(*user_id)),
@@ -243,6 +253,7 @@ impl From<&CallOrigin> for pb::call_context::CallOrigin {
                 Self::CanisterQuery(pb::call_context::CanisterUpdateOrQuery {
                     canister_id: Some(pb_types::CanisterId::from(*canister_id)),
                     callback_id: callback_id.get(),
+                    deadline_seconds: NO_DEADLINE.as_secs_since_unix_epoch(),
                 })
             }
             CallOrigin::SystemTask => Self::SystemTask(pb::call_context::SystemTask {}),
@@ -268,10 +279,12 @@ impl TryFrom<pb::call_context::CallOrigin> for CallOrigin {
                 pb::call_context::CanisterUpdateOrQuery {
                     canister_id,
                     callback_id,
+                    deadline_seconds,
                 },
             ) => Self::CanisterUpdate(
                 try_from_option_field(canister_id, "CallOrigin::CanisterUpdate::canister_id")?,
                 callback_id.into(),
+                CoarseTime::from_secs_since_unix_epoch(deadline_seconds),
             ),
             pb::call_context::CallOrigin::Query(user_id) => {
                 Self::Query(user_id_try_from_protobuf(user_id)?)
@@ -280,6 +293,7 @@ impl TryFrom<pb::call_context::CallOrigin> for CallOrigin {
                 pb::call_context::CanisterUpdateOrQuery {
                     canister_id,
                     callback_id,
+                    ..
                 },
             ) => Self::CanisterQuery(
                 try_from_option_field(canister_id, "CallOrigin::CanisterQuery::canister_id")?,
@@ -491,6 +505,10 @@ impl CallContextManager {
     pub fn register_callback(&mut self, callback: Callback) -> CallbackId {
         self.next_callback_id += 1;
         let callback_id = CallbackId::from(self.next_callback_id);
+        if callback.deadline != NO_DEADLINE {
+            self.unexpired_callbacks
+                .insert((callback.deadline, callback_id));
+        }
         self.callbacks.insert(callback_id, callback);
         callback_id
     }
@@ -503,7 +521,52 @@ impl CallContextManager {
     /// If we get a response for one of the outstanding calls, we unregister
     /// the callback and return it.
     pub fn unregister_callback(&mut self, callback_id: CallbackId) -> Option<Callback> {
-        self.callbacks.remove(&callback_id)
+        let callback = self.callbacks.remove(&callback_id)?;
+        self.unexpired_callbacks
+            .remove(&(callback.deadline, callback_id));
+        Some(callback)
+    }
+
+    /// Returns `true` if the given best-effort callback is still awaiting a
+    /// response, i.e. it has neither expired nor had a response enqueued.
+    pub fn is_awaiting_response(&self, callback_id: CallbackId) -> bool {
+        match self.callbacks.get(&callback_id) {
+            Some(callback) => self
+                .unexpired_callbacks
+                .contains(&(callback.deadline, callback_id)),
+            None => false,
+        }
+    }
+
+    /// Records that a response for the given best-effort callback was
+    /// enqueued, so the callback no longer expires.
+    pub(crate) fn on_response_enqueued(&mut self, callback_id: CallbackId) {
+        if let Some(callback) = self.callbacks.get(&callback_id) {
+            self.unexpired_callbacks
+                .remove(&(callback.deadline, callback_id));
+        }
+    }
+
+    /// Removes and returns the IDs of all best-effort callbacks whose deadline
+    /// was reached at `current_time` and that are still awaiting a response.
+    pub(crate) fn expire_callbacks(&mut self, current_time: Time) -> Vec<CallbackId> {
+        let mut expired_callbacks = Vec::new();
+        while let Some(&(deadline, callback_id)) = self.unexpired_callbacks.iter().next() {
+            if Time::from(deadline) > current_time {
+                break;
+            }
+            self.unexpired_callbacks.remove(&(deadline, callback_id));
+            expired_callbacks.push(callback_id);
+        }
+        expired_callbacks
+    }
+
+    /// Returns `true` if any best-effort callback has expired at `current_time`.
+    pub(crate) fn has_expired_callbacks(&self, current_time: Time) -> bool {
+        match self.unexpired_callbacks.iter().next() {
+            Some((deadline, _)) => Time::from(*deadline) <= current_time,
+            None => false,
+        }
     }
 
     /// Returns the call origin, which is either the message id of the ingress
@@ -566,9 +629,11 @@ impl CallContextManager {
 impl From<&CanisterCall> for CallOrigin {
     fn from(msg: &CanisterCall) -> Self {
         match msg {
-            CanisterCall::Request(request) => {
-                CallOrigin::CanisterUpdate(request.sender, request.sender_reply_callback)
-            }
+            CanisterCall::Request(request) => CallOrigin::CanisterUpdate(
+                request.sender,
+                request.sender_reply_callback,
+                request.deadline,
+            ),
             CanisterCall::Ingress(ingress) => {
                 CallOrigin::Ingress(ingress.source, ingress.message_id.clone())
             }
@@ -606,6 +671,11 @@ impl From<&CallContextManager> for pb::CallContextManager {
                     callback: Some(callback.into()),
                 })
                 .collect(),
+            unexpired_callbacks: item
+                .unexpired_callbacks
+                .iter()
+                .map(|(_, callback_id)| callback_id.get())
+                .collect(),
         }
     }
 }
@@ -635,12 +705,23 @@ impl TryFrom<pb::CallContextManager> for CallContextManager {
                 try_from_option_field(callback, "CallContextManager::callbacks::V")?,
             );
         }
+        let mut unexpired_callbacks = BTreeSet::new();
+        for callback_id in value.unexpired_callbacks.into_iter().map(CallbackId::from) {
+            let callback = callbacks.get(&callback_id).ok_or_else(|| {
+                ProxyDecodeError::Other(format!(
+                    "CallContextManager: unknown unexpired callback {}",
+                    callback_id
+                ))
+            })?;
+            unexpired_callbacks.insert((callback.deadline, callback_id));
+        }
 
         Ok(Self {
             next_call_context_id: value.next_call_context_id,
             next_callback_id: value.next_callback_id,
             call_contexts,
             callbacks,
+            unexpired_callbacks,
         })
     }
 }
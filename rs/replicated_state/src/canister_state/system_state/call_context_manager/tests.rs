@@ -8,13 +8,13 @@ fn call_context_origin() {
     let id = canister_test_id(42);
     let cb_id = CallbackId::from(1);
     let cc_id = ccm.new_call_context(
-        CallOrigin::CanisterUpdate(id, cb_id),
+        CallOrigin::CanisterUpdate(id, cb_id, NO_DEADLINE),
         Cycles::new(10),
         Time::from_nanos_since_unix_epoch(0),
     );
     assert_eq!(
         ccm.call_contexts().get(&cc_id).unwrap().call_origin,
-        CallOrigin::CanisterUpdate(id, cb_id)
+        CallOrigin::CanisterUpdate(id, cb_id, NO_DEADLINE)
     );
 }
 
@@ -26,18 +26,18 @@ fn call_context_handling() {
 
     // On two incoming calls
     let call_context_id1 = call_context_manager.new_call_context(
-        CallOrigin::CanisterUpdate(canister_test_id(123), CallbackId::from(1)),
+        CallOrigin::CanisterUpdate(canister_test_id(123), CallbackId::from(1), NO_DEADLINE),
         Cycles::zero(),
         Time::from_nanos_since_unix_epoch(0),
     );
     let call_context_id2 = call_context_manager.new_call_context(
-        CallOrigin::CanisterUpdate(canister_test_id(123), CallbackId::from(2)),
+        CallOrigin::CanisterUpdate(canister_test_id(123), CallbackId::from(2), NO_DEADLINE),
         Cycles::zero(),
         Time::from_nanos_since_unix_epoch(0),
     );
 
     let call_context_id3 = call_context_manager.new_call_context(
-        CallOrigin::CanisterUpdate(canister_test_id(123), CallbackId::from(3)),
+        CallOrigin::CanisterUpdate(canister_test_id(123), CallbackId::from(3), NO_DEADLINE),
         Cycles::zero(),
         Time::from_nanos_since_unix_epoch(0),
     );
@@ -78,6 +78,7 @@ fn call_context_handling() {
         WasmClosure::new(0, 1),
         WasmClosure::new(2, 3),
         None,
+        NO_DEADLINE,
     ));
     let callback_id2 = call_context_manager.register_callback(Callback::new(
         call_context_id1,
@@ -89,6 +90,7 @@ fn call_context_handling() {
         WasmClosure::new(4, 5),
         WasmClosure::new(6, 7),
         None,
+        NO_DEADLINE,
     ));
 
     // There are 2 ougoing calls
@@ -105,6 +107,7 @@ fn call_context_handling() {
         WasmClosure::new(8, 9),
         WasmClosure::new(10, 11),
         None,
+        NO_DEADLINE,
     ));
     // There is 1 outgoing call
     assert_eq!(call_context_manager.outstanding_calls(call_context_id2), 1);
@@ -254,7 +257,7 @@ fn withdraw_cycles_fails_when_not_enough_available_cycles() {
     let id = canister_test_id(42);
     let cb_id = CallbackId::from(1);
     let cc_id = ccm.new_call_context(
-        CallOrigin::CanisterUpdate(id, cb_id),
+        CallOrigin::CanisterUpdate(id, cb_id, NO_DEADLINE),
         Cycles::new(30),
         Time::from_nanos_since_unix_epoch(0),
     );
@@ -276,7 +279,7 @@ fn withdraw_cycles_succeeds_when_enough_available_cycles() {
     let id = canister_test_id(42);
     let cb_id = CallbackId::from(1);
     let cc_id = ccm.new_call_context(
-        CallOrigin::CanisterUpdate(id, cb_id),
+        CallOrigin::CanisterUpdate(id, cb_id, NO_DEADLINE),
         Cycles::new(30),
         Time::from_nanos_since_unix_epoch(0),
     );
@@ -288,3 +291,83 @@ fn withdraw_cycles_succeeds_when_enough_available_cycles() {
         Ok(())
     );
 }
+
+fn best_effort_callback(call_context_id: CallContextId, deadline_secs: u32) -> Callback {
+    Callback::new(
+        call_context_id,
+        Some(canister_test_id(1)),
+        Some(canister_test_id(2)),
+        Cycles::zero(),
+        None,
+        None,
+        WasmClosure::new(0, 1),
+        WasmClosure::new(2, 3),
+        None,
+        CoarseTime::from_secs_since_unix_epoch(deadline_secs),
+    )
+}
+
+#[test]
+fn expire_callbacks_in_deadline_order() {
+    let mut ccm = CallContextManager::default();
+    let cc_id = ccm.new_call_context(
+        CallOrigin::CanisterUpdate(canister_test_id(42), CallbackId::from(1), NO_DEADLINE),
+        Cycles::zero(),
+        Time::from_nanos_since_unix_epoch(0),
+    );
+    let cb_id1 = ccm.register_callback(best_effort_callback(cc_id, 20));
+    let cb_id2 = ccm.register_callback(best_effort_callback(cc_id, 10));
+    let cb_id3 = ccm.register_callback(best_effort_callback(cc_id, 30));
+
+    let time = |secs| Time::from_secs_since_unix_epoch(secs).unwrap();
+    assert!(!ccm.has_expired_callbacks(time(9)));
+    assert!(ccm.expire_callbacks(time(9)).is_empty());
+
+    assert!(ccm.has_expired_callbacks(time(20)));
+    assert_eq!(vec![cb_id2, cb_id1], ccm.expire_callbacks(time(20)));
+    assert!(!ccm.is_awaiting_response(cb_id1));
+    assert!(!ccm.is_awaiting_response(cb_id2));
+    assert!(ccm.is_awaiting_response(cb_id3));
+
+    // Expired callbacks stick around until their reject response is executed.
+    assert_eq!(3, ccm.callbacks().len());
+    // But are not expired a second time.
+    assert_eq!(vec![cb_id3], ccm.expire_callbacks(time(30)));
+    assert!(ccm.expire_callbacks(time(40)).is_empty());
+}
+
+#[test]
+fn best_effort_callback_with_enqueued_response_does_not_expire() {
+    let mut ccm = CallContextManager::default();
+    let cc_id = ccm.new_call_context(
+        CallOrigin::CanisterUpdate(canister_test_id(42), CallbackId::from(1), NO_DEADLINE),
+        Cycles::zero(),
+        Time::from_nanos_since_unix_epoch(0),
+    );
+    let cb_id = ccm.register_callback(best_effort_callback(cc_id, 10));
+    assert!(ccm.is_awaiting_response(cb_id));
+
+    ccm.on_response_enqueued(cb_id);
+    assert!(!ccm.is_awaiting_response(cb_id));
+    assert!(ccm
+        .expire_callbacks(Time::from_secs_since_unix_epoch(10).unwrap())
+        .is_empty());
+}
+
+#[test]
+fn unexpired_callbacks_roundtrip_through_proto() {
+    let mut ccm = CallContextManager::default();
+    let cc_id = ccm.new_call_context(
+        CallOrigin::CanisterUpdate(canister_test_id(42), CallbackId::from(1), NO_DEADLINE),
+        Cycles::zero(),
+        Time::from_nanos_since_unix_epoch(0),
+    );
+    ccm.register_callback(best_effort_callback(cc_id, 10));
+    let cb_id = ccm.register_callback(best_effort_callback(cc_id, 20));
+    ccm.expire_callbacks(Time::from_secs_since_unix_epoch(10).unwrap());
+
+    let pb_ccm: pb::CallContextManager = (&ccm).into();
+    assert_eq!(vec![cb_id.get()], pb_ccm.unexpired_callbacks);
+    let round_trip = CallContextManager::try_from(pb_ccm).unwrap();
+    assert_eq!(ccm, round_trip);
+}
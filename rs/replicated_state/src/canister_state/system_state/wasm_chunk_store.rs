@@ -0,0 +1,212 @@
+#[cfg(test)]
+mod tests;
+
+use crate::page_map::{Buffer, PageAllocatorFileDescriptor, PageMap};
+use ic_protobuf::{proxy::ProxyDecodeError, state::canister_state_bits::v1 as pb};
+use ic_types::NumBytes;
+use std::{collections::BTreeMap, convert::TryFrom, sync::Arc};
+
+/// The maximum size of a single chunk. Each chunk occupies a region of this
+/// size in the chunk store, regardless of its actual length.
+pub const CHUNK_SIZE: u64 = 1024 * 1024;
+
+/// The default maximum size of a canister's chunk store, i.e. 100 chunks.
+pub const DEFAULT_MAX_SIZE: NumBytes = NumBytes::new(100 * CHUNK_SIZE);
+
+/// The SHA-256 hash of a chunk, which is used as its key in the chunk store.
+pub type WasmChunkHash = [u8; 32];
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+struct ChunkInfo {
+    /// The index of the chunk's region in the `PageMap`.
+    index: u64,
+    /// The length of the chunk in bytes.
+    length: u64,
+}
+
+/// Keeps track of the chunks held by a `WasmChunkStore`.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct WasmChunkStoreMetadata {
+    chunks: BTreeMap<WasmChunkHash, ChunkInfo>,
+}
+
+impl WasmChunkStoreMetadata {
+    pub fn is_empty(&self) -> bool {
+        self.chunks.is_empty()
+    }
+
+    /// The index of the region to be used by the next chunk.
+    fn next_index(&self) -> u64 {
+        self.chunks
+            .values()
+            .map(|info| info.index + 1)
+            .max()
+            .unwrap_or(0)
+    }
+}
+
+impl From<&WasmChunkStoreMetadata> for pb::WasmChunkStoreMetadata {
+    fn from(item: &WasmChunkStoreMetadata) -> Self {
+        Self {
+            chunks: item
+                .chunks
+                .iter()
+                .map(|(hash, info)| pb::WasmChunkData {
+                    hash: hash.to_vec(),
+                    index: info.index,
+                    length: info.length,
+                })
+                .collect(),
+        }
+    }
+}
+
+impl TryFrom<pb::WasmChunkStoreMetadata> for WasmChunkStoreMetadata {
+    type Error = ProxyDecodeError;
+
+    fn try_from(value: pb::WasmChunkStoreMetadata) -> Result<Self, Self::Error> {
+        let mut chunks = BTreeMap::new();
+        for chunk in value.chunks {
+            let hash = WasmChunkHash::try_from(chunk.hash.as_slice()).map_err(|_| {
+                ProxyDecodeError::Other(format!(
+                    "Invalid Wasm chunk hash of length {}",
+                    chunk.hash.len()
+                ))
+            })?;
+            if chunk.length > CHUNK_SIZE {
+                return Err(ProxyDecodeError::Other(format!(
+                    "Wasm chunk of length {} exceeds the maximum chunk size {}",
+                    chunk.length, CHUNK_SIZE
+                )));
+            }
+            chunks.insert(
+                hash,
+                ChunkInfo {
+                    index: chunk.index,
+                    length: chunk.length,
+                },
+            );
+        }
+        Ok(Self { chunks })
+    }
+}
+
+/// Stores the chunks uploaded via `upload_chunk`, from which a Wasm module can
+/// be assembled by `install_chunked_code`.
+///
+/// Chunks are addressed by their SHA-256 hash. Each chunk is stored in its own
+/// `CHUNK_SIZE` region of a `PageMap`, which is only allocated once the first
+/// chunk is inserted.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct WasmChunkStore {
+    data: Option<PageMap>,
+    metadata: WasmChunkStoreMetadata,
+}
+
+impl WasmChunkStore {
+    /// Creates a chunk store from its checkpointed parts. `data` must be
+    /// present if and only if the metadata lists any chunks.
+    pub fn new_from_checkpoint(data: Option<PageMap>, metadata: WasmChunkStoreMetadata) -> Self {
+        debug_assert_eq!(data.is_some(), !metadata.is_empty());
+        Self { data, metadata }
+    }
+
+    pub fn page_map(&self) -> Option<&PageMap> {
+        self.data.as_ref()
+    }
+
+    pub fn page_map_mut(&mut self) -> Option<&mut PageMap> {
+        self.data.as_mut()
+    }
+
+    pub fn metadata(&self) -> &WasmChunkStoreMetadata {
+        &self.metadata
+    }
+
+    /// Returns the memory charged for the stored chunks. Every chunk takes up
+    /// `CHUNK_SIZE` bytes, independent of its length.
+    pub fn memory_usage(&self) -> NumBytes {
+        NumBytes::new(self.metadata.chunks.len() as u64 * CHUNK_SIZE)
+    }
+
+    /// Returns the hashes of all stored chunks.
+    pub fn keys(&self) -> impl Iterator<Item = &WasmChunkHash> {
+        self.metadata.chunks.keys()
+    }
+
+    /// Returns whether the given chunk is already stored.
+    pub fn contains_chunk(&self, chunk: &[u8]) -> bool {
+        self.metadata
+            .chunks
+            .contains_key(&ic_crypto_sha::Sha256::hash(chunk))
+    }
+
+    /// Returns the contents of the chunk with the given hash, if it exists.
+    pub fn get_chunk_data(&self, hash: &WasmChunkHash) -> Option<Vec<u8>> {
+        let info = self.metadata.chunks.get(hash)?;
+        let page_map = self.data.as_ref()?;
+        let mut chunk = vec![0; info.length as usize];
+        Buffer::new(page_map.clone()).read(&mut chunk, (info.index * CHUNK_SIZE) as usize);
+        Some(chunk)
+    }
+
+    /// Checks whether the given chunk can be inserted without exceeding
+    /// `max_size`. Inserting an already stored chunk is always possible.
+    pub fn can_insert_chunk(&self, max_size: NumBytes, chunk: &[u8]) -> Result<(), String> {
+        if chunk.len() as u64 > CHUNK_SIZE {
+            return Err(format!(
+                "Wasm chunk size {} exceeds the maximum chunk size of {} bytes.",
+                chunk.len(),
+                CHUNK_SIZE
+            ));
+        }
+        if self.contains_chunk(chunk) {
+            return Ok(());
+        }
+        if self.memory_usage() + NumBytes::new(CHUNK_SIZE) > max_size {
+            return Err(format!(
+                "Wasm chunk store already contains the maximum of {} chunks.",
+                max_size.get() / CHUNK_SIZE
+            ));
+        }
+        Ok(())
+    }
+
+    /// Inserts the given chunk and returns its hash. Callers must check
+    /// `can_insert_chunk()` first.
+    pub fn insert_chunk(
+        &mut self,
+        chunk: &[u8],
+        fd_factory: Arc<dyn PageAllocatorFileDescriptor>,
+    ) -> WasmChunkHash {
+        assert!(chunk.len() as u64 <= CHUNK_SIZE);
+        let hash = ic_crypto_sha::Sha256::hash(chunk);
+        if self.metadata.chunks.contains_key(&hash) {
+            return hash;
+        }
+
+        let index = self.metadata.next_index();
+        let page_map = self.data.take().unwrap_or_else(|| PageMap::new(fd_factory));
+        let mut buffer = Buffer::new(page_map);
+        buffer.write(chunk, (index * CHUNK_SIZE) as usize);
+        self.data = Some(buffer.into_page_map());
+        self.metadata.chunks.insert(
+            hash,
+            ChunkInfo {
+                index,
+                length: chunk.len() as u64,
+            },
+        );
+        hash
+    }
+
+    /// Removes all chunks and releases the underlying `PageMap`.
+    pub fn clear(&mut self) {
+        self.data = None;
+        self.metadata = WasmChunkStoreMetadata::default();
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.metadata.is_empty()
+    }
+}
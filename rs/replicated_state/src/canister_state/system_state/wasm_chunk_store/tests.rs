@@ -0,0 +1,81 @@
+use super::*;
+use crate::page_map::TestPageAllocatorFileDescriptorImpl;
+
+fn fd_factory() -> Arc<dyn PageAllocatorFileDescriptor> {
+    Arc::new(TestPageAllocatorFileDescriptorImpl::new())
+}
+
+#[test]
+fn store_and_retrieve_chunks() {
+    let mut store = WasmChunkStore::default();
+    assert!(store.is_empty());
+    assert_eq!(store.page_map(), None);
+
+    let chunk1 = vec![1; 1000];
+    let chunk2 = vec![2; CHUNK_SIZE as usize];
+    let hash1 = store.insert_chunk(&chunk1, fd_factory());
+    let hash2 = store.insert_chunk(&chunk2, fd_factory());
+
+    assert_eq!(hash1, ic_crypto_sha::Sha256::hash(&chunk1));
+    assert_eq!(store.get_chunk_data(&hash1), Some(chunk1));
+    assert_eq!(store.get_chunk_data(&hash2), Some(chunk2));
+    assert_eq!(store.get_chunk_data(&[0; 32]), None);
+    assert_eq!(store.keys().count(), 2);
+    assert_eq!(store.memory_usage(), NumBytes::new(2 * CHUNK_SIZE));
+}
+
+#[test]
+fn inserting_existing_chunk_is_noop() {
+    let mut store = WasmChunkStore::default();
+    let chunk = vec![7; 10];
+    let hash = store.insert_chunk(&chunk, fd_factory());
+    let before = store.clone();
+
+    assert_eq!(
+        store.can_insert_chunk(NumBytes::new(CHUNK_SIZE), &chunk),
+        Ok(())
+    );
+    assert_eq!(store.insert_chunk(&chunk, fd_factory()), hash);
+    assert_eq!(store, before);
+}
+
+#[test]
+fn cannot_insert_oversized_chunk() {
+    let store = WasmChunkStore::default();
+    let chunk = vec![0; CHUNK_SIZE as usize + 1];
+    assert!(store.can_insert_chunk(DEFAULT_MAX_SIZE, &chunk).is_err());
+}
+
+#[test]
+fn cannot_exceed_max_size() {
+    let mut store = WasmChunkStore::default();
+    let max_size = NumBytes::new(2 * CHUNK_SIZE);
+    for i in 0..2 {
+        assert_eq!(store.can_insert_chunk(max_size, &[i]), Ok(()));
+        store.insert_chunk(&[i], fd_factory());
+    }
+    assert!(store.can_insert_chunk(max_size, &[2]).is_err());
+}
+
+#[test]
+fn clear_removes_all_chunks() {
+    let mut store = WasmChunkStore::default();
+    let hash = store.insert_chunk(&[1, 2, 3], fd_factory());
+    store.clear();
+
+    assert!(store.is_empty());
+    assert_eq!(store.page_map(), None);
+    assert_eq!(store.get_chunk_data(&hash), None);
+    assert_eq!(store.memory_usage(), NumBytes::new(0));
+}
+
+#[test]
+fn metadata_proto_roundtrip() {
+    let mut store = WasmChunkStore::default();
+    store.insert_chunk(&[1, 2, 3], fd_factory());
+    store.insert_chunk(&[4, 5], fd_factory());
+
+    let proto = pb::WasmChunkStoreMetadata::from(store.metadata());
+    let metadata = WasmChunkStoreMetadata::try_from(proto).unwrap();
+    assert_eq!(&metadata, store.metadata());
+}
@@ -10,7 +10,9 @@ use crate::canister_state::system_state::{
 };
 use crate::CallOrigin;
 use crate::Memory;
+use assert_matches::assert_matches;
 use ic_base_types::NumSeconds;
+use ic_error_types::RejectCode;
 use ic_ic00_types::{CanisterChange, CanisterChangeDetails, CanisterChangeOrigin};
 use ic_logger::replica_logger::no_op_logger;
 use ic_test_utilities::mock_time;
@@ -19,7 +21,9 @@ use ic_test_utilities::types::{
     ids::user_test_id,
     messages::{RequestBuilder, ResponseBuilder},
 };
-use ic_types::messages::CallContextId;
+use ic_types::instruction_profile::InstructionProfile;
+use ic_types::messages::{CallContextId, Payload, NO_DEADLINE};
+use ic_types::time::CoarseTime;
 use ic_types::{
     messages::CallbackId,
     methods::{Callback, WasmClosure},
@@ -80,13 +84,17 @@ impl CanisterStateFixture {
     }
 
     fn make_callback(&mut self) -> CallbackId {
+        self.make_callback_with_deadline(NO_DEADLINE)
+    }
+
+    fn make_callback_with_deadline(&mut self, deadline: CoarseTime) -> CallbackId {
         let call_context_id = self
             .canister_state
             .system_state
             .call_context_manager_mut()
             .unwrap()
             .new_call_context(
-                CallOrigin::CanisterUpdate(CANISTER_ID, CallbackId::from(1)),
+                CallOrigin::CanisterUpdate(CANISTER_ID, CallbackId::from(1), NO_DEADLINE),
                 Cycles::zero(),
                 Time::from_nanos_since_unix_epoch(0),
             );
@@ -104,6 +112,7 @@ impl CanisterStateFixture {
                 WasmClosure::new(0, 2),
                 WasmClosure::new(0, 2),
                 None,
+                deadline,
             ))
     }
 
@@ -176,6 +185,143 @@ fn canister_state_push_input_response_success() {
         .unwrap();
 }
 
+#[test]
+fn canister_state_time_out_callbacks_enqueues_sys_unknown_reject() {
+    let mut fixture = CanisterStateFixture::new();
+    fixture.with_input_reservation();
+    let deadline = CoarseTime::from_secs_since_unix_epoch(10);
+    let callback_id = fixture.make_callback_with_deadline(deadline);
+    let system_state = &mut fixture.canister_state.system_state;
+
+    let before_deadline = Time::from_secs_since_unix_epoch(9).unwrap();
+    assert!(!system_state.has_expired_callbacks(before_deadline));
+    assert_eq!(
+        (0, vec![]),
+        system_state.time_out_callbacks(before_deadline, &CANISTER_ID, &BTreeMap::new())
+    );
+
+    let at_deadline = Time::from(deadline);
+    assert!(system_state.has_expired_callbacks(at_deadline));
+    assert_eq!(
+        (1, vec![]),
+        system_state.time_out_callbacks(at_deadline, &CANISTER_ID, &BTreeMap::new())
+    );
+    // Only times out the callback once.
+    assert_eq!(
+        (0, vec![]),
+        system_state.time_out_callbacks(at_deadline, &CANISTER_ID, &BTreeMap::new())
+    );
+
+    match fixture.canister_state.pop_input() {
+        Some(CanisterMessage::Response(response)) => {
+            assert_eq!(callback_id, response.originator_reply_callback);
+            assert_eq!(OTHER_CANISTER_ID, response.respondent);
+            assert_eq!(deadline, response.deadline);
+            assert_matches!(
+                &response.response_payload,
+                Payload::Reject(context) if context.code == RejectCode::SysUnknown
+            );
+        }
+        msg => panic!("Expected a reject response, got {:?}", msg),
+    }
+}
+
+#[test]
+fn canister_state_push_input_late_best_effort_response_is_dropped() {
+    let mut fixture = CanisterStateFixture::new();
+    fixture.with_input_reservation();
+    let deadline = CoarseTime::from_secs_since_unix_epoch(10);
+    let callback_id = fixture.make_callback_with_deadline(deadline);
+    fixture.canister_state.system_state.time_out_callbacks(
+        Time::from(deadline),
+        &CANISTER_ID,
+        &BTreeMap::new(),
+    );
+    fixture.canister_state.pop_input().unwrap();
+
+    // The actual response arrives after the callback has expired.
+    let response: RequestOrResponse = ResponseBuilder::default()
+        .originator(CANISTER_ID)
+        .respondent(OTHER_CANISTER_ID)
+        .originator_reply_callback(callback_id)
+        .deadline(deadline)
+        .build()
+        .into();
+    fixture
+        .push_input(
+            response,
+            SubnetType::Application,
+            InputQueueType::RemoteSubnet,
+        )
+        .unwrap();
+    assert!(!fixture.canister_state.has_input());
+}
+
+#[test]
+fn canister_state_best_effort_response_prevents_callback_expiry() {
+    let mut fixture = CanisterStateFixture::new();
+    fixture.with_input_reservation();
+    let deadline = CoarseTime::from_secs_since_unix_epoch(10);
+    let callback_id = fixture.make_callback_with_deadline(deadline);
+
+    let response: RequestOrResponse = ResponseBuilder::default()
+        .originator(CANISTER_ID)
+        .respondent(OTHER_CANISTER_ID)
+        .originator_reply_callback(callback_id)
+        .deadline(deadline)
+        .build()
+        .into();
+    fixture
+        .push_input(
+            response,
+            SubnetType::Application,
+            InputQueueType::RemoteSubnet,
+        )
+        .unwrap();
+
+    assert_eq!(
+        (0, vec![]),
+        fixture.canister_state.system_state.time_out_callbacks(
+            Time::from(deadline),
+            &CANISTER_ID,
+            &BTreeMap::new()
+        )
+    );
+}
+
+#[test]
+fn canister_state_best_effort_response_without_deadline_prevents_callback_expiry() {
+    let mut fixture = CanisterStateFixture::new();
+    fixture.with_input_reservation();
+    let deadline = CoarseTime::from_secs_since_unix_epoch(10);
+    let callback_id = fixture.make_callback_with_deadline(deadline);
+
+    // A response encoded by a subnet at a certification version without
+    // deadlines arrives without the deadline of the call.
+    let response: RequestOrResponse = ResponseBuilder::default()
+        .originator(CANISTER_ID)
+        .respondent(OTHER_CANISTER_ID)
+        .originator_reply_callback(callback_id)
+        .build()
+        .into();
+    fixture
+        .push_input(
+            response,
+            SubnetType::Application,
+            InputQueueType::RemoteSubnet,
+        )
+        .unwrap();
+
+    assert_eq!(
+        (0, vec![]),
+        fixture.canister_state.system_state.time_out_callbacks(
+            Time::from(deadline),
+            &CANISTER_ID,
+            &BTreeMap::new()
+        )
+    );
+}
+
 #[test]
 #[should_panic(expected = "Expected `RequestOrResponse` to be targeted to canister ID")]
 fn canister_state_push_input_request_mismatched_receiver() {
@@ -551,6 +697,7 @@ fn canister_state_callback_round_trip() {
         WasmClosure::new(0, 2),
         WasmClosure::new(0, 2),
         None,
+        NO_DEADLINE,
     );
 
     let pb_callback = pb::Callback::from(&callback);
@@ -590,6 +737,17 @@ fn execution_state_test_partial_eq() {
         state_1
     );
 
+    let mut instruction_profile = InstructionProfile::default();
+    instruction_profile.enter(0, 0);
+    instruction_profile.finish(10);
+    assert_eq!(
+        ExecutionState {
+            instruction_profile,
+            ..state_1.clone()
+        },
+        state_1
+    );
+
     assert_ne!(
         ExecutionState {
             wasm_binary: execution_state::WasmBinary::new(CanisterModule::new(vec![1, 2, 4])),
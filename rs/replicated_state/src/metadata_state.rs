@@ -3,7 +3,7 @@ pub mod subnet_call_context_manager;
 mod tests;
 
 use crate::{
-    canister_state::system_state::CyclesUseCase,
+    canister_snapshots::SnapshotId, canister_state::system_state::CyclesUseCase,
     metadata_state::subnet_call_context_manager::SubnetCallContextManager,
 };
 use ic_base_types::CanisterId;
@@ -153,6 +153,10 @@ pub struct SystemMetadata {
     /// response limit. To work around this limitation, large responses are paginated
     /// and are stored here temporarily until they're fetched by the calling canister.
     pub bitcoin_get_successors_follow_up_responses: BTreeMap<CanisterId, Vec<BlockBlob>>,
+
+    /// The local part of the next canister snapshot ID to be generated.
+    /// Snapshot IDs are unique per subnet, so this counter is never reset.
+    next_snapshot_id: u64,
 }
 
 /// Full description of the IC network toplogy.
@@ -498,6 +502,7 @@ impl From<&SystemMetadata> for pb_metadata::SystemMetadata {
                     },
                 )
                 .collect(),
+            next_snapshot_id: item.next_snapshot_id,
         }
     }
 }
@@ -598,6 +603,7 @@ impl TryFrom<pb_metadata::SystemMetadata> for SystemMetadata {
             },
             expected_compiled_wasms: BTreeSet::new(),
             bitcoin_get_successors_follow_up_responses,
+            next_snapshot_id: item.next_snapshot_id,
         })
     }
 }
@@ -632,6 +638,7 @@ impl SystemMetadata {
             subnet_metrics: Default::default(),
             expected_compiled_wasms: BTreeSet::new(),
             bitcoin_get_successors_follow_up_responses: BTreeMap::default(),
+            next_snapshot_id: 0,
         }
     }
 
@@ -747,6 +754,13 @@ impl SystemMetadata {
         res.ok_or_else(|| "Canister ID allocation was consumed".into())
     }
 
+    /// Generates a new ID for a snapshot of canister `canister_id`.
+    pub fn generate_snapshot_id(&mut self, canister_id: CanisterId) -> SnapshotId {
+        let local_id = self.next_snapshot_id;
+        self.next_snapshot_id += 1;
+        SnapshotId::new(canister_id, local_id)
+    }
+
     /// Returns the number of canister IDs that can still be generated.
     pub fn available_canister_ids(&self) -> u64 {
         let generated_canister_ids = match (
@@ -799,6 +813,10 @@ impl SystemMetadata {
         // Preserve ingress history.
         res.ingress_history = self.ingress_history;
 
+        // Preserve the snapshot ID counter, so that snapshots of canisters migrated
+        // to subnet B keep unique IDs.
+        res.next_snapshot_id = self.next_snapshot_id;
+
         // All other fields have been reset to default.
         res
     }
@@ -864,6 +882,7 @@ impl SystemMetadata {
             subnet_metrics,
             expected_compiled_wasms,
             bitcoin_get_successors_follow_up_responses,
+            next_snapshot_id,
         } = self;
 
         let split_from = split_from.expect("Not a state resulting from a subnet split");
@@ -899,6 +918,7 @@ impl SystemMetadata {
             subnet_metrics,
             expected_compiled_wasms,
             bitcoin_get_successors_follow_up_responses,
+            next_snapshot_id,
         }
     }
 }
@@ -1,6 +1,6 @@
 use ic_btc_types_internal::{GetSuccessorsRequestInitial, SendTransactionRequest};
 use ic_error_types::{ErrorCode, UserError};
-use ic_ic00_types::EcdsaKeyId;
+use ic_ic00_types::{EcdsaKeyId, SchnorrKeyId};
 use ic_logger::{info, ReplicaLogger};
 use ic_protobuf::{
     proxy::{try_from_option_field, ProxyDecodeError},
@@ -20,6 +20,7 @@ use std::{
 pub enum SubnetCallContext {
     SetupInitialDKG(SetupInitialDkgContext),
     SignWithEcsda(SignWithEcdsaContext),
+    SignWithSchnorr(SignWithSchnorrContext),
     CanisterHttpRequest(CanisterHttpRequestContext),
     EcdsaDealings(EcdsaDealingsContext),
     BitcoinGetSuccessors(BitcoinGetSuccessorsContext),
@@ -32,6 +33,7 @@ impl SubnetCallContext {
         match &self {
             SubnetCallContext::SetupInitialDKG(context) => &context.request,
             SubnetCallContext::SignWithEcsda(context) => &context.request,
+            SubnetCallContext::SignWithSchnorr(context) => &context.request,
             SubnetCallContext::CanisterHttpRequest(context) => &context.request,
             SubnetCallContext::EcdsaDealings(context) => &context.request,
             SubnetCallContext::BitcoinGetSuccessors(context) => &context.request,
@@ -44,6 +46,7 @@ impl SubnetCallContext {
         match &self {
             SubnetCallContext::SetupInitialDKG(context) => context.time,
             SubnetCallContext::SignWithEcsda(context) => context.batch_time,
+            SubnetCallContext::SignWithSchnorr(context) => context.batch_time,
             SubnetCallContext::CanisterHttpRequest(context) => context.time,
             SubnetCallContext::EcdsaDealings(context) => context.time,
             SubnetCallContext::BitcoinGetSuccessors(context) => context.time,
@@ -64,6 +67,7 @@ pub struct SubnetCallContextManager {
     pub bitcoin_send_transaction_internal_contexts:
         BTreeMap<CallbackId, BitcoinSendTransactionInternalContext>,
     pub install_code_contexts: BTreeMap<CallbackId, InstallCodeContext>,
+    pub sign_with_schnorr_contexts: BTreeMap<CallbackId, SignWithSchnorrContext>,
 }
 
 impl SubnetCallContextManager {
@@ -93,6 +97,25 @@ impl SubnetCallContextManager {
         }
     }
 
+    pub fn push_sign_with_schnorr_request(
+        &mut self,
+        context: SignWithSchnorrContext,
+        max_queue_size: u32,
+    ) -> Result<(), UserError> {
+        if self.sign_with_schnorr_contexts.len() >= max_queue_size as usize {
+            Err(UserError::new(
+                ErrorCode::CanisterRejectedMessage,
+                "sign_with_schnorr request could not be handled, the Schnorr signature queue is full."
+                    .to_string(),
+            ))
+        } else {
+            let callback_id = CallbackId::new(self.next_callback_id);
+            self.next_callback_id += 1;
+            self.sign_with_schnorr_contexts.insert(callback_id, context);
+            Ok(())
+        }
+    }
+
     pub fn push_http_request(&mut self, context: CanisterHttpRequestContext) {
         let callback_id = CallbackId::new(self.next_callback_id);
         self.next_callback_id += 1;
@@ -167,6 +190,19 @@ impl SubnetCallContextManager {
                         SubnetCallContext::SignWithEcsda(context)
                     })
             })
+            .or_else(|| {
+                self.sign_with_schnorr_contexts
+                    .remove(&callback_id)
+                    .map(|context| {
+                        info!(
+                            logger,
+                            "Received the response for SignWithSchnorr request with id {:?} from {:?}",
+                            context.pseudo_random_id,
+                            context.request.sender
+                        );
+                        SubnetCallContext::SignWithSchnorr(context)
+                    })
+            })
             .or_else(|| {
                 self.ecdsa_dealings_contexts
                     .remove(&callback_id)
@@ -296,6 +332,16 @@ impl From<&SubnetCallContextManager> for pb_metadata::SubnetCallContextManager {
                     },
                 )
                 .collect(),
+            sign_with_schnorr_contexts: item
+                .sign_with_schnorr_contexts
+                .iter()
+                .map(
+                    |(callback_id, context)| pb_metadata::SignWithSchnorrContextTree {
+                        callback_id: callback_id.get(),
+                        context: Some(context.into()),
+                    },
+                )
+                .collect(),
         }
     }
 }
@@ -367,6 +413,13 @@ impl TryFrom<(Time, pb_metadata::SubnetCallContextManager)> for SubnetCallContex
             install_code_contexts.insert(CallbackId::new(entry.callback_id), context);
         }
 
+        let mut sign_with_schnorr_contexts = BTreeMap::<CallbackId, SignWithSchnorrContext>::new();
+        for entry in item.sign_with_schnorr_contexts {
+            let context: SignWithSchnorrContext =
+                try_from_option_field(entry.context, "SystemMetadata::SignWithSchnorrContext")?;
+            sign_with_schnorr_contexts.insert(CallbackId::new(entry.callback_id), context);
+        }
+
         Ok(Self {
             next_callback_id: item.next_callback_id,
             setup_initial_dkg_contexts,
@@ -376,6 +429,7 @@ impl TryFrom<(Time, pb_metadata::SubnetCallContextManager)> for SubnetCallContex
             bitcoin_get_successors_contexts,
             bitcoin_send_transaction_internal_contexts,
             install_code_contexts,
+            sign_with_schnorr_contexts,
         })
     }
 }
@@ -489,6 +543,49 @@ impl TryFrom<pb_metadata::SignWithEcdsaContext> for SignWithEcdsaContext {
     }
 }
 
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct SignWithSchnorrContext {
+    pub request: Request,
+    pub key_id: SchnorrKeyId,
+    pub message: Vec<u8>,
+    pub derivation_path: Vec<Vec<u8>>,
+    pub pseudo_random_id: [u8; 32],
+    pub batch_time: Time,
+}
+
+impl From<&SignWithSchnorrContext> for pb_metadata::SignWithSchnorrContext {
+    fn from(context: &SignWithSchnorrContext) -> Self {
+        pb_metadata::SignWithSchnorrContext {
+            request: Some((&context.request).into()),
+            key_id: Some((&context.key_id).into()),
+            message: context.message.clone(),
+            derivation_path: context.derivation_path.clone(),
+            pseudo_random_id: context.pseudo_random_id.to_vec(),
+            batch_time: context.batch_time.as_nanos_since_unix_epoch(),
+        }
+    }
+}
+
+impl TryFrom<pb_metadata::SignWithSchnorrContext> for SignWithSchnorrContext {
+    type Error = ProxyDecodeError;
+    fn try_from(context: pb_metadata::SignWithSchnorrContext) -> Result<Self, Self::Error> {
+        let request: Request =
+            try_from_option_field(context.request, "SignWithSchnorrContext::request")?;
+        let key_id = try_from_option_field(context.key_id, "SignWithSchnorrContext::key_id")?;
+        Ok(SignWithSchnorrContext {
+            request,
+            key_id,
+            message: context.message,
+            derivation_path: context.derivation_path,
+            pseudo_random_id: context
+                .pseudo_random_id
+                .try_into()
+                .map_err(|_| Self::Error::Other("pseudo_random_id is not 32 bytes.".to_string()))?,
+            batch_time: Time::from_nanos_since_unix_epoch(context.batch_time),
+        })
+    }
+}
+
 #[derive(Clone, Debug, PartialEq, Eq)]
 pub struct EcdsaDealingsContext {
     pub request: Request,
@@ -509,6 +509,7 @@ fn system_metadata_split() {
         consumed_cycles_by_deleted_canisters: 2197.into(),
         ..Default::default()
     };
+    system_metadata.generate_snapshot_id(CANISTER_1);
 
     // Split off subnet A', phase 1.
     let metadata_a_phase_1 = system_metadata.clone().split(SUBNET_A);
@@ -535,8 +536,10 @@ fn system_metadata_split() {
     // Split off subnet B, phase 1.
     let metadata_b_phase_1 = system_metadata.clone().split(SUBNET_B);
 
-    // Should only retain ingress history; plus a split marker pointing to subnet A.
+    // Should only retain ingress history and the snapshot ID counter; plus a split
+    // marker pointing to subnet A.
     let mut expected = SystemMetadata::new(SUBNET_B, SubnetType::VerifiedApplication);
+    expected.next_snapshot_id = system_metadata.next_snapshot_id;
     expected.ingress_history = system_metadata.ingress_history;
     expected.split_from = Some(SUBNET_A);
     assert_eq!(expected, metadata_b_phase_1);
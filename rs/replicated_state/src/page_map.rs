@@ -441,6 +441,46 @@ impl PageMap {
         self.persist_to_file(&self.unflushed_delta, dst)
     }
 
+    /// Persists the full contents of this page map (checkpoint and deltas) to
+    /// the specified destination, overwriting any existing file.
+    pub fn persist_all(&self, dst: &Path) -> Result<(), PersistenceError> {
+        let mut file = OpenOptions::new()
+            .write(true)
+            .create(true)
+            .truncate(true)
+            .open(dst)
+            .map_err(|err| PersistenceError::FileSystemError {
+                path: dst.display().to_string(),
+                context: "Failed to open file".to_string(),
+                internal_error: err.to_string(),
+            })?;
+        let num_host_pages = self.num_host_pages() as u64;
+        let mut start = 0;
+        while start < num_host_pages {
+            let end = num_host_pages.min(start + MAXIMUM_GAP);
+            let mut buffer = WriteBuffer {
+                content: (start..end)
+                    .map(|index| &self.get_page(PageIndex::from(index))[..])
+                    .collect(),
+                start_index: PageIndex::from(start),
+            };
+            buffer.apply_to_file(&mut file, dst)?;
+            start = end;
+        }
+        Ok(())
+    }
+
+    /// Returns a copy of this page map that holds all of its pages in the page
+    /// delta and is not backed by a checkpoint file. Unlike a clone, the copy
+    /// can replace a page map backed by a different file: flushing it
+    /// truncates the destination and writes out all pages.
+    pub fn detached_copy(&self, fd_factory: Arc<dyn PageAllocatorFileDescriptor>) -> Self {
+        let mut copy = Self::new(fd_factory);
+        let pages: Vec<_> = self.host_pages_iter().collect();
+        copy.update(&pages);
+        copy
+    }
+
     /// Returns the iterator over host pages managed by this `PageMap`.
     pub fn host_pages_iter(&self) -> impl Iterator<Item = (PageIndex, &PageBytes)> + '_ {
         (0..self.num_host_pages()).map(move |i| {
@@ -159,6 +159,95 @@ fn persisted_map_is_equivalent_to_the_original() {
     assert_eq!(persisted_map, original_map);
 }
 
+#[test]
+fn persist_all_writes_checkpoint_and_deltas() {
+    let tmp = tempfile::Builder::new()
+        .prefix("checkpoints")
+        .tempdir()
+        .unwrap();
+    let heap_file = tmp.path().join("heap");
+    let copy_file = tmp.path().join("copy");
+
+    let base_page = [42u8; PAGE_SIZE];
+    let base_pages: Vec<(PageIndex, &[u8; PAGE_SIZE])> =
+        (0..50).map(|i| (PageIndex::new(i), &base_page)).collect();
+    let mut base_map = PageMap::new_for_testing();
+    base_map.update(base_pages.as_slice());
+    base_map.persist_delta(&heap_file).unwrap();
+
+    let mut original_map = PageMap::open(
+        &heap_file,
+        Height::new(0),
+        Arc::new(TestPageAllocatorFileDescriptorImpl::new()),
+    )
+    .unwrap();
+
+    // Span more than one write buffer.
+    let page_3 = [3u8; PAGE_SIZE];
+    let page_450 = [45u8; PAGE_SIZE];
+    original_map.update(&[
+        (PageIndex::new(3), &page_3),
+        (PageIndex::new(450), &page_450),
+    ]);
+
+    // Existing contents of the destination are overwritten.
+    std::fs::write(&copy_file, vec![7u8; 1000 * PAGE_SIZE]).unwrap();
+    original_map.persist_all(&copy_file).unwrap();
+
+    let persisted_map = PageMap::open(
+        &copy_file,
+        Height::new(0),
+        Arc::new(TestPageAllocatorFileDescriptorImpl::new()),
+    )
+    .unwrap();
+
+    assert_equal_page_maps(&persisted_map, &original_map);
+}
+
+#[test]
+fn detached_copy_is_not_backed_by_checkpoint() {
+    let tmp = tempfile::Builder::new()
+        .prefix("checkpoints")
+        .tempdir()
+        .unwrap();
+    let heap_file = tmp.path().join("heap");
+    let other_file = tmp.path().join("other");
+
+    let base_page = [42u8; PAGE_SIZE];
+    let base_pages: Vec<(PageIndex, &[u8; PAGE_SIZE])> =
+        (0..50).map(|i| (PageIndex::new(i), &base_page)).collect();
+    let mut base_map = PageMap::new_for_testing();
+    base_map.update(base_pages.as_slice());
+    base_map.persist_delta(&heap_file).unwrap();
+
+    let mut original_map = PageMap::open(
+        &heap_file,
+        Height::new(0),
+        Arc::new(TestPageAllocatorFileDescriptorImpl::new()),
+    )
+    .unwrap();
+    let page_3 = [3u8; PAGE_SIZE];
+    original_map.update(&[(PageIndex::new(3), &page_3)]);
+
+    let copy = original_map.detached_copy(Arc::new(TestPageAllocatorFileDescriptorImpl::new()));
+    assert_eq!(copy.base_height, None);
+    assert_equal_page_maps(&copy, &original_map);
+
+    // Mirror how the state manager flushes a page map that is not backed by a
+    // checkpoint: the stale destination is truncated before the unflushed
+    // delta is written.
+    std::fs::write(&other_file, vec![7u8; 10 * PAGE_SIZE]).unwrap();
+    nix::unistd::truncate(&other_file, 0).unwrap();
+    copy.persist_unflushed_delta(&other_file).unwrap();
+    let persisted_map = PageMap::open(
+        &other_file,
+        Height::new(0),
+        Arc::new(TestPageAllocatorFileDescriptorImpl::new()),
+    )
+    .unwrap();
+    assert_equal_page_maps(&persisted_map, &original_map);
+}
+
 #[test]
 fn can_persist_and_load_an_empty_page_map() {
     let tmp = tempfile::Builder::new()
@@ -3,6 +3,7 @@ use super::{
     metadata_state::{IngressHistoryState, Stream, Streams, SystemMetadata},
 };
 use crate::{
+    canister_snapshots::CanisterSnapshots,
     canister_state::queues::CanisterQueuesLoopDetector,
     canister_state::system_state::{push_input, CanisterOutputQueuesIterator},
     metadata_state::StreamMap,
@@ -373,6 +374,9 @@ pub struct ReplicatedState {
     /// The queue is, therefore, emptied at the end of every round.
     // TODO(EXE-109): Move this queue into `subnet_queues`
     pub consensus_queue: Vec<Response>,
+
+    /// Snapshots of canisters taken via `take_canister_snapshot`.
+    pub canister_snapshots: CanisterSnapshots,
 }
 
 impl ReplicatedState {
@@ -383,6 +387,7 @@ impl ReplicatedState {
             metadata: SystemMetadata::new(own_subnet_id, own_subnet_type),
             subnet_queues: CanisterQueues::default(),
             consensus_queue: Vec::new(),
+            canister_snapshots: CanisterSnapshots::default(),
         }
     }
 
@@ -397,6 +402,7 @@ impl ReplicatedState {
             metadata,
             subnet_queues,
             consensus_queue: Vec::new(),
+            canister_snapshots: CanisterSnapshots::default(),
         };
         res.update_stream_responses_size_bytes();
         res
@@ -811,6 +817,45 @@ impl ReplicatedState {
         timed_out_requests_count
     }
 
+    /// Times out all best-effort callbacks with expired deadlines (given
+    /// `current_time`) across all canisters. Returns the number of timed out
+    /// callbacks and the errors encountered when enqueuing their reject
+    /// responses.
+    ///
+    /// Must be called before `time_out_requests()`, so that best-effort requests
+    /// timing out in output queues find their callbacks already expired.
+    ///
+    /// See `SystemState::time_out_callbacks` for further details.
+    pub fn time_out_callbacks(&mut self, current_time: Time) -> (u64, Vec<StateError>) {
+        // Same remove-call-replace approach as in `time_out_requests()`.
+        let canister_ids_with_expired_callbacks = self
+            .canister_states
+            .iter()
+            .filter(|(_, canister_state)| {
+                canister_state
+                    .system_state
+                    .has_expired_callbacks(current_time)
+            })
+            .map(|(canister_id, _)| *canister_id)
+            .collect::<Vec<_>>();
+
+        let mut timed_out_callbacks_count = 0;
+        let mut errors = Vec::new();
+        for canister_id in canister_ids_with_expired_callbacks {
+            let mut canister = self.canister_states.remove(&canister_id).unwrap();
+            let (count, canister_errors) = canister.system_state.time_out_callbacks(
+                current_time,
+                &canister_id,
+                &self.canister_states,
+            );
+            timed_out_callbacks_count += count;
+            errors.extend(canister_errors);
+            self.canister_states.insert(canister_id, canister);
+        }
+
+        (timed_out_callbacks_count, errors)
+    }
+
     /// Splits the replicated state as part of subnet splitting phase 1, retaining
     /// only the canisters of `new_subnet_id` (as determined by the provided routing
     /// table).
@@ -824,7 +869,7 @@ impl ReplicatedState {
     ///
     /// This first phase only consists of:
     ///  * Splitting the canisters hosted by A among A' and B, as determined by the
-    ///    provided routing table.
+    ///    provided routing table. Canister snapshots follow their canisters.
     ///  * Producing a new, empty `MetadataState` for subnet B, but preserving
     ///    the ingress history unchanged.
     ///
@@ -843,6 +888,7 @@ impl ReplicatedState {
             metadata,
             mut subnet_queues,
             consensus_queue,
+            mut canister_snapshots,
         } = self;
 
         // Consensus queue is always empty at the end of the round.
@@ -854,6 +900,9 @@ impl ReplicatedState {
         canister_states
             .retain(|canister_id, _| routing_table.route(canister_id.get()) == Some(new_subnet_id));
 
+        // Snapshots follow their canisters.
+        canister_snapshots.retain(|canister_id| canister_states.contains_key(canister_id));
+
         // All subnet messages (ingress and canister) only remain on subnet A' because:
         //
         //  * Message Routing would drop a response from subnet B to a request it had
@@ -877,6 +926,7 @@ impl ReplicatedState {
             metadata,
             subnet_queues,
             consensus_queue,
+            canister_snapshots,
         }
     }
 
@@ -896,6 +946,7 @@ impl ReplicatedState {
             mut metadata,
             subnet_queues,
             consensus_queue,
+            canister_snapshots,
         } = self;
 
         metadata
@@ -921,6 +972,7 @@ impl ReplicatedState {
             metadata,
             subnet_queues,
             consensus_queue,
+            canister_snapshots,
         };
         res.update_stream_responses_size_bytes();
         res
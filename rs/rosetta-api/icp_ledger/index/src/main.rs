@@ -432,8 +432,16 @@ fn process_balance_changes(block_index: BlockIndex, block: &Block) -> Result<(),
             debit(block_index, from, amount.get_e8s() + fee.get_e8s());
             credit(block_index, to, amount.get_e8s())
         }
-        _ => {
-            return Err("[process_balance_changes]: Indexer only supports Burn, Mint and Transfer Operations".to_owned());
+        Operation::Approve { from, fee, .. } => debit(block_index, from, fee.get_e8s()),
+        Operation::TransferFrom {
+            from,
+            to,
+            amount,
+            fee,
+            ..
+        } => {
+            debit(block_index, from, amount.get_e8s() + fee.get_e8s());
+            credit(block_index, to, amount.get_e8s())
         }
     };
     Ok(())
@@ -483,10 +491,10 @@ fn get_account_identifiers(block: &Block) -> Result<Vec<AccountIdentifier>, Stri
         Operation::Burn { from, .. } => Ok(vec![from]),
         Operation::Mint { to, .. } => Ok(vec![to]),
         Operation::Transfer { from, to, .. } => Ok(vec![from, to]),
-        _ => Err(
-            "[get_account_identifiers]: Indexer only supports Burn, Mint and Transfer Operations"
-                .to_owned(),
-        ),
+        Operation::Approve { from, spender, .. } => Ok(vec![from, spender]),
+        Operation::TransferFrom {
+            from, to, spender, ..
+        } => Ok(vec![from, to, spender]),
     }
 }
 
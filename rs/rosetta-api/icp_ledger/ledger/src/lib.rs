@@ -11,8 +11,8 @@ use ic_ledger_core::{
 use ic_ledger_core::{block::BlockIndex, tokens::Tokens};
 use ic_ledger_hash_of::HashOf;
 use icp_ledger::{
-    AccountIdentifier, ApprovalKey, Block, LedgerBalances, Memo, Operation, PaymentError,
-    Transaction, TransferError, TransferFee, UpgradeArgs, DEFAULT_TRANSFER_FEE,
+    AccountIdentifier, ApprovalKey, Block, FeatureFlags, LedgerBalances, Memo, Operation,
+    PaymentError, Transaction, TransferError, TransferFee, UpgradeArgs, DEFAULT_TRANSFER_FEE,
 };
 use icrc_ledger_types::icrc1::account::Account;
 use intmap::IntMap;
@@ -102,6 +102,10 @@ pub struct Ledger {
     /// Token name
     #[serde(default = "unknown_token")]
     pub token_name: String,
+
+    /// Ledger features that are switched on, e.g. ICRC-2.
+    #[serde(default)]
+    pub feature_flags: FeatureFlags,
 }
 
 impl LedgerContext for Ledger {
@@ -219,6 +223,7 @@ impl Default for Ledger {
             transfer_fee: DEFAULT_TRANSFER_FEE,
             token_symbol: unknown_token(),
             token_name: unknown_token(),
+            feature_flags: FeatureFlags::default(),
         }
     }
 }
@@ -321,6 +326,7 @@ impl Ledger {
         transfer_fee: Option<Tokens>,
         token_symbol: Option<String>,
         token_name: Option<String>,
+        feature_flags: Option<FeatureFlags>,
     ) {
         self.token_symbol = token_symbol.unwrap_or_else(|| "ICP".to_string());
         self.token_name = token_name.unwrap_or_else(|| "Internet Computer".to_string());
@@ -345,6 +351,9 @@ impl Ledger {
         if let Some(transfer_fee) = transfer_fee {
             self.transfer_fee = transfer_fee;
         }
+        if let Some(feature_flags) = feature_flags {
+            self.feature_flags = feature_flags;
+        }
     }
 
     pub fn change_notification_state(
@@ -427,6 +436,9 @@ impl Ledger {
             }
             self.icrc1_minting_account = Some(icrc1_minting_account);
         }
+        if let Some(feature_flags) = args.feature_flags {
+            self.feature_flags = feature_flags;
+        }
     }
 }
 
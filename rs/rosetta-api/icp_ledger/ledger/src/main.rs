@@ -19,6 +19,7 @@ use ic_ledger_canister_core::{
     range_utils,
 };
 use ic_ledger_core::{
+    approvals::Approvals,
     block::{BlockIndex, BlockType, EncodedBlock},
     timestamp::TimeStamp,
     tokens::{Tokens, DECIMAL_PLACES},
@@ -26,14 +27,19 @@ use ic_ledger_core::{
 use icp_ledger::{
     protobuf, tokens_into_proto, AccountBalanceArgs, AccountIdentifier, ArchiveInfo,
     ArchivedBlocksRange, ArchivedEncodedBlocksRange, Archives, BinaryAccountBalanceArgs, Block,
-    BlockArg, BlockRes, CandidBlock, Decimals, GetBlocksArgs, InitArgs, IterBlocksArgs,
-    LedgerCanisterPayload, Memo, Name, Operation, PaymentError, QueryBlocksResponse,
-    QueryEncodedBlocksResponse, SendArgs, Subaccount, Symbol, TipOfChainRes, TotalSupplyArgs,
-    Transaction, TransferArgs, TransferError, TransferFee, TransferFeeArgs, MAX_BLOCKS_PER_REQUEST,
-    MEMO_SIZE_BYTES,
+    BlockArg, BlockRes, CandidBlock, Decimals, FeatureFlags, GetBlocksArgs, InitArgs,
+    IterBlocksArgs, LedgerCanisterPayload, Memo, Name, Operation, PaymentError,
+    QueryBlocksResponse, QueryEncodedBlocksResponse, SendArgs, Subaccount, Symbol, TipOfChainRes,
+    TotalSupplyArgs, Transaction, TransferArgs, TransferError, TransferFee, TransferFeeArgs,
+    MAX_BLOCKS_PER_REQUEST, MEMO_SIZE_BYTES,
 };
 use icrc_ledger_types::icrc1::account::Account;
 use icrc_ledger_types::icrc1::transfer::TransferArg;
+use icrc_ledger_types::icrc2::{
+    allowance::{Allowance, AllowanceArgs},
+    approve::{ApproveArgs, ApproveError},
+    transfer_from::{TransferFromArgs, TransferFromError},
+};
 use icrc_ledger_types::{
     icrc::generic_metadata_value::MetadataValue as Value, icrc3::archive::QueryArchiveFn,
 };
@@ -48,6 +54,10 @@ use std::{
     time::Duration,
 };
 
+/// The expiration of approvals that do not specify one, and the upper bound
+/// for approvals that do.
+const DEFAULT_APPROVAL_EXPIRATION: u64 = Duration::from_secs(3600 * 24 * 7).as_nanos() as u64;
+
 #[derive(Clone)]
 struct DebugOutSink;
 
@@ -75,6 +85,7 @@ impl Sink for DebugOutSink {
 /// * `transfer_fee` - The fee to pay to perform a transaction.
 /// * `token_symbol` - Token symbol.
 /// * `token_name` - Token name.
+/// * `feature_flags` - Optional ledger features, e.g. ICRC-2.
 #[allow(clippy::too_many_arguments)]
 fn init(
     minting_account: AccountIdentifier,
@@ -87,6 +98,7 @@ fn init(
     transfer_fee: Option<Tokens>,
     token_symbol: Option<String>,
     token_name: Option<String>,
+    feature_flags: Option<FeatureFlags>,
 ) {
     print(format!(
         "[ledger] init(): minting account is {}",
@@ -102,6 +114,7 @@ fn init(
         transfer_fee,
         token_symbol,
         token_name,
+        feature_flags,
     );
     match max_message_size_bytes {
         None => {
@@ -547,10 +560,17 @@ fn icrc1_balance_of(acc: Account) -> Nat {
 
 #[candid_method(query, rename = "icrc1_supported_standards")]
 fn icrc1_supported_standards() -> Vec<StandardRecord> {
-    vec![StandardRecord {
+    let mut standards = vec![StandardRecord {
         name: "ICRC-1".to_string(),
         url: "https://github.com/dfinity/ICRC-1".to_string(),
-    }]
+    }];
+    if LEDGER.read().unwrap().feature_flags.icrc2 {
+        standards.push(StandardRecord {
+            name: "ICRC-2".to_string(),
+            url: "https://github.com/dfinity/ICRC-1/tree/main/standards/ICRC-2".to_string(),
+        });
+    }
+    standards
 }
 
 #[candid_method(query, rename = "icrc1_minting_account")]
@@ -642,6 +662,7 @@ fn canister_init(arg: LedgerCanisterPayload) {
             arg.transfer_fee,
             arg.token_symbol,
             arg.token_name,
+            arg.feature_flags,
         ),
         LedgerCanisterPayload::Upgrade(_) => {
             trap_with("Cannot initialize the canister with an Upgrade argument. Please provide an Init argument.");
@@ -672,6 +693,7 @@ fn main() {
                         arg.transfer_fee,
                         arg.token_symbol,
                         arg.token_name,
+                        arg.feature_flags,
                     ),
                     Err(old_err) =>
                     trap_with(&format!("Unable to decode init argument.\nDecode as new init returned the error {}\nDecode as old init returned the error {}", new_err, old_err))
@@ -871,6 +893,232 @@ fn icrc1_transfer_candid() {
     })
 }
 
+#[candid_method(update, rename = "icrc2_approve")]
+async fn icrc2_approve(arg: ApproveArgs) -> Result<Nat, ApproveError> {
+    let block_index = {
+        let mut ledger = LEDGER.write().unwrap();
+        if !ledger.feature_flags.icrc2 {
+            trap_with("ICRC-2 features are not enabled on the ledger.");
+        }
+        let now = TimeStamp::from_nanos_since_unix_epoch(time_nanos());
+
+        let from_account = Account {
+            owner: caller().into(),
+            subaccount: arg.from_subaccount,
+        };
+        if from_account.owner == arg.spender.owner {
+            trap_with("self approval is not allowed");
+        }
+        let from = AccountIdentifier::from(from_account);
+        let spender = AccountIdentifier::from(arg.spender);
+        if Some(from) == ledger.minting_account_id {
+            trap_with("the minting account cannot delegate mints");
+        }
+        match arg.memo.as_ref() {
+            Some(memo) if memo.0.len() > MEMO_SIZE_BYTES => {
+                trap_with("the memo field is too large")
+            }
+            _ => {}
+        };
+        let allowance = Tokens::from_e8s(arg.amount.0.to_u64().unwrap_or(u64::MAX));
+        let expected_allowance = match arg.expected_allowance {
+            Some(n) => match n.0.to_u64() {
+                Some(n) => Some(Tokens::from_e8s(n)),
+                None => {
+                    let current_allowance = ledger.approvals.allowance(&from, &spender, now).amount;
+                    return Err(ApproveError::AllowanceChanged {
+                        current_allowance: Nat::from(current_allowance.get_e8s()),
+                    });
+                }
+            },
+            None => None,
+        };
+
+        let default_expiration =
+            TimeStamp::from_nanos_since_unix_epoch(time_nanos() + DEFAULT_APPROVAL_EXPIRATION);
+
+        let expected_fee = ledger.transfer_fee;
+        if arg.fee.is_some() && arg.fee.as_ref() != Some(&Nat::from(expected_fee.get_e8s())) {
+            return Err(ApproveError::BadFee {
+                expected_fee: Nat::from(expected_fee.get_e8s()),
+            });
+        }
+
+        let tx = Transaction {
+            operation: Operation::Approve {
+                from,
+                spender,
+                allowance,
+                expected_allowance,
+                expires_at: Some(
+                    arg.expires_at
+                        .map(TimeStamp::from_nanos_since_unix_epoch)
+                        .map(|expires_at| expires_at.min(default_expiration))
+                        .unwrap_or(default_expiration),
+                ),
+                fee: expected_fee,
+            },
+            memo: Memo(0),
+            icrc1_memo: arg.memo.map(|x| x.0),
+            created_at_time: arg
+                .created_at_time
+                .map(TimeStamp::from_nanos_since_unix_epoch),
+        };
+        let (block_index, hash) = apply_transaction(&mut *ledger, tx, now, expected_fee)
+            .map_err(convert_transfer_error)
+            .map_err(|err| {
+                let err: ApproveError = match err.try_into() {
+                    Ok(err) => err,
+                    Err(err) => trap_with(&err),
+                };
+                err
+            })?;
+
+        set_certified_data(&hash.into_bytes());
+
+        block_index
+    };
+
+    let max_msg_size = *MAX_MESSAGE_SIZE_BYTES.read().unwrap();
+    archive_blocks::<Access>(DebugOutSink, max_msg_size as u64).await;
+    Ok(Nat::from(block_index))
+}
+
+#[export_name = "canister_update icrc2_approve"]
+fn icrc2_approve_candid() {
+    over_async_may_reject(candid_one, |arg: ApproveArgs| async {
+        if !LEDGER.read().unwrap().can_send(&caller()) {
+            return Err(
+                "Anonymous principal cannot approve token transfers on the ledger.".to_string(),
+            );
+        }
+
+        Ok(icrc2_approve(arg).await)
+    })
+}
+
+#[candid_method(update, rename = "icrc2_transfer_from")]
+async fn icrc2_transfer_from(arg: TransferFromArgs) -> Result<Nat, TransferFromError> {
+    let block_index = {
+        let mut ledger = LEDGER.write().unwrap();
+        if !ledger.feature_flags.icrc2 {
+            trap_with("ICRC-2 features are not enabled on the ledger.");
+        }
+        let now = TimeStamp::from_nanos_since_unix_epoch(time_nanos());
+
+        let spender_account = Account {
+            owner: caller().into(),
+            subaccount: arg.spender_subaccount,
+        };
+        let from = AccountIdentifier::from(arg.from);
+        let to = AccountIdentifier::from(arg.to);
+        // NB. The ledger only sees account identifiers, which hide the owner
+        // principal. We set the spender to the source account if the caller
+        // owns it so that the transfer bypasses the allowance check.
+        let spender = if spender_account == arg.from {
+            from
+        } else {
+            AccountIdentifier::from(spender_account)
+        };
+        let minting_acc = ledger
+            .minting_account_id
+            .expect("Minting canister id not initialized");
+        if from == minting_acc {
+            trap_with("the minter account cannot delegate mints");
+        }
+        if to == minting_acc {
+            trap_with("the ledger does not support burning tokens with icrc2_transfer_from");
+        }
+        match arg.memo.as_ref() {
+            Some(memo) if memo.0.len() > MEMO_SIZE_BYTES => {
+                trap_with("the memo field is too large")
+            }
+            _ => {}
+        };
+        let amount = match arg.amount.0.to_u64() {
+            Some(n) => Tokens::from_e8s(n),
+            None => {
+                // No one can have so many tokens
+                let balance = Nat::from(ledger.balances.account_balance(&from).get_e8s());
+                assert!(balance < arg.amount);
+                return Err(TransferFromError::InsufficientFunds { balance });
+            }
+        };
+        let expected_fee = ledger.transfer_fee;
+        if arg.fee.is_some() && arg.fee.as_ref() != Some(&Nat::from(expected_fee.get_e8s())) {
+            return Err(TransferFromError::BadFee {
+                expected_fee: Nat::from(expected_fee.get_e8s()),
+            });
+        }
+
+        let tx = Transaction {
+            operation: Operation::TransferFrom {
+                from,
+                to,
+                spender,
+                amount,
+                fee: expected_fee,
+            },
+            memo: Memo(0),
+            icrc1_memo: arg.memo.map(|x| x.0),
+            created_at_time: arg
+                .created_at_time
+                .map(TimeStamp::from_nanos_since_unix_epoch),
+        };
+        let (block_index, hash) = apply_transaction(&mut *ledger, tx, now, expected_fee)
+            .map_err(convert_transfer_error)
+            .map_err(|err| {
+                let err: TransferFromError = match err.try_into() {
+                    Ok(err) => err,
+                    Err(err) => trap_with(&err),
+                };
+                err
+            })?;
+
+        set_certified_data(&hash.into_bytes());
+
+        block_index
+    };
+
+    let max_msg_size = *MAX_MESSAGE_SIZE_BYTES.read().unwrap();
+    archive_blocks::<Access>(DebugOutSink, max_msg_size as u64).await;
+    Ok(Nat::from(block_index))
+}
+
+#[export_name = "canister_update icrc2_transfer_from"]
+fn icrc2_transfer_from_candid() {
+    over_async_may_reject(candid_one, |arg: TransferFromArgs| async {
+        if !LEDGER.read().unwrap().can_send(&caller()) {
+            return Err("Anonymous principal cannot hold tokens on the ledger.".to_string());
+        }
+
+        Ok(icrc2_transfer_from(arg).await)
+    })
+}
+
+#[candid_method(query, rename = "icrc2_allowance")]
+fn icrc2_allowance(arg: AllowanceArgs) -> Allowance {
+    let ledger = LEDGER.read().unwrap();
+    if !ledger.feature_flags.icrc2 {
+        trap_with("ICRC-2 features are not enabled on the ledger.");
+    }
+    let now = TimeStamp::from_nanos_since_unix_epoch(time_nanos());
+    let allowance = ledger.approvals.allowance(
+        &AccountIdentifier::from(arg.account),
+        &AccountIdentifier::from(arg.spender),
+        now,
+    );
+    Allowance {
+        allowance: Nat::from(allowance.amount.get_e8s()),
+        expires_at: allowance.expires_at.map(|t| t.as_nanos_since_unix_epoch()),
+    }
+}
+
+#[export_name = "canister_query icrc2_allowance"]
+fn icrc2_allowance_candid() {
+    over(candid_one, icrc2_allowance)
+}
+
 /// See caveats of use on send_dfx
 #[cfg(feature = "notify-method")]
 #[export_name = "canister_update notify_dfx"]
@@ -256,6 +256,7 @@ fn serialize() {
         None,
         Some("ICP".into()),
         Some("icp".into()),
+        None,
     );
 
     let txn = Transaction::new(
@@ -590,6 +591,7 @@ fn get_blocks_returns_correct_blocks() {
         None,
         Some("ICP".into()),
         Some("icp".into()),
+        None,
     );
 
     for i in 0..10 {
@@ -651,6 +653,7 @@ fn test_purge() {
         None,
         Some("ICP".into()),
         Some("icp".into()),
+        None,
     );
     let little_later = genesis + Duration::from_millis(1);
 
@@ -853,6 +856,7 @@ fn test_approvals_are_not_cumulative() {
             from,
             spender,
             allowance: approved_amount,
+            expected_allowance: None,
             expires_at: None,
             fee,
         },
@@ -880,6 +884,7 @@ fn test_approvals_are_not_cumulative() {
             from,
             spender,
             allowance: new_allowance,
+            expected_allowance: None,
             expires_at: Some(expiration),
             fee,
         },
@@ -934,6 +939,7 @@ fn test_approval_transfer_from() {
             from,
             spender,
             allowance: tokens(150_000),
+            expected_allowance: None,
             expires_at: None,
             fee,
         },
@@ -963,7 +969,7 @@ fn test_approval_transfer_from() {
     assert_eq!(
         ctx.approvals().allowance(&from, &spender, now),
         Allowance {
-            amount: tokens(50_000),
+            amount: tokens(40_000),
             expires_at: None
         },
     );
@@ -982,14 +988,14 @@ fn test_approval_transfer_from() {
         )
         .unwrap_err(),
         TxApplyError::InsufficientAllowance {
-            allowance: tokens(50_000)
+            allowance: tokens(40_000)
         }
     );
 
     assert_eq!(
         ctx.approvals().allowance(&from, &spender, now),
         Allowance {
-            amount: tokens(50_000),
+            amount: tokens(40_000),
             expires_at: None
         },
     );
@@ -997,6 +1003,57 @@ fn test_approval_transfer_from() {
     assert_eq!(ctx.balances().account_balance(&to), tokens(100_000),);
 }
 
+#[test]
+fn test_approval_expected_allowance() {
+    let mut ctx = Ledger::default();
+
+    let from = test_account_id(1);
+    let spender = test_account_id(2);
+    let now = ts(1000);
+
+    ctx.balances_mut().mint(&from, tokens(100_000)).unwrap();
+
+    let approve = |amount: Tokens, expected_allowance: Option<Tokens>| Operation::Approve {
+        from,
+        spender,
+        allowance: amount,
+        expected_allowance,
+        expires_at: None,
+        fee: tokens(10_000),
+    };
+
+    apply_operation(&mut ctx, &approve(tokens(50_000), Some(Tokens::ZERO)), now).unwrap();
+
+    assert_eq!(
+        apply_operation(
+            &mut ctx,
+            &approve(tokens(20_000), Some(tokens(40_000))),
+            now
+        )
+        .unwrap_err(),
+        TxApplyError::AllowanceChanged {
+            current_allowance: tokens(50_000)
+        }
+    );
+    assert_eq!(ctx.balances().account_balance(&from), tokens(90_000));
+
+    apply_operation(
+        &mut ctx,
+        &approve(tokens(20_000), Some(tokens(50_000))),
+        now,
+    )
+    .unwrap();
+
+    assert_eq!(
+        ctx.approvals().allowance(&from, &spender, now),
+        Allowance {
+            amount: tokens(20_000),
+            expires_at: None
+        },
+    );
+    assert_eq!(ctx.balances().account_balance(&from), tokens(80_000));
+}
+
 #[test]
 fn test_approval_expiration_override() {
     let mut ctx = Ledger::default();
@@ -1011,6 +1068,7 @@ fn test_approval_expiration_override() {
         from,
         spender,
         allowance: amount,
+        expected_allowance: None,
         expires_at: expires_at.map(ts),
         fee: tokens(10_000),
     };
@@ -1077,6 +1135,7 @@ fn test_approval_no_fee_on_reject() {
                 from,
                 spender,
                 allowance: tokens(1_000),
+                expected_allowance: None,
                 expires_at: Some(ts(1)),
                 fee: tokens(10_000),
             },
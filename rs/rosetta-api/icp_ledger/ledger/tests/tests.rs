@@ -6,14 +6,18 @@ use ic_icrc1_ledger_sm_tests::{transfer, MINTER};
 use ic_ledger_core::{block::BlockType, Tokens};
 use ic_state_machine_tests::{ErrorCode, PrincipalId, StateMachine, UserError};
 use icp_ledger::{
-    AccountIdentifier, ArchiveOptions, Block, CandidBlock, GetBlocksArgs, GetBlocksRes, InitArgs,
-    LedgerCanisterInitPayload, LedgerCanisterPayload, Operation, QueryBlocksResponse,
-    QueryEncodedBlocksResponse,
+    AccountIdentifier, ArchiveOptions, Block, CandidBlock, FeatureFlags, GetBlocksArgs,
+    GetBlocksRes, InitArgs, LedgerCanisterInitPayload, LedgerCanisterPayload,
+    LedgerCanisterUpgradePayload, Operation, QueryBlocksResponse, QueryEncodedBlocksResponse,
 };
 use icrc_ledger_types::icrc1::{
     account::Account,
     transfer::{Memo, TransferArg, TransferError},
 };
+use icrc_ledger_types::icrc2::{
+    approve::{ApproveArgs, ApproveError},
+    transfer_from::{TransferFromArgs, TransferFromError},
+};
 use on_wire::{FromWire, IntoWire};
 use serde_bytes::ByteBuf;
 use std::collections::{HashMap, HashSet};
@@ -32,15 +36,19 @@ fn encode_init_args(args: ic_icrc1_ledger_sm_tests::InitArgs) -> LedgerCanisterI
         .into_iter()
         .map(|(account, amount)| (account.into(), Tokens::from_e8s(amount)))
         .collect();
-    LedgerCanisterInitPayload::builder()
+    let mut builder = LedgerCanisterInitPayload::builder()
         .initial_values(initial_values)
         .minting_account(args.minting_account.into())
         .icrc1_minting_account(args.minting_account)
         .archive_options(args.archive_options)
         .transfer_fee(Tokens::from_e8s(args.transfer_fee))
-        .token_symbol_and_name(&args.token_symbol, &args.token_name)
-        .build()
-        .unwrap()
+        .token_symbol_and_name(&args.token_symbol, &args.token_name);
+    if let Some(feature_flags) = args.feature_flags {
+        builder = builder.feature_flags(FeatureFlags {
+            icrc2: feature_flags.icrc2,
+        });
+    }
+    builder.build().unwrap()
 }
 
 fn query_blocks(
@@ -188,6 +196,184 @@ fn check_transfer_model() {
     ic_icrc1_ledger_sm_tests::check_transfer_model(ledger_wasm(), encode_init_args);
 }
 
+#[test]
+fn test_approve_smoke() {
+    ic_icrc1_ledger_sm_tests::test_approve_smoke(ledger_wasm(), encode_init_args);
+}
+
+#[test]
+fn test_approve_expiration() {
+    ic_icrc1_ledger_sm_tests::test_approve_expiration(ledger_wasm(), encode_init_args);
+}
+
+#[test]
+fn test_approve_self() {
+    ic_icrc1_ledger_sm_tests::test_approve_self(ledger_wasm(), encode_init_args);
+}
+
+#[test]
+fn test_approve_expected_allowance() {
+    ic_icrc1_ledger_sm_tests::test_approve_expected_allowance(ledger_wasm(), encode_init_args);
+}
+
+#[test]
+fn test_approve_cant_pay_fee() {
+    ic_icrc1_ledger_sm_tests::test_approve_cant_pay_fee(ledger_wasm(), encode_init_args);
+}
+
+#[test]
+fn test_approve_cap() {
+    ic_icrc1_ledger_sm_tests::test_approve_cap(ledger_wasm(), encode_init_args);
+}
+
+#[test]
+fn test_approve_pruning() {
+    ic_icrc1_ledger_sm_tests::test_approve_pruning(ledger_wasm(), encode_init_args);
+}
+
+#[test]
+fn test_approve_max_expiration() {
+    ic_icrc1_ledger_sm_tests::test_approve_max_expiration(ledger_wasm(), encode_init_args);
+}
+
+#[test]
+fn test_approve_from_minter() {
+    ic_icrc1_ledger_sm_tests::test_approve_from_minter(ledger_wasm(), encode_init_args);
+}
+
+#[test]
+fn test_transfer_from_smoke() {
+    ic_icrc1_ledger_sm_tests::test_transfer_from_smoke(ledger_wasm(), encode_init_args);
+}
+
+#[test]
+fn test_transfer_from_self() {
+    ic_icrc1_ledger_sm_tests::test_transfer_from_self(ledger_wasm(), encode_init_args);
+}
+
+#[test]
+fn test_transfer_from_minter() {
+    ic_icrc1_ledger_sm_tests::test_transfer_from_minter(ledger_wasm(), encode_init_args);
+}
+
+#[test]
+fn test_icrc2_feature_flag() {
+    let env = StateMachine::new();
+    let from = PrincipalId::new_user_test_id(1);
+    let spender = PrincipalId::new_user_test_id(2);
+    let to = PrincipalId::new_user_test_id(3);
+
+    let mut initial_values = HashMap::new();
+    initial_values.insert(Account::from(from.0).into(), Tokens::from_e8s(100_000));
+    let init_args = LedgerCanisterInitPayload::builder()
+        .minting_account(MINTER.into())
+        .icrc1_minting_account(MINTER)
+        .initial_values(initial_values)
+        .transfer_fee(Tokens::from_e8s(10_000))
+        .token_symbol_and_name("ICP", "Internet Computer")
+        .build()
+        .unwrap();
+    let canister_id = env
+        .install_canister(ledger_wasm(), Encode!(&init_args).unwrap(), None)
+        .expect("Unable to install the Ledger canister");
+
+    let approve_args = ApproveArgs {
+        from_subaccount: None,
+        spender: spender.0.into(),
+        amount: Nat::from(150_000),
+        expected_allowance: None,
+        expires_at: None,
+        fee: None,
+        memo: None,
+        created_at_time: None,
+    };
+    let err = env
+        .execute_ingress_as(
+            from,
+            canister_id,
+            "icrc2_approve",
+            Encode!(&approve_args).unwrap(),
+        )
+        .unwrap_err();
+    assert_eq!(err.code(), ErrorCode::CanisterCalledTrap);
+    assert!(err
+        .description()
+        .ends_with("ICRC-2 features are not enabled on the ledger."));
+
+    let upgrade_args = LedgerCanisterUpgradePayload::builder()
+        .feature_flags(FeatureFlags { icrc2: true })
+        .build()
+        .unwrap();
+    env.upgrade_canister(
+        canister_id,
+        ledger_wasm(),
+        Encode!(&Some(upgrade_args)).unwrap(),
+    )
+    .expect("failed to upgrade the ledger canister");
+
+    let standards: Vec<String> = ic_icrc1_ledger_sm_tests::supported_standards(&env, canister_id)
+        .into_iter()
+        .map(|standard| standard.name)
+        .collect();
+    assert_eq!(standards, vec!["ICRC-1", "ICRC-2"]);
+
+    let res = env
+        .execute_ingress_as(
+            from,
+            canister_id,
+            "icrc2_approve",
+            Encode!(&approve_args).unwrap(),
+        )
+        .expect("failed to call icrc2_approve");
+    assert_eq!(
+        Decode!(&res.bytes(), Result<Nat, ApproveError>).unwrap(),
+        Ok(Nat::from(1))
+    );
+
+    let transfer_from_args = TransferFromArgs {
+        spender_subaccount: None,
+        from: from.0.into(),
+        to: to.0.into(),
+        amount: Nat::from(50_000),
+        fee: None,
+        memo: None,
+        created_at_time: None,
+    };
+    let res = env
+        .execute_ingress_as(
+            spender,
+            canister_id,
+            "icrc2_transfer_from",
+            Encode!(&transfer_from_args).unwrap(),
+        )
+        .expect("failed to call icrc2_transfer_from");
+    assert_eq!(
+        Decode!(&res.bytes(), Result<Nat, TransferFromError>).unwrap(),
+        Ok(Nat::from(2))
+    );
+    // 100_000 minus the approve fee, the amount, and the transfer_from fee.
+    assert_eq!(
+        ic_icrc1_ledger_sm_tests::balance_of(&env, canister_id, from.0),
+        30_000
+    );
+    assert_eq!(
+        ic_icrc1_ledger_sm_tests::balance_of(&env, canister_id, to.0),
+        50_000
+    );
+
+    // The Approve and TransferFrom blocks are served by query_blocks.
+    let blocks = query_blocks(&env, from.0, canister_id, 0, u32::MAX.into()).blocks;
+    assert_eq!(blocks.len(), 3);
+    assert!(matches!(
+        Operation::try_from(blocks[1].transaction.operation.clone().unwrap()).unwrap(),
+        Operation::Approve { .. }
+    ));
+    assert!(matches!(
+        Operation::try_from(blocks[2].transaction.operation.clone().unwrap()).unwrap(),
+        Operation::TransferFrom { .. }
+    ));
+}
+
 #[test]
 fn check_old_init() {
     let env = StateMachine::new();
@@ -202,6 +388,7 @@ fn check_old_init() {
         transfer_fee: None,
         token_symbol: Some("ICP".into()),
         token_name: Some("Internet Computer".into()),
+        feature_flags: None,
     })
     .unwrap();
     env.install_canister(ledger_wasm(), old_init, None)
@@ -222,6 +409,7 @@ fn check_new_init() {
         transfer_fee: None,
         token_symbol: Some("ICP".into()),
         token_name: Some("Internet Computer".into()),
+        feature_flags: None,
     }))
     .unwrap();
     env.install_canister(ledger_wasm(), new_init, None)
@@ -242,6 +430,7 @@ fn check_memo() {
         transfer_fee: None,
         token_symbol: Some("ICP".into()),
         token_name: Some("Internet Computer".into()),
+        feature_flags: None,
     }))
     .unwrap();
     let ledger_id = env
@@ -330,6 +519,7 @@ fn check_query_blocks_coherence() {
         transfer_fee: Some(Tokens::from_e8s(10_000)),
         token_symbol: Some("ICP".into()),
         token_name: Some("Internet Computer".into()),
+        feature_flags: None,
     }))
     .unwrap();
     let canister_id = env
@@ -421,6 +611,7 @@ fn test_block_transformation() {
         transfer_fee: Some(Tokens::from_e8s(10_000)),
         token_symbol: Some("ICP".into()),
         token_name: Some("Internet Computer".into()),
+        feature_flags: None,
     }))
     .unwrap();
     let canister_id = env
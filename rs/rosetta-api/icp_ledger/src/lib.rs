@@ -99,6 +99,8 @@ pub enum Operation {
         from: AccountIdentifier,
         spender: AccountIdentifier,
         allowance: Tokens,
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        expected_allowance: Option<Tokens>,
         expires_at: Option<TimeStamp>,
         fee: Tokens,
     },
@@ -134,6 +136,7 @@ where
             from,
             spender,
             allowance,
+            expected_allowance,
             expires_at,
             fee,
         } => {
@@ -145,7 +148,14 @@ where
 
             let result = context
                 .approvals_mut()
-                .approve(from, spender, *allowance, *expires_at, now, None)
+                .approve(
+                    from,
+                    spender,
+                    *allowance,
+                    *expires_at,
+                    now,
+                    *expected_allowance,
+                )
                 .map_err(TxApplyError::from);
             if let Err(e) = result {
                 context
@@ -177,8 +187,15 @@ where
                 return Ok(());
             }
 
+            // The spender pays both the amount and the fee from the allowance.
             let allowance = context.approvals().allowance(from, spender, now);
-            if allowance.amount < *amount {
+            let used_allowance =
+                amount
+                    .checked_add(fee)
+                    .ok_or(TxApplyError::InsufficientAllowance {
+                        allowance: allowance.amount,
+                    })?;
+            if allowance.amount < used_allowance {
                 return Err(TxApplyError::InsufficientAllowance {
                     allowance: allowance.amount,
                 });
@@ -188,7 +205,7 @@ where
                 .transfer(from, to, *amount, *fee, None)?;
             context
                 .approvals_mut()
-                .use_allowance(from, spender, *amount, now)
+                .use_allowance(from, spender, used_allowance, now)
                 .expect("bug: cannot use allowance");
         }
     };
@@ -411,6 +428,27 @@ pub struct UpgradeArgs {
 
     #[serde(default, skip_serializing_if = "Option::is_none")]
     pub icrc1_minting_account: Option<Account>,
+
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub feature_flags: Option<FeatureFlags>,
+}
+
+/// Optional ledger features that can be switched on at init or upgrade.
+#[derive(Serialize, Deserialize, CandidType, Clone, Debug, PartialEq, Eq)]
+pub struct FeatureFlags {
+    pub icrc2: bool,
+}
+
+impl FeatureFlags {
+    const fn const_default() -> Self {
+        Self { icrc2: false }
+    }
+}
+
+impl Default for FeatureFlags {
+    fn default() -> Self {
+        Self::const_default()
+    }
 }
 
 // This is how we pass arguments to 'init' in main.rs
@@ -426,6 +464,8 @@ pub struct InitArgs {
     pub transfer_fee: Option<Tokens>,
     pub token_symbol: Option<String>,
     pub token_name: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub feature_flags: Option<FeatureFlags>,
 }
 
 impl LedgerCanisterInitPayload {
@@ -457,6 +497,7 @@ pub struct LedgerCanisterInitPayloadBuilder {
     transfer_fee: Option<Tokens>,
     token_symbol: Option<String>,
     token_name: Option<String>,
+    feature_flags: Option<FeatureFlags>,
 }
 
 impl LedgerCanisterInitPayloadBuilder {
@@ -472,6 +513,7 @@ impl LedgerCanisterInitPayloadBuilder {
             transfer_fee: None,
             token_symbol: None,
             token_name: None,
+            feature_flags: None,
         }
     }
 
@@ -521,6 +563,11 @@ impl LedgerCanisterInitPayloadBuilder {
         self
     }
 
+    pub fn feature_flags(mut self, feature_flags: FeatureFlags) -> Self {
+        self.feature_flags = Some(feature_flags);
+        self
+    }
+
     pub fn build(self) -> Result<LedgerCanisterInitPayload, String> {
         let minting_account = self
             .minting_account
@@ -553,6 +600,7 @@ impl LedgerCanisterInitPayloadBuilder {
                 transfer_fee: self.transfer_fee,
                 token_symbol: self.token_symbol,
                 token_name: self.token_name,
+                feature_flags: self.feature_flags,
             },
         )))
     }
@@ -561,6 +609,7 @@ impl LedgerCanisterInitPayloadBuilder {
 pub struct LedgerCanisterUpgradePayloadBuilder {
     maximum_number_of_accounts: Option<usize>,
     icrc1_minting_account: Option<Account>,
+    feature_flags: Option<FeatureFlags>,
 }
 
 impl LedgerCanisterUpgradePayloadBuilder {
@@ -568,6 +617,7 @@ impl LedgerCanisterUpgradePayloadBuilder {
         Self {
             maximum_number_of_accounts: None,
             icrc1_minting_account: None,
+            feature_flags: None,
         }
     }
 
@@ -581,11 +631,17 @@ impl LedgerCanisterUpgradePayloadBuilder {
         self
     }
 
+    pub fn feature_flags(mut self, feature_flags: FeatureFlags) -> Self {
+        self.feature_flags = Some(feature_flags);
+        self
+    }
+
     pub fn build(self) -> Result<LedgerCanisterUpgradePayload, String> {
         Ok(LedgerCanisterUpgradePayload(
             LedgerCanisterPayload::Upgrade(Some(UpgradeArgs {
                 maximum_number_of_accounts: self.maximum_number_of_accounts,
                 icrc1_minting_account: self.icrc1_minting_account,
+                feature_flags: self.feature_flags,
             })),
         ))
     }
@@ -774,6 +830,7 @@ pub enum CandidOperation {
         allowance: Tokens,
         fee: Tokens,
         expires_at: Option<TimeStamp>,
+        expected_allowance: Option<Tokens>,
     },
     TransferFrom {
         from: AccountIdBlob,
@@ -810,6 +867,7 @@ impl From<Operation> for CandidOperation {
                 from,
                 spender,
                 allowance,
+                expected_allowance,
                 fee,
                 expires_at,
             } => Self::Approve {
@@ -819,6 +877,7 @@ impl From<Operation> for CandidOperation {
                 fee,
                 expires_at,
                 allowance,
+                expected_allowance,
             },
             Operation::TransferFrom {
                 from,
@@ -870,11 +929,13 @@ impl TryFrom<CandidOperation> for Operation {
                 fee,
                 expires_at,
                 allowance,
+                expected_allowance,
                 ..
             } => Operation::Approve {
                 spender: address_to_accountidentifier(spender)?,
                 from: address_to_accountidentifier(from)?,
                 allowance,
+                expected_allowance,
                 fee,
                 expires_at,
             },
@@ -62,6 +62,7 @@ prop_compose! {
         from in arb_account_id(),
         spender in arb_account_id(),
         allowance in arb_tokens(),
+        expected_allowance in proptest::option::of(arb_tokens()),
         expires_at in proptest::option::of(arb_ts()),
         fee in 0..100_000u64,
     ) -> Operation {
@@ -69,6 +70,7 @@ prop_compose! {
             from,
             spender,
             allowance,
+            expected_allowance,
             expires_at,
             fee: Tokens::from_e8s(fee)
         }
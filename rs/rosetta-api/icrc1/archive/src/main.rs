@@ -3,17 +3,20 @@ use ic_canisters_http_types::{HttpRequest, HttpResponse, HttpResponseBuilder};
 use ic_cdk_macros::{init, post_upgrade, query, update};
 use ic_icrc1::{blocks::encoded_block_to_generic_block, Block};
 use ic_ledger_core::block::{BlockIndex, BlockType, EncodedBlock};
+use ic_ledger_core::mmr::{contains_node, MmrAccumulator, MmrHash, NodeId};
 use ic_stable_structures::memory_manager::{MemoryId, VirtualMemory};
 use ic_stable_structures::{
     cell::Cell as StableCell, log::Log as StableLog, memory_manager::MemoryManager,
-    DefaultMemoryImpl, RestrictedMemory, Storable,
+    DefaultMemoryImpl, RestrictedMemory, StableBTreeMap, Storable,
 };
 use icrc_ledger_types::icrc3::blocks::BlockRange;
 use icrc_ledger_types::icrc3::blocks::GenericBlock as IcrcBlock;
+use icrc_ledger_types::icrc3::proofs::{ArchivedBlockPath, MmrNode};
 
 use icrc_ledger_types::icrc3::transactions::Transaction;
 use icrc_ledger_types::icrc3::transactions::{GetTransactionsRequest, TransactionRange};
 use serde::{Deserialize, Serialize};
+use serde_bytes::ByteBuf;
 use std::borrow::Cow;
 use std::cell::RefCell;
 
@@ -32,10 +35,15 @@ const NUM_WASM_PAGES: u64 = 4 * GIB / WASM_PAGE_SIZE;
 
 const BLOCK_LOG_INDEX_MEMORY_ID: MemoryId = MemoryId::new(0);
 const BLOCK_LOG_DATA_MEMORY_ID: MemoryId = MemoryId::new(1);
+const MMR_ACCUMULATOR_MEMORY_ID: MemoryId = MemoryId::new(2);
+const MMR_NODES_MEMORY_ID: MemoryId = MemoryId::new(3);
 
 type Memory = RestrictedMemory<DefaultMemoryImpl>;
 type BlockLog = StableLog<Vec<u8>, VirtualMemory<Memory>, VirtualMemory<Memory>>;
 type ConfigCell = StableCell<ArchiveConfig, Memory>;
+type MmrCell = StableCell<MmrState, VirtualMemory<Memory>>;
+// (height, index) -> hash
+type MmrNodes = StableBTreeMap<(u64, u64), MmrHash, VirtualMemory<Memory>>;
 
 /// Creates a memory region for the configuration stable cell.
 fn config_memory() -> Memory {
@@ -61,6 +69,16 @@ thread_local! {
     static BLOCKS: RefCell<BlockLog> = with_memory_manager(|memory_manager| {
         RefCell::new(BlockLog::init(memory_manager.get(BLOCK_LOG_INDEX_MEMORY_ID), memory_manager.get(BLOCK_LOG_DATA_MEMORY_ID)).expect("failed to initialize stable log"))
     });
+
+    /// The peaks of the Merkle mountain range over the archived block hashes.
+    static MMR: RefCell<MmrCell> = with_memory_manager(|memory_manager| {
+        RefCell::new(MmrCell::init(memory_manager.get(MMR_ACCUMULATOR_MEMORY_ID), MmrState::default()).expect("failed to initialize stable cell"))
+    });
+
+    /// The nodes of the Merkle mountain range over the archived block hashes.
+    static MMR_NODES: RefCell<MmrNodes> = with_memory_manager(|memory_manager| {
+        RefCell::new(MmrNodes::init(memory_manager.get(MMR_NODES_MEMORY_ID)))
+    });
 }
 
 /// Configuration of the archive node.
@@ -102,6 +120,26 @@ impl Storable for ArchiveConfig {
     }
 }
 
+/// The state of the Merkle mountain range over the archived blocks.
+#[derive(Clone, Default, Serialize, Deserialize)]
+struct MmrState {
+    /// None if the archive was created by a version without the MMR and
+    /// has not been upgraded yet.
+    accumulator: Option<MmrAccumulator>,
+}
+
+impl Storable for MmrState {
+    fn to_bytes(&self) -> Cow<[u8]> {
+        let mut buf = vec![];
+        ciborium::ser::into_writer(self, &mut buf).expect("failed to encode mmr state");
+        Cow::Owned(buf)
+    }
+
+    fn from_bytes(bytes: Cow<'_, [u8]>) -> Self {
+        ciborium::de::from_reader(&bytes[..]).expect("failed to decode mmr state")
+    }
+}
+
 /// A helper function to access the configuration.
 fn with_archive_opts<R>(f: impl FnOnce(&ArchiveConfig) -> R) -> R {
     CONFIG.with(|cell| f(cell.borrow().get()))
@@ -117,6 +155,40 @@ fn with_blocks<R>(f: impl FnOnce(&BlockLog) -> R) -> R {
     BLOCKS.with(|cell| f(&cell.borrow()))
 }
 
+/// Starts the MMR at the given block if the archive does not have one yet.
+fn init_mmr(start: u64) {
+    MMR.with(|cell| {
+        let mut cell = cell.borrow_mut();
+        if cell.get().accumulator.is_none() {
+            cell.set(MmrState {
+                accumulator: Some(MmrAccumulator::new(start)),
+            })
+            .expect("failed to set mmr state");
+        }
+    });
+}
+
+/// Appends the hashes of the blocks to the MMR and stores the new nodes.
+fn append_to_mmr(blocks: &[EncodedBlock]) {
+    MMR.with(|cell| {
+        let mut cell = cell.borrow_mut();
+        let mut state = cell.get().clone();
+        let accumulator = state
+            .accumulator
+            .as_mut()
+            .expect("bug: the mmr is not initialized");
+        MMR_NODES.with(|nodes| {
+            let mut nodes = nodes.borrow_mut();
+            for block in blocks {
+                accumulator.append(Block::block_hash(block).into_bytes(), |node, hash| {
+                    nodes.insert((node.height as u64, node.index), hash);
+                });
+            }
+        });
+        cell.set(state).expect("failed to set mmr state");
+    });
+}
+
 fn decode_transaction(txid: u64, bytes: Vec<u8>) -> Transaction {
     Block::decode(EncodedBlock::from(bytes))
         .unwrap_or_else(|e| ic_cdk::api::trap(&format!("failed to decode block {}: {}", txid, e)))
@@ -161,7 +233,9 @@ fn init(
                 memory_manager.get(BLOCK_LOG_DATA_MEMORY_ID),
             )
         });
-    })
+    });
+
+    init_mmr(block_index_offset);
 }
 
 #[post_upgrade]
@@ -173,6 +247,12 @@ fn post_upgrade() {
     // the upgrade if the initialization traps.
     let max_memory_size_bytes = with_archive_opts(|opts| opts.max_memory_size_bytes);
     with_blocks(|blocks| assert!(blocks.log_size_bytes() <= max_memory_size_bytes));
+
+    // Archives created before the MMR existed only cover the blocks they
+    // receive from now on.
+    let next_block_index =
+        with_archive_opts(|opts| opts.block_index_offset) + with_blocks(|blocks| blocks.len());
+    init_mmr(next_block_index);
 }
 
 #[update]
@@ -188,6 +268,8 @@ fn append_blocks(new_blocks: Vec<EncodedBlock>) {
         opts.max_memory_size_bytes
     });
 
+    append_to_mmr(&new_blocks);
+
     with_blocks(|blocks| {
         let bytes: u64 = new_blocks.iter().map(|b| b.size_bytes() as u64).sum();
         if max_memory_size_bytes < blocks.log_size_bytes().saturating_add(bytes) {
@@ -201,6 +283,36 @@ fn append_blocks(new_blocks: Vec<EncodedBlock>) {
     })
 }
 
+/// Returns the path from the block to the largest MMR node stored in this
+/// archive that covers the block. The ledger serves the rest of the proof
+/// starting at the returned node.
+#[query]
+#[candid_method(query)]
+fn get_block_proof(block_index: BlockIndex) -> Option<ArchivedBlockPath> {
+    let range = MMR.with(|cell| Some(cell.borrow().get().accumulator.as_ref()?.range()))?;
+    if !range.contains(&block_index) {
+        return None;
+    }
+    MMR_NODES.with(|nodes| {
+        let nodes = nodes.borrow();
+        let mut node = NodeId::leaf(block_index);
+        let mut siblings = vec![];
+        while contains_node(range.start, range.end, &node.parent()) {
+            let sibling = node.sibling();
+            let hash = nodes.get(&(sibling.height as u64, sibling.index))?;
+            siblings.push(ByteBuf::from(hash.to_vec()));
+            node = node.parent();
+        }
+        Some(ArchivedBlockPath {
+            node: MmrNode {
+                height: node.height,
+                index: node.index,
+            },
+            siblings,
+        })
+    })
+}
+
 #[query]
 #[candid_method(query)]
 fn remaining_capacity() -> u64 {
@@ -299,6 +411,12 @@ fn encode_metrics(w: &mut ic_metrics_encoder::MetricsEncoder<Vec<u8>>) -> std::i
         "Total number of blocks stored in the main memory.",
     )?;
 
+    w.encode_gauge(
+        "archive_stored_mmr_nodes",
+        MMR_NODES.with(|nodes| nodes.borrow().len()) as f64,
+        "Total number of Merkle mountain range nodes stored in the stable memory.",
+    )?;
+
     Ok(())
 }
 
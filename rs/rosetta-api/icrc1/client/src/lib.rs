@@ -3,10 +3,15 @@ use candid::types::number::Nat;
 use candid::utils::{ArgumentDecoder, ArgumentEncoder};
 use candid::Principal;
 use ic_ledger_core::block::BlockIndex;
+use ic_ledger_core::mmr::{follow_path, peak_ids, root_hash, MmrHash, NodeId};
 use icrc_ledger_types::icrc::generic_metadata_value::MetadataValue as Value;
 use icrc_ledger_types::icrc1::account::Account;
 use icrc_ledger_types::icrc1::transfer::{TransferArg, TransferError};
+use icrc_ledger_types::icrc3::proofs::{
+    ArchivedBlockPath, BlockProof, GetBlockProofArgs, GetBlockProofError,
+};
 use num_traits::ToPrimitive;
+use serde_bytes::ByteBuf;
 
 // Abstraction over the runtime. Implement this in terms of cdk call if you use
 // the cdk or dfn_* if you use dfn_* call.
@@ -112,6 +117,102 @@ impl<R: Runtime> ICRC1Client<R> {
             .map(untuple)?;
         Ok(result.map(nat_to_u64))
     }
+
+    /// Fetches the inclusion proof for the block with the given index. If the
+    /// block is archived, the first part of the proof comes from the archive.
+    /// Use [verify_block_proof] to check the result.
+    pub async fn get_block_proof(
+        &self,
+        block_index: BlockIndex,
+    ) -> Result<Result<(Option<ArchivedBlockPath>, BlockProof), GetBlockProofError>, (i32, String)>
+    {
+        let mut args = GetBlockProofArgs {
+            block_index: Nat::from(block_index),
+            from_node: None,
+        };
+        let result: Result<BlockProof, GetBlockProofError> = self
+            .runtime
+            .call(self.ledger_canister_id, "get_block_proof", (args.clone(),))
+            .await
+            .map(untuple)?;
+        let archive = match result {
+            Err(GetBlockProofError::BlockArchived { archive }) => archive,
+            result => return Ok(result.map(|proof| (None, proof))),
+        };
+        let path: Option<ArchivedBlockPath> = self
+            .runtime
+            .call(archive, "get_block_proof", (block_index,))
+            .await
+            .map(untuple)?;
+        let path = match path {
+            Some(path) => path,
+            None => return Ok(Err(GetBlockProofError::BlockArchived { archive })),
+        };
+        args.from_node = Some(path.node);
+        let result: Result<BlockProof, GetBlockProofError> = self
+            .runtime
+            .call(self.ledger_canister_id, "get_block_proof", (args,))
+            .await
+            .map(untuple)?;
+        Ok(result.map(|proof| (Some(path), proof)))
+    }
+}
+
+fn to_hashes(bufs: &[ByteBuf]) -> Result<Vec<MmrHash>, String> {
+    bufs.iter()
+        .map(|buf| {
+            MmrHash::try_from(buf.as_slice())
+                .map_err(|_| format!("expected a 32-byte hash, got {} bytes", buf.len()))
+        })
+        .collect()
+}
+
+/// Checks that the block with the given index and hash is part of the MMR
+/// described by the proof and returns the root hash of that MMR.
+///
+/// The proof is valid if the returned hash equals the `mmr_root` label of the
+/// hash tree that the ledger certifies (see `get_data_certificate`).
+pub fn verify_block_proof(
+    block_index: BlockIndex,
+    block_hash: MmrHash,
+    archived_path: Option<&ArchivedBlockPath>,
+    proof: &BlockProof,
+) -> Result<MmrHash, String> {
+    if !(proof.mmr_start..proof.mmr_end).contains(&block_index) {
+        return Err(format!(
+            "block {} is not in the proof range [{}, {})",
+            block_index, proof.mmr_start, proof.mmr_end
+        ));
+    }
+
+    let mut node = NodeId::leaf(block_index);
+    let mut hash = block_hash;
+    if let Some(path) = archived_path {
+        (node, hash) = follow_path(node, hash, &to_hashes(&path.siblings)?);
+        if node.height != path.node.height || node.index != path.node.index {
+            return Err(format!(
+                "the archived path leads to {:?} instead of {:?}",
+                node, path.node
+            ));
+        }
+    }
+    let (peak, peak_hash) = follow_path(node, hash, &to_hashes(&proof.siblings)?);
+
+    let peaks = to_hashes(&proof.peaks)?;
+    let expected_peaks = peak_ids(proof.mmr_start, proof.mmr_end);
+    if peaks.len() != expected_peaks.len() {
+        return Err(format!(
+            "expected {} peaks, got {}",
+            expected_peaks.len(),
+            peaks.len()
+        ));
+    }
+    match expected_peaks.iter().position(|id| id == &peak) {
+        Some(i) if peaks[i] == peak_hash => {}
+        Some(_) => return Err(format!("hash mismatch at peak {:?}", peak)),
+        None => return Err(format!("the path leads to {:?}, which is not a peak", peak)),
+    }
+    Ok(root_hash(proof.mmr_start, proof.mmr_end, &peaks))
 }
 
 // extract the element from an unary tuple
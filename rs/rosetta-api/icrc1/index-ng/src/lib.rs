@@ -7,6 +7,31 @@ use icrc_ledger_types::icrc3::transactions::Transaction;
 /// The maximum number of blocks to return in a single [get_blocks] request.
 pub const DEFAULT_MAX_BLOCKS_PER_RESPONSE: u64 = 2000;
 
+/// The maximum number of allowances to return in a single [list_allowances] request.
+pub const DEFAULT_MAX_ALLOWANCES_PER_RESPONSE: u64 = 500;
+
+/// The maximum number of subscriptions the index accepts.
+pub const MAX_SUBSCRIPTIONS: u64 = 1000;
+
+/// The maximum number of accounts and owners in a subscription filter.
+pub const MAX_SUBSCRIPTION_FILTER_SIZE: u64 = 100;
+
+/// The maximum length in bytes of the method receiving the notifications.
+pub const MAX_SUBSCRIPTION_METHOD_LENGTH: u64 = 100;
+
+/// The maximum number of block indexes queued for a subscriber. Older
+/// block indexes are dropped when the queue is full.
+pub const MAX_PENDING_BLOCKS_PER_SUBSCRIPTION: u64 = 10_000;
+
+/// The maximum number of block indexes in a single [Notification].
+pub const MAX_BLOCKS_PER_NOTIFICATION: u64 = 500;
+
+/// The cycles charged to a subscriber for each [Notification].
+pub const NOTIFICATION_FEE: u128 = 5_000_000;
+
+/// The maximum number of days to return in a single [get_daily_stats] request.
+pub const MAX_DAILY_STATS_PER_RESPONSE: u64 = 366;
+
 #[derive(CandidType, Debug, Deserialize)]
 pub enum IndexArg {
     Init(InitArg),
@@ -79,3 +104,98 @@ pub struct Status {
 pub struct FeeCollectorRanges {
     pub ranges: Vec<(Account, Vec<(BlockIndex, BlockIndex)>)>,
 }
+
+#[derive(CandidType, Debug, Deserialize, PartialEq, Eq)]
+pub struct SubscribeArgs {
+    // The accounts whose blocks should be notified.
+    pub accounts: Vec<Account>,
+    // The principals whose blocks should be notified, for any of
+    // their subaccounts.
+    pub owners: Vec<Principal>,
+    // The method of the subscriber that receives the notifications.
+    // The method must accept a single [Notification] argument.
+    pub method: String,
+}
+
+#[derive(CandidType, Debug, Deserialize, PartialEq, Eq)]
+pub enum SubscribeError {
+    // The caller is the anonymous principal.
+    AnonymousCaller,
+    // The filter contains more than [MAX_SUBSCRIPTION_FILTER_SIZE] entries.
+    FilterTooLarge { max_size: u64 },
+    // The method is longer than [MAX_SUBSCRIPTION_METHOD_LENGTH] bytes.
+    MethodTooLong { max_length: u64 },
+    // The index has reached [MAX_SUBSCRIPTIONS] subscriptions.
+    TooManySubscriptions { max_subscriptions: u64 },
+    // The subscription has not enough cycles to pay for a
+    // single notification.
+    InsufficientCycles { balance: Nat, notification_fee: Nat },
+}
+
+#[derive(CandidType, Debug, Deserialize, PartialEq, Eq)]
+pub enum UnsubscribeError {
+    // The caller has no subscription.
+    NotSubscribed,
+    // The unused cycles could not be returned to the caller. The
+    // subscription is kept without its filter, so that calling
+    // unsubscribe again retries returning the cycles.
+    RefundFailed { cycles_balance: Nat, reason: String },
+}
+
+#[derive(CandidType, Clone, Debug, Deserialize, PartialEq, Eq)]
+pub struct SubscriptionInfo {
+    pub accounts: Vec<Account>,
+    pub owners: Vec<Principal>,
+    pub method: String,
+    // The cycles left to pay for the notifications.
+    pub cycles_balance: Nat,
+    // The cycles charged for each notification.
+    pub notification_fee: Nat,
+    // The number of block indexes waiting to be notified.
+    pub pending_blocks: u64,
+    // The number of block indexes dropped because the queue was full
+    // since the last successful notification.
+    pub dropped_blocks: u64,
+    // The number of consecutive notifications that failed.
+    pub failed_attempts: u32,
+}
+
+/// The argument of the one-way call sent to the subscribers.
+#[derive(CandidType, Debug, Deserialize, PartialEq, Eq)]
+pub struct Notification {
+    pub ledger_id: Principal,
+    // The indexes of the new blocks matching the subscription filter,
+    // in increasing order.
+    pub block_indexes: Vec<BlockIndex>,
+    // The number of matching block indexes dropped because the queue
+    // was full since the last notification.
+    pub dropped_blocks: u64,
+}
+
+#[derive(CandidType, Debug, Deserialize, PartialEq, Eq)]
+pub struct GetDailyStatsArgs {
+    // The timestamp in nanoseconds of a moment in the first day to return.
+    // If None then the results will start from the first day with blocks.
+    pub start: Option<u64>,
+    // Maximum number of days to fetch.
+    pub limit: Option<u64>,
+}
+
+/// The aggregated activity of the ledger during a UTC day.
+#[derive(CandidType, Clone, Debug, Default, Deserialize, PartialEq, Eq)]
+pub struct DailyStats {
+    // The timestamp in nanoseconds of the start of the day.
+    pub day_start: u64,
+    // The number of transfers, including the ones made by a spender.
+    pub transfer_count: u64,
+    // The total amount transferred, excluding fees.
+    pub transfer_volume: Nat,
+    // The fees credited to a fee collector.
+    pub fees_collected: Nat,
+    // The fees burned because no fee collector was set.
+    pub fees_burned: Nat,
+    pub mint_count: u64,
+    pub mint_volume: Nat,
+    pub burn_count: u64,
+    pub burn_volume: Nat,
+}
@@ -1,16 +1,22 @@
-use candid::{candid_method, Nat, Principal};
+use candid::{candid_method, CandidType, Decode, Encode, Nat, Principal};
 use ic_canister_profiler::{measure_span, SpanStats};
 use ic_canisters_http_types::{HttpRequest, HttpResponse, HttpResponseBuilder};
+use ic_cdk::api::call::{msg_cycles_accept128, msg_cycles_available128};
+use ic_cdk::api::management_canister::main::{deposit_cycles, CanisterIdRecord};
 use ic_cdk::trap;
-use ic_cdk_macros::{init, post_upgrade, query};
+use ic_cdk_macros::{init, post_upgrade, query, update};
 use ic_cdk_timers::TimerId;
 use ic_crypto_sha::Sha256;
 use ic_icrc1::blocks::{encoded_block_to_generic_block, generic_block_to_encoded_block};
 use ic_icrc1::{Block, Operation};
 use ic_icrc1_index_ng::{
-    FeeCollectorRanges, GetAccountTransactionsArgs, GetAccountTransactionsResponse,
-    GetAccountTransactionsResult, IndexArg, ListSubaccountsArgs, Status, TransactionWithId,
-    DEFAULT_MAX_BLOCKS_PER_RESPONSE,
+    DailyStats, FeeCollectorRanges, GetAccountTransactionsArgs, GetAccountTransactionsResponse,
+    GetAccountTransactionsResult, GetDailyStatsArgs, IndexArg, ListSubaccountsArgs, Notification,
+    Status, SubscribeArgs, SubscribeError, SubscriptionInfo, TransactionWithId, UnsubscribeError,
+    DEFAULT_MAX_ALLOWANCES_PER_RESPONSE, DEFAULT_MAX_BLOCKS_PER_RESPONSE,
+    MAX_BLOCKS_PER_NOTIFICATION, MAX_DAILY_STATS_PER_RESPONSE, MAX_PENDING_BLOCKS_PER_SUBSCRIPTION,
+    MAX_SUBSCRIPTIONS, MAX_SUBSCRIPTION_FILTER_SIZE, MAX_SUBSCRIPTION_METHOD_LENGTH,
+    NOTIFICATION_FEE,
 };
 use ic_ledger_core::block::{BlockIndex as BlockIndex64, BlockType, EncodedBlock};
 use ic_stable_structures::memory_manager::{MemoryId, VirtualMemory};
@@ -20,6 +26,9 @@ use ic_stable_structures::{
     StableLog, Storable,
 };
 use icrc_ledger_types::icrc1::account::{Account, Subaccount};
+use icrc_ledger_types::icrc2::allowance::{
+    ListAllowancesArgs, ListAllowancesResponse, SpenderAllowance,
+};
 use icrc_ledger_types::icrc3::archive::{ArchivedRange, QueryBlockArchiveFn};
 use icrc_ledger_types::icrc3::blocks::{
     BlockRange, GenericBlock, GetBlocksRequest, GetBlocksResponse,
@@ -31,10 +40,10 @@ use serde::{Deserialize, Serialize};
 use std::borrow::Cow;
 use std::cell::RefCell;
 use std::cmp::Reverse;
-use std::collections::HashMap;
+use std::collections::{BTreeSet, HashMap};
 use std::convert::TryFrom;
 use std::hash::Hash;
-use std::ops::Bound::{Excluded, Included};
+use std::ops::Bound::{Excluded, Included, Unbounded};
 use std::ops::Range;
 use std::time::Duration;
 
@@ -43,9 +52,21 @@ const BLOCK_LOG_INDEX_MEMORY_ID: MemoryId = MemoryId::new(1);
 const BLOCK_LOG_DATA_MEMORY_ID: MemoryId = MemoryId::new(2);
 const ACCOUNT_BLOCK_IDS_MEMORY_ID: MemoryId = MemoryId::new(3);
 const ACCOUNT_DATA_MEMORY_ID: MemoryId = MemoryId::new(4);
+const ALLOWANCES_MEMORY_ID: MemoryId = MemoryId::new(5);
+const ALLOWANCE_EXPIRATIONS_MEMORY_ID: MemoryId = MemoryId::new(6);
+const SUBSCRIPTION_QUEUES_MEMORY_ID: MemoryId = MemoryId::new(7);
+const DAILY_STATS_MEMORY_ID: MemoryId = MemoryId::new(8);
+const SUBSCRIPTIONS_MEMORY_ID: MemoryId = MemoryId::new(9);
+
+const NANOS_PER_DAY: u64 = 24 * 60 * 60 * 1_000_000_000;
 
 const DEFAULT_MAX_WAIT_TIME: Duration = Duration::from_secs(2);
 const DEFAULT_RETRY_WAIT_TIME: Duration = Duration::from_secs(1);
+const MAX_NOTIFICATION_BACKOFF: Duration = Duration::from_secs(60 * 60);
+// The maximum number of stored blocks processed by a single [build_index]
+// call when building the data of blocks indexed by an older version, see
+// [index_stored_blocks].
+const MAX_STORED_BLOCKS_PER_BATCH: u64 = 10_000;
 
 type VM = VirtualMemory<DefaultMemoryImpl>;
 type StateCell = StableCell<State, VM>;
@@ -60,6 +81,33 @@ type AccountBlockIdsMap = StableBTreeMap<AccountBlockIdsMapKey, (), VM>;
 type AccountDataMapKey = (AccountDataType, (Blob<29>, [u8; 32]));
 type AccountDataMap = StableBTreeMap<AccountDataMapKey, u64, VM>;
 
+// The allowances are keyed by the (from, spender) pair, both accounts
+// represented as principal of type Blob<29> and the effective subaccount.
+// The value is the remaining amount and the expiration timestamp
+// in nanoseconds, where u64::MAX means that the allowance never expires.
+type AllowanceMapKey = ((Blob<29>, [u8; 32]), (Blob<29>, [u8; 32]));
+type AllowanceMap = StableBTreeMap<AllowanceMapKey, (u64, u64), VM>;
+
+// The allowances sorted by expiration timestamp, used to prune the expired
+// allowances. An allowance may appear more than once if its expiration
+// changed, the stale entries are skipped during pruning.
+type AllowanceExpirationsMapKey = (u64, AllowanceMapKey);
+type AllowanceExpirationsMap = StableBTreeMap<AllowanceExpirationsMapKey, (), VM>;
+
+// The subscriptions to the indexed blocks, keyed by the subscriber
+// principal of type Blob<29>.
+type SubscriptionsMap = StableBTreeMap<Blob<29>, Subscription, VM>;
+
+// The block indexes waiting to be notified to a subscriber, keyed by
+// the subscriber principal of type Blob<29> and the block index.
+type SubscriptionQueuesMapKey = (Blob<29>, u64);
+type SubscriptionQueuesMap = StableBTreeMap<SubscriptionQueuesMapKey, (), VM>;
+
+// The daily aggregates of the indexed blocks, keyed by the number of days
+// since the Unix epoch and the [DailyStatsEntry] type.
+type DailyStatsMapKey = (u64, u32);
+type DailyStatsMap = StableBTreeMap<DailyStatsMapKey, u64, VM>;
+
 thread_local! {
     /// Static memory manager to manage the memory available for stable structures.
     static MEMORY_MANAGER: RefCell<MemoryManager<DefaultMemoryImpl>> = RefCell::new(MemoryManager::init(DefaultMemoryImpl::default()));
@@ -87,6 +135,31 @@ thread_local! {
         RefCell::new(AccountDataMap::init(memory_manager.get(ACCOUNT_DATA_MEMORY_ID)))
     });
 
+    /// Map that contains the active allowances.
+    static ALLOWANCES: RefCell<AllowanceMap> = with_memory_manager(|memory_manager| {
+        RefCell::new(AllowanceMap::init(memory_manager.get(ALLOWANCES_MEMORY_ID)))
+    });
+
+    /// Map that contains the expiration timestamps of the allowances.
+    static ALLOWANCE_EXPIRATIONS: RefCell<AllowanceExpirationsMap> = with_memory_manager(|memory_manager| {
+        RefCell::new(AllowanceExpirationsMap::init(memory_manager.get(ALLOWANCE_EXPIRATIONS_MEMORY_ID)))
+    });
+
+    /// Map that contains the subscriptions to the indexed blocks.
+    static SUBSCRIPTIONS: RefCell<SubscriptionsMap> = with_memory_manager(|memory_manager| {
+        RefCell::new(SubscriptionsMap::init(memory_manager.get(SUBSCRIPTIONS_MEMORY_ID)))
+    });
+
+    /// Map that contains the block indexes waiting to be notified to the subscribers.
+    static SUBSCRIPTION_QUEUES: RefCell<SubscriptionQueuesMap> = with_memory_manager(|memory_manager| {
+        RefCell::new(SubscriptionQueuesMap::init(memory_manager.get(SUBSCRIPTION_QUEUES_MEMORY_ID)))
+    });
+
+    /// Map that contains the daily aggregates of the indexed blocks.
+    static DAILY_STATS: RefCell<DailyStatsMap> = with_memory_manager(|memory_manager| {
+        RefCell::new(DailyStatsMap::init(memory_manager.get(DAILY_STATS_MEMORY_ID)))
+    });
+
     /// Profiling data to understand cycles usage
     static PROFILING_DATA: RefCell<SpanStats> = RefCell::new(SpanStats::default());
 }
@@ -107,6 +180,126 @@ struct State {
 
     // The fees collectors with the ranges of blocks for which they collected the fee.
     fee_collectors: HashMap<Account, Vec<Range<BlockIndex64>>>,
+
+    // Equals to `true` if the allowances have been built from all the indexed
+    // blocks. This is not the case after an upgrade from a version of the
+    // index that did not index allowances, see [index_stored_blocks].
+    #[serde(default)]
+    allowances_indexed: bool,
+
+    // The number of blocks, from the start of the block log, whose allowance
+    // changes have been processed while `allowances_indexed` is `false`.
+    #[serde(default)]
+    allowances_indexed_blocks: u64,
+
+    // Equals to `true` if the daily stats have been built from all the indexed
+    // blocks. This is not the case after an upgrade from a version of the
+    // index that did not compute daily stats, see [index_stored_blocks].
+    #[serde(default)]
+    daily_stats_indexed: bool,
+
+    // Equals to `true` once the daily stats computed by a version of the
+    // index that did not compute them for all the indexed blocks have been
+    // dropped, while `daily_stats_indexed` is `false`.
+    #[serde(default)]
+    daily_stats_cleared: bool,
+
+    // The number of blocks, from the start of the block log, that have been
+    // added to the daily stats while `daily_stats_indexed` is `false`.
+    #[serde(default)]
+    daily_stats_indexed_blocks: u64,
+}
+
+#[derive(CandidType, Clone, Debug, Default, Serialize, Deserialize)]
+struct Subscription {
+    // The accounts whose blocks are notified.
+    accounts: BTreeSet<Account>,
+
+    // The principals whose blocks are notified, for any subaccount.
+    owners: BTreeSet<Principal>,
+
+    // The method of the subscriber receiving the notifications.
+    method: String,
+
+    // The cycles left to pay for the notifications.
+    cycles_balance: u128,
+
+    // The number of block indexes in the subscriber queue.
+    pending_blocks: u64,
+
+    // The number of block indexes dropped since the last notification.
+    dropped_blocks: u64,
+
+    // The number of consecutive notifications that failed.
+    failed_attempts: u32,
+
+    // The time in nanoseconds before which no notification is attempted.
+    next_attempt_at: u64,
+}
+
+impl Subscription {
+    fn matches(&self, accounts: &[Account]) -> bool {
+        accounts
+            .iter()
+            .any(|account| self.accounts.contains(account) || self.owners.contains(&account.owner))
+    }
+
+    fn info(&self) -> SubscriptionInfo {
+        SubscriptionInfo {
+            accounts: self.accounts.iter().cloned().collect(),
+            owners: self.owners.iter().cloned().collect(),
+            method: self.method.clone(),
+            cycles_balance: Nat::from(self.cycles_balance),
+            notification_fee: Nat::from(NOTIFICATION_FEE),
+            pending_blocks: self.pending_blocks,
+            dropped_blocks: self.dropped_blocks,
+            failed_attempts: self.failed_attempts,
+        }
+    }
+}
+
+// Subscriptions are encoded with Candid, which is more compact than CBOR
+// for the principals and subaccounts of the filter.
+impl Storable for Subscription {
+    fn to_bytes(&self) -> Cow<[u8]> {
+        Cow::Owned(Encode!(self).expect("failed to encode subscription"))
+    }
+
+    fn from_bytes(bytes: Cow<'_, [u8]>) -> Self {
+        Decode!(&bytes, Self).expect("failed to decode subscription")
+    }
+}
+
+impl BoundedStorable for Subscription {
+    // Large enough for the encoding of a subscription with the maximum
+    // number of accounts in its filter and the longest method, see
+    // [test_subscription_max_size].
+    const MAX_SIZE: u32 = 10 * 1024;
+    const IS_FIXED_SIZE: bool = false;
+}
+
+#[test]
+fn test_subscription_max_size() {
+    let subscription = Subscription {
+        accounts: (0..MAX_SUBSCRIPTION_FILTER_SIZE)
+            .map(|i| {
+                let mut subaccount = [0xff; 32];
+                subaccount[..8].copy_from_slice(&i.to_be_bytes());
+                Account {
+                    owner: Principal::from_slice(&[0xff; 29]),
+                    subaccount: Some(subaccount),
+                }
+            })
+            .collect(),
+        owners: BTreeSet::new(),
+        method: "m".repeat(MAX_SUBSCRIPTION_METHOD_LENGTH as usize),
+        cycles_balance: u128::MAX,
+        pending_blocks: u64::MAX,
+        dropped_blocks: u64::MAX,
+        failed_attempts: u32::MAX,
+        next_attempt_at: u64::MAX,
+    };
+    assert!(subscription.to_bytes().len() <= Subscription::MAX_SIZE as usize);
 }
 
 // NOTE: the default configuration is dysfunctional, but it's convenient to have
@@ -119,6 +312,11 @@ impl Default for State {
             max_blocks_per_response: DEFAULT_MAX_BLOCKS_PER_RESPONSE,
             last_wait_time: Duration::from_secs(0),
             fee_collectors: Default::default(),
+            allowances_indexed: false,
+            allowances_indexed_blocks: 0,
+            daily_stats_indexed: false,
+            daily_stats_cleared: false,
+            daily_stats_indexed_blocks: 0,
         }
     }
 }
@@ -213,6 +411,31 @@ fn with_account_data<R>(f: impl FnOnce(&mut AccountDataMap) -> R) -> R {
     ACCOUNT_DATA.with(|cell| f(&mut cell.borrow_mut()))
 }
 
+/// A helper function to access the allowances.
+fn with_allowances<R>(f: impl FnOnce(&mut AllowanceMap) -> R) -> R {
+    ALLOWANCES.with(|cell| f(&mut cell.borrow_mut()))
+}
+
+/// A helper function to access the allowance expirations.
+fn with_allowance_expirations<R>(f: impl FnOnce(&mut AllowanceExpirationsMap) -> R) -> R {
+    ALLOWANCE_EXPIRATIONS.with(|cell| f(&mut cell.borrow_mut()))
+}
+
+/// A helper function to access the subscriptions.
+fn with_subscriptions<R>(f: impl FnOnce(&mut SubscriptionsMap) -> R) -> R {
+    SUBSCRIPTIONS.with(|cell| f(&mut cell.borrow_mut()))
+}
+
+/// A helper function to access the subscription queues.
+fn with_subscription_queues<R>(f: impl FnOnce(&mut SubscriptionQueuesMap) -> R) -> R {
+    SUBSCRIPTION_QUEUES.with(|cell| f(&mut cell.borrow_mut()))
+}
+
+/// A helper function to access the daily stats.
+fn with_daily_stats<R>(f: impl FnOnce(&mut DailyStatsMap) -> R) -> R {
+    DAILY_STATS.with(|cell| f(&mut cell.borrow_mut()))
+}
+
 /// A helper function that returns a decoded block stored in the
 /// block log at the given index or None if there is no block at that index.
 /// This function can trap if the index at the given block cannot be decoded
@@ -242,11 +465,23 @@ fn change_balance(account: Account, f: impl FnOnce(u64) -> u64) {
 }
 
 fn balance_key(account: Account) -> (AccountDataType, (Blob<29>, [u8; 32])) {
+    (AccountDataType::Balance, account_key(account))
+}
+
+fn account_key(account: Account) -> (Blob<29>, [u8; 32]) {
     let owner = Blob::try_from(account.owner.as_slice()).unwrap();
-    (
-        AccountDataType::Balance,
-        (owner, *account.effective_subaccount()),
-    )
+    (owner, *account.effective_subaccount())
+}
+
+fn account_from_key((owner, subaccount): (Blob<29>, [u8; 32])) -> Account {
+    Account {
+        owner: Principal::from_slice(owner.as_slice()),
+        subaccount: Some(subaccount),
+    }
+}
+
+fn allowance_key(from: Account, spender: Account) -> AllowanceMapKey {
+    (account_key(from), account_key(spender))
 }
 
 #[init]
@@ -260,6 +495,9 @@ fn init(index_arg: Option<IndexArg>) {
     // stable memory initialization
     mutate_state(|state| {
         state.ledger_id = init_arg.ledger_id;
+        // there are no blocks yet, so the allowances and daily stats are up to date
+        state.allowances_indexed = !cfg!(feature = "upgrade_test_old_version");
+        state.daily_stats_indexed = !cfg!(feature = "upgrade_test_old_version");
     });
 
     // set the first build_index to be called after init
@@ -316,6 +554,7 @@ pub async fn build_index() -> Result<(), String> {
     let failure_guard = guard((), |_| {
         set_build_index_timer(DEFAULT_RETRY_WAIT_TIME);
     });
+    let stored_blocks_indexed = index_stored_blocks();
     let next_txid = with_blocks(|blocks| blocks.len());
     let res = get_blocks_from_ledger(next_txid).await?;
     let mut tx_indexed_count: usize = 0;
@@ -337,7 +576,13 @@ pub async fn build_index() -> Result<(), String> {
     }
     tx_indexed_count += res.blocks.len();
     append_blocks(res.blocks);
-    let wait_time = compute_wait_time(tx_indexed_count);
+    notify_subscribers();
+    let wait_time = if stored_blocks_indexed {
+        compute_wait_time(tx_indexed_count)
+    } else {
+        // continue with the next batch of stored blocks right away
+        Duration::ZERO
+    };
     ic_cdk::eprintln!("Indexed: {} waiting : {:?}", tx_indexed_count, wait_time);
     mutate_state(|mut state| state.last_wait_time = wait_time);
     ScopeGuard::into_inner(failure_guard);
@@ -390,6 +635,21 @@ fn append_block(block_index: BlockIndex64, block: GenericBlock) {
 
         // change the balance of the involved accounts
         process_balance_changes(block_index, &decoded_block);
+
+        // change the allowances of the involved accounts, unless the
+        // allowances of the previous blocks are still being built
+        if with_state(|state| state.allowances_indexed) {
+            process_allowance_changes(block_index, &decoded_block);
+        }
+
+        // queue the block for the subscribers interested in it
+        enqueue_notifications(block_index, &decoded_block);
+
+        // add the block to the aggregates of its day, unless the daily
+        // stats of the previous blocks are still being built
+        if with_state(|state| state.daily_stats_indexed) {
+            process_daily_stats(block_index, &decoded_block);
+        }
     });
 }
 
@@ -469,6 +729,427 @@ fn process_balance_changes(block_index: BlockIndex64, block: &Block) {
     );
 }
 
+fn process_allowance_changes(block_index: BlockIndex64, block: &Block) {
+    measure_span(
+        &PROFILING_DATA,
+        "append_blocks.process_allowance_changes",
+        move || {
+            match block.transaction.operation {
+                Operation::Approve {
+                    from,
+                    spender,
+                    amount,
+                    expires_at,
+                    ..
+                } => {
+                    let expires_at = expires_at.map(|ts| ts.as_nanos_since_unix_epoch());
+                    set_allowance(from, spender, amount, expires_at);
+                }
+                Operation::Transfer {
+                    from,
+                    spender: Some(spender),
+                    amount,
+                    fee,
+                    ..
+                } if from != spender => {
+                    let fee = block.effective_fee.or(fee).unwrap_or_else(|| {
+                        ic_cdk::trap(&format!(
+                            "Block {} is of type Transfer but has no fee or effective fee!",
+                            block_index
+                        ))
+                    });
+                    use_allowance(block_index, from, spender, amount + fee);
+                }
+                Operation::Burn {
+                    from,
+                    spender: Some(spender),
+                    amount,
+                } if from != spender => use_allowance(block_index, from, spender, amount),
+                _ => {}
+            }
+            prune_expired_allowances(block.timestamp);
+        },
+    );
+}
+
+/// Builds the data that an older version of the index did not compute for the
+/// blocks it stored, in batches of at most [MAX_STORED_BLOCKS_PER_BATCH]
+/// blocks so that an upgrade of an index with many blocks doesn't run out of
+/// instructions. New blocks are only processed once all the stored blocks
+/// have been. Returns `true` when there is nothing left to do.
+fn index_stored_blocks() -> bool {
+    if cfg!(feature = "upgrade_test_old_version") {
+        return true;
+    }
+    let allowances_indexed = index_allowances_of_stored_blocks();
+    let daily_stats_indexed = index_daily_stats_of_stored_blocks();
+    allowances_indexed && daily_stats_indexed
+}
+
+/// Processes the allowance changes of the next batch of stored blocks, see
+/// [index_stored_blocks]. Returns `true` once all stored blocks have been
+/// processed.
+fn index_allowances_of_stored_blocks() -> bool {
+    if with_state(|state| state.allowances_indexed) {
+        return true;
+    }
+    let num_blocks = with_blocks(|blocks| blocks.len());
+    let start = with_state(|state| state.allowances_indexed_blocks);
+    let end = num_blocks.min(start.saturating_add(MAX_STORED_BLOCKS_PER_BATCH));
+    for block_index in start..end {
+        let block = get_decoded_block(block_index)
+            .unwrap_or_else(|| trap(&format!("Block {} not found in the block log", block_index)));
+        process_allowance_changes(block_index, &block);
+    }
+    ic_cdk::eprintln!("Indexed the allowances of blocks {}..{}", start, end);
+    mutate_state(|state| {
+        state.allowances_indexed_blocks = end;
+        state.allowances_indexed = end == num_blocks;
+    });
+    end == num_blocks
+}
+
+fn set_allowance(from: Account, spender: Account, amount: u64, expires_at: Option<u64>) {
+    let key = allowance_key(from, spender);
+    if amount == 0 {
+        with_allowances(|allowances| allowances.remove(&key));
+        return;
+    }
+    let expires_at = expires_at.unwrap_or(u64::MAX);
+    with_allowances(|allowances| allowances.insert(key.clone(), (amount, expires_at)));
+    if expires_at != u64::MAX {
+        with_allowance_expirations(|expirations| expirations.insert((expires_at, key), ()));
+    }
+}
+
+fn use_allowance(block_index: BlockIndex64, from: Account, spender: Account, amount: u64) {
+    let key = allowance_key(from, spender);
+    let (allowance, expires_at) = with_allowances(|allowances| allowances.get(&key))
+        .unwrap_or_else(|| {
+            ic_cdk::trap(&format!(
+                "Block {} uses the allowance of {} for spender {} but no allowance was found",
+                block_index, from, spender
+            ))
+        });
+    if allowance < amount {
+        ic_cdk::trap(&format!("Block {} caused an underflow for the allowance of {} for spender {} when calculating allowance {} - amount {}",
+            block_index, from, spender, allowance, amount));
+    }
+    if allowance == amount {
+        with_allowances(|allowances| allowances.remove(&key));
+    } else {
+        with_allowances(|allowances| allowances.insert(key, (allowance - amount, expires_at)));
+    }
+}
+
+/// Removes the allowances that expired at or before `now`.
+fn prune_expired_allowances(now: u64) {
+    loop {
+        let expired = with_allowance_expirations(|expirations| {
+            expirations
+                .iter()
+                .next()
+                .map(|(key, _)| key)
+                .filter(|(expires_at, _)| *expires_at <= now)
+        });
+        let Some((expires_at, key)) = expired else {
+            return;
+        };
+        with_allowance_expirations(|expirations| expirations.remove(&(expires_at, key.clone())));
+        // The expiration entry is stale if the allowance has been
+        // removed or its expiration has been changed in the meantime.
+        with_allowances(|allowances| {
+            if matches!(allowances.get(&key), Some((_, e)) if e == expires_at) {
+                allowances.remove(&key);
+            }
+        });
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+enum DailyStatsEntry {
+    TransferCount = 0,
+    TransferVolume = 1,
+    FeesCollected = 2,
+    FeesBurned = 3,
+    MintCount = 4,
+    MintVolume = 5,
+    BurnCount = 6,
+    BurnVolume = 7,
+}
+
+impl From<u32> for DailyStatsEntry {
+    fn from(num: u32) -> Self {
+        match num {
+            0 => DailyStatsEntry::TransferCount,
+            1 => DailyStatsEntry::TransferVolume,
+            2 => DailyStatsEntry::FeesCollected,
+            3 => DailyStatsEntry::FeesBurned,
+            4 => DailyStatsEntry::MintCount,
+            5 => DailyStatsEntry::MintVolume,
+            6 => DailyStatsEntry::BurnCount,
+            7 => DailyStatsEntry::BurnVolume,
+            _ => panic!("Invalid DailyStatsEntry value: {}", num),
+        }
+    }
+}
+
+fn add_daily_stat(day: u64, entry: DailyStatsEntry, value: u64) {
+    with_daily_stats(|stats| {
+        let key = (day, entry as u32);
+        let current = stats.get(&key).unwrap_or_default();
+        stats.insert(key, current.saturating_add(value));
+    });
+}
+
+fn process_daily_stats(block_index: BlockIndex64, block: &Block) {
+    measure_span(
+        &PROFILING_DATA,
+        "append_blocks.process_daily_stats",
+        move || {
+            let day = block.timestamp / NANOS_PER_DAY;
+            match block.transaction.operation {
+                Operation::Burn { amount, .. } => {
+                    add_daily_stat(day, DailyStatsEntry::BurnCount, 1);
+                    add_daily_stat(day, DailyStatsEntry::BurnVolume, amount);
+                }
+                Operation::Mint { amount, .. } => {
+                    add_daily_stat(day, DailyStatsEntry::MintCount, 1);
+                    add_daily_stat(day, DailyStatsEntry::MintVolume, amount);
+                }
+                Operation::Transfer { amount, fee, .. } => {
+                    // process_balance_changes already checked that the fee is set
+                    let fee = block.effective_fee.or(fee).unwrap_or_default();
+                    add_daily_stat(day, DailyStatsEntry::TransferCount, 1);
+                    add_daily_stat(day, DailyStatsEntry::TransferVolume, amount);
+                    if get_fee_collector(block_index, block).is_some() {
+                        add_daily_stat(day, DailyStatsEntry::FeesCollected, fee);
+                    } else {
+                        add_daily_stat(day, DailyStatsEntry::FeesBurned, fee);
+                    }
+                }
+                Operation::Approve { fee, .. } => {
+                    let fee = block.effective_fee.or(fee).unwrap_or_default();
+                    add_daily_stat(day, DailyStatsEntry::FeesBurned, fee);
+                }
+            }
+        },
+    );
+}
+
+/// Rebuilds the daily stats from the next batch of stored blocks, see
+/// [index_stored_blocks]. The stats computed so far are dropped first, in
+/// batches as well, since they may only cover the blocks indexed since an
+/// earlier upgrade. Returns `true` once all stored blocks have been processed.
+fn index_daily_stats_of_stored_blocks() -> bool {
+    if with_state(|state| state.daily_stats_indexed) {
+        return true;
+    }
+    if !with_state(|state| state.daily_stats_cleared) {
+        let keys: Vec<DailyStatsMapKey> = with_daily_stats(|stats| {
+            stats
+                .iter()
+                .take(MAX_STORED_BLOCKS_PER_BATCH as usize)
+                .map(|(key, _)| key)
+                .collect()
+        });
+        with_daily_stats(|stats| {
+            for key in &keys {
+                stats.remove(key);
+            }
+        });
+        if keys.len() as u64 == MAX_STORED_BLOCKS_PER_BATCH {
+            return false;
+        }
+        mutate_state(|state| state.daily_stats_cleared = true);
+    }
+    let num_blocks = with_blocks(|blocks| blocks.len());
+    let start = with_state(|state| state.daily_stats_indexed_blocks);
+    let end = num_blocks.min(start.saturating_add(MAX_STORED_BLOCKS_PER_BATCH));
+    for block_index in start..end {
+        let block = get_decoded_block(block_index)
+            .unwrap_or_else(|| trap(&format!("Block {} not found in the block log", block_index)));
+        process_daily_stats(block_index, &block);
+    }
+    ic_cdk::eprintln!("Indexed the daily stats of blocks {}..{}", start, end);
+    mutate_state(|state| {
+        state.daily_stats_indexed_blocks = end;
+        state.daily_stats_indexed = end == num_blocks;
+    });
+    end == num_blocks
+}
+
+fn set_daily_stat(stats: &mut DailyStats, entry: DailyStatsEntry, value: u64) {
+    match entry {
+        DailyStatsEntry::TransferCount => stats.transfer_count = value,
+        DailyStatsEntry::TransferVolume => stats.transfer_volume = value.into(),
+        DailyStatsEntry::FeesCollected => stats.fees_collected = value.into(),
+        DailyStatsEntry::FeesBurned => stats.fees_burned = value.into(),
+        DailyStatsEntry::MintCount => stats.mint_count = value,
+        DailyStatsEntry::MintVolume => stats.mint_volume = value.into(),
+        DailyStatsEntry::BurnCount => stats.burn_count = value,
+        DailyStatsEntry::BurnVolume => stats.burn_volume = value.into(),
+    }
+}
+
+/// Returns up to `limit` days with blocks, starting from the day `start_day`.
+fn daily_stats(start_day: u64, limit: usize) -> Vec<DailyStats> {
+    let mut result: Vec<DailyStats> = vec![];
+    with_daily_stats(|stats| {
+        for ((day, entry), value) in stats.range((start_day, 0)..) {
+            let day_start = day.saturating_mul(NANOS_PER_DAY);
+            if result.last().map(|s| s.day_start) != Some(day_start) {
+                if result.len() == limit {
+                    break;
+                }
+                result.push(DailyStats {
+                    day_start,
+                    ..Default::default()
+                });
+            }
+            set_daily_stat(result.last_mut().unwrap(), entry.into(), value);
+        }
+    });
+    result
+}
+
+fn subscriber_key(subscriber: Principal) -> Blob<29> {
+    Blob::try_from(subscriber.as_slice()).unwrap()
+}
+
+fn enqueue_notifications(block_index: BlockIndex64, block: &Block) {
+    let accounts = get_accounts(block);
+    with_subscriptions(|subscriptions| {
+        let matching: Vec<(Blob<29>, Subscription)> = subscriptions
+            .iter()
+            .filter(|(_, subscription)| subscription.matches(&accounts))
+            .collect();
+        for (key, mut subscription) in matching {
+            with_subscription_queues(|queues| {
+                queues.insert((key, block_index), ());
+                if subscription.pending_blocks < MAX_PENDING_BLOCKS_PER_SUBSCRIPTION {
+                    subscription.pending_blocks += 1;
+                } else {
+                    // The queue is full, drop the oldest block index.
+                    let oldest = queues
+                        .range((key, 0)..)
+                        .next()
+                        .map(|(oldest, _)| oldest)
+                        .expect("bug: the subscription queue is empty");
+                    queues.remove(&oldest);
+                    subscription.dropped_blocks += 1;
+                }
+            });
+            subscriptions.insert(key, subscription);
+        }
+    });
+}
+
+/// Returns the time to wait before notifying again a subscriber
+/// after the given number of consecutive failed notifications.
+fn notification_backoff(failed_attempts: u32) -> Duration {
+    DEFAULT_RETRY_WAIT_TIME
+        .checked_mul(2u32.saturating_pow(failed_attempts.saturating_sub(1)))
+        .unwrap_or(MAX_NOTIFICATION_BACKOFF)
+        .min(MAX_NOTIFICATION_BACKOFF)
+}
+
+/// A notification due to a subscriber, see [due_notifications].
+#[derive(Debug, PartialEq, Eq)]
+struct DueNotification {
+    subscriber: Principal,
+    method: String,
+    block_indexes: Vec<BlockIndex64>,
+    dropped_blocks: u64,
+}
+
+/// Returns the notifications due at `now` to the subscribers that have
+/// pending block indexes, are not backing off and can pay for the
+/// notification.
+fn due_notifications(now: u64) -> Vec<DueNotification> {
+    let due: Vec<(Blob<29>, Subscription)> = with_subscriptions(|subscriptions| {
+        subscriptions
+            .iter()
+            .filter(|(_, subscription)| {
+                subscription.pending_blocks > 0
+                    && subscription.next_attempt_at <= now
+                    && subscription.cycles_balance >= NOTIFICATION_FEE
+            })
+            .collect()
+    });
+    due.into_iter()
+        .map(|(key, subscription)| DueNotification {
+            subscriber: Principal::from_slice(key.as_slice()),
+            method: subscription.method,
+            block_indexes: with_subscription_queues(|queues| {
+                queues
+                    .range((key, 0)..)
+                    .take_while(|((k, _), _)| k == &key)
+                    .take(MAX_BLOCKS_PER_NOTIFICATION as usize)
+                    .map(|((_, block_index), _)| block_index)
+                    .collect()
+            }),
+            dropped_blocks: subscription.dropped_blocks,
+        })
+        .collect()
+}
+
+/// Removes the notified block indexes from the queue of the subscriber
+/// and charges the subscriber for the notification.
+fn on_notification_sent(notification: &DueNotification) {
+    let key = subscriber_key(notification.subscriber);
+    with_subscription_queues(|queues| {
+        for block_index in &notification.block_indexes {
+            queues.remove(&(key, *block_index));
+        }
+    });
+    with_subscriptions(|subscriptions| {
+        if let Some(mut subscription) = subscriptions.get(&key) {
+            subscription.pending_blocks -= notification.block_indexes.len() as u64;
+            subscription.dropped_blocks = 0;
+            subscription.failed_attempts = 0;
+            subscription.next_attempt_at = 0;
+            subscription.cycles_balance -= NOTIFICATION_FEE;
+            subscriptions.insert(key, subscription);
+        }
+    });
+}
+
+/// Makes the subscriber back off after a notification failed at `now`.
+fn on_notification_failed(subscriber: Principal, now: u64) {
+    let key = subscriber_key(subscriber);
+    with_subscriptions(|subscriptions| {
+        if let Some(mut subscription) = subscriptions.get(&key) {
+            subscription.failed_attempts += 1;
+            subscription.next_attempt_at = now.saturating_add(
+                notification_backoff(subscription.failed_attempts).as_nanos() as u64,
+            );
+            subscriptions.insert(key, subscription);
+        }
+    });
+}
+
+/// Sends a one-way notification to each subscriber that has pending
+/// block indexes, is not backing off and can pay for the notification.
+fn notify_subscribers() {
+    let now = ic_cdk::api::time();
+    let ledger_id = with_state(|state| state.ledger_id);
+    for due in due_notifications(now) {
+        let notification = Notification {
+            ledger_id,
+            block_indexes: due.block_indexes.iter().map(|i| Nat::from(*i)).collect(),
+            dropped_blocks: due.dropped_blocks,
+        };
+        match ic_cdk::api::call::notify(due.subscriber, &due.method, (notification,)) {
+            Ok(()) => on_notification_sent(&due),
+            Err(code) => {
+                ic_cdk::eprintln!("Failed to notify subscriber {}: {:?}", due.subscriber, code);
+                on_notification_failed(due.subscriber, now);
+            }
+        }
+    }
+}
+
 fn debit(block_index: BlockIndex64, account: Account, amount: u64) {
     change_balance(account, |balance| {
         if balance < amount {
@@ -767,6 +1448,148 @@ fn list_subaccounts(args: ListSubaccountsArgs) -> Vec<Subaccount> {
     })
 }
 
+#[query]
+#[candid_method(query)]
+fn list_allowances(args: ListAllowancesArgs) -> ListAllowancesResponse {
+    let now = ic_cdk::api::time();
+    let start_key = allowance_key(
+        args.account,
+        args.from_spender
+            .unwrap_or_else(|| Account::from(Principal::management_canister())),
+    );
+    let start = if args.from_spender.is_none() {
+        Included(start_key)
+    } else {
+        Excluded(start_key)
+    };
+    let from_key = account_key(args.account);
+    let limit = args
+        .limit
+        .unwrap_or(DEFAULT_MAX_ALLOWANCES_PER_RESPONSE)
+        .min(DEFAULT_MAX_ALLOWANCES_PER_RESPONSE) as usize;
+    with_allowances(|allowances| {
+        allowances
+            .range((start, Unbounded))
+            .take_while(|((from, _), _)| from == &from_key)
+            .filter(|(_, (_, expires_at))| *expires_at > now)
+            .take(limit)
+            .map(|((_, spender), (amount, expires_at))| SpenderAllowance {
+                spender: account_from_key(spender),
+                allowance: amount.into(),
+                expires_at: (expires_at != u64::MAX).then_some(expires_at),
+            })
+            .collect()
+    })
+}
+
+#[update]
+#[candid_method(update)]
+fn subscribe(args: SubscribeArgs) -> Result<SubscriptionInfo, SubscribeError> {
+    let caller = ic_cdk::api::caller();
+    if caller == Principal::anonymous() {
+        return Err(SubscribeError::AnonymousCaller);
+    }
+    if (args.accounts.len() + args.owners.len()) as u64 > MAX_SUBSCRIPTION_FILTER_SIZE {
+        return Err(SubscribeError::FilterTooLarge {
+            max_size: MAX_SUBSCRIPTION_FILTER_SIZE,
+        });
+    }
+    if args.method.len() as u64 > MAX_SUBSCRIPTION_METHOD_LENGTH {
+        return Err(SubscribeError::MethodTooLong {
+            max_length: MAX_SUBSCRIPTION_METHOD_LENGTH,
+        });
+    }
+    let key = subscriber_key(caller);
+    let (num_subscriptions, subscription) =
+        with_subscriptions(|subscriptions| (subscriptions.len(), subscriptions.get(&key)));
+    let cycles_balance = subscription
+        .as_ref()
+        .map(|subscription| subscription.cycles_balance);
+    if cycles_balance.is_none() && num_subscriptions >= MAX_SUBSCRIPTIONS {
+        return Err(SubscribeError::TooManySubscriptions {
+            max_subscriptions: MAX_SUBSCRIPTIONS,
+        });
+    }
+    let balance = cycles_balance
+        .unwrap_or(0)
+        .saturating_add(msg_cycles_available128());
+    if balance < NOTIFICATION_FEE {
+        return Err(SubscribeError::InsufficientCycles {
+            balance: Nat::from(balance),
+            notification_fee: Nat::from(NOTIFICATION_FEE),
+        });
+    }
+    let accepted = msg_cycles_accept128(msg_cycles_available128());
+    let mut subscription = subscription.unwrap_or_default();
+    subscription.accounts = args.accounts.into_iter().collect();
+    subscription.owners = args.owners.into_iter().collect();
+    subscription.method = args.method;
+    subscription.cycles_balance += accepted;
+    let info = subscription.info();
+    with_subscriptions(|subscriptions| subscriptions.insert(key, subscription));
+    Ok(info)
+}
+
+#[update]
+#[candid_method(update)]
+async fn unsubscribe() -> Result<SubscriptionInfo, UnsubscribeError> {
+    let caller = ic_cdk::api::caller();
+    let key = subscriber_key(caller);
+    let subscription = with_subscriptions(|subscriptions| subscriptions.remove(&key))
+        .ok_or(UnsubscribeError::NotSubscribed)?;
+    with_subscription_queues(|queues| {
+        let block_indexes: Vec<SubscriptionQueuesMapKey> = queues
+            .range((key, 0)..)
+            .take_while(|((k, _), _)| k == &key)
+            .map(|(k, _)| k)
+            .collect();
+        for k in block_indexes {
+            queues.remove(&k);
+        }
+    });
+    // Return the unused cycles to the subscriber.
+    if subscription.cycles_balance > 0 {
+        if let Err((code, msg)) = deposit_cycles(
+            CanisterIdRecord {
+                canister_id: caller,
+            },
+            subscription.cycles_balance,
+        )
+        .await
+        {
+            ic_cdk::eprintln!(
+                "Failed to return {} cycles to {}: {:?} {}",
+                subscription.cycles_balance,
+                caller,
+                code,
+                msg
+            );
+            // Keep the cycles for the caller without notifying any block,
+            // adding them to a subscription made in the meantime if any.
+            let cycles_balance = with_subscriptions(|subscriptions| {
+                let mut refundable = subscriptions.get(&key).unwrap_or_default();
+                refundable.cycles_balance += subscription.cycles_balance;
+                let cycles_balance = refundable.cycles_balance;
+                subscriptions.insert(key, refundable);
+                cycles_balance
+            });
+            return Err(UnsubscribeError::RefundFailed {
+                cycles_balance: Nat::from(cycles_balance),
+                reason: format!("{:?}: {}", code, msg),
+            });
+        }
+    }
+    Ok(subscription.info())
+}
+
+#[query]
+#[candid_method(query)]
+fn get_subscription(subscriber: Principal) -> Option<SubscriptionInfo> {
+    with_subscriptions(|subscriptions| subscriptions.get(&subscriber_key(subscriber)))
+        .as_ref()
+        .map(Subscription::info)
+}
+
 #[candid_method(query)]
 #[query]
 fn http_request(req: HttpRequest) -> HttpResponse {
@@ -789,6 +1612,10 @@ fn http_request(req: HttpRequest) -> HttpResponse {
     }
 }
 
+fn nat_to_f64(n: &Nat) -> f64 {
+    n.0.to_f64().unwrap_or(f64::MAX)
+}
+
 pub fn encode_metrics(w: &mut ic_metrics_encoder::MetricsEncoder<Vec<u8>>) -> std::io::Result<()> {
     w.encode_gauge(
         "index_stable_memory_pages",
@@ -815,6 +1642,72 @@ pub fn encode_metrics(w: &mut ic_metrics_encoder::MetricsEncoder<Vec<u8>>) -> st
         with_blocks(|blocks| blocks.len()) as f64,
         "Total number of blocks stored in the stable memory.",
     )?;
+    w.encode_gauge(
+        "index_number_of_allowances",
+        with_allowances(|allowances| allowances.len()) as f64,
+        "Total number of allowances stored in the stable memory.",
+    )?;
+    w.encode_gauge(
+        "index_number_of_subscriptions",
+        with_subscriptions(|subscriptions| subscriptions.len()) as f64,
+        "Total number of subscriptions to the indexed blocks.",
+    )?;
+    w.encode_gauge(
+        "index_pending_notifications",
+        with_subscription_queues(|queues| queues.len()) as f64,
+        "Total number of block indexes waiting to be notified to the subscribers.",
+    )?;
+    // The aggregates of the most recent day with blocks.
+    let last_day = with_blocks(|blocks| blocks.len().checked_sub(1))
+        .and_then(get_decoded_block)
+        .map(|block| block.timestamp / NANOS_PER_DAY);
+    if let Some(stats) = last_day.and_then(|day| daily_stats(day, 1).pop()) {
+        w.encode_gauge(
+            "index_daily_stats_day_start",
+            stats.day_start as f64,
+            "Start of the most recent day with blocks in nanoseconds since the Unix epoch.",
+        )?;
+        w.encode_gauge(
+            "index_daily_transfer_count",
+            stats.transfer_count as f64,
+            "Number of transfers in the most recent day with blocks.",
+        )?;
+        w.encode_gauge(
+            "index_daily_transfer_volume",
+            nat_to_f64(&stats.transfer_volume),
+            "Amount transferred in the most recent day with blocks.",
+        )?;
+        w.encode_gauge(
+            "index_daily_fees_collected",
+            nat_to_f64(&stats.fees_collected),
+            "Fees credited to the fee collector in the most recent day with blocks.",
+        )?;
+        w.encode_gauge(
+            "index_daily_fees_burned",
+            nat_to_f64(&stats.fees_burned),
+            "Fees burned in the most recent day with blocks.",
+        )?;
+        w.encode_gauge(
+            "index_daily_mint_count",
+            stats.mint_count as f64,
+            "Number of mints in the most recent day with blocks.",
+        )?;
+        w.encode_gauge(
+            "index_daily_mint_volume",
+            nat_to_f64(&stats.mint_volume),
+            "Amount minted in the most recent day with blocks.",
+        )?;
+        w.encode_gauge(
+            "index_daily_burn_count",
+            stats.burn_count as f64,
+            "Number of burns in the most recent day with blocks.",
+        )?;
+        w.encode_gauge(
+            "index_daily_burn_volume",
+            nat_to_f64(&stats.burn_volume),
+            "Amount burned in the most recent day with blocks.",
+        )?;
+    }
     w.encode_gauge(
         "index_last_wait_time",
         with_state(|state| state.last_wait_time)
@@ -832,6 +1725,17 @@ pub fn encode_metrics(w: &mut ic_metrics_encoder::MetricsEncoder<Vec<u8>>) -> st
     Ok(())
 }
 
+#[query]
+#[candid_method(query)]
+fn get_daily_stats(args: GetDailyStatsArgs) -> Vec<DailyStats> {
+    let start_day = args.start.unwrap_or_default() / NANOS_PER_DAY;
+    let limit = args
+        .limit
+        .unwrap_or(MAX_DAILY_STATS_PER_RESPONSE)
+        .min(MAX_DAILY_STATS_PER_RESPONSE);
+    daily_stats(start_day, limit as usize)
+}
+
 #[candid_method(query)]
 #[query]
 fn get_fee_collectors_ranges() -> FeeCollectorRanges {
@@ -875,6 +1779,15 @@ fn check_candid_interface() {
     });
 }
 
+#[test]
+fn notification_backoff_test() {
+    assert_eq!(notification_backoff(1), DEFAULT_RETRY_WAIT_TIME);
+    assert_eq!(notification_backoff(2), DEFAULT_RETRY_WAIT_TIME * 2);
+    assert_eq!(notification_backoff(5), DEFAULT_RETRY_WAIT_TIME * 16);
+    assert_eq!(notification_backoff(20), MAX_NOTIFICATION_BACKOFF);
+    assert_eq!(notification_backoff(u32::MAX), MAX_NOTIFICATION_BACKOFF);
+}
+
 #[test]
 fn compute_wait_time_test() {
     fn blocks(n: u64) -> usize {
@@ -892,3 +1805,143 @@ fn compute_wait_time_test() {
     assert_eq!(wait_time(25), compute_wait_time(blocks(75)));
     assert_eq!(wait_time(0), compute_wait_time(blocks(100)));
 }
+
+#[cfg(test)]
+fn test_mint_block(to: Account) -> Block {
+    Block {
+        parent_hash: None,
+        transaction: ic_icrc1::Transaction {
+            operation: Operation::Mint { to, amount: 1 },
+            created_at_time: None,
+            memo: None,
+        },
+        effective_fee: None,
+        timestamp: 0,
+        fee_collector: None,
+        fee_collector_block_index: None,
+    }
+}
+
+#[cfg(test)]
+fn test_subscription(subscriber: Principal) -> Subscription {
+    with_subscriptions(|subscriptions| subscriptions.get(&subscriber_key(subscriber)))
+        .expect("subscription not found")
+}
+
+#[test]
+fn notification_delivery_test() {
+    let subscriber = Principal::from_slice(&[1]);
+    let watched = Account {
+        owner: Principal::from_slice(&[2]),
+        subaccount: None,
+    };
+    let other = Account {
+        owner: Principal::from_slice(&[2]),
+        subaccount: Some([1; 32]),
+    };
+    with_subscriptions(|subscriptions| {
+        subscriptions.insert(
+            subscriber_key(subscriber),
+            Subscription {
+                accounts: BTreeSet::from([watched]),
+                method: "on_blocks".to_string(),
+                cycles_balance: 2 * NOTIFICATION_FEE,
+                ..Default::default()
+            },
+        )
+    });
+
+    // Only the blocks of the subscribed accounts are enqueued.
+    enqueue_notifications(0, &test_mint_block(watched));
+    enqueue_notifications(1, &test_mint_block(other));
+    enqueue_notifications(2, &test_mint_block(watched));
+    assert_eq!(test_subscription(subscriber).pending_blocks, 2);
+
+    let due = due_notifications(0);
+    assert_eq!(
+        due,
+        vec![DueNotification {
+            subscriber,
+            method: "on_blocks".to_string(),
+            block_indexes: vec![0, 2],
+            dropped_blocks: 0,
+        }]
+    );
+
+    // Each notification sent is charged to the subscriber.
+    on_notification_sent(&due[0]);
+    let subscription = test_subscription(subscriber);
+    assert_eq!(subscription.pending_blocks, 0);
+    assert_eq!(subscription.cycles_balance, NOTIFICATION_FEE);
+    assert_eq!(due_notifications(0), vec![]);
+
+    // A failed notification is retried after the backoff, free of charge.
+    enqueue_notifications(3, &test_mint_block(watched));
+    on_notification_failed(subscriber, 10);
+    assert_eq!(due_notifications(10), vec![]);
+    let retry_at = 10 + DEFAULT_RETRY_WAIT_TIME.as_nanos() as u64;
+    let due = due_notifications(retry_at);
+    assert_eq!(due.len(), 1);
+    assert_eq!(
+        test_subscription(subscriber).cycles_balance,
+        NOTIFICATION_FEE
+    );
+
+    // Subscribers that cannot pay for a notification are not notified.
+    on_notification_sent(&due[0]);
+    let subscription = test_subscription(subscriber);
+    assert_eq!(subscription.cycles_balance, 0);
+    assert_eq!(subscription.failed_attempts, 0);
+    enqueue_notifications(4, &test_mint_block(watched));
+    assert_eq!(due_notifications(u64::MAX), vec![]);
+}
+
+#[test]
+fn notification_queue_overflow_test() {
+    let subscriber = Principal::from_slice(&[1]);
+    let owner = Principal::from_slice(&[2]);
+    with_subscriptions(|subscriptions| {
+        subscriptions.insert(
+            subscriber_key(subscriber),
+            Subscription {
+                owners: BTreeSet::from([owner]),
+                method: "on_blocks".to_string(),
+                cycles_balance: NOTIFICATION_FEE,
+                ..Default::default()
+            },
+        )
+    });
+
+    // The oldest block indexes are dropped once the queue is full.
+    let dropped = 3;
+    for block_index in 0..MAX_PENDING_BLOCKS_PER_SUBSCRIPTION + dropped {
+        let to = Account {
+            owner,
+            subaccount: Some([block_index as u8; 32]),
+        };
+        enqueue_notifications(block_index, &test_mint_block(to));
+    }
+    let subscription = test_subscription(subscriber);
+    assert_eq!(
+        subscription.pending_blocks,
+        MAX_PENDING_BLOCKS_PER_SUBSCRIPTION
+    );
+    assert_eq!(subscription.dropped_blocks, dropped);
+
+    let due = due_notifications(0);
+    assert_eq!(due.len(), 1);
+    assert_eq!(
+        due[0].block_indexes,
+        (dropped..dropped + MAX_BLOCKS_PER_NOTIFICATION).collect::<Vec<_>>()
+    );
+    assert_eq!(due[0].dropped_blocks, dropped);
+
+    // The dropped block indexes are only reported once.
+    on_notification_sent(&due[0]);
+    let subscription = test_subscription(subscriber);
+    assert_eq!(
+        subscription.pending_blocks,
+        MAX_PENDING_BLOCKS_PER_SUBSCRIPTION - MAX_BLOCKS_PER_NOTIFICATION
+    );
+    assert_eq!(subscription.dropped_blocks, 0);
+}
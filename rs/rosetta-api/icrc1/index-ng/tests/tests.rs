@@ -1,20 +1,26 @@
 use candid::{Decode, Encode, Nat};
 use ic_base_types::{CanisterId, PrincipalId};
 use ic_icrc1_index_ng::{
-    FeeCollectorRanges, GetAccountTransactionsArgs, GetAccountTransactionsResponse,
-    GetAccountTransactionsResult, GetBlocksResponse, IndexArg, InitArg as IndexInitArg,
-    ListSubaccountsArgs, Status, TransactionWithId, DEFAULT_MAX_BLOCKS_PER_RESPONSE,
+    DailyStats, FeeCollectorRanges, GetAccountTransactionsArgs, GetAccountTransactionsResponse,
+    GetAccountTransactionsResult, GetBlocksResponse, GetDailyStatsArgs, IndexArg,
+    InitArg as IndexInitArg, ListSubaccountsArgs, Status, SubscribeArgs, SubscribeError,
+    SubscriptionInfo, TransactionWithId, UnsubscribeError, DEFAULT_MAX_BLOCKS_PER_RESPONSE,
+    MAX_SUBSCRIPTION_METHOD_LENGTH, NOTIFICATION_FEE,
 };
 use ic_icrc1_ledger::{
-    ChangeFeeCollector, InitArgs as LedgerInitArgs, LedgerArgument,
+    ChangeFeeCollector, FeatureFlags, InitArgs as LedgerInitArgs, LedgerArgument,
     UpgradeArgs as LedgerUpgradeArgs,
 };
 use ic_icrc1_test_utils::{valid_transactions_strategy, CallerTransferArg};
 use ic_ledger_canister_core::archive::ArchiveOptions;
-use ic_state_machine_tests::StateMachine;
+use ic_state_machine_tests::{Cycles, StateMachine};
+use ic_universal_canister::{call_args, wasm, UNIVERSAL_CANISTER_WASM};
 use icrc_ledger_types::icrc::generic_metadata_value::MetadataValue as Value;
 use icrc_ledger_types::icrc1::account::{Account, Subaccount};
 use icrc_ledger_types::icrc1::transfer::{BlockIndex, TransferArg, TransferError};
+use icrc_ledger_types::icrc2::allowance::{ListAllowancesArgs, ListAllowancesResponse};
+use icrc_ledger_types::icrc2::approve::{ApproveArgs, ApproveError};
+use icrc_ledger_types::icrc2::transfer_from::{TransferFromArgs, TransferFromError};
 use icrc_ledger_types::icrc3::blocks::{BlockRange, GenericBlock, GetBlocksRequest};
 use icrc_ledger_types::icrc3::transactions::{Mint, Transaction, Transfer};
 use num_traits::cast::ToPrimitive;
@@ -62,6 +68,16 @@ fn index_ng_wasm() -> Vec<u8> {
     )
 }
 
+// A version of the index that stores blocks without indexing their allowances
+// and daily stats.
+fn index_ng_old_version_wasm() -> Vec<u8> {
+    ic_test_utilities_load_wasm::load_wasm(
+        std::env::var("CARGO_MANIFEST_DIR").unwrap(),
+        "ic-icrc1-index-ng",
+        &["upgrade_test_old_version"],
+    )
+}
+
 fn ledger_wasm() -> Vec<u8> {
     ic_test_utilities_load_wasm::load_wasm(
         PathBuf::from(std::env::var("CARGO_MANIFEST_DIR").unwrap())
@@ -392,6 +408,35 @@ fn get_fee_collectors_ranges(env: &StateMachine, index: CanisterId) -> FeeCollec
     .expect("failed to decode get_fee_collectors_ranges response")
 }
 
+fn get_daily_stats(env: &StateMachine, index: CanisterId, start: Option<u64>) -> Vec<DailyStats> {
+    let args = GetDailyStatsArgs { start, limit: None };
+    Decode!(
+        &env.query(index, "get_daily_stats", Encode!(&args).unwrap())
+            .expect("Failed to send get_daily_stats")
+            .bytes(),
+        Vec<DailyStats>
+    )
+    .expect("Failed to decode get_daily_stats response")
+}
+
+fn get_subscription(
+    env: &StateMachine,
+    index_id: CanisterId,
+    subscriber: PrincipalId,
+) -> Option<SubscriptionInfo> {
+    Decode!(
+        &env.query(
+            index_id,
+            "get_subscription",
+            Encode!(&subscriber.0).unwrap()
+        )
+        .expect("failed to get_subscription")
+        .bytes(),
+        Option<SubscriptionInfo>
+    )
+    .expect("failed to decode get_subscription response")
+}
+
 // Assert that the index canister contains the same blocks as the ledger
 #[track_caller]
 fn assert_ledger_index_parity(env: &StateMachine, ledger_id: CanisterId, index_id: CanisterId) {
@@ -1073,6 +1118,115 @@ fn test_fee_collector() {
     );
 }
 
+#[test]
+fn test_daily_stats() {
+    const NANOS_PER_DAY: u64 = 24 * 60 * 60 * 1_000_000_000;
+
+    let env = &StateMachine::new();
+    let fee_collector = account(42, 0);
+    let ledger_id = install_ledger(
+        env,
+        vec![(account(1, 0), 10_000_000)], // mint on day 0
+        default_archive_options(),
+        Some(fee_collector),
+    );
+    let index_id = install_index_ng(env, ledger_id);
+
+    transfer(env, ledger_id, account(1, 0), account(2, 0), 100_000);
+    transfer(env, ledger_id, account(1, 0), account(3, 0), 200_000);
+    transfer(env, ledger_id, account(1, 0), MINTER, 300_000); // burn
+
+    env.advance_time(Duration::from_secs(24 * 60 * 60));
+    upgrade_ledger(env, ledger_id, None);
+    transfer(env, ledger_id, account(1, 0), account(2, 0), 400_000); // fee is burned
+    transfer(env, ledger_id, MINTER, account(2, 0), 500_000); // mint
+
+    wait_until_sync_is_completed(env, index_id, ledger_id);
+
+    let stats = get_daily_stats(env, index_id, None);
+    assert_eq!(stats.len(), 2);
+    let first_day = stats[0].day_start;
+    assert_eq!(first_day % NANOS_PER_DAY, 0);
+    assert_eq!(
+        stats[0],
+        DailyStats {
+            day_start: first_day,
+            transfer_count: 2,
+            transfer_volume: 300_000.into(),
+            fees_collected: (2 * FEE).into(),
+            fees_burned: 0.into(),
+            mint_count: 1,
+            mint_volume: 10_000_000.into(),
+            burn_count: 1,
+            burn_volume: 300_000.into(),
+        }
+    );
+    assert_eq!(
+        stats[1],
+        DailyStats {
+            day_start: first_day + NANOS_PER_DAY,
+            transfer_count: 1,
+            transfer_volume: 400_000.into(),
+            fees_collected: 0.into(),
+            fees_burned: FEE.into(),
+            mint_count: 1,
+            mint_volume: 500_000.into(),
+            burn_count: 0,
+            burn_volume: 0.into(),
+        }
+    );
+
+    // the start can be any moment of the first day to return
+    assert_eq!(
+        get_daily_stats(env, index_id, Some(first_day + 2 * NANOS_PER_DAY - 1)),
+        stats[1..].to_vec()
+    );
+
+    // the stats are neither lost nor counted twice by an upgrade
+    env.upgrade_canister(index_id, index_ng_wasm(), vec![])
+        .unwrap();
+    wait_until_sync_is_completed(env, index_id, ledger_id);
+    assert_eq!(get_daily_stats(env, index_id, None), stats);
+}
+
+#[test]
+fn test_daily_stats_after_upgrade() {
+    let env = &StateMachine::new();
+    let ledger_id = install_ledger(
+        env,
+        vec![(account(1, 0), 10_000_000)],
+        default_archive_options(),
+        Some(account(42, 0)),
+    );
+    let index_id = install_index_ng(env, ledger_id);
+    let args = IndexArg::Init(IndexInitArg {
+        ledger_id: ledger_id.into(),
+    });
+    let old_index_id = env
+        .install_canister(index_ng_old_version_wasm(), Encode!(&args).unwrap(), None)
+        .unwrap();
+
+    transfer(env, ledger_id, account(1, 0), account(2, 0), 100_000);
+    transfer(env, ledger_id, account(1, 0), MINTER, 300_000);
+    wait_until_sync_is_completed(env, index_id, ledger_id);
+    wait_until_sync_is_completed(env, old_index_id, ledger_id);
+    assert_eq!(get_daily_stats(env, old_index_id, None), vec![]);
+
+    env.upgrade_canister(old_index_id, index_ng_wasm(), vec![])
+        .unwrap();
+
+    // The daily stats of the blocks stored before the upgrade are built from
+    // the block log, and keep being updated by the new blocks.
+    env.advance_time(Duration::from_secs(24 * 60 * 60));
+    transfer(env, ledger_id, MINTER, account(2, 0), 500_000);
+    wait_until_sync_is_completed(env, index_id, ledger_id);
+    wait_until_sync_is_completed(env, old_index_id, ledger_id);
+
+    let stats = get_daily_stats(env, index_id, None);
+    assert_eq!(stats.len(), 2);
+    assert_eq!(get_daily_stats(env, old_index_id, None), stats);
+}
+
 #[test]
 fn test_get_account_transactions_vs_old_index() {
     let mut runner = TestRunner::new(TestRunnerConfig::with_cases(1));
@@ -1109,3 +1263,421 @@ fn test_get_account_transactions_vs_old_index() {
         )
         .unwrap();
 }
+
+fn enable_icrc2(env: &StateMachine, ledger_id: CanisterId) {
+    let args = LedgerArgument::Upgrade(Some(LedgerUpgradeArgs {
+        metadata: None,
+        token_name: None,
+        token_symbol: None,
+        transfer_fee: None,
+        change_fee_collector: None,
+        max_memo_length: None,
+        feature_flags: Some(FeatureFlags { icrc2: true }),
+    }));
+    env.upgrade_canister(ledger_id, ledger_wasm(), Encode!(&args).unwrap())
+        .unwrap()
+}
+
+fn approve(
+    env: &StateMachine,
+    ledger_id: CanisterId,
+    from: Account,
+    spender: Account,
+    amount: u64,
+    expires_at: Option<u64>,
+) -> BlockIndex {
+    let arg = ApproveArgs {
+        from_subaccount: from.subaccount,
+        spender,
+        amount: amount.into(),
+        expected_allowance: None,
+        expires_at,
+        fee: None,
+        memo: None,
+        created_at_time: None,
+    };
+    Decode!(
+        &env.execute_ingress_as(
+            PrincipalId(from.owner),
+            ledger_id,
+            "icrc2_approve",
+            Encode!(&arg).unwrap()
+        )
+        .expect("failed to approve")
+        .bytes(),
+        Result<BlockIndex, ApproveError>
+    )
+    .expect("failed to decode icrc2_approve response")
+    .expect("failed to approve")
+}
+
+fn transfer_from(
+    env: &StateMachine,
+    ledger_id: CanisterId,
+    from: Account,
+    to: Account,
+    spender: Account,
+    amount: u64,
+) -> BlockIndex {
+    let arg = TransferFromArgs {
+        spender_subaccount: spender.subaccount,
+        from,
+        to,
+        amount: amount.into(),
+        fee: None,
+        memo: None,
+        created_at_time: None,
+    };
+    Decode!(
+        &env.execute_ingress_as(
+            PrincipalId(spender.owner),
+            ledger_id,
+            "icrc2_transfer_from",
+            Encode!(&arg).unwrap()
+        )
+        .expect("failed to transfer_from")
+        .bytes(),
+        Result<BlockIndex, TransferFromError>
+    )
+    .expect("failed to decode icrc2_transfer_from response")
+    .expect("failed to transfer_from")
+}
+
+fn list_allowances(
+    env: &StateMachine,
+    canister_id: CanisterId,
+    method: &str,
+    account: Account,
+    from_spender: Option<Account>,
+) -> ListAllowancesResponse {
+    Decode!(
+        &env.query(
+            canister_id,
+            method,
+            Encode!(&ListAllowancesArgs {
+                account,
+                from_spender,
+                limit: None,
+            })
+            .unwrap()
+        )
+        .expect("failed to list allowances")
+        .bytes(),
+        ListAllowancesResponse
+    )
+    .expect("failed to decode list allowances response")
+}
+
+#[test]
+fn test_list_allowances() {
+    let owner = account(1, 0);
+    let spender_1 = account(2, 0);
+    let spender_2 = account(3, 0);
+    let spender_3 = account(4, 0);
+
+    let env = &StateMachine::new();
+    let ledger_id = install_ledger(
+        env,
+        vec![(owner, 1_000_000_000)],
+        default_archive_options(),
+        None,
+    );
+    enable_icrc2(env, ledger_id);
+    let index_id = install_index_ng(env, ledger_id);
+
+    let expires_at = env
+        .time()
+        .duration_since(std::time::UNIX_EPOCH)
+        .unwrap()
+        .as_nanos() as u64
+        + Duration::from_secs(3600).as_nanos() as u64;
+    approve(env, ledger_id, owner, spender_1, 100_000, None);
+    approve(env, ledger_id, owner, spender_2, 200_000, Some(expires_at));
+    approve(env, ledger_id, owner, spender_3, 300_000, None);
+    transfer_from(env, ledger_id, owner, spender_1, spender_1, 50_000);
+    // Using the whole allowance removes it.
+    transfer_from(env, ledger_id, owner, spender_3, spender_3, 300_000 - FEE);
+
+    wait_until_sync_is_completed(env, index_id, ledger_id);
+
+    let ledger_allowances = list_allowances(env, ledger_id, "icrc2_list_allowances", owner, None);
+    let index_allowances = list_allowances(env, index_id, "list_allowances", owner, None);
+    assert_eq!(ledger_allowances, index_allowances);
+    assert_eq!(
+        index_allowances
+            .iter()
+            .map(|a| (a.spender, a.allowance.clone()))
+            .collect::<Vec<_>>(),
+        vec![
+            (spender_1, Nat::from(100_000 - 50_000 - FEE)),
+            (spender_2, Nat::from(200_000u64)),
+        ]
+    );
+    assert_eq!(
+        list_allowances(env, index_id, "list_allowances", owner, Some(spender_1)),
+        index_allowances[1..]
+    );
+
+    // The allowance of spender_2 expires and is pruned when the next block is indexed.
+    env.advance_time(Duration::from_secs(7200));
+    transfer(env, ledger_id, owner, spender_1, 1);
+    wait_until_sync_is_completed(env, index_id, ledger_id);
+
+    assert_eq!(
+        list_allowances(env, ledger_id, "icrc2_list_allowances", owner, None),
+        list_allowances(env, index_id, "list_allowances", owner, None),
+    );
+    assert_eq!(
+        list_allowances(env, index_id, "list_allowances", owner, None).len(),
+        1
+    );
+}
+
+#[test]
+fn test_list_allowances_after_upgrade() {
+    let owner = account(1, 0);
+    let spender = account(2, 0);
+
+    let env = &StateMachine::new();
+    let ledger_id = install_ledger(
+        env,
+        vec![(owner, 1_000_000_000)],
+        default_archive_options(),
+        None,
+    );
+    enable_icrc2(env, ledger_id);
+    let args = IndexArg::Init(IndexInitArg {
+        ledger_id: ledger_id.into(),
+    });
+    let index_id = env
+        .install_canister(index_ng_old_version_wasm(), Encode!(&args).unwrap(), None)
+        .unwrap();
+
+    approve(env, ledger_id, owner, spender, 100_000, None);
+    transfer_from(env, ledger_id, owner, spender, spender, 10_000);
+    wait_until_sync_is_completed(env, index_id, ledger_id);
+    assert_eq!(
+        list_allowances(env, index_id, "list_allowances", owner, None),
+        vec![]
+    );
+
+    env.upgrade_canister(index_id, index_ng_wasm(), vec![])
+        .unwrap();
+
+    // The allowances of the blocks stored before the upgrade are built from
+    // the block log, and keep being updated by the new blocks.
+    transfer_from(env, ledger_id, owner, spender, spender, 20_000);
+    wait_until_sync_is_completed(env, index_id, ledger_id);
+
+    let index_allowances = list_allowances(env, index_id, "list_allowances", owner, None);
+    assert_eq!(
+        list_allowances(env, ledger_id, "icrc2_list_allowances", owner, None),
+        index_allowances
+    );
+    assert_eq!(
+        index_allowances[0].allowance,
+        Nat::from(100_000 - 10_000 - 20_000 - 2 * FEE)
+    );
+}
+
+#[test]
+fn test_subscribe_requires_cycles() {
+    let env = &StateMachine::new();
+    let ledger_id = install_ledger(
+        env,
+        vec![(account(1, 0), 10_000_000)],
+        default_archive_options(),
+        None,
+    );
+    let index_id = install_index_ng(env, ledger_id);
+
+    let subscriber = PrincipalId::new_user_test_id(1);
+    let args = SubscribeArgs {
+        accounts: vec![account(1, 0)],
+        owners: vec![],
+        method: "on_blocks".to_string(),
+    };
+    let res = Decode!(
+        &env.execute_ingress_as(subscriber, index_id, "subscribe", Encode!(&args).unwrap())
+            .expect("failed to subscribe")
+            .bytes(),
+        Result<SubscriptionInfo, SubscribeError>
+    )
+    .expect("failed to decode subscribe response");
+    // Ingress messages cannot carry cycles.
+    assert_eq!(
+        res,
+        Err(SubscribeError::InsufficientCycles {
+            balance: Nat::from(0u64),
+            notification_fee: Nat::from(NOTIFICATION_FEE),
+        })
+    );
+
+    assert_eq!(get_subscription(env, index_id, subscriber), None);
+}
+
+#[test]
+fn test_subscribe_rejects_long_method() {
+    let env = &StateMachine::new();
+    let ledger_id = install_ledger(env, vec![], default_archive_options(), None);
+    let index_id = install_index_ng(env, ledger_id);
+
+    let args = SubscribeArgs {
+        accounts: vec![account(1, 0)],
+        owners: vec![],
+        method: "m".repeat(MAX_SUBSCRIPTION_METHOD_LENGTH as usize + 1),
+    };
+    let res = Decode!(
+        &env.execute_ingress_as(
+            PrincipalId::new_user_test_id(1),
+            index_id,
+            "subscribe",
+            Encode!(&args).unwrap()
+        )
+        .expect("failed to subscribe")
+        .bytes(),
+        Result<SubscriptionInfo, SubscribeError>
+    )
+    .expect("failed to decode subscribe response");
+    assert_eq!(
+        res,
+        Err(SubscribeError::MethodTooLong {
+            max_length: MAX_SUBSCRIPTION_METHOD_LENGTH,
+        })
+    );
+}
+
+#[test]
+fn test_notifications_are_charged_to_subscriber() {
+    let env = &StateMachine::new();
+    let ledger_id = install_ledger(
+        env,
+        vec![(account(1, 0), 10_000_000)],
+        default_archive_options(),
+        None,
+    );
+    let index_id = install_index_ng(env, ledger_id);
+    wait_until_sync_is_completed(env, index_id, ledger_id);
+
+    // The subscriber is a canister, as subscriptions must be paid with cycles.
+    let subscriber = env
+        .install_canister_with_cycles(
+            UNIVERSAL_CANISTER_WASM.to_vec(),
+            vec![],
+            None,
+            Cycles::new(100 * NOTIFICATION_FEE),
+        )
+        .unwrap();
+    let args = SubscribeArgs {
+        accounts: vec![account(2, 0)],
+        owners: vec![],
+        method: "on_blocks".to_string(),
+    };
+    let subscribe = wasm()
+        .call_with_cycles(
+            index_id,
+            "subscribe",
+            call_args()
+                .other_side(Encode!(&args).unwrap())
+                .on_reply(wasm().message_payload().append_and_reply()),
+            Cycles::new(3 * NOTIFICATION_FEE),
+        )
+        .build();
+    let res = Decode!(
+        &env.execute_ingress(subscriber, "update", subscribe)
+            .expect("failed to subscribe")
+            .bytes(),
+        Result<SubscriptionInfo, SubscribeError>
+    )
+    .expect("failed to decode subscribe response")
+    .expect("failed to subscribe");
+    assert_eq!(res.cycles_balance, Nat::from(3 * NOTIFICATION_FEE));
+
+    // The blocks of other accounts are not notified.
+    transfer(env, ledger_id, account(1, 0), account(3, 0), 1);
+    wait_until_sync_is_completed(env, index_id, ledger_id);
+    let info = get_subscription(env, index_id, subscriber.get()).unwrap();
+    assert_eq!(info.cycles_balance, Nat::from(3 * NOTIFICATION_FEE));
+
+    // Each notification is charged once it is sent.
+    transfer(env, ledger_id, account(1, 0), account(2, 0), 1);
+    wait_until_sync_is_completed(env, index_id, ledger_id);
+    let info = get_subscription(env, index_id, subscriber.get()).unwrap();
+    assert_eq!(info.pending_blocks, 0);
+    assert_eq!(info.dropped_blocks, 0);
+    assert_eq!(info.cycles_balance, Nat::from(2 * NOTIFICATION_FEE));
+}
+
+#[test]
+fn test_unsubscribe_returns_unused_cycles() {
+    let env = &StateMachine::new();
+    let ledger_id = install_ledger(
+        env,
+        vec![(account(1, 0), 10_000_000)],
+        default_archive_options(),
+        None,
+    );
+    let index_id = install_index_ng(env, ledger_id);
+    wait_until_sync_is_completed(env, index_id, ledger_id);
+
+    let subscriber = env
+        .install_canister_with_cycles(
+            UNIVERSAL_CANISTER_WASM.to_vec(),
+            vec![],
+            None,
+            Cycles::new(100 * NOTIFICATION_FEE),
+        )
+        .unwrap();
+    let args = SubscribeArgs {
+        accounts: vec![account(2, 0)],
+        owners: vec![],
+        method: "on_blocks".to_string(),
+    };
+    let subscribe = wasm()
+        .call_with_cycles(
+            index_id,
+            "subscribe",
+            call_args()
+                .other_side(Encode!(&args).unwrap())
+                .on_reply(wasm().message_payload().append_and_reply()),
+            Cycles::new(3 * NOTIFICATION_FEE),
+        )
+        .build();
+    Decode!(
+        &env.execute_ingress(subscriber, "update", subscribe)
+            .expect("failed to subscribe")
+            .bytes(),
+        Result<SubscriptionInfo, SubscribeError>
+    )
+    .expect("failed to decode subscribe response")
+    .expect("failed to subscribe");
+
+    let unsubscribe = || {
+        let unsubscribe = wasm()
+            .call_simple(
+                index_id,
+                "unsubscribe",
+                call_args()
+                    .other_side(Encode!().unwrap())
+                    .on_reply(wasm().message_payload().append_and_reply()),
+            )
+            .build();
+        Decode!(
+            &env.execute_ingress(subscriber, "update", unsubscribe)
+                .expect("failed to unsubscribe")
+                .bytes(),
+            Result<SubscriptionInfo, UnsubscribeError>
+        )
+        .expect("failed to decode unsubscribe response")
+    };
+
+    // The unused cycles are returned along with the final state of the
+    // subscription.
+    let balance_before = env.cycle_balance(subscriber);
+    let info = unsubscribe().expect("failed to unsubscribe");
+    assert_eq!(info.cycles_balance, Nat::from(3 * NOTIFICATION_FEE));
+    assert_eq!(get_subscription(env, index_id, subscriber.get()), None);
+    assert!(env.cycle_balance(subscriber) > balance_before);
+
+    assert_eq!(unsubscribe(), Err(UnsubscribeError::NotSubscribed));
+}
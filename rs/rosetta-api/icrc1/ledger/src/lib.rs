@@ -19,14 +19,16 @@ use ic_ledger_canister_core::{
     range_utils,
 };
 use ic_ledger_core::{
-    approvals::AllowanceTable,
+    approvals::{Allowance, AllowanceTable},
     balances::Balances,
     block::{BlockIndex, BlockType, EncodedBlock, FeeCollector},
+    mmr::NodeId,
     timestamp::TimeStamp,
     tokens::Tokens,
 };
 use ic_ledger_hash_of::HashOf;
 use icrc_ledger_types::icrc1::account::Account;
+use icrc_ledger_types::icrc3::proofs::{BlockProof, GetBlockProofError};
 use icrc_ledger_types::icrc3::transactions::Transaction as Tx;
 use icrc_ledger_types::icrc3::{blocks::GetBlocksResponse, transactions::GetTransactionsResponse};
 use icrc_ledger_types::{
@@ -37,6 +39,7 @@ use serde::{Deserialize, Serialize};
 use serde_bytes::ByteBuf;
 use std::borrow::Cow;
 use std::collections::{BTreeMap, HashMap, VecDeque};
+use std::ops::Bound::{Excluded, Included, Unbounded};
 use std::time::Duration;
 
 const TRANSACTION_WINDOW: Duration = Duration::from_secs(24 * 60 * 60);
@@ -47,6 +50,9 @@ const MAX_TRANSACTIONS_PER_REQUEST: usize = 2_000;
 const ACCOUNTS_OVERFLOW_TRIM_QUANTITY: usize = 100_000;
 const MAX_TRANSACTIONS_IN_WINDOW: usize = 3_000_000;
 const MAX_TRANSACTIONS_TO_PURGE: usize = 100_000;
+/// The maximum number of allowances the ledger should return for a single
+/// icrc2_list_allowances request.
+pub const MAX_ALLOWANCES_PER_REQUEST: usize = 500;
 
 const DEFAULT_MAX_MEMO_LENGTH: u16 = 32;
 
@@ -236,6 +242,7 @@ impl Ledger {
             max_memo_length: max_memo_length.unwrap_or(DEFAULT_MAX_MEMO_LENGTH),
             feature_flags: feature_flags.unwrap_or_default(),
         };
+        ledger.blockchain.enable_mmr();
 
         for (account, balance) in initial_balances.into_iter() {
             apply_transaction(
@@ -390,6 +397,33 @@ impl Ledger {
         &self.feature_flags
     }
 
+    /// Returns up to `limit` allowances granted by the `account` that have not
+    /// expired at `now`, ordered by spender. If `from_spender` is set, the
+    /// listing starts after that spender.
+    pub fn list_allowances(
+        &self,
+        account: Account,
+        from_spender: Option<Account>,
+        limit: usize,
+        now: TimeStamp,
+    ) -> Vec<(Account, Allowance<Tokens>)> {
+        let start = match from_spender {
+            Some(spender) => Excluded(ApprovalKey(account, spender)),
+            // The management canister has the smallest principal and the
+            // default subaccount is the smallest subaccount.
+            None => Included(ApprovalKey(
+                account,
+                Account::from(Principal::management_canister()),
+            )),
+        };
+        self.approvals
+            .allowances_in_range((start, Unbounded), now)
+            .take_while(|(key, _)| key.0 == account)
+            .take(limit.min(MAX_ALLOWANCES_PER_REQUEST))
+            .map(|(key, allowance)| (key.1, allowance.clone()))
+            .collect()
+    }
+
     pub fn upgrade(&mut self, args: UpgradeArgs) {
         if let Some(upgrade_metadata_args) = args.metadata {
             self.metadata = upgrade_metadata_args
@@ -437,11 +471,23 @@ impl Ledger {
         match self.blockchain().last_hash {
             Some(hash) => {
                 let last_block_index = self.blockchain().chain_length().checked_sub(1).unwrap();
+                let last_block_index_tree = MixedHashTree::Labeled(
+                    Label::from("last_block_index"),
+                    Box::new(MixedHashTree::Leaf(last_block_index.to_be_bytes().to_vec())),
+                );
+                // Labels must be sorted: last_block_index < mmr_root < tip_hash.
+                let left = match &self.blockchain().mmr {
+                    Some(mmr) => MixedHashTree::Fork(Box::new((
+                        last_block_index_tree,
+                        MixedHashTree::Labeled(
+                            Label::from("mmr_root"),
+                            Box::new(MixedHashTree::Leaf(mmr.root_hash().to_vec())),
+                        ),
+                    ))),
+                    None => last_block_index_tree,
+                };
                 MixedHashTree::Fork(Box::new((
-                    MixedHashTree::Labeled(
-                        Label::from("last_block_index"),
-                        Box::new(MixedHashTree::Leaf(last_block_index.to_be_bytes().to_vec())),
-                    ),
+                    left,
                     MixedHashTree::Labeled(
                         Label::from("tip_hash"),
                         Box::new(MixedHashTree::Leaf(hash.as_slice().to_vec())),
@@ -452,6 +498,72 @@ impl Ledger {
         }
     }
 
+    /// Starts maintaining the MMR over the block hashes if the ledger does
+    /// not have one yet, e.g., after an upgrade from a version without it.
+    pub fn enable_block_proofs(&mut self) {
+        self.blockchain.enable_mmr();
+    }
+
+    /// Returns a proof that the block with the given index is part of the
+    /// MMR whose root the ledger certifies. If `from_node` is set, the proof
+    /// starts at that node instead of the leaf of the block.
+    pub fn get_block_proof(
+        &self,
+        block_index: BlockIndex,
+        from_node: Option<NodeId>,
+    ) -> Result<BlockProof, GetBlockProofError> {
+        let mmr = self
+            .blockchain
+            .mmr
+            .as_ref()
+            .ok_or(GetBlockProofError::ProofUnavailable {
+                mmr_start: 0,
+                mmr_end: 0,
+            })?;
+        let mmr_range = mmr.range();
+        let unavailable = || GetBlockProofError::ProofUnavailable {
+            mmr_start: mmr_range.start,
+            mmr_end: mmr_range.end,
+        };
+        if !mmr_range.contains(&block_index) {
+            return Err(unavailable());
+        }
+
+        let siblings = match from_node {
+            Some(node) if node.range().contains(&block_index) => {
+                mmr.path(node).ok_or_else(unavailable)?
+            }
+            Some(_) => return Err(unavailable()),
+            None => match mmr.path(NodeId::leaf(block_index)) {
+                Some(siblings) => siblings,
+                None => {
+                    let locations = block_locations(self, block_index, 1);
+                    return match locations.archived_blocks.first() {
+                        Some((canister_id, _)) => Err(GetBlockProofError::BlockArchived {
+                            archive: canister_id.get().0,
+                        }),
+                        None => Err(unavailable()),
+                    };
+                }
+            },
+        };
+
+        Ok(BlockProof {
+            mmr_start: mmr_range.start,
+            mmr_end: mmr_range.end,
+            siblings: siblings
+                .into_iter()
+                .map(|hash| ByteBuf::from(hash.to_vec()))
+                .collect(),
+            peaks: mmr
+                .peaks()
+                .iter()
+                .map(|(_, hash)| ByteBuf::from(hash.to_vec()))
+                .collect(),
+            certificate: ic_cdk::api::data_certificate().map(ByteBuf::from),
+        })
+    }
+
     fn query_blocks<ArchiveFn, B>(
         &self,
         start: BlockIndex,
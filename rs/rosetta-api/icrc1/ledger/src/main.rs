@@ -13,7 +13,7 @@ use ic_ledger_canister_core::ledger::{
     apply_transaction, archive_blocks, LedgerAccess, LedgerContext, LedgerData,
     TransferError as CoreTransferError,
 };
-use ic_ledger_core::{approvals::Approvals, timestamp::TimeStamp, tokens::Tokens};
+use ic_ledger_core::{approvals::Approvals, mmr::NodeId, timestamp::TimeStamp, tokens::Tokens};
 use icrc_ledger_types::icrc1::transfer::Memo;
 use icrc_ledger_types::icrc2::approve::{ApproveArgs, ApproveError};
 use icrc_ledger_types::icrc3::blocks::DataCertificate;
@@ -22,12 +22,15 @@ use icrc_ledger_types::{
     icrc3::{
         archive::ArchiveInfo,
         blocks::{GetBlocksRequest, GetBlocksResponse},
+        proofs::{BlockProof, GetBlockProofArgs, GetBlockProofError},
         transactions::{GetTransactionsRequest, GetTransactionsResponse},
     },
 };
 use icrc_ledger_types::{
     icrc1::account::Account,
-    icrc2::allowance::{Allowance, AllowanceArgs},
+    icrc2::allowance::{
+        Allowance, AllowanceArgs, ListAllowancesArgs, ListAllowancesResponse, SpenderAllowance,
+    },
 };
 use icrc_ledger_types::{
     icrc1::transfer::{TransferArg, TransferError},
@@ -108,6 +111,10 @@ fn post_upgrade(args: Option<LedgerArgument>) {
             }
         }
     }
+
+    // Enabling the MMR changes the certified hash tree.
+    Access::with_ledger_mut(|ledger| ledger.enable_block_proofs());
+    ic_cdk::api::set_certified_data(&Access::with_ledger(Ledger::root_hash));
 }
 
 fn encode_metrics(w: &mut ic_metrics_encoder::MetricsEncoder<Vec<u8>>) -> std::io::Result<()> {
@@ -492,6 +499,21 @@ fn get_blocks(req: GetBlocksRequest) -> GetBlocksResponse {
     Access::with_ledger(|ledger| ledger.get_blocks(start, length as usize))
 }
 
+#[query]
+#[candid_method(query)]
+fn get_block_proof(arg: GetBlockProofArgs) -> Result<BlockProof, GetBlockProofError> {
+    let block_index = arg
+        .block_index
+        .0
+        .to_u64()
+        .unwrap_or_else(|| ic_cdk::api::trap("block index is too large"));
+    let from_node = arg.from_node.map(|node| NodeId {
+        height: node.height,
+        index: node.index,
+    });
+    Access::with_ledger(|ledger| ledger.get_block_proof(block_index, from_node))
+}
+
 #[query]
 #[candid_method(query)]
 fn get_data_certificate() -> DataCertificate {
@@ -612,6 +634,30 @@ fn icrc2_allowance(arg: AllowanceArgs) -> Allowance {
     })
 }
 
+#[query]
+#[candid_method(query)]
+fn icrc2_list_allowances(arg: ListAllowancesArgs) -> ListAllowancesResponse {
+    Access::with_ledger(|ledger| {
+        if !ledger.feature_flags().icrc2 {
+            ic_cdk::trap("ICRC-2 features are not enabled on the ledger.");
+        }
+        let now = TimeStamp::from_nanos_since_unix_epoch(ic_cdk::api::time());
+        let limit = arg
+            .limit
+            .map(|limit| limit.min(usize::MAX as u64) as usize)
+            .unwrap_or(usize::MAX);
+        ledger
+            .list_allowances(arg.account, arg.from_spender, limit, now)
+            .into_iter()
+            .map(|(spender, allowance)| SpenderAllowance {
+                spender,
+                allowance: Nat::from(allowance.amount.get_e8s()),
+                expires_at: allowance.expires_at.map(|t| t.as_nanos_since_unix_epoch()),
+            })
+            .collect()
+    })
+}
+
 candid::export_service!();
 
 #[query]
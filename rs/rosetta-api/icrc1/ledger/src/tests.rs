@@ -593,3 +593,91 @@ fn test_approval_burn_from() {
     assert_eq!(ctx.balances().account_balance(&spender), Tokens::ZERO);
     assert_eq!(ctx.balances().total_supply().get_e8s(), 90_000);
 }
+
+#[test]
+fn test_list_allowances() {
+    let now = ts(1000);
+
+    let mut ctx = Ledger::from_init_args(default_init_args(), now);
+
+    let from = test_account_id(1);
+    let other = test_account_id(2);
+    let spenders: Vec<Account> = (3..6).map(test_account_id).collect();
+
+    ctx.balances_mut().mint(&from, tokens(200_000)).unwrap();
+    ctx.balances_mut().mint(&other, tokens(200_000)).unwrap();
+
+    let approve =
+        |from: Account, spender: Account, amount: u64, expires_at: Option<TimeStamp>| Transaction {
+            operation: Operation::Approve {
+                from,
+                spender,
+                amount,
+                expected_allowance: None,
+                expires_at,
+                fee: Some(10_000),
+            },
+            created_at_time: None,
+            memo: None,
+        };
+    approve(from, spenders[0], 100_000, Some(ts(2000)))
+        .apply(&mut ctx, now, Tokens::ZERO)
+        .unwrap();
+    approve(from, spenders[1], 200_000, Some(ts(1500)))
+        .apply(&mut ctx, now, Tokens::ZERO)
+        .unwrap();
+    approve(from, spenders[2], 300_000, None)
+        .apply(&mut ctx, now, Tokens::ZERO)
+        .unwrap();
+    approve(other, spenders[0], 400_000, None)
+        .apply(&mut ctx, now, Tokens::ZERO)
+        .unwrap();
+
+    let mut expected = vec![
+        (
+            spenders[0],
+            Allowance {
+                amount: tokens(100_000),
+                expires_at: Some(ts(2000)),
+            },
+        ),
+        (
+            spenders[1],
+            Allowance {
+                amount: tokens(200_000),
+                expires_at: Some(ts(1500)),
+            },
+        ),
+        (
+            spenders[2],
+            Allowance {
+                amount: tokens(300_000),
+                expires_at: None,
+            },
+        ),
+    ];
+    expected.sort_by_key(|(spender, _)| *spender);
+
+    assert_eq!(ctx.list_allowances(from, None, usize::MAX, now), expected);
+    assert_eq!(ctx.list_allowances(from, None, 2, now), expected[..2]);
+    assert_eq!(
+        ctx.list_allowances(from, Some(expected[0].0), usize::MAX, now),
+        expected[1..]
+    );
+    assert_eq!(
+        ctx.list_allowances(from, Some(expected[2].0), usize::MAX, now),
+        vec![]
+    );
+
+    // Expired allowances are not listed.
+    let later = ts(1800);
+    let not_expired: Vec<_> = expected
+        .iter()
+        .filter(|(spender, _)| spender != &spenders[1])
+        .cloned()
+        .collect();
+    assert_eq!(
+        ctx.list_allowances(from, None, usize::MAX, later),
+        not_expired
+    );
+}
@@ -12,6 +12,13 @@ use ic_state_machine_tests::StateMachine;
 use icrc_ledger_types::icrc::generic_metadata_value::MetadataValue as Value;
 use icrc_ledger_types::icrc1::account::Account;
 use icrc_ledger_types::icrc1::transfer::{TransferArg, TransferError};
+use icrc_ledger_types::icrc3::archive::ArchiveInfo;
+use icrc_ledger_types::icrc3::blocks::{
+    BlockRange, DataCertificate, GenericBlock as IcrcBlock, GetBlocksRequest, GetBlocksResponse,
+};
+use icrc_ledger_types::icrc3::proofs::{
+    ArchivedBlockPath, BlockProof, GetBlockProofArgs, GetBlockProofError,
+};
 use num_traits::ToPrimitive;
 use std::path::PathBuf;
 
@@ -337,3 +344,232 @@ fn test_upgrade_from_first_version() {
     transfer(&env, ledger_id, MINTER, account(2), 3_000_000);
     transfer(&env, ledger_id, account(1), account(3), 1_000_000);
 }
+
+fn get_block_proof(
+    env: &StateMachine,
+    ledger_id: CanisterId,
+    args: &GetBlockProofArgs,
+) -> Result<BlockProof, GetBlockProofError> {
+    let res = env
+        .query(ledger_id, "get_block_proof", Encode!(args).unwrap())
+        .expect("Unable to perform get_block_proof")
+        .bytes();
+    Decode!(&res, Result<BlockProof, GetBlockProofError>).unwrap()
+}
+
+fn certified_mmr_root(env: &StateMachine, ledger_id: CanisterId) -> Vec<u8> {
+    use ic_crypto_tree_hash::{LookupStatus, MixedHashTree};
+
+    let res = env
+        .query(ledger_id, "get_data_certificate", Encode!().unwrap())
+        .expect("Unable to perform get_data_certificate")
+        .bytes();
+    let certificate = Decode!(&res, DataCertificate).unwrap();
+    let tree: MixedHashTree = ciborium::de::from_reader(certificate.hash_tree.as_slice())
+        .expect("failed to decode the hash tree");
+    match tree.lookup(&[b"mmr_root"]) {
+        LookupStatus::Found(MixedHashTree::Leaf(root)) => root.clone(),
+        _ => panic!("the certified tree does not contain the mmr root"),
+    }
+}
+
+fn list_archives(env: &StateMachine, ledger_id: CanisterId) -> Vec<ArchiveInfo> {
+    let res = env
+        .query(ledger_id, "archives", Encode!().unwrap())
+        .unwrap()
+        .bytes();
+    Decode!(&res, Vec<ArchiveInfo>).unwrap()
+}
+
+// Returns all blocks of the ledger, assuming that it has a single archive.
+fn all_blocks(env: &StateMachine, ledger_id: CanisterId, archive_id: CanisterId) -> Vec<IcrcBlock> {
+    let request = Encode!(&GetBlocksRequest {
+        start: Nat::from(0),
+        length: Nat::from(u64::MAX),
+    })
+    .unwrap();
+    let res = env
+        .query(archive_id, "get_blocks", request.clone())
+        .unwrap()
+        .bytes();
+    let mut blocks = Decode!(&res, BlockRange).unwrap().blocks;
+    let res = env.query(ledger_id, "get_blocks", request).unwrap().bytes();
+    blocks.extend(Decode!(&res, GetBlocksResponse).unwrap().blocks);
+    blocks
+}
+
+// Checks that the proof of the block, obtained from the ledger and, if the
+// ledger pruned the nodes of the block, the archive, verifies against the
+// certified MMR root. Returns true if the archive served part of the proof.
+fn assert_block_proof_verifies(
+    env: &StateMachine,
+    ledger_id: CanisterId,
+    archive: &ArchiveInfo,
+    block_index: u64,
+    block: &IcrcBlock,
+) -> bool {
+    let archive_id = CanisterId::new(archive.canister_id.into()).unwrap();
+    let mmr_root = certified_mmr_root(env, ledger_id);
+    let mut args = GetBlockProofArgs {
+        block_index: Nat::from(block_index),
+        from_node: None,
+    };
+    let (path, proof) = match get_block_proof(env, ledger_id, &args) {
+        Ok(proof) => (None, proof),
+        Err(GetBlockProofError::BlockArchived {
+            archive: canister_id,
+        }) => {
+            assert_eq!(canister_id, archive.canister_id);
+            let res = env
+                .query(
+                    archive_id,
+                    "get_block_proof",
+                    Encode!(&block_index).unwrap(),
+                )
+                .unwrap()
+                .bytes();
+            let path = Decode!(&res, Option<ArchivedBlockPath>)
+                .unwrap()
+                .expect("the archive has no path for the block");
+            args.from_node = Some(path.node);
+            let proof = get_block_proof(env, ledger_id, &args).unwrap();
+            (Some(path), proof)
+        }
+        Err(err) => panic!(
+            "failed to get the proof of block {}: {:?}",
+            block_index, err
+        ),
+    };
+    let root =
+        ic_icrc1_client::verify_block_proof(block_index, block.hash(), path.as_ref(), &proof)
+            .unwrap();
+    assert_eq!(root.to_vec(), mmr_root);
+
+    // A proof for another block must not verify.
+    let mut other_hash = block.hash();
+    other_hash[0] ^= 1;
+    assert!(
+        ic_icrc1_client::verify_block_proof(block_index, other_hash, path.as_ref(), &proof)
+            .map_or(true, |root| root.to_vec() != mmr_root)
+    );
+
+    path.is_some()
+}
+
+#[test]
+fn test_block_proofs() {
+    let (env, ledger_id) = ic_icrc1_ledger_sm_tests::setup(
+        ledger_wasm(),
+        encode_init_args,
+        vec![(account(1), 10_000_000)],
+    );
+    for i in 0..ARCHIVE_TRIGGER_THRESHOLD {
+        ic_icrc1_ledger_sm_tests::transfer(&env, ledger_id, account(1), account(2), 10_000 + i)
+            .expect("transfer failed");
+    }
+    env.run_until_completion(/*max_ticks=*/ 10);
+
+    let archives = list_archives(&env, ledger_id);
+    assert_eq!(archives.len(), 1);
+    let archive_id = CanisterId::new(archives[0].canister_id.into()).unwrap();
+
+    let blocks = all_blocks(&env, ledger_id, archive_id);
+    assert_eq!(blocks.len() as u64, ARCHIVE_TRIGGER_THRESHOLD + 1);
+
+    for (block_index, block) in blocks.iter().enumerate() {
+        assert_block_proof_verifies(&env, ledger_id, &archives[0], block_index as u64, block);
+    }
+
+    assert_eq!(
+        get_block_proof(
+            &env,
+            ledger_id,
+            &GetBlockProofArgs {
+                block_index: Nat::from(ARCHIVE_TRIGGER_THRESHOLD + 1),
+                from_node: None,
+            }
+        ),
+        Err(GetBlockProofError::ProofUnavailable {
+            mmr_start: 0,
+            mmr_end: ARCHIVE_TRIGGER_THRESHOLD + 1,
+        })
+    );
+}
+
+#[test]
+fn test_block_proofs_after_upgrade_with_archive_predating_mmr() {
+    let (env, ledger_id) = ic_icrc1_ledger_sm_tests::setup(
+        std::fs::read(std::env::var("IC_ICRC1_LEDGER_DEPLOYED_VERSION_WASM_PATH").unwrap())
+            .unwrap(),
+        encode_init_args,
+        vec![(account(1), 10_000_000)],
+    );
+    for i in 0..ARCHIVE_TRIGGER_THRESHOLD {
+        ic_icrc1_ledger_sm_tests::transfer(&env, ledger_id, account(1), account(2), 10_000 + i)
+            .expect("transfer failed");
+    }
+    env.run_until_completion(/*max_ticks=*/ 10);
+    let archives = list_archives(&env, ledger_id);
+    assert_eq!(archives.len(), 1);
+    let archive_id = CanisterId::new(archives[0].canister_id.into()).unwrap();
+
+    // The MMR of the ledger starts after the blocks that exist at the time of
+    // the upgrade, while the upgraded archive, which predates the MMR, starts
+    // its own at the next block it receives.
+    let mmr_start = ARCHIVE_TRIGGER_THRESHOLD + 1;
+    env.upgrade_canister(
+        ledger_id,
+        ledger_wasm(),
+        Encode!(&LedgerArgument::Upgrade(None)).unwrap(),
+    )
+    .expect("failed to upgrade the ledger");
+    env.upgrade_canister(archive_id, archive_wasm(), vec![])
+        .expect("failed to upgrade the archive");
+
+    // Archive blocks that are covered by the MMR of the ledger into the
+    // archive that predates the MMR.
+    for i in 0..2 * NUM_BLOCKS_TO_ARCHIVE {
+        ic_icrc1_ledger_sm_tests::transfer(&env, ledger_id, account(1), account(2), 20_000 + i)
+            .expect("transfer failed");
+    }
+    env.run_until_completion(/*max_ticks=*/ 10);
+    let archives = list_archives(&env, ledger_id);
+    assert_eq!(archives.len(), 1);
+    assert_eq!(archives[0].canister_id, archive_id.get().0);
+
+    let blocks = all_blocks(&env, ledger_id, archive_id);
+    let chain_length = mmr_start + 2 * NUM_BLOCKS_TO_ARCHIVE;
+    assert_eq!(blocks.len() as u64, chain_length);
+
+    // Blocks from before the upgrade have no proofs.
+    assert_eq!(
+        get_block_proof(
+            &env,
+            ledger_id,
+            &GetBlockProofArgs {
+                block_index: Nat::from(mmr_start - 1),
+                from_node: None,
+            }
+        ),
+        Err(GetBlockProofError::ProofUnavailable {
+            mmr_start,
+            mmr_end: chain_length,
+        })
+    );
+
+    // The ledger pruned the nodes that the archive can serve for the
+    // archived blocks it covers, and all proofs still verify.
+    let mut num_served_by_archive = 0;
+    for block_index in mmr_start..chain_length {
+        if assert_block_proof_verifies(
+            &env,
+            ledger_id,
+            &archives[0],
+            block_index,
+            &blocks[block_index as usize],
+        ) {
+            num_served_by_archive += 1;
+        }
+    }
+    assert!(num_served_by_archive > 0);
+}
@@ -10,7 +10,10 @@ use ic_ledger_core::{
     approvals::AllowanceTable, balances::BalancesStore, block::BlockType, timestamp::TimeStamp,
     tokens::CheckedAdd, Tokens,
 };
-use icp_ledger::{apply_operation, AccountIdentifier, ApprovalKey, Block, Operation};
+use icp_ledger::{
+    apply_operation, AccountIdentifier, ApprovalKey, Block, Memo, Operation, Transaction,
+    DEFAULT_TRANSFER_FEE,
+};
 use rusqlite::params;
 use std::path::Path;
 
@@ -185,6 +188,69 @@ async fn store_account_balances_test() {
     }
 }
 
+#[actix_rt::test]
+async fn store_approve_and_transfer_from_test() {
+    init_test_logger();
+    let tmpdir = create_tmp_dir();
+    let mut store = sqlite_on_disk_store(tmpdir.path());
+    let mut scribe = Scribe::new();
+    scribe.gen_accounts(3, 1_000_000);
+
+    let from = scribe.accounts[0];
+    let spender = scribe.accounts[1];
+    let to = scribe.accounts[2];
+    let from_balance = scribe.balance_book[&from];
+    let to_balance = scribe.balance_book[&to];
+
+    let approve = Transaction {
+        operation: Operation::Approve {
+            from,
+            spender,
+            allowance: Tokens::from_e8s(100_000),
+            expected_allowance: Some(Tokens::ZERO),
+            expires_at: None,
+            fee: DEFAULT_TRANSFER_FEE,
+        },
+        memo: Memo(1),
+        icrc1_memo: None,
+        created_at_time: None,
+    };
+    scribe.add_block(approve.clone(), DEFAULT_TRANSFER_FEE);
+
+    let transfer_from = Transaction {
+        operation: Operation::TransferFrom {
+            from,
+            to,
+            spender,
+            amount: Tokens::from_e8s(50_000),
+            fee: DEFAULT_TRANSFER_FEE,
+        },
+        memo: Memo(2),
+        icrc1_memo: None,
+        created_at_time: None,
+    };
+    scribe.add_block(transfer_from.clone(), DEFAULT_TRANSFER_FEE);
+
+    for hb in &scribe.blockchain {
+        store.push(hb).unwrap();
+        store.set_hashed_block_to_verified(&hb.index).unwrap();
+    }
+
+    let last_idx = scribe.blockchain.back().unwrap().index;
+    assert_eq!(store.get_transaction(&(last_idx - 1)).unwrap(), approve);
+    assert_eq!(store.get_transaction(&last_idx).unwrap(), transfer_from);
+
+    let fee = DEFAULT_TRANSFER_FEE.get_e8s();
+    assert_eq!(
+        store.get_account_balance(&from, &last_idx).unwrap(),
+        Tokens::from_e8s(from_balance.get_e8s() - 50_000 - 2 * fee)
+    );
+    assert_eq!(
+        store.get_account_balance(&to, &last_idx).unwrap(),
+        Tokens::from_e8s(to_balance.get_e8s() + 50_000)
+    );
+}
+
 #[actix_rt::test]
 async fn store_prune_test() {
     init_test_logger();
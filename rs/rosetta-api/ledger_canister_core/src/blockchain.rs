@@ -8,6 +8,7 @@ use std::convert::TryFrom;
 use std::sync::{Arc, RwLock};
 
 use ic_ledger_core::block::{BlockIndex, BlockType, EncodedBlock};
+use ic_ledger_core::mmr::{contains_node, MerkleMountainRange};
 use ic_ledger_core::timestamp::TimeStamp;
 use ic_ledger_hash_of::HashOf;
 
@@ -30,6 +31,11 @@ pub struct Blockchain<Rt: Runtime, Wasm: ArchiveCanisterWasm> {
 
     /// How many blocks have been sent to the archive
     pub num_archived_blocks: u64,
+
+    /// The Merkle mountain range over the block hashes, if the ledger
+    /// maintains one. See [Blockchain::enable_mmr].
+    #[serde(default)]
+    pub mmr: Option<MerkleMountainRange>,
 }
 
 impl<Rt: Runtime, Wasm: ArchiveCanisterWasm> Default for Blockchain<Rt, Wasm> {
@@ -40,6 +46,7 @@ impl<Rt: Runtime, Wasm: ArchiveCanisterWasm> Default for Blockchain<Rt, Wasm> {
             last_timestamp: TimeStamp::from_nanos_since_unix_epoch(0),
             archive: Arc::new(RwLock::new(None)),
             num_archived_blocks: 0,
+            mmr: None,
         }
     }
 }
@@ -52,6 +59,14 @@ impl<Rt: Runtime, Wasm: ArchiveCanisterWasm> Blockchain<Rt, Wasm> {
         }
     }
 
+    /// Starts maintaining a Merkle mountain range over the hashes of the
+    /// blocks added from now on. Does nothing if the MMR is already enabled.
+    pub fn enable_mmr(&mut self) {
+        if self.mmr.is_none() {
+            self.mmr = Some(MerkleMountainRange::new(self.chain_length()));
+        }
+    }
+
     pub fn add_block<B>(&mut self, block: B) -> Result<BlockIndex, String>
     where
         B: BlockType,
@@ -67,7 +82,11 @@ impl<Rt: Runtime, Wasm: ArchiveCanisterWasm> Blockchain<Rt, Wasm> {
         }
         self.last_timestamp = block.timestamp();
         let encoded_block = block.encode();
-        self.last_hash = Some(B::block_hash(&encoded_block));
+        let block_hash = B::block_hash(&encoded_block);
+        if let Some(mmr) = self.mmr.as_mut() {
+            mmr.append(block_hash.into_bytes());
+        }
+        self.last_hash = Some(block_hash);
         self.blocks.push(encoded_block);
         Ok(self.chain_length().checked_sub(1).unwrap())
     }
@@ -132,6 +151,38 @@ impl<Rt: Runtime, Wasm: ArchiveCanisterWasm> Blockchain<Rt, Wasm> {
         }
         self.blocks = self.blocks.split_off(len);
         self.num_archived_blocks += len as u64;
+        self.prune_archived_mmr_nodes();
+    }
+
+    /// Drops the MMR nodes that the archives can serve themselves.
+    ///
+    /// An archive maintains the MMR nodes for the blocks it stores, so a
+    /// proof for an archived block only needs the nodes above the largest
+    /// node that fits into the archive. Archives that predate the MMR start
+    /// theirs when they are upgraded together with the ledger, so they can
+    /// only serve the nodes of the blocks that the ledger's MMR covers, and
+    /// their ranges are clipped to it.
+    fn prune_archived_mmr_nodes(&mut self) {
+        let mmr = match self.mmr.as_mut() {
+            Some(mmr) => mmr,
+            None => return,
+        };
+        let mmr_range = mmr.range();
+        let archive_ranges: Vec<(u64, u64)> = match self.archive.read().unwrap().as_ref() {
+            Some(archive) => archive
+                .index()
+                .into_iter()
+                .map(|((from, to), _)| (from.max(mmr_range.start), to + 1))
+                .filter(|(start, end)| start < end)
+                .collect(),
+            None => return,
+        };
+        mmr.retain_nodes(|node| {
+            let parent = node.parent();
+            !archive_ranges
+                .iter()
+                .any(|(start, end)| contains_node(*start, *end, &parent))
+        });
     }
 
     pub fn get_blocks_for_archiving(
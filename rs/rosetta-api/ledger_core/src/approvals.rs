@@ -5,6 +5,7 @@ use std::cmp::Reverse;
 use std::collections::btree_map::Entry;
 use std::collections::{BTreeMap, BinaryHeap};
 use std::marker::PhantomData;
+use std::ops::RangeBounds;
 
 #[cfg(test)]
 mod tests;
@@ -106,6 +107,21 @@ where
             _marker: PhantomData,
         }
     }
+
+    /// Returns the allowances with keys in the specified range that have not
+    /// expired at `now`, in the key order.
+    pub fn allowances_in_range<R>(
+        &self,
+        range: R,
+        now: TimeStamp,
+    ) -> impl Iterator<Item = (&K, &Allowance<Tokens>)>
+    where
+        R: RangeBounds<K>,
+    {
+        self.allowances
+            .range(range)
+            .filter(move |(_, allowance)| allowance.expires_at.unwrap_or_else(remote_future) > now)
+    }
 }
 
 impl<K, AccountId, Tokens> Approvals for AllowanceTable<K, AccountId, Tokens>
@@ -316,3 +316,56 @@ fn disallow_self_approval() {
         ApproveError::SelfApproval
     );
 }
+
+#[test]
+fn allowances_in_range_skips_expired() {
+    let mut table = TestAllowanceTable::default();
+
+    table
+        .approve(&Account(1), &Account(2), tokens(5), None, ts(1), None)
+        .unwrap();
+    table
+        .approve(
+            &Account(1),
+            &Account(3),
+            tokens(7),
+            Some(ts(5)),
+            ts(1),
+            None,
+        )
+        .unwrap();
+    table
+        .approve(
+            &Account(1),
+            &Account(4),
+            tokens(9),
+            Some(ts(20)),
+            ts(1),
+            None,
+        )
+        .unwrap();
+    table
+        .approve(&Account(2), &Account(1), tokens(11), None, ts(1), None)
+        .unwrap();
+
+    let spenders = |range: std::ops::Range<Key>, now| -> Vec<(u64, Tokens)> {
+        table
+            .allowances_in_range(range, now)
+            .map(|(key, allowance)| (key.1, allowance.amount))
+            .collect()
+    };
+
+    assert_eq!(
+        spenders(Key(1, 0)..Key(2, 0), ts(1)),
+        vec![(2, tokens(5)), (3, tokens(7)), (4, tokens(9))]
+    );
+    assert_eq!(
+        spenders(Key(1, 0)..Key(2, 0), ts(10)),
+        vec![(2, tokens(5)), (4, tokens(9))]
+    );
+    assert_eq!(spenders(Key(1, 3)..Key(2, 0), ts(10)), vec![(4, tokens(9))]);
+    assert_eq!(
+        spenders(Key(2, 0)..Key(3, 0), ts(10)),
+        vec![(1, tokens(11))]
+    );
+}
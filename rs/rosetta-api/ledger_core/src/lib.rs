@@ -1,6 +1,7 @@
 pub mod approvals;
 pub mod balances;
 pub mod block;
+pub mod mmr;
 pub mod timestamp;
 pub mod tokens;
 
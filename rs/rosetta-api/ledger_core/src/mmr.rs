@@ -0,0 +1,252 @@
+//! A Merkle mountain range (MMR) over the block hashes of a ledger.
+//!
+//! The leaf at position `i` is the hash of the block with index `i`. The
+//! MMR covers a contiguous range of blocks `[start, end)` and consists of
+//! perfect binary trees, each covering an aligned range of blocks
+//! `[index * 2^height, (index + 1) * 2^height)`. The roots of these trees are
+//! the peaks of the MMR. An MMR can start at a block other than zero, e.g.,
+//! when a ledger with an existing chain starts maintaining it: nodes whose
+//! range reaches before `start` do not exist in such an MMR.
+//!
+//! The root hash of the MMR commits to the covered range and to its peaks.
+//! A block inclusion proof consists of the hashes of the siblings of the
+//! nodes on the path from the leaf to its peak plus the hashes of the peaks.
+use ic_crypto_sha::Sha256;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::ops::Range;
+
+#[cfg(test)]
+mod tests;
+
+pub type MmrHash = [u8; 32];
+
+const NODE_DOMAIN: u8 = 1;
+const ROOT_DOMAIN: u8 = 2;
+
+/// Identifies a node of the MMR by its height and its index at that height.
+/// The node covers the blocks `[index * 2^height, (index + 1) * 2^height)`.
+#[derive(Copy, Clone, Debug, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize)]
+pub struct NodeId {
+    pub height: u8,
+    pub index: u64,
+}
+
+impl NodeId {
+    pub fn leaf(block_index: u64) -> Self {
+        Self {
+            height: 0,
+            index: block_index,
+        }
+    }
+
+    /// The range of blocks covered by this node.
+    pub fn range(&self) -> Range<u64> {
+        let start = self.index.saturating_mul(1 << self.height);
+        start..start.saturating_add(1 << self.height)
+    }
+
+    pub fn parent(&self) -> Self {
+        Self {
+            height: self.height + 1,
+            index: self.index / 2,
+        }
+    }
+
+    pub fn sibling(&self) -> Self {
+        Self {
+            height: self.height,
+            index: self.index ^ 1,
+        }
+    }
+
+    /// Returns true if this node is the left child of its parent.
+    pub fn is_left(&self) -> bool {
+        self.index % 2 == 0
+    }
+}
+
+/// Returns the hash of the parent of the nodes with the given hashes.
+pub fn hash_node(left: &MmrHash, right: &MmrHash) -> MmrHash {
+    let mut hasher = Sha256::new();
+    hasher.write(&[NODE_DOMAIN]);
+    hasher.write(left);
+    hasher.write(right);
+    hasher.finish()
+}
+
+/// Returns the root hash of an MMR covering the blocks `[start, end)` with
+/// the given peaks, ordered from left to right.
+pub fn root_hash(start: u64, end: u64, peaks: &[MmrHash]) -> MmrHash {
+    let mut hasher = Sha256::new();
+    hasher.write(&[ROOT_DOMAIN]);
+    hasher.write(&start.to_be_bytes());
+    hasher.write(&end.to_be_bytes());
+    for peak in peaks {
+        hasher.write(peak);
+    }
+    hasher.finish()
+}
+
+/// Returns the peaks of an MMR covering the blocks `[start, end)`, ordered
+/// from left to right.
+pub fn peak_ids(start: u64, end: u64) -> Vec<NodeId> {
+    let mut peaks = vec![];
+    let mut pos = start;
+    while pos < end {
+        let mut height = 0;
+        while height < 63 && pos % (2 << height) == 0 && (2 << height) <= end - pos {
+            height += 1;
+        }
+        peaks.push(NodeId {
+            height,
+            index: pos >> height,
+        });
+        pos += 1 << height;
+    }
+    peaks
+}
+
+/// Returns true if the node is part of an MMR covering the blocks `[start, end)`.
+pub fn contains_node(start: u64, end: u64, node: &NodeId) -> bool {
+    let range = node.range();
+    start <= range.start && range.end <= end
+}
+
+/// Computes the hash of the node reached by following the path of sibling
+/// hashes from `node`, which has the given hash. Returns the reached node
+/// and its hash.
+pub fn follow_path(mut node: NodeId, mut hash: MmrHash, siblings: &[MmrHash]) -> (NodeId, MmrHash) {
+    for sibling in siblings {
+        hash = if node.is_left() {
+            hash_node(&hash, sibling)
+        } else {
+            hash_node(sibling, &hash)
+        };
+        node = node.parent();
+    }
+    (node, hash)
+}
+
+/// The part of an MMR needed to append new leaves: the range of covered
+/// blocks and the hashes of the current peaks.
+#[derive(Clone, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct MmrAccumulator {
+    start: u64,
+    end: u64,
+    peaks: Vec<(NodeId, MmrHash)>,
+}
+
+impl MmrAccumulator {
+    /// Creates an empty accumulator whose first leaf is the block `start`.
+    pub fn new(start: u64) -> Self {
+        Self {
+            start,
+            end: start,
+            peaks: vec![],
+        }
+    }
+
+    /// The range of blocks covered by this accumulator.
+    pub fn range(&self) -> Range<u64> {
+        self.start..self.end
+    }
+
+    pub fn peaks(&self) -> &[(NodeId, MmrHash)] {
+        &self.peaks
+    }
+
+    pub fn root_hash(&self) -> MmrHash {
+        let peaks: Vec<MmrHash> = self.peaks.iter().map(|(_, hash)| *hash).collect();
+        root_hash(self.start, self.end, &peaks)
+    }
+
+    /// Appends the hash of the next block and calls `on_node` for each node
+    /// created by the append, starting with the leaf.
+    pub fn append(&mut self, leaf: MmrHash, mut on_node: impl FnMut(NodeId, MmrHash)) {
+        let mut node = NodeId::leaf(self.end);
+        let mut hash = leaf;
+        on_node(node, hash);
+        while let Some((last, last_hash)) = self.peaks.last() {
+            if node.is_left() || *last != node.sibling() {
+                break;
+            }
+            hash = hash_node(last_hash, &hash);
+            node = node.parent();
+            self.peaks.pop();
+            on_node(node, hash);
+        }
+        self.peaks.push((node, hash));
+        self.end += 1;
+    }
+}
+
+/// An MMR that stores the hashes of its nodes so that it can produce
+/// inclusion proofs. Nodes that will never be part of a requested proof can
+/// be dropped with [MerkleMountainRange::retain_nodes].
+#[derive(Clone, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct MerkleMountainRange {
+    accumulator: MmrAccumulator,
+    nodes: BTreeMap<NodeId, MmrHash>,
+}
+
+impl MerkleMountainRange {
+    pub fn new(start: u64) -> Self {
+        Self {
+            accumulator: MmrAccumulator::new(start),
+            nodes: BTreeMap::new(),
+        }
+    }
+
+    /// The range of blocks covered by this MMR.
+    pub fn range(&self) -> Range<u64> {
+        self.accumulator.range()
+    }
+
+    pub fn peaks(&self) -> &[(NodeId, MmrHash)] {
+        self.accumulator.peaks()
+    }
+
+    pub fn root_hash(&self) -> MmrHash {
+        self.accumulator.root_hash()
+    }
+
+    pub fn append(&mut self, leaf: MmrHash) {
+        let nodes = &mut self.nodes;
+        self.accumulator.append(leaf, |node, hash| {
+            nodes.insert(node, hash);
+        });
+    }
+
+    pub fn node_hash(&self, node: &NodeId) -> Option<&MmrHash> {
+        self.nodes.get(node)
+    }
+
+    pub fn num_stored_nodes(&self) -> usize {
+        self.nodes.len()
+    }
+
+    /// Returns the hashes of the siblings on the path from `node` to its
+    /// peak, or None if the node is not in the MMR or a sibling was dropped.
+    pub fn path(&self, node: NodeId) -> Option<Vec<MmrHash>> {
+        let Range { start, end } = self.range();
+        if !contains_node(start, end, &node) {
+            return None;
+        }
+        let mut siblings = vec![];
+        let mut node = node;
+        while contains_node(start, end, &node.parent()) {
+            siblings.push(*self.nodes.get(&node.sibling())?);
+            node = node.parent();
+        }
+        Some(siblings)
+    }
+
+    /// Drops the stored nodes for which `f` returns false. The peaks are
+    /// always kept.
+    pub fn retain_nodes(&mut self, mut f: impl FnMut(&NodeId) -> bool) {
+        let peaks = &self.accumulator.peaks;
+        self.nodes
+            .retain(|node, _| f(node) || peaks.iter().any(|(peak, _)| peak == node));
+    }
+}
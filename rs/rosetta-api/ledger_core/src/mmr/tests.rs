@@ -0,0 +1,103 @@
+use super::*;
+use proptest::prelude::*;
+
+fn leaf(i: u64) -> MmrHash {
+    let mut hash = [0u8; 32];
+    hash[..8].copy_from_slice(&i.to_be_bytes());
+    hash
+}
+
+fn mmr(start: u64, end: u64) -> MerkleMountainRange {
+    let mut mmr = MerkleMountainRange::new(start);
+    for i in start..end {
+        mmr.append(leaf(i));
+    }
+    mmr
+}
+
+#[test]
+fn peak_ids_of_mmr_from_zero() {
+    assert_eq!(peak_ids(0, 0), vec![]);
+    assert_eq!(peak_ids(0, 1), vec![NodeId::leaf(0)]);
+    assert_eq!(
+        peak_ids(0, 7),
+        vec![
+            NodeId {
+                height: 2,
+                index: 0
+            },
+            NodeId {
+                height: 1,
+                index: 2
+            },
+            NodeId::leaf(6),
+        ]
+    );
+}
+
+#[test]
+fn peak_ids_of_mmr_from_offset() {
+    assert_eq!(
+        peak_ids(3, 8),
+        vec![
+            NodeId::leaf(3),
+            NodeId {
+                height: 2,
+                index: 1
+            },
+        ]
+    );
+}
+
+#[test]
+fn accumulator_matches_peak_ids() {
+    for start in 0..20 {
+        for end in start..70 {
+            let mmr = mmr(start, end);
+            let peaks: Vec<NodeId> = mmr.peaks().iter().map(|(node, _)| *node).collect();
+            assert_eq!(peaks, peak_ids(start, end), "start: {} end: {}", start, end);
+        }
+    }
+}
+
+#[test]
+fn path_leads_to_the_root() {
+    let mmr = mmr(5, 100);
+    let peaks: Vec<MmrHash> = mmr.peaks().iter().map(|(_, hash)| *hash).collect();
+    for i in mmr.range() {
+        let siblings = mmr.path(NodeId::leaf(i)).unwrap();
+        let (peak, hash) = follow_path(NodeId::leaf(i), leaf(i), &siblings);
+        let peak_index = peak_ids(5, 100)
+            .iter()
+            .position(|node| node == &peak)
+            .unwrap();
+        assert_eq!(peaks[peak_index], hash);
+    }
+    assert_eq!(mmr.root_hash(), root_hash(5, 100, &peaks));
+    assert_eq!(mmr.path(NodeId::leaf(4)), None);
+    assert_eq!(mmr.path(NodeId::leaf(100)), None);
+}
+
+#[test]
+fn retain_nodes_keeps_peaks() {
+    let mut mmr = mmr(0, 10);
+    mmr.retain_nodes(|_| false);
+    assert_eq!(mmr.num_stored_nodes(), mmr.peaks().len());
+    for (node, hash) in mmr.peaks() {
+        assert_eq!(mmr.node_hash(node), Some(hash));
+    }
+    assert_eq!(mmr.path(NodeId::leaf(0)), None);
+}
+
+proptest! {
+    #[test]
+    fn root_hash_depends_on_every_leaf(start in 0u64..100, len in 1u64..100, changed in 0u64..100) {
+        let end = start + len;
+        let changed = start + changed % len;
+        let mut other = MerkleMountainRange::new(start);
+        for i in start..end {
+            other.append(if i == changed { leaf(u64::MAX) } else { leaf(i) });
+        }
+        prop_assert_ne!(mmr(start, end).root_hash(), other.root_hash());
+    }
+}
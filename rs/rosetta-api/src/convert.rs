@@ -11,10 +11,10 @@ use crate::request::transaction_operation_results::TransactionOperationResults;
 use crate::request::transaction_results::TransactionResults;
 use crate::request::Request;
 use crate::request_types::{
-    ChangeAutoStakeMaturityMetadata, DisburseMetadata, FollowMetadata, KeyMetadata,
-    MergeMaturityMetadata, NeuronIdentifierMetadata, NeuronInfoMetadata, PublicKeyOrPrincipal,
-    RegisterVoteMetadata, RequestResultMetadata, SetDissolveTimestampMetadata, SpawnMetadata,
-    StakeMaturityMetadata, Status, STATUS_COMPLETED,
+    ChangeAutoStakeMaturityMetadata, DisburseMetadata, DisburseToNeuron, DisburseToNeuronMetadata,
+    FollowMetadata, KeyMetadata, MergeMaturityMetadata, MergeMetadata, NeuronIdentifierMetadata,
+    NeuronInfoMetadata, PublicKeyOrPrincipal, RegisterVoteMetadata, RequestResultMetadata,
+    SetDissolveTimestampMetadata, SpawnMetadata, StakeMaturityMetadata, Status, STATUS_COMPLETED,
 };
 use crate::transaction_id::TransactionIdentifier;
 use crate::{convert, errors};
@@ -269,6 +269,54 @@ pub fn operations_to_requests(
                 };
                 state.follow(account, pid, neuron_index, topic, followees)?;
             }
+            OperationType::Split => {
+                let NeuronIdentifierMetadata { neuron_index } = o.metadata.clone().try_into()?;
+                validate_neuron_management_op()?;
+                let amount = o
+                    .amount
+                    .as_ref()
+                    .ok_or_else(|| op_error(o, "Amount must be populated".into()))?;
+                let amount = ledgeramount_from_amount(amount, token_name).map_err(|e| {
+                    ApiError::internal_error(format!("Could not convert Amount {:?}", e))
+                })?;
+                state.split(account, neuron_index, amount)?;
+            }
+            OperationType::Merge => {
+                let MergeMetadata {
+                    source_neuron_id,
+                    neuron_index,
+                } = o.metadata.clone().try_into()?;
+                validate_neuron_management_op()?;
+                state.merge(account, neuron_index, source_neuron_id)?;
+            }
+            OperationType::DisburseToNeuron => {
+                let DisburseToNeuronMetadata {
+                    neuron_index,
+                    controller,
+                    dissolve_delay_seconds,
+                    kyc_verified,
+                    spawned_neuron_index,
+                } = o.metadata.clone().try_into()?;
+                validate_neuron_management_op()?;
+                let amount = o
+                    .amount
+                    .as_ref()
+                    .ok_or_else(|| op_error(o, "Amount must be populated".into()))?;
+                let amount = ledgeramount_from_amount(amount, token_name).map_err(|e| {
+                    ApiError::internal_error(format!("Could not convert Amount {:?}", e))
+                })?;
+                state.disburse_to_neuron(DisburseToNeuron {
+                    account,
+                    amount,
+                    controller: controller
+                        .map(principal_id_from_public_key_or_principal)
+                        .transpose()?,
+                    dissolve_delay_seconds,
+                    kyc_verified,
+                    spawned_neuron_index,
+                    neuron_index,
+                })?;
+            }
         }
     }
 
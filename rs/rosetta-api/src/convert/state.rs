@@ -2,9 +2,9 @@ use crate::errors::ApiError;
 use crate::models::seconds::Seconds;
 use crate::request::Request;
 use crate::request_types::{
-    AddHotKey, ChangeAutoStakeMaturity, Disburse, Follow, MergeMaturity, NeuronInfo,
-    PublicKeyOrPrincipal, RegisterVote, RemoveHotKey, SetDissolveTimestamp, Spawn, Stake,
-    StakeMaturity, StartDissolve, StopDissolve,
+    AddHotKey, ChangeAutoStakeMaturity, Disburse, DisburseToNeuron, Follow, Merge, MergeMaturity,
+    NeuronInfo, PublicKeyOrPrincipal, RegisterVote, RemoveHotKey, SetDissolveTimestamp, Spawn,
+    Split, Stake, StakeMaturity, StartDissolve, StopDissolve,
 };
 use ic_types::PrincipalId;
 use icp_ledger::{Operation, Tokens, DEFAULT_TRANSFER_FEE};
@@ -376,6 +376,53 @@ impl State {
         }));
         Ok(())
     }
+
+    pub fn split(
+        &mut self,
+        account: icp_ledger::AccountIdentifier,
+        neuron_index: u64,
+        amount: Tokens,
+    ) -> Result<(), ApiError> {
+        if amount == Tokens::ZERO {
+            let err = ApiError::InvalidTransaction(false, "Split amount must be positive".into());
+            return Err(err);
+        }
+        self.flush()?;
+        self.actions.push(Request::Split(Split {
+            account,
+            amount,
+            neuron_index,
+        }));
+        Ok(())
+    }
+
+    pub fn merge(
+        &mut self,
+        account: icp_ledger::AccountIdentifier,
+        neuron_index: u64,
+        source_neuron_id: u64,
+    ) -> Result<(), ApiError> {
+        self.flush()?;
+        self.actions.push(Request::Merge(Merge {
+            account,
+            source_neuron_id,
+            neuron_index,
+        }));
+        Ok(())
+    }
+
+    pub fn disburse_to_neuron(&mut self, req: DisburseToNeuron) -> Result<(), ApiError> {
+        if req.amount == Tokens::ZERO {
+            let err = ApiError::InvalidTransaction(
+                false,
+                "Disburse to neuron amount must be positive".into(),
+            );
+            return Err(err);
+        }
+        self.flush()?;
+        self.actions.push(Request::DisburseToNeuron(req));
+        Ok(())
+    }
 }
 
 /// Structure for manipulating tokens in relation to account, for example during transfers.
@@ -1,7 +1,9 @@
 mod handle_add_hotkey;
 mod handle_change_auto_stake_maturity;
 mod handle_disburse;
+mod handle_disburse_to_neuron;
 mod handle_follow;
+mod handle_merge;
 mod handle_merge_maturity;
 mod handle_neuron_info;
 mod handle_register_vote;
@@ -9,10 +11,12 @@ mod handle_remove_hotkey;
 mod handle_send;
 mod handle_set_dissolve_timestamp;
 mod handle_spawn;
+mod handle_split;
 mod handle_stake;
 mod handle_stake_maturity;
 mod handle_start_dissolve;
 mod handle_stop_dissolve;
+pub mod list_neurons_response;
 mod neuron_response;
 pub mod pending_proposals_response;
 pub mod proposal_info_response;
@@ -52,11 +56,12 @@ use crate::ledger_client::neuron_response::NeuronResponse;
 use crate::ledger_client::{
     handle_add_hotkey::handle_add_hotkey,
     handle_change_auto_stake_maturity::handle_change_auto_stake_maturity,
-    handle_disburse::handle_disburse, handle_follow::handle_follow,
+    handle_disburse::handle_disburse, handle_disburse_to_neuron::handle_disburse_to_neuron,
+    handle_follow::handle_follow, handle_merge::handle_merge,
     handle_merge_maturity::handle_merge_maturity, handle_neuron_info::handle_neuron_info,
     handle_register_vote::handle_register_vote, handle_remove_hotkey::handle_remove_hotkey,
     handle_send::handle_send, handle_set_dissolve_timestamp::handle_set_dissolve_timestamp,
-    handle_spawn::handle_spawn, handle_stake::handle_stake,
+    handle_spawn::handle_spawn, handle_split::handle_split, handle_stake::handle_stake,
     handle_stake_maturity::handle_stake_maturity, handle_start_dissolve::handle_start_dissolve,
     handle_stop_dissolve::handle_stop_dissolve,
 };
@@ -772,6 +777,9 @@ impl LedgerClient {
             RequestType::Stake { .. } => handle_stake(bytes),
             RequestType::StartDissolve { .. } => handle_start_dissolve(bytes, request_type),
             RequestType::StopDissolve { .. } => handle_stop_dissolve(bytes, request_type),
+            RequestType::Split { .. } => handle_split(bytes),
+            RequestType::Merge { .. } => handle_merge(bytes),
+            RequestType::DisburseToNeuron { .. } => handle_disburse_to_neuron(bytes),
         }
     }
 }
@@ -0,0 +1,55 @@
+use crate::{errors::ApiError, models, models::Object};
+use icp_ledger::BlockIndex;
+use serde_json::Value;
+
+/// How a neuron was derived from another one.
+#[derive(serde::Serialize, serde::Deserialize, std::fmt::Debug, Clone, PartialEq, Eq)]
+#[serde(rename_all = "SCREAMING_SNAKE_CASE")]
+pub enum NeuronLineageKind {
+    /// The target neuron was created from part of the source neuron's stake,
+    /// either by `SPLIT` or by `DISBURSE_TO_NEURON`.
+    Split,
+    /// The source neuron's stake was moved into an existing target neuron.
+    Merge,
+}
+
+/// A ledger transfer between two of the listed neurons.
+#[derive(serde::Serialize, serde::Deserialize, std::fmt::Debug, Clone, PartialEq, Eq)]
+pub struct NeuronLineageEntry {
+    pub block_index: BlockIndex,
+    pub kind: NeuronLineageKind,
+    pub source: models::AccountIdentifier,
+    pub target: models::AccountIdentifier,
+    pub amount_e8s: u64,
+}
+
+#[derive(serde::Serialize, serde::Deserialize, std::fmt::Debug, Clone, PartialEq, Eq)]
+pub struct NeuronLineage {
+    pub account_identifier: models::AccountIdentifier,
+    pub stake_e8s: u64,
+    pub lineage: Vec<NeuronLineageEntry>,
+}
+
+#[derive(serde::Serialize, serde::Deserialize, std::fmt::Debug, Clone)]
+pub struct ListNeuronsResponse {
+    pub neurons: Vec<NeuronLineage>,
+}
+impl From<ListNeuronsResponse> for Object {
+    fn from(r: ListNeuronsResponse) -> Self {
+        match serde_json::to_value(r) {
+            Ok(Value::Object(o)) => o,
+            _ => Object::default(),
+        }
+    }
+}
+impl TryFrom<Option<Object>> for ListNeuronsResponse {
+    type Error = ApiError;
+    fn try_from(o: Option<Object>) -> Result<Self, Self::Error> {
+        serde_json::from_value(serde_json::Value::Object(o.unwrap_or_default())).map_err(|e| {
+            ApiError::internal_error(format!(
+                "Could not parse a `ListNeuronsResponse` from JSON object: {}",
+                e
+            ))
+        })
+    }
+}
@@ -170,4 +170,13 @@ pub enum OperationType {
     #[serde(rename = "FOLLOW")]
     #[strum(serialize = "FOLLOW")]
     Follow,
+    #[serde(rename = "SPLIT")]
+    #[strum(serialize = "SPLIT")]
+    Split,
+    #[serde(rename = "MERGE")]
+    #[strum(serialize = "MERGE")]
+    Merge,
+    #[serde(rename = "DISBURSE_TO_NEURON")]
+    #[strum(serialize = "DISBURSE_TO_NEURON")]
+    DisburseToNeuron,
 }
@@ -58,6 +58,12 @@ pub enum Request {
     NeuronInfo(NeuronInfo),
     #[serde(rename = "FOLLOW")]
     Follow(Follow),
+    #[serde(rename = "SPLIT")]
+    Split(Split),
+    #[serde(rename = "MERGE")]
+    Merge(Merge),
+    #[serde(rename = "DISBURSE_TO_NEURON")]
+    DisburseToNeuron(DisburseToNeuron),
 }
 
 impl Request {
@@ -147,6 +153,17 @@ impl Request {
                 neuron_index: *neuron_index,
                 controller: controller.map(PublicKeyOrPrincipal::Principal),
             }),
+            Request::Split(Split { neuron_index, .. }) => Ok(RequestType::Split {
+                neuron_index: *neuron_index,
+            }),
+            Request::Merge(Merge { neuron_index, .. }) => Ok(RequestType::Merge {
+                neuron_index: *neuron_index,
+            }),
+            Request::DisburseToNeuron(DisburseToNeuron { neuron_index, .. }) => {
+                Ok(RequestType::DisburseToNeuron {
+                    neuron_index: *neuron_index,
+                })
+            }
         }
     }
 
@@ -176,6 +193,9 @@ impl Request {
                 Request::StakeMaturity(o) => builder.stake_maturity(o),
                 Request::NeuronInfo(o) => builder.neuron_info(o),
                 Request::Follow(o) => builder.follow(o),
+                Request::Split(o) => builder.split(o, token_name),
+                Request::Merge(o) => builder.merge(o),
+                Request::DisburseToNeuron(o) => builder.disburse_to_neuron(o, token_name),
             };
         }
         Ok(builder.build())
@@ -202,6 +222,9 @@ impl Request {
                 | Request::StakeMaturity(_)
                 | Request::NeuronInfo(_) // not neuron management but we need it signed.
                 | Request::Follow(_)
+                | Request::Split(_)
+                | Request::Merge(_)
+                | Request::DisburseToNeuron(_)
         )
     }
 }
@@ -466,8 +489,9 @@ impl TryFrom<&models::Request> for Request {
                 neuron_index,
                 controller,
             } => {
-                if let Some(Command::Follow(manage_neuron::Follow { topic, followees })) =
-                    manage_neuron()?
+                if let Some(Command::Follow(manage_neuron::Follow {
+                    topic, followees, ..
+                })) = manage_neuron()?
                 {
                     let ids = followees.iter().map(|n| n.id).collect();
                     match controller
@@ -494,6 +518,56 @@ impl TryFrom<&models::Request> for Request {
                     Err(ApiError::invalid_request("Invalid follow request."))
                 }
             }
+            RequestType::Split { neuron_index } => {
+                if let Some(Command::Split(manage_neuron::Split { amount_e8s })) = manage_neuron()?
+                {
+                    Ok(Request::Split(Split {
+                        account,
+                        amount: Tokens::from_e8s(amount_e8s),
+                        neuron_index: *neuron_index,
+                    }))
+                } else {
+                    Err(ApiError::invalid_request("Invalid split request."))
+                }
+            }
+            RequestType::Merge { neuron_index } => {
+                if let Some(Command::Merge(manage_neuron::Merge {
+                    source_neuron_id: Some(source_neuron_id),
+                })) = manage_neuron()?
+                {
+                    Ok(Request::Merge(Merge {
+                        account,
+                        source_neuron_id: source_neuron_id.id,
+                        neuron_index: *neuron_index,
+                    }))
+                } else {
+                    Err(ApiError::invalid_request("Invalid merge request."))
+                }
+            }
+            RequestType::DisburseToNeuron { neuron_index } => {
+                if let Some(Command::DisburseToNeuron(manage_neuron::DisburseToNeuron {
+                    new_controller,
+                    amount_e8s,
+                    dissolve_delay_seconds,
+                    kyc_verified,
+                    nonce,
+                })) = manage_neuron()?
+                {
+                    Ok(Request::DisburseToNeuron(DisburseToNeuron {
+                        account,
+                        amount: Tokens::from_e8s(amount_e8s),
+                        controller: new_controller,
+                        dissolve_delay_seconds,
+                        kyc_verified,
+                        spawned_neuron_index: nonce,
+                        neuron_index: *neuron_index,
+                    }))
+                } else {
+                    Err(ApiError::invalid_request(
+                        "Invalid disburse to neuron request.",
+                    ))
+                }
+            }
         }
     }
 }
@@ -7,10 +7,13 @@ mod construction_payloads;
 mod construction_preprocess;
 mod construction_submit;
 
+use crate::ledger_client::list_neurons_response::{
+    ListNeuronsResponse, NeuronLineage, NeuronLineageEntry, NeuronLineageKind,
+};
 use crate::ledger_client::pending_proposals_response::PendingProposalsResponse;
 use crate::ledger_client::proposal_info_response::ProposalInfoResponse;
 use crate::models::{CallResponse, Object};
-use crate::request_types::GetProposalInfo;
+use crate::request_types::{GetProposalInfo, ListNeurons};
 use crate::{convert, models, API_VERSION, NODE_VERSION};
 use ic_ledger_canister_blocks_synchronizer::blocks::Blocks;
 use ic_ledger_canister_blocks_synchronizer::blocks::HashedBlock;
@@ -23,6 +26,7 @@ use ic_types::crypto::DOMAIN_IC_REQUEST;
 use ic_types::messages::MessageId;
 use ic_types::CanisterId;
 use icp_ledger::{Block, BlockIndex};
+use std::collections::{HashMap, HashSet};
 use std::convert::{TryFrom, TryInto};
 use std::sync::Arc;
 use strum::IntoEnumIterator;
@@ -179,6 +183,12 @@ impl RosettaRequestHandler {
                 let pending_proposals_response = PendingProposalsResponse::from(pending_proposals);
                 Ok(CallResponse::new(Object::from(pending_proposals_response)))
             }
+            "list_neurons" => {
+                let list_neurons = ListNeurons::try_from(Some(msg.parameters))?;
+                let blocks = self.ledger.read_blocks().await;
+                let response = neuron_lineage(&blocks, &list_neurons.neuron_accounts)?;
+                Ok(CallResponse::new(Object::from(response)))
+            }
             _ => Err(ApiError::InvalidRequest(
                 false,
                 Details::from(format!(
@@ -706,3 +716,103 @@ pub fn make_sig_data(message_id: &MessageId) -> Vec<u8> {
     sig_data.extend_from_slice(message_id.as_bytes());
     sig_data
 }
+
+/// Lists the given neuron accounts, followed by the neurons split off them,
+/// together with the ledger transfers between them. Governance moves stake
+/// between neuron subaccounts when splitting, merging or disbursing to a
+/// neuron, so these transfers are the lineage of the neurons. A transfer
+/// creating its target account is a split, any other one is a merge.
+fn neuron_lineage(
+    blocks: &Blocks,
+    neuron_accounts: &[models::AccountIdentifier],
+) -> Result<ListNeuronsResponse, ApiError> {
+    let mut accounts = neuron_accounts
+        .iter()
+        .map(|aid| {
+            from_model_account_identifier(aid)
+                .map_err(|e| ApiError::InvalidAccountId(false, e.into()))
+        })
+        .collect::<Result<Vec<_>, _>>()?;
+    let mut known: HashSet<_> = accounts.iter().cloned().collect();
+
+    // Account balance histories are sorted by descending block index. The
+    // children of a neuron get their subaccounts from governance, so the
+    // caller may not know them: they are found by following the transfers
+    // out of the neuron that created their account.
+    let mut histories = HashMap::new();
+    let mut next = 0;
+    while next < accounts.len() {
+        let account = accounts[next];
+        next += 1;
+        let history = blocks.get_account_balance_history(&account, None)?;
+        for (block_index, _) in history.iter().rev() {
+            if let icp_ledger::Operation::Transfer { from, to, .. } =
+                blocks.get_transaction(block_index)?.operation
+            {
+                if from != account || known.contains(&to) {
+                    continue;
+                }
+                let created_target = blocks
+                    .get_account_balance_history(&to, None)?
+                    .last()
+                    .map(|(first, _)| first == block_index)
+                    .unwrap_or(false);
+                if created_target {
+                    known.insert(to);
+                    accounts.push(to);
+                }
+            }
+        }
+        histories.insert(account, history);
+    }
+
+    let mut entries = vec![];
+    for (account, history) in &histories {
+        for (block_index, _) in history {
+            if let icp_ledger::Operation::Transfer {
+                from, to, amount, ..
+            } = blocks.get_transaction(block_index)?.operation
+            {
+                if from != *account || !known.contains(&to) {
+                    continue;
+                }
+                let created_target = histories[&to]
+                    .last()
+                    .map(|(first, _)| first == block_index)
+                    .unwrap_or(false);
+                entries.push(NeuronLineageEntry {
+                    block_index: *block_index,
+                    kind: if created_target {
+                        NeuronLineageKind::Split
+                    } else {
+                        NeuronLineageKind::Merge
+                    },
+                    source: convert::to_model_account_identifier(&from),
+                    target: convert::to_model_account_identifier(&to),
+                    amount_e8s: amount.get_e8s(),
+                });
+            }
+        }
+    }
+    entries.sort_by_key(|entry| entry.block_index);
+
+    let neurons = accounts
+        .iter()
+        .map(|account| {
+            let aid = convert::to_model_account_identifier(account);
+            NeuronLineage {
+                stake_e8s: histories[account]
+                    .first()
+                    .map(|(_, tokens)| tokens.get_e8s())
+                    .unwrap_or(0),
+                lineage: entries
+                    .iter()
+                    .filter(|entry| entry.source == aid || entry.target == aid)
+                    .cloned()
+                    .collect(),
+                account_identifier: aid,
+            }
+        })
+        .collect();
+    Ok(ListNeuronsResponse { neurons })
+}
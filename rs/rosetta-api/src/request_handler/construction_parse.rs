@@ -3,9 +3,9 @@ use crate::errors::ApiError;
 use crate::models::{ConstructionParseRequest, ConstructionParseResponse, ParsedTransaction};
 use crate::request_handler::{verify_network_id, RosettaRequestHandler};
 use crate::request_types::{
-    AddHotKey, ChangeAutoStakeMaturity, Disburse, Follow, MergeMaturity, NeuronInfo,
-    PublicKeyOrPrincipal, RegisterVote, RemoveHotKey, RequestType, SetDissolveTimestamp, Spawn,
-    Stake, StakeMaturity, StartDissolve, StopDissolve,
+    AddHotKey, ChangeAutoStakeMaturity, Disburse, DisburseToNeuron, Follow, Merge, MergeMaturity,
+    NeuronInfo, PublicKeyOrPrincipal, RegisterVote, RemoveHotKey, RequestType,
+    SetDissolveTimestamp, Spawn, Split, Stake, StakeMaturity, StartDissolve, StopDissolve,
 };
 
 use ic_nns_governance::pb::v1::{
@@ -17,7 +17,7 @@ use crate::models::seconds::Seconds;
 use crate::request::Request;
 use ic_types::messages::{Blob, HttpCallContent, HttpCanisterUpdate};
 use ic_types::PrincipalId;
-use icp_ledger::{AccountIdentifier, Operation, SendArgs};
+use icp_ledger::{AccountIdentifier, Operation, SendArgs, Tokens};
 use std::convert::TryFrom;
 
 impl RosettaRequestHandler {
@@ -98,6 +98,15 @@ impl RosettaRequestHandler {
                     neuron_index,
                     controller,
                 } => follow(&mut requests, arg, from, neuron_index, controller)?,
+                RequestType::Split { neuron_index } => {
+                    split(&mut requests, arg, from, neuron_index)?
+                }
+                RequestType::Merge { neuron_index } => {
+                    merge(&mut requests, arg, from, neuron_index)?
+                }
+                RequestType::DisburseToNeuron { neuron_index } => {
+                    disburse_to_neuron(&mut requests, arg, from, neuron_index)?
+                }
             }
         }
 
@@ -528,7 +537,10 @@ fn follow(
     let manage: ManageNeuron = candid::decode_one(arg.0.as_ref()).map_err(|e| {
         ApiError::internal_error(format!("Could not decode ManageNeuron argument: {:?}", e))
     })?;
-    if let Some(Command::Follow(manage_neuron::Follow { topic, followees })) = manage.command {
+    if let Some(Command::Follow(manage_neuron::Follow {
+        topic, followees, ..
+    })) = manage.command
+    {
         let ids = followees.iter().map(|x| x.id).collect();
         match controller.map(convert::principal_id_from_public_key_or_principal) {
             None => {
@@ -560,3 +572,89 @@ fn follow(
     }
     Ok(())
 }
+
+/// Handle SPLIT.
+fn split(
+    requests: &mut Vec<Request>,
+    arg: Blob,
+    from: AccountIdentifier,
+    neuron_index: u64,
+) -> Result<(), ApiError> {
+    let manage: ManageNeuron = candid::decode_one(arg.0.as_ref()).map_err(|e| {
+        ApiError::internal_error(format!("Could not decode ManageNeuron argument: {:?}", e))
+    })?;
+    if let Some(Command::Split(manage_neuron::Split { amount_e8s })) = manage.command {
+        requests.push(Request::Split(Split {
+            account: from,
+            amount: Tokens::from_e8s(amount_e8s),
+            neuron_index,
+        }));
+    } else {
+        return Err(ApiError::internal_error(
+            "Incompatible manage_neuron command".to_string(),
+        ));
+    }
+    Ok(())
+}
+
+/// Handle MERGE.
+fn merge(
+    requests: &mut Vec<Request>,
+    arg: Blob,
+    from: AccountIdentifier,
+    neuron_index: u64,
+) -> Result<(), ApiError> {
+    let manage: ManageNeuron = candid::decode_one(arg.0.as_ref()).map_err(|e| {
+        ApiError::internal_error(format!("Could not decode ManageNeuron argument: {:?}", e))
+    })?;
+    if let Some(Command::Merge(manage_neuron::Merge {
+        source_neuron_id: Some(source_neuron_id),
+    })) = manage.command
+    {
+        requests.push(Request::Merge(Merge {
+            account: from,
+            source_neuron_id: source_neuron_id.id,
+            neuron_index,
+        }));
+    } else {
+        return Err(ApiError::internal_error(
+            "Incompatible manage_neuron command (source neuron id is required).",
+        ));
+    }
+    Ok(())
+}
+
+/// Handle DISBURSE_TO_NEURON.
+fn disburse_to_neuron(
+    requests: &mut Vec<Request>,
+    arg: Blob,
+    from: AccountIdentifier,
+    neuron_index: u64,
+) -> Result<(), ApiError> {
+    let manage: ManageNeuron = candid::decode_one(arg.0.as_ref()).map_err(|e| {
+        ApiError::internal_error(format!("Could not decode ManageNeuron argument: {:?}", e))
+    })?;
+    if let Some(Command::DisburseToNeuron(manage_neuron::DisburseToNeuron {
+        new_controller,
+        amount_e8s,
+        dissolve_delay_seconds,
+        kyc_verified,
+        nonce,
+    })) = manage.command
+    {
+        requests.push(Request::DisburseToNeuron(DisburseToNeuron {
+            account: from,
+            amount: Tokens::from_e8s(amount_e8s),
+            controller: new_controller,
+            dissolve_delay_seconds,
+            kyc_verified,
+            spawned_neuron_index: nonce,
+            neuron_index,
+        }));
+    } else {
+        return Err(ApiError::internal_error(
+            "Incompatible manage_neuron command".to_string(),
+        ));
+    }
+    Ok(())
+}
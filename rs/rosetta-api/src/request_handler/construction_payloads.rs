@@ -24,9 +24,9 @@ use crate::models::{
 use crate::request::Request;
 use crate::request_handler::{make_sig_data, verify_network_id, RosettaRequestHandler};
 use crate::request_types::{
-    AddHotKey, ChangeAutoStakeMaturity, Disburse, Follow, MergeMaturity, NeuronInfo,
-    PublicKeyOrPrincipal, RegisterVote, RemoveHotKey, RequestType, SetDissolveTimestamp, Spawn,
-    Stake, StakeMaturity, StartDissolve, StopDissolve,
+    AddHotKey, ChangeAutoStakeMaturity, Disburse, DisburseToNeuron, Follow, Merge, MergeMaturity,
+    NeuronInfo, PublicKeyOrPrincipal, RegisterVote, RemoveHotKey, RequestType,
+    SetDissolveTimestamp, Spawn, Split, Stake, StakeMaturity, StartDissolve, StopDissolve,
 };
 use crate::{convert, models};
 
@@ -209,6 +209,27 @@ impl RosettaRequestHandler {
                     &pks_map,
                     &ingress_expiries,
                 )?,
+                Request::Split(req) => handle_split(
+                    req,
+                    &mut payloads,
+                    &mut updates,
+                    &pks_map,
+                    &ingress_expiries,
+                )?,
+                Request::Merge(req) => handle_merge(
+                    req,
+                    &mut payloads,
+                    &mut updates,
+                    &pks_map,
+                    &ingress_expiries,
+                )?,
+                Request::DisburseToNeuron(req) => handle_disburse_to_neuron(
+                    req,
+                    &mut payloads,
+                    &mut updates,
+                    &pks_map,
+                    &ingress_expiries,
+                )?,
             }
         }
 
@@ -778,6 +799,7 @@ fn handle_follow(
     let command = Command::Follow(manage_neuron::Follow {
         topic,
         followees: neuron_ids,
+        weights_basis_points: vec![],
     });
     add_neuron_management_payload(
         RequestType::Follow {
@@ -796,6 +818,102 @@ fn handle_follow(
     Ok(())
 }
 
+/// Handle SPLIT.
+fn handle_split(
+    req: Split,
+    payloads: &mut Vec<SigningPayload>,
+    updates: &mut Vec<(RequestType, HttpCanisterUpdate)>,
+    pks_map: &HashMap<icp_ledger::AccountIdentifier, &PublicKey>,
+    ingress_expiries: &[u64],
+) -> Result<(), ApiError> {
+    let neuron_index = req.neuron_index;
+    let command = Command::Split(manage_neuron::Split {
+        amount_e8s: req.amount.get_e8s(),
+    });
+    add_neuron_management_payload(
+        RequestType::Split { neuron_index },
+        req.account,
+        None,
+        neuron_index,
+        command,
+        payloads,
+        updates,
+        pks_map,
+        ingress_expiries,
+    )?;
+    Ok(())
+}
+
+/// Handle MERGE.
+fn handle_merge(
+    req: Merge,
+    payloads: &mut Vec<SigningPayload>,
+    updates: &mut Vec<(RequestType, HttpCanisterUpdate)>,
+    pks_map: &HashMap<icp_ledger::AccountIdentifier, &PublicKey>,
+    ingress_expiries: &[u64],
+) -> Result<(), ApiError> {
+    let neuron_index = req.neuron_index;
+    let command = Command::Merge(manage_neuron::Merge {
+        source_neuron_id: Some(NeuronId {
+            id: req.source_neuron_id,
+        }),
+    });
+    add_neuron_management_payload(
+        RequestType::Merge { neuron_index },
+        req.account,
+        None,
+        neuron_index,
+        command,
+        payloads,
+        updates,
+        pks_map,
+        ingress_expiries,
+    )?;
+    Ok(())
+}
+
+/// Handle DISBURSE_TO_NEURON.
+fn handle_disburse_to_neuron(
+    req: DisburseToNeuron,
+    payloads: &mut Vec<SigningPayload>,
+    updates: &mut Vec<(RequestType, HttpCanisterUpdate)>,
+    pks_map: &HashMap<icp_ledger::AccountIdentifier, &PublicKey>,
+    ingress_expiries: &[u64],
+) -> Result<(), ApiError> {
+    let account = req.account;
+    let neuron_index = req.neuron_index;
+    // Governance requires an explicit controller for the new neuron, so
+    // default to the principal signing the request.
+    let new_controller = match req.controller {
+        Some(controller) => controller,
+        None => {
+            let pk = pks_map.get(&account).ok_or_else(|| {
+                ApiError::internal_error(format!("Cannot find public key for account {}", account,))
+            })?;
+            convert::principal_id_from_public_key(pk)?
+        }
+    };
+    let command = Command::DisburseToNeuron(manage_neuron::DisburseToNeuron {
+        new_controller: Some(new_controller),
+        amount_e8s: req.amount.get_e8s(),
+        dissolve_delay_seconds: req.dissolve_delay_seconds,
+        kyc_verified: req.kyc_verified,
+        nonce: req.spawned_neuron_index,
+    });
+    add_neuron_management_payload(
+        RequestType::DisburseToNeuron { neuron_index },
+        account,
+        None,
+        neuron_index,
+        command,
+        payloads,
+        updates,
+        pks_map,
+        ingress_expiries,
+    )?;
+    Ok(())
+}
+
 fn add_neuron_management_payload(
     request_type: RequestType,
     account: icp_ledger::AccountIdentifier,
@@ -7,8 +7,9 @@ use crate::models::{
 use crate::request::Request;
 use crate::request_handler::{verify_network_id, RosettaRequestHandler};
 use crate::request_types::{
-    AddHotKey, ChangeAutoStakeMaturity, Disburse, Follow, MergeMaturity, NeuronInfo, RegisterVote,
-    RemoveHotKey, SetDissolveTimestamp, Spawn, Stake, StakeMaturity, StartDissolve, StopDissolve,
+    AddHotKey, ChangeAutoStakeMaturity, Disburse, DisburseToNeuron, Follow, Merge, MergeMaturity,
+    NeuronInfo, RegisterVote, RemoveHotKey, SetDissolveTimestamp, Spawn, Split, Stake,
+    StakeMaturity, StartDissolve, StopDissolve,
 };
 use icp_ledger::Operation;
 use std::collections::HashSet;
@@ -74,6 +75,9 @@ fn required_public_key(request: Request) -> Result<icp_ledger::AccountIdentifier
         | Request::MergeMaturity(MergeMaturity { account, .. })
         | Request::StakeMaturity(StakeMaturity { account, .. })
         | Request::NeuronInfo(NeuronInfo { account, .. })
-        | Request::Follow(Follow { account, .. }) => Ok(account),
+        | Request::Follow(Follow { account, .. })
+        | Request::Split(Split { account, .. })
+        | Request::Merge(Merge { account, .. })
+        | Request::DisburseToNeuron(DisburseToNeuron { account, .. }) => Ok(account),
     }
 }
@@ -36,6 +36,9 @@ pub const REGISTER_VOTE: &str = "REGISTER_VOTE";
 pub const STAKE_MATURITY: &str = "STAKE_MATURITY";
 pub const NEURON_INFO: &str = "NEURON_INFO";
 pub const FOLLOW: &str = "FOLLOW";
+pub const SPLIT: &str = "SPLIT";
+pub const MERGE: &str = "MERGE";
+pub const DISBURSE_TO_NEURON: &str = "DISBURSE_TO_NEURON";
 
 /// `RequestType` contains all supported values of `Operation.type`.
 /// Extra information, such as `neuron_index` should only be included
@@ -94,6 +97,15 @@ pub enum RequestType {
         neuron_index: u64,
         controller: Option<PublicKeyOrPrincipal>,
     },
+    #[serde(rename = "SPLIT")]
+    #[serde(alias = "Split")]
+    Split { neuron_index: u64 },
+    #[serde(rename = "MERGE")]
+    #[serde(alias = "Merge")]
+    Merge { neuron_index: u64 },
+    #[serde(rename = "DISBURSE_TO_NEURON")]
+    #[serde(alias = "DisburseToNeuron")]
+    DisburseToNeuron { neuron_index: u64 },
 }
 
 impl RequestType {
@@ -114,6 +126,9 @@ impl RequestType {
             RequestType::StakeMaturity { .. } => STAKE_MATURITY,
             RequestType::NeuronInfo { .. } => NEURON_INFO,
             RequestType::Follow { .. } => FOLLOW,
+            RequestType::Split { .. } => SPLIT,
+            RequestType::Merge { .. } => MERGE,
+            RequestType::DisburseToNeuron { .. } => DISBURSE_TO_NEURON,
         }
     }
 
@@ -138,6 +153,9 @@ impl RequestType {
                 | RequestType::StakeMaturity { .. }
                 | RequestType::NeuronInfo { .. }
                 | RequestType::Follow { .. }
+                | RequestType::Split { .. }
+                | RequestType::Merge { .. }
+                | RequestType::DisburseToNeuron { .. }
         )
     }
 }
@@ -244,6 +262,25 @@ impl TryFrom<Option<Object>> for GetProposalInfo {
     }
 }
 
+/// Parameters of the `list_neurons` call. Neurons are identified by their
+/// ledger accounts, since split neurons live on random subaccounts.
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize, Serialize)]
+pub struct ListNeurons {
+    pub neuron_accounts: Vec<models::AccountIdentifier>,
+}
+
+impl TryFrom<Option<Object>> for ListNeurons {
+    type Error = ApiError;
+    fn try_from(o: Option<Object>) -> Result<Self, Self::Error> {
+        serde_json::from_value(serde_json::Value::Object(o.unwrap_or_default())).map_err(|e| {
+            ApiError::internal_error(format!(
+                "Could not parse `neuron_accounts` from metadata JSON object: {}",
+                e
+            ))
+        })
+    }
+}
+
 #[derive(Debug, Clone, PartialEq, Eq, Deserialize, Serialize)]
 pub struct SetDissolveTimestamp {
     pub account: icp_ledger::AccountIdentifier,
@@ -358,6 +395,38 @@ pub struct Follow {
     pub neuron_index: u64,
 }
 
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize, Serialize)]
+pub struct Split {
+    pub account: icp_ledger::AccountIdentifier,
+    /// The stake moved to the new neuron, including the transfer fee.
+    pub amount: Tokens,
+    #[serde(default)]
+    pub neuron_index: u64,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize, Serialize)]
+pub struct Merge {
+    pub account: icp_ledger::AccountIdentifier,
+    /// The neuron whose stake and maturity are merged into this neuron.
+    pub source_neuron_id: u64,
+    #[serde(default)]
+    pub neuron_index: u64,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize, Serialize)]
+pub struct DisburseToNeuron {
+    pub account: icp_ledger::AccountIdentifier,
+    pub amount: Tokens,
+    /// Controller of the new neuron. Defaults to the signer of the request.
+    pub controller: Option<PrincipalId>,
+    pub dissolve_delay_seconds: u64,
+    pub kyc_verified: bool,
+    /// Used as the nonce of the new neuron, like `spawned_neuron_index`.
+    pub spawned_neuron_index: u64,
+    #[serde(default)]
+    pub neuron_index: u64,
+}
+
 #[derive(Debug, Clone, Eq, PartialOrd, Ord, Deserialize, Serialize)]
 #[serde(rename_all = "snake_case")]
 // Externally tagged by default.
@@ -830,6 +899,94 @@ impl From<FollowMetadata> for Object {
     }
 }
 
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord, Deserialize, Serialize)]
+pub struct MergeMetadata {
+    pub source_neuron_id: u64,
+    #[serde(default)]
+    pub neuron_index: u64,
+}
+
+impl TryFrom<Option<Object>> for MergeMetadata {
+    type Error = ApiError;
+    fn try_from(o: Option<Object>) -> Result<Self, Self::Error> {
+        serde_json::from_value(serde_json::Value::Object(o.unwrap_or_default())).map_err(|e| {
+            ApiError::internal_error(format!(
+                "Could not parse MERGE operation metadata from metadata JSON object: {}",
+                e
+            ))
+        })
+    }
+}
+
+impl From<MergeMetadata> for Object {
+    fn from(m: MergeMetadata) -> Self {
+        match serde_json::to_value(m) {
+            Ok(Value::Object(o)) => o,
+            _ => unreachable!(),
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord, Deserialize, Serialize)]
+pub struct DisburseToNeuronMetadata {
+    #[serde(default)]
+    pub neuron_index: u64,
+
+    #[serde(default)]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub controller: Option<PublicKeyOrPrincipal>,
+
+    pub dissolve_delay_seconds: u64,
+
+    #[serde(default)]
+    pub kyc_verified: bool,
+
+    pub spawned_neuron_index: u64,
+}
+
+impl TryFrom<Option<Object>> for DisburseToNeuronMetadata {
+    type Error = ApiError;
+    fn try_from(o: Option<Object>) -> Result<Self, Self::Error> {
+        serde_json::from_value(serde_json::Value::Object(o.unwrap_or_default())).map_err(|e| {
+            ApiError::internal_error(format!(
+                "Could not parse DISBURSE_TO_NEURON operation metadata from metadata JSON object: {}",
+                e
+            ))
+        })
+    }
+}
+
+impl From<DisburseToNeuronMetadata> for Object {
+    fn from(m: DisburseToNeuronMetadata) -> Self {
+        match serde_json::to_value(m) {
+            Ok(Value::Object(o)) => o,
+            _ => unreachable!(),
+        }
+    }
+}
+
+#[test]
+fn test_parse_disburse_to_neuron_metadata() {
+    let m1 = r#"
+            {
+                "neuron_index": 2,
+                "dissolve_delay_seconds": 31536000,
+                "spawned_neuron_index": 7
+            }
+        "#;
+    let m1: DisburseToNeuronMetadata = serde_json::from_str(m1).unwrap();
+    assert_eq!(
+        m1,
+        DisburseToNeuronMetadata {
+            neuron_index: 2,
+            controller: None,
+            dissolve_delay_seconds: 31_536_000,
+            kyc_verified: false,
+            spawned_neuron_index: 7,
+        }
+    );
+}
+
 /// Transaction is a bit of a misnomer, since operations can succeed or fail
 /// independently from a Transaction.
 #[derive(Default)]
@@ -1268,6 +1425,86 @@ impl TransactionBuilder {
             ),
         });
     }
+    pub fn split(&mut self, split: &Split, token_name: &str) {
+        let Split {
+            account,
+            amount,
+            neuron_index,
+        } = split;
+        let operation_identifier = self.allocate_op_id();
+        self.ops.push(Operation {
+            operation_identifier,
+            _type: OperationType::Split,
+            status: None,
+            account: Some(to_model_account_identifier(account)),
+            amount: Some(tokens_to_amount(*amount, token_name).expect("failed to convert amount")),
+            related_operations: None,
+            coin_change: None,
+            metadata: Some(
+                NeuronIdentifierMetadata {
+                    neuron_index: *neuron_index,
+                }
+                .into(),
+            ),
+        });
+    }
+
+    pub fn merge(&mut self, merge: &Merge) {
+        let Merge {
+            account,
+            source_neuron_id,
+            neuron_index,
+        } = merge;
+        let operation_identifier = self.allocate_op_id();
+        self.ops.push(Operation {
+            operation_identifier,
+            _type: OperationType::Merge,
+            status: None,
+            account: Some(to_model_account_identifier(account)),
+            amount: None,
+            related_operations: None,
+            coin_change: None,
+            metadata: Some(
+                MergeMetadata {
+                    source_neuron_id: *source_neuron_id,
+                    neuron_index: *neuron_index,
+                }
+                .into(),
+            ),
+        });
+    }
+
+    pub fn disburse_to_neuron(&mut self, disburse: &DisburseToNeuron, token_name: &str) {
+        let DisburseToNeuron {
+            account,
+            amount,
+            controller,
+            dissolve_delay_seconds,
+            kyc_verified,
+            spawned_neuron_index,
+            neuron_index,
+        } = disburse;
+        let operation_identifier = self.allocate_op_id();
+        self.ops.push(Operation {
+            operation_identifier,
+            _type: OperationType::DisburseToNeuron,
+            status: None,
+            account: Some(to_model_account_identifier(account)),
+            amount: Some(tokens_to_amount(*amount, token_name).expect("failed to convert amount")),
+            related_operations: None,
+            coin_change: None,
+            metadata: Some(
+                DisburseToNeuronMetadata {
+                    neuron_index: *neuron_index,
+                    controller: pkp_from_principal(controller),
+                    dissolve_delay_seconds: *dissolve_delay_seconds,
+                    kyc_verified: *kyc_verified,
+                    spawned_neuron_index: *spawned_neuron_index,
+                }
+                .into(),
+            ),
+        });
+    }
 }
 
 /// Converts an optional PrincipalId to an optional PublicKeyOrPrincipal.
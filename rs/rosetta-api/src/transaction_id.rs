@@ -77,7 +77,10 @@ impl TransactionIdentifier {
             | RequestType::MergeMaturity { .. }
             | RequestType::StakeMaturity { .. }
             | RequestType::NeuronInfo { .. }
-            | RequestType::Follow { .. } => {
+            | RequestType::Follow { .. }
+            | RequestType::Split { .. }
+            | RequestType::Merge { .. }
+            | RequestType::DisburseToNeuron { .. } => {
                 // Unfortunately, staking operations don't really have a transaction ID
                 Ok(TransactionIdentifier {
                     hash: NEURON_MANAGEMENT_PSEUDO_HASH.to_string(),
@@ -0,0 +1,339 @@
+use super::*;
+
+use ic_ledger_core::tokens::CheckedSub;
+use ic_rosetta_api::convert::operations_to_requests;
+use ic_rosetta_api::ledger_client::list_neurons_response::{
+    ListNeuronsResponse, NeuronLineage, NeuronLineageEntry, NeuronLineageKind,
+};
+use ic_rosetta_api::models::{
+    CallRequest, ConstructionParseRequest, ConstructionPayloadsRequest, Object,
+};
+use ic_rosetta_api::request_types::{DisburseToNeuron, Merge, Split};
+use icp_ledger::Memo;
+
+/// Builds the unsigned transaction for `request` with the construction API and
+/// parses it back into requests.
+fn construct_and_parse(req_handler: &RosettaRequestHandler, request: Request) -> Vec<Request> {
+    let (_acc_id, _ed_kp, pk, _pid) = ic_rosetta_test_utils::make_user_ed25519(1);
+    let operations = Request::requests_to_operations(&[request], DEFAULT_TOKEN_SYMBOL).unwrap();
+
+    let mut payloads_request =
+        ConstructionPayloadsRequest::new(req_handler.network_id(), operations);
+    payloads_request.public_keys = Some(vec![pk]);
+    let payloads = req_handler.construction_payloads(payloads_request).unwrap();
+
+    let parsed = req_handler
+        .construction_parse(ConstructionParseRequest::new(
+            req_handler.network_id(),
+            false,
+            payloads.unsigned_transaction,
+        ))
+        .unwrap();
+    operations_to_requests(&parsed.operations, false, DEFAULT_TOKEN_SYMBOL).unwrap()
+}
+
+#[test]
+fn split_round_trip_test() {
+    let req_handler =
+        RosettaRequestHandler::new_with_default_blockchain(Arc::new(TestLedger::new()));
+    let (acc_id, _ed_kp, _pk, _pid) = ic_rosetta_test_utils::make_user_ed25519(1);
+
+    let request = Request::Split(Split {
+        account: acc_id,
+        amount: Tokens::new(5, 0).unwrap(),
+        neuron_index: 2,
+    });
+    assert_eq!(
+        construct_and_parse(&req_handler, request.clone()),
+        vec![request]
+    );
+}
+
+#[test]
+fn merge_round_trip_test() {
+    let req_handler =
+        RosettaRequestHandler::new_with_default_blockchain(Arc::new(TestLedger::new()));
+    let (acc_id, _ed_kp, _pk, _pid) = ic_rosetta_test_utils::make_user_ed25519(1);
+
+    let request = Request::Merge(Merge {
+        account: acc_id,
+        source_neuron_id: 42,
+        neuron_index: 1,
+    });
+    assert_eq!(
+        construct_and_parse(&req_handler, request.clone()),
+        vec![request]
+    );
+}
+
+#[test]
+fn disburse_to_neuron_round_trip_test() {
+    let req_handler =
+        RosettaRequestHandler::new_with_default_blockchain(Arc::new(TestLedger::new()));
+    let (acc_id, _ed_kp, _pk, pid) = ic_rosetta_test_utils::make_user_ed25519(1);
+    let (_, _, _, controller) = ic_rosetta_test_utils::make_user_ed25519(2);
+
+    let request = DisburseToNeuron {
+        account: acc_id,
+        amount: Tokens::new(3, 0).unwrap(),
+        controller: Some(controller),
+        dissolve_delay_seconds: 6 * 30 * 24 * 60 * 60,
+        kyc_verified: true,
+        spawned_neuron_index: 7,
+        neuron_index: 1,
+    };
+    assert_eq!(
+        construct_and_parse(&req_handler, Request::DisburseToNeuron(request.clone())),
+        vec![Request::DisburseToNeuron(request.clone())]
+    );
+
+    // Without an explicit controller the signer becomes the controller of the
+    // new neuron.
+    assert_eq!(
+        construct_and_parse(
+            &req_handler,
+            Request::DisburseToNeuron(DisburseToNeuron {
+                controller: None,
+                ..request.clone()
+            })
+        ),
+        vec![Request::DisburseToNeuron(DisburseToNeuron {
+            controller: Some(pid),
+            ..request
+        })]
+    );
+}
+
+async fn push_block(ledger: &TestLedger, operation: Operation) -> BlockIndex {
+    let (parent_hash, index) = match ledger.last_submitted().await.ok() {
+        None => (None, 0),
+        Some(hb) => (Some(hb.hash), hb.index + 1),
+    };
+    let timestamp = ledger.next_block_timestamp();
+    let block = Block::new(
+        parent_hash,
+        operation,
+        Memo(0),
+        timestamp,
+        timestamp,
+        DEFAULT_TRANSFER_FEE,
+    )
+    .unwrap();
+    ledger
+        .add_block(HashedBlock::hash_block(block.encode(), parent_hash, index))
+        .await
+        .unwrap();
+    index
+}
+
+#[actix_rt::test]
+async fn list_neurons_lineage_test() {
+    let ledger = TestLedger::new();
+    let neuron_a = acc_id(1);
+    let neuron_b = acc_id(2);
+    let neuron_c = acc_id(3);
+    let other = acc_id(4);
+    let fee = DEFAULT_TRANSFER_FEE;
+    let transfer = |from, to, amount| Operation::Transfer {
+        from,
+        to,
+        amount,
+        fee,
+    };
+
+    push_block(
+        &ledger,
+        Operation::Mint {
+            to: neuron_a,
+            amount: Tokens::new(100, 0).unwrap(),
+        },
+    )
+    .await;
+    // B is created by this transfer, so it is split off A.
+    let split_index = push_block(
+        &ledger,
+        transfer(neuron_a, neuron_b, Tokens::new(30, 0).unwrap()),
+    )
+    .await;
+    push_block(
+        &ledger,
+        Operation::Mint {
+            to: neuron_c,
+            amount: Tokens::new(10, 0).unwrap(),
+        },
+    )
+    .await;
+    // A already exists, so C is merged into it.
+    let merge_index = push_block(
+        &ledger,
+        transfer(neuron_c, neuron_a, Tokens::new(5, 0).unwrap()),
+    )
+    .await;
+    // Transfers to existing accounts that are not listed are not part of the
+    // lineage.
+    push_block(
+        &ledger,
+        Operation::Mint {
+            to: other,
+            amount: Tokens::new(1, 0).unwrap(),
+        },
+    )
+    .await;
+    push_block(
+        &ledger,
+        transfer(neuron_a, other, Tokens::new(1, 0).unwrap()),
+    )
+    .await;
+
+    let req_handler = RosettaRequestHandler::new_with_default_blockchain(Arc::new(ledger));
+    let mut parameters = Object::new();
+    parameters.insert(
+        "neuron_accounts".to_owned(),
+        serde_json::to_value(
+            [neuron_a, neuron_b, neuron_c]
+                .iter()
+                .map(to_model_account_identifier)
+                .collect::<Vec<_>>(),
+        )
+        .unwrap(),
+    );
+    let response = req_handler
+        .call(CallRequest::new(
+            req_handler.network_id(),
+            "list_neurons".to_owned(),
+            parameters,
+        ))
+        .await
+        .unwrap();
+    let response = ListNeuronsResponse::try_from(Some(response.result)).unwrap();
+
+    let split = NeuronLineageEntry {
+        block_index: split_index,
+        kind: NeuronLineageKind::Split,
+        source: to_model_account_identifier(&neuron_a),
+        target: to_model_account_identifier(&neuron_b),
+        amount_e8s: Tokens::new(30, 0).unwrap().get_e8s(),
+    };
+    let merge = NeuronLineageEntry {
+        block_index: merge_index,
+        kind: NeuronLineageKind::Merge,
+        source: to_model_account_identifier(&neuron_c),
+        target: to_model_account_identifier(&neuron_a),
+        amount_e8s: Tokens::new(5, 0).unwrap().get_e8s(),
+    };
+    let stake_a = Tokens::new(100 - 30 + 5 - 1, 0)
+        .unwrap()
+        .checked_sub(&fee)
+        .and_then(|t| t.checked_sub(&fee))
+        .unwrap();
+    let stake_c = Tokens::new(10 - 5, 0).unwrap().checked_sub(&fee).unwrap();
+    assert_eq!(
+        response.neurons,
+        vec![
+            NeuronLineage {
+                account_identifier: to_model_account_identifier(&neuron_a),
+                stake_e8s: stake_a.get_e8s(),
+                lineage: vec![split.clone(), merge.clone()],
+            },
+            NeuronLineage {
+                account_identifier: to_model_account_identifier(&neuron_b),
+                stake_e8s: Tokens::new(30, 0).unwrap().get_e8s(),
+                lineage: vec![split],
+            },
+            NeuronLineage {
+                account_identifier: to_model_account_identifier(&neuron_c),
+                stake_e8s: stake_c.get_e8s(),
+                lineage: vec![merge],
+            },
+        ]
+    );
+}
+
+#[actix_rt::test]
+async fn list_neurons_follows_splits_to_unlisted_neurons() {
+    let ledger = TestLedger::new();
+    let parent = acc_id(1);
+    let child = acc_id(2);
+    let grandchild = acc_id(3);
+    let fee = DEFAULT_TRANSFER_FEE;
+    let transfer = |from, to, amount| Operation::Transfer {
+        from,
+        to,
+        amount,
+        fee,
+    };
+
+    push_block(
+        &ledger,
+        Operation::Mint {
+            to: parent,
+            amount: Tokens::new(100, 0).unwrap(),
+        },
+    )
+    .await;
+    let split_index = push_block(
+        &ledger,
+        transfer(parent, child, Tokens::new(30, 0).unwrap()),
+    )
+    .await;
+    let nested_split_index = push_block(
+        &ledger,
+        transfer(child, grandchild, Tokens::new(10, 0).unwrap()),
+    )
+    .await;
+
+    // Only the parent is listed, as governance picked the subaccounts of the
+    // neurons split off it.
+    let req_handler = RosettaRequestHandler::new_with_default_blockchain(Arc::new(ledger));
+    let mut parameters = Object::new();
+    parameters.insert(
+        "neuron_accounts".to_owned(),
+        serde_json::to_value(vec![to_model_account_identifier(&parent)]).unwrap(),
+    );
+    let response = req_handler
+        .call(CallRequest::new(
+            req_handler.network_id(),
+            "list_neurons".to_owned(),
+            parameters,
+        ))
+        .await
+        .unwrap();
+    let response = ListNeuronsResponse::try_from(Some(response.result)).unwrap();
+
+    let split = NeuronLineageEntry {
+        block_index: split_index,
+        kind: NeuronLineageKind::Split,
+        source: to_model_account_identifier(&parent),
+        target: to_model_account_identifier(&child),
+        amount_e8s: Tokens::new(30, 0).unwrap().get_e8s(),
+    };
+    let nested_split = NeuronLineageEntry {
+        block_index: nested_split_index,
+        kind: NeuronLineageKind::Split,
+        source: to_model_account_identifier(&child),
+        target: to_model_account_identifier(&grandchild),
+        amount_e8s: Tokens::new(10, 0).unwrap().get_e8s(),
+    };
+    let stake_parent = Tokens::new(100 - 30, 0).unwrap().checked_sub(&fee).unwrap();
+    let stake_child = Tokens::new(30 - 10, 0).unwrap().checked_sub(&fee).unwrap();
+    assert_eq!(
+        response.neurons,
+        vec![
+            NeuronLineage {
+                account_identifier: to_model_account_identifier(&parent),
+                stake_e8s: stake_parent.get_e8s(),
+                lineage: vec![split.clone()],
+            },
+            NeuronLineage {
+                account_identifier: to_model_account_identifier(&child),
+                stake_e8s: stake_child.get_e8s(),
+                lineage: vec![split, nested_split.clone()],
+            },
+            NeuronLineage {
+                account_identifier: to_model_account_identifier(&grandchild),
+                stake_e8s: Tokens::new(10, 0).unwrap().get_e8s(),
+                lineage: vec![nested_split],
+            },
+        ]
+    );
+}
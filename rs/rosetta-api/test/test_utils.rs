@@ -1,4 +1,5 @@
 mod basic_tests;
+mod neuron_management_tests;
 mod rosetta_cli_tests;
 
 use ic_ledger_canister_blocks_synchronizer_test_utils::sample_data::{acc_id, Scribe};
@@ -1,3 +1,4 @@
+use crate::types::LockedAssetSource;
 use crate::{EntryType, TVL_TIMESERIES};
 use std::fmt;
 use std::io::Write;
@@ -159,12 +160,65 @@ fn construct_tvl_table() -> String {
                         )
                         .unwrap();
                     }
+                    EntryType::AssetPrice(id) => {
+                        writeln!(
+                            buf,
+                            "<tr><td>{}</td><td>{} Price</td><td>{}</td></tr>",
+                            ts,
+                            asset_symbol(id),
+                            DisplayAmount(value)
+                        )
+                        .unwrap();
+                    }
+                    EntryType::LockedAsset(id) => {
+                        writeln!(
+                            buf,
+                            "<tr><td>{}</td><td>Locked {}</td><td>{}</td></tr>",
+                            ts,
+                            asset_symbol(id),
+                            DisplayAmount(value)
+                        )
+                        .unwrap();
+                    }
                 }
             }
         });
     })
 }
 
+fn asset_symbol(id: u32) -> String {
+    crate::state::read_state(|s| {
+        s.assets
+            .iter()
+            .find(|asset| asset.id == id)
+            .map(|asset| asset.symbol.clone())
+            .unwrap_or_else(|| format!("Asset #{}", id))
+    })
+}
+
+fn construct_assets_rows() -> String {
+    with_utf8_buffer(|buf| {
+        crate::state::read_state(|s| {
+            for asset in &s.assets {
+                let source = match asset.source {
+                    LockedAssetSource::Icrc1Ledger { ledger_id } => {
+                        format!("ICRC-1 ledger <code>{}</code>", ledger_id)
+                    }
+                    LockedAssetSource::SnsGovernance { governance_id } => {
+                        format!("SNS governance <code>{}</code>", governance_id)
+                    }
+                };
+                writeln!(
+                    buf,
+                    "<tr><th>Tracked Asset {}</th><td>{} ({})</td></tr>",
+                    asset.id, asset.symbol, source
+                )
+                .unwrap();
+            }
+        })
+    })
+}
+
 pub fn construct_metadata() -> String {
     crate::state::read_state(|s| {
         format!(
@@ -190,13 +244,15 @@ pub fn construct_metadata() -> String {
                         <th>Last Locked ICP Update Timestamp</th>
                         <td>{}</td>
                     </tr>
+                    {}
                 </tbody>
             </table>",
             s.governance_principal,
             s.xrc_principal,
             s.update_period,
             s.last_icp_rate_ts,
-            s.last_icp_locked_ts
+            s.last_icp_locked_ts,
+            construct_assets_rows()
         )
     })
 }
@@ -1,14 +1,17 @@
 use crate::memory::push_entry;
-use crate::memory::{EntryType, TVL_TIMESERIES};
+use crate::memory::{EntryType, MAX_ASSET_ID, TVL_TIMESERIES};
 use crate::state::{mutate_state, read_state, replace_state};
 use crate::types::{
     Asset, AssetClass, GetExchangeRateRequest, GetExchangeRateResult, GovernanceCachedMetrics,
-    GovernanceError, TvlArgs, TvlResult, TvlResultError,
+    GovernanceError, ListSnsNeurons, ListSnsNeuronsResponse, LockedAssetSource, TrackedAsset,
+    TvlArgs, TvlResult, TvlResultError,
 };
 use candid::utils::{ArgumentDecoder, ArgumentEncoder};
 use candid::{CandidType, Nat, Principal};
 use ic_base_types::PrincipalId;
+use num_traits::ToPrimitive;
 use state::TvlState;
+use std::collections::BTreeSet;
 use std::str::FromStr;
 use std::time::Duration;
 
@@ -29,6 +32,9 @@ const DEFAULT_XRC_PRINCIPAL: &str = "uf6dk-hyaaa-aaaaq-qaaaq-cai";
 // We query XRC data slightly in the past to be sure to have a price with consensus.
 const XRC_MARGIN_SEC: u64 = 5 * 60;
 
+// The maximum number of neurons returned by one list_neurons call to SNS governance.
+const SNS_NEURONS_PAGE_SIZE: u32 = 100;
+
 #[derive(CandidType, Debug, candid::Deserialize, PartialEq, Eq, Ord, PartialOrd, Clone)]
 pub enum FiatCurrency {
     USD,
@@ -77,6 +83,22 @@ pub async fn post_upgrade(args: TvlArgs) {
 }
 
 fn init_state(args: TvlArgs) {
+    let assets = args.assets.unwrap_or_default();
+    let mut asset_ids = BTreeSet::new();
+    for asset in &assets {
+        assert!(
+            asset.id <= MAX_ASSET_ID,
+            "Asset id {} of {} is larger than {}",
+            asset.id,
+            asset.symbol,
+            MAX_ASSET_ID
+        );
+        assert!(
+            asset_ids.insert(asset.id),
+            "Duplicate asset id {}",
+            asset.id
+        );
+    }
     replace_state(TvlState {
         governance_principal: args
             .governance_id
@@ -91,6 +113,9 @@ fn init_state(args: TvlArgs) {
         last_icp_locked_ts: 0,
         exchange_rate: Default::default(),
         currencies_to_fetch: Default::default(),
+        assets,
+        last_asset_price: Default::default(),
+        last_asset_locked: Default::default(),
     });
 }
 
@@ -107,6 +132,11 @@ pub fn init_timers() {
             update_locked_amount().await;
         });
     });
+    ic_cdk_timers::set_timer_interval(update_period, || {
+        ic_cdk::spawn(async {
+            update_assets().await;
+        });
+    });
     ic_cdk_timers::set_timer_interval(ONE_DAY, || {
         ic_cdk::spawn(async {
             let is_currencies_to_fetch_empty = read_state(|s| s.currencies_to_fetch.is_empty());
@@ -133,8 +163,7 @@ pub fn multiply_e8s(amount: u64, rate: u64) -> u64 {
 
 /// Retrieve last data from timeseries. Perform a TVL update if none is present.
 pub async fn get_tvl(req: Option<TvlRequest>) -> Result<TvlResult, TvlResultError> {
-    let (last_icp_rate, last_icp_locked) = read_state(|s| (s.last_icp_rate, s.last_icp_locked));
-    let tvl = multiply_e8s(last_icp_rate, last_icp_locked);
+    let tvl = read_state(|s| s.total_tvl());
     if let Some(req) = req {
         let currency = req.currency;
         match read_state(|s| s.exchange_rate.get(&currency).cloned()) {
@@ -170,6 +199,16 @@ fn convert_to_8_decimals(amount: u64, decimals: u32) -> u64 {
     }
 }
 
+/// Converts an amount with the given number of decimals to 8 decimals.
+fn convert_nat_to_8_decimals(amount: Nat, decimals: u8) -> Option<u64> {
+    let amount = if decimals >= 8 {
+        amount / Nat::from(10u128.checked_pow(decimals as u32 - 8)?)
+    } else {
+        amount * Nat::from(10u128.pow(8 - decimals as u32))
+    };
+    amount.0.to_u64()
+}
+
 pub async fn update_icp_price() -> Option<u64> {
     let icp = Asset {
         symbol: "ICP".to_string(),
@@ -260,6 +299,112 @@ pub async fn update_locked_amount() -> Option<u64> {
     None
 }
 
+pub async fn update_assets() {
+    let assets = read_state(|s| s.assets.clone());
+    for asset in assets {
+        update_asset_price(&asset).await;
+        update_asset_locked_amount(&asset).await;
+    }
+}
+
+async fn update_asset_price(asset: &TrackedAsset) -> Option<u64> {
+    let base_asset = Asset {
+        symbol: asset.symbol.clone(),
+        class: AssetClass::Cryptocurrency,
+    };
+    let usd = Asset {
+        symbol: "USD".to_string(),
+        class: AssetClass::FiatCurrency,
+    };
+    let xrc_result = get_exchange_rate(base_asset.clone(), usd.clone()).await;
+    if let Ok(GetExchangeRateResult::Ok(xr)) = xrc_result {
+        if xr.base_asset != base_asset || xr.quote_asset != usd {
+            return None;
+        }
+        let price = convert_to_8_decimals(xr.rate, xr.metadata.decimals);
+        push_entry(xr.timestamp, EntryType::AssetPrice(asset.id), price);
+        return Some(price);
+    }
+    None
+}
+
+async fn update_asset_locked_amount(asset: &TrackedAsset) -> Option<u64> {
+    let locked_amount = match asset.source {
+        LockedAssetSource::Icrc1Ledger { ledger_id } => get_icrc1_total_supply(ledger_id).await,
+        LockedAssetSource::SnsGovernance { governance_id } => {
+            get_sns_total_staked(governance_id).await
+        }
+    };
+    match locked_amount {
+        Ok(locked_amount) => {
+            push_entry(
+                ic_cdk::api::time() / SEC_NANOS,
+                EntryType::LockedAsset(asset.id),
+                locked_amount,
+            );
+            Some(locked_amount)
+        }
+        Err(err) => {
+            ic_cdk::println!(
+                "Failed to fetch the locked amount of {}: {}",
+                asset.symbol,
+                err
+            );
+            None
+        }
+    }
+}
+
+/// Retrieve the total supply of an ICRC-1 ledger (e8s).
+async fn get_icrc1_total_supply(ledger_id: PrincipalId) -> Result<u64, String> {
+    ic_cdk::println!("Calling ICRC-1 ledger ({})", ledger_id);
+    let (decimals,): (u8,) = call(ledger_id.0, "icrc1_decimals", ())
+        .await
+        .map_err(|(code, msg)| format!("Error while calling ledger ({}): {}", code, msg))?;
+    let (total_supply,): (Nat,) = call(ledger_id.0, "icrc1_total_supply", ())
+        .await
+        .map_err(|(code, msg)| format!("Error while calling ledger ({}): {}", code, msg))?;
+    convert_nat_to_8_decimals(total_supply.clone(), decimals).ok_or_else(|| {
+        format!(
+            "Total supply {} with {} decimals does not fit in u64",
+            total_supply, decimals
+        )
+    })
+}
+
+/// Retrieve the total stake of the neurons of an SNS governance canister (e8s).
+async fn get_sns_total_staked(governance_id: PrincipalId) -> Result<u64, String> {
+    ic_cdk::println!("Calling SNS Governance canister ({})", governance_id);
+    let mut total_staked: u64 = 0;
+    let mut start_page_at = None;
+    loop {
+        let args = ListSnsNeurons {
+            limit: SNS_NEURONS_PAGE_SIZE,
+            start_page_at,
+            of_principal: None,
+        };
+        let (response,): (ListSnsNeuronsResponse,) = call(governance_id.0, "list_neurons", (args,))
+            .await
+            .map_err(|(code, msg)| {
+                format!("Error while calling SNS Governance ({}): {}", code, msg)
+            })?;
+        for neuron in &response.neurons {
+            total_staked = total_staked.saturating_add(
+                neuron
+                    .cached_neuron_stake_e8s
+                    .saturating_sub(neuron.neuron_fees_e8s),
+            );
+        }
+        if response.neurons.len() < SNS_NEURONS_PAGE_SIZE as usize {
+            return Ok(total_staked);
+        }
+        start_page_at = match response.neurons.last().and_then(|n| n.id.clone()) {
+            Some(id) => Some(id),
+            None => return Ok(total_staked),
+        };
+    }
+}
+
 /// Query the XRC canister to retrieve the last ICP/USD price.
 async fn get_exchange_rate(
     base_asset: Asset,
@@ -9,15 +9,24 @@ use std::cell::RefCell;
 
 const TIMESERIES: MemoryId = MemoryId::new(0);
 
+// The entries of tracked assets are encoded as ASSET_ENTRY_OFFSET + 2 * id for
+// the price and ASSET_ENTRY_OFFSET + 2 * id + 1 for the locked amount.
+const ASSET_ENTRY_OFFSET: u32 = 1_000;
+pub const MAX_ASSET_ID: u32 = (u32::MAX - ASSET_ENTRY_OFFSET - 1) / 2;
+
 // All the exchange rates are expressed with USD as base quote.
-#[derive(CandidType, Clone, Debug, Deserialize, Ord, PartialOrd, Eq, PartialEq)]
+#[derive(CandidType, Clone, Copy, Debug, Deserialize, Ord, PartialOrd, Eq, PartialEq)]
 pub enum EntryType {
-    ICPrice = 0,
-    LockedIcp = 1,
-    EURExchangeRate = 2,
-    CNYExchangeRate = 3,
-    JPYExchangeRate = 4,
-    GBPExchangeRate = 5,
+    ICPrice,
+    LockedIcp,
+    EURExchangeRate,
+    CNYExchangeRate,
+    JPYExchangeRate,
+    GBPExchangeRate,
+    // The USD price (e8s) of the tracked asset with the given id.
+    AssetPrice(u32),
+    // The locked amount (e8s) of the tracked asset with the given id.
+    LockedAsset(u32),
 }
 
 impl From<FiatCurrency> for EntryType {
@@ -41,11 +50,34 @@ impl From<u32> for EntryType {
             3 => EntryType::CNYExchangeRate,
             4 => EntryType::JPYExchangeRate,
             5 => EntryType::GBPExchangeRate,
+            n if n >= ASSET_ENTRY_OFFSET => {
+                let id = (n - ASSET_ENTRY_OFFSET) / 2;
+                if (n - ASSET_ENTRY_OFFSET) % 2 == 0 {
+                    EntryType::AssetPrice(id)
+                } else {
+                    EntryType::LockedAsset(id)
+                }
+            }
             _ => panic!("Invalid EntryType value: {}", num),
         }
     }
 }
 
+impl From<EntryType> for u32 {
+    fn from(entry: EntryType) -> Self {
+        match entry {
+            EntryType::ICPrice => 0,
+            EntryType::LockedIcp => 1,
+            EntryType::EURExchangeRate => 2,
+            EntryType::CNYExchangeRate => 3,
+            EntryType::JPYExchangeRate => 4,
+            EntryType::GBPExchangeRate => 5,
+            EntryType::AssetPrice(id) => ASSET_ENTRY_OFFSET + 2 * id,
+            EntryType::LockedAsset(id) => ASSET_ENTRY_OFFSET + 2 * id + 1,
+        }
+    }
+}
+
 type Memory = VirtualMemory<DefaultMemoryImpl>;
 
 thread_local! {
@@ -65,8 +97,7 @@ pub fn push_entry(ts: u64, entry: EntryType, value: u64) {
     match entry {
         EntryType::ICPrice => {
             TVL_TIMESERIES.with(|m| {
-                m.borrow_mut()
-                    .insert((ts, EntryType::ICPrice as u32), value);
+                m.borrow_mut().insert((ts, entry.into()), value);
             });
             mutate_state(|s| {
                 s.last_icp_rate_ts = ts;
@@ -75,17 +106,32 @@ pub fn push_entry(ts: u64, entry: EntryType, value: u64) {
         }
         EntryType::LockedIcp => {
             TVL_TIMESERIES.with(|m| {
-                m.borrow_mut()
-                    .insert((ts, EntryType::LockedIcp as u32), value);
+                m.borrow_mut().insert((ts, entry.into()), value);
             });
             mutate_state(|s| {
                 s.last_icp_locked_ts = ts;
                 s.last_icp_locked = value;
             });
         }
+        EntryType::AssetPrice(id) => {
+            TVL_TIMESERIES.with(|m| {
+                m.borrow_mut().insert((ts, entry.into()), value);
+            });
+            mutate_state(|s| {
+                s.last_asset_price.insert(id, value);
+            });
+        }
+        EntryType::LockedAsset(id) => {
+            TVL_TIMESERIES.with(|m| {
+                m.borrow_mut().insert((ts, entry.into()), value);
+            });
+            mutate_state(|s| {
+                s.last_asset_locked.insert(id, value);
+            });
+        }
         _ => {
             TVL_TIMESERIES.with(|m| {
-                m.borrow_mut().insert((ts, entry as u32), value);
+                m.borrow_mut().insert((ts, entry.into()), value);
             });
         }
     }
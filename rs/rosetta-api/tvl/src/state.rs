@@ -1,6 +1,7 @@
 use crate::memory::EntryType;
-use crate::FiatCurrency;
+use crate::types::TrackedAsset;
 use crate::TVL_TIMESERIES;
+use crate::{multiply_e8s, FiatCurrency};
 use ic_base_types::PrincipalId;
 use std::cell::RefCell;
 use std::collections::{BTreeMap, BTreeSet};
@@ -25,6 +26,13 @@ pub struct TvlState {
     pub exchange_rate: BTreeMap<FiatCurrency, u64>,
 
     pub currencies_to_fetch: BTreeSet<FiatCurrency>,
+
+    // The assets tracked in addition to the ICP locked in NNS neurons.
+    pub assets: Vec<TrackedAsset>,
+    // The last USD price (e8s) of each tracked asset, by asset id.
+    pub last_asset_price: BTreeMap<u32, u64>,
+    // The last locked amount (e8s) of each tracked asset, by asset id.
+    pub last_asset_locked: BTreeMap<u32, u64>,
 }
 
 impl TvlState {
@@ -64,10 +72,37 @@ impl TvlState {
                         self.last_icp_locked = value;
                         self.last_icp_locked_ts = ts;
                     }
+                    EntryType::AssetPrice(id) => {
+                        self.last_asset_price.insert(id, value);
+                    }
+                    EntryType::LockedAsset(id) => {
+                        self.last_asset_locked.insert(id, value);
+                    }
                 }
             }
         })
     }
+
+    /// Returns the value (e8s) locked in ICP expressed in USD.
+    pub fn icp_tvl(&self) -> u64 {
+        multiply_e8s(self.last_icp_rate, self.last_icp_locked)
+    }
+
+    /// Returns the value (e8s) locked in the tracked asset with the given id
+    /// expressed in USD, or None if its price or locked amount is unknown.
+    pub fn asset_tvl(&self, id: u32) -> Option<u64> {
+        let price = self.last_asset_price.get(&id)?;
+        let locked = self.last_asset_locked.get(&id)?;
+        Some(multiply_e8s(*price, *locked))
+    }
+
+    /// Returns the total value (e8s) locked expressed in USD.
+    pub fn total_tvl(&self) -> u64 {
+        self.assets
+            .iter()
+            .filter_map(|asset| self.asset_tvl(asset.id))
+            .fold(self.icp_tvl(), |acc, value| acc.saturating_add(value))
+    }
 }
 
 thread_local! {
@@ -6,6 +6,25 @@ pub struct TvlArgs {
     pub governance_id: Option<PrincipalId>,
     pub xrc_id: Option<PrincipalId>,
     pub update_period: Option<u64>,
+    // The assets tracked in addition to the ICP locked in NNS neurons.
+    pub assets: Option<Vec<TrackedAsset>>,
+}
+
+#[derive(CandidType, Clone, Debug, candid::Deserialize, PartialEq, Eq)]
+pub struct TrackedAsset {
+    // Identifies the timeseries of the asset, must not change across upgrades.
+    pub id: u32,
+    // The symbol of the asset on the exchange rate canister, e.g., "BTC".
+    pub symbol: String,
+    pub source: LockedAssetSource,
+}
+
+#[derive(CandidType, Clone, Debug, candid::Deserialize, PartialEq, Eq)]
+pub enum LockedAssetSource {
+    // The total supply of an ICRC-1 ledger, e.g., ckBTC or ckETH.
+    Icrc1Ledger { ledger_id: PrincipalId },
+    // The stake of the neurons of an SNS governance canister.
+    SnsGovernance { governance_id: PrincipalId },
 }
 
 // Timeseries types.
@@ -147,3 +166,29 @@ pub struct GovernanceError {
     pub error_type: i32,
     pub error_message: String,
 }
+
+// SNS governance types.
+#[derive(candid::CandidType, candid::Deserialize, Debug, Clone, PartialEq, Eq)]
+pub struct SnsNeuronId {
+    #[serde(with = "serde_bytes")]
+    pub id: Vec<u8>,
+}
+
+#[derive(candid::CandidType, candid::Deserialize, Debug, Clone, PartialEq, Eq)]
+pub struct SnsNeuron {
+    pub id: Option<SnsNeuronId>,
+    pub cached_neuron_stake_e8s: u64,
+    pub neuron_fees_e8s: u64,
+}
+
+#[derive(candid::CandidType, candid::Deserialize, Debug, Clone, PartialEq, Eq)]
+pub struct ListSnsNeurons {
+    pub limit: u32,
+    pub start_page_at: Option<SnsNeuronId>,
+    pub of_principal: Option<PrincipalId>,
+}
+
+#[derive(candid::CandidType, candid::Deserialize, Debug, Clone, PartialEq, Eq)]
+pub struct ListSnsNeuronsResponse {
+    pub neurons: Vec<SnsNeuron>,
+}
@@ -1,12 +1,18 @@
 use assert_matches::assert_matches;
 use candid::{Decode, Encode, Nat};
+use ic_base_types::PrincipalId;
+use ic_icrc1_ledger::{InitArgs as LedgerInitArgs, LedgerArgument};
+use ic_ledger_canister_core::archive::ArchiveOptions;
 use ic_nns_test_utils::common::NnsInitPayloadsBuilder;
 use ic_nns_test_utils::state_test_helpers::setup_nns_canisters;
 use ic_state_machine_tests::{CanisterId, StateMachine};
-use ic_tvl_canister::types::{TvlArgs as TVLInitArgs, TvlResult, TvlResultError};
+use ic_tvl_canister::types::{
+    LockedAssetSource, TrackedAsset, TvlArgs as TVLInitArgs, TvlResult, TvlResultError,
+};
 use ic_tvl_canister::{
     multiply_e8s, FiatCurrency, TvlRequest, DEFAULT_UPDATE_PERIOD, ONE_DAY, OTHER_CURRENCIES,
 };
+use icrc_ledger_types::icrc1::account::Account;
 use rand::{thread_rng, Rng};
 use xrc_mock::{ExchangeRate, Response, SetExchangeRate, XrcMockInitPayload};
 
@@ -17,6 +23,10 @@ fn tvl_wasm() -> Vec<u8> {
     std::fs::read(std::env::var("TVL_WASM").unwrap()).unwrap()
 }
 
+fn ledger_wasm() -> Vec<u8> {
+    std::fs::read(std::env::var("IC_ICRC1_LEDGER_WASM_PATH").unwrap()).unwrap()
+}
+
 fn xrc_wasm() -> Vec<u8> {
     ic_test_utilities_load_wasm::load_wasm(std::env::var("XRC_WASM_PATH").unwrap(), "xrc", &[])
 }
@@ -55,6 +65,7 @@ impl TvlSetup {
             update_period: Some(DEFAULT_UPDATE_PERIOD),
             governance_id: Some(GOVERNANCE_CANISTER_ID.get()),
             xrc_id: Some(xrc_id.get()),
+            assets: None,
         };
         let args = Encode!(&args).unwrap();
         let tvl_id = env.install_canister(tvl_wasm(), args, None).unwrap();
@@ -76,6 +87,41 @@ impl TvlSetup {
         .expect("failed to decode get_tvl response")
     }
 
+    pub fn install_icrc1_ledger(&self, symbol: &str, total_supply: u64) -> CanisterId {
+        let args = LedgerArgument::Init(LedgerInitArgs {
+            minting_account: Account {
+                owner: PrincipalId::new_user_test_id(1).0,
+                subaccount: None,
+            },
+            fee_collector_account: None,
+            initial_balances: vec![(
+                Account {
+                    owner: PrincipalId::new_user_test_id(2).0,
+                    subaccount: None,
+                },
+                total_supply,
+            )],
+            transfer_fee: 10,
+            token_name: format!("ck{}", symbol),
+            token_symbol: format!("ck{}", symbol),
+            metadata: vec![],
+            archive_options: ArchiveOptions {
+                trigger_threshold: 1_000,
+                num_blocks_to_archive: 1_000,
+                node_max_memory_size_bytes: None,
+                max_message_size_bytes: None,
+                controller_id: PrincipalId::new_user_test_id(100),
+                cycles_for_archive_creation: None,
+                max_transactions_per_response: None,
+            },
+            max_memo_length: None,
+            feature_flags: None,
+        });
+        self.env
+            .install_canister(ledger_wasm(), Encode!(&args).unwrap(), None)
+            .unwrap()
+    }
+
     pub fn set_exchange_rate(&self, arg: SetExchangeRate) {
         let _ = &self
             .env
@@ -103,6 +149,7 @@ fn test_tvl() {
         update_period: Some(30),
         governance_id: Some(tvl.governance_id.get()),
         xrc_id: Some(tvl.xrc_id.get()),
+        assets: None,
     };
     tvl.env
         .upgrade_canister(tvl.tvl_id, tvl_wasm(), Encode!(&upgrade_args).unwrap())
@@ -165,6 +212,7 @@ fn test_multiple_currencies() {
         update_period: Some(30),
         governance_id: Some(tvl.governance_id.get()),
         xrc_id: Some(tvl.xrc_id.get()),
+        assets: None,
     };
     tvl.env
         .upgrade_canister(tvl.tvl_id, tvl_wasm(), Encode!(&upgrade_args).unwrap())
@@ -264,3 +312,73 @@ fn test_fiat_updates() {
     let get_tvl_result = tvl.get_tvl(Some(arg)).unwrap();
     assert_eq!(get_tvl_result.tvl, Nat::from(expected_tvl));
 }
+
+#[test]
+fn test_tracked_assets() {
+    let tvl = TvlSetup::new();
+    tvl.env.run_until_completion(10_000);
+
+    // 2 ckBTC are minted and the BTC price is 30,000$.
+    let ckbtc_supply = 2 * E8S;
+    let btc_rate = 30_000 * E8S;
+    let ckbtc_ledger_id = tvl.install_icrc1_ledger("BTC", ckbtc_supply);
+    tvl.set_exchange_rate(SetExchangeRate {
+        base_asset: "BTC".to_string(),
+        quote_asset: "USD".to_string(),
+        rate: btc_rate,
+    });
+
+    let upgrade_args = TVLInitArgs {
+        update_period: Some(DEFAULT_UPDATE_PERIOD),
+        governance_id: Some(tvl.governance_id.get()),
+        xrc_id: Some(tvl.xrc_id.get()),
+        assets: Some(vec![TrackedAsset {
+            id: 0,
+            symbol: "BTC".to_string(),
+            source: LockedAssetSource::Icrc1Ledger {
+                ledger_id: ckbtc_ledger_id.get(),
+            },
+        }]),
+    };
+    tvl.env
+        .upgrade_canister(tvl.tvl_id, tvl_wasm(), Encode!(&upgrade_args).unwrap())
+        .expect("failed to upgrade the tvl canister");
+    tvl.env
+        .advance_time(std::time::Duration::from_secs(DEFAULT_UPDATE_PERIOD));
+    tvl.env.tick();
+    tvl.env.run_until_completion(10_000);
+
+    // 111$ locked in NNS neurons and 60,000$ in ckBTC.
+    let icp_tvl_e8s = multiply_e8s(1_110_000_000, DEFAULT_ICP_RATE);
+    let expected_tvl_e8s = icp_tvl_e8s + multiply_e8s(ckbtc_supply, btc_rate);
+    let get_tvl_result: TvlResult = tvl.get_tvl(None).unwrap();
+    assert_eq!(get_tvl_result.tvl, Nat::from(expected_tvl_e8s / E8S));
+
+    // The value of the tracked assets is converted into other currencies.
+    let eur_rate = 90_000_000;
+    tvl.set_exchange_rate(SetExchangeRate {
+        base_asset: "USD".to_string(),
+        quote_asset: FiatCurrency::EUR.to_string(),
+        rate: eur_rate,
+    });
+    tvl.env.advance_time(ONE_DAY);
+    tvl.env.tick();
+    tvl.env.run_until_completion(10_000);
+
+    let get_tvl_result = tvl
+        .get_tvl(Some(TvlRequest {
+            currency: FiatCurrency::EUR,
+        }))
+        .unwrap();
+    assert_eq!(
+        get_tvl_result.tvl,
+        Nat::from(multiply_e8s(expected_tvl_e8s, eur_rate) / E8S)
+    );
+
+    // The last values are restored from the timeseries after an upgrade.
+    tvl.env
+        .upgrade_canister(tvl.tvl_id, tvl_wasm(), Encode!(&upgrade_args).unwrap())
+        .expect("failed to upgrade the tvl canister");
+    let get_tvl_result: TvlResult = tvl.get_tvl(None).unwrap();
+    assert_eq!(get_tvl_result.tvl, Nat::from(expected_tvl_e8s / E8S));
+}
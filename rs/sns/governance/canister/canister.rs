@@ -42,7 +42,8 @@ use ic_sns_governance::{
         GetProposalResponse, GetRunningSnsVersionRequest, GetRunningSnsVersionResponse,
         GetSnsInitializationParametersRequest, GetSnsInitializationParametersResponse,
         Governance as GovernanceProto, ListNervousSystemFunctionsResponse, ListNeurons,
-        ListNeuronsResponse, ListProposals, ListProposalsResponse, ManageNeuron,
+        ListNeuronsResponse, ListProposals, ListProposalsResponse, ListTopicsRequest,
+        ListTopicsResponse, ListTreasuryStreamsRequest, ListTreasuryStreamsResponse, ManageNeuron,
         ManageNeuronResponse, NervousSystemParameters, RewardEvent, SetMode, SetModeResponse,
     },
     types::{Environment, HeapGrowthPotential},
@@ -468,6 +469,32 @@ fn list_nervous_system_functions_() -> ListNervousSystemFunctionsResponse {
     governance().list_nervous_system_functions()
 }
 
+/// Returns the topics of proposals and the functions that belong to them.
+#[export_name = "canister_query list_topics"]
+fn list_topics() {
+    log!(INFO, "list_topics");
+    over(candid_one, list_topics_)
+}
+
+/// Internal method for calling list_topics.
+#[candid_method(query, rename = "list_topics")]
+fn list_topics_(_request: ListTopicsRequest) -> ListTopicsResponse {
+    governance().list_topics()
+}
+
+/// Returns all treasury streams, including their payouts.
+#[export_name = "canister_query list_treasury_streams"]
+fn list_treasury_streams() {
+    log!(INFO, "list_treasury_streams");
+    over(candid_one, list_treasury_streams_)
+}
+
+/// Internal method for calling list_treasury_streams.
+#[candid_method(query, rename = "list_treasury_streams")]
+fn list_treasury_streams_(_request: ListTreasuryStreamsRequest) -> ListTreasuryStreamsResponse {
+    governance().list_treasury_streams()
+}
+
 /// Returns the latest reward event.
 #[export_name = "canister_query get_latest_reward_event"]
 fn get_latest_reward_event() {
@@ -148,6 +148,15 @@ pub struct Neuron {
     /// entry\[i\].timestamp_of_disbursement_seconds <= entry\[i+1\].timestamp_of_disbursement_seconds
     #[prost(message, repeated, tag = "18")]
     pub disburse_maturity_in_progress: ::prost::alloc::vec::Vec<DisburseMaturityInProgress>,
+    /// The neuron's followees per proposal topic, specified as a map of topics to
+    /// followees neuron IDs. The map's keys are the integer values of `Topic`, as
+    /// Protobuf does not support enum keys in maps.
+    ///
+    /// Topic following is consulted for proposals whose function has no entry in
+    /// `followees`, before falling back to the catch-all (unspecified function)
+    /// followees.
+    #[prost(btree_map = "int32, message", tag = "19")]
+    pub topic_followees: ::prost::alloc::collections::BTreeMap<i32, neuron::Followees>,
     /// The neuron's dissolve state, specifying whether the neuron is dissolving,
     /// non-dissolving, or dissolved.
     ///
@@ -290,6 +299,11 @@ pub mod nervous_system_function {
         /// <method_name>(proposal_data: ProposalData) -> Result<String, String>
         #[prost(string, optional, tag = "5")]
         pub validator_method_name: ::core::option::Option<::prost::alloc::string::String>,
+        /// The topic of proposals executing this function, used for following.
+        /// Must not be TOPIC_UNSPECIFIED if set. If not set, only function and
+        /// catch-all following apply to such proposals.
+        #[prost(enumeration = "super::Topic", optional, tag = "6")]
+        pub topic: ::core::option::Option<i32>,
     }
     #[derive(
         candid::CandidType,
@@ -508,6 +522,125 @@ pub struct DeregisterDappCanisters {
     #[prost(message, repeated, tag = "2")]
     pub new_controllers: ::prost::alloc::vec::Vec<::ic_base_types::PrincipalId>,
 }
+/// A proposal to create a stream of payments from an SNS treasury to (optionally
+/// a Subaccount of) the target principal.
+///
+/// The total amount vests linearly over the vesting period, which begins at the
+/// start timestamp. Governance periodically pays out the amount that has vested
+/// but has not been paid out yet. Nothing is paid out before the cliff has
+/// passed; the amount that vested during the cliff is paid out with the first
+/// payout after it.
+#[derive(
+    candid::CandidType,
+    candid::Deserialize,
+    comparable::Comparable,
+    Clone,
+    PartialEq,
+    ::prost::Message,
+)]
+pub struct CreateTreasuryStream {
+    /// Whether the payments are made from the NNS ledger (in ICP) or from the SNS
+    /// ledger (in SNS tokens).
+    #[prost(enumeration = "transfer_sns_treasury_funds::TransferFrom", tag = "1")]
+    pub from_treasury: i32,
+    /// The principal to pay the funds to.
+    #[prost(message, optional, tag = "2")]
+    pub to_principal: ::core::option::Option<::ic_base_types::PrincipalId>,
+    /// An (optional) Subaccount of the principal to pay the funds to.
+    #[prost(message, optional, tag = "3")]
+    pub to_subaccount: ::core::option::Option<Subaccount>,
+    /// The total amount paid out by the stream, in e8s. Transfer fees are paid
+    /// by the treasury on top of this amount.
+    #[prost(uint64, tag = "4")]
+    pub total_amount_e8s: u64,
+    /// When the amount starts vesting, in seconds since the Unix epoch.
+    #[prost(uint64, tag = "5")]
+    pub start_timestamp_seconds: u64,
+    /// For how long after the start nothing is paid out. Must not exceed the
+    /// vesting period.
+    #[prost(uint64, tag = "6")]
+    pub cliff_seconds: u64,
+    /// How long it takes for the total amount to vest. Must be positive.
+    #[prost(uint64, tag = "7")]
+    pub vesting_period_seconds: u64,
+    /// An optional memo to use for the payouts.
+    #[prost(uint64, optional, tag = "8")]
+    pub memo: ::core::option::Option<u64>,
+}
+/// A proposal to cancel a treasury stream. No payouts are made after the
+/// stream has been cancelled, including of amounts that vested before.
+#[derive(
+    candid::CandidType,
+    candid::Deserialize,
+    comparable::Comparable,
+    Clone,
+    PartialEq,
+    ::prost::Message,
+)]
+pub struct CancelTreasuryStream {
+    /// The id of the stream, which is the id of the proposal that created it.
+    #[prost(uint64, tag = "1")]
+    pub stream_id: u64,
+}
+/// A stream of payments from an SNS treasury, created by an adopted
+/// CreateTreasuryStream proposal.
+#[derive(
+    candid::CandidType,
+    candid::Deserialize,
+    comparable::Comparable,
+    Clone,
+    PartialEq,
+    ::prost::Message,
+)]
+pub struct TreasuryStream {
+    /// The id of the stream, which is the id of the proposal that created it.
+    #[prost(uint64, tag = "1")]
+    pub id: u64,
+    /// The stream as specified in the proposal that created it.
+    #[prost(message, optional, tag = "2")]
+    pub parameters: ::core::option::Option<CreateTreasuryStream>,
+    /// The total amount paid out so far, in e8s (excluding transfer fees).
+    #[prost(uint64, tag = "3")]
+    pub paid_amount_e8s: u64,
+    /// The payouts made so far, oldest first.
+    #[prost(message, repeated, tag = "4")]
+    pub payouts: ::prost::alloc::vec::Vec<treasury_stream::Payout>,
+    /// When the stream was cancelled, in seconds since the Unix epoch, if it
+    /// was cancelled.
+    #[prost(uint64, optional, tag = "5")]
+    pub cancelled_timestamp_seconds: ::core::option::Option<u64>,
+    /// The id of the proposal that cancelled the stream, if it was cancelled.
+    #[prost(uint64, optional, tag = "6")]
+    pub cancelled_by_proposal_id: ::core::option::Option<u64>,
+    /// When the last attempt to make a payout failed, in seconds since the Unix
+    /// epoch, unless a payout has been made since. Failed payouts are retried
+    /// once per payout interval.
+    #[prost(uint64, optional, tag = "7")]
+    pub last_failed_payout_timestamp_seconds: ::core::option::Option<u64>,
+}
+/// Nested message and enum types in `TreasuryStream`.
+pub mod treasury_stream {
+    /// A payout made by the stream.
+    #[derive(
+        candid::CandidType,
+        candid::Deserialize,
+        comparable::Comparable,
+        Clone,
+        PartialEq,
+        ::prost::Message,
+    )]
+    pub struct Payout {
+        /// The amount paid out, in e8s (excluding the transfer fee).
+        #[prost(uint64, tag = "1")]
+        pub amount_e8s: u64,
+        /// When the payout was made, in seconds since the Unix epoch.
+        #[prost(uint64, tag = "2")]
+        pub timestamp_seconds: u64,
+        /// The index of the ledger block that records the transfer.
+        #[prost(uint64, tag = "3")]
+        pub block_index: u64,
+    }
+}
 /// A proposal is the immutable input of a proposal submission.
 #[derive(candid::CandidType, candid::Deserialize, comparable::Comparable)]
 #[compare_default]
@@ -536,7 +669,7 @@ pub struct Proposal {
     /// of this mapping.
     #[prost(
         oneof = "proposal::Action",
-        tags = "4, 5, 6, 7, 8, 9, 10, 11, 12, 13, 14, 15"
+        tags = "4, 5, 6, 7, 8, 9, 10, 11, 12, 13, 14, 15, 16, 17"
     )]
     pub action: ::core::option::Option<proposal::Action>,
 }
@@ -626,6 +759,16 @@ pub mod proposal {
         /// Id = 11.
         #[prost(message, tag = "15")]
         DeregisterDappCanisters(super::DeregisterDappCanisters),
+        /// Create a stream of payments from an SNS treasury to an account.
+        ///
+        /// Id = 12.
+        #[prost(message, tag = "16")]
+        CreateTreasuryStream(super::CreateTreasuryStream),
+        /// Cancel a stream of payments created by a CreateTreasuryStream proposal.
+        ///
+        /// Id = 13.
+        #[prost(message, tag = "17")]
+        CancelTreasuryStream(super::CancelTreasuryStream),
     }
 }
 #[derive(candid::CandidType, candid::Deserialize, comparable::Comparable)]
@@ -923,6 +1066,11 @@ pub struct ProposalData {
     /// accepted, it is considered "ready to settle".
     #[prost(uint64, optional, tag = "19")]
     pub reward_event_end_timestamp_seconds: ::core::option::Option<u64>,
+    /// The topic of the proposal, determined by its action when the proposal
+    /// was made. Not set for proposals made before topics existed, or whose
+    /// function has no topic.
+    #[prost(enumeration = "Topic", optional, tag = "20")]
+    pub topic: ::core::option::Option<i32>,
 }
 /// The nervous system's parameters, which are parameters that can be changed, via proposals,
 /// by each nervous system community.
@@ -1323,6 +1471,15 @@ pub struct Governance {
     pub is_finalizing_disburse_maturity: ::core::option::Option<bool>,
     #[prost(message, optional, tag = "26")]
     pub maturity_modulation: ::core::option::Option<governance::MaturityModulation>,
+    /// The treasury streams created by proposals, as a map from the streams'
+    /// ids to the streams. Streams are kept after they have been completed or
+    /// cancelled.
+    #[prost(btree_map = "uint64, message", tag = "27")]
+    pub treasury_streams: ::prost::alloc::collections::BTreeMap<u64, TreasuryStream>,
+    /// True if the heartbeat function is currently paying out treasury streams,
+    /// meaning that it should finish before being called again.
+    #[prost(bool, optional, tag = "28")]
+    pub is_paying_treasury_streams: ::core::option::Option<bool>,
 }
 /// Nested message and enum types in `Governance`.
 pub mod governance {
@@ -1614,6 +1771,30 @@ pub mod governance {
         }
     }
 }
+/// Request message for 'list_treasury_streams'.
+#[derive(
+    candid::CandidType,
+    candid::Deserialize,
+    comparable::Comparable,
+    Clone,
+    PartialEq,
+    ::prost::Message,
+)]
+pub struct ListTreasuryStreamsRequest {}
+/// Response message for 'list_treasury_streams'.
+#[derive(
+    candid::CandidType,
+    candid::Deserialize,
+    comparable::Comparable,
+    Clone,
+    PartialEq,
+    ::prost::Message,
+)]
+pub struct ListTreasuryStreamsResponse {
+    /// All treasury streams, ordered by id.
+    #[prost(message, repeated, tag = "1")]
+    pub treasury_streams: ::prost::alloc::vec::Vec<TreasuryStream>,
+}
 /// Request message for 'get_metadata'.
 #[derive(
     candid::CandidType,
@@ -1743,7 +1924,7 @@ pub struct ManageNeuron {
     pub subaccount: ::prost::alloc::vec::Vec<u8>,
     #[prost(
         oneof = "manage_neuron::Command",
-        tags = "2, 3, 4, 5, 6, 7, 8, 9, 10, 11, 12, 13"
+        tags = "2, 3, 4, 5, 6, 7, 8, 9, 10, 11, 12, 13, 14"
     )]
     pub command: ::core::option::Option<manage_neuron::Command>,
 }
@@ -2029,6 +2210,48 @@ pub mod manage_neuron {
         #[prost(message, repeated, tag = "2")]
         pub followees: ::prost::alloc::vec::Vec<super::NeuronId>,
     }
+    /// Sets the followees of the neuron for one or more proposal topics.
+    ///
+    /// For each listed topic, the neuron's followees on that topic are replaced
+    /// with the given list; an empty list removes following on that topic.
+    /// Topics that are not listed are left unchanged.
+    ///
+    /// When voting on a proposal, a neuron follows the followees for the
+    /// proposal's function if there are any, otherwise the followees for the
+    /// proposal's topic, and otherwise the catch-all (unspecified function)
+    /// followees.
+    #[derive(
+        candid::CandidType,
+        candid::Deserialize,
+        comparable::Comparable,
+        Clone,
+        PartialEq,
+        ::prost::Message,
+    )]
+    pub struct SetFollowing {
+        /// Each topic may appear at most once.
+        #[prost(message, repeated, tag = "1")]
+        pub topic_following: ::prost::alloc::vec::Vec<set_following::FolloweesForTopic>,
+    }
+    /// Nested message and enum types in `SetFollowing`.
+    pub mod set_following {
+        #[derive(
+            candid::CandidType,
+            candid::Deserialize,
+            comparable::Comparable,
+            Clone,
+            PartialEq,
+            ::prost::Message,
+        )]
+        pub struct FolloweesForTopic {
+            /// The topic on which to follow. Must not be TOPIC_UNSPECIFIED.
+            #[prost(enumeration = "super::super::Topic", tag = "1")]
+            pub topic: i32,
+            /// The list of followee neurons, specified by their neuron ID.
+            #[prost(message, repeated, tag = "2")]
+            pub followees: ::prost::alloc::vec::Vec<super::super::NeuronId>,
+        }
+    }
     /// The operation that registers a given vote from the neuron for a given
     /// proposal (a directly cast vote as opposed to a vote that is cast as
     /// a result of a follow relation).
@@ -2178,6 +2401,8 @@ pub mod manage_neuron {
         RemoveNeuronPermissions(RemoveNeuronPermissions),
         #[prost(message, tag = "13")]
         StakeMaturity(StakeMaturity),
+        #[prost(message, tag = "14")]
+        SetFollowing(SetFollowing),
     }
 }
 /// The response of a ManageNeuron command.
@@ -2193,7 +2418,7 @@ pub mod manage_neuron {
 pub struct ManageNeuronResponse {
     #[prost(
         oneof = "manage_neuron_response::Command",
-        tags = "1, 2, 3, 4, 5, 6, 7, 8, 9, 10, 11, 12, 13"
+        tags = "1, 2, 3, 4, 5, 6, 7, 8, 9, 10, 11, 12, 13, 14"
     )]
     pub command: ::core::option::Option<manage_neuron_response::Command>,
 }
@@ -2280,6 +2505,16 @@ pub mod manage_neuron_response {
         ::prost::Message,
     )]
     pub struct FollowResponse {}
+    /// The response to the ManageNeuron command 'set_following'.
+    #[derive(
+        candid::CandidType,
+        candid::Deserialize,
+        comparable::Comparable,
+        Clone,
+        PartialEq,
+        ::prost::Message,
+    )]
+    pub struct SetFollowingResponse {}
     /// The response to the ManageNeuron command 'make_proposal'.
     #[derive(
         candid::CandidType,
@@ -2388,6 +2623,8 @@ pub mod manage_neuron_response {
         RemoveNeuronPermission(RemoveNeuronPermissionsResponse),
         #[prost(message, tag = "13")]
         StakeMaturity(StakeMaturityResponse),
+        #[prost(message, tag = "14")]
+        SetFollowing(SetFollowingResponse),
     }
 }
 /// An operation that attempts to get a neuron by a given neuron ID.
@@ -2606,6 +2843,59 @@ pub struct ListNervousSystemFunctionsResponse {
     #[prost(uint64, repeated, tag = "2")]
     pub reserved_ids: ::prost::alloc::vec::Vec<u64>,
 }
+/// The request for the list_topics query.
+#[derive(
+    candid::CandidType,
+    candid::Deserialize,
+    comparable::Comparable,
+    Clone,
+    PartialEq,
+    ::prost::Message,
+)]
+pub struct ListTopicsRequest {}
+/// A proposal topic, with the native and generic functions that belong to it.
+#[derive(
+    candid::CandidType,
+    candid::Deserialize,
+    comparable::Comparable,
+    Clone,
+    PartialEq,
+    ::prost::Message,
+)]
+pub struct TopicInfo {
+    #[prost(enumeration = "Topic", tag = "1")]
+    pub topic: i32,
+    /// A short, human readable name of the topic.
+    #[prost(string, tag = "2")]
+    pub name: ::prost::alloc::string::String,
+    /// A description of which proposals belong to the topic.
+    #[prost(string, tag = "3")]
+    pub description: ::prost::alloc::string::String,
+    /// The native functions whose proposals belong to the topic.
+    #[prost(message, repeated, tag = "4")]
+    pub native_functions: ::prost::alloc::vec::Vec<NervousSystemFunction>,
+    /// The generic functions whose proposals belong to the topic.
+    #[prost(message, repeated, tag = "5")]
+    pub custom_functions: ::prost::alloc::vec::Vec<NervousSystemFunction>,
+}
+/// The response to the list_topics query.
+#[derive(
+    candid::CandidType,
+    candid::Deserialize,
+    comparable::Comparable,
+    Clone,
+    PartialEq,
+    ::prost::Message,
+)]
+pub struct ListTopicsResponse {
+    /// All topics (except TOPIC_UNSPECIFIED), in the order of their values.
+    #[prost(message, repeated, tag = "1")]
+    pub topics: ::prost::alloc::vec::Vec<TopicInfo>,
+    /// The generic functions that do not have a topic. Proposals executing them
+    /// are only followed by function or with catch-all following.
+    #[prost(message, repeated, tag = "2")]
+    pub uncategorized_functions: ::prost::alloc::vec::Vec<NervousSystemFunction>,
+}
 #[derive(
     candid::CandidType,
     candid::Deserialize,
@@ -2962,6 +3252,64 @@ impl Vote {
         }
     }
 }
+/// Proposal topics group related proposal functions, both native and generic,
+/// so that neurons can follow other neurons on a whole group of functions at
+/// once instead of function by function.
+#[derive(
+    candid::CandidType,
+    candid::Deserialize,
+    comparable::Comparable,
+    strum_macros::EnumIter,
+    Clone,
+    Copy,
+    Debug,
+    PartialEq,
+    Eq,
+    Hash,
+    PartialOrd,
+    Ord,
+    ::prost::Enumeration,
+)]
+#[repr(i32)]
+pub enum Topic {
+    /// Proposals without a topic. Following on this topic is not allowed; use
+    /// the catch-all (unspecified function) followees instead.
+    Unspecified = 0,
+    /// Proposals that change the DAO's own settings, such as its nervous system
+    /// parameters or metadata.
+    DaoCommunitySettings = 1,
+    /// Proposals that upgrade the SNS framework canisters.
+    SnsFrameworkManagement = 2,
+    /// Proposals that upgrade or register dapp canisters.
+    DappCanisterManagement = 3,
+    /// Generic proposals that implement the dapp's business logic.
+    ApplicationBusinessLogic = 4,
+    /// Motions.
+    Governance = 5,
+    /// Proposals that move the DAO's treasury funds.
+    TreasuryAssetManagement = 6,
+    /// Proposals that can severely affect the dapp, such as adding or removing
+    /// generic functions and deregistering dapp canisters.
+    CriticalDappOperations = 7,
+}
+impl Topic {
+    /// String value of the enum field names used in the ProtoBuf definition.
+    ///
+    /// The values are not transformed in any way and thus are considered stable
+    /// (if the ProtoBuf definition does not change) and safe for programmatic use.
+    pub fn as_str_name(&self) -> &'static str {
+        match self {
+            Topic::Unspecified => "TOPIC_UNSPECIFIED",
+            Topic::DaoCommunitySettings => "TOPIC_DAO_COMMUNITY_SETTINGS",
+            Topic::SnsFrameworkManagement => "TOPIC_SNS_FRAMEWORK_MANAGEMENT",
+            Topic::DappCanisterManagement => "TOPIC_DAPP_CANISTER_MANAGEMENT",
+            Topic::ApplicationBusinessLogic => "TOPIC_APPLICATION_BUSINESS_LOGIC",
+            Topic::Governance => "TOPIC_GOVERNANCE",
+            Topic::TreasuryAssetManagement => "TOPIC_TREASURY_ASSET_MANAGEMENT",
+            Topic::CriticalDappOperations => "TOPIC_CRITICAL_DAPP_OPERATIONS",
+        }
+    }
+}
 #[derive(
     candid::CandidType,
     candid::Deserialize,
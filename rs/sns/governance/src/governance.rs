@@ -36,8 +36,9 @@ use crate::{
             neuron::{DissolveState, Followees},
             proposal::Action,
             transfer_sns_treasury_funds::TransferFrom,
-            Account as AccountProto, Ballot, ClaimSwapNeuronsError, ClaimSwapNeuronsRequest,
-            ClaimSwapNeuronsResponse, ClaimedSwapNeuronStatus, DefaultFollowees,
+            treasury_stream, Account as AccountProto, Ballot, CancelTreasuryStream,
+            ClaimSwapNeuronsError, ClaimSwapNeuronsRequest, ClaimSwapNeuronsResponse,
+            ClaimedSwapNeuronStatus, CreateTreasuryStream, DefaultFollowees,
             DeregisterDappCanisters, DisburseMaturityInProgress, Empty,
             ExecuteGenericNervousSystemFunction, FailStuckUpgradeInProgressRequest,
             FailStuckUpgradeInProgressResponse, GetMaturityModulationRequest,
@@ -45,18 +46,19 @@ use crate::{
             GetModeResponse, GetNeuron, GetNeuronResponse, GetProposal, GetProposalResponse,
             GetSnsInitializationParametersRequest, GetSnsInitializationParametersResponse,
             Governance as GovernanceProto, GovernanceError, ListNervousSystemFunctionsResponse,
-            ListNeurons, ListNeuronsResponse, ListProposals, ListProposalsResponse, ManageNeuron,
-            ManageNeuronResponse, ManageSnsMetadata, NervousSystemFunction,
-            NervousSystemParameters, Neuron, NeuronId, NeuronPermission, NeuronPermissionList,
-            NeuronPermissionType, Proposal, ProposalData, ProposalDecisionStatus, ProposalId,
-            ProposalRewardStatus, RegisterDappCanisters, RewardEvent, Tally,
-            TransferSnsTreasuryFunds, UpgradeSnsControlledCanister, UpgradeSnsToNextVersion, Vote,
-            VotingRewardsParameters, WaitForQuietState,
+            ListNeurons, ListNeuronsResponse, ListProposals, ListProposalsResponse,
+            ListTopicsResponse, ListTreasuryStreamsResponse, ManageNeuron, ManageNeuronResponse,
+            ManageSnsMetadata, NervousSystemFunction, NervousSystemParameters, Neuron, NeuronId,
+            NeuronPermission, NeuronPermissionList, NeuronPermissionType, Proposal, ProposalData,
+            ProposalDecisionStatus, ProposalId, ProposalRewardStatus, RegisterDappCanisters,
+            RewardEvent, Tally, Topic, TransferSnsTreasuryFunds, TreasuryStream,
+            UpgradeSnsControlledCanister, UpgradeSnsToNextVersion, Vote, VotingRewardsParameters,
+            WaitForQuietState,
         },
     },
     proposal::{
         validate_and_render_proposal, ValidGenericNervousSystemFunction, MAX_LIST_PROPOSAL_RESULTS,
-        MAX_NUMBER_OF_PROPOSALS_WITH_BALLOTS,
+        MAX_NUMBER_OF_ACTIVE_TREASURY_STREAMS, MAX_NUMBER_OF_PROPOSALS_WITH_BALLOTS,
     },
     sns_upgrade::{
         get_all_sns_canisters, get_running_version, get_upgrade_params, get_wasm, SnsCanisterType,
@@ -135,6 +137,9 @@ pub fn log_prefix() -> String {
 /// The static MEMO used when calculating the SNS Treasury subaccount.
 pub const TREASURY_SUBACCOUNT_NONCE: u64 = 0;
 
+/// The minimum time between two payouts of the same treasury stream.
+pub const TREASURY_STREAM_PAYOUT_INTERVAL_SECONDS: u64 = ONE_DAY_SECONDS;
+
 /// Converts bytes to a subaccountpub fn bytes_to_subaccount(bytes: &[u8]) -> Result<icrc_ledger_types::icrc1::account::Subaccount, GovernanceError> {
 pub fn bytes_to_subaccount(
     bytes: &[u8],
@@ -144,6 +149,27 @@ pub fn bytes_to_subaccount(
     })
 }
 
+/// Returns the account that a treasury stream pays out to.
+fn treasury_stream_target_account(
+    parameters: &CreateTreasuryStream,
+) -> Result<Account, GovernanceError> {
+    let owner = parameters.to_principal.ok_or_else(|| {
+        GovernanceError::new_with_message(
+            ErrorType::PreconditionFailed,
+            "Treasury stream has no target principal",
+        )
+    })?;
+    let subaccount = parameters
+        .to_subaccount
+        .as_ref()
+        .map(|s| bytes_to_subaccount(&s.subaccount[..]))
+        .transpose()?;
+    Ok(Account {
+        owner: owner.0,
+        subaccount,
+    })
+}
+
 impl NeuronPermissionType {
     /// Returns all the different types of neuron permissions as a vector.
     pub fn all() -> Vec<i32> {
@@ -268,6 +294,64 @@ impl GovernanceProto {
         }
     }
 
+    /// Builds an index that maps proposal topics to (followee) neuron IDs to these neuron's
+    /// followers. The resulting index is a map
+    /// Topic -> (followee's neuron ID) -> set of followers' neuron IDs.
+    ///
+    /// The index is built from the `neurons` in the `Governance` struct, which map followers
+    /// (the neuron ID) to a set of followees per topic.
+    pub fn build_topic_followee_index(
+        neurons: &BTreeMap<String, Neuron>,
+    ) -> BTreeMap<i32, BTreeMap<String, BTreeSet<NeuronId>>> {
+        let mut topic_followee_index = BTreeMap::new();
+        for neuron in neurons.values() {
+            GovernanceProto::add_neuron_to_topic_followee_index(&mut topic_followee_index, neuron);
+        }
+        topic_followee_index
+    }
+
+    /// Adds a neuron to the topic_followee_index.
+    pub fn add_neuron_to_topic_followee_index(
+        index: &mut BTreeMap<i32, BTreeMap<String, BTreeSet<NeuronId>>>,
+        neuron: &Neuron,
+    ) {
+        for (topic, followees) in neuron.topic_followees.iter() {
+            let followee_index = index.entry(*topic).or_insert_with(BTreeMap::new);
+            for followee in followees.followees.iter() {
+                followee_index
+                    .entry(followee.to_string())
+                    .or_insert_with(BTreeSet::new)
+                    .insert(
+                        neuron
+                            .id
+                            .as_ref()
+                            .expect("Neuron must have a NeuronId")
+                            .clone(),
+                    );
+            }
+        }
+    }
+
+    /// Removes a neuron from the topic_followee_index.
+    pub fn remove_neuron_from_topic_followee_index(
+        index: &mut BTreeMap<i32, BTreeMap<String, BTreeSet<NeuronId>>>,
+        neuron: &Neuron,
+    ) {
+        for (topic, followees) in neuron.topic_followees.iter() {
+            if let Some(followee_index) = index.get_mut(topic) {
+                for followee in followees.followees.iter() {
+                    let nid = followee.to_string();
+                    if let Some(followee_set) = followee_index.get_mut(&nid) {
+                        followee_set.remove(neuron.id.as_ref().expect("Neuron must have an id"));
+                        if followee_set.is_empty() {
+                            followee_index.remove(&nid);
+                        }
+                    }
+                }
+            }
+        }
+    }
+
     /// Iterate through one neuron and add all the principals that have some permission on this
     /// neuron to the index that maps principalIDs to a set of neurons for which the principal
     /// has some permissions.
@@ -649,6 +733,16 @@ pub struct Governance {
     /// Function ID -> (followee's neuron ID) -> set of followers' neuron IDs.
     pub function_followee_index: BTreeMap<u64, BTreeMap<String, BTreeSet<NeuronId>>>,
 
+    /// Cached data structure that (for each proposal topic) maps a followee to
+    /// the set of its followers. It is the inverse of the mapping from follower
+    /// to topic followees that is stored in each (follower) neuron.
+    ///
+    /// This is a cached index and will be removed and recreated when the state
+    /// is saved and restored.
+    ///
+    /// Topic -> (followee's neuron ID) -> set of followers' neuron IDs.
+    pub topic_followee_index: BTreeMap<i32, BTreeMap<String, BTreeSet<NeuronId>>>,
+
     /// Maps Principals to the Neuron IDs of all Neurons for which this principal
     /// has some permissions, i.e., all neurons that have this principal associated
     /// with a NeuronPermissionType for the Neuron.
@@ -718,6 +812,7 @@ impl Governance {
             nns_ledger,
             cmc,
             function_followee_index: BTreeMap::new(),
+            topic_followee_index: BTreeMap::new(),
             principal_to_neuron_ids_index: BTreeMap::new(),
             closest_proposal_deadline_timestamp_seconds: 0,
             latest_gc_timestamp_seconds: 0,
@@ -776,6 +871,8 @@ impl Governance {
         self.function_followee_index = self
             .proto
             .build_function_followee_index(&self.proto.neurons);
+        self.topic_followee_index =
+            GovernanceProto::build_topic_followee_index(&self.proto.neurons);
         self.principal_to_neuron_ids_index = self
             .proto
             .build_principal_to_neuron_ids_index(&self.proto.neurons);
@@ -886,7 +983,8 @@ impl Governance {
     }
 
     /// Adds a neuron to the list of neurons and updates the indices
-    /// `principal_to_neuron_ids_index` and `function_followee_index`.
+    /// `principal_to_neuron_ids_index`, `function_followee_index` and
+    /// `topic_followee_index`.
     ///
     /// Preconditions:
     /// - the heap can still grow
@@ -926,13 +1024,19 @@ impl Governance {
             &neuron,
         );
 
+        GovernanceProto::add_neuron_to_topic_followee_index(
+            &mut self.topic_followee_index,
+            &neuron,
+        );
+
         self.proto.neurons.insert(neuron_id.to_string(), neuron);
 
         Ok(())
     }
 
     /// Removes a neuron from the list of neurons and updates the indices
-    /// `principal_to_neuron_ids_index` and `function_followee_index`.
+    /// `principal_to_neuron_ids_index`, `function_followee_index` and
+    /// `topic_followee_index`.
     ///
     /// Preconditions:
     /// - the given `neuron_id` exists in `self.proto.neurons`
@@ -961,6 +1065,11 @@ impl Governance {
             &neuron,
         );
 
+        GovernanceProto::remove_neuron_from_topic_followee_index(
+            &mut self.topic_followee_index,
+            &neuron,
+        );
+
         self.proto.neurons.remove(&neuron_id.to_string());
 
         Ok(())
@@ -1320,6 +1429,7 @@ impl Governance {
                 auto_stake_maturity: parent_neuron.auto_stake_maturity,
                 vesting_period_seconds: None,
                 disburse_maturity_in_progress: vec![],
+                topic_followees: parent_neuron.topic_followees.clone(),
             };
 
             // Add the child neuron's id to the set of neurons with ongoing operations.
@@ -1899,6 +2009,18 @@ impl Governance {
         }
     }
 
+    /// Returns all topics together with the functions that belong to them.
+    pub fn list_topics(&self) -> ListTopicsResponse {
+        crate::topics::list_topics(&self.proto.id_to_nervous_system_functions)
+    }
+
+    /// Returns all treasury streams, including completed and cancelled ones.
+    pub fn list_treasury_streams(&self) -> ListTreasuryStreamsResponse {
+        ListTreasuryStreamsResponse {
+            treasury_streams: self.proto.treasury_streams.values().cloned().collect(),
+        }
+    }
+
     /// Returns the proposal IDs for all proposals that have reward status ReadyToSettle
     fn ready_to_be_settled_proposal_ids(&self) -> impl Iterator<Item = ProposalId> + '_ {
         let now = self.env.now();
@@ -2098,6 +2220,12 @@ impl Governance {
             Action::TransferSnsTreasuryFunds(transfer) => {
                 self.perform_transfer_sns_treasury_funds(transfer).await
             }
+            Action::CreateTreasuryStream(create) => {
+                self.perform_create_treasury_stream(proposal_id, create)
+            }
+            Action::CancelTreasuryStream(cancel) => {
+                self.perform_cancel_treasury_stream(proposal_id, cancel)
+            }
             // This should not be possible, because Proposal validation is performed when
             // a proposal is first made.
             Action::Unspecified(_) => Err(GovernanceError::new_with_message(
@@ -2612,18 +2740,36 @@ impl Governance {
                     .expect("Couldn't transform transfer.subaccount to Subaccount")
             }),
         };
-        match transfer.from_treasury() {
+        self.transfer_from_treasury(
+            transfer.from_treasury(),
+            transfer.amount_e8s,
+            to,
+            transfer.memo.unwrap_or(0),
+        )
+        .await
+        .map(|_| ())
+    }
+
+    /// Transfers `amount_e8s` from the given treasury to `to`, and returns
+    /// the index of the ledger block that records the transfer.
+    async fn transfer_from_treasury(
+        &self,
+        from_treasury: TransferFrom,
+        amount_e8s: u64,
+        to: Account,
+        memo: u64,
+    ) -> Result<u64, GovernanceError> {
+        match from_treasury {
             TransferFrom::IcpTreasury => self
                 .nns_ledger
                 .transfer_funds(
-                    transfer.amount_e8s,
+                    amount_e8s,
                     NNS_DEFAULT_TRANSFER_FEE.get_e8s(),
                     None,
                     to,
-                    transfer.memo.unwrap_or(0),
+                    memo,
                 )
                 .await
-                .map(|_| ())
                 .map_err(|e| {
                     GovernanceError::new_with_message(
                         ErrorType::External,
@@ -2639,14 +2785,13 @@ impl Governance {
                 );
                 self.ledger
                     .transfer_funds(
-                        transfer.amount_e8s,
+                        amount_e8s,
                         transaction_fee_e8s,
                         Some(treasury_subaccount),
                         to,
-                        transfer.memo.unwrap_or(0),
+                        memo,
                     )
                     .await
-                    .map(|_| ())
                     .map_err(|e| {
                         GovernanceError::new_with_message(
                             ErrorType::External,
@@ -2661,6 +2806,83 @@ impl Governance {
         }
     }
 
+    /// Creates the treasury stream of an adopted CreateTreasuryStream
+    /// proposal. The stream's id is the proposal's id. Payouts are made by
+    /// the heartbeat, see `maybe_pay_treasury_streams`.
+    fn perform_create_treasury_stream(
+        &mut self,
+        proposal_id: u64,
+        create: CreateTreasuryStream,
+    ) -> Result<(), GovernanceError> {
+        if self.proto.treasury_streams.contains_key(&proposal_id) {
+            return Err(GovernanceError::new_with_message(
+                ErrorType::PreconditionFailed,
+                format!(
+                    "There is already a treasury stream with id: {}",
+                    proposal_id
+                ),
+            ));
+        }
+        // Other streams might have been created since the proposal was made.
+        let number_of_active_streams = self
+            .proto
+            .treasury_streams
+            .values()
+            .filter(|stream| stream.is_active())
+            .count();
+        if number_of_active_streams >= MAX_NUMBER_OF_ACTIVE_TREASURY_STREAMS {
+            return Err(GovernanceError::new_with_message(
+                ErrorType::ResourceExhausted,
+                format!(
+                    "Reached maximum number of active treasury streams ({})",
+                    MAX_NUMBER_OF_ACTIVE_TREASURY_STREAMS
+                ),
+            ));
+        }
+        self.proto.treasury_streams.insert(
+            proposal_id,
+            TreasuryStream {
+                id: proposal_id,
+                parameters: Some(create),
+                ..Default::default()
+            },
+        );
+        Ok(())
+    }
+
+    /// Cancels a treasury stream, so that no further payouts are made.
+    fn perform_cancel_treasury_stream(
+        &mut self,
+        proposal_id: u64,
+        cancel: CancelTreasuryStream,
+    ) -> Result<(), GovernanceError> {
+        let now = self.env.now();
+        let stream = self
+            .proto
+            .treasury_streams
+            .get_mut(&cancel.stream_id)
+            .ok_or_else(|| {
+                GovernanceError::new_with_message(
+                    ErrorType::NotFound,
+                    format!("There is no treasury stream with id: {}", cancel.stream_id),
+                )
+            })?;
+        // The stream might have been completed or cancelled since the
+        // proposal was made.
+        if !stream.is_active() {
+            return Err(GovernanceError::new_with_message(
+                ErrorType::PreconditionFailed,
+                format!(
+                    "Treasury stream {} has already been completed or cancelled.",
+                    cancel.stream_id
+                ),
+            ));
+        }
+        stream.cancelled_timestamp_seconds = Some(now);
+        stream.cancelled_by_proposal_id = Some(proposal_id);
+        Ok(())
+    }
+
     // Returns an option with the NervousSystemParameters
     fn nervous_system_parameters(&self) -> Option<&NervousSystemParameters> {
         self.proto.parameters.as_ref()
@@ -2966,6 +3188,13 @@ impl Governance {
             let proposal_num = self.next_proposal_id();
             let proposal_id = ProposalId { id: proposal_num };
 
+            // The topic is fixed when the proposal is made, so that following
+            // on the proposal is not affected by later changes to functions.
+            let topic = Topic::of_function(
+                u64::from(action),
+                &self.proto.id_to_nervous_system_functions,
+            );
+
             // Compute whether the proposal is eligible for rewards
             let is_eligible_for_rewards =
                 self.voting_rewards_parameters_or_panic().rewards_enabled();
@@ -2993,6 +3222,7 @@ impl Governance {
                 wait_for_quiet_state: ProposalData::default().wait_for_quiet_state,
                 reward_event_end_timestamp_seconds: ProposalData::default()
                     .reward_event_end_timestamp_seconds,
+                topic: topic.map(|topic| topic as i32),
             };
 
             proposal_data.wait_for_quiet_state = Some(WaitForQuietState {
@@ -3018,7 +3248,9 @@ impl Governance {
                 proposer_id,
                 Vote::Yes,
                 function_id,
+                topic,
                 &self.function_followee_index,
+                &self.topic_followee_index,
                 &self.proto.neurons,
                 now_seconds,
                 &mut proposal_data.ballots,
@@ -3034,7 +3266,8 @@ impl Governance {
     /// Registers the vote `vote_of_neuron` for the neuron `voting_neuron_id`
     /// and cascades voting according to the following relationship given in
     /// function_followee_index that (for each action) maps a followee to
-    /// the set of followers.
+    /// the set of followers, and topic_followee_index that does the same for
+    /// the proposal's `topic`.
     ///
     /// This method should only be called with `vote_of_neuron` being `yes`
     /// or `no`.
@@ -3043,7 +3276,9 @@ impl Governance {
         voting_neuron_id: &NeuronId,
         vote_of_neuron: Vote,
         function_id: u64,
+        topic: Option<Topic>,
         function_followee_index: &BTreeMap<u64, BTreeMap<String, BTreeSet<NeuronId>>>,
+        topic_followee_index: &BTreeMap<i32, BTreeMap<String, BTreeSet<NeuronId>>>,
         neurons: &BTreeMap<String, Neuron>,
         now_seconds: u64,
         ballots: &mut BTreeMap<String, Ballot>, // This is ultimately what gets changed.
@@ -3052,11 +3287,14 @@ impl Governance {
         // voted on.
         let unspecified_function_id = u64::from(&Action::Unspecified(Empty {}));
         assert!(function_id != unspecified_function_id);
-        // The follow graph is the union of these two "successor list" tables.
+        // The follow graph is the union of these three "successor list" tables.
         let empty_neuron_id_to_follower_neuron_ids = BTreeMap::new();
         let neuron_id_to_follower_neuron_ids_on_function = function_followee_index
             .get(&function_id)
             .unwrap_or(&empty_neuron_id_to_follower_neuron_ids);
+        let neuron_id_to_follower_neuron_ids_on_topic = topic
+            .and_then(|topic| topic_followee_index.get(&(topic as i32)))
+            .unwrap_or(&empty_neuron_id_to_follower_neuron_ids);
         let neuron_id_to_blanket_follower_neuron_ids = function_followee_index
             .get(&unspecified_function_id)
             .unwrap_or(&empty_neuron_id_to_follower_neuron_ids);
@@ -3112,11 +3350,16 @@ impl Governance {
                     .get(current_neuron_id)
                     .cloned()
                     .unwrap_or_default();
+                let mut topic_follower_neuron_ids = neuron_id_to_follower_neuron_ids_on_topic
+                    .get(current_neuron_id)
+                    .cloned()
+                    .unwrap_or_default();
                 let mut blanket_follower_neuron_ids = neuron_id_to_blanket_follower_neuron_ids
                     .get(current_neuron_id)
                     .cloned()
                     .unwrap_or_default();
                 follower_neuron_ids.append(&mut specific_follower_neuron_ids);
+                follower_neuron_ids.append(&mut topic_follower_neuron_ids);
                 follower_neuron_ids.append(&mut blanket_follower_neuron_ids);
             }
 
@@ -3143,7 +3386,8 @@ impl Governance {
                     }
                 };
 
-                let follower_vote = follower_neuron.would_follow_ballots(function_id, ballots);
+                let follower_vote =
+                    follower_neuron.would_follow_ballots(function_id, topic, ballots);
                 if follower_vote != Vote::Unspecified {
                     // follower_neuron would be swayed by its followees!
                     //
@@ -3240,12 +3484,15 @@ impl Governance {
 
             // Update ballots.
             let function_id = u64::from(action);
+            let topic = proposal.topic.and_then(Topic::from_i32);
             Governance::cast_vote_and_cascade_follow(
                 proposal_id,
                 neuron_id,
                 vote,
                 function_id,
+                topic,
                 &self.function_followee_index,
+                &self.topic_followee_index,
                 &self.proto.neurons,
                 now_seconds,
                 &mut proposal.ballots,
@@ -3369,6 +3616,104 @@ impl Governance {
         })
     }
 
+    /// Sets the followees of a given neuron (specified by the given neuron
+    /// id) for the given topics.
+    ///
+    /// For each topic, if the list of followees is empty, the followees for
+    /// this topic are removed. Otherwise, the current list of followees for
+    /// the topic is replaced with the provided list. Topics that are not
+    /// mentioned in the request are left unchanged.
+    ///
+    /// When voting on a proposal, followees for the proposal's function take
+    /// precedence over followees for the proposal's topic, which in turn take
+    /// precedence over the catch-all followees.
+    ///
+    /// Preconditions:
+    /// - the follower neuron exists
+    /// - the caller has the permission to change followers (same authorization
+    ///   as voting required, i.e., permission `Vote`)
+    /// - each topic is specified and appears at most once
+    /// - no list of followees is too long (does not exceed max_followees_per_function
+    ///   as defined in the nervous system parameters)
+    fn set_following(
+        &mut self,
+        id: &NeuronId,
+        caller: &PrincipalId,
+        set_following: &manage_neuron::SetFollowing,
+    ) -> Result<(), GovernanceError> {
+        measure_span(self.profiling_information, "set_following", || {
+            let neuron = self.proto.neurons.get_mut(&id.to_string()).ok_or_else(||
+            // The specified neuron is not present.
+            GovernanceError::new_with_message(ErrorType::NotFound, format!("Follower neuron not found: {}", id)))?;
+
+            // Check that the caller is authorized to change followers (same authorization
+            // as voting required).
+            neuron.check_authorized(caller, NeuronPermissionType::Vote)?;
+
+            let max_followees_per_function = self
+                .proto
+                .parameters
+                .as_ref()
+                .expect("NervousSystemParameters not present")
+                .max_followees_per_function
+                .expect("NervousSystemParameters must have max_followees_per_function");
+
+            let mut topics = BTreeSet::new();
+            for followees_for_topic in &set_following.topic_following {
+                let topic = followees_for_topic.topic;
+                match Topic::from_i32(topic) {
+                    Some(Topic::Unspecified) | None => {
+                        return Err(GovernanceError::new_with_message(
+                            ErrorType::InvalidCommand,
+                            format!("Invalid topic: {}", topic),
+                        ));
+                    }
+                    Some(_) => (),
+                }
+                if !topics.insert(topic) {
+                    return Err(GovernanceError::new_with_message(
+                        ErrorType::InvalidCommand,
+                        format!("Topic {} is specified more than once.", topic),
+                    ));
+                }
+                // Same reasoning as in `follow`: allowing neurons to follow
+                // too many neurons allows a memory exhaustion attack.
+                if followees_for_topic.followees.len() > max_followees_per_function as usize {
+                    return Err(GovernanceError::new_with_message(
+                        ErrorType::InvalidCommand,
+                        "Too many followees.",
+                    ));
+                }
+            }
+
+            // Update the neuron's topic followees, keeping the
+            // topic_followee_index in sync by removing the neuron from it
+            // before the update and adding it back afterwards.
+            GovernanceProto::remove_neuron_from_topic_followee_index(
+                &mut self.topic_followee_index,
+                neuron,
+            );
+            for followees_for_topic in &set_following.topic_following {
+                if followees_for_topic.followees.is_empty() {
+                    neuron.topic_followees.remove(&followees_for_topic.topic);
+                } else {
+                    neuron.topic_followees.insert(
+                        followees_for_topic.topic,
+                        Followees {
+                            followees: followees_for_topic.followees.clone(),
+                        },
+                    );
+                }
+            }
+            GovernanceProto::add_neuron_to_topic_followee_index(
+                &mut self.topic_followee_index,
+                neuron,
+            );
+
+            Ok(())
+        })
+    }
+
     /// Configures a given neuron (specified by the given neuron id).
     /// Specifically, this allows to stop and start dissolving a neuron
     /// as well as to increase a neuron's dissolve delay.
@@ -3551,6 +3896,7 @@ impl Governance {
             auto_stake_maturity: None,
             vesting_period_seconds: None,
             disburse_maturity_in_progress: vec![],
+            topic_followees: BTreeMap::new(),
         };
 
         // This also verifies that there are not too many neurons already.
@@ -3705,6 +4051,7 @@ impl Governance {
                 auto_stake_maturity: neuron_parameter.construct_auto_staking_maturity(),
                 vesting_period_seconds: None,
                 disburse_maturity_in_progress: vec![],
+                topic_followees: BTreeMap::new(),
             };
 
             // Add the neuron to the various data structures and indexes to support neurons. This
@@ -4013,6 +4360,9 @@ impl Governance {
             C::Follow(f) => self
                 .follow(&neuron_id, caller, f)
                 .map(|_| ManageNeuronResponse::follow_response()),
+            C::SetFollowing(s) => self
+                .set_following(&neuron_id, caller, s)
+                .map(|_| ManageNeuronResponse::set_following_response()),
             C::MakeProposal(p) => self
                 .make_proposal(&neuron_id, caller, p)
                 .await
@@ -4074,6 +4424,7 @@ impl Governance {
             Disburse(_) => err("Disburse"),
             Split(_) => err("Split"),
             Follow(_)
+            | SetFollowing(_)
             | MakeProposal(_)
             | RegisterVote(_)
             | ClaimOrRefresh(_)
@@ -4224,6 +4575,89 @@ impl Governance {
         self.proto.is_finalizing_disburse_maturity = None;
     }
 
+    // Pays out the vested amounts of all treasury streams that have a payout
+    // due, unless this is already happening.
+    async fn maybe_pay_treasury_streams(&mut self) {
+        if self.proto.is_paying_treasury_streams == Some(true) {
+            return;
+        }
+        let now_seconds = self.env.now();
+        let payouts_due: Vec<(u64, u64, CreateTreasuryStream)> = self
+            .proto
+            .treasury_streams
+            .values()
+            .filter_map(|stream| {
+                let amount_due_e8s =
+                    stream.amount_due_e8s(now_seconds, TREASURY_STREAM_PAYOUT_INTERVAL_SECONDS)?;
+                Some((stream.id, amount_due_e8s, stream.parameters.clone()?))
+            })
+            .collect();
+        if payouts_due.is_empty() {
+            return;
+        }
+
+        self.proto.is_paying_treasury_streams = Some(true);
+        for (stream_id, amount_e8s, parameters) in payouts_due {
+            let to = match treasury_stream_target_account(&parameters) {
+                Ok(to) => to,
+                Err(e) => {
+                    log!(
+                        ERROR,
+                        "Invalid target account of treasury stream {}, skipping: {}",
+                        stream_id,
+                        e
+                    );
+                    continue;
+                }
+            };
+            let transfer_result = self
+                .transfer_from_treasury(
+                    parameters.from_treasury(),
+                    amount_e8s,
+                    to,
+                    parameters.memo.unwrap_or(0),
+                )
+                .await;
+            match transfer_result {
+                Ok(block_index) => {
+                    log!(
+                        INFO,
+                        "Paid out {} e8s of treasury stream {} at block {}.",
+                        amount_e8s,
+                        stream_id,
+                        block_index
+                    );
+                    // The payout is recorded even if the stream was cancelled
+                    // in the meantime, as the funds have been transferred.
+                    if let Some(stream) = self.proto.treasury_streams.get_mut(&stream_id) {
+                        stream.paid_amount_e8s = stream.paid_amount_e8s.saturating_add(amount_e8s);
+                        stream.payouts.push(treasury_stream::Payout {
+                            amount_e8s,
+                            timestamp_seconds: now_seconds,
+                            block_index,
+                        });
+                        stream.last_failed_payout_timestamp_seconds = None;
+                    }
+                }
+                Err(e) => {
+                    log!(
+                        ERROR,
+                        "Failed paying out {} e8s of treasury stream {}, retrying in {} seconds: {}",
+                        amount_e8s,
+                        stream_id,
+                        TREASURY_STREAM_PAYOUT_INTERVAL_SECONDS,
+                        e
+                    );
+                    // Throttle the retries, see `TreasuryStream::amount_due_e8s`.
+                    if let Some(stream) = self.proto.treasury_streams.get_mut(&stream_id) {
+                        stream.last_failed_payout_timestamp_seconds = Some(now_seconds);
+                    }
+                }
+            }
+        }
+        self.proto.is_paying_treasury_streams = None;
+    }
+
     /// When a neuron is finally dissolved, if there is any staked maturity it is moved to regular maturity
     /// which can be spawned.
     pub(crate) fn maybe_move_staked_maturity(&mut self) {
@@ -4364,6 +4798,8 @@ impl Governance {
 
         self.maybe_finalize_disburse_maturity().await;
 
+        self.maybe_pay_treasury_streams().await;
+
         measure_span(
             self.profiling_information,
             "maybe_move_staked_maturity",
@@ -5061,7 +5497,7 @@ impl Governance {
     ///   `principal_to_neuron_ids_index`)
     /// - the followees are not changed (it's easy to update followees
     ///   via `manage_neuron` and doing it here would require updating
-    ///   `function_followee_index`)
+    ///   `function_followee_index` and `topic_followee_index`)
     #[cfg(feature = "test")]
     pub fn update_neuron(&mut self, neuron: Neuron) -> Result<(), GovernanceError> {
         let neuron_id = &neuron.id.as_ref().expect("Neuron must have a NeuronId");
@@ -5279,7 +5715,7 @@ mod tests {
     use ic_protobuf::types::v1::CanisterInstallMode as CanisterInstallModeProto;
     use ic_sns_test_utils::itest_helpers::UserInfo;
     use ic_test_utilities::types::ids::canister_test_id;
-    use maplit::btreemap;
+    use maplit::{btreemap, btreeset};
     use proptest::prelude::{prop_assert, proptest};
     use std::sync::{Arc, Mutex};
 
@@ -5313,6 +5749,34 @@ mod tests {
         }
     }
 
+    struct AlwaysFailingLedger {}
+
+    #[async_trait]
+    impl ICRC1Ledger for AlwaysFailingLedger {
+        async fn transfer_funds(
+            &self,
+            _amount_e8s: u64,
+            _fee_e8s: u64,
+            _from_subaccount: Option<Subaccount>,
+            _to: Account,
+            _memo: u64,
+        ) -> Result<u64, NervousSystemError> {
+            Err(NervousSystemError::new_with_message("Insufficient funds"))
+        }
+
+        async fn total_supply(&self) -> Result<Tokens, NervousSystemError> {
+            unimplemented!()
+        }
+
+        async fn account_balance(&self, _account: Account) -> Result<Tokens, NervousSystemError> {
+            unimplemented!()
+        }
+
+        fn canister_id(&self) -> CanisterId {
+            CanisterId::from_u64(42)
+        }
+    }
+
     struct AlwaysSucceedingLedger {}
 
     #[async_trait]
@@ -5643,6 +6107,7 @@ mod tests {
                         target_method_name: Some("test_method".to_string()),
                         validator_canister_id: Some(CanisterId::from_u64(1).get()),
                         validator_method_name: Some("test_validator_method".to_string()),
+                        topic: None,
                     },
                 )),
             },
@@ -8280,6 +8745,7 @@ mod tests {
                     target_method_name: Some("test_method".to_string()),
                     validator_canister_id: Some(CanisterId::from(100).get()),
                     validator_method_name: Some("test_validator_method".to_string()),
+                    topic: None,
                 },
             )),
         };
@@ -8533,6 +8999,339 @@ mod tests {
         }
     }
 
+    #[test]
+    fn test_set_following_updates_topic_followees_and_index() {
+        // Step 1: Prepare the world and parameters.
+        let controller = *TEST_NEURON_1_OWNER_PRINCIPAL;
+        let neuron_id = test_neuron_id(controller);
+        let followee_1 = test_neuron_id(*TEST_NEURON_2_OWNER_PRINCIPAL);
+        let followee_2 = NeuronId { id: vec![42] };
+        let neuron = Neuron {
+            id: Some(neuron_id.clone()),
+            permissions: vec![NeuronPermission {
+                principal: Some(controller),
+                permission_type: vec![NeuronPermissionType::Vote as i32],
+            }],
+            ..Default::default()
+        };
+        let mut governance_proto = basic_governance_proto();
+        governance_proto
+            .neurons
+            .insert(neuron_id.to_string(), neuron);
+        let mut governance = default_governance_with_proto(governance_proto);
+        let followees_for_topic = |topic: Topic, followees: Vec<NeuronId>| {
+            manage_neuron::set_following::FolloweesForTopic {
+                topic: topic as i32,
+                followees,
+            }
+        };
+
+        // Step 2: Run code under test.
+        let result = governance.set_following(
+            &neuron_id,
+            &controller,
+            &manage_neuron::SetFollowing {
+                topic_following: vec![
+                    followees_for_topic(Topic::Governance, vec![followee_1.clone()]),
+                    followees_for_topic(
+                        Topic::CriticalDappOperations,
+                        vec![followee_1.clone(), followee_2.clone()],
+                    ),
+                ],
+            },
+        );
+
+        // Step 3: Inspect result(s).
+        assert_is_ok!(result);
+        let neuron = &governance.proto.neurons[&neuron_id.to_string()];
+        assert_eq!(
+            neuron.topic_followees[&(Topic::Governance as i32)].followees,
+            vec![followee_1.clone()]
+        );
+        assert_eq!(
+            governance.topic_followee_index[&(Topic::CriticalDappOperations as i32)]
+                [&followee_2.to_string()],
+            btreeset! {neuron_id.clone()}
+        );
+
+        // Step 4: Clear the following on one topic; the other is kept.
+        let result = governance.set_following(
+            &neuron_id,
+            &controller,
+            &manage_neuron::SetFollowing {
+                topic_following: vec![followees_for_topic(Topic::CriticalDappOperations, vec![])],
+            },
+        );
+
+        assert_is_ok!(result);
+        let neuron = &governance.proto.neurons[&neuron_id.to_string()];
+        assert_eq!(
+            neuron.topic_followees.keys().copied().collect::<Vec<_>>(),
+            vec![Topic::Governance as i32]
+        );
+        assert_eq!(
+            governance.topic_followee_index,
+            GovernanceProto::build_topic_followee_index(&governance.proto.neurons)
+        );
+        assert!(
+            !governance.topic_followee_index[&(Topic::CriticalDappOperations as i32)]
+                .values()
+                .any(|followers| followers.contains(&neuron_id))
+        );
+    }
+
+    #[test]
+    fn test_set_following_fails_on_invalid_topics() {
+        // Step 1: Prepare the world and parameters.
+        let controller = *TEST_NEURON_1_OWNER_PRINCIPAL;
+        let neuron_id = test_neuron_id(controller);
+        let neuron = Neuron {
+            id: Some(neuron_id.clone()),
+            permissions: vec![NeuronPermission {
+                principal: Some(controller),
+                permission_type: vec![NeuronPermissionType::Vote as i32],
+            }],
+            ..Default::default()
+        };
+        let mut governance_proto = basic_governance_proto();
+        governance_proto
+            .neurons
+            .insert(neuron_id.to_string(), neuron);
+        let mut governance = default_governance_with_proto(governance_proto);
+
+        for topic_following in [
+            vec![Topic::Unspecified as i32],
+            vec![1_000],
+            vec![Topic::Governance as i32, Topic::Governance as i32],
+        ] {
+            let set_following = manage_neuron::SetFollowing {
+                topic_following: topic_following
+                    .into_iter()
+                    .map(|topic| manage_neuron::set_following::FolloweesForTopic {
+                        topic,
+                        followees: vec![],
+                    })
+                    .collect(),
+            };
+
+            // Step 2: Run code under test.
+            let result = governance.set_following(&neuron_id, &controller, &set_following);
+
+            // Step 3: Inspect result(s).
+            assert_matches!(
+                result,
+                Err(GovernanceError{error_type: code, error_message: _msg})
+                    if code == ErrorType::InvalidCommand as i32
+            );
+        }
+    }
+
+    #[test]
+    fn test_treasury_stream_pays_out_vested_amounts_until_cancelled() {
+        // Step 1: Prepare the world and parameters.
+        let env = NativeEnvironment::new(Some(CanisterId::from_u64(350519)));
+        let start_timestamp_seconds = env.now();
+        let mut governance = Governance::new(
+            basic_governance_proto().try_into().unwrap(),
+            Box::new(env),
+            Box::new(AlwaysSucceedingLedger {}),
+            Box::new(DoNothingLedger {}),
+            Box::new(FakeCmc::new()),
+        );
+        let proposal_id = 42;
+        let create = CreateTreasuryStream {
+            from_treasury: TransferFrom::SnsTokenTreasury as i32,
+            to_principal: Some(*TEST_NEURON_1_OWNER_PRINCIPAL),
+            total_amount_e8s: 100 * E8,
+            start_timestamp_seconds,
+            cliff_seconds: 10 * ONE_DAY_SECONDS,
+            vesting_period_seconds: 100 * ONE_DAY_SECONDS,
+            ..Default::default()
+        };
+
+        // Step 2: Create the stream.
+        governance
+            .perform_create_treasury_stream(proposal_id, create)
+            .unwrap();
+
+        // Step 3: Nothing is paid out before the cliff.
+        governance.env.set_time_warp(TimeWarp {
+            delta_s: (5 * ONE_DAY_SECONDS) as i64,
+        });
+        governance
+            .maybe_pay_treasury_streams()
+            .now_or_never()
+            .unwrap();
+        assert_eq!(
+            governance.proto.treasury_streams[&proposal_id].paid_amount_e8s,
+            0
+        );
+
+        // Step 4: After the cliff, the amount that vested so far is paid out,
+        // but only once per payout interval.
+        governance.env.set_time_warp(TimeWarp {
+            delta_s: (15 * ONE_DAY_SECONDS) as i64,
+        });
+        governance
+            .maybe_pay_treasury_streams()
+            .now_or_never()
+            .unwrap();
+        governance
+            .maybe_pay_treasury_streams()
+            .now_or_never()
+            .unwrap();
+        let stream = &governance.proto.treasury_streams[&proposal_id];
+        assert_eq!(stream.paid_amount_e8s, 20 * E8);
+        assert_eq!(
+            stream.payouts,
+            vec![treasury_stream::Payout {
+                amount_e8s: 20 * E8,
+                timestamp_seconds: start_timestamp_seconds + 20 * ONE_DAY_SECONDS,
+                block_index: 0,
+            }]
+        );
+        assert!(governance.proto.is_paying_treasury_streams.is_none());
+        assert_eq!(
+            governance.list_treasury_streams().treasury_streams,
+            vec![stream.clone()]
+        );
+
+        // Step 5: After cancellation, nothing is paid out anymore.
+        governance
+            .perform_cancel_treasury_stream(
+                43,
+                CancelTreasuryStream {
+                    stream_id: proposal_id,
+                },
+            )
+            .unwrap();
+        governance.env.set_time_warp(TimeWarp {
+            delta_s: (10 * ONE_DAY_SECONDS) as i64,
+        });
+        governance
+            .maybe_pay_treasury_streams()
+            .now_or_never()
+            .unwrap();
+        let stream = &governance.proto.treasury_streams[&proposal_id];
+        assert_eq!(stream.paid_amount_e8s, 20 * E8);
+        assert_eq!(stream.cancelled_by_proposal_id, Some(43));
+        assert!(!stream.is_active());
+        assert_matches!(
+            governance.perform_cancel_treasury_stream(44, CancelTreasuryStream { stream_id: proposal_id }),
+            Err(GovernanceError{error_type: code, error_message: _msg})
+                if code == ErrorType::PreconditionFailed as i32
+        );
+    }
+
+    #[test]
+    fn test_treasury_stream_retries_failed_payouts_once_per_interval() {
+        // Step 1: Prepare the world and parameters.
+        let env = NativeEnvironment::new(Some(CanisterId::from_u64(350519)));
+        let start_timestamp_seconds = env.now();
+        let mut governance = Governance::new(
+            basic_governance_proto().try_into().unwrap(),
+            Box::new(env),
+            Box::new(AlwaysFailingLedger {}),
+            Box::new(DoNothingLedger {}),
+            Box::new(FakeCmc::new()),
+        );
+        let proposal_id = 42;
+        governance
+            .perform_create_treasury_stream(
+                proposal_id,
+                CreateTreasuryStream {
+                    from_treasury: TransferFrom::SnsTokenTreasury as i32,
+                    to_principal: Some(*TEST_NEURON_1_OWNER_PRINCIPAL),
+                    total_amount_e8s: 100 * E8,
+                    start_timestamp_seconds,
+                    cliff_seconds: 10 * ONE_DAY_SECONDS,
+                    vesting_period_seconds: 100 * ONE_DAY_SECONDS,
+                    ..Default::default()
+                },
+            )
+            .unwrap();
+
+        // Step 2: The failed payout is recorded, and not retried right away.
+        governance.env.set_time_warp(TimeWarp {
+            delta_s: (20 * ONE_DAY_SECONDS) as i64,
+        });
+        governance
+            .maybe_pay_treasury_streams()
+            .now_or_never()
+            .unwrap();
+        let failed_timestamp_seconds = start_timestamp_seconds + 20 * ONE_DAY_SECONDS;
+        let stream = &governance.proto.treasury_streams[&proposal_id];
+        assert_eq!(stream.paid_amount_e8s, 0);
+        assert!(stream.payouts.is_empty());
+        assert_eq!(
+            stream.last_failed_payout_timestamp_seconds,
+            Some(failed_timestamp_seconds)
+        );
+        assert!(governance.proto.is_paying_treasury_streams.is_none());
+        assert_eq!(
+            stream.amount_due_e8s(
+                failed_timestamp_seconds + TREASURY_STREAM_PAYOUT_INTERVAL_SECONDS - 1,
+                TREASURY_STREAM_PAYOUT_INTERVAL_SECONDS
+            ),
+            None
+        );
+
+        // Step 3: Once the payout interval has passed, the payout is retried,
+        // and a successful payout clears the failure.
+        governance.ledger = Box::new(AlwaysSucceedingLedger {});
+        governance.env.set_time_warp(TimeWarp {
+            delta_s: TREASURY_STREAM_PAYOUT_INTERVAL_SECONDS as i64,
+        });
+        governance
+            .maybe_pay_treasury_streams()
+            .now_or_never()
+            .unwrap();
+        let stream = &governance.proto.treasury_streams[&proposal_id];
+        assert_eq!(stream.paid_amount_e8s, 21 * E8);
+        assert_eq!(stream.payouts.len(), 1);
+        assert_eq!(stream.last_failed_payout_timestamp_seconds, None);
+    }
+
+    #[test]
+    fn test_perform_create_treasury_stream_limits_the_number_of_active_streams() {
+        let mut governance = Governance::new(
+            basic_governance_proto().try_into().unwrap(),
+            Box::new(NativeEnvironment::new(Some(CanisterId::from_u64(350519)))),
+            Box::new(AlwaysSucceedingLedger {}),
+            Box::new(DoNothingLedger {}),
+            Box::new(FakeCmc::new()),
+        );
+        let create = CreateTreasuryStream {
+            from_treasury: TransferFrom::SnsTokenTreasury as i32,
+            to_principal: Some(*TEST_NEURON_1_OWNER_PRINCIPAL),
+            total_amount_e8s: 100 * E8,
+            vesting_period_seconds: 100 * ONE_DAY_SECONDS,
+            ..Default::default()
+        };
+        for proposal_id in 0..MAX_NUMBER_OF_ACTIVE_TREASURY_STREAMS as u64 {
+            governance
+                .perform_create_treasury_stream(proposal_id, create.clone())
+                .unwrap();
+        }
+
+        // Proposals that were valid when they were made are rejected once the
+        // maximum has been reached in the meantime.
+        let proposal_id = MAX_NUMBER_OF_ACTIVE_TREASURY_STREAMS as u64;
+        assert_matches!(
+            governance.perform_create_treasury_stream(proposal_id, create.clone()),
+            Err(GovernanceError{error_type: code, error_message: _msg})
+                if code == ErrorType::ResourceExhausted as i32
+        );
+
+        // Completed or cancelled streams do not count.
+        governance
+            .perform_cancel_treasury_stream(proposal_id + 1, CancelTreasuryStream { stream_id: 0 })
+            .unwrap();
+        governance
+            .perform_create_treasury_stream(proposal_id, create)
+            .unwrap();
+    }
+
     #[test]
     fn test_move_staked_maturity_on_dissolved_neurons_works() {
         // Step 1: Prepare the world and parameters.
@@ -9263,6 +10062,7 @@ mod tests {
                     target_method_name: Some("test_method".to_string()),
                     validator_canister_id: Some(CanisterId::from(1).get()),
                     validator_method_name: Some("test_validator_method".to_string()),
+                    topic: None,
                 },
             )),
         };
@@ -9285,6 +10085,7 @@ mod tests {
                     target_method_name: Some("test_method".to_string()),
                     validator_canister_id: Some(invalid_canister_target.get()),
                     validator_method_name: Some("test_validator_method".to_string()),
+                    topic: None,
                 },
             )),
         };
@@ -1,7 +1,7 @@
 use crate::pb::v1::{
     governance_error::ErrorType, manage_neuron, neuron::DissolveState, proposal::Action, Ballot,
     Empty, GovernanceError, Neuron, NeuronId, NeuronPermission, NeuronPermissionList,
-    NeuronPermissionType, Vote,
+    NeuronPermissionType, Topic, Vote,
 };
 use ic_base_types::PrincipalId;
 use icrc_ledger_types::icrc1::account::Subaccount;
@@ -231,21 +231,29 @@ impl Neuron {
 
     /// Given the specified `ballots`, determine how the neuron would
     /// vote on a proposal of `action` based on which neurons this
-    /// neuron follows on this action (or on the default action if this
-    /// neuron doesn't specify any followees for `action`).
+    /// neuron follows on this action (or on the proposal's `topic` if this
+    /// neuron doesn't specify any followees for `action`, or on the default
+    /// action if it doesn't specify any followees for the topic either).
     pub(crate) fn would_follow_ballots(
         &self,
         action: u64,
+        topic: Option<Topic>,
         ballots: &BTreeMap<String, Ballot>,
     ) -> Vote {
         // Compute the list of followees for this action. If no
         // following is specified for the action, use the followees
-        // from the 'Unspecified' action.
+        // for the topic, and then the followees from the 'Unspecified'
+        // action.
         let unspecified_key = u64::from(&Action::Unspecified(Empty {}));
         if let Some(followees) = self
             .followees
             .get(&(action))
             .filter(|followees| !followees.followees.is_empty())
+            .or_else(|| {
+                topic
+                    .and_then(|topic| self.topic_followees.get(&(topic as i32)))
+                    .filter(|followees| !followees.followees.is_empty())
+            })
             .or_else(|| self.followees.get(&unspecified_key))
             // extract plain vector from 'Followees' proto
             .map(|x| &x.followees)
@@ -781,6 +789,7 @@ impl PartialOrd for NeuronId {
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::pb::v1::neuron::Followees;
     use proptest::prelude::proptest;
 
     #[test]
@@ -799,6 +808,75 @@ mod tests {
         assert!(!neuron.is_vesting(10000));
     }
 
+    #[test]
+    fn test_would_follow_ballots_falls_back_from_function_to_topic_to_catch_all() {
+        let function_followee = NeuronId { id: vec![1] };
+        let topic_followee = NeuronId { id: vec![2] };
+        let catch_all_followee = NeuronId { id: vec![3] };
+        let ballots = BTreeMap::from([
+            (
+                function_followee.to_string(),
+                Ballot {
+                    vote: Vote::Yes as i32,
+                    ..Default::default()
+                },
+            ),
+            (
+                topic_followee.to_string(),
+                Ballot {
+                    vote: Vote::No as i32,
+                    ..Default::default()
+                },
+            ),
+            (
+                catch_all_followee.to_string(),
+                Ballot {
+                    vote: Vote::Yes as i32,
+                    ..Default::default()
+                },
+            ),
+        ]);
+        let motion = u64::from(&Action::Motion(Default::default()));
+        let followees = |followee: &NeuronId| Followees {
+            followees: vec![followee.clone()],
+        };
+
+        let mut neuron = Neuron {
+            followees: BTreeMap::from([
+                (motion, followees(&function_followee)),
+                (
+                    u64::from(&Action::Unspecified(Empty {})),
+                    followees(&catch_all_followee),
+                ),
+            ]),
+            topic_followees: BTreeMap::from([(
+                Topic::Governance as i32,
+                followees(&topic_followee),
+            )]),
+            ..Default::default()
+        };
+        assert_eq!(
+            neuron.would_follow_ballots(motion, Some(Topic::Governance), &ballots),
+            Vote::Yes
+        );
+
+        neuron.followees.remove(&motion);
+        assert_eq!(
+            neuron.would_follow_ballots(motion, Some(Topic::Governance), &ballots),
+            Vote::No
+        );
+        assert_eq!(
+            neuron.would_follow_ballots(motion, None, &ballots),
+            Vote::Yes
+        );
+
+        neuron.topic_followees.clear();
+        assert_eq!(
+            neuron.would_follow_ballots(motion, Some(Topic::Governance), &ballots),
+            Vote::Yes
+        );
+    }
+
     #[test]
     fn test_voting_power_fully_boosted() {
         let base_stake = 100;
@@ -8,10 +8,12 @@ use crate::{
         proposal,
         proposal::Action,
         transfer_sns_treasury_funds::TransferFrom,
-        DeregisterDappCanisters, ExecuteGenericNervousSystemFunction, Governance,
-        ManageSnsMetadata, Motion, NervousSystemFunction, NervousSystemParameters, Proposal,
-        ProposalData, ProposalDecisionStatus, ProposalRewardStatus, RegisterDappCanisters, Tally,
-        TransferSnsTreasuryFunds, UpgradeSnsControlledCanister, UpgradeSnsToNextVersion, Vote,
+        CancelTreasuryStream, CreateTreasuryStream, DeregisterDappCanisters,
+        ExecuteGenericNervousSystemFunction, Governance, ManageSnsMetadata, Motion,
+        NervousSystemFunction, NervousSystemParameters, Proposal, ProposalData,
+        ProposalDecisionStatus, ProposalRewardStatus, RegisterDappCanisters, Tally, Topic,
+        TransferSnsTreasuryFunds, TreasuryStream, UpgradeSnsControlledCanister,
+        UpgradeSnsToNextVersion, Vote,
     },
 };
 
@@ -65,6 +67,9 @@ pub const MAX_NUMBER_OF_GENERIC_NERVOUS_SYSTEM_FUNCTIONS: usize = 200_000;
 /// RegisterDappCanisters proposal.
 pub const MAX_NUMBER_OF_DAPPS_TO_REGISTER_PER_PROPOSAL: usize = 1_000;
 
+/// The maximum number of treasury streams that can be active at the same time.
+pub const MAX_NUMBER_OF_ACTIVE_TREASURY_STREAMS: usize = 100;
+
 impl Proposal {
     /// Returns whether a proposal is allowed to be submitted when
     /// the heap growth potential is low.
@@ -245,6 +250,21 @@ pub async fn validate_and_render_action(
                 .unwrap_or(DEFAULT_TRANSFER_FEE.get_e8s());
             validate_and_render_transfer_sns_treasury_funds(transfer, sns_transfer_fee_e8s)
         }
+        proposal::Action::CreateTreasuryStream(create) => {
+            let sns_transfer_fee_e8s = governance_proto
+                .parameters
+                .as_ref()
+                .and_then(|params| params.transaction_fee_e8s)
+                .unwrap_or(DEFAULT_TRANSFER_FEE.get_e8s());
+            validate_and_render_create_treasury_stream(
+                create,
+                sns_transfer_fee_e8s,
+                &governance_proto.treasury_streams,
+            )
+        }
+        proposal::Action::CancelTreasuryStream(cancel) => {
+            validate_and_render_cancel_treasury_stream(cancel, &governance_proto.treasury_streams)
+        }
     }
 }
 
@@ -369,6 +389,140 @@ fn validate_and_render_transfer_sns_treasury_funds(
     ))
 }
 
+/// Validates and renders a proposal with action CreateTreasuryStream.
+fn validate_and_render_create_treasury_stream(
+    create: &CreateTreasuryStream,
+    sns_transfer_fee_e8s: u64,
+    existing_streams: &BTreeMap<u64, TreasuryStream>,
+) -> Result<String, String> {
+    let mut defects: Vec<String> = vec![];
+    let from = match create.from_treasury() {
+        TransferFrom::IcpTreasury => "ICP Treasury (NNS Ledger)",
+        TransferFrom::SnsTokenTreasury => "SNS Token Treasury (SNS Ledger)",
+        TransferFrom::Unspecified => {
+            defects.push(
+                "Must specify a treasury from which to pay out the funds (ICP/SNS Token)."
+                    .to_string(),
+            );
+            ""
+        }
+    };
+
+    let minimum_transaction = match create.from_treasury() {
+        TransferFrom::IcpTreasury => NNS_DEFAULT_TRANSFER_FEE.get_e8s(),
+        TransferFrom::SnsTokenTreasury => sns_transfer_fee_e8s,
+        TransferFrom::Unspecified => 0,
+    };
+
+    if create.total_amount_e8s < minimum_transaction {
+        defects.push(format!(
+            "For streams from {}, the minimum total amount is {} e8s",
+            from, minimum_transaction
+        ))
+    }
+
+    let to_principal = if let Some(to_principal) = create.to_principal {
+        if to_principal == PrincipalId::new_anonymous() {
+            defects.push("Principal must not be anonymous.".to_string());
+        }
+        to_principal
+    } else {
+        defects.push("Must specify a principal to pay the funds to.".to_string());
+        PrincipalId::new_anonymous()
+    };
+
+    let to_account = match &create.to_subaccount {
+        None => Account {
+            owner: to_principal.0,
+            subaccount: None,
+        }
+        .to_string(),
+        Some(s) => match bytes_to_subaccount(&s.subaccount[..]) {
+            Ok(s) => Account {
+                owner: to_principal.0,
+                subaccount: Some(s),
+            }
+            .to_string(),
+            Err(e) => {
+                defects.push(e.error_message);
+                "".to_string()
+            }
+        },
+    };
+
+    if create.vesting_period_seconds == 0 {
+        defects.push("The vesting period must be positive.".to_string());
+    }
+
+    if create.cliff_seconds > create.vesting_period_seconds {
+        defects.push("The cliff must not be longer than the vesting period.".to_string());
+    }
+
+    let number_of_active_streams = existing_streams
+        .values()
+        .filter(|stream| stream.is_active())
+        .count();
+    if number_of_active_streams >= MAX_NUMBER_OF_ACTIVE_TREASURY_STREAMS {
+        defects.push(format!(
+            "Reached maximum number of active treasury streams ({})",
+            MAX_NUMBER_OF_ACTIVE_TREASURY_STREAMS
+        ));
+    }
+
+    // Generate final report.
+    if !defects.is_empty() {
+        return Err(format!(
+            "CreateTreasuryStream proposal was invalid for the following reason(s):\n{}",
+            defects.join("\n"),
+        ));
+    }
+
+    Ok(format!(
+        r"# Proposal to create a treasury stream:
+## Source treasury: {}
+## Total amount (e8s): {}
+## Target principal: {}
+## Target account: {}
+## Start timestamp (seconds): {}
+## Cliff (seconds): {}
+## Vesting period (seconds): {}
+## Memo: {}",
+        from,
+        create.total_amount_e8s,
+        to_principal,
+        to_account,
+        create.start_timestamp_seconds,
+        create.cliff_seconds,
+        create.vesting_period_seconds,
+        create.memo.unwrap_or(0)
+    ))
+}
+
+/// Validates and renders a proposal with action CancelTreasuryStream.
+fn validate_and_render_cancel_treasury_stream(
+    cancel: &CancelTreasuryStream,
+    existing_streams: &BTreeMap<u64, TreasuryStream>,
+) -> Result<String, String> {
+    match existing_streams.get(&cancel.stream_id) {
+        None => Err(format!(
+            "There is no treasury stream with id: {}",
+            cancel.stream_id
+        )),
+        Some(stream) if !stream.is_active() => Err(format!(
+            "Treasury stream {} has already been completed or cancelled.",
+            cancel.stream_id
+        )),
+        Some(stream) => Ok(format!(
+            r"# Proposal to cancel a treasury stream:
+
+## Stream:
+
+{:#?}",
+            stream
+        )),
+    }
+}
+
 /// Validates and renders a proposal with action UpgradeSnsControlledCanister.
 fn validate_and_render_upgrade_sns_controlled_canister(
     upgrade: &UpgradeSnsControlledCanister,
@@ -577,6 +731,7 @@ impl TryFrom<&NervousSystemFunction> for ValidGenericNervousSystemFunction {
                 target_method_name,
                 validator_canister_id,
                 validator_method_name,
+                topic,
             })) => {
                 // Validate the target_canister_id field.
                 let target_canister_id =
@@ -600,6 +755,17 @@ impl TryFrom<&NervousSystemFunction> for ValidGenericNervousSystemFunction {
                     defects.push("validator_method_name was empty.".to_string());
                 }
 
+                // Validate the topic field. Functions without a topic are
+                // allowed, but a set topic must be a known one.
+                if let Some(topic) = topic {
+                    match Topic::from_i32(*topic) {
+                        Some(Topic::Unspecified) | None => {
+                            defects.push(format!("topic {} is not a valid topic.", topic));
+                        }
+                        Some(_) => (),
+                    }
+                }
+
                 if !defects.is_empty() {
                     return Err(format!(
                         "ExecuteNervousSystemFunction was invalid for the following reason(s):\n{}",
@@ -1205,7 +1371,7 @@ mod tests {
     use ic_protobuf::types::v1::CanisterInstallMode as CanisterInstallModeProto;
     use ic_test_utilities::types::ids::canister_test_id;
     use lazy_static::lazy_static;
-    use maplit::hashset;
+    use maplit::{btreemap, hashset};
     use std::convert::TryFrom;
 
     pub const FORBIDDEN_CANISTER: CanisterId = CanisterId::ic_00();
@@ -1241,6 +1407,8 @@ mod tests {
             sns_initialization_parameters: "".to_string(),
             is_finalizing_disburse_maturity: None,
             maturity_modulation: None,
+            treasury_streams: Default::default(),
+            is_paying_treasury_streams: None,
         }
     }
 
@@ -1500,6 +1668,7 @@ mod tests {
                     target_method_name: Some("test_method".to_string()),
                     validator_canister_id: Some(CanisterId::from_u64(1).get()),
                     validator_method_name: Some("test_validator_method".to_string()),
+                    topic: None,
                 },
             )),
         };
@@ -1697,6 +1866,7 @@ mod tests {
                     target_method_name: Some("test_method".to_string()),
                     validator_canister_id: Some(CanisterId::from_u64(1).get()),
                     validator_method_name: Some("test_validator_method".to_string()),
+                    topic: None,
                 },
             )),
         };
@@ -1743,6 +1913,7 @@ mod tests {
                         target_method_name: Some("test_method".to_string()),
                         validator_canister_id: Some(CanisterId::from_u64(i as u64).get()),
                         validator_method_name: Some("test_validator_method".to_string()),
+                        topic: None,
                     },
                 )),
             };
@@ -1759,6 +1930,7 @@ mod tests {
                     target_method_name: Some("test_method".to_string()),
                     validator_canister_id: Some(CanisterId::from_u64(u64::MAX).get()),
                     validator_method_name: Some("test_validator_method".to_string()),
+                    topic: None,
                 },
             )),
         };
@@ -2158,6 +2330,7 @@ Version {
                     target_method_name: Some("test_method".to_string()),
                     validator_canister_id: Some(CanisterId::from(1).get()),
                     validator_method_name: Some("test_validator_method".to_string()),
+                    topic: None,
                 },
             )),
         };
@@ -2179,6 +2352,7 @@ Version {
                     target_method_name: Some("test_method".to_string()),
                     validator_canister_id: Some(CanisterId::from(1).get()),
                     validator_method_name: Some("test_validator_method".to_string()),
+                    topic: None,
                 },
             )),
         };
@@ -2198,6 +2372,7 @@ Version {
                     target_method_name: Some("test_method".to_string()),
                     validator_canister_id: Some(CanisterId::ic_00().get()),
                     validator_method_name: Some("test_validator_method".to_string()),
+                    topic: None,
                 },
             )),
         };
@@ -2350,6 +2525,135 @@ Version {
         );
     }
 
+    fn basic_create_treasury_stream() -> CreateTreasuryStream {
+        CreateTreasuryStream {
+            from_treasury: TransferFrom::SnsTokenTreasury.into(),
+            to_principal: Some(basic_principal_id()),
+            to_subaccount: None,
+            total_amount_e8s: 12_000_000,
+            start_timestamp_seconds: 1_000,
+            cliff_seconds: 100,
+            vesting_period_seconds: 1_200,
+            memo: Some(7),
+        }
+    }
+
+    #[test]
+    fn validate_and_render_create_treasury_stream_renders_for_valid_inputs() {
+        assert_eq!(
+            validate_and_render_create_treasury_stream(
+                &basic_create_treasury_stream(),
+                1000,
+                &BTreeMap::new()
+            )
+            .unwrap(),
+            r"# Proposal to create a treasury stream:
+## Source treasury: SNS Token Treasury (SNS Ledger)
+## Total amount (e8s): 12000000
+## Target principal: bg4sm-wzk
+## Target account: bg4sm-wzk
+## Start timestamp (seconds): 1000
+## Cliff (seconds): 100
+## Vesting period (seconds): 1200
+## Memo: 7"
+        );
+    }
+
+    #[test]
+    fn validate_and_render_create_treasury_stream_invalid_inputs() {
+        assert_eq!(
+            validate_and_render_create_treasury_stream(
+                &CreateTreasuryStream {
+                    total_amount_e8s: 999,
+                    cliff_seconds: 1_201,
+                    ..basic_create_treasury_stream()
+                },
+                1000,
+                &BTreeMap::new()
+            )
+            .unwrap_err(),
+            "CreateTreasuryStream proposal was invalid for the following reason(s):\n\
+             For streams from SNS Token Treasury (SNS Ledger), the minimum total amount is 1000 e8s\n\
+             The cliff must not be longer than the vesting period."
+        );
+        assert_eq!(
+            validate_and_render_create_treasury_stream(
+                &CreateTreasuryStream {
+                    vesting_period_seconds: 0,
+                    cliff_seconds: 0,
+                    ..basic_create_treasury_stream()
+                },
+                1000,
+                &BTreeMap::new()
+            )
+            .unwrap_err(),
+            "CreateTreasuryStream proposal was invalid for the following reason(s):\n\
+             The vesting period must be positive."
+        );
+
+        let active_streams = (0..MAX_NUMBER_OF_ACTIVE_TREASURY_STREAMS as u64)
+            .map(|id| {
+                (
+                    id,
+                    TreasuryStream {
+                        id,
+                        parameters: Some(basic_create_treasury_stream()),
+                        ..Default::default()
+                    },
+                )
+            })
+            .collect::<BTreeMap<_, _>>();
+        assert_eq!(
+            validate_and_render_create_treasury_stream(
+                &basic_create_treasury_stream(),
+                1000,
+                &active_streams
+            )
+            .unwrap_err(),
+            "CreateTreasuryStream proposal was invalid for the following reason(s):\n\
+             Reached maximum number of active treasury streams (100)"
+        );
+    }
+
+    #[test]
+    fn validate_and_render_cancel_treasury_stream_requires_an_active_stream() {
+        let active_stream = TreasuryStream {
+            id: 1,
+            parameters: Some(basic_create_treasury_stream()),
+            ..Default::default()
+        };
+        let cancelled_stream = TreasuryStream {
+            id: 2,
+            cancelled_timestamp_seconds: Some(1_500),
+            ..active_stream.clone()
+        };
+        let streams = btreemap! {
+            1 => active_stream,
+            2 => cancelled_stream,
+        };
+
+        assert_is_ok(validate_and_render_cancel_treasury_stream(
+            &CancelTreasuryStream { stream_id: 1 },
+            &streams,
+        ));
+        assert_eq!(
+            validate_and_render_cancel_treasury_stream(
+                &CancelTreasuryStream { stream_id: 2 },
+                &streams,
+            )
+            .unwrap_err(),
+            "Treasury stream 2 has already been completed or cancelled."
+        );
+        assert_eq!(
+            validate_and_render_cancel_treasury_stream(
+                &CancelTreasuryStream { stream_id: 3 },
+                &streams,
+            )
+            .unwrap_err(),
+            "There is no treasury stream with id: 3"
+        );
+    }
+
     #[test]
     fn validate_and_render_register_dapp_canisters_lists_canisters() {
         let canister_ids = (0..10_u8)
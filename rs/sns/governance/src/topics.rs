@@ -0,0 +1,253 @@
+use crate::{
+    governance::NERVOUS_SYSTEM_FUNCTION_DELETION_MARKER,
+    pb::v1::{
+        nervous_system_function::FunctionType, proposal::Action, ListTopicsResponse,
+        NervousSystemFunction, Topic, TopicInfo,
+    },
+    types::native_action_ids,
+};
+use std::collections::BTreeMap;
+use strum::IntoEnumIterator;
+
+impl Topic {
+    /// A short, human readable name of the topic.
+    pub fn name(&self) -> &'static str {
+        match self {
+            Topic::Unspecified => "Unspecified",
+            Topic::DaoCommunitySettings => "DAO community settings",
+            Topic::SnsFrameworkManagement => "SNS framework management",
+            Topic::DappCanisterManagement => "Dapp canister management",
+            Topic::ApplicationBusinessLogic => "Application business logic",
+            Topic::Governance => "Governance",
+            Topic::TreasuryAssetManagement => "Treasury & asset management",
+            Topic::CriticalDappOperations => "Critical dapp operations",
+        }
+    }
+
+    /// A description of which proposals belong to the topic.
+    pub fn description(&self) -> &'static str {
+        match self {
+            Topic::Unspecified => "Proposals without a topic.",
+            Topic::DaoCommunitySettings => {
+                "Proposals to change the DAO's nervous system parameters or metadata."
+            }
+            Topic::SnsFrameworkManagement => "Proposals to upgrade the SNS framework canisters.",
+            Topic::DappCanisterManagement => {
+                "Proposals to upgrade or register the dapp's canisters."
+            }
+            Topic::ApplicationBusinessLogic => {
+                "Generic proposals that implement the dapp's business logic."
+            }
+            Topic::Governance => "Motion proposals that set the general direction of the DAO.",
+            Topic::TreasuryAssetManagement => {
+                "Proposals to move the DAO's treasury funds, at once or as a stream of payments."
+            }
+            Topic::CriticalDappOperations => {
+                "Proposals that can severely affect the dapp, such as adding or removing \
+                 generic functions and deregistering dapp canisters."
+            }
+        }
+    }
+
+    /// Returns the topic of proposals of the native function `function_id`, if
+    /// `function_id` is a native function that has a topic.
+    fn of_native_function(function_id: u64) -> Option<Topic> {
+        match function_id {
+            native_action_ids::MOTION => Some(Topic::Governance),
+            native_action_ids::MANAGE_NERVOUS_SYSTEM_PARAMETERS
+            | native_action_ids::MANAGE_SNS_METADATA => Some(Topic::DaoCommunitySettings),
+            native_action_ids::UPGRADE_SNS_TO_NEXT_VERSION => Some(Topic::SnsFrameworkManagement),
+            native_action_ids::UPGRADE_SNS_CONTROLLER_CANISTER
+            | native_action_ids::REGISTER_DAPP_CANISTERS => Some(Topic::DappCanisterManagement),
+            native_action_ids::TRANSFER_SNS_TREASURY_FUNDS
+            | native_action_ids::CREATE_TREASURY_STREAM
+            | native_action_ids::CANCEL_TREASURY_STREAM => Some(Topic::TreasuryAssetManagement),
+            native_action_ids::ADD_GENERIC_NERVOUS_SYSTEM_FUNCTION
+            | native_action_ids::REMOVE_GENERIC_NERVOUS_SYSTEM_FUNCTION
+            | native_action_ids::DEREGISTER_DAPP_CANISTERS => Some(Topic::CriticalDappOperations),
+            // The unspecified function is the catch-all for following, and
+            // generic functions are executed under their own ids.
+            _ => None,
+        }
+    }
+
+    /// Returns the topic of proposals of the function `function_id`, which is
+    /// either a native function or one of `id_to_nervous_system_functions`.
+    ///
+    /// Returns None for functions without a topic, including unknown and
+    /// deleted functions.
+    pub fn of_function(
+        function_id: u64,
+        id_to_nervous_system_functions: &BTreeMap<u64, NervousSystemFunction>,
+    ) -> Option<Topic> {
+        if let Some(topic) = Self::of_native_function(function_id) {
+            return Some(topic);
+        }
+        id_to_nervous_system_functions
+            .get(&function_id)
+            .and_then(NervousSystemFunction::topic)
+    }
+}
+
+impl NervousSystemFunction {
+    /// Returns the topic of a generic function, if it has a valid one.
+    pub fn topic(&self) -> Option<Topic> {
+        match &self.function_type {
+            Some(FunctionType::GenericNervousSystemFunction(generic)) => generic
+                .topic
+                .and_then(Topic::from_i32)
+                .filter(|topic| *topic != Topic::Unspecified),
+            _ => None,
+        }
+    }
+}
+
+/// Lists all topics, each with the native and generic functions that belong
+/// to it, as well as the generic functions without a topic.
+pub fn list_topics(
+    id_to_nervous_system_functions: &BTreeMap<u64, NervousSystemFunction>,
+) -> ListTopicsResponse {
+    let generic_functions = id_to_nervous_system_functions
+        .values()
+        .filter(|function| *function != &*NERVOUS_SYSTEM_FUNCTION_DELETION_MARKER)
+        .collect::<Vec<_>>();
+
+    let topics = Topic::iter()
+        .filter(|topic| *topic != Topic::Unspecified)
+        .map(|topic| TopicInfo {
+            topic: topic as i32,
+            name: topic.name().to_string(),
+            description: topic.description().to_string(),
+            native_functions: Action::native_functions()
+                .into_iter()
+                .filter(|function| Topic::of_native_function(function.id) == Some(topic))
+                .collect(),
+            custom_functions: generic_functions
+                .iter()
+                .filter(|function| function.topic() == Some(topic))
+                .map(|function| (*function).clone())
+                .collect(),
+        })
+        .collect();
+
+    let uncategorized_functions = generic_functions
+        .into_iter()
+        .filter(|function| function.topic().is_none())
+        .cloned()
+        .collect();
+
+    ListTopicsResponse {
+        topics,
+        uncategorized_functions,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::pb::v1::{nervous_system_function::GenericNervousSystemFunction, Empty};
+
+    fn generic_function(id: u64, topic: Option<Topic>) -> NervousSystemFunction {
+        NervousSystemFunction {
+            id,
+            name: format!("Function {}", id),
+            description: None,
+            function_type: Some(FunctionType::GenericNervousSystemFunction(
+                GenericNervousSystemFunction {
+                    topic: topic.map(|topic| topic as i32),
+                    ..Default::default()
+                },
+            )),
+        }
+    }
+
+    #[test]
+    fn every_native_function_except_unspecified_and_generic_has_a_topic() {
+        for function in Action::native_functions() {
+            let expects_topic = function.id != native_action_ids::UNSPECIFIED
+                && function.id != native_action_ids::EXECUTE_GENERIC_NERVOUS_SYSTEM_FUNCTION;
+            assert_eq!(
+                Topic::of_native_function(function.id).is_some(),
+                expects_topic,
+                "{:?}",
+                function
+            );
+        }
+    }
+
+    #[test]
+    fn generic_functions_use_their_own_topic() {
+        let functions = BTreeMap::from([
+            (
+                1000,
+                generic_function(1000, Some(Topic::CriticalDappOperations)),
+            ),
+            (1001, generic_function(1001, None)),
+            (1002, generic_function(1002, Some(Topic::Unspecified))),
+            (1003, NERVOUS_SYSTEM_FUNCTION_DELETION_MARKER.clone()),
+        ]);
+
+        assert_eq!(
+            Topic::of_function(1000, &functions),
+            Some(Topic::CriticalDappOperations)
+        );
+        assert_eq!(Topic::of_function(1001, &functions), None);
+        assert_eq!(Topic::of_function(1002, &functions), None);
+        assert_eq!(Topic::of_function(1003, &functions), None);
+        assert_eq!(Topic::of_function(1004, &functions), None);
+        assert_eq!(
+            Topic::of_function(native_action_ids::MOTION, &functions),
+            Some(Topic::Governance)
+        );
+    }
+
+    #[test]
+    fn list_topics_groups_functions_by_topic() {
+        let functions = BTreeMap::from([
+            (
+                1000,
+                generic_function(1000, Some(Topic::CriticalDappOperations)),
+            ),
+            (1001, generic_function(1001, None)),
+        ]);
+
+        let ListTopicsResponse {
+            topics,
+            uncategorized_functions,
+        } = list_topics(&functions);
+
+        assert_eq!(topics.len(), 7);
+        assert!(topics
+            .iter()
+            .all(|topic_info| topic_info.topic != Topic::Unspecified as i32));
+
+        let critical = topics
+            .iter()
+            .find(|topic_info| topic_info.topic == Topic::CriticalDappOperations as i32)
+            .unwrap();
+        assert_eq!(critical.custom_functions, vec![functions[&1000].clone()]);
+        assert!(critical
+            .native_functions
+            .iter()
+            .any(|function| function.id == native_action_ids::DEREGISTER_DAPP_CANISTERS));
+
+        let governance = topics
+            .iter()
+            .find(|topic_info| topic_info.topic == Topic::Governance as i32)
+            .unwrap();
+        assert_eq!(
+            governance
+                .native_functions
+                .iter()
+                .map(|function| function.id)
+                .collect::<Vec<_>>(),
+            vec![native_action_ids::MOTION]
+        );
+        assert_eq!(
+            governance.native_functions[0].function_type,
+            Some(FunctionType::NativeNervousSystemFunction(Empty {}))
+        );
+
+        assert_eq!(uncategorized_functions, vec![functions[&1001].clone()]);
+    }
+}
@@ -21,13 +21,13 @@ use crate::{
             nervous_system_function::FunctionType,
             neuron::Followees,
             proposal::Action,
-            ClaimSwapNeuronsError, ClaimSwapNeuronsResponse, ClaimedSwapNeuronStatus,
-            DefaultFollowees, DeregisterDappCanisters, Empty, ExecuteGenericNervousSystemFunction,
-            GovernanceError, ManageNeuronResponse, Motion, NervousSystemFunction,
-            NervousSystemParameters, Neuron, NeuronId, NeuronPermission, NeuronPermissionList,
-            NeuronPermissionType, ProposalId, RegisterDappCanisters, RewardEvent,
-            TransferSnsTreasuryFunds, UpgradeSnsControlledCanister, UpgradeSnsToNextVersion, Vote,
-            VotingRewardsParameters,
+            CancelTreasuryStream, ClaimSwapNeuronsError, ClaimSwapNeuronsResponse,
+            ClaimedSwapNeuronStatus, CreateTreasuryStream, DefaultFollowees,
+            DeregisterDappCanisters, Empty, ExecuteGenericNervousSystemFunction, GovernanceError,
+            ManageNeuronResponse, Motion, NervousSystemFunction, NervousSystemParameters, Neuron,
+            NeuronId, NeuronPermission, NeuronPermissionList, NeuronPermissionType, ProposalId,
+            RegisterDappCanisters, RewardEvent, TransferSnsTreasuryFunds, TreasuryStream,
+            UpgradeSnsControlledCanister, UpgradeSnsToNextVersion, Vote, VotingRewardsParameters,
         },
     },
     proposal::ValidGenericNervousSystemFunction,
@@ -99,6 +99,12 @@ pub mod native_action_ids {
 
     /// DeregisterDappCanisters Action.
     pub const DEREGISTER_DAPP_CANISTERS: u64 = 11;
+
+    /// CreateTreasuryStream Action.
+    pub const CREATE_TREASURY_STREAM: u64 = 12;
+
+    /// CancelTreasuryStream Action.
+    pub const CANCEL_TREASURY_STREAM: u64 = 13;
 }
 
 impl governance::Mode {
@@ -127,6 +133,7 @@ impl governance::Mode {
         use manage_neuron::Command as C;
         let ok = match command {
             C::Follow(_)
+            | C::SetFollowing(_)
             | C::MakeProposal(_)
             | C::RegisterVote(_)
             | C::AddNeuronPermissions(_)
@@ -224,6 +231,15 @@ impl governance::Mode {
                 )
             )),
 
+            Action::CreateTreasuryStream(_) => Err(GovernanceError::new_with_message(
+                ErrorType::PreconditionFailed,
+                format!(
+                    "CreateTreasuryStream proposals are not allowed while \
+                        governance is in PreInitializationSwap mode: {:#?}",
+                    action
+                )
+            )),
+
             _ => Ok(()),
         }
     }
@@ -288,6 +304,7 @@ impl From<&manage_neuron::Command> for neuron_in_flight_command::Command {
             S::AddNeuronPermissions   (x) => D::AddNeuronPermissions   (x),
             S::RemoveNeuronPermissions(x) => D::RemoveNeuronPermissions(x),
             S::StakeMaturity          (_) => D::SyncCommand(SyncCommand{}),
+            S::SetFollowing           (_) => D::SyncCommand(SyncCommand{}),
         }
     }
 }
@@ -1058,6 +1075,24 @@ impl From<Action> for NervousSystemFunction {
                 ),
                 function_type: Some(FunctionType::NativeNervousSystemFunction(Empty {})),
             },
+            Action::CreateTreasuryStream(_) => NervousSystemFunction {
+                id: native_action_ids::CREATE_TREASURY_STREAM,
+                name: "Create treasury stream".to_string(),
+                description: Some(
+                    "Proposal to create a stream of payments that periodically pays out \
+                     vested funds from an SNS Governance controlled treasury account"
+                        .to_string(),
+                ),
+                function_type: Some(FunctionType::NativeNervousSystemFunction(Empty {})),
+            },
+            Action::CancelTreasuryStream(_) => NervousSystemFunction {
+                id: native_action_ids::CANCEL_TREASURY_STREAM,
+                name: "Cancel treasury stream".to_string(),
+                description: Some(
+                    "Proposal to stop all further payouts of a treasury stream.".to_string(),
+                ),
+                function_type: Some(FunctionType::NativeNervousSystemFunction(Empty {})),
+            },
         }
     }
 }
@@ -1126,6 +1161,7 @@ impl manage_neuron::Command {
             manage_neuron::Command::AddNeuronPermissions(_) => "AddNeuronPermissions",
             manage_neuron::Command::RemoveNeuronPermissions(_) => "RemoveNeuronPermissions",
             manage_neuron::Command::StakeMaturity(_) => "StakeMaturity",
+            manage_neuron::Command::SetFollowing(_) => "SetFollowing",
         }
         .to_string()
     }
@@ -1214,6 +1250,14 @@ impl ManageNeuronResponse {
         }
     }
 
+    pub fn set_following_response() -> Self {
+        ManageNeuronResponse {
+            command: Some(manage_neuron_response::Command::SetFollowing(
+                manage_neuron_response::SetFollowingResponse {},
+            )),
+        }
+    }
+
     pub fn make_proposal_response(proposal_id: ProposalId) -> Self {
         let proposal_id = Some(proposal_id);
         ManageNeuronResponse {
@@ -1454,6 +1498,69 @@ impl ExecuteGenericNervousSystemFunction {
     }
 }
 
+impl TreasuryStream {
+    /// Returns the amount, in e8s, that has vested by `now_seconds`.
+    ///
+    /// Nothing has vested before the cliff has passed. Afterwards, the total
+    /// amount vests linearly from the start over the vesting period.
+    pub fn vested_amount_e8s(&self, now_seconds: u64) -> u64 {
+        let parameters = match &self.parameters {
+            Some(parameters) => parameters,
+            None => return 0,
+        };
+        let elapsed_seconds = now_seconds.saturating_sub(parameters.start_timestamp_seconds);
+        if elapsed_seconds < parameters.cliff_seconds {
+            return 0;
+        }
+        if elapsed_seconds >= parameters.vesting_period_seconds {
+            return parameters.total_amount_e8s;
+        }
+        // Cannot overflow, because elapsed_seconds < vesting_period_seconds.
+        (parameters.total_amount_e8s as u128 * elapsed_seconds as u128
+            / parameters.vesting_period_seconds as u128) as u64
+    }
+
+    /// Returns true if the stream has neither been cancelled nor been paid out
+    /// completely.
+    pub fn is_active(&self) -> bool {
+        let total_amount_e8s = self
+            .parameters
+            .as_ref()
+            .map_or(0, |parameters| parameters.total_amount_e8s);
+        self.cancelled_timestamp_seconds.is_none() && self.paid_amount_e8s < total_amount_e8s
+    }
+
+    /// Returns the amount, in e8s, to pay out at `now_seconds`.
+    ///
+    /// Returns None if no payout is due, i.e., if the stream is not active, if
+    /// the last payout was made or the last attempt to make one failed less
+    /// than `payout_interval_seconds` ago, or if nothing has vested since the
+    /// last payout.
+    pub fn amount_due_e8s(&self, now_seconds: u64, payout_interval_seconds: u64) -> Option<u64> {
+        if !self.is_active() {
+            return None;
+        }
+        let last_attempt_timestamp_seconds = self
+            .payouts
+            .last()
+            .map(|payout| payout.timestamp_seconds)
+            .max(self.last_failed_payout_timestamp_seconds);
+        if let Some(last_attempt_timestamp_seconds) = last_attempt_timestamp_seconds {
+            if now_seconds < last_attempt_timestamp_seconds + payout_interval_seconds {
+                return None;
+            }
+        }
+        let amount_due_e8s = self
+            .vested_amount_e8s(now_seconds)
+            .saturating_sub(self.paid_amount_e8s);
+        if amount_due_e8s == 0 {
+            None
+        } else {
+            Some(amount_due_e8s)
+        }
+    }
+}
+
 /// If blob is of length <= 64 (bytes), a copy is returned. Otherwise, a (UTF-8
 /// encoded) human-readable textual summary is returned. This summary is
 /// guaranteed to be of length > 64. Therefore, it is always possible to
@@ -1517,6 +1624,8 @@ impl From<&Action> for u64 {
             Action::DeregisterDappCanisters(_) => native_action_ids::DEREGISTER_DAPP_CANISTERS,
             Action::ManageSnsMetadata(_) => native_action_ids::MANAGE_SNS_METADATA,
             Action::TransferSnsTreasuryFunds(_) => native_action_ids::TRANSFER_SNS_TREASURY_FUNDS,
+            Action::CreateTreasuryStream(_) => native_action_ids::CREATE_TREASURY_STREAM,
+            Action::CancelTreasuryStream(_) => native_action_ids::CANCEL_TREASURY_STREAM,
         }
     }
 }
@@ -1974,6 +2083,18 @@ impl From<DeregisterDappCanisters> for Action {
     }
 }
 
+impl From<CreateTreasuryStream> for Action {
+    fn from(create_treasury_stream: CreateTreasuryStream) -> Action {
+        Action::CreateTreasuryStream(create_treasury_stream)
+    }
+}
+
+impl From<CancelTreasuryStream> for Action {
+    fn from(cancel_treasury_stream: CancelTreasuryStream) -> Action {
+        Action::CancelTreasuryStream(cancel_treasury_stream)
+    }
+}
+
 pub mod test_helpers {
     use super::*;
     use ic_crypto_sha::Sha256;
@@ -2215,7 +2336,8 @@ pub(crate) mod tests {
         governance::Mode::PreInitializationSwap,
         nervous_system_function::{FunctionType, GenericNervousSystemFunction},
         neuron::Followees,
-        ExecuteGenericNervousSystemFunction, Proposal, ProposalData, VotingRewardsParameters,
+        treasury_stream, ExecuteGenericNervousSystemFunction, Proposal, ProposalData,
+        VotingRewardsParameters,
     };
     use ic_base_types::PrincipalId;
     use ic_nervous_system_common_test_keys::{TEST_USER1_PRINCIPAL, TEST_USER2_PRINCIPAL};
@@ -2418,6 +2540,7 @@ pub(crate) mod tests {
             #[rustfmt::skip]
             let allowed_in_pre_initialization_swap = vec! [
                 Command::Follow                  (Default::default()),
+                Command::SetFollowing            (Default::default()),
                 Command::MakeProposal            (Default::default()),
                 Command::RegisterVote            (Default::default()),
                 Command::AddNeuronPermissions    (Default::default()),
@@ -2530,7 +2653,8 @@ pub(crate) mod tests {
 
             let disallowed_in_pre_initialization_swap = vec! [
                 Action::ManageNervousSystemParameters(Default::default()),
-                Action::TransferSnsTreasuryFunds(Default::default())
+                Action::TransferSnsTreasuryFunds(Default::default()),
+                Action::CreateTreasuryStream(Default::default()),
             ];
 
             // Conditionally allow: No targetting SNS canisters.
@@ -2568,6 +2692,7 @@ pub(crate) mod tests {
                         target_method_name: Some("Foo".to_string()),
                         validator_canister_id: Some(*target_canister_id),
                         validator_method_name: Some("Bar".to_string()),
+                        topic: None,
                     })),
                 }
             }
@@ -3162,4 +3287,73 @@ pub(crate) mod tests {
             execute_generic_nervous_system_function_proposal,
         );
     }
+
+    fn treasury_stream(total_amount_e8s: u64) -> TreasuryStream {
+        TreasuryStream {
+            id: 1,
+            parameters: Some(CreateTreasuryStream {
+                total_amount_e8s,
+                start_timestamp_seconds: 1_000,
+                cliff_seconds: 100,
+                vesting_period_seconds: 1_000,
+                ..Default::default()
+            }),
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn test_treasury_stream_vests_linearly_after_the_cliff() {
+        let stream = treasury_stream(10_000);
+
+        assert_eq!(stream.vested_amount_e8s(0), 0);
+        assert_eq!(stream.vested_amount_e8s(1_000), 0);
+        assert_eq!(stream.vested_amount_e8s(1_099), 0);
+        assert_eq!(stream.vested_amount_e8s(1_100), 1_000);
+        assert_eq!(stream.vested_amount_e8s(1_500), 5_000);
+        assert_eq!(stream.vested_amount_e8s(2_000), 10_000);
+        assert_eq!(stream.vested_amount_e8s(u64::MAX), 10_000);
+
+        // Large amounts do not overflow.
+        let stream = treasury_stream(u64::MAX);
+        assert_eq!(stream.vested_amount_e8s(1_500), u64::MAX / 2);
+    }
+
+    #[test]
+    fn test_treasury_stream_amount_due() {
+        let mut stream = treasury_stream(10_000);
+
+        // Nothing is due before the cliff.
+        assert_eq!(stream.amount_due_e8s(1_050, 100), None);
+        assert_eq!(stream.amount_due_e8s(1_500, 100), Some(5_000));
+
+        // After a payout, nothing is due until the payout interval has passed.
+        stream.paid_amount_e8s = 5_000;
+        stream.payouts.push(treasury_stream::Payout {
+            amount_e8s: 5_000,
+            timestamp_seconds: 1_500,
+            block_index: 42,
+        });
+        assert_eq!(stream.amount_due_e8s(1_599, 100), None);
+        assert_eq!(stream.amount_due_e8s(1_600, 100), Some(1_000));
+        assert_eq!(stream.amount_due_e8s(3_000, 100), Some(5_000));
+
+        // After a failed payout, nothing is due until the payout interval has
+        // passed again.
+        stream.last_failed_payout_timestamp_seconds = Some(1_650);
+        assert_eq!(stream.amount_due_e8s(1_700, 100), None);
+        assert_eq!(stream.amount_due_e8s(1_750, 100), Some(2_500));
+
+        // Nothing is due once the stream has been paid out completely.
+        stream.paid_amount_e8s = 10_000;
+        assert!(!stream.is_active());
+        assert_eq!(stream.amount_due_e8s(3_000, 100), None);
+
+        // Nothing is due once the stream has been cancelled.
+        stream.paid_amount_e8s = 5_000;
+        assert!(stream.is_active());
+        stream.cancelled_timestamp_seconds = Some(1_550);
+        assert!(!stream.is_active());
+        assert_eq!(stream.amount_due_e8s(3_000, 100), None);
+    }
 }
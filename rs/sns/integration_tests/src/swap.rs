@@ -1772,6 +1772,7 @@ fn assert_successful_swap_finalizes_correctly(
                     }
                 ),
                 error_message: None,
+                sweep_payment_tokens_result: None,
             }
         );
     }
@@ -2681,6 +2682,8 @@ fn test_upgrade() {
         nns_proposal_id: None,                       // TODO[NNS1-2339]
         neurons_fund_participants: None,             // TODO[NNS1-2339]
         should_auto_finalize: Some(true),
+        additional_payment_tokens: vec![],
+        exchange_rate_canister_id: None,
     })
     .unwrap();
     let canister_id = state_machine
@@ -3086,6 +3089,8 @@ fn test_last_man_less_than_min() {
         nns_proposal_id: None,                       // TODO[NNS1-2339]
         neurons_fund_participants: None,             // TODO[NNS1-2339]
         should_auto_finalize: Some(true),
+        additional_payment_tokens: vec![],
+        exchange_rate_canister_id: None,
     })
     .unwrap();
     state_machine
@@ -18,7 +18,7 @@ use ic_nervous_system_common::{
 use ic_nervous_system_runtime::DfnRuntime;
 use ic_sns_governance::ledger::LedgerCanister;
 use ic_sns_swap::{
-    clients::RealSnsRootClient,
+    clients::{RealExchangeRateCanisterClient, RealSnsRootClient},
     logs::{ERROR, INFO},
     memory::UPGRADES_MEMORY,
     pb::v1::{
@@ -171,11 +171,35 @@ async fn refresh_buyer_tokens_(arg: RefreshBuyerTokensRequest) -> RefreshBuyerTo
     } else {
         PrincipalId::from_str(&arg.buyer).unwrap()
     };
-    let icp_ledger = create_real_icp_ledger(swap().init_or_panic().icp_ledger_or_panic());
-    match swap_mut()
-        .refresh_buyer_token_e8s(p, arg.confirmation_text, id(), &icp_ledger)
-        .await
-    {
+    let result = match arg.payment_token_ledger_canister_id {
+        Some(ledger_canister_id) => {
+            let ledger_canister_id = CanisterId::from_str(&ledger_canister_id).unwrap();
+            let ledger = create_real_icrc1_ledger(ledger_canister_id);
+            match swap().init_or_panic().exchange_rate_canister() {
+                Ok(exchange_rate_canister_id) => {
+                    swap_mut()
+                        .refresh_buyer_payment_token_e8s(
+                            p,
+                            arg.confirmation_text,
+                            id(),
+                            ledger_canister_id,
+                            &ledger,
+                            now_seconds(),
+                            &mut RealExchangeRateCanisterClient::new(exchange_rate_canister_id),
+                        )
+                        .await
+                }
+                Err(error) => Err(error),
+            }
+        }
+        None => {
+            let icp_ledger = create_real_icp_ledger(swap().init_or_panic().icp_ledger_or_panic());
+            swap_mut()
+                .refresh_buyer_token_e8s(p, arg.confirmation_text, id(), &icp_ledger)
+                .await
+        }
+    };
+    match result {
         Ok(r) => r,
         Err(msg) => panic!("{}", msg),
     }
@@ -3,11 +3,17 @@ use crate::pb::v1::{
     SettleCommunityFundParticipation,
 };
 use async_trait::async_trait;
+use dfn_core::api::Funds;
 use ic_base_types::CanisterId;
 use ic_sns_governance::pb::v1::{
     ClaimSwapNeuronsRequest, ClaimSwapNeuronsResponse, ManageNeuron, ManageNeuronResponse, SetMode,
     SetModeResponse,
 };
+use ic_xrc_types::{GetExchangeRateRequest, GetExchangeRateResult};
+
+/// The cycles that the exchange rate canister charges per `get_exchange_rate`
+/// call. Any excess is refunded.
+const EXCHANGE_RATE_CANISTER_CALL_CYCLES: u64 = 1_000_000_000;
 
 #[async_trait]
 pub trait SnsRootClient {
@@ -148,3 +154,39 @@ impl NnsGovernanceClient for RealNnsGovernanceClient {
         .map_err(CanisterCallError::from)
     }
 }
+
+#[async_trait]
+pub trait ExchangeRateCanisterClient {
+    async fn get_exchange_rate(
+        &mut self,
+        request: GetExchangeRateRequest,
+    ) -> Result<GetExchangeRateResult, CanisterCallError>;
+}
+
+pub struct RealExchangeRateCanisterClient {
+    canister_id: CanisterId,
+}
+
+impl RealExchangeRateCanisterClient {
+    pub fn new(canister_id: CanisterId) -> Self {
+        Self { canister_id }
+    }
+}
+
+#[async_trait]
+impl ExchangeRateCanisterClient for RealExchangeRateCanisterClient {
+    async fn get_exchange_rate(
+        &mut self,
+        request: GetExchangeRateRequest,
+    ) -> Result<GetExchangeRateResult, CanisterCallError> {
+        dfn_core::api::call_with_funds(
+            self.canister_id,
+            "get_exchange_rate",
+            dfn_candid::candid_one,
+            request,
+            Funds::new(EXCHANGE_RATE_CANISTER_CALL_CYCLES),
+        )
+        .await
+        .map_err(CanisterCallError::from)
+    }
+}
@@ -1,5 +1,7 @@
 use crate::clients::{NnsGovernanceClient, SnsGovernanceClient, SnsRootClient};
+use ic_base_types::CanisterId;
 use ic_nervous_system_common::ledger::ICRC1Ledger;
+use std::collections::BTreeMap;
 
 pub trait CanisterEnvironment {
     type SnsRootClientT: SnsRootClient;
@@ -22,6 +24,10 @@ pub trait CanisterEnvironment {
 
     fn nns_governance(&self) -> &Self::NnsGovernanceClientT;
     fn nns_governance_mut(&mut self) -> &mut Self::NnsGovernanceClientT;
+
+    /// Returns the ledger of the additional payment token with the given
+    /// ledger canister ID, or None if the environment has no client for it.
+    fn payment_token_ledger(&self, ledger_canister_id: CanisterId) -> Option<&dyn ICRC1Ledger>;
 }
 
 #[derive(Clone, Debug)]
@@ -37,6 +43,10 @@ pub struct CanisterClients<
     pub sns_ledger: SnsLedgerT,
     pub icp_ledger: IcpLedgerT,
     pub nns_governance: NnsGovernanceClientT,
+    /// The ledgers of the additional payment tokens, keyed by their canister
+    /// IDs. Like the SNS ledger, these are ICRC-1 ledgers, so they share its
+    /// client type.
+    pub payment_token_ledgers: BTreeMap<CanisterId, SnsLedgerT>,
 }
 
 impl<
@@ -94,4 +104,10 @@ impl<
     fn nns_governance_mut(&mut self) -> &mut NnsGovernanceClientT {
         &mut self.nns_governance
     }
+
+    fn payment_token_ledger(&self, ledger_canister_id: CanisterId) -> Option<&dyn ICRC1Ledger> {
+        self.payment_token_ledgers
+            .get(&ledger_canister_id)
+            .map(|ledger| ledger as &dyn ICRC1Ledger)
+    }
 }
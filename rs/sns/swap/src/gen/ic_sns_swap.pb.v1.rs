@@ -217,6 +217,12 @@ pub struct Swap {
     /// from being attempted more than once.
     #[prost(bool, optional, tag = "17")]
     pub already_tried_to_auto_finalize: ::core::option::Option<bool>,
+    /// The exchange rates of the additional payment tokens (see
+    /// `Init.additional_payment_tokens`) to ICP. Obtained from the exchange rate
+    /// canister when the swap is due to be committed or aborted, and used to
+    /// value contributions in these tokens in ICP.
+    #[prost(message, repeated, tag = "18")]
+    pub payment_token_exchange_rates: ::prost::alloc::vec::Vec<PaymentTokenExchangeRate>,
 }
 /// The initialisation data of the canister. Always specified on
 /// canister creation, and cannot be modified afterwards.
@@ -349,6 +355,70 @@ pub struct Init {
     /// (regardless of the value of this field).
     #[prost(bool, optional, tag = "28")]
     pub should_auto_finalize: ::core::option::Option<bool>,
+    /// ICRC-1 tokens other than ICP that buyers can contribute to the swap.
+    /// Contributions in these tokens are valued in ICP at the exchange rate
+    /// reported by the exchange rate canister when the token is first contributed.
+    #[prost(message, repeated, tag = "29")]
+    pub additional_payment_tokens: ::prost::alloc::vec::Vec<PaymentToken>,
+    /// The canister ID of the exchange rate canister. Required if
+    /// `additional_payment_tokens` is not empty.
+    #[prost(string, optional, tag = "30")]
+    pub exchange_rate_canister_id: ::core::option::Option<::prost::alloc::string::String>,
+}
+/// An ICRC-1 token, other than ICP, that is accepted as payment in the swap.
+#[derive(
+    candid::CandidType,
+    candid::Deserialize,
+    serde::Serialize,
+    comparable::Comparable,
+    Clone,
+    PartialEq,
+    ::prost::Message,
+)]
+pub struct PaymentToken {
+    /// The canister ID of the token's ICRC-1 ledger.
+    #[prost(string, tag = "1")]
+    pub ledger_canister_id: ::prost::alloc::string::String,
+    /// The symbol of the token's underlying asset as known by the exchange rate
+    /// canister, e.g., "BTC" for ckBTC.
+    #[prost(string, tag = "2")]
+    pub symbol: ::prost::alloc::string::String,
+    /// The transaction fee of the token's ledger.
+    #[prost(uint64, tag = "3")]
+    pub transaction_fee_e8s: u64,
+    /// The minimum amount of the token that a participant must contribute.
+    #[prost(uint64, tag = "4")]
+    pub min_participant_e8s: u64,
+    /// The maximum amount of the token that a participant can contribute.
+    #[prost(uint64, tag = "5")]
+    pub max_participant_e8s: u64,
+    /// The number of decimals of the token, i.e., one whole token is
+    /// 10^decimals of the smallest units in which amounts (the `_e8s` fields)
+    /// are denominated. Must match the `icrc1:decimals` metadata of the ledger.
+    #[prost(uint32, tag = "6")]
+    pub decimals: u32,
+}
+/// The exchange rate of an additional payment token to ICP.
+#[derive(
+    candid::CandidType,
+    candid::Deserialize,
+    serde::Serialize,
+    comparable::Comparable,
+    Clone,
+    PartialEq,
+    ::prost::Message,
+)]
+pub struct PaymentTokenExchangeRate {
+    /// The canister ID of the token's ICRC-1 ledger.
+    #[prost(string, tag = "1")]
+    pub ledger_canister_id: ::prost::alloc::string::String,
+    /// The amount of ICP (in e8s) that one whole token (10^decimals of its
+    /// smallest units) is worth.
+    #[prost(uint64, tag = "2")]
+    pub icp_e8s_per_token: u64,
+    /// When the rate was obtained from the exchange rate canister.
+    #[prost(uint64, tag = "3")]
+    pub timestamp_seconds: u64,
 }
 /// Represents multiple Neurons' Fund participants.
 #[derive(
@@ -564,6 +634,14 @@ pub struct BuyerState {
     /// * ABORTED - owned by the buyer, can be transferred out
     #[prost(message, optional, tag = "5")]
     pub icp: ::core::option::Option<TransferableAmount>,
+    /// The amounts of additional payment tokens (see
+    /// `Init.additional_payment_tokens`) accepted from this buyer, keyed by the
+    /// canister ID of the token's ledger. Tokens are accepted the same way as ICP,
+    /// by calling `refresh_buyer_token_e8s` with the ledger's canister ID, and
+    /// follow the same ownership rules.
+    #[prost(btree_map = "string, message", tag = "6")]
+    pub payment_tokens:
+        ::prost::alloc::collections::BTreeMap<::prost::alloc::string::String, TransferableAmount>,
 }
 /// Information about a direct investor.
 #[derive(
@@ -933,7 +1011,14 @@ pub struct RefreshBuyerTokensRequest {
     /// during SNS initialization.
     #[prost(string, optional, tag = "2")]
     pub confirmation_text: ::core::option::Option<::prost::alloc::string::String>,
+    /// The canister ID of the ledger of an additional payment token (see
+    /// `Init.additional_payment_tokens`) that the buyer has sent funds on. If not
+    /// specified, the funds are ICP.
+    #[prost(string, optional, tag = "3")]
+    pub payment_token_ledger_canister_id: ::core::option::Option<::prost::alloc::string::String>,
 }
+/// If the request was for an additional payment token, the amounts are
+/// denominated in that token.
 #[derive(
     candid::CandidType,
     candid::Deserialize,
@@ -988,6 +1073,8 @@ pub struct FinalizeSwapResponse {
     /// Explains what (if anything) went wrong.
     #[prost(string, optional, tag = "7")]
     pub error_message: ::core::option::Option<::prost::alloc::string::String>,
+    #[prost(message, optional, tag = "8")]
+    pub sweep_payment_tokens_result: ::core::option::Option<SweepResult>,
 }
 #[derive(
     candid::CandidType,
@@ -1,5 +1,8 @@
 use crate::{
-    clients::{NnsGovernanceClient, SnsGovernanceClient, SnsRootClient},
+    clients::{
+        ExchangeRateCanisterClient, NnsGovernanceClient, RealExchangeRateCanisterClient,
+        SnsGovernanceClient, SnsRootClient,
+    },
     environment::CanisterEnvironment,
     logs::{ERROR, INFO},
     memory,
@@ -18,9 +21,10 @@ use crate::{
         ListDirectParticipantsRequest, ListDirectParticipantsResponse, ListSnsNeuronRecipesRequest,
         ListSnsNeuronRecipesResponse, NeuronBasketConstructionParameters, NeuronId as SaleNeuronId,
         NewSaleTicketRequest, NewSaleTicketResponse, OpenRequest, OpenResponse, Participant,
-        RefreshBuyerTokensResponse, RestoreDappControllersResponse, SetDappControllersCallResult,
-        SetModeCallResult, SettleCommunityFundParticipationResult, SnsNeuronRecipe, Swap,
-        SweepResult, Ticket, TransferableAmount,
+        PaymentToken, PaymentTokenExchangeRate, RefreshBuyerTokensResponse,
+        RestoreDappControllersResponse, SetDappControllersCallResult, SetModeCallResult,
+        SettleCommunityFundParticipationResult, SnsNeuronRecipe, Swap, SweepResult, Ticket,
+        TransferableAmount,
     },
     types::{ScheduledVestingEvent, TransferResult},
 };
@@ -41,6 +45,7 @@ use ic_sns_governance::{
     },
 };
 use ic_stable_structures::{storable::Blob, BoundedStorable, GrowFailed, Storable};
+use ic_xrc_types::{Asset, AssetClass, GetExchangeRateRequest};
 use icp_ledger::DEFAULT_TRANSFER_FEE;
 use icrc_ledger_types::icrc1::account::{Account, Subaccount};
 use itertools::{Either, Itertools};
@@ -98,6 +103,19 @@ pub const FIRST_PRINCIPAL_BYTES: [u8; PrincipalId::MAX_LENGTH_IN_BYTES] =
 /// 2. Avoid having the SNS Governance canister hit the instruction limit per message.
 pub const CLAIM_SWAP_NEURONS_BATCH_SIZE: usize = 500;
 
+/// The symbol of ICP at the exchange rate canister, which is the quote asset
+/// of the exchange rates of additional payment tokens.
+const ICP_SYMBOL: &str = "ICP";
+
+/// The number of e8s in a whole token.
+const E8: u64 = 100_000_000;
+
+/// How long after the swap is due the exchange rates of contributed
+/// additional payment tokens are retried. If some rate still could not be
+/// obtained by then, the swap is aborted and all contributions are refunded
+/// (see `payment_token_exchange_rates_timed_out`).
+pub const PAYMENT_TOKEN_EXCHANGE_RATE_TIMEOUT_SECONDS: u64 = 60 * 60 * 24; // 1 day
+
 impl From<(Option<i32>, String)> for CanisterCallError {
     fn from((code, description): (Option<i32>, String)) -> Self {
         Self { code, description }
@@ -249,6 +267,7 @@ impl Swap {
             purge_old_tickets_last_completion_timestamp_nanoseconds: Some(0),
             purge_old_tickets_next_principal: Some(FIRST_PRINCIPAL_BYTES.to_vec()),
             already_tried_to_auto_finalize: Some(false),
+            payment_token_exchange_rates: vec![],
         };
         if init.is_swap_init_for_single_proposal() {
             // Automatically fill out the fields that the (legacy) open request
@@ -321,6 +340,83 @@ impl Swap {
             .fold(0, |sum, v| sum.saturating_add(v))
     }
 
+    /// The value in ICP of `amount_e8s` (in the token's smallest units) of the
+    /// additional payment token with the given ledger canister ID, or None if
+    /// no exchange rate has been recorded for the token.
+    pub fn payment_token_icp_e8s(&self, ledger_canister_id: &str, amount_e8s: u64) -> Option<u64> {
+        let token = self.init.as_ref()?.payment_token(ledger_canister_id)?;
+        let rate = self.payment_token_exchange_rate(ledger_canister_id)?;
+        let icp_e8s =
+            (amount_e8s as u128) * (rate.icp_e8s_per_token as u128) / token.units_per_token();
+        Some(u64::try_from(icp_e8s).unwrap_or(u64::MAX))
+    }
+
+    /// The amount (in the token's smallest units) of the additional payment
+    /// token with the given ledger canister ID that is worth at most
+    /// `icp_e8s`, or None if no exchange rate has been recorded for the token.
+    pub fn payment_token_e8s_worth(&self, ledger_canister_id: &str, icp_e8s: u64) -> Option<u64> {
+        let token = self.init.as_ref()?.payment_token(ledger_canister_id)?;
+        let rate = self.payment_token_exchange_rate(ledger_canister_id)?;
+        let amount_e8s =
+            (icp_e8s as u128) * token.units_per_token() / (rate.icp_e8s_per_token.max(1) as u128);
+        Some(u64::try_from(amount_e8s).unwrap_or(u64::MAX))
+    }
+
+    /// The recorded exchange rate of the additional payment token with the
+    /// given ledger canister ID, if any.
+    fn payment_token_exchange_rate(
+        &self,
+        ledger_canister_id: &str,
+    ) -> Option<&PaymentTokenExchangeRate> {
+        self.payment_token_exchange_rates
+            .iter()
+            .find(|rate| rate.ledger_canister_id == ledger_canister_id)
+    }
+
+    /// The value in ICP of everything a direct investor contributed, i.e.,
+    /// their ICP plus their additional payment tokens valued at the recorded
+    /// exchange rates. Tokens without a recorded rate are not counted.
+    pub fn buyer_icp_equivalent_e8s(&self, buyer_state: &BuyerState) -> u64 {
+        buyer_state
+            .payment_tokens
+            .iter()
+            .filter_map(|(ledger_canister_id, amount)| {
+                self.payment_token_icp_e8s(ledger_canister_id, amount.amount_e8s)
+            })
+            .fold(buyer_state.amount_icp_e8s(), |sum, v| sum.saturating_add(v))
+    }
+
+    /// The value in ICP of everything contributed by direct investors and the
+    /// community fund (see `buyer_icp_equivalent_e8s`).
+    pub fn participant_total_icp_equivalent_e8s(&self) -> u64 {
+        self.buyers
+            .values()
+            .map(|buyer_state| self.buyer_icp_equivalent_e8s(buyer_state))
+            .fold(self.cf_total_icp_e8s(), |sum, v| sum.saturating_add(v))
+    }
+
+    /// The additional payment tokens that have been contributed, but for which
+    /// no exchange rate has been recorded yet.
+    pub fn payment_tokens_without_exchange_rate(&self) -> Vec<&PaymentToken> {
+        let Some(init) = self.init.as_ref() else {
+            return vec![];
+        };
+        init.additional_payment_tokens
+            .iter()
+            .filter(|token| {
+                !self
+                    .payment_token_exchange_rates
+                    .iter()
+                    .any(|rate| rate.ledger_canister_id == token.ledger_canister_id)
+            })
+            .filter(|token| {
+                self.buyers
+                    .values()
+                    .any(|buyer| buyer.amount_payment_token_e8s(&token.ledger_canister_id) > 0)
+            })
+            .collect()
+    }
+
     /// The count of unique CommunityFund Neurons.
     pub fn cf_neuron_count(&self) -> u64 {
         self.cf_participants
@@ -491,9 +587,11 @@ impl Swap {
         // OPEN without transferring tokens being offered to the swap canister.
         assert!(sns_being_offered_e8s > 0);
         // Note that this value has to be > 0 as we have > 0
-        // participants each with > 0 ICP contributed.
-        let total_participant_icp_e8s = NonZeroU64::try_from(self.participant_total_icp_e8s())
-            .expect("participant_total_icp_e8s must be greater than 0");
+        // participants each with > 0 ICP (or ICP worth of additional
+        // payment tokens) contributed.
+        let total_participant_icp_e8s =
+            NonZeroU64::try_from(self.participant_total_icp_equivalent_e8s())
+                .expect("participant_total_icp_equivalent_e8s must be greater than 0");
 
         // Keep track of SNS tokens sold just to check that the amount
         // is correct at the end.
@@ -505,7 +603,7 @@ impl Swap {
         // =====================================================================
         for (buyer_principal, buyer_state) in self.buyers.iter() {
             let amount_sns_e8s = Swap::scale(
-                buyer_state.amount_icp_e8s(),
+                self.buyer_icp_equivalent_e8s(buyer_state),
                 sns_being_offered_e8s,
                 total_participant_icp_e8s,
             );
@@ -592,6 +690,122 @@ impl Swap {
         true
     }
 
+    /// Obtains the exchange rates to ICP of the additional payment tokens that
+    /// have been contributed but have no recorded rate yet, and records them.
+    /// Rates are normally recorded when a token is first contributed (see
+    /// `refresh_buyer_payment_token_e8s`), so this only catches up on
+    /// contributions accepted without one.
+    ///
+    /// Returns an error describing the tokens whose rates could not be
+    /// obtained, in which case this should be retried (until
+    /// `payment_token_exchange_rates_timed_out`).
+    pub async fn update_payment_token_exchange_rates(
+        &mut self,
+        now_seconds: u64,
+        exchange_rate_canister: &mut impl ExchangeRateCanisterClient,
+    ) -> Result<(), String> {
+        let tokens: Vec<PaymentToken> = self
+            .payment_tokens_without_exchange_rate()
+            .into_iter()
+            .cloned()
+            .collect();
+
+        let mut errors = vec![];
+        for token in tokens {
+            if let Err(err) = self
+                .update_payment_token_exchange_rate(&token, now_seconds, exchange_rate_canister)
+                .await
+            {
+                errors.push(err);
+            }
+        }
+
+        if errors.is_empty() {
+            Ok(())
+        } else {
+            Err(format!(
+                "Failed to get exchange rates of payment tokens: {}",
+                errors.join("; ")
+            ))
+        }
+    }
+
+    /// Obtains the exchange rate to ICP of `token` from the exchange rate
+    /// canister and records it, unless a rate has already been recorded.
+    async fn update_payment_token_exchange_rate(
+        &mut self,
+        token: &PaymentToken,
+        now_seconds: u64,
+        exchange_rate_canister: &mut impl ExchangeRateCanisterClient,
+    ) -> Result<(), String> {
+        if self
+            .payment_token_exchange_rate(&token.ledger_canister_id)
+            .is_some()
+        {
+            return Ok(());
+        }
+        let request = GetExchangeRateRequest {
+            base_asset: Asset {
+                class: AssetClass::Cryptocurrency,
+                symbol: token.symbol.clone(),
+            },
+            quote_asset: Asset {
+                class: AssetClass::Cryptocurrency,
+                symbol: ICP_SYMBOL.to_string(),
+            },
+            timestamp: None,
+        };
+        let exchange_rate = match exchange_rate_canister.get_exchange_rate(request).await {
+            Ok(Ok(exchange_rate)) => exchange_rate,
+            Ok(Err(err)) => return Err(format!("{}/{}: {:?}", token.symbol, ICP_SYMBOL, err)),
+            Err(err) => return Err(format!("{}/{}: {:?}", token.symbol, ICP_SYMBOL, err)),
+        };
+
+        // The rate is the amount of ICP that one whole token is worth,
+        // scaled by 10^decimals.
+        let icp_e8s_per_token = 10_u128
+            .checked_pow(exchange_rate.metadata.decimals)
+            .and_then(|scale| {
+                (exchange_rate.rate as u128)
+                    .checked_mul(E8 as u128)
+                    .map(|r| r / scale)
+            })
+            .and_then(|r| u64::try_from(r).ok());
+        let icp_e8s_per_token = match icp_e8s_per_token {
+            Some(icp_e8s_per_token) if icp_e8s_per_token > 0 => icp_e8s_per_token,
+            _ => {
+                return Err(format!(
+                    "{}/{}: unusable rate {} with {} decimals",
+                    token.symbol, ICP_SYMBOL, exchange_rate.rate, exchange_rate.metadata.decimals
+                ));
+            }
+        };
+
+        // Recheck the state after the async call, as the rate could have
+        // been recorded by a concurrent call in the meantime.
+        if self.lifecycle() != Lifecycle::Open
+            || self
+                .payment_token_exchange_rate(&token.ledger_canister_id)
+                .is_some()
+        {
+            return Ok(());
+        }
+        log!(
+            INFO,
+            "Recorded exchange rate of payment token {} ({}): {} ICP e8s per token",
+            token.ledger_canister_id,
+            token.symbol,
+            icp_e8s_per_token
+        );
+        self.payment_token_exchange_rates
+            .push(PaymentTokenExchangeRate {
+                ledger_canister_id: token.ledger_canister_id.clone(),
+                icp_e8s_per_token,
+                timestamp_seconds: now_seconds,
+            });
+        Ok(())
+    }
+
     /// Retrieves the balance of 'this' canister on the SNS token
     /// ledger.
     ///
@@ -650,6 +864,28 @@ impl Swap {
                 log!(INFO, "Swap opened at timestamp {}", heartbeat_start_seconds);
             }
         }
+        // Obtain the exchange rates of additional payment tokens, which are
+        // needed before the swap can be committed or aborted.
+        else if self.payment_token_exchange_rates_pending(heartbeat_start_seconds) {
+            let exchange_rate_canister = self
+                .init
+                .as_ref()
+                .ok_or_else(|| "couldn't get `init`".to_string())
+                .and_then(|init| init.exchange_rate_canister());
+            let result = match exchange_rate_canister {
+                Ok(canister_id) => {
+                    self.update_payment_token_exchange_rates(
+                        heartbeat_start_seconds,
+                        &mut RealExchangeRateCanisterClient::new(canister_id),
+                    )
+                    .await
+                }
+                Err(error) => Err(error),
+            };
+            if let Err(error) = result {
+                log!(ERROR, "{}", error);
+            }
+        }
         // Auto-commit the swap
         else if self.can_commit(heartbeat_start_seconds) {
             if self.try_commit(heartbeat_start_seconds) {
@@ -763,23 +999,7 @@ impl Swap {
             return Err("The ICP target for this token swap has already been reached.".to_string());
         }
 
-        match (
-            self.init_or_panic().confirmation_text.as_ref(),
-            confirmation_text,
-        ) {
-            (Some(expected_text), Some(text)) => {
-                if &text != expected_text {
-                    return Err("The value of `confirmation_text` does not match the value provided in SNS init payload.".to_string());
-                }
-            }
-            (Some(_), None) => {
-                return Err("No value provided for `confirmation_text`.".to_string());
-            }
-            (None, Some(_)) => {
-                return Err("Found a value for `confirmation_text`, expected none.".to_string());
-            }
-            (None, None) => {}
-        }
+        self.check_confirmation_text(confirmation_text)?;
 
         // Look for the token balance of the specified principal's subaccount on 'this' canister.
         let account = Account {
@@ -802,8 +1022,9 @@ impl Swap {
             );
         }
 
-        // Recheck total amount of ICP bought after async call.
-        let participant_total_icp_e8s = self.participant_total_icp_e8s();
+        // Recheck total amount of ICP bought after async call. Contributions
+        // of additional payment tokens count towards the target as well.
+        let participant_total_icp_e8s = self.participant_total_icp_equivalent_e8s();
         let params = &self.params.as_ref().expect("Expected params to be set"); // Safe as lifecycle is OPEN.
         let max_icp_e8s = params.max_icp_e8s;
         if participant_total_icp_e8s >= max_icp_e8s {
@@ -829,7 +1050,17 @@ impl Swap {
                 e8s, params.min_participant_icp_e8s
             ));
         }
-        let max_participant_icp_e8s = params.max_participant_icp_e8s;
+        // Contributions of additional payment tokens count towards the limit
+        // per participant as well.
+        let buyer_payment_tokens_icp_e8s = self
+            .buyers
+            .get(&buyer.to_string())
+            .map_or(0, |buyer_state| {
+                self.buyer_icp_equivalent_e8s(buyer_state) - buyer_state.amount_icp_e8s()
+            });
+        let max_participant_icp_e8s = params
+            .max_participant_icp_e8s
+            .saturating_sub(buyer_payment_tokens_icp_e8s);
 
         let old_amount_icp_e8s = self
             .buyers
@@ -915,6 +1146,7 @@ impl Swap {
                     amount_e8s: 0,
                     ..TransferableAmount::default()
                 }),
+                payment_tokens: BTreeMap::new(),
             });
         buyer_state.set_amount_icp_e8s(new_balance_e8s);
         log!(
@@ -934,6 +1166,208 @@ impl Swap {
         })
     }
 
+    /// In state Open, this method can be called to refresh the amount of an
+    /// additional payment token (see `Init.additional_payment_tokens`) a buyer
+    /// has contributed from the token's ledger canister.
+    ///
+    /// This works like `refresh_buyer_token_e8s`, except that tickets do not
+    /// apply, and that the amount is limited by the token's
+    /// `min_participant_e8s` and `max_participant_e8s` in addition to the ICP
+    /// participation limits. To value the contribution in ICP, the exchange
+    /// rate of the token is obtained from the exchange rate canister when the
+    /// token is first contributed; no contribution is accepted without it.
+    #[allow(clippy::too_many_arguments)]
+    pub async fn refresh_buyer_payment_token_e8s(
+        &mut self,
+        buyer: PrincipalId,
+        confirmation_text: Option<String>,
+        this_canister: CanisterId,
+        ledger_canister_id: CanisterId,
+        ledger: &dyn ICRC1Ledger,
+        now_seconds: u64,
+        exchange_rate_canister: &mut impl ExchangeRateCanisterClient,
+    ) -> Result<RefreshBuyerTokensResponse, String> {
+        if self.lifecycle() != Lifecycle::Open {
+            return Err(
+                "The token amount can only be refreshed when the canister is in the OPEN state"
+                    .to_string(),
+            );
+        }
+        let ledger_canister_id = ledger_canister_id.to_string();
+        let token = self
+            .init_or_panic()
+            .payment_token(&ledger_canister_id)
+            .cloned()
+            .ok_or_else(|| {
+                format!(
+                    "The ledger {} is not the ledger of a payment token accepted by this swap.",
+                    ledger_canister_id
+                )
+            })?;
+        if self.icp_target_reached() {
+            return Err("The ICP target for this token swap has already been reached.".to_string());
+        }
+        self.check_confirmation_text(confirmation_text)?;
+
+        // The exchange rate is needed to value the contribution against the
+        // ICP participation limits, so it must be known before accepting it.
+        self.update_payment_token_exchange_rate(&token, now_seconds, exchange_rate_canister)
+            .await
+            .map_err(|err| {
+                format!(
+                    "Failed to get the exchange rate of the payment token: {}",
+                    err
+                )
+            })?;
+
+        // Look for the token balance of the specified principal's subaccount on 'this' canister.
+        let account = Account {
+            owner: this_canister.get().0,
+            subaccount: Some(principal_to_subaccount(&buyer)),
+        };
+        let e8s = ledger
+            .account_balance(account)
+            .await
+            .map_err(|x| x.to_string())
+            .map(|x| x.get_e8s())?;
+
+        // Recheck lifecycle state after async call because the swap
+        // could have been closed (committed or aborted) while the
+        // call to get the account balance was outstanding.
+        if self.lifecycle() != Lifecycle::Open {
+            return Err(
+                "The token amount can only be refreshed when the canister is in the OPEN state"
+                    .to_string(),
+            );
+        }
+
+        if e8s < token.min_participant_e8s {
+            return Err(format!(
+                "Amount transferred: {}; minimum required to participate: {}",
+                e8s, token.min_participant_e8s
+            ));
+        }
+
+        let old_amount_e8s = self.buyers.get(&buyer.to_string()).map_or(0, |buyer| {
+            buyer.amount_payment_token_e8s(&ledger_canister_id)
+        });
+        if old_amount_e8s >= e8s {
+            // Already up-to-date. Strict inequality can happen if messages are re-ordered.
+            return Ok(RefreshBuyerTokensResponse {
+                icp_accepted_participation_e8s: old_amount_e8s,
+                icp_ledger_account_balance_e8s: e8s,
+            });
+        }
+        if e8s > token.max_participant_e8s {
+            log!(
+                INFO,
+                "Participant {} contributed {} e8s of payment token {} - the limit per participant is {}",
+                buyer,
+                e8s,
+                ledger_canister_id,
+                token.max_participant_e8s
+            );
+        }
+
+        // Limit the contribution such that, valued in ICP, neither the target
+        // (max) ICP of the swap nor the maximum per participant is exceeded.
+        // Both limits are computed without the buyer's current contribution in
+        // this token, which the new balance replaces.
+        let params = self.params.as_ref().expect("Expected params to be set"); // Safe as lifecycle is OPEN.
+        let old_amount_icp_e8s = self
+            .payment_token_icp_e8s(&ledger_canister_id, old_amount_e8s)
+            .unwrap_or(0);
+        let others_icp_e8s = self
+            .participant_total_icp_equivalent_e8s()
+            .saturating_sub(old_amount_icp_e8s);
+        let buyer_other_icp_e8s = self
+            .buyers
+            .get(&buyer.to_string())
+            .map_or(0, |buyer_state| self.buyer_icp_equivalent_e8s(buyer_state))
+            .saturating_sub(old_amount_icp_e8s);
+        let max_icp_e8s = std::cmp::min(
+            params.max_icp_e8s.saturating_sub(others_icp_e8s),
+            params
+                .max_participant_icp_e8s
+                .saturating_sub(buyer_other_icp_e8s),
+        );
+        let max_amount_e8s = self
+            .payment_token_e8s_worth(&ledger_canister_id, max_icp_e8s)
+            .ok_or_else(|| {
+                format!(
+                    "No exchange rate has been recorded for the payment token {}.",
+                    ledger_canister_id
+                )
+            })?;
+        let new_balance_e8s = e8s.min(token.max_participant_e8s).min(max_amount_e8s);
+        if new_balance_e8s <= old_amount_e8s {
+            return Ok(RefreshBuyerTokensResponse {
+                icp_accepted_participation_e8s: old_amount_e8s,
+                icp_ledger_account_balance_e8s: e8s,
+            });
+        }
+        if new_balance_e8s < token.min_participant_e8s {
+            return Err(format!(
+                "New balance: {}; minimum required to participate: {}",
+                new_balance_e8s, token.min_participant_e8s
+            ));
+        }
+
+        // Append to a new buyer to the BUYERS_LIST_INDEX
+        let is_preexisting_buyer = self.buyers.contains_key(&buyer.to_string());
+        if !is_preexisting_buyer {
+            insert_buyer_into_buyers_list_index(buyer)
+                .map_err(|grow_failed| {
+                    format!(
+                        "Failed to add buyer {} to state, the canister's stable memory could not grow: {}",
+                        buyer, grow_failed
+                    )
+                })?;
+        }
+
+        let buyer_state = self
+            .buyers
+            .entry(buyer.to_string())
+            .or_insert_with(|| BuyerState::new(0));
+        buyer_state.set_amount_payment_token_e8s(&ledger_canister_id, new_balance_e8s);
+        log!(
+            INFO,
+            "Refresh_buyer_tokens for buyer {} and payment token {}; old e8s {}; new e8s {}",
+            buyer,
+            ledger_canister_id,
+            old_amount_e8s,
+            new_balance_e8s
+        );
+
+        Ok(RefreshBuyerTokensResponse {
+            icp_accepted_participation_e8s: new_balance_e8s,
+            icp_ledger_account_balance_e8s: e8s,
+        })
+    }
+
+    /// Checks that `confirmation_text` matches the swap confirmation text set
+    /// during SNS initialization, if any.
+    fn check_confirmation_text(&self, confirmation_text: Option<String>) -> Result<(), String> {
+        match (
+            self.init_or_panic().confirmation_text.as_ref(),
+            confirmation_text,
+        ) {
+            (Some(expected_text), Some(text)) => {
+                if &text != expected_text {
+                    return Err("The value of `confirmation_text` does not match the value provided in SNS init payload.".to_string());
+                }
+            }
+            (Some(_), None) => {
+                return Err("No value provided for `confirmation_text`.".to_string());
+            }
+            (None, Some(_)) => {
+                return Err("Found a value for `confirmation_text`, expected none.".to_string());
+            }
+            (None, None) => {}
+        }
+        Ok(())
+    }
+
     /*
 
     Transfers OUT.
@@ -1181,6 +1615,20 @@ impl Swap {
             return finalize_swap_response;
         }
 
+        // Transfer the additional payment tokens (if any) from the Swap canister.
+        let accepts_payment_tokens = self
+            .init
+            .as_ref()
+            .map_or(false, |init| !init.additional_payment_tokens.is_empty());
+        if accepts_payment_tokens {
+            finalize_swap_response.set_sweep_payment_tokens_result(
+                self.sweep_payment_tokens(now_fn, environment).await,
+            );
+            if finalize_swap_response.has_error_message() {
+                return finalize_swap_response;
+            }
+        }
+
         // Settle the CommunityFund's participation in the Swap (if any).
         finalize_swap_response.set_settle_community_fund_participation_result(
             self.settle_community_fund_participation(environment.nns_governance_mut())
@@ -1782,6 +2230,13 @@ impl Swap {
                 }
             };
 
+            // Buyers that only contributed additional payment tokens have no
+            // ICP to transfer.
+            if buyer_state.amount_icp_e8s() == 0 && !buyer_state.payment_tokens.is_empty() {
+                sweep_result.skipped += 1;
+                continue;
+            }
+
             let subaccount = principal_to_subaccount(&principal);
             let dst = if lifecycle == Lifecycle::Committed {
                 // This Account should be given a name, such as SNS ICP Treasury...
@@ -1856,6 +2311,119 @@ impl Swap {
         sweep_result
     }
 
+    /// Transfers the additional payment tokens (see
+    /// `Init.additional_payment_tokens`) from buyer's subaccounts to the SNS
+    /// governance canister if COMMITTED or back to the buyer if ABORTED.
+    ///
+    /// Returns the same kind of values as `sweep_icp`, counting each
+    /// (buyer, token) pair separately.
+    ///
+    /// Pre-conditions:
+    /// - The Swap canister's `Lifecycle` is either ABORTED or COMMITTED
+    pub async fn sweep_payment_tokens(
+        &mut self,
+        now_fn: fn(bool) -> u64,
+        environment: &impl CanisterEnvironment,
+    ) -> SweepResult {
+        let lifecycle: Lifecycle = self.lifecycle();
+
+        let init = match self.init_and_validate() {
+            Ok(init) => init,
+            Err(error_message) => {
+                log!(
+                    ERROR,
+                    "Halting sweep_payment_tokens(). State is missing or corrupted: {:?}",
+                    error_message
+                );
+                return SweepResult::new_with_global_failures(1);
+            }
+        };
+
+        // The following methods are safe to call since we validated Init in the above block
+        let sns_governance = init.sns_governance_or_panic();
+        let tokens = init.additional_payment_tokens.clone();
+
+        let mut sweep_result = SweepResult::default();
+
+        for (principal_str, buyer_state) in self.buyers.iter_mut() {
+            // principal_str should always be parseable as a PrincipalId as that is enforced
+            // in `refresh_buyer_tokens`. In the case of a bug due to programmer error, increment
+            // the invalid field. This will require a manual intervention via an upgrade to correct
+            let principal = match string_to_principal(principal_str) {
+                Some(p) => p,
+                None => {
+                    sweep_result.invalid += 1;
+                    continue;
+                }
+            };
+
+            let subaccount = principal_to_subaccount(&principal);
+            let dst = if lifecycle == Lifecycle::Committed {
+                Account {
+                    owner: sns_governance.get().0,
+                    subaccount: None,
+                }
+            } else {
+                Account {
+                    owner: principal.0,
+                    subaccount: None,
+                }
+            };
+
+            for (ledger_canister_id, transferable_amount) in buyer_state.payment_tokens.iter_mut() {
+                // Payment tokens are only accepted from ledgers listed in Init
+                // (see `refresh_buyer_payment_token_e8s`). In the case of a bug
+                // due to programmer error, increment the invalid field.
+                let token = tokens
+                    .iter()
+                    .find(|token| &token.ledger_canister_id == ledger_canister_id);
+                let ledger = CanisterId::from_str(ledger_canister_id)
+                    .ok()
+                    .and_then(|canister_id| environment.payment_token_ledger(canister_id));
+                let (token, ledger) = match (token, ledger) {
+                    (Some(token), Some(ledger)) => (token, ledger),
+                    _ => {
+                        log!(
+                            ERROR,
+                            "PrincipalId {} contributed payment token {} which is unknown",
+                            principal,
+                            ledger_canister_id
+                        );
+                        sweep_result.invalid += 1;
+                        continue;
+                    }
+                };
+                let fee = Tokens::from_e8s(token.transaction_fee_e8s);
+
+                let result = transferable_amount
+                    .transfer_helper(now_fn, fee, Some(subaccount), &dst, ledger)
+                    .await;
+                match result {
+                    // AmountToSmall should never happen as the amount contributed is checked in
+                    // `refresh_buyer_payment_token_e8s`. In the case of a bug due to programmer
+                    // error, increment the invalid field.
+                    TransferResult::AmountTooSmall => {
+                        sweep_result.invalid += 1;
+                    }
+                    TransferResult::AlreadyStarted => {
+                        sweep_result.skipped += 1;
+                    }
+                    TransferResult::Success(_) => {
+                        transferable_amount.transfer_fee_paid_e8s = Some(fee.get_e8s());
+                        transferable_amount.amount_transferred_e8s =
+                            Some(transferable_amount.amount_e8s - fee.get_e8s());
+                        sweep_result.success += 1;
+                    }
+                    TransferResult::Failure(_) => {
+                        sweep_result.failure += 1;
+                    }
+                }
+            }
+        }
+
+        sweep_result
+    }
+
     /// In state COMMITTED. Transfers SNS tokens from the swap
     /// canister to each buyer.
     ///
@@ -2084,7 +2652,7 @@ impl Swap {
             .get(&caller.to_string())
             .map_or(0, |buyer_state| buyer_state.amount_icp_e8s());
         let amount_icp_e8s = match compute_participation_increment(
-            self.participant_total_icp_e8s(),
+            self.participant_total_icp_equivalent_e8s(),
             params.max_icp_e8s,
             params.min_participant_icp_e8s,
             params.max_participant_icp_e8s,
@@ -2330,22 +2898,50 @@ impl Swap {
             {
                 false
             } else {
-                self.participant_total_icp_e8s() >= params.min_icp_e8s
+                self.participant_total_icp_equivalent_e8s() >= params.min_icp_e8s
             }
         } else {
             false
         }
     }
 
-    /// The total number of ICP contributed by all buyers is at least
-    /// the target (max) ICP of the swap.
+    /// The total value in ICP contributed by all buyers (see
+    /// `participant_total_icp_equivalent_e8s`) is at least the target (max)
+    /// ICP of the swap.
     pub fn icp_target_reached(&self) -> bool {
         if let Some(params) = &self.params {
-            return self.participant_total_icp_e8s() >= params.max_icp_e8s;
+            return self.participant_total_icp_equivalent_e8s() >= params.max_icp_e8s;
         }
         false
     }
 
+    /// Returns true if the swap is due to be committed or aborted, but the
+    /// exchange rates of some contributed additional payment tokens still need
+    /// to be obtained to value the participation.
+    pub fn payment_token_exchange_rates_pending(&self, now_seconds: u64) -> bool {
+        self.lifecycle() == Lifecycle::Open
+            && (self.swap_due(now_seconds) || self.icp_target_reached())
+            && !self.payment_tokens_without_exchange_rate().is_empty()
+            && !self.payment_token_exchange_rates_timed_out(now_seconds)
+    }
+
+    /// Returns true if the exchange rates of some contributed additional
+    /// payment tokens could still not be obtained
+    /// `PAYMENT_TOKEN_EXCHANGE_RATE_TIMEOUT_SECONDS` after the swap was due.
+    /// As the participation cannot be valued, the swap can then only be
+    /// aborted.
+    pub fn payment_token_exchange_rates_timed_out(&self, now_seconds: u64) -> bool {
+        let Some(params) = &self.params else {
+            return false;
+        };
+        self.lifecycle() == Lifecycle::Open
+            && now_seconds
+                >= params
+                    .swap_due_timestamp_seconds
+                    .saturating_add(PAYMENT_TOKEN_EXCHANGE_RATE_TIMEOUT_SECONDS)
+            && !self.payment_tokens_without_exchange_rate().is_empty()
+    }
+
     /// Returns true if the swap can be opened at the specified
     /// timestamp, and false otherwise.
     pub fn can_open(&self, now_seconds: u64) -> bool {
@@ -2361,8 +2957,13 @@ impl Swap {
         if self.lifecycle() != Lifecycle::Open {
             return false;
         }
+        // Contributions of payment tokens without an exchange rate cannot be
+        // valued, so they must not be committed.
+        if !self.payment_tokens_without_exchange_rate().is_empty() {
+            return false;
+        }
         // Possible optimization: both 'sufficient_participation' and
-        // 'icp_target_reached' compute 'participant_total_icp_e8s', and
+        // 'icp_target_reached' compute 'participant_total_icp_equivalent_e8s', and
         // this computation could be shared (or cached).
         if !self.sufficient_participation() {
             return false;
@@ -2378,6 +2979,12 @@ impl Swap {
         if self.lifecycle() != Lifecycle::Open {
             return false;
         }
+        if self.payment_token_exchange_rates_pending(now_seconds) {
+            return false;
+        }
+        if self.payment_token_exchange_rates_timed_out(now_seconds) {
+            return true;
+        }
 
         // if the swap is due or the ICP target is reached without sufficient participation, we can abort
         (self.swap_due(now_seconds) || self.icp_target_reached())
@@ -3050,6 +3657,8 @@ mod tests {
             nns_proposal_id: None,                       // TODO[NNS1-2339]
             neurons_fund_participants: None,             // TODO[NNS1-2339]
             should_auto_finalize: Some(true),
+            additional_payment_tokens: vec![],
+            exchange_rate_canister_id: None,
         });
     }
 
@@ -3648,6 +4257,8 @@ mod tests {
                     nns_proposal_id: None, // TODO[NNS1-2339]
                     neurons_fund_participants: None, // TODO[NNS1-2339]
                     should_auto_finalize: Some(true),
+                    additional_payment_tokens: vec![],
+                    exchange_rate_canister_id: None,
                 }),
                 params: Some(Params {
                     min_participants: 1,
@@ -3673,6 +4284,7 @@ mod tests {
                 purge_old_tickets_last_completion_timestamp_nanoseconds: Some(0),
                 purge_old_tickets_next_principal: Some(FIRST_PRINCIPAL_BYTES.to_vec()),
                 already_tried_to_auto_finalize: Some(false),
+                payment_token_exchange_rates: vec![],
             };
             let mut ticket_ids = HashSet::new();
             for pid in pids {
@@ -3723,6 +4335,7 @@ mod tests {
                     amount_e8s: 1,
                     ..TransferableAmount::default()
                 }),
+                payment_tokens: Default::default(),
             },
         };
         let mut swap = Swap {
@@ -3756,6 +4369,7 @@ mod tests {
                     amount_e8s: 10,
                     ..TransferableAmount::default()
                 }),
+                payment_tokens: Default::default(),
             },
         };
         let mut swap = Swap {
@@ -3791,6 +4405,7 @@ mod tests {
                     amount_e8s: 20,
                     ..TransferableAmount::default()
                 }),
+                payment_tokens: Default::default(),
             },
         };
         let mut swap = Swap {
@@ -3880,6 +4495,7 @@ mod tests {
                     amount_e8s: 20,
                     ..TransferableAmount::default()
                 }),
+                payment_tokens: Default::default(),
             },
         };
         let mut swap = Swap {
@@ -3952,6 +4568,8 @@ mod tests {
                 nns_proposal_id: None,                       // TODO[NNS1-2339]
                 neurons_fund_participants: None,             // TODO[NNS1-2339]
                 should_auto_finalize: Some(true),
+                additional_payment_tokens: vec![],
+                exchange_rate_canister_id: None,
             }),
             params: Some(Params {
                 min_participants: 0,
@@ -3977,6 +4595,7 @@ mod tests {
             purge_old_tickets_last_completion_timestamp_nanoseconds: Some(0),
             purge_old_tickets_next_principal: Some(FIRST_PRINCIPAL_BYTES.to_vec()),
             already_tried_to_auto_finalize: Some(false),
+            payment_token_exchange_rates: vec![],
         };
 
         let try_purge_old_tickets = |sale: &mut Swap, time: u64| loop {
@@ -9,7 +9,7 @@ use crate::{
         sns_neuron_recipe::{ClaimedStatus, Investor},
         BuyerState, CfInvestment, CfNeuron, CfParticipant, DirectInvestment,
         ErrorRefundIcpResponse, FinalizeSwapResponse, Init, Lifecycle, NeuronId as SaleNeuronId,
-        OpenRequest, Params, SetDappControllersCallResult, SetModeCallResult,
+        OpenRequest, Params, PaymentToken, SetDappControllersCallResult, SetModeCallResult,
         SettleCommunityFundParticipationResult, SnsNeuronRecipe, SweepResult, TransferableAmount,
     },
     swap::is_valid_principal,
@@ -21,7 +21,10 @@ use ic_nervous_system_common::{ledger::ICRC1Ledger, SECONDS_PER_DAY};
 use ic_sns_governance::pb::v1::{ClaimedSwapNeuronStatus, NeuronId};
 use icrc_ledger_types::icrc1::account::{Account, Subaccount};
 use maplit::btreemap;
-use std::{collections::BTreeMap, str::FromStr};
+use std::{
+    collections::{BTreeMap, BTreeSet},
+    str::FromStr,
+};
 
 pub fn validate_principal(p: &str) -> Result<(), String> {
     let _ = PrincipalId::from_str(p).map_err(|x| {
@@ -187,6 +190,22 @@ impl Init {
             .expect("could not get canister id of icp ledger")
     }
 
+    pub fn exchange_rate_canister(&self) -> Result<CanisterId, String> {
+        let exchange_rate_canister_id = self
+            .exchange_rate_canister_id
+            .as_ref()
+            .ok_or_else(|| "exchange_rate_canister_id is not set".to_string())?;
+        principal_string_to_canister_id(exchange_rate_canister_id)
+    }
+
+    /// Returns the additional payment token whose ledger has the given
+    /// canister ID, if the swap accepts it.
+    pub fn payment_token(&self, ledger_canister_id: &str) -> Option<&PaymentToken> {
+        self.additional_payment_tokens
+            .iter()
+            .find(|token| token.ledger_canister_id == ledger_canister_id)
+    }
+
     pub fn environment(&self) -> Result<impl CanisterEnvironment, String> {
         use ic_nervous_system_common::ledger::IcpLedgerCanister;
         use ic_sns_governance::ledger::LedgerCanister;
@@ -227,12 +246,21 @@ impl Init {
             RealNnsGovernanceClient::new(nns_governance_canister_id)
         };
 
+        let mut payment_token_ledgers = BTreeMap::new();
+        for token in &self.additional_payment_tokens {
+            let ledger_canister_id = principal_string_to_canister_id(&token.ledger_canister_id)
+                .map_err(|s| format!("unable to get payment token ledger canister id: {s}"))?;
+            payment_token_ledgers
+                .insert(ledger_canister_id, LedgerCanister::new(ledger_canister_id));
+        }
+
         Ok(CanisterClients {
             sns_root,
             sns_governance,
             sns_ledger,
             icp_ledger,
             nns_governance,
+            payment_token_ledgers,
         })
     }
 
@@ -504,6 +532,77 @@ impl Init {
             return Err("should_auto_finalize is required.".to_string());
         }
 
+        if !self.additional_payment_tokens.is_empty() {
+            match &self.exchange_rate_canister_id {
+                Some(exchange_rate_canister_id) => validate_canister_id(exchange_rate_canister_id)?,
+                None => return Err(
+                    "exchange_rate_canister_id is required if there are additional payment tokens."
+                        .to_string(),
+                ),
+            }
+        }
+        let mut ledger_canister_ids = BTreeSet::new();
+        for token in &self.additional_payment_tokens {
+            token.validate()?;
+            if token.ledger_canister_id == self.icp_ledger_canister_id
+                || token.ledger_canister_id == self.sns_ledger_canister_id
+            {
+                return Err(format!(
+                    "The ledger {} of an additional payment token must be neither the ICP nor the SNS ledger.",
+                    token.ledger_canister_id
+                ));
+            }
+            if !ledger_canister_ids.insert(&token.ledger_canister_id) {
+                return Err(format!(
+                    "The ledger {} is listed more than once in additional_payment_tokens.",
+                    token.ledger_canister_id
+                ));
+            }
+        }
+
+        Ok(())
+    }
+}
+
+impl PaymentToken {
+    /// The largest supported number of decimals of a payment token.
+    pub const MAX_DECIMALS: u32 = 18;
+
+    /// The number of smallest units (e.g., e8s) that make up one whole token.
+    pub fn units_per_token(&self) -> u128 {
+        10_u128.pow(self.decimals)
+    }
+
+    pub fn validate(&self) -> Result<(), String> {
+        validate_canister_id(&self.ledger_canister_id)?;
+        if self.symbol.is_empty() {
+            return Err(format!(
+                "The payment token with ledger {} has no symbol.",
+                self.ledger_canister_id
+            ));
+        }
+        if self.decimals > Self::MAX_DECIMALS {
+            return Err(format!(
+                "decimals ({}) of the payment token with ledger {} must not exceed {}.",
+                self.decimals,
+                self.ledger_canister_id,
+                Self::MAX_DECIMALS
+            ));
+        }
+        // A contribution must at least cover the fee of transferring it out
+        // of the swap canister.
+        if self.min_participant_e8s <= self.transaction_fee_e8s {
+            return Err(format!(
+                "min_participant_e8s ({}) of the payment token with ledger {} must be greater than its transaction_fee_e8s ({}).",
+                self.min_participant_e8s, self.ledger_canister_id, self.transaction_fee_e8s
+            ));
+        }
+        if self.min_participant_e8s > self.max_participant_e8s {
+            return Err(format!(
+                "min_participant_e8s ({}) of the payment token with ledger {} must not exceed its max_participant_e8s ({}).",
+                self.min_participant_e8s, self.ledger_canister_id, self.max_participant_e8s
+            ));
+        }
         Ok(())
     }
 }
@@ -675,9 +774,13 @@ impl BuyerState {
                 amount_transferred_e8s: Some(0),
                 transfer_fee_paid_e8s: Some(0),
             }),
+            payment_tokens: BTreeMap::new(),
         }
     }
     pub fn validate(&self) -> Result<(), String> {
+        for payment_token in self.payment_tokens.values() {
+            payment_token.validate()?;
+        }
         if let Some(icp) = &self.icp {
             icp.validate()
         } else {
@@ -705,6 +808,27 @@ impl BuyerState {
             });
         }
     }
+
+    /// The amount of the additional payment token with the given ledger
+    /// canister ID accepted from this buyer.
+    pub fn amount_payment_token_e8s(&self, ledger_canister_id: &str) -> u64 {
+        self.payment_tokens
+            .get(ledger_canister_id)
+            .map_or(0, |payment_token| payment_token.amount_e8s)
+    }
+
+    pub fn set_amount_payment_token_e8s(&mut self, ledger_canister_id: &str, val: u64) {
+        self.payment_tokens
+            .entry(ledger_canister_id.to_string())
+            .or_insert_with(|| TransferableAmount {
+                amount_e8s: 0,
+                transfer_start_timestamp_seconds: 0,
+                transfer_success_timestamp_seconds: 0,
+                amount_transferred_e8s: Some(0),
+                transfer_fee_paid_e8s: Some(0),
+            })
+            .amount_e8s = val;
+    }
 }
 
 impl TransferableAmount {
@@ -963,6 +1087,15 @@ impl FinalizeSwapResponse {
         self.sweep_icp_result = Some(sweep_icp_result);
     }
 
+    pub fn set_sweep_payment_tokens_result(&mut self, sweep_payment_tokens_result: SweepResult) {
+        if !sweep_payment_tokens_result.is_successful_sweep() {
+            self.set_error_message(
+                "Transferring additional payment tokens did not complete fully, some transfers were invalid or failed. Halting swap finalization".to_string()
+            );
+        }
+        self.sweep_payment_tokens_result = Some(sweep_payment_tokens_result);
+    }
+
     pub fn set_settle_community_fund_participation_result(
         &mut self,
         result: SettleCommunityFundParticipationResult,
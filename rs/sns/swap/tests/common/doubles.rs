@@ -9,13 +9,16 @@ use ic_sns_governance::pb::v1::{
     SetModeResponse,
 };
 use ic_sns_swap::{
-    clients::{NnsGovernanceClient, SnsGovernanceClient, SnsRootClient},
+    clients::{
+        ExchangeRateCanisterClient, NnsGovernanceClient, SnsGovernanceClient, SnsRootClient,
+    },
     environment::CanisterClients,
     pb::v1::{
         CanisterCallError, GovernanceError, SetDappControllersRequest, SetDappControllersResponse,
         SettleCommunityFundParticipation,
     },
 };
+use ic_xrc_types::{GetExchangeRateRequest, GetExchangeRateResult};
 use icrc_ledger_types::icrc1::account::{Account, Subaccount};
 use std::{
     collections::VecDeque,
@@ -252,6 +255,27 @@ impl NnsGovernanceClient for SpyNnsGovernanceClient {
     }
 }
 
+/// ExchangeRateCanisterClient that lets the test spy on the calls made
+#[derive(Default, Debug)]
+pub struct SpyExchangeRateCanisterClient {
+    pub calls: Vec<GetExchangeRateRequest>,
+    pub replies: Vec<GetExchangeRateResult>,
+}
+
+#[async_trait]
+impl ExchangeRateCanisterClient for SpyExchangeRateCanisterClient {
+    async fn get_exchange_rate(
+        &mut self,
+        request: GetExchangeRateRequest,
+    ) -> Result<GetExchangeRateResult, CanisterCallError> {
+        self.calls.push(request);
+        Ok(self
+            .replies
+            .pop()
+            .expect("Expected there to be a reply in the ExchangeRateCanisterClient queue"))
+    }
+}
+
 /// Expectation of one call on the mock Ledger.
 #[derive(Debug, Clone, Copy)]
 pub enum LedgerExpect {
@@ -341,6 +365,7 @@ pub fn spy_clients() -> CanisterClients<
         sns_ledger,
         icp_ledger,
         nns_governance,
+        payment_token_ledgers: Default::default(),
     }
 }
 
@@ -363,5 +388,6 @@ pub fn spy_clients_exploding_root() -> CanisterClients<
         sns_ledger,
         icp_ledger,
         nns_governance,
+        payment_token_ledgers: Default::default(),
     }
 }
@@ -5,8 +5,9 @@ use crate::common::{
     doubles::{
         spy_clients, spy_clients_exploding_root, ExplodingSnsRootClient, LedgerExpect,
         NnsGovernanceClientCall, NnsGovernanceClientReply, SnsGovernanceClientCall,
-        SnsGovernanceClientReply, SnsRootClientCall, SnsRootClientReply, SpyNnsGovernanceClient,
-        SpySnsGovernanceClient, SpySnsRootClient,
+        SnsGovernanceClientReply, SnsRootClientCall, SnsRootClientReply,
+        SpyExchangeRateCanisterClient, SpyNnsGovernanceClient, SpySnsGovernanceClient,
+        SpySnsRootClient,
     },
     extract_canister_call_error, extract_set_dapp_controller_response,
     get_account_balance_mock_ledger, get_snapshot_of_buyers_index_list, get_sns_balance,
@@ -25,7 +26,7 @@ use ic_nervous_system_common::{
     NervousSystemError, E8, SECONDS_PER_DAY, START_OF_2022_TIMESTAMP_SECONDS,
 };
 use ic_nervous_system_common_test_keys::{
-    TEST_USER1_PRINCIPAL, TEST_USER2_PRINCIPAL, TEST_USER3_PRINCIPAL,
+    TEST_USER1_PRINCIPAL, TEST_USER2_PRINCIPAL, TEST_USER3_PRINCIPAL, TEST_USER4_PRINCIPAL,
 };
 use ic_nervous_system_common_test_utils::{
     drain_receiver_channel, InterleavingTestLedger, LedgerCall, LedgerControlMessage, LedgerReply,
@@ -51,9 +52,13 @@ use ic_sns_swap::{
     },
     swap::{
         apportion_approximately_equally, principal_to_subaccount, CLAIM_SWAP_NEURONS_BATCH_SIZE,
-        FIRST_PRINCIPAL_BYTES, SALE_NEURON_MEMO_RANGE_START,
+        FIRST_PRINCIPAL_BYTES, PAYMENT_TOKEN_EXCHANGE_RATE_TIMEOUT_SECONDS,
+        SALE_NEURON_MEMO_RANGE_START,
     },
 };
+use ic_xrc_types::{
+    Asset, AssetClass, ExchangeRate, ExchangeRateError, ExchangeRateMetadata, GetExchangeRateResult,
+};
 use icp_ledger::DEFAULT_TRANSFER_FEE;
 use icrc_ledger_types::icrc1::account::Account;
 use maplit::btreemap;
@@ -113,6 +118,8 @@ fn init_with_confirmation_text(confirmation_text: Option<String>) -> Init {
         nns_proposal_id: None,                       // TODO[NNS1-2339]
         neurons_fund_participants: None,             // TODO[NNS1-2339]
         should_auto_finalize: Some(true),
+        additional_payment_tokens: vec![],
+        exchange_rate_canister_id: None,
     };
     assert_is_ok!(result.validate());
     result
@@ -172,6 +179,7 @@ fn create_generic_committed_swap() -> Swap {
         purge_old_tickets_last_completion_timestamp_nanoseconds: Some(0),
         purge_old_tickets_next_principal: Some(FIRST_PRINCIPAL_BYTES.to_vec()),
         already_tried_to_auto_finalize: Some(false),
+        payment_token_exchange_rates: vec![],
     }
 }
 
@@ -1148,6 +1156,7 @@ async fn test_finalize_swap_ok() {
         purge_old_tickets_last_completion_timestamp_nanoseconds: Some(0),
         purge_old_tickets_next_principal: Some(vec![0; 32]),
         already_tried_to_auto_finalize: Some(false),
+        payment_token_exchange_rates: vec![],
     };
 
     // Step 1.5: Attempt to auto-finalize the swap. It should not work, since
@@ -1279,6 +1288,7 @@ async fn test_finalize_swap_ok() {
                     successful_settle_community_fund_participation_result()
                 ),
                 error_message: None,
+                sweep_payment_tokens_result: None,
             },
         );
     }
@@ -1455,7 +1465,8 @@ async fn test_finalize_swap_ok() {
                         transfer_success_timestamp_seconds: END_TIMESTAMP_SECONDS + 10,
                         amount_transferred_e8s: Some(expected_amount_committed_e8s),
                         transfer_fee_paid_e8s: Some(fee_e8s)
-                    })
+                    }),
+                    payment_tokens: Default::default(),
                 }
             );
         });
@@ -1503,6 +1514,7 @@ async fn test_finalize_swap_abort() {
         purge_old_tickets_last_completion_timestamp_nanoseconds: Some(0),
         purge_old_tickets_next_principal: Some(vec![0; 32]),
         already_tried_to_auto_finalize: Some(false),
+        payment_token_exchange_rates: vec![],
     };
 
     // Step 1.5: Attempt to auto-finalize the swap. It should not work, since
@@ -1620,6 +1632,7 @@ async fn test_finalize_swap_abort() {
                     successful_settle_community_fund_participation_result()
                 ),
                 error_message: None,
+                sweep_payment_tokens_result: None,
             },
         );
     }
@@ -2233,6 +2246,7 @@ fn test_finalize_swap_rejects_concurrent_calls() {
         sns_ledger: SpyLedger::new(vec![LedgerReply::TransferFunds(Ok(1000))]),
         sns_root: spy_clients().sns_root,
         nns_governance: spy_clients().nns_governance,
+        payment_token_ledgers: Default::default(),
     };
 
     // Step 2: Call finalize and have the thread block
@@ -2456,7 +2470,8 @@ async fn test_sweep_icp_handles_ledger_transfers() {
                 icp: Some(TransferableAmount {
                     amount_e8s: DEFAULT_TRANSFER_FEE.get_e8s() - 1,
                     ..Default::default()
-                })
+                }),
+                payment_tokens: Default::default(),
             },
             // This Buyer has already had its transfer succeed, and should result in
             // as Skipped field increment
@@ -2466,7 +2481,8 @@ async fn test_sweep_icp_handles_ledger_transfers() {
                     transfer_start_timestamp_seconds: END_TIMESTAMP_SECONDS,
                     transfer_success_timestamp_seconds: END_TIMESTAMP_SECONDS + 1,
                     ..Default::default()
-                })
+                }),
+                payment_tokens: Default::default(),
             },
             // This buyer's state is valid, and a mock call to the ledger will allow it
             // to succeed, which should result in a success field increment
@@ -2474,7 +2490,8 @@ async fn test_sweep_icp_handles_ledger_transfers() {
                 icp: Some(TransferableAmount {
                     amount_e8s: 10 * E8,
                     ..Default::default()
-                })
+                }),
+                payment_tokens: Default::default(),
             },
             // This buyer's state is valid, but a mock call to the ledger will fail the transfer,
             // which should result in a failure field increment.
@@ -2482,7 +2499,8 @@ async fn test_sweep_icp_handles_ledger_transfers() {
                 icp: Some(TransferableAmount {
                     amount_e8s: 10 * E8,
                     ..Default::default()
-                })
+                }),
+                payment_tokens: Default::default(),
             },
         },
         ..Default::default()
@@ -2534,7 +2552,8 @@ async fn test_finalization_halts_when_sweep_icp_fails() {
                 icp: Some(TransferableAmount {
                     amount_e8s: DEFAULT_TRANSFER_FEE.get_e8s() - 1,
                     ..Default::default()
-                })
+                }),
+                payment_tokens: Default::default(),
             },
             // This buyer's state is valid, but a mock call to the ledger will fail the transfer,
             // which should result in a failure field increment.
@@ -2542,7 +2561,8 @@ async fn test_finalization_halts_when_sweep_icp_fails() {
                 icp: Some(TransferableAmount {
                     amount_e8s: 10 * E8,
                     ..Default::default()
-                })
+                }),
+                payment_tokens: Default::default(),
             },
         },
         ..Default::default()
@@ -3361,6 +3381,7 @@ fn test_derived_state() {
             transfer_success_timestamp_seconds: 12,
             ..Default::default()
         }),
+        payment_tokens: Default::default(),
     };
     let buyers = btreemap! {
         "".to_string() => buyer_state,
@@ -4761,7 +4782,526 @@ async fn test_finalize_swap_abort_sets_amount_transferred_and_fees_correctly() {
                 transfer_success_timestamp_seconds: END_TIMESTAMP_SECONDS + 10,
                 amount_transferred_e8s: Some(50 * E8 - DEFAULT_TRANSFER_FEE.get_e8s()),
                 transfer_fee_paid_e8s: Some(DEFAULT_TRANSFER_FEE.get_e8s())
+            }),
+            payment_tokens: Default::default(),
+        }
+    );
+}
+
+const PAYMENT_TOKEN_LEDGER_CANISTER_ID: CanisterId = CanisterId::from_u64(2213);
+const EXCHANGE_RATE_CANISTER_ID: CanisterId = CanisterId::from_u64(2587);
+
+fn init_with_payment_token() -> Init {
+    let result = Init {
+        additional_payment_tokens: vec![PaymentToken {
+            ledger_canister_id: PAYMENT_TOKEN_LEDGER_CANISTER_ID.to_string(),
+            symbol: "CKBTC".to_string(),
+            transaction_fee_e8s: 10,
+            min_participant_e8s: 1_000,
+            max_participant_e8s: 10 * E8,
+            decimals: 8,
+        }],
+        exchange_rate_canister_id: Some(EXCHANGE_RATE_CANISTER_ID.to_string()),
+        ..init()
+    };
+    assert_is_ok!(result.validate());
+    result
+}
+
+fn icp_exchange_rate(symbol: &str, rate: u64, decimals: u32) -> GetExchangeRateResult {
+    Ok(ExchangeRate {
+        base_asset: Asset {
+            symbol: symbol.to_string(),
+            class: AssetClass::Cryptocurrency,
+        },
+        quote_asset: Asset {
+            symbol: "ICP".to_string(),
+            class: AssetClass::Cryptocurrency,
+        },
+        timestamp: END_TIMESTAMP_SECONDS,
+        rate,
+        metadata: ExchangeRateMetadata {
+            decimals,
+            base_asset_num_queried_sources: 3,
+            base_asset_num_received_rates: 3,
+            quote_asset_num_queried_sources: 3,
+            quote_asset_num_received_rates: 3,
+            standard_deviation: 0,
+            forex_timestamp: None,
+        },
+    })
+}
+
+/// An exchange rate canister that reports once that one whole unit of the
+/// payment token is worth 5 ICP.
+fn payment_token_exchange_rate_canister() -> SpyExchangeRateCanisterClient {
+    SpyExchangeRateCanisterClient {
+        replies: vec![icp_exchange_rate("CKBTC", 5_000_000_000, 9)],
+        ..Default::default()
+    }
+}
+
+/// Opens a swap that accepts a payment token, in which `user1` contributes
+/// 10 ICP and `user2` contributes 2 units of the payment token, which are
+/// worth 10 ICP.
+async fn open_swap_with_payment_token_participation(
+    user1: &PrincipalId,
+    user2: &PrincipalId,
+) -> Swap {
+    let params = Params {
+        min_participants: 2,
+        min_icp_e8s: E8,
+        min_participant_icp_e8s: E8,
+        max_participant_icp_e8s: 100 * E8,
+        sns_token_e8s: 100_000 * E8,
+        neuron_basket_construction_parameters: Some(NeuronBasketConstructionParameters {
+            count: 1,
+            dissolve_delay_interval_seconds: ONE_MONTH_SECONDS,
+        }),
+        ..params()
+    };
+    let mut swap = Swap::new(init_with_payment_token());
+    open_swap(&mut swap, &params).await;
+
+    buy_token(
+        &mut swap,
+        user1,
+        &(10 * E8),
+        &mock_stub(get_account_balance_mock_ledger(&(10 * E8), user1)),
+    )
+    .await;
+
+    let response = swap
+        .refresh_buyer_payment_token_e8s(
+            *user2,
+            None,
+            SWAP_CANISTER_ID,
+            PAYMENT_TOKEN_LEDGER_CANISTER_ID,
+            &mock_stub(get_account_balance_mock_ledger(&(2 * E8), user2)),
+            START_TIMESTAMP_SECONDS,
+            &mut payment_token_exchange_rate_canister(),
+        )
+        .await
+        .unwrap();
+    assert_eq!(response.icp_accepted_participation_e8s, 2 * E8);
+
+    swap
+}
+
+#[tokio::test]
+async fn test_refresh_buyer_payment_token_e8s() {
+    let user = *TEST_USER1_PRINCIPAL;
+    let mut swap = Swap::new(init_with_payment_token());
+    open_swap(&mut swap, &params()).await;
+
+    // Tokens can only be contributed via the ledgers listed in Init.
+    assert_is_err!(
+        swap.refresh_buyer_payment_token_e8s(
+            user,
+            None,
+            SWAP_CANISTER_ID,
+            SNS_LEDGER_CANISTER_ID,
+            &mock_stub(vec![]),
+            START_TIMESTAMP_SECONDS,
+            &mut SpyExchangeRateCanisterClient::default(),
+        )
+        .await
+    );
+
+    // Without an exchange rate, the contribution cannot be valued, so it is
+    // rejected without even looking up the balance.
+    assert_is_err!(
+        swap.refresh_buyer_payment_token_e8s(
+            user,
+            None,
+            SWAP_CANISTER_ID,
+            PAYMENT_TOKEN_LEDGER_CANISTER_ID,
+            &mock_stub(vec![]),
+            START_TIMESTAMP_SECONDS,
+            &mut SpyExchangeRateCanisterClient {
+                replies: vec![Err(ExchangeRateError::CryptoBaseAssetNotFound)],
+                ..Default::default()
+            },
+        )
+        .await
+    );
+    assert!(swap.buyers.is_empty());
+    assert!(swap.payment_token_exchange_rates.is_empty());
+
+    // Contributions below the minimum of the token are rejected. The
+    // exchange rate is recorded nonetheless.
+    let mut exchange_rate_canister = payment_token_exchange_rate_canister();
+    assert_is_err!(
+        swap.refresh_buyer_payment_token_e8s(
+            user,
+            None,
+            SWAP_CANISTER_ID,
+            PAYMENT_TOKEN_LEDGER_CANISTER_ID,
+            &mock_stub(get_account_balance_mock_ledger(&999, &user)),
+            START_TIMESTAMP_SECONDS,
+            &mut exchange_rate_canister,
+        )
+        .await
+    );
+    assert!(swap.buyers.is_empty());
+    assert_eq!(exchange_rate_canister.calls.len(), 1);
+    assert_eq!(exchange_rate_canister.calls[0].base_asset.symbol, "CKBTC");
+    assert_eq!(exchange_rate_canister.calls[0].quote_asset.symbol, "ICP");
+    assert_eq!(
+        swap.payment_token_exchange_rates,
+        vec![PaymentTokenExchangeRate {
+            ledger_canister_id: PAYMENT_TOKEN_LEDGER_CANISTER_ID.to_string(),
+            icp_e8s_per_token: 5 * E8,
+            timestamp_seconds: START_TIMESTAMP_SECONDS,
+        }]
+    );
+
+    // Contributions above the maximum of the token are capped. The recorded
+    // exchange rate is reused.
+    let mut exchange_rate_canister = SpyExchangeRateCanisterClient::default();
+    let response = swap
+        .refresh_buyer_payment_token_e8s(
+            user,
+            None,
+            SWAP_CANISTER_ID,
+            PAYMENT_TOKEN_LEDGER_CANISTER_ID,
+            &mock_stub(get_account_balance_mock_ledger(&(11 * E8), &user)),
+            START_TIMESTAMP_SECONDS,
+            &mut exchange_rate_canister,
+        )
+        .await
+        .unwrap();
+    assert_eq!(response.icp_accepted_participation_e8s, 10 * E8);
+    assert_eq!(response.icp_ledger_account_balance_e8s, 11 * E8);
+    assert!(exchange_rate_canister.calls.is_empty());
+
+    let buyer_state = swap.buyers.get(&user.to_string()).unwrap();
+    assert_eq!(buyer_state.amount_icp_e8s(), 0);
+    assert_eq!(
+        buyer_state.amount_payment_token_e8s(&PAYMENT_TOKEN_LEDGER_CANISTER_ID.to_string()),
+        10 * E8
+    );
+    assert_eq!(swap.participant_total_icp_equivalent_e8s(), 50 * E8);
+}
+
+#[tokio::test]
+async fn test_payment_token_exchange_rate_is_required_to_commit() {
+    let user1 = *TEST_USER1_PRINCIPAL;
+    let user2 = *TEST_USER2_PRINCIPAL;
+    let mut swap = open_swap_with_payment_token_participation(&user1, &user2).await;
+
+    // The exchange rate was recorded with the contribution, so the swap can
+    // be committed as soon as it is due.
+    assert!(!swap.payment_token_exchange_rates_pending(END_TIMESTAMP_SECONDS));
+    assert_eq!(swap.participant_total_icp_equivalent_e8s(), 20 * E8);
+
+    // Contributions accepted by an earlier version of the canister may lack
+    // an exchange rate.
+    let exchange_rates = std::mem::take(&mut swap.payment_token_exchange_rates);
+
+    // Before the swap is due, no exchange rate is needed.
+    assert!(!swap.payment_token_exchange_rates_pending(END_TIMESTAMP_SECONDS - 1));
+
+    // Once the swap is due, it can neither commit nor abort until the
+    // exchange rate of the payment token has been recorded.
+    assert!(swap.payment_token_exchange_rates_pending(END_TIMESTAMP_SECONDS));
+    assert!(!swap.can_abort(END_TIMESTAMP_SECONDS));
+    assert!(!swap.try_commit(END_TIMESTAMP_SECONDS));
+
+    // A failed lookup leaves the rate pending.
+    let mut exchange_rate_canister = SpyExchangeRateCanisterClient {
+        replies: vec![Err(ExchangeRateError::CryptoBaseAssetNotFound)],
+        ..Default::default()
+    };
+    assert_is_err!(
+        swap.update_payment_token_exchange_rates(
+            END_TIMESTAMP_SECONDS,
+            &mut exchange_rate_canister
+        )
+        .await
+    );
+    assert!(swap.payment_token_exchange_rates_pending(END_TIMESTAMP_SECONDS));
+
+    let mut exchange_rate_canister = payment_token_exchange_rate_canister();
+    assert_is_ok!(
+        swap.update_payment_token_exchange_rates(
+            END_TIMESTAMP_SECONDS,
+            &mut exchange_rate_canister
+        )
+        .await
+    );
+    assert_eq!(exchange_rate_canister.calls.len(), 1);
+    assert_eq!(
+        swap.payment_token_exchange_rates,
+        vec![PaymentTokenExchangeRate {
+            timestamp_seconds: END_TIMESTAMP_SECONDS,
+            ..exchange_rates[0].clone()
+        }]
+    );
+    assert!(!swap.payment_token_exchange_rates_pending(END_TIMESTAMP_SECONDS));
+    assert_eq!(swap.participant_total_icp_equivalent_e8s(), 20 * E8);
+
+    // Both participants contributed the equivalent of 10 ICP, so they
+    // receive the same amount of SNS tokens.
+    assert!(swap.try_commit(END_TIMESTAMP_SECONDS));
+    assert_eq!(swap.lifecycle(), Committed);
+    for user in [user1, user2] {
+        let sns_e8s: u64 = swap
+            .neuron_recipes
+            .iter()
+            .filter(|recipe| {
+                recipe.investor
+                    == Some(Investor::Direct(DirectInvestment {
+                        buyer_principal: user.to_string(),
+                    }))
             })
+            .map(|recipe| recipe.sns.as_ref().unwrap().amount_e8s)
+            .sum();
+        assert_eq!(sns_e8s, 50_000 * E8);
+    }
+}
+
+#[tokio::test]
+async fn test_sweep_payment_tokens_after_commit() {
+    let user1 = *TEST_USER1_PRINCIPAL;
+    let user2 = *TEST_USER2_PRINCIPAL;
+    let mut swap = open_swap_with_payment_token_participation(&user1, &user2).await;
+    assert!(swap.try_commit(END_TIMESTAMP_SECONDS));
+
+    let clients = CanisterClients {
+        payment_token_ledgers: btreemap! {
+            PAYMENT_TOKEN_LEDGER_CANISTER_ID =>
+                SpyLedger::new(vec![LedgerReply::TransferFunds(Ok(1000))]),
+        },
+        ..spy_clients()
+    };
+
+    let result = swap.sweep_payment_tokens(now_fn, &clients).await;
+    assert_eq!(
+        result,
+        SweepResult {
+            success: 1,
+            skipped: 0,
+            failure: 0,
+            invalid: 0,
+            global_failures: 0,
         }
     );
+
+    // The payment tokens are sent to SNS governance, like the ICP.
+    let calls =
+        clients.payment_token_ledgers[&PAYMENT_TOKEN_LEDGER_CANISTER_ID].get_calls_snapshot();
+    assert_eq!(
+        calls,
+        vec![LedgerCall::TransferFundsICRC1 {
+            amount_e8s: 2 * E8 - 10,
+            fee_e8s: 10,
+            from_subaccount: Some(principal_to_subaccount(&user2)),
+            to: Account {
+                owner: SNS_GOVERNANCE_CANISTER_ID.into(),
+                subaccount: None,
+            },
+            memo: 0,
+        }]
+    );
+
+    // Sweeping again does not transfer the tokens twice.
+    let result = swap.sweep_payment_tokens(now_fn, &clients).await;
+    assert_eq!(result.success, 0);
+    assert_eq!(result.skipped, 1);
+}
+
+#[tokio::test]
+async fn test_swap_aborts_when_payment_token_exchange_rate_times_out() {
+    let user1 = *TEST_USER1_PRINCIPAL;
+    let user2 = *TEST_USER2_PRINCIPAL;
+    let mut swap = open_swap_with_payment_token_participation(&user1, &user2).await;
+    // Contributions accepted by an earlier version of the canister may lack
+    // an exchange rate.
+    swap.payment_token_exchange_rates.clear();
+    let timeout_seconds = END_TIMESTAMP_SECONDS + PAYMENT_TOKEN_EXCHANGE_RATE_TIMEOUT_SECONDS;
+
+    // The exchange rate is retried until the timeout has passed.
+    assert!(swap.payment_token_exchange_rates_pending(timeout_seconds - 1));
+    assert!(!swap.payment_token_exchange_rates_timed_out(timeout_seconds - 1));
+    assert!(!swap.can_abort(timeout_seconds - 1));
+
+    // Afterwards, the swap cannot be committed, as the payment token
+    // contribution cannot be valued, so it is aborted instead, even though
+    // the participation would otherwise be sufficient.
+    assert!(!swap.payment_token_exchange_rates_pending(timeout_seconds));
+    assert!(swap.payment_token_exchange_rates_timed_out(timeout_seconds));
+    assert!(swap.sufficient_participation());
+    assert!(!swap.can_commit(timeout_seconds));
+    assert!(swap.try_abort(timeout_seconds));
+    assert_eq!(swap.lifecycle(), Aborted);
+    assert!(!swap.payment_token_exchange_rates_timed_out(timeout_seconds));
+
+    // The payment tokens are refunded to the buyer.
+    let clients = CanisterClients {
+        payment_token_ledgers: btreemap! {
+            PAYMENT_TOKEN_LEDGER_CANISTER_ID =>
+                SpyLedger::new(vec![LedgerReply::TransferFunds(Ok(1000))]),
+        },
+        ..spy_clients()
+    };
+    let result = swap.sweep_payment_tokens(now_fn, &clients).await;
+    assert_eq!(result.success, 1);
+    assert_eq!(result.failure, 0);
+    let calls =
+        clients.payment_token_ledgers[&PAYMENT_TOKEN_LEDGER_CANISTER_ID].get_calls_snapshot();
+    assert_eq!(
+        calls,
+        vec![LedgerCall::TransferFundsICRC1 {
+            amount_e8s: 2 * E8 - 10,
+            fee_e8s: 10,
+            from_subaccount: Some(principal_to_subaccount(&user2)),
+            to: Account {
+                owner: user2.into(),
+                subaccount: None,
+            },
+            memo: 0,
+        }]
+    );
+}
+
+#[tokio::test]
+async fn test_payment_token_contributions_count_towards_max_icp() {
+    let user1 = *TEST_USER1_PRINCIPAL;
+    let user2 = *TEST_USER2_PRINCIPAL;
+    let user3 = *TEST_USER3_PRINCIPAL;
+    let user4 = *TEST_USER4_PRINCIPAL;
+    let mut swap = open_swap_with_payment_token_participation(&user1, &user2).await;
+    swap.params.as_mut().unwrap().max_icp_e8s = 25 * E8;
+    assert!(!swap.icp_target_reached());
+
+    // Only 5 ICP are left until the target is reached, so of the 3 units of
+    // the payment token of user3 (worth 15 ICP), only 1 is accepted.
+    let response = swap
+        .refresh_buyer_payment_token_e8s(
+            user3,
+            None,
+            SWAP_CANISTER_ID,
+            PAYMENT_TOKEN_LEDGER_CANISTER_ID,
+            &mock_stub(get_account_balance_mock_ledger(&(3 * E8), &user3)),
+            START_TIMESTAMP_SECONDS,
+            &mut SpyExchangeRateCanisterClient::default(),
+        )
+        .await
+        .unwrap();
+    assert_eq!(response.icp_accepted_participation_e8s, E8);
+    assert_eq!(response.icp_ledger_account_balance_e8s, 3 * E8);
+    assert_eq!(swap.participant_total_icp_equivalent_e8s(), 25 * E8);
+    assert!(swap.icp_target_reached());
+    assert!(swap.can_commit(END_TIMESTAMP_SECONDS - 1));
+
+    // No further contributions are accepted, neither in ICP nor in the
+    // payment token, without even looking up the balance.
+    assert_is_err!(
+        swap.refresh_buyer_token_e8s(user4, None, SWAP_CANISTER_ID, &mock_stub(vec![]),)
+            .await
+    );
+    assert_is_err!(
+        swap.refresh_buyer_payment_token_e8s(
+            user4,
+            None,
+            SWAP_CANISTER_ID,
+            PAYMENT_TOKEN_LEDGER_CANISTER_ID,
+            &mock_stub(vec![]),
+            START_TIMESTAMP_SECONDS,
+            &mut SpyExchangeRateCanisterClient::default(),
+        )
+        .await
+    );
+    assert!(!swap.buyers.contains_key(&user4.to_string()));
+}
+
+#[tokio::test]
+async fn test_payment_token_contributions_count_towards_max_participant_icp() {
+    let user1 = *TEST_USER1_PRINCIPAL;
+    let user2 = *TEST_USER2_PRINCIPAL;
+    let mut swap = open_swap_with_payment_token_participation(&user1, &user2).await;
+    swap.params.as_mut().unwrap().max_participant_icp_e8s = 12 * E8;
+    let payment_token = PAYMENT_TOKEN_LEDGER_CANISTER_ID.to_string();
+
+    // user1 already contributed 10 ICP, so only 0.4 units of the payment
+    // token (worth 2 ICP) are accepted.
+    let response = swap
+        .refresh_buyer_payment_token_e8s(
+            user1,
+            None,
+            SWAP_CANISTER_ID,
+            PAYMENT_TOKEN_LEDGER_CANISTER_ID,
+            &mock_stub(get_account_balance_mock_ledger(&E8, &user1)),
+            START_TIMESTAMP_SECONDS,
+            &mut SpyExchangeRateCanisterClient::default(),
+        )
+        .await
+        .unwrap();
+    assert_eq!(response.icp_accepted_participation_e8s, 40_000_000);
+    let buyer_state = swap.buyers.get(&user1.to_string()).unwrap();
+    assert_eq!(buyer_state.amount_icp_e8s(), 10 * E8);
+    assert_eq!(
+        buyer_state.amount_payment_token_e8s(&payment_token),
+        40_000_000
+    );
+    assert_eq!(swap.buyer_icp_equivalent_e8s(buyer_state), 12 * E8);
+
+    // user2 already contributed the equivalent of 10 ICP in the payment
+    // token, so only 2 more ICP are accepted.
+    let response = swap
+        .refresh_buyer_token_e8s(
+            user2,
+            None,
+            SWAP_CANISTER_ID,
+            &mock_stub(get_account_balance_mock_ledger(&(5 * E8), &user2)),
+        )
+        .await
+        .unwrap();
+    assert_eq!(response.icp_accepted_participation_e8s, 2 * E8);
+    let buyer_state = swap.buyers.get(&user2.to_string()).unwrap();
+    assert_eq!(buyer_state.amount_payment_token_e8s(&payment_token), 2 * E8);
+    assert_eq!(swap.buyer_icp_equivalent_e8s(buyer_state), 12 * E8);
+}
+
+#[tokio::test]
+async fn test_payment_token_decimals() {
+    let user = *TEST_USER1_PRINCIPAL;
+    let mut init = init_with_payment_token();
+    init.additional_payment_tokens[0].decimals = PaymentToken::MAX_DECIMALS + 1;
+    assert_is_err!(init.validate());
+
+    // One whole unit of a token with 6 decimals is 10^6 of its smallest
+    // units.
+    init.additional_payment_tokens[0].decimals = 6;
+    init.additional_payment_tokens[0].max_participant_e8s = 10_000_000;
+    assert_is_ok!(init.validate());
+    let mut swap = Swap::new(init);
+    open_swap(&mut swap, &params()).await;
+
+    let response = swap
+        .refresh_buyer_payment_token_e8s(
+            user,
+            None,
+            SWAP_CANISTER_ID,
+            PAYMENT_TOKEN_LEDGER_CANISTER_ID,
+            &mock_stub(get_account_balance_mock_ledger(&2_000_000, &user)),
+            START_TIMESTAMP_SECONDS,
+            &mut payment_token_exchange_rate_canister(),
+        )
+        .await
+        .unwrap();
+    assert_eq!(response.icp_accepted_participation_e8s, 2_000_000);
+
+    // The 2 whole units are worth 10 ICP.
+    let payment_token = PAYMENT_TOKEN_LEDGER_CANISTER_ID.to_string();
+    assert_eq!(
+        swap.payment_token_icp_e8s(&payment_token, 2_000_000),
+        Some(10 * E8)
+    );
+    assert_eq!(
+        swap.payment_token_e8s_worth(&payment_token, 10 * E8),
+        Some(2_000_000)
+    );
+    assert_eq!(swap.participant_total_icp_equivalent_e8s(), 10 * E8);
 }
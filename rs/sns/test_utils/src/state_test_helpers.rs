@@ -215,6 +215,7 @@ pub fn participate_in_swap(
             Encode!(&RefreshBuyerTokensRequest {
                 buyer: participant_principal_id.to_string(),
                 confirmation_text: None,
+                payment_token_ledger_canister_id: None,
             })
             .unwrap(),
         )
@@ -557,6 +558,7 @@ pub fn refresh_buyer_tokens(
     let args = Encode!(&RefreshBuyerTokensRequest {
         buyer: sender.to_string(),
         confirmation_text,
+        payment_token_ledger_canister_id: None,
     })
     .unwrap();
     match env.execute_ingress_as(*sender, *swap_id, "refresh_buyer_tokens", args) {
@@ -2,6 +2,7 @@ use crate::error::LayoutError;
 use crate::utils::do_copy;
 
 use ic_base_types::{NumBytes, NumSeconds};
+use ic_ic00_types::LogVisibility;
 use ic_logger::{error, info, ReplicaLogger};
 use ic_metrics::{buckets::decimal_buckets, MetricsRegistry};
 use ic_protobuf::{
@@ -14,14 +15,16 @@ use ic_protobuf::{
 use ic_replicated_state::{
     canister_state::{
         execution_state::{NextScheduledMethod, WasmMetadata},
-        system_state::{CanisterHistory, CyclesUseCase},
+        system_state::{wasm_chunk_store::WasmChunkStoreMetadata, CanisterHistory, CyclesUseCase},
     },
     CallContextManager, CanisterStatus, ExecutionTask, ExportedFunctions, Global, NumWasmPages,
+    SnapshotId,
 };
 use ic_sys::mmap::ScopedMmap;
 use ic_types::{
-    nominal_cycles::NominalCycles, AccumulatedPriority, CanisterId, ComputeAllocation, Cycles,
-    ExecutionRound, Height, MemoryAllocation, NumInstructions, PrincipalId,
+    canister_log::CanisterLog, nominal_cycles::NominalCycles, AccumulatedPriority, CanisterId,
+    ComputeAllocation, Cycles, ExecutionRound, Height, MemoryAllocation, NumInstructions,
+    PrincipalId, Time,
 };
 use ic_utils::fs::sync_path;
 use ic_utils::thread::parallel_map;
@@ -50,6 +53,8 @@ pub const INGRESS_HISTORY_FILE: &str = "ingress_history.pbuf";
 pub const SPLIT_MARKER_FILE: &str = "split_from.pbuf";
 pub const SUBNET_QUEUES_FILE: &str = "subnet_queues.pbuf";
 pub const SYSTEM_METADATA_FILE: &str = "system_metadata.pbuf";
+pub const SNAPSHOTS_DIR: &str = "snapshots";
+pub const SNAPSHOT_FILE: &str = "snapshot.pbuf";
 
 /// `ReadOnly` is the access policy used for reading checkpoints. We
 /// don't want to ever modify persisted states.
@@ -143,6 +148,8 @@ pub struct CanisterStateBits {
     pub skipped_round_due_to_no_messages: u64,
     pub executed: u64,
     pub interruped_during_execution: u64,
+    pub skipped_round_due_to_limits: u64,
+    pub slices_executed: u64,
     pub certified_data: Vec<u8>,
     pub consumed_cycles_since_replica_started: NominalCycles,
     pub stable_memory_size: NumWasmPages,
@@ -154,6 +161,22 @@ pub struct CanisterStateBits {
     pub canister_version: u64,
     pub consumed_cycles_since_replica_started_by_use_cases: BTreeMap<CyclesUseCase, NominalCycles>,
     pub canister_history: CanisterHistory,
+    pub log_visibility: LogVisibility,
+    pub canister_log: CanisterLog,
+    pub wasm_chunk_store_metadata: WasmChunkStoreMetadata,
+    pub wasm_memory_limit: Option<NumBytes>,
+}
+
+/// This struct contains bits of the `CanisterSnapshot` that are not already
+/// covered somewhere else and are too small to be serialized separately.
+#[derive(Debug)]
+pub struct CanisterSnapshotBits {
+    pub snapshot_id: SnapshotId,
+    pub taken_at_timestamp: Time,
+    pub canister_version: u64,
+    pub certified_data: Vec<u8>,
+    pub execution_state_bits: ExecutionStateBits,
+    pub stable_memory_size: NumWasmPages,
 }
 
 #[derive(Clone)]
@@ -374,6 +397,27 @@ impl TipHandler {
         }
         Ok(())
     }
+
+    /// Deletes snapshots from tip if they are not in ids.
+    pub fn filter_tip_snapshots(
+        &mut self,
+        height: Height,
+        ids: &BTreeSet<SnapshotId>,
+    ) -> Result<(), LayoutError> {
+        let tip = self.tip(height)?;
+        let snapshots_on_disk = tip.snapshot_ids()?;
+        for id in snapshots_on_disk {
+            if !ids.contains(&id) {
+                let snapshot_path = tip.snapshot(&id)?.raw_path();
+                std::fs::remove_dir_all(&snapshot_path).map_err(|err| LayoutError::IoError {
+                    path: snapshot_path,
+                    message: "Cannot remove snapshot.".to_string(),
+                    io_err: err,
+                })?;
+            }
+        }
+        Ok(())
+    }
 }
 
 impl StateLayout {
@@ -1169,6 +1213,17 @@ fn parse_canister_id(hex: &str) -> Result<CanisterId, String> {
     .map_err(|err| format!("failed to create canister ID: {}", err))
 }
 
+/// Helper for parsing hex representations of local snapshot IDs, used for the
+/// directory names under `snapshots/<canister ID>`.
+fn parse_snapshot_local_id(hex: &str) -> Result<u64, String> {
+    u64::from_str_radix(hex, 16).map_err(|err| {
+        format!(
+            "failed to convert directory name {} into a snapshot ID: {}",
+            hex, err
+        )
+    })
+}
+
 /// Parses the canister ID from a relative path, if it is the path of a canister
 /// state file (e.g. `canister_states/00000000000000010101/queues.pbuf`).
 /// Returns `None` if the path is not under `canister_states`; or if parsing
@@ -1308,6 +1363,33 @@ impl<Permissions: AccessPolicy> CheckpointLayout<Permissions> {
         )
     }
 
+    /// Returns the IDs of all canister snapshots in this checkpoint. Snapshots
+    /// are stored under `snapshots/<canister ID>/<local snapshot ID>`.
+    pub fn snapshot_ids(&self) -> Result<Vec<SnapshotId>, LayoutError> {
+        let snapshots_dir = self.root.join(SNAPSHOTS_DIR);
+        Permissions::check_dir(&snapshots_dir)?;
+        let mut snapshot_ids = Vec::new();
+        for canister_id in collect_subdirs(snapshots_dir.as_path(), parse_canister_id)? {
+            let canister_dir = snapshots_dir.join(hex::encode(canister_id.get_ref().as_slice()));
+            for local_id in collect_subdirs(canister_dir.as_path(), parse_snapshot_local_id)? {
+                snapshot_ids.push(SnapshotId::new(canister_id, local_id));
+            }
+        }
+        Ok(snapshot_ids)
+    }
+
+    pub fn snapshot(
+        &self,
+        snapshot_id: &SnapshotId,
+    ) -> Result<SnapshotLayout<Permissions>, LayoutError> {
+        SnapshotLayout::new(
+            self.root
+                .join(SNAPSHOTS_DIR)
+                .join(hex::encode(snapshot_id.canister_id().get_ref().as_slice()))
+                .join(format!("{:016x}", snapshot_id.local_id())),
+        )
+    }
+
     pub fn height(&self) -> Height {
         self.height
     }
@@ -1356,6 +1438,47 @@ impl<Permissions: AccessPolicy> CanisterLayout<Permissions> {
     pub fn stable_memory_blob(&self) -> PathBuf {
         self.canister_root.join("stable_memory.bin")
     }
+
+    pub fn wasm_chunk_store(&self) -> PathBuf {
+        self.canister_root.join("wasm_chunk_store.bin")
+    }
+}
+
+pub struct SnapshotLayout<Permissions: AccessPolicy> {
+    snapshot_root: PathBuf,
+    permissions_tag: PhantomData<Permissions>,
+}
+
+impl<Permissions: AccessPolicy> SnapshotLayout<Permissions> {
+    pub fn new(snapshot_root: PathBuf) -> Result<Self, LayoutError> {
+        Permissions::check_dir(&snapshot_root)?;
+        Ok(Self {
+            snapshot_root,
+            permissions_tag: PhantomData,
+        })
+    }
+
+    pub fn raw_path(&self) -> PathBuf {
+        self.snapshot_root.clone()
+    }
+
+    pub fn snapshot(
+        &self,
+    ) -> ProtoFileWith<pb_canister_state_bits::CanisterSnapshotBits, Permissions> {
+        self.snapshot_root.join(SNAPSHOT_FILE).into()
+    }
+
+    pub fn wasm(&self) -> WasmFile<Permissions> {
+        self.snapshot_root.join("software.wasm").into()
+    }
+
+    pub fn vmemory_0(&self) -> PathBuf {
+        self.snapshot_root.join("vmemory_0.bin")
+    }
+
+    pub fn stable_memory_blob(&self) -> PathBuf {
+        self.snapshot_root.join("stable_memory.bin")
+    }
 }
 
 fn open_for_write(path: &Path) -> Result<std::fs::File, LayoutError> {
@@ -1601,6 +1724,8 @@ impl From<CanisterStateBits> for pb_canister_state_bits::CanisterStateBits {
             skipped_round_due_to_no_messages: item.skipped_round_due_to_no_messages,
             executed: item.executed,
             interruped_during_execution: item.interruped_during_execution,
+            skipped_round_due_to_limits: item.skipped_round_due_to_limits,
+            slices_executed: item.slices_executed,
             certified_data: item.certified_data.clone(),
             consumed_cycles_since_replica_started: Some(
                 (&item.consumed_cycles_since_replica_started).into(),
@@ -1621,6 +1746,17 @@ impl From<CanisterStateBits> for pb_canister_state_bits::CanisterStateBits {
                 })
                 .collect(),
             canister_history: Some((&item.canister_history).into()),
+            log_visibility: pb_canister_state_bits::LogVisibility::from(&item.log_visibility)
+                .into(),
+            canister_log_records: item
+                .canister_log
+                .records()
+                .iter()
+                .map(|record| record.into())
+                .collect(),
+            next_canister_log_record_idx: item.canister_log.next_idx(),
+            wasm_chunk_store_metadata: Some((&item.wasm_chunk_store_metadata).into()),
+            wasm_memory_limit: item.wasm_memory_limit.map(|limit| limit.get()),
         }
     }
 }
@@ -1694,6 +1830,8 @@ impl TryFrom<pb_canister_state_bits::CanisterStateBits> for CanisterStateBits {
             skipped_round_due_to_no_messages: value.skipped_round_due_to_no_messages,
             executed: value.executed,
             interruped_during_execution: value.interruped_during_execution,
+            skipped_round_due_to_limits: value.skipped_round_due_to_limits,
+            slices_executed: value.slices_executed,
             certified_data: value.certified_data,
             consumed_cycles_since_replica_started,
             stable_memory_size: NumWasmPages::from(value.stable_memory_size64 as usize),
@@ -1724,6 +1862,23 @@ impl TryFrom<pb_canister_state_bits::CanisterStateBits> for CanisterStateBits {
                 "CanisterStateBits::canister_history",
             )
             .unwrap_or_default(),
+            log_visibility: pb_canister_state_bits::LogVisibility::from_i32(value.log_visibility)
+                .unwrap_or(pb_canister_state_bits::LogVisibility::Unspecified)
+                .into(),
+            canister_log: CanisterLog::new(
+                value.next_canister_log_record_idx,
+                value
+                    .canister_log_records
+                    .into_iter()
+                    .map(|record| record.into())
+                    .collect(),
+            ),
+            wasm_chunk_store_metadata: value
+                .wasm_chunk_store_metadata
+                .map(|metadata| metadata.try_into())
+                .transpose()?
+                .unwrap_or_default(),
+            wasm_memory_limit: value.wasm_memory_limit.map(NumBytes::from),
         })
     }
 }
@@ -1791,6 +1946,40 @@ impl TryFrom<pb_canister_state_bits::ExecutionStateBits> for ExecutionStateBits
     }
 }
 
+impl From<CanisterSnapshotBits> for pb_canister_state_bits::CanisterSnapshotBits {
+    fn from(item: CanisterSnapshotBits) -> Self {
+        Self {
+            snapshot_id: item.snapshot_id.local_id(),
+            canister_id: Some(item.snapshot_id.canister_id().into()),
+            taken_at_timestamp: item.taken_at_timestamp.as_nanos_since_unix_epoch(),
+            canister_version: item.canister_version,
+            certified_data: item.certified_data,
+            execution_state_bits: Some((&item.execution_state_bits).into()),
+            stable_memory_size: item.stable_memory_size.get() as u64,
+        }
+    }
+}
+
+impl TryFrom<pb_canister_state_bits::CanisterSnapshotBits> for CanisterSnapshotBits {
+    type Error = ProxyDecodeError;
+    fn try_from(value: pb_canister_state_bits::CanisterSnapshotBits) -> Result<Self, Self::Error> {
+        let canister_id: CanisterId =
+            try_from_option_field(value.canister_id, "CanisterSnapshotBits::canister_id")?;
+        let execution_state_bits: ExecutionStateBits = try_from_option_field(
+            value.execution_state_bits,
+            "CanisterSnapshotBits::execution_state_bits",
+        )?;
+        Ok(Self {
+            snapshot_id: SnapshotId::new(canister_id, value.snapshot_id),
+            taken_at_timestamp: Time::from_nanos_since_unix_epoch(value.taken_at_timestamp),
+            canister_version: value.canister_version,
+            certified_data: value.certified_data,
+            execution_state_bits,
+            stable_memory_size: NumWasmPages::from(value.stable_memory_size as usize),
+        })
+    }
+}
+
 fn dir_file_names(p: &Path) -> std::io::Result<Vec<String>> {
     if !p.exists() {
         return Ok(vec![]);
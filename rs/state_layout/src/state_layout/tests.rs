@@ -4,7 +4,10 @@ use ic_ic00_types::{
     CanisterChange, CanisterChangeDetails, CanisterChangeOrigin, CanisterInstallMode, IC_00,
 };
 use ic_interfaces::messages::{CanisterCall, CanisterMessage, CanisterMessageOrTask};
-use ic_replicated_state::canister_state::system_state::CanisterHistory;
+use ic_replicated_state::canister_state::system_state::{
+    wasm_chunk_store::WasmChunkStore, CanisterHistory,
+};
+use ic_replicated_state::page_map::TestPageAllocatorFileDescriptorImpl;
 use ic_test_utilities::types::ids::user_test_id;
 use ic_test_utilities::{
     mock_time,
@@ -34,6 +37,8 @@ fn default_canister_state_bits() -> CanisterStateBits {
         skipped_round_due_to_no_messages: 0,
         executed: 0,
         interruped_during_execution: 0,
+        skipped_round_due_to_limits: 0,
+        slices_executed: 0,
         certified_data: vec![],
         consumed_cycles_since_replica_started: NominalCycles::from(0),
         stable_memory_size: NumWasmPages::from(0),
@@ -45,6 +50,10 @@ fn default_canister_state_bits() -> CanisterStateBits {
         canister_version: 0,
         consumed_cycles_since_replica_started_by_use_cases: BTreeMap::new(),
         canister_history: CanisterHistory::default(),
+        log_visibility: LogVisibility::default(),
+        canister_log: CanisterLog::default(),
+        wasm_chunk_store_metadata: WasmChunkStoreMetadata::default(),
+        wasm_memory_limit: None,
     }
 }
 
@@ -226,6 +235,42 @@ fn test_encode_decode_task_queue() {
     assert_eq!(canister_state_bits.task_queue, task_queue);
 }
 
+#[test]
+fn test_encode_decode_canister_log() {
+    let mut canister_log = CanisterLog::default();
+    canister_log.add_record(111, b"hello".to_vec());
+    canister_log.add_record(222, b"world".to_vec());
+    let canister_state_bits = CanisterStateBits {
+        log_visibility: LogVisibility::Public,
+        canister_log: canister_log.clone(),
+        ..default_canister_state_bits()
+    };
+
+    let pb_bits = pb_canister_state_bits::CanisterStateBits::from(canister_state_bits);
+    let canister_state_bits = CanisterStateBits::try_from(pb_bits).unwrap();
+    assert_eq!(canister_state_bits.log_visibility, LogVisibility::Public);
+    assert_eq!(canister_state_bits.canister_log, canister_log);
+}
+
+#[test]
+fn test_encode_decode_wasm_chunk_store_metadata() {
+    let mut wasm_chunk_store = WasmChunkStore::default();
+    let fd_factory = Arc::new(TestPageAllocatorFileDescriptorImpl::new());
+    wasm_chunk_store.insert_chunk(&[1, 2, 3], fd_factory.clone());
+    wasm_chunk_store.insert_chunk(&[4, 5, 6], fd_factory);
+    let canister_state_bits = CanisterStateBits {
+        wasm_chunk_store_metadata: wasm_chunk_store.metadata().clone(),
+        ..default_canister_state_bits()
+    };
+
+    let pb_bits = pb_canister_state_bits::CanisterStateBits::from(canister_state_bits);
+    let canister_state_bits = CanisterStateBits::try_from(pb_bits).unwrap();
+    assert_eq!(
+        &canister_state_bits.wasm_chunk_store_metadata,
+        wasm_chunk_store.metadata()
+    );
+}
+
 #[test]
 fn test_removal_when_last_dropped() {
     with_test_replica_logger(|log| {
@@ -342,3 +387,74 @@ fn test_canister_id_from_path() {
         canister_id_from_path(Path::new("canister_states/not-a-canister-ID/queues.pbuf"))
     );
 }
+
+#[test]
+fn test_snapshot_ids_and_filter_tip_snapshots() {
+    with_test_replica_logger(|log| {
+        let tempdir = tmpdir("state_layout");
+        let root_path = tempdir.path().to_path_buf();
+        let metrics_registry = ic_metrics::MetricsRegistry::new();
+        let state_layout = StateLayout::try_new(log, root_path, &metrics_registry).unwrap();
+        let mut tip_handler = state_layout.capture_tip_handler();
+        let height = Height::new(1);
+
+        let snapshot_1 = SnapshotId::new(canister_test_id(1), 0);
+        let snapshot_2 = SnapshotId::new(canister_test_id(1), 17);
+        let snapshot_3 = SnapshotId::new(canister_test_id(2), 1);
+        {
+            let tip = tip_handler.tip(height).unwrap();
+            assert_eq!(tip.snapshot_ids().unwrap(), vec![]);
+            for snapshot_id in [snapshot_3, snapshot_1, snapshot_2] {
+                tip.snapshot(&snapshot_id).unwrap();
+            }
+            assert_eq!(
+                tip.snapshot_ids().unwrap(),
+                vec![snapshot_1, snapshot_2, snapshot_3]
+            );
+        }
+
+        tip_handler
+            .filter_tip_snapshots(height, &BTreeSet::from([snapshot_2]))
+            .unwrap();
+        assert_eq!(
+            tip_handler.tip(height).unwrap().snapshot_ids().unwrap(),
+            vec![snapshot_2]
+        );
+    });
+}
+
+#[test]
+fn test_encode_decode_snapshot_bits() {
+    let snapshot_bits = CanisterSnapshotBits {
+        snapshot_id: SnapshotId::new(canister_test_id(1), 42),
+        taken_at_timestamp: mock_time(),
+        canister_version: 7,
+        certified_data: vec![1, 2, 3],
+        execution_state_bits: ExecutionStateBits {
+            exported_globals: vec![Global::I64(13)],
+            heap_size: NumWasmPages::from(3),
+            exports: ExportedFunctions::new(BTreeSet::new()),
+            last_executed_round: ExecutionRound::from(0),
+            metadata: WasmMetadata::default(),
+            binary_hash: None,
+            next_scheduled_method: NextScheduledMethod::default(),
+        },
+        stable_memory_size: NumWasmPages::from(5),
+    };
+
+    let pb_bits = pb_canister_state_bits::CanisterSnapshotBits::from(snapshot_bits);
+    let snapshot_bits = CanisterSnapshotBits::try_from(pb_bits).unwrap();
+
+    assert_eq!(
+        snapshot_bits.snapshot_id,
+        SnapshotId::new(canister_test_id(1), 42)
+    );
+    assert_eq!(snapshot_bits.taken_at_timestamp, mock_time());
+    assert_eq!(snapshot_bits.canister_version, 7);
+    assert_eq!(snapshot_bits.certified_data, vec![1, 2, 3]);
+    assert_eq!(
+        snapshot_bits.execution_state_bits.heap_size,
+        NumWasmPages::from(3)
+    );
+    assert_eq!(snapshot_bits.stable_memory_size, NumWasmPages::from(5));
+}
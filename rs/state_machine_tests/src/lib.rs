@@ -1,3 +1,4 @@
+use curve25519_dalek::{constants::ED25519_BASEPOINT_TABLE, scalar::Scalar};
 use ic_config::flag_status::FlagStatus;
 use ic_config::{execution_environment::Config as HypervisorConfig, subnet_config::SubnetConfig};
 use ic_constants::{MAX_INGRESS_TTL, PERMITTED_DRIFT, SMALL_APP_SUBNET_MAX_SIZE};
@@ -9,18 +10,24 @@ use ic_crypto_internal_threshold_sig_bls12381::api::{
 };
 use ic_crypto_internal_threshold_sig_bls12381::types::SecretKeyBytes;
 use ic_crypto_internal_types::sign::threshold_sig::public_key::CspThresholdSigPublicKey;
+use ic_crypto_sha::Sha512;
 use ic_crypto_tree_hash::{flatmap, Label, LabeledTree, LabeledTree::SubTree};
+use ic_crypto_tschnorr::derive_ed25519_key_offset;
 use ic_cycles_account_manager::CyclesAccountManager;
 pub use ic_error_types::{ErrorCode, UserError};
-use ic_execution_environment::ExecutionServices;
+use ic_execution_environment::{util::folded_instruction_profile, ExecutionServices};
 use ic_ic00_types::{self as ic00, CanisterIdRecord, InstallCodeArgs, Method, Payload};
 pub use ic_ic00_types::{
     CanisterHttpResponsePayload, CanisterInstallMode, CanisterSettingsArgs, ECDSAPublicKeyResponse,
-    EcdsaCurve, EcdsaKeyId, HttpHeader, HttpMethod, SignWithECDSAReply, UpdateSettingsArgs,
+    EcdsaCurve, EcdsaKeyId, HttpHeader, HttpMethod, SchnorrAlgorithm, SchnorrKeyId,
+    SchnorrPublicKeyResponse, SignWithECDSAReply, SignWithSchnorrReply, UpdateSettingsArgs,
 };
 use ic_interfaces::{
     certification::{Verifier, VerifierError},
-    execution_environment::{IngressHistoryReader, QueryHandler},
+    execution_environment::{
+        CrossSubnetQuery, CrossSubnetQueryClient, CrossSubnetQueryResponse, IngressHistoryReader,
+        QueryHandler,
+    },
     messaging::MessageRouting,
     validation::ValidationResult,
 };
@@ -53,7 +60,9 @@ use ic_registry_routing_table::{
 use ic_registry_subnet_features::{EcdsaConfig, SubnetFeatures, DEFAULT_ECDSA_MAX_QUEUE_SIZE};
 use ic_registry_subnet_type::SubnetType;
 use ic_replicated_state::canister_state::system_state::CyclesUseCase;
-use ic_replicated_state::metadata_state::subnet_call_context_manager::SignWithEcdsaContext;
+use ic_replicated_state::metadata_state::subnet_call_context_manager::{
+    SignWithEcdsaContext, SignWithSchnorrContext,
+};
 use ic_replicated_state::page_map::Buffer;
 use ic_replicated_state::{
     canister_state::{NumWasmPages, WASM_PAGE_SIZE_IN_BYTES},
@@ -70,11 +79,13 @@ use ic_types::consensus::certification::CertificationContent;
 use ic_types::crypto::threshold_sig::ni_dkg::{NiDkgId, NiDkgTag, NiDkgTargetSubnet};
 pub use ic_types::crypto::threshold_sig::ThresholdSigPublicKey;
 use ic_types::crypto::{
-    canister_threshold_sig::MasterEcdsaPublicKey, AlgorithmId, CombinedThresholdSig,
-    CombinedThresholdSigOf, Signable, Signed,
+    canister_threshold_sig::{
+        ExtendedDerivationPath, MasterEcdsaPublicKey, MasterSchnorrPublicKey,
+    },
+    AlgorithmId, CombinedThresholdSig, CombinedThresholdSigOf, Signable, Signed,
 };
 use ic_types::malicious_flags::MaliciousFlags;
-use ic_types::messages::{CallbackId, Certificate, Response};
+use ic_types::messages::{CallbackId, Certificate, Response, NO_DEADLINE};
 use ic_types::signature::ThresholdSignature;
 use ic_types::time::GENESIS;
 use ic_types::{
@@ -100,7 +111,7 @@ use std::io::stderr;
 use std::path::Path;
 use std::str::FromStr;
 use std::string::ToString;
-use std::sync::Arc;
+use std::sync::{Arc, Mutex, RwLock, Weak};
 use std::time::{Duration, Instant, SystemTime};
 use std::{collections::BTreeMap, convert::TryFrom};
 use std::{fmt, io};
@@ -261,6 +272,101 @@ fn replica_logger() -> ReplicaLogger {
     logger.into()
 }
 
+/// Connects the `StateMachine`s of several subnets so that composite queries
+/// can call canisters across them. It stands in for the HTTP endpoints through
+/// which replicas forward query calls to other subnets.
+#[derive(Default)]
+pub struct CrossSubnetQueryRouter {
+    subnets: RwLock<
+        BTreeMap<
+            SubnetId,
+            (
+                Weak<dyn QueryHandler<State = ReplicatedState>>,
+                Weak<StateManagerImpl>,
+            ),
+        >,
+    >,
+    /// The number of query calls each subnet executes at once, like the query
+    /// threads of the XNet endpoint. Further calls are rejected rather than
+    /// queued. Unlimited if `None`.
+    max_concurrent_queries: RwLock<Option<usize>>,
+    /// The number of query calls each subnet is currently executing.
+    queries_in_flight: Mutex<BTreeMap<SubnetId, usize>>,
+    /// The query calls forwarded so far, along with their callee subnet.
+    forwarded_queries: Mutex<Vec<(SubnetId, CrossSubnetQuery)>>,
+}
+
+impl CrossSubnetQueryRouter {
+    fn register(
+        &self,
+        subnet_id: SubnetId,
+        query_handler: &Arc<dyn QueryHandler<State = ReplicatedState>>,
+        state_manager: &Arc<StateManagerImpl>,
+    ) {
+        self.subnets.write().unwrap().insert(
+            subnet_id,
+            (Arc::downgrade(query_handler), Arc::downgrade(state_manager)),
+        );
+    }
+
+    /// Makes the given subnet unreachable for query calls from other subnets.
+    pub fn disconnect(&self, subnet_id: SubnetId) {
+        self.subnets.write().unwrap().remove(&subnet_id);
+    }
+
+    /// Limits the number of query calls each subnet executes at once.
+    pub fn set_max_concurrent_queries(&self, max_concurrent_queries: usize) {
+        *self.max_concurrent_queries.write().unwrap() = Some(max_concurrent_queries);
+    }
+
+    /// Returns the query calls forwarded so far, along with their callee
+    /// subnet.
+    pub fn forwarded_queries(&self) -> Vec<(SubnetId, CrossSubnetQuery)> {
+        self.forwarded_queries.lock().unwrap().clone()
+    }
+}
+
+impl CrossSubnetQueryClient for CrossSubnetQueryRouter {
+    fn query(
+        &self,
+        subnet_id: SubnetId,
+        query: CrossSubnetQuery,
+    ) -> Result<CrossSubnetQueryResponse, String> {
+        // Release the lock before executing the query, which may call back
+        // into this router.
+        let subnet = self.subnets.read().unwrap().get(&subnet_id).and_then(
+            |(query_handler, state_manager)| {
+                Some((query_handler.upgrade()?, state_manager.upgrade()?))
+            },
+        );
+        let (query_handler, state_manager) =
+            subnet.ok_or_else(|| format!("subnet {} is not connected", subnet_id))?;
+        self.forwarded_queries
+            .lock()
+            .unwrap()
+            .push((subnet_id, query.clone()));
+
+        let max_concurrent_queries = *self.max_concurrent_queries.read().unwrap();
+        {
+            let mut queries_in_flight = self.queries_in_flight.lock().unwrap();
+            let in_flight = queries_in_flight.entry(subnet_id).or_default();
+            if max_concurrent_queries.map_or(false, |max| *in_flight >= max) {
+                return Err(format!("subnet {} is saturated", subnet_id));
+            }
+            *in_flight += 1;
+        }
+        let response =
+            query_handler.cross_subnet_query(query, state_manager.get_latest_state().take());
+        *self
+            .queries_in_flight
+            .lock()
+            .unwrap()
+            .get_mut(&subnet_id)
+            .unwrap() -= 1;
+        Ok(response)
+    }
+}
+
 /// Bundles the configuration of a `StateMachine`.
 #[derive(Clone)]
 pub struct StateMachineConfig {
@@ -284,6 +390,8 @@ pub struct StateMachine {
     public_key: ThresholdSigPublicKey,
     secret_key: SecretKeyBytes,
     ecdsa_secret_key: PrivateKey,
+    schnorr_bip340_secret_key: PrivateKey,
+    schnorr_ed25519_secret_key: Scalar,
     registry_data_provider: Arc<ProtoRegistryDataProvider>,
     registry_client: Arc<FakeRegistryClient>,
     pub state_manager: Arc<StateManagerImpl>,
@@ -297,6 +405,7 @@ pub struct StateMachine {
     nonce: std::cell::Cell<u64>,
     time: std::cell::Cell<Time>,
     ecdsa_subnet_public_keys: BTreeMap<EcdsaKeyId, MasterEcdsaPublicKey>,
+    schnorr_subnet_public_keys: BTreeMap<SchnorrKeyId, MasterSchnorrPublicKey>,
 }
 
 impl Default for StateMachine {
@@ -327,7 +436,9 @@ pub struct StateMachineBuilder {
     routing_table: RoutingTable,
     use_cost_scaling_flag: bool,
     ecdsa_keys: Vec<EcdsaKeyId>,
+    schnorr_keys: Vec<SchnorrKeyId>,
     features: SubnetFeatures,
+    cross_subnet_query_router: Option<Arc<CrossSubnetQueryRouter>>,
 }
 
 impl StateMachineBuilder {
@@ -349,10 +460,21 @@ impl StateMachineBuilder {
                 curve: EcdsaCurve::Secp256k1,
                 name: "master_ecdsa_public_key".to_string(),
             }],
+            schnorr_keys: vec![
+                SchnorrKeyId {
+                    algorithm: SchnorrAlgorithm::Bip340Secp256k1,
+                    name: "master_schnorr_public_key".to_string(),
+                },
+                SchnorrKeyId {
+                    algorithm: SchnorrAlgorithm::Ed25519,
+                    name: "master_schnorr_public_key".to_string(),
+                },
+            ],
             features: SubnetFeatures {
                 http_requests: true,
                 ..SubnetFeatures::default()
             },
+            cross_subnet_query_router: None,
         }
     }
 
@@ -453,10 +575,28 @@ impl StateMachineBuilder {
         Self { ecdsa_keys, ..self }
     }
 
+    pub fn with_schnorr_key(self, key: SchnorrKeyId) -> Self {
+        let mut schnorr_keys = self.schnorr_keys;
+        schnorr_keys.push(key);
+        Self {
+            schnorr_keys,
+            ..self
+        }
+    }
+
     pub fn with_features(self, features: SubnetFeatures) -> Self {
         Self { features, ..self }
     }
 
+    /// Connects the state machine to the other subnets registered with the
+    /// given router, for composite queries across subnets.
+    pub fn with_cross_subnet_query_router(self, router: Arc<CrossSubnetQueryRouter>) -> Self {
+        Self {
+            cross_subnet_query_router: Some(router),
+            ..self
+        }
+    }
+
     pub fn build(self) -> StateMachine {
         StateMachine::setup_from_dir(
             self.state_dir,
@@ -471,7 +611,9 @@ impl StateMachineBuilder {
             self.routing_table,
             self.use_cost_scaling_flag,
             self.ecdsa_keys,
+            self.schnorr_keys,
             self.features,
+            self.cross_subnet_query_router,
         )
     }
 }
@@ -511,7 +653,9 @@ impl StateMachine {
         routing_table: RoutingTable,
         use_cost_scaling_flag: bool,
         ecdsa_keys: Vec<EcdsaKeyId>,
+        schnorr_keys: Vec<SchnorrKeyId>,
         features: SubnetFeatures,
+        cross_subnet_query_router: Option<Arc<CrossSubnetQueryRouter>>,
     ) -> Self {
         let replica_logger = replica_logger();
 
@@ -585,8 +729,18 @@ impl StateMachine {
                 Arc::clone(&cycles_account_manager),
                 Arc::clone(&state_manager) as Arc<_>,
                 Arc::clone(&state_manager.get_fd_factory()),
+                cross_subnet_query_router
+                    .clone()
+                    .map(|router| router as Arc<dyn CrossSubnetQueryClient>),
             )
         });
+        if let Some(router) = &cross_subnet_query_router {
+            router.register(
+                subnet_id,
+                &execution_services.sync_query_handler,
+                &state_manager,
+            );
+        }
 
         let message_routing = MessageRoutingImpl::new(
             Arc::clone(&state_manager) as _,
@@ -653,11 +807,47 @@ impl StateMachine {
             },
         );
 
+        // Like the ECDSA key above, the Schnorr keys are fixed to have
+        // deterministic results. Please do not use these private keys anywhere.
+        let schnorr_bip340_secret_key = PrivateKey::deserialize_sec1(
+            &hex::decode("8069a8bcf52c517b85ef5beb508f229d0303d0c9ff9718b42ee5beb4d4fb15b8")
+                .unwrap(),
+        )
+        .unwrap();
+        let mut ed25519_secret_key_bytes = [0u8; 32];
+        ed25519_secret_key_bytes.copy_from_slice(
+            &hex::decode("21cd6b128358a26b1bd8abe4e91de6da2b11eb136a190d5363a3fa6af4a4e6eb")
+                .unwrap(),
+        );
+        let schnorr_ed25519_secret_key = Scalar::from_bytes_mod_order(ed25519_secret_key_bytes);
+
+        let schnorr_subnet_public_keys = schnorr_keys
+            .into_iter()
+            .map(|key_id| {
+                let public_key = match key_id.algorithm {
+                    SchnorrAlgorithm::Bip340Secp256k1 => MasterSchnorrPublicKey {
+                        algorithm_id: AlgorithmId::SchnorrSecp256k1,
+                        public_key: schnorr_bip340_secret_key.public_key().serialize_sec1(true),
+                    },
+                    SchnorrAlgorithm::Ed25519 => MasterSchnorrPublicKey {
+                        algorithm_id: AlgorithmId::Ed25519,
+                        public_key: (&schnorr_ed25519_secret_key * &ED25519_BASEPOINT_TABLE)
+                            .compress()
+                            .to_bytes()
+                            .to_vec(),
+                    },
+                };
+                (key_id, public_key)
+            })
+            .collect();
+
         Self {
             subnet_id,
             secret_key: secret_key_bytes.get(0).unwrap().clone(),
             public_key,
             ecdsa_secret_key,
+            schnorr_bip340_secret_key,
+            schnorr_ed25519_secret_key,
             registry_data_provider,
             registry_client,
             state_manager,
@@ -673,6 +863,7 @@ impl StateMachine {
             nonce: std::cell::Cell::new(nonce),
             time: std::cell::Cell::new(time),
             ecdsa_subnet_public_keys,
+            schnorr_subnet_public_keys,
         }
     }
 
@@ -788,6 +979,41 @@ impl StateMachine {
                 originator_reply_callback: id,
                 refund: Cycles::zero(),
                 response_payload: MsgPayload::Data(reply.encode()),
+                deadline: NO_DEADLINE,
+            });
+        }
+        let sign_with_schnorr_contexts = state
+            .metadata
+            .subnet_call_context_manager
+            .sign_with_schnorr_contexts
+            .clone();
+        for (id, schnorr_context) in sign_with_schnorr_contexts {
+            let derivation_path = ExtendedDerivationPath {
+                caller: schnorr_context.request.sender.get(),
+                derivation_path: schnorr_context.derivation_path.clone(),
+            };
+            let signature = match schnorr_context.key_id.algorithm {
+                SchnorrAlgorithm::Bip340Secp256k1 => sign_with_derived_bip340_key(
+                    &self.schnorr_bip340_secret_key,
+                    &schnorr_context.message,
+                    &derivation_path,
+                ),
+                SchnorrAlgorithm::Ed25519 => sign_with_derived_ed25519_key(
+                    &self.schnorr_ed25519_secret_key,
+                    &schnorr_context.message,
+                    &derivation_path,
+                ),
+            };
+
+            let reply = SignWithSchnorrReply { signature };
+
+            payload.consensus_responses.push(Response {
+                originator: CanisterId::ic_00(),
+                respondent: CanisterId::ic_00(),
+                originator_reply_callback: id,
+                refund: Cycles::zero(),
+                response_payload: MsgPayload::Data(reply.encode()),
+                deadline: NO_DEADLINE,
             });
         }
         self.execute_payload(payload)
@@ -841,6 +1067,7 @@ impl StateMachine {
             },
             randomness: Randomness::from(seed),
             ecdsa_subnet_public_keys: self.ecdsa_subnet_public_keys.clone(),
+            schnorr_subnet_public_keys: self.schnorr_subnet_public_keys.clone(),
             registry_version: self.registry_client.get_latest_version(),
             time: self.time.get(),
             consensus_responses: payload.consensus_responses,
@@ -1456,6 +1683,28 @@ impl StateMachine {
         )
     }
 
+    /// Returns the instructions executed by the functions of the specified
+    /// canister in the folded stack format, which flamegraph tools can render.
+    /// The profile covers all update executions since the module was
+    /// installed, and is only recorded if `canister_profiling` is enabled in
+    /// the hypervisor config.
+    ///
+    /// # Panics
+    ///
+    /// This function panics if:
+    ///   * The specified canister does not exist.
+    ///   * The specified canister does not have a module installed.
+    pub fn instruction_profile(&self, canister_id: CanisterId) -> String {
+        let state = self.state_manager.get_latest_state().take();
+        let execution_state = state
+            .canister_state(&canister_id)
+            .unwrap_or_else(|| panic!("Canister {} does not exist", canister_id))
+            .execution_state
+            .as_ref()
+            .unwrap_or_else(|| panic!("Canister {} has no module", canister_id));
+        folded_instruction_profile(execution_state)
+    }
+
     /// Executes an ingress message on the canister with the specified ID.
     ///
     /// This function is synchronous, it blocks until the result of the ingress
@@ -1788,6 +2037,16 @@ impl StateMachine {
             .clone()
     }
 
+    /// Returns sign with Schnorr contexts from internal subnet call context manager.
+    pub fn sign_with_schnorr_contexts(&self) -> BTreeMap<CallbackId, SignWithSchnorrContext> {
+        let state = self.state_manager.get_latest_state().take();
+        state
+            .metadata
+            .subnet_call_context_manager
+            .sign_with_schnorr_contexts
+            .clone()
+    }
+
     /// Returns canister HTTP request contexts from internal subnet call context manager.
     pub fn canister_http_request_contexts(
         &self,
@@ -1831,6 +2090,75 @@ fn sign_message_with_derived_key(
     signature.to_vec()
 }
 
+/// Signs `message` with the BIP340 key derived from `secret_key`, using the
+/// same derivation as threshold ECDSA.
+fn sign_with_derived_bip340_key(
+    secret_key: &PrivateKey,
+    message: &[u8],
+    derivation_path: &ExtendedDerivationPath,
+) -> Vec<u8> {
+    let derivation_path = DerivationPath::new(
+        std::iter::once(derivation_path.caller.as_slice().to_vec())
+            .chain(derivation_path.derivation_path.iter().cloned())
+            .map(DerivationIndex)
+            .collect(),
+    );
+    let derived_private_key_bytes = derivation_path
+        .private_key_derivation(&secret_key.serialize_sec1(), &[0; 32])
+        .expect("couldn't derive bip340 private key");
+    let signing_key =
+        k256::schnorr::SigningKey::from_bytes(&derived_private_key_bytes.derived_private_key)
+            .expect("couldn't deserialize bip340 private key");
+    let signature = signing_key
+        .sign_raw(message, &[0; 32])
+        .expect("couldn't sign with bip340 key");
+    signing_key
+        .verifying_key()
+        .verify_raw(message, &signature)
+        .expect("couldn't verify bip340 signature");
+    signature.to_bytes().to_vec()
+}
+
+/// Signs `message` with the Ed25519 key derived from `secret_key`, i.e., with
+/// the secret scalar `secret_key + offset`.
+///
+/// Since there is no seed for the derived key, the nonce is derived from the
+/// secret scalar, the offset, and the message instead of the hashed seed.
+fn sign_with_derived_ed25519_key(
+    secret_key: &Scalar,
+    message: &[u8],
+    derivation_path: &ExtendedDerivationPath,
+) -> Vec<u8> {
+    let master_public_key = (secret_key * &ED25519_BASEPOINT_TABLE).compress();
+    let offset = derive_ed25519_key_offset(master_public_key.as_bytes(), derivation_path)
+        .expect("couldn't derive ed25519 key offset");
+    let derived_secret_key = secret_key + Scalar::from_bytes_mod_order(offset);
+    let derived_public_key = (&derived_secret_key * &ED25519_BASEPOINT_TABLE).compress();
+
+    let mut nonce_hash = Sha512::new();
+    nonce_hash.write(secret_key.as_bytes());
+    nonce_hash.write(&offset);
+    nonce_hash.write(message);
+    let nonce = Scalar::from_bytes_mod_order_wide(&nonce_hash.finish());
+    let nonce_commitment = (&nonce * &ED25519_BASEPOINT_TABLE).compress();
+
+    let mut challenge_hash = Sha512::new();
+    challenge_hash.write(nonce_commitment.as_bytes());
+    challenge_hash.write(derived_public_key.as_bytes());
+    challenge_hash.write(message);
+    let challenge = Scalar::from_bytes_mod_order_wide(&challenge_hash.finish());
+
+    let mut signature = [0u8; 64];
+    signature[..32].copy_from_slice(nonce_commitment.as_bytes());
+    signature[32..].copy_from_slice((nonce + challenge * derived_secret_key).as_bytes());
+
+    ed25519_consensus::VerificationKey::try_from(derived_public_key.to_bytes())
+        .expect("couldn't deserialize ed25519 public key")
+        .verify(&ed25519_consensus::Signature::from(signature), message)
+        .expect("couldn't verify ed25519 signature");
+    signature.to_vec()
+}
+
 #[derive(Clone)]
 pub struct PayloadBuilder {
     expiry_time: Time,
@@ -1918,6 +2246,7 @@ impl PayloadBuilder {
             originator_reply_callback: id,
             refund: Cycles::zero(),
             response_payload: MsgPayload::Data(payload.encode()),
+            deadline: NO_DEADLINE,
         });
         self
     }
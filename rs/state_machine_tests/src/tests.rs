@@ -1,5 +1,9 @@
 use ic_crypto_ecdsa_secp256k1::{PrivateKey, PublicKey};
 use ic_crypto_extended_bip32::{DerivationIndex, DerivationPath};
+use ic_crypto_tschnorr::derive_tschnorr_public_key;
+use ic_types::crypto::canister_threshold_sig::{ExtendedDerivationPath, MasterSchnorrPublicKey};
+use ic_types::crypto::AlgorithmId;
+use ic_types::PrincipalId;
 use proptest::{collection::vec as pvec, prelude::*, prop_assert, proptest};
 
 proptest! {
@@ -32,4 +36,53 @@ proptest! {
             .expect("couldn't deserialize sec1");
         prop_assert!(derived_public_key.verify_signature(&message_hash, &signature));
     }
+
+    #[test]
+    fn test_schnorr_derivation_prop(
+        caller in any::<u64>(),
+        derivation_path_bytes in pvec(pvec(any::<u8>(), 0..10), 0..10),
+        message in pvec(any::<u8>(), 32)
+    ) {
+        let derivation_path = ExtendedDerivationPath {
+            caller: PrincipalId::new_user_test_id(caller),
+            derivation_path: derivation_path_bytes,
+        };
+
+        let bip340_secret_key = PrivateKey::deserialize_sec1(
+            &hex::decode("8069a8bcf52c517b85ef5beb508f229d0303d0c9ff9718b42ee5beb4d4fb15b8").unwrap(),
+        )
+        .unwrap();
+        let signature = crate::sign_with_derived_bip340_key(&bip340_secret_key, &message, &derivation_path);
+        let derived_public_key = derive_tschnorr_public_key(
+            &MasterSchnorrPublicKey {
+                algorithm_id: AlgorithmId::SchnorrSecp256k1,
+                public_key: bip340_secret_key.public_key().serialize_sec1(true),
+            },
+            &derivation_path,
+        )
+        .unwrap();
+        let verifying_key = k256::schnorr::VerifyingKey::from_bytes(&derived_public_key.public_key[1..]).unwrap();
+        let signature = k256::schnorr::Signature::try_from(signature.as_slice()).unwrap();
+        prop_assert!(verifying_key.verify_raw(&message, &signature).is_ok());
+
+        let ed25519_secret_key = curve25519_dalek::scalar::Scalar::from_bytes_mod_order([42; 32]);
+        let signature = crate::sign_with_derived_ed25519_key(&ed25519_secret_key, &message, &derivation_path);
+        let derived_public_key = derive_tschnorr_public_key(
+            &MasterSchnorrPublicKey {
+                algorithm_id: AlgorithmId::Ed25519,
+                public_key: (&ed25519_secret_key * &curve25519_dalek::constants::ED25519_BASEPOINT_TABLE)
+                    .compress()
+                    .to_bytes()
+                    .to_vec(),
+            },
+            &derivation_path,
+        )
+        .unwrap();
+        let verification_key = ed25519_consensus::VerificationKey::try_from(derived_public_key.public_key.as_slice()).unwrap();
+        let mut signature_bytes = [0u8; 64];
+        signature_bytes.copy_from_slice(&signature);
+        prop_assert!(verification_key
+            .verify(&ed25519_consensus::Signature::from(signature_bytes), &message)
+            .is_ok());
+    }
 }
@@ -4,14 +4,20 @@ use ic_base_types::{subnet_id_try_from_protobuf, CanisterId};
 // TODO(MR-412): uncomment
 //use ic_protobuf::proxy::try_from_option_field;
 use ic_registry_subnet_type::SubnetType;
+use ic_replicated_state::canister_state::system_state::wasm_chunk_store::WasmChunkStore;
 use ic_replicated_state::page_map::PageAllocatorFileDescriptor;
 use ic_replicated_state::Memory;
 use ic_replicated_state::{
-    canister_state::execution_state::WasmBinary, page_map::PageMap, CanisterMetrics, CanisterState,
-    ExecutionState, ReplicatedState, SchedulerState, SystemState,
+    canister_state::execution_state::WasmBinary, page_map::PageMap, CanisterMetrics,
+    CanisterSnapshot, CanisterSnapshots, CanisterState, ExecutionState, ReplicatedState,
+    SchedulerState, SnapshotId, SystemState,
+};
+use ic_state_layout::{
+    CanisterLayout, CanisterSnapshotBits, CanisterStateBits, CheckpointLayout, ReadOnly, ReadPolicy,
+};
+use ic_types::{
+    instruction_profile::InstructionProfile, CanisterTimer, Height, LongExecutionMode, Time,
 };
-use ic_state_layout::{CanisterLayout, CanisterStateBits, CheckpointLayout, ReadOnly, ReadPolicy};
-use ic_types::{CanisterTimer, Height, LongExecutionMode, Time};
 use ic_utils::thread::parallel_map;
 use std::collections::BTreeMap;
 use std::convert::TryFrom;
@@ -59,6 +65,13 @@ pub(crate) fn make_checkpoint(
         })
         .unwrap();
 
+    tip_channel
+        .send(TipRequest::FilterTipSnapshots {
+            height,
+            ids: state.canister_snapshots.snapshot_ids(),
+        })
+        .unwrap();
+
     let cp = {
         let _timer = metrics
             .make_checkpoint_step_duration
@@ -213,7 +226,33 @@ pub fn load_checkpoint<P: ReadPolicy + Send + Sync>(
         canister_states
     };
 
-    let state = ReplicatedState::new_from_checkpoint(canister_states, metadata, subnet_queues);
+    let canister_snapshots = {
+        let _timer = metrics
+            .load_checkpoint_step_duration
+            .with_label_values(&["canister_snapshots"])
+            .start_timer();
+
+        let mut canister_snapshots = BTreeMap::new();
+        for snapshot_id in checkpoint_layout.snapshot_ids()? {
+            let snapshot = load_snapshot_from_checkpoint(
+                checkpoint_layout,
+                &snapshot_id,
+                Arc::clone(&fd_factory),
+            )?;
+            canister_snapshots.insert(snapshot_id, Arc::new(snapshot));
+        }
+        CanisterSnapshots::new(canister_snapshots)
+    };
+
+    let mut state = ReplicatedState::new_from_checkpoint(canister_states, metadata, subnet_queues);
+    for canister in state.canister_states.values_mut() {
+        let snapshots_memory_usage =
+            canister_snapshots.memory_taken_by_canister(canister.canister_id());
+        canister
+            .system_state
+            .set_snapshots_memory_usage(snapshots_memory_usage);
+    }
+    state.canister_snapshots = canister_snapshots;
 
     Ok(state)
 }
@@ -308,6 +347,7 @@ pub fn load_canister_state<P: ReadPolicy>(
                 metadata: execution_state_bits.metadata,
                 last_executed_round: execution_state_bits.last_executed_round,
                 next_scheduled_method: execution_state_bits.next_scheduled_method,
+                instruction_profile: InstructionProfile::default(),
             })
         }
         None => None,
@@ -324,11 +364,31 @@ pub fn load_canister_state<P: ReadPolicy>(
             })?;
     durations.insert("canister_queues", starting_time.elapsed());
 
+    let starting_time = Instant::now();
+    // The chunk store file only holds data if there are chunks; otherwise it
+    // may be missing or truncated.
+    let wasm_chunk_store_data = if canister_state_bits.wasm_chunk_store_metadata.is_empty() {
+        None
+    } else {
+        Some(PageMap::open(
+            &canister_layout.wasm_chunk_store(),
+            height,
+            Arc::clone(&fd_factory),
+        )?)
+    };
+    let wasm_chunk_store = WasmChunkStore::new_from_checkpoint(
+        wasm_chunk_store_data,
+        canister_state_bits.wasm_chunk_store_metadata,
+    );
+    durations.insert("wasm_chunk_store", starting_time.elapsed());
+
     let canister_metrics = CanisterMetrics::new(
         canister_state_bits.scheduled_as_first,
         canister_state_bits.skipped_round_due_to_no_messages,
         canister_state_bits.executed,
         canister_state_bits.interruped_during_execution,
+        canister_state_bits.skipped_round_due_to_limits,
+        canister_state_bits.slices_executed,
         canister_state_bits.consumed_cycles_since_replica_started,
         canister_state_bits.consumed_cycles_since_replica_started_by_use_cases,
     );
@@ -347,6 +407,10 @@ pub fn load_canister_state<P: ReadPolicy>(
         CanisterTimer::from_nanos_since_unix_epoch(canister_state_bits.global_timer_nanos),
         canister_state_bits.canister_version,
         canister_state_bits.canister_history,
+        canister_state_bits.log_visibility,
+        canister_state_bits.canister_log,
+        wasm_chunk_store,
+        canister_state_bits.wasm_memory_limit,
     );
 
     let canister_state = CanisterState {
@@ -373,6 +437,71 @@ pub fn load_canister_state<P: ReadPolicy>(
     Ok((canister_state, metrics))
 }
 
+fn load_snapshot_from_checkpoint<P: ReadPolicy>(
+    checkpoint_layout: &CheckpointLayout<P>,
+    snapshot_id: &SnapshotId,
+    fd_factory: Arc<dyn PageAllocatorFileDescriptor>,
+) -> Result<CanisterSnapshot, CheckpointError> {
+    let snapshot_layout = checkpoint_layout.snapshot(snapshot_id)?;
+    let height = checkpoint_layout.height();
+    let canister_id = snapshot_id.canister_id();
+
+    let snapshot_bits: CanisterSnapshotBits = CanisterSnapshotBits::try_from(
+        snapshot_layout.snapshot().deserialize()?,
+    )
+    .map_err(|err| CheckpointError::ProtoError {
+        path: snapshot_layout.raw_path(),
+        field: format!("snapshots[{}]::snapshot_bits", snapshot_id),
+        proto_err: err.to_string(),
+    })?;
+    let execution_state_bits = snapshot_bits.execution_state_bits;
+
+    let wasm_memory = Memory::new(
+        PageMap::open(
+            &snapshot_layout.vmemory_0(),
+            height,
+            Arc::clone(&fd_factory),
+        )?,
+        execution_state_bits.heap_size,
+    );
+    let stable_memory = Memory::new(
+        PageMap::open(
+            &snapshot_layout.stable_memory_blob(),
+            height,
+            Arc::clone(&fd_factory),
+        )?,
+        snapshot_bits.stable_memory_size,
+    );
+    let wasm_binary = WasmBinary::new(
+        snapshot_layout
+            .wasm()
+            .deserialize(execution_state_bits.binary_hash)?,
+    );
+    let canister_root = CheckpointLayout::<ReadOnly>::new_untracked("NOT_USED".into(), height)?
+        .canister(&canister_id)?
+        .raw_path();
+
+    Ok(CanisterSnapshot::new(
+        canister_id,
+        snapshot_bits.taken_at_timestamp,
+        snapshot_bits.canister_version,
+        snapshot_bits.certified_data,
+        ExecutionState {
+            canister_root,
+            session_nonce: None,
+            wasm_binary,
+            wasm_memory,
+            stable_memory,
+            exported_globals: execution_state_bits.exported_globals,
+            exports: execution_state_bits.exports,
+            metadata: execution_state_bits.metadata,
+            last_executed_round: execution_state_bits.last_executed_round,
+            next_scheduled_method: execution_state_bits.next_scheduled_method,
+            instruction_profile: InstructionProfile::default(),
+        },
+    ))
+}
+
 fn load_canister_state_from_checkpoint<P: ReadPolicy>(
     checkpoint_layout: &CheckpointLayout<P>,
     canister_id: &CanisterId,
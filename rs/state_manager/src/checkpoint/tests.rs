@@ -24,8 +24,8 @@ use ic_test_utilities::{
 use ic_test_utilities_logger::with_test_replica_logger;
 use ic_test_utilities_tmpdir::tmpdir;
 use ic_types::{
-    malicious_flags::MaliciousFlags, messages::StopCanisterContext, CanisterId, Cycles,
-    ExecutionRound, Height,
+    instruction_profile::InstructionProfile, malicious_flags::MaliciousFlags,
+    messages::StopCanisterContext, CanisterId, Cycles, ExecutionRound, Height,
 };
 use ic_wasm_types::CanisterModule;
 use std::{collections::BTreeSet, fs::OpenOptions};
@@ -242,6 +242,7 @@ fn can_recover_from_a_checkpoint() {
             metadata: WasmMetadata::default(),
             last_executed_round: ExecutionRound::from(0),
             next_scheduled_method: NextScheduledMethod::default(),
+            instruction_profile: InstructionProfile::default(),
         };
 
         canister_state.execution_state = Some(execution_state);
@@ -969,6 +969,7 @@ struct PopulatedMetadata {
 pub enum PageMapType {
     WasmMemory(CanisterId),
     StableMemory(CanisterId),
+    WasmChunkStore(CanisterId),
 }
 
 impl PageMapType {
@@ -980,6 +981,9 @@ impl PageMapType {
                 result.push(Self::WasmMemory(id.to_owned()));
                 result.push(Self::StableMemory(id.to_owned()));
             }
+            if canister.system_state.wasm_chunk_store.page_map().is_some() {
+                result.push(Self::WasmChunkStore(id.to_owned()));
+            }
         }
 
         result
@@ -993,6 +997,7 @@ impl PageMapType {
         match &self {
             PageMapType::WasmMemory(id) => Ok(layout.canister(id)?.vmemory_0()),
             PageMapType::StableMemory(id) => Ok(layout.canister(id)?.stable_memory_blob()),
+            PageMapType::WasmChunkStore(id) => Ok(layout.canister(id)?.wasm_chunk_store()),
         }
     }
 
@@ -1009,6 +1014,9 @@ impl PageMapType {
                     .as_ref()
                     .map(|ex| &ex.stable_memory.page_map)
             }),
+            PageMapType::WasmChunkStore(id) => state
+                .canister_state(id)
+                .and_then(|can| can.system_state.wasm_chunk_store.page_map()),
         }
     }
 
@@ -1025,6 +1033,9 @@ impl PageMapType {
                     .as_mut()
                     .map(|ex| &mut ex.stable_memory.page_map)
             }),
+            PageMapType::WasmChunkStore(id) => state
+                .canister_state_mut(id)
+                .and_then(|can| can.system_state.wasm_chunk_store.page_map_mut()),
         }
     }
 }
@@ -1186,6 +1197,14 @@ fn switch_to_checkpoint(tip: &mut ReplicatedState, src: &ReplicatedState) {
             tip_state.stable_memory.sandbox_memory = SandboxMemory::new();
         }
     }
+
+    // Snapshots are immutable, so we can simply take over the ones backed by
+    // the checkpoint files.
+    assert_eq!(
+        tip.canister_snapshots.snapshot_ids(),
+        src.canister_snapshots.snapshot_ids()
+    );
+    tip.canister_snapshots = src.canister_snapshots.clone();
 }
 
 /// Persists metadata after releasing the write lock
@@ -9,12 +9,12 @@ use ic_logger::{error, fatal, info, ReplicaLogger};
 use ic_protobuf::state::system_metadata::v1::{SplitFrom, SystemMetadata};
 #[allow(unused)]
 use ic_replicated_state::{
-    canister_state::execution_state::SandboxMemory, CanisterState, NumWasmPages, PageMap,
-    ReplicatedState,
+    canister_state::execution_state::SandboxMemory, CanisterSnapshot, CanisterState, NumWasmPages,
+    PageMap, ReplicatedState, SnapshotId,
 };
 use ic_state_layout::{
-    error::LayoutError, CanisterStateBits, CheckpointLayout, ExecutionStateBits, ReadOnly,
-    RwPolicy, StateLayout, TipHandler,
+    error::LayoutError, CanisterSnapshotBits, CanisterStateBits, CheckpointLayout,
+    ExecutionStateBits, ReadOnly, RwPolicy, StateLayout, TipHandler,
 };
 use ic_types::state_sync::{
     FILE_GROUP_CHUNK_ID_OFFSET, MANIFEST_CHUNK_ID_OFFSET, MAX_SUPPORTED_STATE_SYNC_VERSION,
@@ -64,6 +64,12 @@ pub(crate) enum TipRequest {
         height: Height,
         ids: BTreeSet<CanisterId>,
     },
+    /// Filter canister snapshots in tip. Remove ones not present in the set.
+    /// State: !Empty
+    FilterTipSnapshots {
+        height: Height,
+        ids: BTreeSet<SnapshotId>,
+    },
     /// Truncate PageMaps's path.
     /// State: ReadyForPageDeltas(h) -> ReadyForPageDeltas(height), height >= h
     TruncatePageMapsPath {
@@ -163,6 +169,21 @@ pub(crate) fn spawn_tip_thread(
                                     )
                                 });
                         }
+                        TipRequest::FilterTipSnapshots { height, ids } => {
+                            debug_assert_ne!(tip_state, TipState::Empty);
+
+                            let _timer = request_timer(&metrics, "filter_tip_snapshots");
+                            tip_handler
+                                .filter_tip_snapshots(height, &ids)
+                                .unwrap_or_else(|err| {
+                                    fatal!(
+                                        log,
+                                        "Failed to filter tip snapshots for height @{}: {}",
+                                        height,
+                                        err
+                                    )
+                                });
+                        }
                         TipRequest::TipToCheckpoint { height, sender } => {
                             debug_assert_eq!(tip_state, TipState::Serialized(height));
                             debug_assert!(have_latest_manifest);
@@ -394,6 +415,82 @@ fn serialize_to_tip(
         result?;
     }
 
+    let results = parallel_map(
+        thread_pool,
+        state.canister_snapshots.iter(),
+        |(snapshot_id, snapshot)| serialize_snapshot_to_tip(log, snapshot_id, snapshot, tip),
+    );
+
+    for result in results.into_iter() {
+        result?;
+    }
+
+    Ok(())
+}
+
+fn serialize_snapshot_to_tip(
+    log: &ReplicaLogger,
+    snapshot_id: &SnapshotId,
+    snapshot: &CanisterSnapshot,
+    tip: &CheckpointLayout<RwPolicy<TipHandler>>,
+) -> Result<(), CheckpointError> {
+    let snapshot_layout = tip.snapshot(snapshot_id)?;
+    let execution_state = snapshot.execution_state();
+
+    let wasm = snapshot_layout.wasm();
+    if !wasm.raw_path().exists() {
+        match execution_state.wasm_binary.binary.file() {
+            Some(path) => {
+                ic_state_layout::utils::do_copy(log, path, wasm.raw_path()).map_err(|io_err| {
+                    CheckpointError::IoError {
+                        path: path.to_path_buf(),
+                        message: "failed to copy Wasm file".to_string(),
+                        io_err: io_err.to_string(),
+                    }
+                })?;
+            }
+            None => wasm.serialize(&execution_state.wasm_binary.binary)?,
+        }
+    }
+
+    // Snapshots are immutable, so their memories only need to be written once,
+    // when the snapshot is first checkpointed. Afterwards, they are copied over
+    // from the previous checkpoint when the tip is reset.
+    let vmemory_0 = snapshot_layout.vmemory_0();
+    if !vmemory_0.exists() {
+        execution_state
+            .wasm_memory
+            .page_map
+            .persist_all(&vmemory_0)?;
+    }
+    let stable_memory_blob = snapshot_layout.stable_memory_blob();
+    if !stable_memory_blob.exists() {
+        execution_state
+            .stable_memory
+            .page_map
+            .persist_all(&stable_memory_blob)?;
+    }
+
+    // Protobuf files are not copied when the tip is reset, so always write them.
+    snapshot_layout.snapshot().serialize(
+        CanisterSnapshotBits {
+            snapshot_id: *snapshot_id,
+            taken_at_timestamp: snapshot.taken_at_timestamp(),
+            canister_version: snapshot.canister_version(),
+            certified_data: snapshot.certified_data().to_vec(),
+            execution_state_bits: ExecutionStateBits {
+                exported_globals: execution_state.exported_globals.clone(),
+                heap_size: execution_state.wasm_memory.size,
+                exports: execution_state.exports.clone(),
+                last_executed_round: execution_state.last_executed_round,
+                metadata: execution_state.metadata.clone(),
+                binary_hash: Some(execution_state.wasm_binary.binary.module_hash().into()),
+                next_scheduled_method: execution_state.next_scheduled_method,
+            },
+            stable_memory_size: execution_state.stable_memory.size,
+        }
+        .into(),
+    )?;
     Ok(())
 }
 
@@ -458,6 +555,10 @@ fn serialize_canister_to_tip(
             None
         }
     };
+    match canister_state.system_state.wasm_chunk_store.page_map() {
+        Some(page_map) => page_map.persist_delta(&canister_layout.wasm_chunk_store())?,
+        None => truncate_path(log, &canister_layout.wasm_chunk_store()),
+    }
     // Priority credit must be zero at this point
     assert_eq!(canister_state.scheduler_state.priority_credit.get(), 0);
     canister_layout.canister().serialize(
@@ -486,6 +587,11 @@ fn serialize_canister_to_tip(
                 .system_state
                 .canister_metrics
                 .interruped_during_execution,
+            skipped_round_due_to_limits: canister_state
+                .system_state
+                .canister_metrics
+                .skipped_round_due_to_limits,
+            slices_executed: canister_state.system_state.canister_metrics.slices_executed,
             certified_data: canister_state.system_state.certified_data.clone(),
             consumed_cycles_since_replica_started: canister_state
                 .system_state
@@ -519,6 +625,14 @@ fn serialize_canister_to_tip(
                 .get_consumed_cycles_since_replica_started_by_use_cases()
                 .clone(),
             canister_history: canister_state.system_state.get_canister_history().clone(),
+            log_visibility: canister_state.system_state.log_visibility,
+            canister_log: canister_state.system_state.canister_log.clone(),
+            wasm_chunk_store_metadata: canister_state
+                .system_state
+                .wasm_chunk_store
+                .metadata()
+                .clone(),
+            wasm_memory_limit: canister_state.system_state.wasm_memory_limit,
         }
         .into(),
     )?;
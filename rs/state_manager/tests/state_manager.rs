@@ -9,8 +9,8 @@ use ic_interfaces_state_manager::*;
 use ic_logger::replica_logger::no_op_logger;
 use ic_metrics::MetricsRegistry;
 use ic_replicated_state::{
-    page_map::PageIndex, testing::ReplicatedStateTesting, Memory, NumWasmPages, PageMap,
-    ReplicatedState, Stream,
+    page_map::PageIndex, testing::ReplicatedStateTesting, CanisterSnapshot, Memory, NumWasmPages,
+    PageMap, ReplicatedState, Stream,
 };
 use ic_state_layout::{CheckpointLayout, ReadOnly, SYSTEM_METADATA_FILE};
 use ic_state_machine_tests::{StateMachine, StateMachineBuilder};
@@ -547,6 +547,78 @@ fn stable_memory_is_persisted() {
     });
 }
 
+#[test]
+fn canister_snapshots_are_persisted() {
+    state_manager_restart_test(|state_manager, restart_fn| {
+        let canister_id = canister_test_id(100);
+        let (_height, mut state) = state_manager.take_tip();
+        insert_dummy_canister(&mut state, canister_id);
+        let execution_state = state
+            .canister_state_mut(&canister_id)
+            .unwrap()
+            .execution_state
+            .as_mut()
+            .unwrap();
+        execution_state.stable_memory =
+            Memory::new(PageMap::from(&[1; 100][..]), NumWasmPages::new(1));
+
+        let snapshot = CanisterSnapshot::from_canister(
+            state.canister_state(&canister_id).unwrap(),
+            state.time(),
+        )
+        .unwrap();
+        let snapshot_id = state.metadata.generate_snapshot_id(canister_id);
+        state
+            .canister_snapshots
+            .push(snapshot_id, Arc::new(snapshot));
+        let snapshots_memory_usage = state
+            .canister_snapshots
+            .memory_taken_by_canister(canister_id);
+        state
+            .canister_state_mut(&canister_id)
+            .unwrap()
+            .system_state
+            .set_snapshots_memory_usage(snapshots_memory_usage);
+
+        // Changes to the canister after the snapshot was taken do not affect it.
+        state
+            .canister_state_mut(&canister_id)
+            .unwrap()
+            .execution_state
+            .as_mut()
+            .unwrap()
+            .stable_memory
+            .page_map = PageMap::from(&[2; 100][..]);
+        state_manager.commit_and_certify(state, height(1), CertificationScope::Full);
+
+        let state_manager = restart_fn(state_manager, None);
+
+        let (_height, mut state) = state_manager.take_tip();
+        let snapshot = state.canister_snapshots.get(&snapshot_id).unwrap();
+        assert_eq!(
+            PageMap::from(&[1; 100][..]),
+            snapshot.execution_state().stable_memory.page_map
+        );
+        assert_eq!(
+            snapshots_memory_usage,
+            state
+                .canister_state(&canister_id)
+                .unwrap()
+                .system_state
+                .snapshots_memory_usage()
+        );
+
+        // Deleted snapshots are removed from the next checkpoint.
+        state.canister_snapshots.remove(&snapshot_id);
+        state_manager.commit_and_certify(state, height(2), CertificationScope::Full);
+
+        let state_manager = restart_fn(state_manager, None);
+
+        let (_height, state) = state_manager.take_tip();
+        assert!(state.canister_snapshots.is_empty());
+    });
+}
+
 #[test]
 fn missing_stable_memory_file_is_handled() {
     use ic_state_layout::{CheckpointLayout, RwPolicy};
@@ -6,9 +6,9 @@ use ic_constants::{LOG_CANISTER_OPERATION_CYCLES_THRESHOLD, SMALL_APP_SUBNET_MAX
 use ic_cycles_account_manager::{CyclesAccountManager, CyclesAccountManagerError};
 use ic_error_types::{ErrorCode, RejectCode, UserError};
 use ic_ic00_types::{
-    CreateCanisterArgs, InstallCodeArgs, Method as Ic00Method, Payload,
-    ProvisionalCreateCanisterWithCyclesArgs, SetControllerArgs, UninstallCodeArgs,
-    UpdateSettingsArgs, IC_00,
+    CreateCanisterArgs, InstallChunkedCodeArgs, InstallCodeArgs, LoadCanisterSnapshotArgs,
+    Method as Ic00Method, Payload, ProvisionalCreateCanisterWithCyclesArgs, SetControllerArgs,
+    UninstallCodeArgs, UpdateSettingsArgs, IC_00,
 };
 use ic_interfaces::execution_environment::{HypervisorError, HypervisorResult};
 use ic_logger::{info, ReplicaLogger};
@@ -53,6 +53,18 @@ pub enum CallbackUpdate {
     Unregister(CallbackId),
 }
 
+/// Determines how an outgoing request is handled when the system state
+/// changes are applied.
+enum RequestRouting {
+    /// The request is pushed into an output queue.
+    Push,
+    /// The management canister request cannot be routed to a subnet and is
+    /// rejected.
+    RejectRouting(ResolveDestinationError),
+    /// The management canister request is invalid and is rejected.
+    RejectUserError(UserError),
+}
+
 /// Tracks changes to the system state that the canister has requested.
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct SystemStateChanges {
@@ -199,6 +211,8 @@ impl SystemStateChanges {
             Ok(Ic00Method::InstallCode) => {
                 InstallCodeArgs::decode(payload).map(|record| record.get_sender_canister_version())
             }
+            Ok(Ic00Method::InstallChunkedCode) => InstallChunkedCodeArgs::decode(payload)
+                .map(|record| record.get_sender_canister_version()),
             Ok(Ic00Method::CreateCanister) => CreateCanisterArgs::decode(payload)
                 .map(|record| record.get_sender_canister_version()),
             Ok(Ic00Method::UpdateSettings) => UpdateSettingsArgs::decode(payload)
@@ -207,6 +221,8 @@ impl SystemStateChanges {
                 .map(|record| record.get_sender_canister_version()),
             Ok(Ic00Method::UninstallCode) => UninstallCodeArgs::decode(payload)
                 .map(|record| record.get_sender_canister_version()),
+            Ok(Ic00Method::LoadCanisterSnapshot) => LoadCanisterSnapshotArgs::decode(payload)
+                .map(|record| record.get_sender_canister_version()),
             Ok(Ic00Method::ProvisionalCreateCanisterWithCycles) => {
                 ProvisionalCreateCanisterWithCyclesArgs::decode(payload)
                     .map(|record| record.get_sender_canister_version())
@@ -223,7 +239,16 @@ impl SystemStateChanges {
             | Ok(Ic00Method::SetupInitialDKG)
             | Ok(Ic00Method::ECDSAPublicKey)
             | Ok(Ic00Method::ComputeInitialEcdsaDealings)
+            | Ok(Ic00Method::SchnorrPublicKey)
+            | Ok(Ic00Method::SignWithSchnorr)
+            | Ok(Ic00Method::UploadChunk)
+            | Ok(Ic00Method::StoredChunks)
+            | Ok(Ic00Method::ClearChunkStore)
             | Ok(Ic00Method::ProvisionalTopUpCanister)
+            | Ok(Ic00Method::TakeCanisterSnapshot)
+            | Ok(Ic00Method::ListCanisterSnapshots)
+            | Ok(Ic00Method::DeleteCanisterSnapshot)
+            | Ok(Ic00Method::FetchCanisterLogs)
             | Ok(Ic00Method::BitcoinSendTransactionInternal)
             | Ok(Ic00Method::BitcoinGetSuccessors)
             | Ok(Ic00Method::BitcoinGetBalance)
@@ -291,7 +316,7 @@ impl SystemStateChanges {
                 })?;
                 if (*amount_taken).get() > LOG_CANISTER_OPERATION_CYCLES_THRESHOLD {
                     match call_context.call_origin() {
-                        CallOrigin::CanisterUpdate(origin_canister_id, _)
+                        CallOrigin::CanisterUpdate(origin_canister_id, _, _)
                         | CallOrigin::CanisterQuery(origin_canister_id, _) => info!(
                             logger,
                             "Canister {} accepted {} cycles from canister {}.",
@@ -305,13 +330,15 @@ impl SystemStateChanges {
             }
         }
 
-        // Push outgoing messages.
+        // Route outgoing messages. Requests to the management canister are
+        // either resolved to the subnet that executes them or rejected.
         let mut callback_changes = BTreeMap::new();
         let nns_subnet_id = network_topology.nns_subnet_id;
         let subnet_ids: Vec<PrincipalId> =
             network_topology.subnets.keys().map(|s| s.get()).collect();
+        let mut routed_requests = Vec::with_capacity(self.requests.len());
         for mut msg in self.requests {
-            if msg.receiver == IC_00 {
+            let routing = if msg.receiver == IC_00 {
                 match Self::validate_sender_canister_version(&msg, system_state.canister_version) {
                     Ok(()) => {
                         // This is a request to ic:00. Update the receiver to be the appropriate
@@ -328,28 +355,12 @@ impl SystemStateChanges {
                                 msg.receiver = destination_subnet;
                                 callback_changes
                                     .insert(msg.sender_reply_callback, destination_subnet);
-                                Self::push_message(system_state, time, msg, logger)?;
-                            }
-                            Err(err) => {
-                                Self::reject_subnet_message_routing(
-                                    system_state,
-                                    &subnet_ids,
-                                    msg,
-                                    err,
-                                    logger,
-                                )?;
+                                RequestRouting::Push
                             }
+                            Err(err) => RequestRouting::RejectRouting(err),
                         }
                     }
-                    Err(err) => {
-                        Self::reject_subnet_message_user_error(
-                            system_state,
-                            &subnet_ids,
-                            msg,
-                            err,
-                            logger,
-                        )?;
-                    }
+                    Err(err) => RequestRouting::RejectUserError(err),
                 }
             } else if subnet_ids.contains(&msg.receiver.get()) {
                 match Self::validate_sender_canister_version(&msg, system_state.canister_version) {
@@ -357,34 +368,24 @@ impl SystemStateChanges {
                         if own_subnet_id != nns_subnet_id {
                             // This is a management canister call providing the target subnet ID
                             // directly in the request. This is only allowed for NNS canisters.
-                            let err = ResolveDestinationError::AlreadyResolved(msg.receiver.get());
-                            Self::reject_subnet_message_routing(
-                                system_state,
-                                &subnet_ids,
-                                msg,
-                                err,
-                                logger,
-                            )?;
+                            RequestRouting::RejectRouting(ResolveDestinationError::AlreadyResolved(
+                                msg.receiver.get(),
+                            ))
                         } else {
-                            Self::push_message(system_state, time, msg, logger)?;
+                            RequestRouting::Push
                         }
                     }
-                    Err(err) => {
-                        Self::reject_subnet_message_user_error(
-                            system_state,
-                            &subnet_ids,
-                            msg,
-                            err,
-                            logger,
-                        )?;
-                    }
+                    Err(err) => RequestRouting::RejectUserError(err),
                 }
             } else {
-                Self::push_message(system_state, time, msg, logger)?;
-            }
+                RequestRouting::Push
+            };
+            routed_requests.push((msg, routing));
         }
 
-        // Verify callback ids and register new callbacks.
+        // Verify callback ids and register new callbacks. This happens before
+        // pushing the outgoing messages, so that the callback of a request
+        // that is rejected right away is known when its reject is enqueued.
         for update in self.callback_updates {
             let call_context_manager = system_state
                 .call_context_manager_mut()
@@ -409,9 +410,30 @@ impl SystemStateChanges {
             }
         }
 
+        // Push outgoing messages.
+        for (msg, routing) in routed_requests {
+            match routing {
+                RequestRouting::Push => Self::push_message(system_state, time, msg, logger)?,
+                RequestRouting::RejectRouting(err) => Self::reject_subnet_message_routing(
+                    system_state,
+                    &subnet_ids,
+                    msg,
+                    err,
+                    logger,
+                )?,
+                RequestRouting::RejectUserError(err) => Self::reject_subnet_message_user_error(
+                    system_state,
+                    &subnet_ids,
+                    msg,
+                    err,
+                    logger,
+                )?,
+            }
+        }
+
         // Verify new certified data isn't too long and set it.
         if let Some(certified_data) = self.new_certified_data.as_ref() {
-            if certified_data.len() > CERTIFIED_DATA_MAX_LENGTH as usize {
+            if certified_data.len() > CERTIFIED_DATA_MAX_LENGTH {
                 return Err(Self::error("Certified data is too large"));
             }
             system_state.certified_data = certified_data.clone();
@@ -515,6 +537,7 @@ pub struct SandboxSafeSystemState {
     global_timer: CanisterTimer,
     canister_version: u64,
     controllers: BTreeSet<PrincipalId>,
+    wasm_memory_limit: Option<NumBytes>,
 }
 
 impl SandboxSafeSystemState {
@@ -539,6 +562,7 @@ impl SandboxSafeSystemState {
         global_timer: CanisterTimer,
         canister_version: u64,
         controllers: BTreeSet<PrincipalId>,
+        wasm_memory_limit: Option<NumBytes>,
     ) -> Self {
         Self {
             canister_id,
@@ -560,6 +584,7 @@ impl SandboxSafeSystemState {
             global_timer,
             canister_version,
             controllers,
+            wasm_memory_limit,
         }
     }
 
@@ -622,6 +647,7 @@ impl SandboxSafeSystemState {
             system_state.global_timer,
             system_state.canister_version,
             system_state.controllers.clone(),
+            system_state.wasm_memory_limit,
         )
     }
 
@@ -637,6 +663,11 @@ impl SandboxSafeSystemState {
         self.canister_version
     }
 
+    /// Returns the upper limit on the Wasm memory of the canister, if any.
+    pub fn wasm_memory_limit(&self) -> Option<NumBytes> {
+        self.wasm_memory_limit
+    }
+
     pub fn set_global_timer(&mut self, timer: CanisterTimer) {
         // Update both sandbox global timer and the changes.
         self.system_state_changes.new_global_timer = Some(timer);
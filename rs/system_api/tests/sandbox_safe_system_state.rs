@@ -23,12 +23,16 @@ use ic_test_utilities::{
 };
 use ic_types::nominal_cycles::NominalCycles;
 use ic_types::{
-    messages::MAX_INTER_CANISTER_PAYLOAD_IN_BYTES, ComputeAllocation, Cycles, NumInstructions,
+    messages::{CallContextId, MAX_INTER_CANISTER_PAYLOAD_IN_BYTES},
+    methods::{Callback, WasmClosure},
+    time::CoarseTime,
+    ComputeAllocation, Cycles, NumInstructions, Time,
 };
 use prometheus::IntCounter;
-use std::collections::BTreeSet;
+use std::collections::{BTreeMap, BTreeSet};
 use std::convert::From;
 use std::sync::Arc;
+use std::time::Duration;
 
 mod common;
 use common::*;
@@ -714,3 +718,97 @@ fn wrong_method_name_subnet_message() {
         "IC0302: Management canister has no method 'start'".to_string(),
     );
 }
+
+#[test]
+fn rejected_best_effort_ic00_call_does_not_time_out() {
+    // A best-effort call to the management canister with an invalid method is
+    // rejected right away. Its callback must not time out afterwards, as that
+    // would enqueue a second response without a reserved slot.
+    let nns_subnet_id = subnet_test_id(1);
+    let nns_canister_id = canister_test_id(1);
+    let test_subnet_id = subnet_test_id(2);
+    let test_canister_id = canister_test_id(2);
+    let topo = two_subnet_topology(
+        nns_subnet_id,
+        nns_canister_id,
+        test_subnet_id,
+        test_canister_id,
+    );
+    let cycles_account_manager = CyclesAccountManagerBuilder::new()
+        .with_max_num_instructions(MAX_NUM_INSTRUCTIONS)
+        .with_subnet_type(SubnetType::Application)
+        .build();
+    let mut system_state = SystemState::new_running(
+        test_canister_id,
+        user_test_id(1).get(),
+        INITIAL_CYCLES,
+        NumSeconds::from(100_000),
+    );
+    let mut sandbox_safe_system_state = SandboxSafeSystemState::new(
+        &system_state,
+        cycles_account_manager,
+        &topo,
+        SchedulerConfig::application_subnet().dirty_page_overhead,
+        ComputeAllocation::default(),
+    );
+
+    let deadline = CoarseTime::floor(mock_time()).saturating_add_secs(1);
+    let callback_id = sandbox_safe_system_state
+        .register_callback(Callback::new(
+            CallContextId::from(1),
+            Some(test_canister_id),
+            Some(IC_00),
+            Cycles::zero(),
+            Some(Cycles::zero()),
+            Some(Cycles::zero()),
+            WasmClosure::new(0, 0),
+            WasmClosure::new(0, 0),
+            None,
+            deadline,
+        ))
+        .unwrap();
+    let request = RequestBuilder::default()
+        .sender(test_canister_id)
+        .receiver(IC_00)
+        .method_name("start")
+        .method_payload(CanisterIdRecord::from(test_canister_id).encode())
+        .sender_reply_callback(callback_id)
+        .deadline(deadline)
+        .build();
+    let prepayment_for_response_execution =
+        cycles_account_manager.prepayment_for_response_execution(SMALL_APP_SUBNET_MAX_SIZE);
+    let prepayment_for_response_transmission =
+        cycles_account_manager.prepayment_for_response_transmission(SMALL_APP_SUBNET_MAX_SIZE);
+    sandbox_safe_system_state
+        .push_output_request(
+            NumBytes::from(0),
+            request,
+            prepayment_for_response_execution,
+            prepayment_for_response_transmission,
+        )
+        .unwrap();
+    sandbox_safe_system_state
+        .system_state_changes
+        .apply_changes(
+            mock_time(),
+            &mut system_state,
+            &topo,
+            test_subnet_id,
+            &no_op_logger(),
+        )
+        .unwrap();
+
+    let after_deadline = Time::from(deadline) + Duration::from_secs(10);
+    assert!(!system_state.has_expired_callbacks(after_deadline));
+    assert_eq!(
+        (0, vec![]),
+        system_state.time_out_callbacks(after_deadline, &test_canister_id, &BTreeMap::new())
+    );
+    assert_failed_call(
+        &mut system_state,
+        test_canister_id,
+        IC_00,
+        "IC0302: Management canister has no method 'start'".to_string(),
+    );
+    assert!(system_state.pop_input().is_none());
+}
@@ -24,8 +24,10 @@ use ic_execution_environment::{
 };
 use ic_ic00_types::{
     CanisterIdRecord, CanisterInstallMode, CanisterSettingsArgs, CanisterSettingsArgsBuilder,
-    CanisterStatusType, EcdsaKeyId, EmptyBlob, InstallCodeArgs, Method, Payload,
-    ProvisionalCreateCanisterWithCyclesArgs, UpdateSettingsArgs,
+    CanisterStatusType, DeleteCanisterSnapshotArgs, EcdsaKeyId, EmptyBlob,
+    FetchCanisterLogsRequest, InstallChunkedCodeArgs, InstallCodeArgs, LoadCanisterSnapshotArgs,
+    LogVisibility, Method, Payload, ProvisionalCreateCanisterWithCyclesArgs, SchnorrAlgorithm,
+    SchnorrKeyId, TakeCanisterSnapshotArgs, UpdateSettingsArgs, UploadChunkArgs,
 };
 use ic_interfaces::{
     execution_environment::{
@@ -52,7 +54,10 @@ use ic_replicated_state::{
 use ic_replicated_state::{page_map::TestPageAllocatorFileDescriptorImpl, PageMap};
 use ic_system_api::InstructionLimits;
 use ic_types::{
-    crypto::{canister_threshold_sig::MasterEcdsaPublicKey, AlgorithmId},
+    crypto::{
+        canister_threshold_sig::{MasterEcdsaPublicKey, MasterSchnorrPublicKey},
+        AlgorithmId,
+    },
     ingress::{IngressState, IngressStatus, WasmResult},
     messages::{
         AnonymousQuery, CallbackId, MessageId, RequestOrResponse, Response, UserQuery,
@@ -133,6 +138,7 @@ pub fn test_registry_settings() -> RegistryExecutionSettings {
         max_number_of_canisters: 0x2000,
         provisional_whitelist: ProvisionalWhitelist::Set(BTreeSet::new()),
         max_ecdsa_queue_size: 20,
+        max_schnorr_queue_size: 20,
         subnet_size: SMALL_APP_SUBNET_MAX_SIZE,
     }
 }
@@ -192,6 +198,7 @@ pub struct ExecutionTest {
     manual_execution: bool,
     caller_canister_id: Option<CanisterId>,
     ecdsa_subnet_public_keys: BTreeMap<EcdsaKeyId, MasterEcdsaPublicKey>,
+    schnorr_subnet_public_keys: BTreeMap<SchnorrKeyId, MasterSchnorrPublicKey>,
 
     // The actual implementation.
     exec_env: ExecutionEnvironment,
@@ -518,12 +525,79 @@ impl ExecutionTest {
         message_id
     }
 
+    /// Sends an `install_chunked_code` message to the IC management canister.
+    pub fn install_chunked_code(
+        &mut self,
+        args: InstallChunkedCodeArgs,
+    ) -> Result<WasmResult, UserError> {
+        self.subnet_message(Method::InstallChunkedCode, args.encode())
+    }
+
+    /// Sends an `upload_chunk` message to the IC management canister.
+    pub fn upload_chunk(&mut self, args: UploadChunkArgs) -> Result<WasmResult, UserError> {
+        self.subnet_message(Method::UploadChunk, args.encode())
+    }
+
+    /// Sends a `stored_chunks` message to the IC management canister.
+    pub fn stored_chunks(&mut self, canister_id: CanisterId) -> Result<WasmResult, UserError> {
+        let payload = CanisterIdRecord::from(canister_id).encode();
+        self.subnet_message(Method::StoredChunks, payload)
+    }
+
+    /// Sends a `clear_chunk_store` message to the IC management canister.
+    pub fn clear_chunk_store(&mut self, canister_id: CanisterId) -> Result<WasmResult, UserError> {
+        let payload = CanisterIdRecord::from(canister_id).encode();
+        self.subnet_message(Method::ClearChunkStore, payload)
+    }
+
     /// Sends an `uninstall_code` message to the IC management canister.
     pub fn uninstall_code(&mut self, canister_id: CanisterId) -> Result<WasmResult, UserError> {
         let payload = CanisterIdRecord::from(canister_id).encode();
         self.subnet_message(Method::UninstallCode, payload)
     }
 
+    /// Sends a `take_canister_snapshot` message to the IC management canister.
+    pub fn take_canister_snapshot(
+        &mut self,
+        args: TakeCanisterSnapshotArgs,
+    ) -> Result<WasmResult, UserError> {
+        self.subnet_message(Method::TakeCanisterSnapshot, args.encode())
+    }
+
+    /// Sends a `load_canister_snapshot` message to the IC management canister.
+    pub fn load_canister_snapshot(
+        &mut self,
+        args: LoadCanisterSnapshotArgs,
+    ) -> Result<WasmResult, UserError> {
+        self.subnet_message(Method::LoadCanisterSnapshot, args.encode())
+    }
+
+    /// Sends a `list_canister_snapshots` message to the IC management canister.
+    pub fn list_canister_snapshots(
+        &mut self,
+        canister_id: CanisterId,
+    ) -> Result<WasmResult, UserError> {
+        let payload = CanisterIdRecord::from(canister_id).encode();
+        self.subnet_message(Method::ListCanisterSnapshots, payload)
+    }
+
+    /// Sends a `delete_canister_snapshot` message to the IC management canister.
+    pub fn delete_canister_snapshot(
+        &mut self,
+        args: DeleteCanisterSnapshotArgs,
+    ) -> Result<WasmResult, UserError> {
+        self.subnet_message(Method::DeleteCanisterSnapshot, args.encode())
+    }
+
+    /// Sends a `fetch_canister_logs` message to the IC management canister.
+    pub fn fetch_canister_logs(
+        &mut self,
+        canister_id: CanisterId,
+    ) -> Result<WasmResult, UserError> {
+        let payload = FetchCanisterLogsRequest::new(canister_id).encode();
+        self.subnet_message(Method::FetchCanisterLogs, payload)
+    }
+
     /// Starts running the given canister.
     /// Consider using higher-level helpers like `canister_from_wat()`.
     pub fn start_canister(&mut self, canister_id: CanisterId) -> Result<WasmResult, UserError> {
@@ -571,6 +645,39 @@ impl ExecutionTest {
         self.subnet_message(Method::UpdateSettings, payload)
     }
 
+    pub fn update_log_visibility(
+        &mut self,
+        canister_id: CanisterId,
+        log_visibility: LogVisibility,
+    ) -> Result<WasmResult, UserError> {
+        let payload = UpdateSettingsArgs {
+            canister_id: canister_id.into(),
+            settings: CanisterSettingsArgsBuilder::new()
+                .with_log_visibility(log_visibility)
+                .build(),
+            sender_canister_version: None,
+        }
+        .encode();
+        self.subnet_message(Method::UpdateSettings, payload)
+    }
+
+    /// Sets the upper limit on the Wasm memory of the given canister.
+    pub fn update_wasm_memory_limit(
+        &mut self,
+        canister_id: CanisterId,
+        wasm_memory_limit: NumBytes,
+    ) -> Result<WasmResult, UserError> {
+        let payload = UpdateSettingsArgs {
+            canister_id: canister_id.into(),
+            settings: CanisterSettingsArgsBuilder::new()
+                .with_wasm_memory_limit(wasm_memory_limit.get())
+                .build(),
+            sender_canister_version: None,
+        }
+        .encode();
+        self.subnet_message(Method::UpdateSettings, payload)
+    }
+
     /// Sets the controller of the canister to the given principal.
     pub fn set_controller(
         &mut self,
@@ -1025,6 +1132,7 @@ impl ExecutionTest {
             self.install_code_instruction_limits.clone(),
             &mut mock_random_number_generator(),
             &self.ecdsa_subnet_public_keys,
+            &self.schnorr_subnet_public_keys,
             &self.registry_settings,
             &mut round_limits,
         );
@@ -1410,6 +1518,8 @@ pub struct ExecutionTestBuilder {
     caller_canister_id: Option<CanisterId>,
     ecdsa_signature_fee: Option<Cycles>,
     ecdsa_key: Option<EcdsaKeyId>,
+    schnorr_signature_fee: Option<Cycles>,
+    schnorr_keys: Vec<SchnorrKeyId>,
     instruction_limit: NumInstructions,
     slice_instruction_limit: NumInstructions,
     install_code_instruction_limit: NumInstructions,
@@ -1428,6 +1538,9 @@ pub struct ExecutionTestBuilder {
     composite_queries: bool,
     query_caching: bool,
     query_cache_capacity: u64,
+    query_cache_data_dependencies: bool,
+    wasm64: bool,
+    canister_profiling: bool,
     allocatable_compute_capacity_in_percent: usize,
     subnet_features: String,
     bitcoin_privileged_access: Vec<CanisterId>,
@@ -1465,6 +1578,8 @@ impl Default for ExecutionTestBuilder {
             caller_canister_id: None,
             ecdsa_signature_fee: None,
             ecdsa_key: None,
+            schnorr_signature_fee: None,
+            schnorr_keys: vec![],
             instruction_limit: scheduler_config.max_instructions_per_message,
             slice_instruction_limit: scheduler_config.max_instructions_per_slice,
             install_code_instruction_limit: scheduler_config.max_instructions_per_install_code,
@@ -1485,6 +1600,9 @@ impl Default for ExecutionTestBuilder {
             composite_queries: false,
             query_caching: false,
             query_cache_capacity: 100_000_000, // 100MB
+            query_cache_data_dependencies: false,
+            wasm64: false,
+            canister_profiling: false,
             allocatable_compute_capacity_in_percent: 100,
             subnet_features: String::default(),
             bitcoin_privileged_access: Vec::default(),
@@ -1564,6 +1682,18 @@ impl ExecutionTestBuilder {
         }
     }
 
+    pub fn with_schnorr_signature_fee(self, schnorr_signing_fee: u128) -> Self {
+        Self {
+            schnorr_signature_fee: Some(Cycles::new(schnorr_signing_fee)),
+            ..self
+        }
+    }
+
+    pub fn with_schnorr_key(mut self, schnorr_key: SchnorrKeyId) -> Self {
+        self.schnorr_keys.push(schnorr_key);
+        self
+    }
+
     pub fn with_instruction_limit(self, limit: u64) -> Self {
         Self {
             instruction_limit: NumInstructions::from(limit),
@@ -1654,6 +1784,16 @@ impl ExecutionTestBuilder {
         }
     }
 
+    pub fn with_max_schnorr_queue_size(self, max_schnorr_queue_size: u32) -> Self {
+        Self {
+            registry_settings: RegistryExecutionSettings {
+                max_schnorr_queue_size,
+                ..self.registry_settings
+            },
+            ..self
+        }
+    }
+
     pub fn with_manual_execution(self) -> Self {
         Self {
             manual_execution: true,
@@ -1703,6 +1843,27 @@ impl ExecutionTestBuilder {
         }
     }
 
+    pub fn with_query_cache_data_dependencies(self) -> Self {
+        Self {
+            query_cache_data_dependencies: true,
+            ..self
+        }
+    }
+
+    pub fn with_wasm64(self) -> Self {
+        Self {
+            wasm64: true,
+            ..self
+        }
+    }
+
+    pub fn with_canister_profiling(self) -> Self {
+        Self {
+            canister_profiling: true,
+            ..self
+        }
+    }
+
     pub fn with_allocatable_compute_capacity_in_percent(
         self,
         allocatable_compute_capacity_in_percent: usize,
@@ -1806,6 +1967,9 @@ impl ExecutionTestBuilder {
         if let Some(ecdsa_signature_fee) = self.ecdsa_signature_fee {
             config.ecdsa_signature_fee = ecdsa_signature_fee;
         }
+        if let Some(schnorr_signature_fee) = self.schnorr_signature_fee {
+            config.schnorr_signature_fee = schnorr_signature_fee;
+        }
         if let Some(ecdsa_key) = &self.ecdsa_key {
             state
                 .metadata
@@ -1834,6 +1998,14 @@ impl ExecutionTestBuilder {
                 )
             })
             .collect();
+        let schnorr_subnet_public_keys = self
+            .schnorr_keys
+            .into_iter()
+            .map(|key| {
+                let public_key = test_master_schnorr_public_key(key.algorithm);
+                (key, public_key)
+            })
+            .collect();
         let cycles_account_manager = Arc::new(CyclesAccountManager::new(
             self.instruction_limit,
             self.subnet_type,
@@ -1865,6 +2037,21 @@ impl ExecutionTestBuilder {
         } else {
             FlagStatus::Disabled
         };
+        let query_cache_data_dependencies = if self.query_cache_data_dependencies {
+            FlagStatus::Enabled
+        } else {
+            FlagStatus::Disabled
+        };
+        let wasm64 = if self.wasm64 {
+            FlagStatus::Enabled
+        } else {
+            FlagStatus::Disabled
+        };
+        let canister_profiling = if self.canister_profiling {
+            FlagStatus::Enabled
+        } else {
+            FlagStatus::Disabled
+        };
         let config = Config {
             rate_limiting_of_instructions,
             deterministic_time_slicing,
@@ -1872,6 +2059,7 @@ impl ExecutionTestBuilder {
             composite_queries,
             query_caching,
             query_cache_capacity: self.query_cache_capacity.into(),
+            query_cache_data_dependencies,
             allocatable_compute_capacity_in_percent: self.allocatable_compute_capacity_in_percent,
             subnet_memory_capacity: NumBytes::from(self.subnet_execution_memory as u64),
             subnet_message_memory_capacity: NumBytes::from(self.subnet_message_memory as u64),
@@ -1883,6 +2071,8 @@ impl ExecutionTestBuilder {
             cost_to_compile_wasm_instruction: self.cost_to_compile_wasm_instruction.into(),
             max_query_call_graph_instructions: self.max_query_call_graph_instructions,
             stable_memory_dirty_page_limit: self.stable_memory_dirty_page_limit,
+            wasm64,
+            canister_profiling,
             ..Config::default()
         };
 
@@ -1930,6 +2120,7 @@ impl ExecutionTestBuilder {
             &metrics_registry,
             self.instruction_limit_without_dts,
             Arc::clone(&cycles_account_manager),
+            None,
         );
         ExecutionTest {
             state: Some(state),
@@ -1966,6 +2157,7 @@ impl ExecutionTestBuilder {
             ingress_history_writer,
             manual_execution: self.manual_execution,
             ecdsa_subnet_public_keys,
+            schnorr_subnet_public_keys,
             log: self.log,
             checkpoint_files: vec![],
         }
@@ -2032,6 +2224,29 @@ fn get_output_messages(state: &mut ReplicatedState) -> Vec<(CanisterId, RequestO
     output
 }
 
+/// Returns a valid master public key for the given Schnorr algorithm: the
+/// generator of the respective curve.
+fn test_master_schnorr_public_key(algorithm: SchnorrAlgorithm) -> MasterSchnorrPublicKey {
+    match algorithm {
+        SchnorrAlgorithm::Bip340Secp256k1 => MasterSchnorrPublicKey {
+            algorithm_id: AlgorithmId::SchnorrSecp256k1,
+            public_key: vec![
+                0x02, 0x79, 0xbe, 0x66, 0x7e, 0xf9, 0xdc, 0xbb, 0xac, 0x55, 0xa0, 0x62, 0x95, 0xce,
+                0x87, 0x0b, 0x07, 0x02, 0x9b, 0xfc, 0xdb, 0x2d, 0xce, 0x28, 0xd9, 0x59, 0xf2, 0x81,
+                0x5b, 0x16, 0xf8, 0x17, 0x98,
+            ],
+        },
+        SchnorrAlgorithm::Ed25519 => {
+            let mut public_key = vec![0x66; 32];
+            public_key[0] = 0x58;
+            MasterSchnorrPublicKey {
+                algorithm_id: AlgorithmId::Ed25519,
+                public_key,
+            }
+        }
+    }
+}
+
 fn get_canister_id_if_install_code(message: CanisterMessage) -> Option<CanisterId> {
     let message = match message {
         CanisterMessage::Response(_) => return None,
@@ -28,7 +28,8 @@ use ic_replicated_state::{
     CallContext, CallOrigin, CanisterState, CanisterStatus, ExecutionState, ExportedFunctions,
     InputQueueType, Memory, NumWasmPages, ReplicatedState, SchedulerState, SystemState,
 };
-use ic_types::messages::CallbackId;
+use ic_types::instruction_profile::InstructionProfile;
+use ic_types::messages::{CallbackId, NO_DEADLINE};
 use ic_types::methods::{Callback, WasmClosure};
 use ic_types::time::UNIX_EPOCH;
 use ic_types::{
@@ -506,6 +507,7 @@ impl Default for ExecutionStateBuilder {
                 metadata: wasm_metadata,
                 last_executed_round: ExecutionRound::from(0),
                 next_scheduled_method: NextScheduledMethod::default(),
+                instruction_profile: InstructionProfile::default(),
             },
         }
     }
@@ -725,7 +727,7 @@ pub fn register_callback(
         .call_context_manager_mut()
         .unwrap();
     let call_context_id = call_context_manager.new_call_context(
-        CallOrigin::CanisterUpdate(originator, callback_id),
+        CallOrigin::CanisterUpdate(originator, callback_id, NO_DEADLINE),
         Cycles::zero(),
         Time::from_nanos_since_unix_epoch(0),
     );
@@ -740,6 +742,7 @@ pub fn register_callback(
         WasmClosure::new(0, 2),
         WasmClosure::new(0, 2),
         None,
+        NO_DEADLINE,
     ));
 }
 
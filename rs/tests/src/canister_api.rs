@@ -320,6 +320,7 @@ impl Request<RefreshBuyerTokensRes> for RefreshBuyerTokensRequest {
                 .map(|p| p.to_string())
                 .unwrap_or_else(|| "".to_string()),
             confirmation_text: self.confirmation_text.clone(),
+            payment_token_ledger_canister_id: None,
         })
         .unwrap()
     }
@@ -691,6 +691,7 @@ pub fn test(env: TestEnv) {
             ledger_canister_id: Some(LEDGER_CANISTER_ID),
             governance_canister_id: Some(GOVERNANCE_CANISTER_ID),
             exchange_rate_canister: None,
+            exchange_rate_sanity_checks: None,
             minting_account_id: None,
             last_purged_notification: None,
         }))
@@ -742,6 +743,7 @@ pub fn test(env: TestEnv) {
             ledger_canister_id: Some(LEDGER_CANISTER_ID),
             governance_canister_id: Some(GOVERNANCE_CANISTER_ID),
             exchange_rate_canister: None,
+            exchange_rate_sanity_checks: None,
             minting_account_id: None,
             last_purged_notification: None,
         }))
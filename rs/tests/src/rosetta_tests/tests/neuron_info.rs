@@ -48,12 +48,14 @@ pub fn test(env: TestEnv) {
                         NeuronId { id: 34 },
                         NeuronId { id: 56 },
                     ],
+                    weights_basis_points: vec![],
                 },
             ),
             (
                 6,
                 Followees {
                     followees: vec![NeuronId { id: 33 }],
+                    weights_basis_points: vec![],
                 },
             ),
         ]);
@@ -75,12 +77,14 @@ pub fn test(env: TestEnv) {
                         NeuronId { id: 345 },
                         NeuronId { id: 567 },
                     ],
+                    weights_basis_points: vec![],
                 },
             ),
             (
                 7,
                 Followees {
                     followees: vec![NeuronId { id: 333 }],
+                    weights_basis_points: vec![],
                 },
             ),
         ]);
@@ -98,12 +102,14 @@ pub fn test(env: TestEnv) {
                 0,
                 Followees {
                     followees: vec![NeuronId { id: 111 }, NeuronId { id: 222 }],
+                    weights_basis_points: vec![],
                 },
             ),
             (
                 8,
                 Followees {
                     followees: vec![NeuronId { id: 555 }, NeuronId { id: 666 }],
+                    weights_basis_points: vec![],
                 },
             ),
         ]);
@@ -663,6 +663,8 @@ async fn open_sns_token_swap(nns_api: &'_ Runtime, payload: OpenSnsTokenSwap) {
             nns_proposal_id: None,                       // TODO[NNS1-2339]
             neurons_fund_participants: None,             // TODO[NNS1-2339]
             should_auto_finalize: Some(true),
+            additional_payment_tokens: vec![],
+            exchange_rate_canister_id: None,
         })
         .unwrap();
 
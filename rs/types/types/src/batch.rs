@@ -16,9 +16,9 @@ use super::{
     xnet::CertifiedStreamSlice,
     Height, Randomness, RegistryVersion, SubnetId, Time,
 };
-use crate::crypto::canister_threshold_sig::MasterEcdsaPublicKey;
+use crate::crypto::canister_threshold_sig::{MasterEcdsaPublicKey, MasterSchnorrPublicKey};
 use ic_btc_types_internal::BitcoinAdapterResponse;
-use ic_ic00_types::EcdsaKeyId;
+use ic_ic00_types::{EcdsaKeyId, SchnorrKeyId};
 use serde::{Deserialize, Serialize};
 use std::{collections::BTreeMap, convert::TryInto};
 
@@ -36,6 +36,8 @@ pub struct Batch {
     pub randomness: Randomness,
     /// The ECDSA public key of the subnet.
     pub ecdsa_subnet_public_keys: BTreeMap<EcdsaKeyId, MasterEcdsaPublicKey>,
+    /// The Schnorr public keys of the subnet.
+    pub schnorr_subnet_public_keys: BTreeMap<SchnorrKeyId, MasterSchnorrPublicKey>,
     /// The version of the registry to be referenced when processing the batch.
     pub registry_version: RegistryVersion,
     /// A clock time to be used for processing messages.
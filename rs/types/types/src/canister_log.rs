@@ -0,0 +1,145 @@
+//! A bounded buffer of log records emitted by a canister.
+use ic_ic00_types::CanisterLogRecord;
+use serde::{Deserialize, Serialize};
+use std::collections::VecDeque;
+
+/// The maximum number of bytes that the log records of a single canister may
+/// occupy. When the limit is exceeded, the oldest records are evicted.
+pub const MAX_ALLOWED_CANISTER_LOG_BUFFER_SIZE: usize = 4 * 1024;
+
+/// The size of the fixed-size fields (`idx` and `timestamp_nanos`) of a record.
+const RECORD_HEADER_SIZE: usize = 2 * std::mem::size_of::<u64>();
+
+/// Log records of a canister, kept in a ring buffer of bounded size.
+///
+/// The same type is used for the records produced by a single message
+/// execution (a delta), which are then appended to the canister's log with
+/// [`CanisterLog::append_delta`]. Indices are assigned on append, so that they
+/// keep increasing monotonically across the whole life of the canister.
+#[derive(Clone, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct CanisterLog {
+    next_idx: u64,
+    records: VecDeque<CanisterLogRecord>,
+    bytes_used: usize,
+}
+
+impl CanisterLog {
+    /// Creates a canister log from the given records, e.g. when loading a
+    /// checkpoint. Records that do not fit into the buffer are dropped, oldest
+    /// first.
+    pub fn new(next_idx: u64, records: Vec<CanisterLogRecord>) -> Self {
+        let mut log = Self {
+            next_idx,
+            bytes_used: records.iter().map(|r| r.data_size()).sum(),
+            records: records.into(),
+        };
+        log.evict_oldest_records();
+        log
+    }
+
+    /// Returns the index that will be assigned to the next record.
+    pub fn next_idx(&self) -> u64 {
+        self.next_idx
+    }
+
+    /// Returns the records, ordered from the oldest to the newest.
+    pub fn records(&self) -> &VecDeque<CanisterLogRecord> {
+        &self.records
+    }
+
+    /// Returns the number of bytes occupied by the records.
+    pub fn bytes_used(&self) -> usize {
+        self.bytes_used
+    }
+
+    /// Returns true if the log has no records.
+    pub fn is_empty(&self) -> bool {
+        self.records.is_empty()
+    }
+
+    /// Appends a new record, evicting the oldest records if the buffer is
+    /// full. Content that does not fit into an empty buffer is truncated.
+    pub fn add_record(&mut self, timestamp_nanos: u64, mut content: Vec<u8>) {
+        content.truncate(MAX_ALLOWED_CANISTER_LOG_BUFFER_SIZE - RECORD_HEADER_SIZE);
+        let record = CanisterLogRecord {
+            idx: self.next_idx,
+            timestamp_nanos,
+            content,
+        };
+        self.next_idx += 1;
+        self.bytes_used += record.data_size();
+        self.records.push_back(record);
+        self.evict_oldest_records();
+    }
+
+    /// Moves the records of `delta` to the end of this log, re-indexing them.
+    pub fn append_delta(&mut self, delta: CanisterLog) {
+        for record in delta.records {
+            self.add_record(record.timestamp_nanos, record.content);
+        }
+    }
+
+    /// Removes all records. The index of the next record is preserved.
+    pub fn clear(&mut self) {
+        self.records.clear();
+        self.bytes_used = 0;
+    }
+
+    fn evict_oldest_records(&mut self) {
+        while self.bytes_used > MAX_ALLOWED_CANISTER_LOG_BUFFER_SIZE {
+            match self.records.pop_front() {
+                Some(record) => self.bytes_used -= record.data_size(),
+                None => break,
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn records_are_indexed_and_evicted_in_order() {
+        let mut log = CanisterLog::default();
+        let content = vec![0; 1000];
+        for i in 0..10 {
+            log.add_record(i, content.clone());
+        }
+        assert_eq!(log.next_idx(), 10);
+        assert!(log.bytes_used() <= MAX_ALLOWED_CANISTER_LOG_BUFFER_SIZE);
+        let indices: Vec<_> = log.records().iter().map(|r| r.idx).collect();
+        assert_eq!(indices, vec![6, 7, 8, 9]);
+    }
+
+    #[test]
+    fn append_delta_reindexes_records() {
+        let mut log = CanisterLog::default();
+        log.add_record(1, b"a".to_vec());
+        let mut delta = CanisterLog::default();
+        delta.add_record(2, b"b".to_vec());
+        delta.add_record(3, b"c".to_vec());
+        log.append_delta(delta);
+        let records: Vec<_> = log
+            .records()
+            .iter()
+            .map(|r| (r.idx, r.timestamp_nanos, r.content.clone()))
+            .collect();
+        assert_eq!(
+            records,
+            vec![
+                (0, 1, b"a".to_vec()),
+                (1, 2, b"b".to_vec()),
+                (2, 3, b"c".to_vec())
+            ]
+        );
+    }
+
+    #[test]
+    fn oversized_content_is_truncated() {
+        let mut log = CanisterLog::default();
+        log.add_record(0, vec![0; 2 * MAX_ALLOWED_CANISTER_LOG_BUFFER_SIZE]);
+        assert_eq!(log.records().len(), 1);
+        assert_eq!(log.bytes_used(), MAX_ALLOWED_CANISTER_LOG_BUFFER_SIZE);
+    }
+}
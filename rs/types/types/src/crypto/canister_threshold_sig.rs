@@ -40,6 +40,30 @@ pub struct MasterEcdsaPublicKey {
     pub public_key: Vec<u8>,
 }
 
+/// A threshold Schnorr public key.
+///
+/// The public key itself is stored as raw bytes.
+///
+/// The chain key is included for BIP32-style key derivation
+#[derive(Clone, Debug, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub struct SchnorrPublicKey {
+    pub algorithm_id: AlgorithmId,
+    #[serde(with = "serde_bytes")]
+    pub public_key: Vec<u8>,
+    #[serde(with = "serde_bytes")]
+    pub chain_key: Vec<u8>,
+}
+
+/// A threshold Schnorr master public key.
+///
+/// The public key itself is stored as raw bytes.
+#[derive(Clone, Debug, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub struct MasterSchnorrPublicKey {
+    pub algorithm_id: AlgorithmId,
+    #[serde(with = "serde_bytes")]
+    pub public_key: Vec<u8>,
+}
+
 /// A combined threshold ECDSA signature.
 ///
 /// The signature itself is stored as raw bytes.
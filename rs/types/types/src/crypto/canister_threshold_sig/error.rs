@@ -85,6 +85,13 @@ pub enum ThresholdEcdsaGetPublicKeyError {
 }
 impl_display_using_debug!(ThresholdEcdsaGetPublicKeyError);
 
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub enum ThresholdSchnorrGetPublicKeyError {
+    InvalidArgument(String),
+    InternalError(String),
+}
+impl_display_using_debug!(ThresholdSchnorrGetPublicKeyError);
+
 #[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
 pub enum IDkgCreateTranscriptError {
     SerializationError {
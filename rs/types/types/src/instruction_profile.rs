@@ -0,0 +1,223 @@
+//! Per-function instruction counts collected while executing a canister.
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::fmt::Write;
+
+/// The maximum number of distinct call stacks that a profile keeps. The
+/// instructions of stacks that do not fit are reported as truncated.
+pub const MAX_INSTRUCTION_PROFILE_STACKS: usize = 10_000;
+
+/// The maximum depth of a recorded call stack. Instructions of deeper frames
+/// are attributed to the deepest recorded frame.
+pub const MAX_INSTRUCTION_PROFILE_DEPTH: usize = 256;
+
+/// The frame name used for instructions that did not fit into the profile.
+const TRUNCATED_FRAME_NAME: &str = "[truncated]";
+
+/// Instructions executed by a canister, broken down by call stack.
+///
+/// Functions are identified by their index in the function index space of the
+/// original (uninstrumented) Wasm module. Every entry and exit of a function is
+/// reported together with the number of instructions executed so far in the
+/// message, so the instructions executed between two consecutive events are
+/// attributed to the function on top of the call stack. Since the instruction
+/// count of a message is preserved across slices of deterministic time
+/// slicing, a profile covers the whole message execution.
+///
+/// The same type is used for the profile of a single message execution (a
+/// delta), which is then added to the canister's profile with
+/// [`InstructionProfile::merge`].
+#[derive(Clone, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct InstructionProfile {
+    stack: Vec<u32>,
+    last_instructions: u64,
+    stacks: BTreeMap<Vec<u32>, u64>,
+    truncated_instructions: u64,
+}
+
+impl InstructionProfile {
+    /// Returns true if no instructions have been recorded.
+    pub fn is_empty(&self) -> bool {
+        self.stacks.is_empty() && self.truncated_instructions == 0
+    }
+
+    /// Returns the recorded call stacks together with the number of
+    /// instructions executed by the innermost function of each stack.
+    pub fn stacks(&self) -> &BTreeMap<Vec<u32>, u64> {
+        &self.stacks
+    }
+
+    /// Returns the total number of recorded instructions.
+    pub fn total_instructions(&self) -> u64 {
+        self.stacks.values().sum::<u64>() + self.truncated_instructions
+    }
+
+    /// Records that the function with the given index was entered after
+    /// `instructions` instructions of the message.
+    pub fn enter(&mut self, func_idx: u32, instructions: u64) {
+        self.record(instructions);
+        self.stack.push(func_idx);
+    }
+
+    /// Records that the function on top of the call stack returned after
+    /// `instructions` instructions of the message.
+    pub fn exit(&mut self, func_idx: u32, instructions: u64) {
+        self.record(instructions);
+        let top = self.stack.pop();
+        debug_assert_eq!(top, Some(func_idx));
+    }
+
+    /// Attributes the remaining instructions to the current call stack and
+    /// closes all frames. This is needed when an execution ends without
+    /// returning from all functions, e.g. because of a trap.
+    pub fn finish(&mut self, instructions: u64) {
+        self.record(instructions);
+        self.stack.clear();
+        self.last_instructions = 0;
+    }
+
+    /// Adds the instructions recorded in `delta` to this profile.
+    pub fn merge(&mut self, delta: InstructionProfile) {
+        for (stack, instructions) in delta.stacks {
+            self.add(stack, instructions);
+        }
+        self.truncated_instructions += delta.truncated_instructions;
+    }
+
+    /// Removes all recorded instructions.
+    pub fn clear(&mut self) {
+        *self = Self::default();
+    }
+
+    /// Returns the profile in the folded stack format accepted by flamegraph
+    /// tools: one line per call stack, with frames separated by `;` and
+    /// followed by the number of instructions. Functions are named with the
+    /// given names, e.g. from the `name` section of the Wasm module, and
+    /// `func[<index>]` otherwise.
+    pub fn to_folded(&self, names: &BTreeMap<u32, String>) -> String {
+        let mut result = String::new();
+        for (stack, instructions) in self.stacks.iter() {
+            let frames: Vec<_> = stack
+                .iter()
+                .map(|idx| match names.get(idx) {
+                    Some(name) => name.replace([';', ' '], "_"),
+                    None => format!("func[{}]", idx),
+                })
+                .collect();
+            writeln!(result, "{} {}", frames.join(";"), instructions).unwrap();
+        }
+        if self.truncated_instructions > 0 {
+            writeln!(
+                result,
+                "{} {}",
+                TRUNCATED_FRAME_NAME, self.truncated_instructions
+            )
+            .unwrap();
+        }
+        result
+    }
+
+    fn record(&mut self, instructions: u64) {
+        let delta = instructions.saturating_sub(self.last_instructions);
+        self.last_instructions = instructions;
+        if delta == 0 || self.stack.is_empty() {
+            return;
+        }
+        let depth = self.stack.len().min(MAX_INSTRUCTION_PROFILE_DEPTH);
+        self.add(self.stack[..depth].to_vec(), delta);
+    }
+
+    fn add(&mut self, stack: Vec<u32>, instructions: u64) {
+        if let Some(total) = self.stacks.get_mut(&stack) {
+            *total += instructions;
+        } else if self.stacks.len() < MAX_INSTRUCTION_PROFILE_STACKS {
+            self.stacks.insert(stack, instructions);
+        } else {
+            self.truncated_instructions += instructions;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn instructions_are_attributed_to_innermost_function() {
+        let mut profile = InstructionProfile::default();
+        profile.enter(1, 0);
+        profile.enter(2, 10);
+        profile.exit(2, 15);
+        profile.enter(2, 20);
+        profile.exit(2, 22);
+        profile.exit(1, 30);
+        assert_eq!(
+            profile.stacks(),
+            &BTreeMap::from([(vec![1], 23), (vec![1, 2], 7)])
+        );
+        assert_eq!(profile.total_instructions(), 30);
+    }
+
+    #[test]
+    fn finish_closes_open_frames() {
+        let mut profile = InstructionProfile::default();
+        profile.enter(1, 5);
+        profile.enter(2, 10);
+        profile.finish(12);
+        assert_eq!(
+            profile.stacks(),
+            &BTreeMap::from([(vec![1], 5), (vec![1, 2], 2)])
+        );
+        profile.enter(3, 0);
+        profile.exit(3, 4);
+        assert_eq!(profile.stacks().get(&vec![3]), Some(&4));
+    }
+
+    #[test]
+    fn merge_adds_instructions() {
+        let mut profile = InstructionProfile::default();
+        profile.enter(1, 0);
+        profile.exit(1, 10);
+        let mut delta = InstructionProfile::default();
+        delta.enter(1, 0);
+        delta.enter(2, 1);
+        delta.exit(2, 3);
+        delta.exit(1, 4);
+        profile.merge(delta);
+        assert_eq!(
+            profile.stacks(),
+            &BTreeMap::from([(vec![1], 12), (vec![1, 2], 2)])
+        );
+    }
+
+    #[test]
+    fn stacks_beyond_limit_are_truncated() {
+        let mut profile = InstructionProfile::default();
+        for i in 0..(MAX_INSTRUCTION_PROFILE_STACKS as u32 + 1) {
+            profile.enter(i, 0);
+            profile.exit(i, 1);
+        }
+        assert_eq!(profile.stacks().len(), MAX_INSTRUCTION_PROFILE_STACKS);
+        assert_eq!(
+            profile.total_instructions(),
+            MAX_INSTRUCTION_PROFILE_STACKS as u64 + 1
+        );
+        assert!(profile
+            .to_folded(&BTreeMap::new())
+            .ends_with("[truncated] 1\n"));
+    }
+
+    #[test]
+    fn folded_output_uses_function_names() {
+        let mut profile = InstructionProfile::default();
+        profile.enter(3, 0);
+        profile.enter(4, 2);
+        profile.exit(4, 7);
+        profile.exit(3, 8);
+        let names = BTreeMap::from([(3, "canister_update go".to_string())]);
+        assert_eq!(
+            profile.to_folded(&names),
+            "canister_update_go 3\ncanister_update_go;func[4] 5\n"
+        );
+    }
+}
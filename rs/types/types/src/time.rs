@@ -200,6 +200,64 @@ impl Time {
     }
 }
 
+/// Time since UNIX_EPOCH, in seconds. Used where second granularity is
+/// sufficient and a compact representation matters, e.g. for the deadlines of
+/// best-effort inter-canister messages.
+#[derive(
+    Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Debug, Default, Hash, Serialize, Deserialize,
+)]
+#[cfg_attr(test, derive(Arbitrary))]
+pub struct CoarseTime(u32);
+
+impl CoarseTime {
+    pub const fn from_secs_since_unix_epoch(secs: u32) -> Self {
+        CoarseTime(secs)
+    }
+
+    /// Number of seconds since UNIX EPOCH
+    pub const fn as_secs_since_unix_epoch(self) -> u32 {
+        self.0
+    }
+
+    /// Rounds the given `Time` down to the nearest second. Saturates at
+    /// `u32::MAX` seconds.
+    ///
+    /// ```
+    /// use ic_types::{time::CoarseTime, Time};
+    ///
+    /// assert_eq!(CoarseTime::floor(Time::from_nanos_since_unix_epoch(1_999_999_999)), CoarseTime::from_secs_since_unix_epoch(1));
+    /// ```
+    pub fn floor(time: Time) -> Self {
+        let secs = time.as_secs_since_unix_epoch();
+        CoarseTime(u32::try_from(secs).unwrap_or(u32::MAX))
+    }
+
+    /// Rounds the given `Time` up to the nearest second. Saturates at
+    /// `u32::MAX` seconds.
+    ///
+    /// ```
+    /// use ic_types::{time::CoarseTime, Time};
+    ///
+    /// assert_eq!(CoarseTime::ceil(Time::from_nanos_since_unix_epoch(1_000_000_001)), CoarseTime::from_secs_since_unix_epoch(2));
+    /// assert_eq!(CoarseTime::ceil(Time::from_nanos_since_unix_epoch(1_000_000_000)), CoarseTime::from_secs_since_unix_epoch(1));
+    /// ```
+    pub fn ceil(time: Time) -> Self {
+        let secs = (time.as_nanos_since_unix_epoch() + NANOS_PER_SEC - 1) / NANOS_PER_SEC;
+        CoarseTime(u32::try_from(secs).unwrap_or(u32::MAX))
+    }
+
+    /// Saturating addition of the given number of seconds.
+    pub fn saturating_add_secs(self, secs: u32) -> Self {
+        CoarseTime(self.0.saturating_add(secs))
+    }
+}
+
+impl From<CoarseTime> for Time {
+    fn from(time: CoarseTime) -> Self {
+        Time(time.0 as u64 * NANOS_PER_SEC)
+    }
+}
+
 #[derive(Error, Clone, Debug, PartialEq, Eq, Hash, Serialize, Deserialize)]
 pub enum TimeInstantiationError {
     #[error("Time cannot be instantiated as it would overflow: {0}")]
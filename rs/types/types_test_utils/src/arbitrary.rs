@@ -1,7 +1,9 @@
 use crate::ids::{canister_test_id, node_test_id, subnet_test_id, user_test_id};
 use ic_types::{
     crypto::{AlgorithmId, KeyPurpose, UserPublicKey},
-    messages::{CallbackId, Payload, RejectContext, Request, RequestOrResponse, Response},
+    messages::{
+        CallbackId, Payload, RejectContext, Request, RequestOrResponse, Response, NO_DEADLINE,
+    },
     state_sync::{ChunkInfo, FileInfo},
     time::UNIX_EPOCH,
     xnet::StreamIndex,
@@ -123,6 +125,7 @@ prop_compose! {
             payment: Cycles::from(cycles_payment),
             method_name,
             method_payload,
+            deadline: NO_DEADLINE,
         }
     }
 }
@@ -157,6 +160,7 @@ prop_compose! {
             originator_reply_callback: CallbackId::from(callback),
             refund: Cycles::from(cycles_refund),
             response_payload
+            deadline: NO_DEADLINE,
         }
     }
 }
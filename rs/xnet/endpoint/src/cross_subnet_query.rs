@@ -0,0 +1,238 @@
+//! Query calls made by composite queries to canisters hosted on other subnets.
+//!
+//! The calls are sent to the `/api/v1/query` resource of the `XNetEndpoint` of
+//! a node of the callee's subnet, over the same mutually authenticated TLS
+//! connections used for fetching XNet stream slices.
+
+use crate::{bad_request, forbidden, observe_response_size, XNetEndpointMetrics, RESOURCE_QUERY};
+use hyper::{client::Client, Body, Request, Response, StatusCode, Uri};
+use ic_async_utils::receive_body_without_timeout;
+use ic_crypto_tls_interfaces::TlsHandshake;
+use ic_interfaces::execution_environment::{
+    CrossSubnetQuery, CrossSubnetQueryClient, CrossSubnetQueryResponse, QueryHandler,
+};
+use ic_interfaces_registry::RegistryClient;
+use ic_interfaces_state_manager::StateReader;
+use ic_metrics::Timer;
+use ic_registry_client_helpers::{node::NodeRegistry, subnet::SubnetRegistry};
+use ic_replicated_state::ReplicatedState;
+use ic_types::{
+    messages::MAX_INTER_CANISTER_PAYLOAD_IN_BYTES_U64,
+    registry::connection_endpoint::ConnectionEndpoint, NodeId, SubnetId,
+};
+use ic_xnet_hyper::{ExecuteOnRuntime, TlsConnector};
+use ic_xnet_uri::XNetAuthority;
+use rand::{thread_rng, Rng};
+use serde::Serialize;
+use std::convert::TryFrom;
+use std::net::SocketAddr;
+use std::sync::{mpsc, Arc};
+use std::time::Duration;
+use tokio::runtime;
+
+pub(crate) const API_URL_QUERY: &str = "/api/v1/query";
+
+/// The maximum size of an encoded `CrossSubnetQuery` or
+/// `CrossSubnetQueryResponse`. They mostly consist of an inter-canister
+/// payload, each byte of which takes up to 2 bytes when encoded as CBOR.
+pub(crate) const MAX_QUERY_BODY_SIZE: usize = 3 * MAX_INTER_CANISTER_PAYLOAD_IN_BYTES_U64 as usize;
+
+/// Executes the query calls received by the `/api/v1/query` resource.
+pub struct CrossSubnetQueryServer {
+    pub query_handler: Arc<dyn QueryHandler<State = ReplicatedState>>,
+    pub state_reader: Arc<dyn StateReader<State = ReplicatedState>>,
+}
+
+impl CrossSubnetQueryServer {
+    /// Executes the CBOR encoded `CrossSubnetQuery` received from `peer`, the
+    /// node authenticated by the TLS handshake (`None` if the connection is
+    /// not authenticated).
+    ///
+    /// As the callee trusts `CrossSubnetQuery::sender` to be the caller, the
+    /// query is only executed if the sender is hosted on the subnet of `peer`,
+    /// according to the latest state.
+    pub(crate) fn handle_query(
+        &self,
+        peer: Option<NodeId>,
+        body: &[u8],
+        metrics: &XNetEndpointMetrics,
+    ) -> Response<Body> {
+        let timer = Timer::start();
+        let response = self.handle_query_impl(peer, body, metrics);
+        metrics
+            .request_duration
+            .with_label_values(&[RESOURCE_QUERY, response.status().as_str()])
+            .observe(timer.elapsed());
+        response
+    }
+
+    fn handle_query_impl(
+        &self,
+        peer: Option<NodeId>,
+        body: &[u8],
+        metrics: &XNetEndpointMetrics,
+    ) -> Response<Body> {
+        let peer = match peer {
+            Some(peer) => peer,
+            None => return forbidden("Query calls are only accepted from authenticated nodes"),
+        };
+        let query: CrossSubnetQuery = match serde_cbor::from_slice(body) {
+            Ok(query) => query,
+            Err(err) => return bad_request(format!("Invalid query: {}", err)),
+        };
+
+        let state = self.state_reader.get_latest_state().take();
+        let sender_subnet = state
+            .metadata
+            .network_topology
+            .routing_table
+            .route(query.sender.get());
+        let peer_hosts_sender = sender_subnet
+            .and_then(|subnet_id| state.metadata.network_topology.subnets.get(&subnet_id))
+            .map_or(false, |subnet| subnet.nodes.contains(&peer));
+        if !peer_hosts_sender {
+            return forbidden(format!(
+                "Canister {} is not hosted on the subnet of node {}",
+                query.sender, peer
+            ));
+        }
+
+        let response = self.query_handler.cross_subnet_query(query, state);
+        observe_response_size(|| cbor_response(&response), RESOURCE_QUERY, metrics)
+    }
+}
+
+/// Serializes the response as CBOR.
+fn cbor_response<R: Serialize>(r: &R) -> (Response<Body>, usize) {
+    let buf = serde_cbor::to_vec(r).expect("Could not serialize response");
+    let size_bytes = buf.len();
+
+    let response = Response::builder()
+        .header("Content-Type", "application/cbor")
+        .body(buf.into())
+        .unwrap();
+
+    (response, size_bytes)
+}
+
+/// A `CrossSubnetQueryClient` that sends the query calls to the `XNetEndpoint`
+/// of a random node of the callee's subnet.
+pub struct XNetCrossSubnetQueryClient {
+    /// An HTTP client authenticating the nodes it connects to.
+    http_client: Client<TlsConnector, Body>,
+
+    /// Used for retrieving the nodes of a subnet and their `XNetEndpoints`.
+    registry: Arc<dyn RegistryClient>,
+
+    /// The runtime executing the requests.
+    runtime_handle: runtime::Handle,
+}
+
+impl XNetCrossSubnetQueryClient {
+    pub fn new(
+        runtime_handle: runtime::Handle,
+        tls: Arc<dyn TlsHandshake + Send + Sync>,
+        registry: Arc<dyn RegistryClient>,
+    ) -> Self {
+        let http_client: Client<TlsConnector, _> = Client::builder()
+            .pool_idle_timeout(Some(Duration::from_secs(600)))
+            .pool_max_idle_per_host(1)
+            .executor(ExecuteOnRuntime(runtime_handle.clone()))
+            .build(
+                #[cfg(not(test))]
+                TlsConnector::new(tls),
+                #[cfg(test)]
+                TlsConnector::new_for_tests(tls),
+            );
+        Self {
+            http_client,
+            registry,
+            runtime_handle,
+        }
+    }
+
+    /// Returns the `/api/v1/query` URL of a random node on `subnet_id`.
+    fn query_url(&self, subnet_id: SubnetId) -> Result<Uri, String> {
+        let version = self.registry.get_latest_version();
+        let nodes = self
+            .registry
+            .get_node_ids_on_subnet(subnet_id, version)
+            .map_err(|err| err.to_string())?
+            .filter(|nodes| !nodes.is_empty())
+            .ok_or_else(|| format!("No nodes found for subnet {}", subnet_id))?;
+        let node_id = nodes[thread_rng().gen_range(0..nodes.len())];
+
+        let xnet_endpoint = self
+            .registry
+            .get_transport_info(node_id, version)
+            .map_err(|err| err.to_string())?
+            .and_then(|node_record| node_record.xnet)
+            .ok_or_else(|| format!("No XNet endpoint found for node {}", node_id))?;
+        let xnet_endpoint = ConnectionEndpoint::try_from(xnet_endpoint)
+            .map_err(|err| format!("Invalid XNet endpoint of node {}: {}", node_id, err))?;
+
+        let authority = XNetAuthority {
+            node_id,
+            registry_version: version,
+            address: SocketAddr::from(&xnet_endpoint),
+        };
+        format!("http://{}{}", authority, API_URL_QUERY)
+            .parse::<Uri>()
+            .map_err(|err| err.to_string())
+    }
+}
+
+impl CrossSubnetQueryClient for XNetCrossSubnetQueryClient {
+    fn query(
+        &self,
+        subnet_id: SubnetId,
+        query: CrossSubnetQuery,
+    ) -> Result<CrossSubnetQueryResponse, String> {
+        let url = self.query_url(subnet_id)?;
+        // The query is given up on once the caller's wall time is used up.
+        let timeout = query.max_walltime;
+        let body = serde_cbor::to_vec(&query)
+            .map_err(|err| format!("Could not serialize query: {}", err))?;
+        let request = Request::post(url)
+            .header("Content-Type", "application/cbor")
+            .body(Body::from(body))
+            .map_err(|err| err.to_string())?;
+
+        // Query calls are executed on threads outside of the runtime, which
+        // block until the response has been received.
+        let http_client = self.http_client.clone();
+        let (response_sender, response_receiver) = mpsc::sync_channel(1);
+        self.runtime_handle.spawn(async move {
+            let result: Result<(StatusCode, Vec<u8>), String> =
+                tokio::time::timeout(timeout, async {
+                    let response = http_client
+                        .request(request)
+                        .await
+                        .map_err(|err| format!("XNet request failed: {}", err))?;
+                    let status = response.status();
+                    let bytes = receive_body_without_timeout(
+                        response.into_body(),
+                        MAX_QUERY_BODY_SIZE.into(),
+                    )
+                    .await
+                    .map_err(|err| format!("Error reading response body: {}", err))?;
+                    Ok((status, bytes))
+                })
+                .await
+                .unwrap_or_else(|_| Err("XNet request timed out".to_string()));
+            response_sender.send(result).ok();
+        });
+        let (status, bytes) = response_receiver
+            .recv()
+            .map_err(|_| "XNet request was dropped".to_string())??;
+
+        if status != StatusCode::OK {
+            return Err(format!(
+                "HTTP {}: {}",
+                status,
+                String::from_utf8_lossy(bytes.as_ref())
+            ));
+        }
+        serde_cbor::from_slice(&bytes).map_err(|err| format!("Could not decode response: {}", err))
+    }
+}
@@ -1,10 +1,15 @@
 #[cfg(test)]
 mod config_tests;
+mod cross_subnet_query;
 #[cfg(test)]
 mod tests;
 
-use hyper::{Body, Request, Response, StatusCode};
-use ic_crypto_tls_interfaces::TlsHandshake;
+pub use cross_subnet_query::{CrossSubnetQueryServer, XNetCrossSubnetQueryClient};
+
+use cross_subnet_query::{API_URL_QUERY, MAX_QUERY_BODY_SIZE};
+use hyper::{Body, Method, Request, Response, StatusCode};
+use ic_async_utils::receive_body_without_timeout;
+use ic_crypto_tls_interfaces::{AuthenticatedPeer, TlsHandshake};
 use ic_interfaces_certified_stream_store::{CertifiedStreamStore, EncodeStreamError};
 use ic_interfaces_registry::RegistryClient;
 use ic_logger::{debug, info, warn, ReplicaLogger};
@@ -25,7 +30,7 @@ use std::sync::Arc;
 use threadpool::ThreadPool;
 use tokio::{
     runtime,
-    sync::{oneshot, Notify},
+    sync::{oneshot, Notify, Semaphore},
 };
 use url::Url;
 
@@ -44,12 +49,20 @@ const METRIC_SLICE_PAYLOAD_SIZE: &str = "xnet_endpoint_slice_payload_size_bytes"
 const METRIC_RESPONSE_SIZE: &str = "xnet_endpoint_response_size_bytes";
 
 const RESOURCE_ERROR: &str = "error";
+const RESOURCE_QUERY: &str = "query";
 const RESOURCE_STREAM: &str = "stream";
 const RESOURCE_STREAMS: &str = "streams";
 const RESOURCE_UNKNOWN: &str = "unknown";
 
 const XNET_ENDPOINT_NUM_WORKER_THREADS: usize = 4;
 
+/// The number of threads executing query calls from other subnets, separate
+/// from the workers serving streams so that slow queries don't delay streams.
+/// Query calls are rejected rather than queued while all threads are busy, as
+/// queued calls may be waiting for their own callers (e.g. in a cycle of
+/// composite queries between two subnets).
+const XNET_ENDPOINT_NUM_QUERY_THREADS: usize = 4;
+
 impl XNetEndpointMetrics {
     pub fn new(metrics_registry: &MetricsRegistry) -> Self {
         Self {
@@ -106,9 +119,14 @@ enum WorkerMessage {
 ///   - Returns a stream slice for the given `SubnetId` with up to `msg_limit`
 ///     messages beginning at `msg_begin`, witness beginning at `witness_begin`
 ///     (`msg_begin` if missing), of up to `byte_limit` bytes.
+/// * `/api/v1/query` (POST)
+///   - Executes a CBOR encoded `CrossSubnetQuery` made by a composite query on
+///     the subnet of the authenticated peer and returns the CBOR encoded
+///     `CrossSubnetQueryResponse` (see `CrossSubnetQueryServer`).
 pub struct XNetEndpoint {
     server_address: SocketAddr,
     handler_thread_pool: threadpool::ThreadPool,
+    query_thread_pool: threadpool::ThreadPool,
     shutdown_notify: Arc<Notify>,
     request_sender: crossbeam_channel::Sender<WorkerMessage>,
     log: ReplicaLogger,
@@ -131,6 +149,7 @@ impl Drop for XNetEndpoint {
 
         // Join the background workers.
         self.handler_thread_pool.join();
+        self.query_thread_pool.join();
 
         info!(self.log, "XNet Endpoint shut down");
     }
@@ -140,12 +159,16 @@ const API_URL_STREAMS: &str = "/api/v1/streams";
 const API_URL_STREAM_PREFIX: &str = "/api/v1/stream/";
 
 impl XNetEndpoint {
-    /// Creates and starts an `XNetEndpoint` to publish XNet `Streams`.
+    /// Creates and starts an `XNetEndpoint` to publish XNet `Streams` and, if
+    /// `cross_subnet_query_server` is provided, to serve query calls made by
+    /// composite queries on other subnets.
+    #[allow(clippy::too_many_arguments)]
     pub fn new(
         runtime_handle: runtime::Handle,
         certified_stream_store: Arc<dyn CertifiedStreamStore>,
         tls: Arc<dyn TlsHandshake + Send + Sync>,
         registry_client: Arc<dyn RegistryClient + Send + Sync>,
+        cross_subnet_query_server: Option<CrossSubnetQueryServer>,
         config: XNetEndpointConfig,
         metrics: &MetricsRegistry,
         log: ReplicaLogger,
@@ -172,18 +195,32 @@ impl XNetEndpoint {
         let (request_sender, request_receiver) =
             crossbeam_channel::bounded(XNET_ENDPOINT_NUM_WORKER_THREADS);
 
+        // Query calls are executed on a separate thread pool, as they can take
+        // much longer than building a stream slice.
+        let query_thread_pool = ThreadPool::with_name(
+            "XNet Query Handler".to_string(),
+            XNET_ENDPOINT_NUM_QUERY_THREADS,
+        );
+        let query_permits = Arc::new(Semaphore::new(XNET_ENDPOINT_NUM_QUERY_THREADS));
+
         let make_service = make_service_fn({
             #[derive(Clone)]
             struct Context {
                 log: ReplicaLogger,
                 request_sender: crossbeam_channel::Sender<WorkerMessage>,
                 metrics: Arc<XNetEndpointMetrics>,
+                cross_subnet_query_server: Option<Arc<CrossSubnetQueryServer>>,
+                query_thread_pool: ThreadPool,
+                query_permits: Arc<Semaphore>,
             }
 
             let ctx = Context {
                 log: log.clone(),
                 metrics: Arc::clone(&metrics),
                 request_sender: request_sender.clone(),
+                cross_subnet_query_server: cross_subnet_query_server.map(Arc::new),
+                query_thread_pool: query_thread_pool.clone(),
+                query_permits,
             };
 
             fn ok<T>(t: T) -> Result<T, Infallible> {
@@ -197,6 +234,9 @@ impl XNetEndpoint {
                     "Serving XNet streams to peer {:?}",
                     tls_conn.peer()
                 );
+                let peer = tls_conn
+                    .peer()
+                    .map(|AuthenticatedPeer::Node(node_id)| *node_id);
 
                 async move {
                     let ctx = ctx.clone();
@@ -206,6 +246,18 @@ impl XNetEndpoint {
 
                             async move {
                                 let _ = &ctx;
+                                if request.uri().path() == API_URL_QUERY {
+                                    return ok(handle_query_request(
+                                        request,
+                                        peer,
+                                        ctx.cross_subnet_query_server.clone(),
+                                        ctx.query_thread_pool.clone(),
+                                        Arc::clone(&ctx.query_permits),
+                                        Arc::clone(&ctx.metrics),
+                                    )
+                                    .await);
+                                }
+
                                 let (response_sender, response_receiver) = oneshot::channel();
                                 let task = WorkerMessage::HandleRequest {
                                     request,
@@ -324,6 +376,7 @@ impl XNetEndpoint {
             server_address: address,
             shutdown_notify,
             handler_thread_pool,
+            query_thread_pool,
             request_sender,
             log,
         }
@@ -340,6 +393,51 @@ impl XNetEndpoint {
     }
 }
 
+/// Handles an incoming `/api/v1/query` request by receiving the body and
+/// executing the query on the query thread pool. Responds with 503 Service
+/// Unavailable if no permit is available, i.e. if all query threads are busy.
+async fn handle_query_request(
+    request: Request<Body>,
+    peer: Option<NodeId>,
+    cross_subnet_query_server: Option<Arc<CrossSubnetQueryServer>>,
+    query_thread_pool: ThreadPool,
+    query_permits: Arc<Semaphore>,
+    metrics: Arc<XNetEndpointMetrics>,
+) -> Response<Body> {
+    let cross_subnet_query_server = match cross_subnet_query_server {
+        Some(cross_subnet_query_server) => cross_subnet_query_server,
+        None => return not_found("Not Found"),
+    };
+    if request.method() != Method::POST {
+        return bad_request(format!("Unexpected method: {}", request.method()));
+    }
+    let permit = match query_permits.try_acquire_owned() {
+        Ok(permit) => permit,
+        Err(_) => {
+            metrics
+                .request_duration
+                .with_label_values(&[RESOURCE_QUERY, StatusCode::SERVICE_UNAVAILABLE.as_str()])
+                .observe(0.0);
+            return service_unavailable("All query threads are busy");
+        }
+    };
+    let body =
+        match receive_body_without_timeout(request.into_body(), MAX_QUERY_BODY_SIZE.into()).await {
+            Ok(body) => body,
+            Err(err) => return bad_request(err.to_string()),
+        };
+
+    let (response_sender, response_receiver) = oneshot::channel();
+    query_thread_pool.execute(move || {
+        let response = cross_subnet_query_server.handle_query(peer, &body, &metrics);
+        drop(permit);
+        response_sender.send(response).ok();
+    });
+    response_receiver.await.unwrap_or_else(|e| {
+        panic!("XNet Query Handler shut down unexpectedly: {}", e);
+    })
+}
+
 /// Handles an incoming HTTP request by parsing the URL, handing over to
 /// `route_request()` and replying with the produced response.
 fn handle_http_request(
@@ -550,6 +648,14 @@ fn bad_request<T: Into<Body>>(msg: T) -> Response<Body> {
         .unwrap()
 }
 
+/// Produces a 403 Forbidden response with the given content.
+fn forbidden<T: Into<Body>>(msg: T) -> Response<Body> {
+    Response::builder()
+        .status(StatusCode::FORBIDDEN)
+        .body(msg.into())
+        .unwrap()
+}
+
 /// Produces a 404 Not Found response with the given content.
 fn not_found<T: Into<Body>>(msg: T) -> Response<Body> {
     Response::builder()
@@ -558,6 +664,14 @@ fn not_found<T: Into<Body>>(msg: T) -> Response<Body> {
         .unwrap()
 }
 
+/// Produces a 503 Service Unavailable response with the given content.
+fn service_unavailable<T: Into<Body>>(msg: T) -> Response<Body> {
+    Response::builder()
+        .status(StatusCode::SERVICE_UNAVAILABLE)
+        .body(msg.into())
+        .unwrap()
+}
+
 /// Produces a 416 Range Not Satisfiable response with the given content.
 fn range_not_satisfiable<T: Into<Body>>(msg: T) -> Response<Body> {
     Response::builder()
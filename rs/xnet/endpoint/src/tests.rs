@@ -1,14 +1,21 @@
 use super::*;
 use bytes::Bytes;
+use ic_error_types::UserError;
+use ic_interfaces::execution_environment::{
+    CrossSubnetQuery, CrossSubnetQueryResponse, QueryHandler,
+};
 use ic_interfaces_registry_mocks::MockRegistryClient;
 use ic_interfaces_state_manager::{CertificationScope, StateManager};
 use ic_protobuf::{messaging::xnet::v1 as pb, proxy::ProtoProxy};
-use ic_replicated_state::{testing::ReplicatedStateTesting, ReplicatedState, Stream};
+use ic_registry_routing_table::{CanisterIdRange, RoutingTable};
+use ic_replicated_state::{
+    metadata_state::SubnetTopology, testing::ReplicatedStateTesting, ReplicatedState, Stream,
+};
 use ic_test_utilities::{
     crypto::fake_tls_handshake::FakeTlsHandshake,
     state_manager::FakeStateManager,
     types::{
-        ids::{canister_test_id, SUBNET_6, SUBNET_7},
+        ids::{canister_test_id, node_test_id, SUBNET_6, SUBNET_7},
         messages::RequestBuilder,
     },
 };
@@ -16,9 +23,15 @@ use ic_test_utilities_logger::with_test_replica_logger;
 use ic_test_utilities_metrics::{
     fetch_histogram_stats, fetch_histogram_vec_count, metric_vec, HistogramStats, MetricVec,
 };
-use ic_types::{messages::CallbackId, xnet::StreamIndexedQueue, Height, SubnetId};
-use maplit::btreemap;
+use ic_types::{
+    ingress::WasmResult,
+    messages::{CallbackId, Payload, UserQuery},
+    xnet::StreamIndexedQueue,
+    CanisterId, Height, NumInstructions, SubnetId,
+};
+use maplit::{btreemap, btreeset};
 use std::sync::Barrier;
+use std::time::Duration;
 use url::Url;
 
 const SRC_CANISTER: u64 = 2;
@@ -87,6 +100,7 @@ fn query_streams() {
             fixture.state_manager.clone(),
             fixture.tls_handshake.clone(),
             fixture.registry_client.clone(),
+            None,
             Default::default(),
             &fixture.metrics,
             log,
@@ -122,6 +136,7 @@ fn query_stream() {
             fixture.state_manager.clone(),
             fixture.tls_handshake.clone(),
             fixture.registry_client.clone(),
+            None,
             Default::default(),
             &fixture.metrics,
             log,
@@ -186,6 +201,7 @@ fn query_stream_parallel() {
             fixture.state_manager.clone(),
             fixture.tls_handshake.clone(),
             fixture.registry_client.clone(),
+            None,
             Default::default(),
             &fixture.metrics,
             log,
@@ -613,3 +629,191 @@ async fn parse_response(response: Response<Body>) -> (u16, Vec<u8>) {
         .to_vec();
     (status, body)
 }
+
+/// A `QueryHandler` replying to cross-subnet queries with their method name.
+struct EchoQueryHandler;
+
+impl QueryHandler for EchoQueryHandler {
+    type State = ReplicatedState;
+
+    fn query(
+        &self,
+        _query: UserQuery,
+        _state: Arc<ReplicatedState>,
+        _data_certificate: Vec<u8>,
+    ) -> Result<WasmResult, UserError> {
+        unreachable!("the XNet endpoint does not execute user queries")
+    }
+
+    fn cross_subnet_query(
+        &self,
+        query: CrossSubnetQuery,
+        _state: Arc<ReplicatedState>,
+    ) -> CrossSubnetQueryResponse {
+        CrossSubnetQueryResponse {
+            payload: Payload::Data(query.method_name.into_bytes()),
+            instructions_executed: NumInstructions::from(1000),
+        }
+    }
+}
+
+/// Returns a `CrossSubnetQueryServer` whose latest state hosts `SRC_CANISTER`
+/// on `UNKNOWN_SUBNET`, consisting of the node `node_test_id(1)`.
+fn cross_subnet_query_server(fixture: &EndpointTestFixture) -> CrossSubnetQueryServer {
+    let (_height, mut state) = fixture.state_manager.take_tip();
+    state.metadata.network_topology.routing_table = Arc::new(
+        RoutingTable::try_from(btreemap! {
+            CanisterIdRange { start: canister_test_id(0), end: canister_test_id(SRC_CANISTER) } => UNKNOWN_SUBNET,
+        })
+        .unwrap(),
+    );
+    state.metadata.network_topology.subnets.insert(
+        UNKNOWN_SUBNET,
+        SubnetTopology {
+            nodes: btreeset! {node_test_id(1)},
+            ..Default::default()
+        },
+    );
+    fixture
+        .state_manager
+        .commit_and_certify(state, Height::new(13), CertificationScope::Metadata);
+
+    CrossSubnetQueryServer {
+        query_handler: Arc::new(EchoQueryHandler),
+        state_reader: fixture.state_manager.clone(),
+    }
+}
+
+fn encoded_query(sender: CanisterId) -> Vec<u8> {
+    serde_cbor::to_vec(&CrossSubnetQuery {
+        sender,
+        receiver: canister_test_id(DST_CANISTER),
+        method_name: "test_method".to_string(),
+        method_payload: vec![],
+        max_call_graph_depth: 5,
+        max_instructions: NumInstructions::from(1_000_000),
+        max_walltime: Duration::from_secs(10),
+    })
+    .unwrap()
+}
+
+/// Tests that the `/api/v1/query` resource executes queries sent by a node
+/// of the subnet hosting the sender.
+#[tokio::test]
+async fn handle_query() {
+    let fixture = EndpointTestFixture::default();
+    let server = cross_subnet_query_server(&fixture);
+    let metrics = XNetEndpointMetrics::new(&fixture.metrics);
+
+    let response = server.handle_query(
+        Some(node_test_id(1)),
+        &encoded_query(canister_test_id(SRC_CANISTER)),
+        &metrics,
+    );
+    let (status, body) = parse_response(response).await;
+
+    assert_eq!(200, status);
+    assert_eq!(
+        CrossSubnetQueryResponse {
+            payload: Payload::Data(b"test_method".to_vec()),
+            instructions_executed: NumInstructions::from(1000),
+        },
+        serde_cbor::from_slice(&body).unwrap()
+    );
+    assert_eq!(
+        metric_vec(&[(&[("resource", "query"), ("status", "200")], 1)]),
+        fixture.request_counts()
+    );
+}
+
+/// Tests that the `/api/v1/query` resource rejects queries that are not sent
+/// by a node of the subnet hosting the sender, as their sender is trusted.
+#[tokio::test]
+async fn handle_query_rejects_unauthenticated_sender() {
+    let fixture = EndpointTestFixture::default();
+    let server = cross_subnet_query_server(&fixture);
+    let metrics = XNetEndpointMetrics::new(&fixture.metrics);
+    let query = encoded_query(canister_test_id(SRC_CANISTER));
+
+    // Unauthenticated peer.
+    let (status, _) = parse_response(server.handle_query(None, &query, &metrics)).await;
+    assert_eq!(403, status);
+
+    // Node of another subnet.
+    let (status, _) =
+        parse_response(server.handle_query(Some(node_test_id(2)), &query, &metrics)).await;
+    assert_eq!(403, status);
+
+    // Sender not hosted on the subnet of the node.
+    let query = encoded_query(canister_test_id(DST_CANISTER));
+    let (status, _) =
+        parse_response(server.handle_query(Some(node_test_id(1)), &query, &metrics)).await;
+    assert_eq!(403, status);
+
+    // Malformed query.
+    let (status, _) =
+        parse_response(server.handle_query(Some(node_test_id(1)), b"garbage", &metrics)).await;
+    assert_eq!(400, status);
+
+    assert_eq!(
+        metric_vec(&[
+            (&[("resource", "query"), ("status", "400")], 1),
+            (&[("resource", "query"), ("status", "403")], 3),
+        ]),
+        fixture.request_counts()
+    );
+}
+
+/// Tests that the `/api/v1/query` resource rejects queries instead of queuing
+/// them while all query threads are busy, so that a cycle of composite queries
+/// between two subnets cannot wait on itself.
+#[tokio::test]
+async fn handle_query_request_rejects_query_when_saturated() {
+    let fixture = EndpointTestFixture::default();
+    let server = Arc::new(cross_subnet_query_server(&fixture));
+    let metrics = Arc::new(XNetEndpointMetrics::new(&fixture.metrics));
+    let query_thread_pool = ThreadPool::new(1);
+    let query_permits = Arc::new(Semaphore::new(1));
+    let request = || {
+        Request::post(API_URL_QUERY)
+            .body(Body::from(encoded_query(canister_test_id(SRC_CANISTER))))
+            .unwrap()
+    };
+
+    // All query threads are busy.
+    let permit = Arc::clone(&query_permits).try_acquire_owned().unwrap();
+    let response = handle_query_request(
+        request(),
+        Some(node_test_id(1)),
+        Some(Arc::clone(&server)),
+        query_thread_pool.clone(),
+        Arc::clone(&query_permits),
+        Arc::clone(&metrics),
+    )
+    .await;
+    let (status, _) = parse_response(response).await;
+    assert_eq!(503, status);
+
+    // A query thread is free again.
+    drop(permit);
+    let response = handle_query_request(
+        request(),
+        Some(node_test_id(1)),
+        Some(server),
+        query_thread_pool,
+        Arc::clone(&query_permits),
+        metrics,
+    )
+    .await;
+    let (status, _) = parse_response(response).await;
+    assert_eq!(200, status);
+    assert_eq!(1, query_permits.available_permits());
+
+    assert_eq!(
+        metric_vec(&[
+            (&[("resource", "query"), ("status", "200")], 1),
+            (&[("resource", "query"), ("status", "503")], 1),
+        ]),
+        fixture.request_counts()
+    );
+}